@@ -0,0 +1,199 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::config::Config;
+
+/// Directories never searched: caches, app data, and other repositories' internals
+const SKIP_DIRS: &[&str] = &[
+    "Library",
+    ".Trash",
+    ".git",
+    ".cache",
+    ".npm",
+    ".cargo",
+    ".rustup",
+    "node_modules",
+];
+
+/// Dotfiles that are state, not configuration
+const SKIP_FILES: &[&str] = &[
+    ".DS_Store",
+    ".CFUserTextEncoding",
+    ".localized",
+    ".bash_history",
+    ".zsh_history",
+    ".python_history",
+    ".node_repl_history",
+    ".lesshst",
+    ".viminfo",
+    ".Xauthority",
+    ".zcompdump",
+];
+
+/// How deep symlinks are searched for, e.g. `.config/nvim/lua/init.lua`
+const MAX_DEPTH: usize = 4;
+
+/// Where a candidate's content currently lives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    /// A symlink into another dotfiles repository; holds the resolved target
+    Symlink(PathBuf),
+
+    /// A plain dotfile at the top of the searched directory
+    File,
+}
+
+/// A file `ordinator adopt` can import
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    /// Path as it would be tracked: relative to the home directory, or
+    /// absolute for files outside it
+    pub path: String,
+    pub origin: Origin,
+}
+
+/// Find files under `root` worth adopting: symlinks to files outside the
+/// `managed` directories (the ordinator repository), and top-level dotfiles.
+/// Paths in `tracked` are skipped.
+pub fn find_candidates(
+    home: &Path,
+    root: &Path,
+    managed: &[PathBuf],
+    tracked: &BTreeSet<String>,
+) -> Result<Vec<Candidate>> {
+    let managed: Vec<PathBuf> = managed
+        .iter()
+        .filter_map(|dir| fs::canonicalize(dir).ok())
+        .collect();
+    let walker = WalkDir::new(root)
+        .min_depth(1)
+        .max_depth(MAX_DEPTH)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            // The root is searched even when it is the repository (home and dotfiles can match)
+            entry.depth() == 0
+                || !(entry.file_type().is_dir()
+                    && (SKIP_DIRS.contains(&name.as_ref())
+                        || fs::canonicalize(entry.path()).is_ok_and(|dir| managed.contains(&dir))))
+        });
+
+    let mut candidates = Vec::new();
+    for entry in walker.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let tracked_path = match path.strip_prefix(home) {
+            Ok(relative) => relative.to_string_lossy().into_owned(),
+            Err(_) => path.to_string_lossy().into_owned(),
+        };
+        if tracked.contains(&tracked_path) {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy();
+
+        if entry.path_is_symlink() {
+            // Broken links and links to directories are left alone
+            let Ok(target) = fs::canonicalize(path) else {
+                continue;
+            };
+            if target.is_file() && !managed.iter().any(|dir| target.starts_with(dir)) {
+                candidates.push(Candidate {
+                    path: tracked_path,
+                    origin: Origin::Symlink(target),
+                });
+            }
+        } else if entry.depth() == 1
+            && entry.file_type().is_file()
+            && name.starts_with('.')
+            && !SKIP_FILES.contains(&name.as_ref())
+        {
+            candidates.push(Candidate {
+                path: tracked_path,
+                origin: Origin::File,
+            });
+        }
+    }
+    candidates.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(candidates)
+}
+
+/// Copy a candidate into `profile` and track it. An adopted symlink is
+/// pointed at the new copy, so the old repository is no longer needed;
+/// plain files stay in place until the next apply links them.
+pub fn adopt(
+    config: &mut Config,
+    dotfiles_dir: &Path,
+    profile: &str,
+    candidate: &Candidate,
+    home: &Path,
+) -> Result<()> {
+    let dest = home.join(&candidate.path);
+    let stored = config.get_profile_file_path(profile, &candidate.path)?;
+    if let Some(parent) = stored.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(&dest, &stored)
+        .with_context(|| format!("Failed to copy {} into the profile", dest.display()))?;
+    crate::large_files::handle_stored(
+        &config.global.large_files,
+        dotfiles_dir,
+        &stored,
+        &candidate.path,
+    )?;
+    config.add_file_to_profile(profile, candidate.path.clone())?;
+    config.record_file_mode(profile, &candidate.path, &dest)?;
+
+    if let Origin::Symlink(_) = candidate.origin {
+        let source = config.get_source_file_path(profile, &candidate.path)?;
+        fs::remove_file(&dest)?;
+        std::os::unix::fs::symlink(&source, &dest).with_context(|| {
+            format!("Failed to link {} to {}", dest.display(), source.display())
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_candidates() {
+        let home = tempfile::tempdir().unwrap();
+        let other_repo = tempfile::tempdir().unwrap();
+        let dotfiles = home.path().join(".dotfiles");
+        fs::create_dir_all(dotfiles.join("files")).unwrap();
+        fs::create_dir_all(home.path().join(".config/nvim")).unwrap();
+        fs::write(other_repo.path().join("init.lua"), "-- nvim\n").unwrap();
+        fs::write(dotfiles.join("files/zshrc"), "# managed\n").unwrap();
+        fs::write(home.path().join(".gitconfig"), "[user]\n").unwrap();
+        fs::write(home.path().join(".DS_Store"), "").unwrap();
+        fs::write(home.path().join(".vimrc"), "set nu\n").unwrap();
+        std::os::unix::fs::symlink(
+            other_repo.path().join("init.lua"),
+            home.path().join(".config/nvim/init.lua"),
+        )
+        .unwrap();
+        std::os::unix::fs::symlink(dotfiles.join("files/zshrc"), home.path().join(".zshrc"))
+            .unwrap();
+        std::os::unix::fs::symlink(
+            other_repo.path().join("missing"),
+            home.path().join(".broken"),
+        )
+        .unwrap();
+
+        let tracked = BTreeSet::from([".vimrc".to_string()]);
+        let candidates = find_candidates(home.path(), home.path(), &[dotfiles], &tracked).unwrap();
+        let found: Vec<(&str, bool)> = candidates
+            .iter()
+            .map(|c| (c.path.as_str(), matches!(c.origin, Origin::Symlink(_))))
+            .collect();
+        assert_eq!(
+            found,
+            [(".config/nvim/init.lua", true), (".gitconfig", false)]
+        );
+    }
+}
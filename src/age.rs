@@ -0,0 +1,188 @@
+//! Native age encryption, for `[secrets] backend = "native"`. Reads and
+//! writes the age v1 format (X25519 recipients, ASCII armor) with the `age`
+//! crate instead of the `age` or `sops` binaries, so files it writes decrypt
+//! with `age --decrypt -i <key file>` and the other way round.
+
+use age::armor::{ArmoredReader, ArmoredWriter, Format};
+use age::secrecy::ExposeSecret;
+use age::x25519::{Identity, Recipient};
+use anyhow::{Context, Result};
+use bech32::FromBase32;
+use std::io::{Read, Write};
+
+const INTRO: &str = "age-encryption.org/v1";
+const ARMOR_BEGIN: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+
+/// Whether `content` is an age file, armored or binary
+pub fn is_age(content: &[u8]) -> bool {
+    let start = content
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(content.len());
+    let content = &content[start..];
+    content.starts_with(ARMOR_BEGIN.as_bytes()) || content.starts_with(INTRO.as_bytes())
+}
+
+/// A new identity, in the format `age-keygen` writes
+pub fn generate_identity() -> Result<String> {
+    let identity = Identity::generate();
+    Ok(format!(
+        "# created: {}\n# public key: {}\n{}\n",
+        chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        identity.to_public(),
+        identity.to_string().expose_secret()
+    ))
+}
+
+/// The recipient (`age1...`) for each identity in a key file's `content`
+pub fn recipients_of(content: &str) -> Result<Vec<String>> {
+    Ok(parse_identities(content)?
+        .iter()
+        .map(|identity| identity.to_public().to_string())
+        .collect())
+}
+
+/// Encrypt `plaintext` for `recipients` (`age1...` public keys), ASCII-armored
+pub fn encrypt(plaintext: &[u8], recipients: &[String]) -> Result<Vec<u8>> {
+    if recipients.is_empty() {
+        return Err(anyhow::anyhow!("No age recipients to encrypt for"));
+    }
+    let recipients = recipients
+        .iter()
+        .map(|recipient| parse_recipient(recipient))
+        .collect::<Result<Vec<_>>>()?;
+    let encryptor = age::Encryptor::with_recipients(
+        recipients
+            .iter()
+            .map(|recipient| recipient as &dyn age::Recipient),
+    )?;
+
+    let mut encrypted = Vec::with_capacity(plaintext.len());
+    let armored = ArmoredWriter::wrap_output(&mut encrypted, Format::AsciiArmor)?;
+    let mut writer = encryptor.wrap_output(armored)?;
+    writer.write_all(plaintext)?;
+    writer.finish()?.finish()?;
+    Ok(encrypted)
+}
+
+/// Decrypt an age file, armored or binary, with the identities in a key
+/// file's `identities`
+pub fn decrypt(content: &[u8], identities: &str) -> Result<Vec<u8>> {
+    let identities = parse_identities(identities)?;
+    let decryptor = age::Decryptor::new_buffered(ArmoredReader::new(content))
+        .context("not a valid age file")?;
+    let mut reader = decryptor
+        .decrypt(
+            identities
+                .iter()
+                .map(|identity| identity as &dyn age::Identity),
+        )
+        .map_err(|e| match e {
+            age::DecryptError::NoMatchingKeys => {
+                anyhow::anyhow!("no identity matched any of the file's recipients (wrong age key?)")
+            }
+            e => e.into(),
+        })?;
+    let mut plaintext = Vec::new();
+    reader
+        .read_to_end(&mut plaintext)
+        .context("the age payload is damaged or truncated")?;
+    Ok(plaintext)
+}
+
+/// An X25519 recipient. Low-order points are refused: every shared secret
+/// with one is all zeros, so the file key would be wrapped for anyone.
+fn parse_recipient(recipient: &str) -> Result<Recipient> {
+    let invalid = || anyhow::anyhow!("'{recipient}' is not a valid age public key");
+    let (hrp, data, _) = bech32::decode(recipient).map_err(|_| invalid())?;
+    if hrp != "age" {
+        return Err(anyhow::anyhow!(
+            "'{recipient}' is not an X25519 age public key"
+        ));
+    }
+    let point: [u8; 32] = Vec::<u8>::from_base32(&data)
+        .map_err(|_| invalid())?
+        .try_into()
+        .map_err(|_| invalid())?;
+    let probe = x25519_dalek::StaticSecret::from([1; 32]);
+    if !probe
+        .diffie_hellman(&x25519_dalek::PublicKey::from(point))
+        .was_contributory()
+    {
+        return Err(anyhow::anyhow!(
+            "'{recipient}' is a low-order point, not a usable age public key"
+        ));
+    }
+    recipient.parse().map_err(|_| invalid())
+}
+
+fn parse_identities(content: &str) -> Result<Vec<Identity>> {
+    let identities = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("AGE-SECRET-KEY-1"))
+        .map(|line| line.parse::<Identity>().map_err(anyhow::Error::msg))
+        .collect::<Result<Vec<_>>>()
+        .context("The age key file holds an invalid AGE-SECRET-KEY line")?;
+    if identities.is_empty() {
+        return Err(anyhow::anyhow!(
+            "The age key file has no line starting with 'AGE-SECRET-KEY-'"
+        ));
+    }
+    Ok(identities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_round_trip() {
+        let identity = generate_identity().unwrap();
+        let other = generate_identity().unwrap();
+        let recipients = [
+            recipients_of(&other).unwrap()[0].clone(),
+            recipients_of(&identity).unwrap()[0].clone(),
+        ];
+        assert!(recipients[1].starts_with("age1") && recipients[1].len() == 62);
+        crate::secrets::validate_age_recipient(&recipients[1]).unwrap();
+
+        for plaintext in [
+            Vec::new(),
+            b"token=abc\n".to_vec(),
+            vec![7u8; 64 * 1024 * 2 + 5],
+        ] {
+            let encrypted = encrypt(&plaintext, &recipients).unwrap();
+            assert!(is_age(&encrypted));
+            assert!(encrypted.starts_with(ARMOR_BEGIN.as_bytes()));
+            assert_eq!(decrypt(&encrypted, &identity).unwrap(), plaintext);
+            assert_eq!(decrypt(&encrypted, &other).unwrap(), plaintext);
+        }
+
+        let stranger = generate_identity().unwrap();
+        assert!(decrypt(&encrypt(b"x", &recipients).unwrap(), &stranger).is_err());
+        assert!(!is_age(b"sops:\n  age: []\n"));
+    }
+
+    #[test]
+    fn test_encrypt_refuses_low_order_recipients() {
+        // The identity point, and the order-8 point from libsodium's blocklist
+        for point in [
+            [0u8; 32],
+            [
+                0xe0, 0xeb, 0x7a, 0x7c, 0x3b, 0x41, 0xb8, 0xae, 0x16, 0x56, 0xe3, 0xfa, 0xf1, 0x9f,
+                0xc4, 0x6a, 0xda, 0x09, 0x8d, 0xeb, 0x9c, 0x32, 0xb1, 0xfd, 0x86, 0x62, 0x05, 0x16,
+                0x5f, 0x49, 0xb8, 0x00,
+            ],
+        ] {
+            let recipient = bech32::encode(
+                "age",
+                bech32::ToBase32::to_base32(&point),
+                bech32::Variant::Bech32,
+            )
+            .unwrap();
+            let err = encrypt(b"x", &[recipient]).unwrap_err();
+            assert!(err.to_string().contains("low-order"), "{err}");
+        }
+    }
+}
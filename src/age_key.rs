@@ -0,0 +1,325 @@
+//! Where the age private key is kept: a plaintext file (the default), a file
+//! encrypted with a passphrase, or the macOS Keychain. For the last two, SOPS
+//! gets the key through `SOPS_AGE_KEY`, so the plaintext never touches the disk.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+use crate::environment::{self, Effect};
+use crate::error::OrdinatorError;
+
+/// Keychain service keys are stored under; the account is the key file path
+const KEYCHAIN_SERVICE: &str = "ordinator-age";
+
+/// How `[secrets] age_key_file` is stored on this machine
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyStorage {
+    /// The key file as `age-keygen` wrote it
+    #[default]
+    File,
+
+    /// `<key file>.age`, encrypted with a passphrase asked for once per run
+    Passphrase,
+
+    /// A generic password in the login Keychain
+    Keychain,
+}
+
+impl KeyStorage {
+    pub fn is_file(&self) -> bool {
+        *self == KeyStorage::File
+    }
+
+    /// Where the key is, for messages
+    pub fn description(&self) -> &'static str {
+        match self {
+            KeyStorage::File => "a plaintext key file",
+            KeyStorage::Passphrase => "a passphrase-protected key file",
+            KeyStorage::Keychain => "the macOS Keychain",
+        }
+    }
+}
+
+impl std::str::FromStr for KeyStorage {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "file" => Ok(KeyStorage::File),
+            "passphrase" => Ok(KeyStorage::Passphrase),
+            "keychain" => Ok(KeyStorage::Keychain),
+            other => Err(OrdinatorError::UnknownKeyStorage(other.to_string()).into()),
+        }
+    }
+}
+
+/// Keys already read this run, so the passphrase is asked for only once
+static KEYS: Mutex<BTreeMap<PathBuf, String>> = Mutex::new(BTreeMap::new());
+
+/// The passphrase-encrypted copy of `key_file`, e.g. `key.txt.age`
+pub fn encrypted_path(key_file: &Path) -> PathBuf {
+    sibling(key_file, "age")
+}
+
+/// The public key kept next to a key that isn't a plaintext file, e.g. `key.txt.pub`
+pub fn public_key_path(key_file: &Path) -> PathBuf {
+    sibling(key_file, "pub")
+}
+
+fn sibling(key_file: &Path, extension: &str) -> PathBuf {
+    let mut path = key_file.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+/// Whether a key for `key_file` is stored in any form
+pub fn exists(key_file: &Path) -> bool {
+    key_file.exists() || encrypted_path(key_file).exists() || keychain_contains(key_file)
+}
+
+/// The contents of the key for `key_file`
+pub fn read(key_file: &Path, storage: KeyStorage) -> Result<String> {
+    let mut keys = KEYS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(key) = keys.get(key_file) {
+        return Ok(key.clone());
+    }
+    let key = match storage {
+        KeyStorage::File => fs::read_to_string(key_file)
+            .with_context(|| format!("Failed to read age key {}", key_file.display()))?,
+        KeyStorage::Passphrase => decrypt_with_passphrase(&encrypted_path(key_file))?,
+        KeyStorage::Keychain => keychain_read(key_file)?,
+    };
+    keys.insert(key_file.to_path_buf(), key.clone());
+    Ok(key)
+}
+
+/// Store `key` for `key_file` as `storage`. Other stored forms of the key are
+/// removed once the new one is in place; the plaintext file is shredded.
+pub fn store(key_file: &Path, storage: KeyStorage, key: &str) -> Result<()> {
+    if !key.contains("AGE-SECRET-KEY-") {
+        return Err(OrdinatorError::InvalidAgeKey.into());
+    }
+    if let Some(dir) = key_file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    match storage {
+        KeyStorage::File => {
+            fs::write(key_file, key)?;
+            fs::set_permissions(key_file, fs::Permissions::from_mode(0o600))?;
+        }
+        KeyStorage::Passphrase => encrypt_with_passphrase(key, &encrypted_path(key_file))?,
+        KeyStorage::Keychain => keychain_write(key_file, key)?,
+    }
+
+    if storage.is_file() {
+        remove_if_exists(&public_key_path(key_file))?;
+    } else {
+        // Commands that only need the public key (SOPS config, recipients)
+        // keep working without unlocking the key
+        if let Some(public_key) = key
+            .lines()
+            .find_map(|line| line.strip_prefix("# public key: "))
+        {
+            fs::write(
+                public_key_path(key_file),
+                format!("# public key: {}\n", public_key.trim()),
+            )?;
+        }
+        if key_file.exists() {
+            crate::secrets::remove_decrypted_secret(key_file, true)?;
+        }
+    }
+    if storage != KeyStorage::Passphrase {
+        remove_if_exists(&encrypted_path(key_file))?;
+    }
+    if storage != KeyStorage::Keychain && keychain_contains(key_file) {
+        keychain_delete(key_file)?;
+    }
+
+    let mut keys = KEYS.lock().unwrap_or_else(|e| e.into_inner());
+    keys.insert(key_file.to_path_buf(), key.to_string());
+    Ok(())
+}
+
+/// Give a `sops` command the configured age key
+pub fn set_sops_key(command: &mut Command, secrets: &crate::config::SecretsConfig) -> Result<()> {
+    let Some(key_file) = &secrets.age_key_file else {
+        return Ok(());
+    };
+    match secrets.age_key_storage {
+        KeyStorage::File => {
+            command.env("SOPS_AGE_KEY_FILE", key_file);
+        }
+        storage => {
+            command.env("SOPS_AGE_KEY", read(key_file, storage)?);
+        }
+    }
+    Ok(())
+}
+
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+fn age_command() -> Result<Command> {
+    let age = which::which("age").map_err(|_| OrdinatorError::AgeNotInstalled)?;
+    Ok(Command::new(age))
+}
+
+/// age reads the passphrase from the terminal itself
+fn encrypt_with_passphrase(key: &str, output: &Path) -> Result<()> {
+    if !crate::prompt::interactive() {
+        return Err(OrdinatorError::PassphraseNeedsTerminal.into());
+    }
+    let mut command = age_command()?;
+    command
+        .args(["--encrypt", "--passphrase", "--output"])
+        .arg(output);
+    let result = environment::output_with_input(&mut command, key.as_bytes(), Effect::Changes)
+        .context("Failed to run age")?;
+    if !result.status.success() {
+        return Err(anyhow::anyhow!(
+            "age failed to encrypt {}",
+            output.display()
+        ));
+    }
+    Ok(())
+}
+
+fn decrypt_with_passphrase(path: &Path) -> Result<String> {
+    if !path.exists() {
+        return Err(anyhow::anyhow!(
+            "Passphrase-protected age key {} not found",
+            path.display()
+        ));
+    }
+    if !crate::transfer::quiet() {
+        eprintln!("Unlocking age key {}", path.display());
+    }
+    let mut command = age_command()?;
+    command.arg("--decrypt").arg(path).stderr(Stdio::inherit());
+    let output = environment::output(&mut command, Effect::Reads).context("Failed to run age")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("age failed to decrypt {}", path.display()));
+    }
+    String::from_utf8(output.stdout).context("The decrypted age key is not valid UTF-8")
+}
+
+fn security_command() -> Result<Command> {
+    let security = which::which("security").map_err(|_| OrdinatorError::KeychainUnavailable)?;
+    Ok(Command::new(security))
+}
+
+fn keychain_args<'a>(command: &'a mut Command, action: &str, key_file: &Path) -> &'a mut Command {
+    command
+        .arg(action)
+        .args(["-s", KEYCHAIN_SERVICE, "-a"])
+        .arg(key_file)
+}
+
+fn keychain_contains(key_file: &Path) -> bool {
+    let Ok(mut command) = security_command() else {
+        return false;
+    };
+    keychain_args(&mut command, "find-generic-password", key_file)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    environment::status(&mut command, Effect::Reads).is_ok_and(|status| status.success())
+}
+
+/// The Keychain holds only the secret key lines, separated by spaces:
+/// `security` prints passwords containing newlines as hex
+fn keychain_read(key_file: &Path) -> Result<String> {
+    let mut command = security_command()?;
+    keychain_args(&mut command, "find-generic-password", key_file).arg("-w");
+    let output =
+        environment::output(&mut command, Effect::Reads).context("Failed to run security")?;
+    if !output.status.success() {
+        return Err(OrdinatorError::KeychainKeyMissing {
+            key_file: key_file.to_path_buf(),
+            reason: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        }
+        .into());
+    }
+    let stored = String::from_utf8(output.stdout).context("The Keychain entry is not UTF-8")?;
+    let mut key = stored.split_whitespace().collect::<Vec<_>>().join("\n");
+    key.push('\n');
+    Ok(key)
+}
+
+fn keychain_write(key_file: &Path, key: &str) -> Result<()> {
+    let secret = key
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("AGE-SECRET-KEY-"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    // `-w` last with no value makes security prompt for the password (and
+    // again to confirm) on stdin, so the key never shows up in `ps`
+    let mut command = security_command()?;
+    keychain_args(&mut command, "add-generic-password", key_file)
+        .arg("-U")
+        .arg("-w")
+        .stderr(Stdio::piped());
+    let output = environment::output_with_input(
+        &mut command,
+        format!("{secret}\n{secret}\n").as_bytes(),
+        Effect::Changes,
+    )
+    .context("Failed to run security")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to add the age key to the Keychain: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+fn keychain_delete(key_file: &Path) -> Result<()> {
+    let mut command = security_command()?;
+    keychain_args(&mut command, "delete-generic-password", key_file);
+    let output =
+        environment::output(&mut command, Effect::Changes).context("Failed to run security")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to remove the age key from the Keychain: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_storage_paths() {
+        let key = Path::new("/home/me/.config/ordinator/age/key.txt");
+        assert_eq!(
+            encrypted_path(key),
+            Path::new("/home/me/.config/ordinator/age/key.txt.age")
+        );
+        assert_eq!(
+            public_key_path(key),
+            Path::new("/home/me/.config/ordinator/age/key.txt.pub")
+        );
+        assert_eq!(
+            "keychain".parse::<KeyStorage>().unwrap(),
+            KeyStorage::Keychain
+        );
+        assert!("vault".parse::<KeyStorage>().is_err());
+    }
+}
@@ -0,0 +1,154 @@
+//! The library API for tools that read and change ordinator repositories
+//! (editor plugins, GUIs, menu bar apps) without shelling out to the CLI.
+//!
+//! [`Repository`] finds and loads `ordinator.toml` the way the CLI does, and
+//! answers the questions `ordinator profiles`, `ordinator plan`, and
+//! `ordinator files` answer. The types re-exported here are the stable
+//! surface: they change only with the crate's minor version, and fields are
+//! only ever added. Everything else in the crate is free to change.
+//!
+//! ```no_run
+//! # async fn example() -> anyhow::Result<()> {
+//! use ordinator_core::api::{LinkState, Repository};
+//!
+//! let repo = Repository::discover()?;
+//! let profile = repo.default_profile().to_string();
+//! for entry in repo.status(&profile)? {
+//!     if entry.state != LinkState::Linked {
+//!         println!("{}: {}", entry.path, entry.state.label());
+//!     }
+//! }
+//! println!("{}", repo.plan(&profile).await?.summary());
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+pub use crate::config::{Config, GlobalConfig, ProfileConfig};
+pub use crate::directory::{DirectoryLink, TrackedDirectory};
+pub use crate::error::OrdinatorError;
+pub use crate::listing::{FileEntry, FileFilter, LinkState, SecretState};
+pub use crate::plan::{Plan, PlanAction, PlannedChange};
+
+/// An ordinator repository: its configuration, and where it was loaded from
+#[derive(Debug, Clone)]
+pub struct Repository {
+    config: Config,
+    config_path: PathBuf,
+}
+
+impl Repository {
+    /// Load the repository the CLI would use: `ORDINATOR_CONFIG`, then
+    /// `ordinator.toml` in the current directory, then in `ORDINATOR_HOME`
+    /// or `~/.dotfiles`
+    pub fn discover() -> Result<Self> {
+        let (config, config_path) = Config::load()?;
+        Ok(Self {
+            config,
+            config_path,
+        })
+    }
+
+    /// Load the repository whose configuration is at `config_path`. Tracked
+    /// copies are still looked up in `ORDINATOR_HOME` or `~/.dotfiles`, as
+    /// the CLI does.
+    pub fn open(config_path: &Path) -> Result<Self> {
+        Ok(Self {
+            config: Config::from_file(config_path)?,
+            config_path: config_path.to_path_buf(),
+        })
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The configuration, to change before [`Repository::save`]
+    pub fn config_mut(&mut self) -> &mut Config {
+        &mut self.config
+    }
+
+    pub fn config_path(&self) -> &Path {
+        &self.config_path
+    }
+
+    /// Directory holding `ordinator.toml`
+    pub fn root(&self) -> &Path {
+        self.config_path.parent().unwrap_or_else(|| Path::new("."))
+    }
+
+    /// Write the configuration back to `ordinator.toml`
+    pub fn save(&self) -> Result<()> {
+        self.config.save_to_file(&self.config_path)
+    }
+
+    /// Profile names, sorted
+    pub fn profiles(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .config
+            .list_profiles()
+            .into_iter()
+            .map(String::as_str)
+            .collect();
+        names.sort();
+        names
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&ProfileConfig> {
+        self.config.get_profile(name)
+    }
+
+    pub fn default_profile(&self) -> &str {
+        self.config.default_profile()
+    }
+
+    /// Where tracked files are placed: `[global] target_root`, or the home directory
+    pub fn target_root(&self) -> Result<PathBuf> {
+        self.config.target_root()
+    }
+
+    /// What `ordinator apply` would change for `profile`, as `ordinator plan` shows it
+    pub async fn plan(&self, profile: &str) -> Result<Plan> {
+        crate::plan::build(
+            &self.config,
+            profile,
+            &self.target_root()?,
+            false,
+            false,
+            false,
+        )
+        .await
+    }
+
+    /// Each tracked file and secret of `profile` with its state, as
+    /// `ordinator files` lists them
+    pub fn status(&self, profile: &str) -> Result<Vec<FileEntry>> {
+        crate::listing::list(&self.config, profile, &self.target_root()?, self.root())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_edit_and_save() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("ordinator.toml");
+        Config::create_default().save_to_file(&path).unwrap();
+
+        let mut repo = Repository::open(&path).unwrap();
+        assert_eq!(repo.profiles(), ["default", "personal", "work"]);
+        assert_eq!(repo.root(), temp.path());
+        repo.config_mut()
+            .add_file_to_profile("work", ".gitconfig".to_string())
+            .unwrap();
+        repo.save().unwrap();
+
+        let reopened = Repository::open(&path).unwrap();
+        assert_eq!(reopened.profile("work").unwrap().files, [".gitconfig"]);
+        assert!(Repository::open(&temp.path().join("missing.toml")).is_err());
+    }
+}
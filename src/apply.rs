@@ -0,0 +1,1591 @@
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+use crate::config::{Config, ProfileOverlap, ResolvedProfile};
+use crate::error::OrdinatorError;
+use crate::filter::PathFilter;
+use crate::hooks::{HookEvent, HookRunner};
+use crate::progress::{Progress, ProgressMode};
+use crate::snapshot::{Snapshot, SnapshotKind};
+use crate::sudo::PrivilegedLink;
+use crate::utils::color_enabled;
+
+/// What `ordinator apply` should do
+#[derive(Debug, Clone)]
+pub struct ApplyOptions {
+    /// Profiles to apply; where two track the same path, the later one wins
+    pub profiles: Vec<String>,
+
+    /// Machine role the profiles come from (`apply --machine`), recorded in the machine state
+    pub machine: Option<String>,
+
+    /// Directory files are linked into, normally the user's home directory
+    pub home_dir: PathBuf,
+
+    /// Tracked paths to apply (`--only`, `--skip`). With `--only`, bootstrap,
+    /// Homebrew, macOS defaults, launchd agents, and GPG keys are skipped too.
+    pub filter: PathFilter,
+
+    pub skip_bootstrap: bool,
+    pub skip_secrets: bool,
+    pub skip_brew: bool,
+    pub skip_defaults: bool,
+
+    /// Profiles whose secrets are left encrypted, as if they set `secrets_enabled = false`
+    pub no_secrets_for: Vec<String>,
+
+    /// Mark this machine as shared (or not) in the machine state, instead of asking
+    pub shared: Option<bool>,
+
+    /// Refuse to apply unless the repository matches `ordinator.lock`
+    pub frozen: bool,
+
+    /// Overwrite existing files that are in the way
+    pub force: bool,
+
+    /// Write links that need sudo to this script instead of running sudo
+    pub sudo_script: Option<PathBuf>,
+
+    /// Save every destination in a snapshot before changing anything
+    pub snapshot: Option<SnapshotKind>,
+
+    /// Fail once the apply is done if a Homebrew package failed to install,
+    /// instead of only reporting it
+    pub strict: bool,
+
+    /// Most files to check or hash at once
+    pub jobs: usize,
+
+    /// How to report progress through the files being linked
+    pub progress: ProgressMode,
+
+    pub dry_run: bool,
+    pub quiet: bool,
+    pub verbose: bool,
+}
+
+/// What an apply changed. Dry runs report nothing as changed.
+#[derive(Debug, Default, Serialize)]
+pub struct ApplyReport {
+    /// Profiles applied, comma-separated
+    pub profile: String,
+    pub dry_run: bool,
+
+    /// Paths tracked by more than one of the profiles applied together
+    pub overlaps: Vec<ProfileOverlap>,
+
+    /// Symlinks created where nothing existed
+    pub symlinked: Vec<PathBuf>,
+
+    /// Existing files replaced by symlinks (with `force`)
+    pub replaced: Vec<PathBuf>,
+
+    /// Broken or stale symlinks pointed back at the tracked file
+    pub repaired: Vec<PathBuf>,
+
+    /// Templates rendered, and encrypted files decrypted, to their destination
+    pub rendered: Vec<PathBuf>,
+
+    /// Links created with sudo, or written to the sudo script
+    pub privileged: Vec<PathBuf>,
+
+    /// Secrets decrypted to their destination
+    pub secrets_decrypted: Vec<PathBuf>,
+
+    /// Secrets whose destination already held the decrypted contents, left unwritten
+    pub secrets_up_to_date: Vec<PathBuf>,
+
+    /// macOS defaults written
+    pub defaults_written: usize,
+
+    /// launchd agents installed or updated in `~/Library/LaunchAgents`
+    pub launch_agents_installed: Vec<PathBuf>,
+
+    /// Old backups deleted under `backup_retention`
+    pub backups_pruned: usize,
+
+    /// Changes recorded in the journal; non-zero means `ordinator rollback` can undo this apply
+    pub journal_entries: usize,
+
+    /// Snapshot taken before anything changed, for `ordinator snapshots restore`
+    pub snapshot: Option<String>,
+
+    /// Homebrew taps, formulas, casks, and App Store apps that failed to install
+    pub brew_failed: Vec<String>,
+
+    /// Missing parent directories created for destinations
+    pub directories_created: Vec<PathBuf>,
+
+    /// GPG keys imported into the keyring, by fingerprint
+    pub gpg_keys_imported: Vec<String>,
+}
+
+/// Apply one or more profiles: link tracked files, decrypt secrets, install
+/// Homebrew packages, and write macOS defaults. Progress is printed as it
+/// happens; the returned report summarizes what changed.
+pub async fn run(
+    config: &Config,
+    config_path: &Path,
+    options: &ApplyOptions,
+) -> Result<ApplyReport> {
+    let profiles = &options.profiles;
+    if profiles.is_empty() {
+        return Err(anyhow::anyhow!("No profile to apply."));
+    }
+    if let Some(missing) = profiles.iter().find(|p| !config.profiles.contains_key(*p)) {
+        return Err(OrdinatorError::ProfileNotFound(missing.to_string()).into());
+    }
+    // Profiles applied together share one name in messages, hooks, and the journal
+    let label = profiles.join(",");
+    let profile = label.as_str();
+    let ApplyOptions {
+        mut skip_bootstrap,
+        mut skip_secrets,
+        mut skip_brew,
+        mut skip_defaults,
+        force,
+        dry_run,
+        quiet,
+        verbose,
+        ..
+    } = *options;
+    let dotfiles_dir = config_path.parent().unwrap();
+    if options.frozen {
+        let diff = crate::lock::check(config, dotfiles_dir)?;
+        if !diff.is_empty() {
+            return Err(anyhow::anyhow!(
+                "The repository doesn't match {}:\n  {}\nRun 'ordinator lock' to accept these changes, or apply without --frozen.",
+                crate::lock::LOCK_FILE,
+                diff.lines().join("\n  ")
+            ));
+        }
+    }
+    let mut report = ApplyReport {
+        profile: profile.to_string(),
+        dry_run,
+        ..Default::default()
+    };
+
+    info!("Applying profile: {}", profile);
+    if !quiet {
+        if profiles.len() > 1 {
+            eprintln!("Applying profiles: {}", profiles.join(", "));
+        } else {
+            eprintln!("Applying profile: {profile}");
+        }
+    }
+
+    // Conflicts between the profiles are reported before anything changes
+    let (mut resolved, overlaps) = config.resolve_profiles(profiles)?;
+    if !overlaps.is_empty() {
+        eprintln!("Profiles track the same paths; the later profile's copy is used:");
+        for overlap in &overlaps {
+            let overridden: Vec<String> = overlap
+                .overridden
+                .iter()
+                .map(|name| format!("'{name}'"))
+                .collect();
+            let msg = format!(
+                "  {}: from '{}', not {}",
+                overlap.path,
+                overlap.winner,
+                overridden.join(", ")
+            );
+            if color_enabled() {
+                eprintln!("{}", msg.yellow());
+            } else {
+                eprintln!("{msg}");
+            }
+        }
+    }
+    report.overlaps = overlaps;
+
+    // Files for a shell this machine doesn't have are left out
+    for skipped in config.withhold_shell_files(&mut resolved, &crate::shell::Shells::detect()) {
+        if !quiet {
+            eprintln!("Skipping {} file(s): {skipped}", skipped.files.len());
+        }
+    }
+
+    // --only narrows the apply to part of the tracked files and nothing else
+    resolved
+        .secrets
+        .retain(|entry| options.filter.matches(&entry.path));
+    if options.filter.is_partial() {
+        skip_bootstrap = true;
+        skip_brew = true;
+        skip_defaults = true;
+        resolved.launch_agents.clear();
+        resolved.gpg_keys.clear();
+        resolved.signing_key = None;
+        if !quiet {
+            eprintln!(
+                "Only applying tracked files matching --only; skipping bootstrap, Homebrew, macOS defaults, launchd agents, and GPG keys"
+            );
+        }
+    }
+
+    let mut state = crate::state::MachineState::load(&options.home_dir)?;
+    if let Some((current, applied)) = state
+        .current()
+        .filter(|(name, _)| !profiles.iter().any(|p| p == name))
+    {
+        eprintln!(
+            "Warning: Switching from profile '{current}' (applied {}) to '{profile}'. Files only in '{current}' stay linked; run 'ordinator uninstall --profile {current}' to remove them.",
+            applied.applied_at_display()
+        );
+    }
+    if skip_bootstrap {
+        info!("Skipping bootstrap");
+        if !quiet {
+            eprintln!("Skipping bootstrap");
+        }
+    }
+    // A shared machine never gets secrets; the first apply with secrets asks
+    if let Some(shared) = options.shared {
+        state.shared = Some(shared);
+    } else if state.shared.is_none()
+        && !skip_secrets
+        && !dry_run
+        && !resolved.secrets.is_empty()
+        && crate::prompt::interactive()
+        && !crate::prompt::assume_yes()
+    {
+        state.shared = Some(crate::prompt::confirm(
+            "Is this machine shared with other people? Secrets won't be decrypted on it",
+            false,
+        ));
+    }
+    if state.shared == Some(true) && !skip_secrets {
+        if !quiet {
+            eprintln!(
+                "Skipping secrets: this machine is marked as shared. Apply with --not-shared to decrypt them."
+            );
+        }
+        skip_secrets = true;
+    }
+    if !skip_secrets {
+        let withheld =
+            config.withhold_secrets(profiles, &options.no_secrets_for, &mut resolved.secrets)?;
+        if !quiet && !withheld.is_empty() {
+            let off: Vec<String> = profiles
+                .iter()
+                .chain(withheld.iter().map(|entry| &entry.profile))
+                .filter(|name| config.secrets_disabled(name, &options.no_secrets_for))
+                .map(|name| format!("'{name}'"))
+                .collect::<std::collections::BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            eprintln!(
+                "Skipping {} secret(s): secrets are off for profile {}",
+                withheld.len(),
+                off.join(", ")
+            );
+        }
+    }
+    if skip_secrets {
+        info!("Skipping secrets");
+        if !quiet {
+            eprintln!("Skipping secrets");
+        }
+    } else if resolved
+        .files
+        .iter()
+        .any(|entry| crate::template::is_template(&entry.path))
+    {
+        // Secret values (`secrets set`) are template variables too
+        let sources =
+            config.secret_value_profiles(profiles, &options.no_secrets_for, &resolved.chain)?;
+        crate::secrets::add_secret_values(dotfiles_dir, &sources, &mut resolved.vars)?;
+    }
+
+    // Tracked directories expand into one link target per file (or the whole directory)
+    let mut link_targets = config.link_targets(&resolved, false)?;
+    link_targets.retain(|(file, _, _)| options.filter.matches(file));
+
+    // Every destination that can't be written is reported before anything changes
+    let problems = permission_problems(config, &link_targets, &options.home_dir);
+    if !problems.is_empty() {
+        eprintln!("{}", crate::permissions::report(&problems));
+        if !dry_run {
+            return Err(OrdinatorError::NotWritable(problems.len()).into());
+        }
+    }
+
+    if dry_run {
+        info!("[DRY RUN] Would apply profile: {}", profile);
+        eprintln!("DRY-RUN: Would apply profile: {profile}");
+        if skip_bootstrap {
+            info!("[DRY RUN] Would skip bootstrap");
+            eprintln!("DRY-RUN: Would skip bootstrap");
+        }
+        if skip_secrets {
+            info!("[DRY RUN] Would skip secrets");
+            eprintln!("DRY-RUN: Would skip secrets");
+        }
+        if skip_brew {
+            info!("[DRY RUN] Would skip brew");
+            eprintln!("DRY-RUN: Would skip brew");
+        }
+        if skip_defaults {
+            info!("[DRY RUN] Would skip defaults");
+            eprintln!("DRY-RUN: Would skip defaults");
+        }
+        if options.snapshot.is_some() {
+            let destinations =
+                snapshot_paths(&link_targets, &resolved, skip_secrets, &options.home_dir);
+            eprintln!(
+                "DRY-RUN: Would snapshot {} destination(s)",
+                destinations.len()
+            );
+        }
+        let runner = HookRunner::new(dotfiles_dir, true);
+        runner.run(&resolved.hooks, HookEvent::PreApply, profile)?;
+        runner.run(&resolved.hooks, HookEvent::PostApply, profile)?;
+        return Ok(report);
+    }
+
+    if let Some(kind) = options.snapshot {
+        let destinations =
+            snapshot_paths(&link_targets, &resolved, skip_secrets, &options.home_dir);
+        let (snapshot, warning) =
+            Snapshot::take(&options.home_dir, profile, None, &destinations, kind)?;
+        if let Some(warning) = warning {
+            eprintln!("Warning: {warning}");
+        }
+        if !quiet {
+            eprintln!(
+                "Snapshot {} saved {} destination(s)",
+                snapshot.id,
+                snapshot.entries.len()
+            );
+        }
+        report.snapshot = Some(snapshot.id);
+    }
+
+    let hook_runner = HookRunner::new(dotfiles_dir, false);
+    hook_runner.run(&resolved.hooks, HookEvent::PreApply, profile)?;
+
+    debug!("Config loaded from: {}", config_path.display());
+    debug!("Available profiles: {:?}", config.list_profiles());
+
+    // For each tracked file, symlink with enhanced conflict resolution
+    use crate::utils::{create_symlink_with_conflict_resolution, get_symlink_target, is_symlink};
+    let home_dir = &options.home_dir;
+
+    // Record every change so `ordinator rollback` can undo this apply
+    use crate::journal::{ApplyJournal, JournalAction};
+    let mut journal = ApplyJournal::begin(dotfiles_dir, profile)?;
+    let directory_modes =
+        crate::parents::DirectoryModes::new(home_dir, &config.global.directory_modes)?;
+    let backup_dir = crate::backups::BackupDir::new(config, dotfiles_dir, home_dir)?;
+    let symlink_backups = config
+        .global
+        .create_backups
+        .unwrap_or(true)
+        .then_some(&backup_dir);
+
+    debug!("Profile '{}' has {} files:", profile, resolved.files.len());
+    for entry in &resolved.files {
+        debug!("  - {} (from {})", entry.path, entry.profile);
+    }
+
+    let mut privileged = Vec::new();
+    let mut hash_cache = crate::drift::HashCache::load(dotfiles_dir);
+
+    // Check every destination up front, concurrently; changes below are made in order
+    let checks = link_targets
+        .iter()
+        .map(|(file, _, source)| {
+            // Absolute paths (e.g. /etc/hosts) are used as-is by join
+            let dest = crate::paths::destination(home_dir, crate::template::rendered_path(file));
+            (source.clone(), dest)
+        })
+        .collect();
+    // Anything outside the user's own home may need sudo, including another
+    // user's home set as `target_root`
+    let home = crate::utils::get_home_dir().unwrap_or_else(|_| home_dir.clone());
+    let inspections = crate::utils::map_concurrent(checks, options.jobs, move |(source, dest)| {
+        inspect_link(&source, &dest, !dest.starts_with(&home))
+    })
+    .await?;
+
+    let mut progress = Progress::start(options.progress, quiet, "apply", link_targets.len());
+    for ((file, source_profile, source_path), inspection) in link_targets.iter().zip(inspections) {
+        progress.advance(file);
+        if verbose && !profiles.contains(source_profile) {
+            progress.eprintln(format!(
+                "  {file}: inherited from profile '{source_profile}'"
+            ));
+        }
+        let source_path = source_path.clone();
+        let dest = inspection.dest;
+
+        if inspection.state == LinkState::MissingSource {
+            return Err(missing_source_file_error(&source_path, &dest));
+        }
+
+        // Templates, encrypted files, and managed blocks are written to the
+        // destination instead of symlinked
+        let file_options = config.file_options(source_profile, file);
+        let encrypted = file_options.encrypt;
+        let written = encrypted || file_options.managed_block || crate::template::is_template(file);
+
+        // Links share the repository copy's mode, which git only keeps the executable bit of
+        let mode = config.file_mode(source_profile, file);
+        if let Some(mode) = mode.filter(|_| !dry_run && !written) {
+            if source_path.is_file() {
+                crate::utils::set_file_mode(&source_path, mode)?;
+            }
+        }
+
+        // Destinations the current user can't write are linked together with sudo after this loop
+        if file_options.requires_sudo || inspection.needs_privileges {
+            if written {
+                return Err(anyhow::anyhow!(
+                    "Cannot write {file} to {}: it needs sudo. Track a plain, unencrypted file instead.",
+                    dest.display()
+                ));
+            }
+            match inspection.state {
+                LinkState::Linked => {
+                    if verbose {
+                        progress.eprintln(format!("  {}: Valid symlink", dest.display()));
+                    }
+                }
+                LinkState::Conflict if !force && !confirm_replace(&progress, &dest, dry_run) => {
+                    return Err(OrdinatorError::TargetExists(dest.to_path_buf()).into());
+                }
+                state => {
+                    // A real file or directory in the way is removed by the
+                    // script once it's backed up. Without a backup it would
+                    // go to the Trash, which sudo can't do for us.
+                    let in_the_way = state == LinkState::Conflict;
+                    if in_the_way && config.global.create_backups.unwrap_or(true) {
+                        let backup_path = backup_dir.backup(&dest)?;
+                        progress.eprintln(format!(
+                            "Backed up {} to {}",
+                            dest.display(),
+                            backup_path.display()
+                        ));
+                    } else if in_the_way && !crate::discard::permanent() {
+                        return Err(anyhow::anyhow!(
+                            "Cannot replace {} with sudo: with create_backups = false it would go to the Trash, which needs your own permissions. Move it aside yourself, or use --permanent to delete it.",
+                            dest.display()
+                        ));
+                    }
+                    privileged.push(PrivilegedLink {
+                        source: source_path.clone(),
+                        dest: dest.clone(),
+                        remove_existing: in_the_way,
+                    });
+                }
+            }
+            continue;
+        }
+
+        // Templates are rendered, and encrypted files decrypted, to the destination
+        if written {
+            let (rendered, verb) = if encrypted {
+                let plaintext = crate::secrets::decrypt_tracked_file(&source_path)
+                    .with_context(|| format!("Failed to decrypt {file}"))?;
+                (plaintext, "Decrypted")
+            } else if crate::template::is_template(file) {
+                let rendered = crate::template::render_file(&source_path, &resolved.vars)?;
+                (rendered.into_bytes(), "Rendered")
+            } else {
+                (std::fs::read(&source_path)?, "Updated")
+            };
+            if file_options.managed_block {
+                let contents = String::from_utf8(rendered).map_err(|_| {
+                    anyhow::anyhow!(
+                        "{file} is not a text file, so it can't be kept in a managed block"
+                    )
+                })?;
+                if !dry_run {
+                    hash_cache.record_sync(
+                        source_profile,
+                        file,
+                        crate::block::hash(contents.as_bytes()),
+                    );
+                }
+                if !dry_run {
+                    create_parents(&directory_modes, &dest, &mut journal, &mut report)?;
+                }
+                match write_block(&dest, &contents, dry_run)? {
+                    None => {
+                        if verbose {
+                            progress.eprintln(format!(
+                                "  {}: Managed block up to date",
+                                dest.display()
+                            ));
+                        }
+                    }
+                    Some(_) if dry_run => progress.println(format!(
+                        "DRY-RUN: Would update the managed block in {} from {}",
+                        dest.display(),
+                        source_path.display()
+                    )),
+                    Some(action) => {
+                        journal.record(action)?;
+                        report.rendered.push(dest.clone());
+                        if !quiet {
+                            let msg = format!(
+                                "Updated block: {} from {}",
+                                dest.display(),
+                                source_path.display()
+                            );
+                            if color_enabled() {
+                                progress.println(msg.green().to_string());
+                            } else {
+                                progress.println(msg);
+                            }
+                        }
+                    }
+                }
+                continue;
+            }
+            if !dry_run {
+                let rendered_hash = crate::drift::hash_bytes(&rendered);
+                hash_cache.record_sync(source_profile, file, rendered_hash);
+            }
+            let dest_is_symlink = is_symlink(&dest);
+            if !dest_is_symlink && std::fs::read(&dest).ok().as_deref() == Some(rendered.as_slice())
+            {
+                if verbose {
+                    let kind = if encrypted { "file" } else { "template" };
+                    progress.eprintln(format!("  {}: {verb} {kind} up to date", dest.display()));
+                }
+                continue;
+            }
+
+            let create_backups = config.global.create_backups.unwrap_or(true);
+            let dest_is_file = !dest_is_symlink && dest.exists();
+            if dest_is_file
+                && !create_backups
+                && !force
+                && !confirm_replace(&progress, &dest, dry_run)
+            {
+                return Err(anyhow::anyhow!(
+                    "Target {} already exists and differs from the {}. Use --force to overwrite.",
+                    dest.display(),
+                    if encrypted {
+                        "decrypted file"
+                    } else {
+                        "rendered template"
+                    }
+                ));
+            }
+            let backup = if dest_is_file && create_backups {
+                let backup_path = backup_dir.backup(&dest)?;
+                progress.eprintln(format!(
+                    "Backed up {} to {}",
+                    dest.display(),
+                    backup_path.display()
+                ));
+                Some(backup_path)
+            } else {
+                // Without a backup, what was there goes to the Trash instead of being overwritten
+                if dest_is_file
+                    && crate::discard::discard(&dest)? == crate::discard::Discarded::Trashed
+                {
+                    progress.eprintln(format!("Moved {} to the Trash", dest.display()));
+                }
+                None
+            };
+            let previous_target = if dest_is_symlink {
+                let target = get_symlink_target(&dest).ok();
+                std::fs::remove_file(&dest)?;
+                target
+            } else {
+                None
+            };
+            create_parents(&directory_modes, &dest, &mut journal, &mut report)?;
+            std::fs::write(&dest, rendered)?;
+            if let Some(mode) = mode {
+                crate::utils::set_file_mode(&dest, mode)?;
+            }
+            journal.record(JournalAction::FileRendered {
+                path: dest.clone(),
+                backup,
+                previous_target,
+            })?;
+            report.rendered.push(dest.clone());
+            if !quiet {
+                let msg = format!("{verb}: {} from {}", dest.display(), source_path.display());
+                if color_enabled() {
+                    progress.println(msg.green().to_string());
+                } else {
+                    progress.println(msg);
+                }
+            }
+            continue;
+        }
+
+        if inspection.state == LinkState::Absent {
+            if dry_run {
+                let msg = format!(
+                    "DRY-RUN: Would create symlink {} -> {}",
+                    dest.display(),
+                    source_path.display()
+                );
+                if color_enabled() {
+                    progress.println(msg.yellow().to_string());
+                } else {
+                    progress.println(msg);
+                }
+            } else {
+                create_parents(&directory_modes, &dest, &mut journal, &mut report)?;
+                create_symlink_with_conflict_resolution(
+                    &source_path,
+                    &dest,
+                    force,
+                    symlink_backups,
+                )?;
+                journal.record(JournalAction::SymlinkCreated {
+                    path: dest.clone(),
+                    target: source_path.clone(),
+                })?;
+                report.symlinked.push(dest.clone());
+                if !quiet {
+                    let msg = format!("Symlinked: {} -> {}", dest.display(), source_path.display());
+                    if color_enabled() {
+                        progress.println(msg.green().to_string());
+                    } else {
+                        progress.println(msg);
+                    }
+                }
+            }
+            continue;
+        }
+
+        if inspection.state == LinkState::Conflict {
+            let force = force || confirm_replace(&progress, &dest, dry_run);
+            if !force {
+                // Handle non-symlink conflict
+                let msg = format!(
+                    "Conflict: {} already exists and is not a symlink",
+                    dest.display()
+                );
+                if color_enabled() {
+                    progress.eprintln(msg.red().to_string());
+                    progress.eprintln(
+                        "Use --force to overwrite, or manually remove the file first."
+                            .yellow()
+                            .to_string(),
+                    );
+                } else {
+                    progress.eprintln(&msg);
+                    progress
+                        .eprintln("Use --force to overwrite, or manually remove the file first.");
+                }
+                return Err(OrdinatorError::TargetExists(dest.to_path_buf()).into());
+            }
+            // Force overwrite - create symlink
+            let msg = format!(
+                "Force creating symlink: {} -> {}",
+                dest.display(),
+                source_path.display()
+            );
+            if color_enabled() {
+                progress.println(msg.yellow().to_string());
+            } else {
+                progress.println(msg);
+            }
+            if dry_run {
+                let msg = format!(
+                    "DRY-RUN: Would force create symlink {} -> {}",
+                    dest.display(),
+                    source_path.display()
+                );
+                if color_enabled() {
+                    progress.println(msg.yellow().to_string());
+                } else {
+                    progress.println(msg);
+                }
+            } else {
+                let backup = create_symlink_with_conflict_resolution(
+                    &source_path,
+                    &dest,
+                    force,
+                    symlink_backups,
+                )?;
+                journal.record(JournalAction::FileReplaced {
+                    path: dest.clone(),
+                    target: source_path.clone(),
+                    backup,
+                })?;
+                report.replaced.push(dest.clone());
+                if !quiet {
+                    let msg = format!("Symlinked: {} -> {}", dest.display(), source_path.display());
+                    if color_enabled() {
+                        progress.println(msg.green().to_string());
+                    } else {
+                        progress.println(msg);
+                    }
+                }
+            }
+            continue;
+        }
+
+        // An existing symlink that points somewhere else is repaired
+        if inspection.state == LinkState::Stale {
+            if dry_run {
+                progress.eprintln(format!("DRY-RUN: Would repair {}", dest.display()));
+            } else {
+                use crate::utils::repair_symlink;
+                let previous_target = get_symlink_target(&dest).ok();
+                repair_symlink(&dest, &source_path)?;
+                if let Some(previous_target) = previous_target {
+                    journal.record(JournalAction::SymlinkRepaired {
+                        path: dest.clone(),
+                        previous_target,
+                    })?;
+                }
+                report.repaired.push(dest.clone());
+                if !quiet {
+                    progress.eprintln(format!(
+                        "Repaired: {} -> {}",
+                        dest.display(),
+                        source_path.display()
+                    ));
+                }
+            }
+        } else if verbose {
+            progress.eprintln(format!("  {}: Valid symlink", dest.display()));
+        }
+    }
+
+    progress.finish();
+
+    if !privileged.is_empty() {
+        if let Some(script_path) = &options.sudo_script {
+            crate::sudo::write_script(&privileged, script_path)?;
+            eprintln!(
+                "Wrote {} link(s) that need sudo to {}. Run it with: sudo sh {}",
+                privileged.len(),
+                script_path.display(),
+                script_path.display()
+            );
+        } else {
+            eprintln!(
+                "{} link(s) need administrator privileges; running sudo",
+                privileged.len()
+            );
+            crate::sudo::run(&privileged)?;
+            if !quiet {
+                for link in &privileged {
+                    eprintln!(
+                        "Symlinked with sudo: {} -> {}",
+                        link.dest.display(),
+                        link.source.display()
+                    );
+                }
+            }
+        }
+        report.privileged = privileged.into_iter().map(|link| link.dest).collect();
+    }
+
+    if !quiet && !link_targets.is_empty() {
+        let changed = report.symlinked.len()
+            + report.replaced.len()
+            + report.repaired.len()
+            + report.rendered.len()
+            + report.privileged.len();
+        eprintln!(
+            "Files: {} symlinked, {} replaced, {} repaired, {} rendered, {} with sudo, {} unchanged",
+            report.symlinked.len(),
+            report.replaced.len(),
+            report.repaired.len(),
+            report.rendered.len(),
+            report.privileged.len(),
+            link_targets.len().saturating_sub(changed)
+        );
+    }
+
+    // Remember what each linked file looked like in sync, for `ordinator status`
+    if !dry_run {
+        let linked: Vec<&(String, String, PathBuf)> = link_targets
+            .iter()
+            .filter(|(file, _, source_path)| {
+                source_path.is_file()
+                    && crate::utils::symlink_points_to(
+                        &crate::paths::destination(home_dir, file),
+                        source_path,
+                    )
+            })
+            .collect();
+        let sources: Vec<PathBuf> = linked.iter().map(|(_, _, source)| source.clone()).collect();
+        let hashes = hash_cache.hash_files(&sources, options.jobs).await?;
+        for ((file, source_profile, _), hash) in linked.into_iter().zip(hashes) {
+            hash_cache.record_sync(source_profile, file, hash);
+        }
+        hash_cache.save()?;
+    }
+
+    // Check for each profile's bootstrap script if not skipped
+    if !skip_bootstrap {
+        let receipts = crate::receipts::load(home_dir)?;
+        for profile in profiles {
+            if let Some(script_path) = config.get_bootstrap_script(profile) {
+                let full_script_path = dotfiles_dir.join(&script_path);
+
+                if full_script_path.exists() {
+                    let content = std::fs::read(&full_script_path).with_context(|| {
+                        format!("Failed to read {}", full_script_path.display())
+                    })?;
+                    if let Some(last) =
+                        crate::receipts::changed_since_success(&receipts, profile, &content)
+                    {
+                        eprintln!(
+                            "⚠️  Warning: The bootstrap script for profile '{profile}' changed since it last ran successfully here ({}). To run it again: ordinator bootstrap run --profile {profile}",
+                            crate::utils::format_timestamp(&last.ran_at)
+                        );
+                    }
+                    if !quiet {
+                        eprintln!("Bootstrap script found: {}", full_script_path.display());
+                        eprintln!("To run the bootstrap script, execute:");
+                        eprintln!("  bash {}", full_script_path.display());
+                    }
+                    info!("Bootstrap script available: {}", full_script_path.display());
+                } else {
+                    if !quiet {
+                        eprintln!("No bootstrap script found for profile '{profile}'");
+                        eprintln!(
+                            "To create one, run: ordinator bootstrap generate --profile {profile}"
+                        );
+                    }
+                    info!("No bootstrap script found for profile '{}'", profile);
+                }
+            } else {
+                if !quiet {
+                    eprintln!("No bootstrap script configured for profile '{profile}'");
+                    eprintln!(
+                        "To create one, run: ordinator bootstrap generate --profile {profile}"
+                    );
+                }
+                info!("No bootstrap script configured for profile '{}'", profile);
+            }
+        }
+    } else {
+        info!("Skipped bootstrap script check");
+        if !quiet {
+            eprintln!("Skipped bootstrap script check");
+        }
+    }
+
+    // Handle secrets decryption if not skipped
+    if !skip_secrets {
+        use crate::secrets::{
+            age_key_exists, decrypt_secret, handle_interactive_age_key_setup, is_file_encrypted,
+        };
+        use std::fs;
+
+        let mut skip_secrets_decryption = false;
+        if !resolved.secrets.is_empty() {
+            // Check that each profile with secrets has an age key before attempting decryption
+            for profile in profiles {
+                let has_secrets =
+                    profiles.len() == 1 || !config.resolve_profile(profile)?.secrets.is_empty();
+                if !has_secrets || age_key_exists(profile) || skip_secrets_decryption {
+                    continue;
+                }
+                if !quiet {
+                    eprintln!("AGE key not found for profile '{profile}'");
+                }
+
+                if dry_run {
+                    eprintln!("DRY-RUN: Would prompt for age key setup");
+                } else {
+                    // Handle interactive age key setup
+                    match handle_interactive_age_key_setup(profile) {
+                        Ok(()) => {
+                            if !quiet {
+                                eprintln!("AGE key setup completed successfully");
+                            }
+                        }
+                        Err(e) => {
+                            if !quiet {
+                                eprintln!("AGE key setup failed: {e}");
+                                eprintln!("Continuing with apply without secrets decryption");
+                            }
+                            // Set flag to skip secrets decryption
+                            skip_secrets_decryption = true;
+                        }
+                    }
+                }
+            }
+
+            if !skip_secrets_decryption {
+                if !quiet {
+                    eprintln!("Decrypting secrets for profile '{profile}'");
+                }
+
+                for entry in &resolved.secrets {
+                    let secret_path = &entry.path;
+                    if verbose && !profiles.contains(&entry.profile) {
+                        eprintln!(
+                            "  {secret_path}: inherited from profile '{}'",
+                            entry.profile
+                        );
+                    }
+                    // Get the encrypted file path in the repository: where
+                    // `secrets add` stored it, else the tracked file layout
+                    let encrypted_file_path = crate::secrets::stored_secret_path(
+                        config,
+                        dotfiles_dir,
+                        &entry.profile,
+                        secret_path,
+                    )?;
+
+                    if !encrypted_file_path.exists() {
+                        if !quiet {
+                            eprintln!(
+                                "Warning: Encrypted secret file not found: {}",
+                                encrypted_file_path.display()
+                            );
+                        }
+                        continue;
+                    }
+
+                    // Check if the file is actually encrypted
+                    if !is_file_encrypted(&encrypted_file_path) {
+                        if !quiet {
+                            eprintln!(
+                                "Warning: File does not appear to be encrypted: {}",
+                                encrypted_file_path.display()
+                            );
+                        }
+                        continue;
+                    }
+
+                    // Determine the target path where the decrypted file should be placed
+                    let target_path = crate::paths::destination(home_dir, secret_path);
+
+                    if dry_run {
+                        if !quiet {
+                            eprintln!(
+                                "DRY-RUN: Would decrypt {} to {}",
+                                encrypted_file_path.display(),
+                                target_path.display()
+                            );
+                        }
+                    } else {
+                        // Decrypt in memory; the plaintext only ever lands at the destination
+                        let decrypted_content = match decrypt_secret(&encrypted_file_path) {
+                            Ok(plaintext) => plaintext,
+                            Err(e) => {
+                                // Check if this is a key mismatch error
+                                use crate::secrets::handle_key_mismatch_error;
+                                match handle_key_mismatch_error(&encrypted_file_path, &e) {
+                                    // Skip this file
+                                    Ok(true) => continue,
+                                    // Retry decryption with new key
+                                    Ok(false) => decrypt_secret(&encrypted_file_path)?,
+                                    Err(e) => return Err(e),
+                                }
+                            }
+                        };
+
+                        // Secrets default to 600 unless another mode was recorded,
+                        // configured, or kept in the secret's metadata
+                        let meta = crate::secret_meta::read(&encrypted_file_path)
+                            .ok()
+                            .flatten();
+                        let mode = config
+                            .file_mode(&entry.profile, secret_path)
+                            .or_else(|| meta.as_ref().and_then(|meta| meta.mode()));
+                        let owner = meta.and_then(|meta| meta.owner);
+
+                        // Leave an unchanged destination alone, so its mtime stays put
+                        // and nothing watching it sees a write
+                        if secret_up_to_date(&target_path, &decrypted_content) {
+                            crate::utils::set_file_mode(&target_path, mode.unwrap_or(0o600))?;
+                            report.secrets_up_to_date.push(target_path.clone());
+                            continue;
+                        }
+
+                        create_parents(&directory_modes, &target_path, &mut journal, &mut report)?;
+
+                        // Keep the previous contents so rollback can restore them
+                        let backup = if target_path.is_file() {
+                            Some(backup_dir.backup(&target_path)?)
+                        } else {
+                            None
+                        };
+
+                        // Copy the decrypted content to the target location
+                        if let Some(owner) = owner.filter(|owner| owner != "root") {
+                            if std::env::var("USER").is_ok_and(|user| user == "root") && !quiet {
+                                eprintln!(
+                                    "⚠️  Warning: {secret_path} belonged to '{owner}' when it was added; running as root, it will be owned by root"
+                                );
+                            }
+                        }
+                        fs::write(&target_path, decrypted_content)?;
+                        journal.record(JournalAction::SecretDecrypted {
+                            path: target_path.clone(),
+                            backup,
+                        })?;
+                        report.secrets_decrypted.push(target_path.clone());
+                        crate::utils::set_file_mode(&target_path, mode.unwrap_or(0o600))?;
+
+                        if !quiet {
+                            eprintln!(
+                                "Decrypted: {} -> {}",
+                                encrypted_file_path.display(),
+                                target_path.display()
+                            );
+                        }
+                    }
+                }
+
+                if !quiet && !dry_run {
+                    eprintln!(
+                        "Secrets: {} updated, {} up to date",
+                        report.secrets_decrypted.len(),
+                        report.secrets_up_to_date.len()
+                    );
+                    for path in &report.secrets_decrypted {
+                        eprintln!("  updated     {}", path.display());
+                    }
+                    for path in &report.secrets_up_to_date {
+                        eprintln!("  up to date  {}", path.display());
+                    }
+                }
+            }
+        } else if !quiet {
+            eprintln!("No secrets configured for profile '{profile}'");
+        }
+    } else {
+        info!("Skipped secrets decryption");
+        if !quiet {
+            eprintln!("Skipped secrets decryption");
+        }
+    }
+
+    // ssh ignores keys and configs that other users can read
+    if !dry_run && crate::ssh::tracks_ssh(&resolved) {
+        for path in crate::ssh::secure(&home_dir.join(".ssh"))? {
+            if verbose {
+                eprintln!("  {}: Restricted permissions", path.display());
+            }
+        }
+    }
+
+    // GPG keys are imported into the keyring, never written out
+    let gnupg_home = crate::gnupg::home_dir(home_dir);
+    for key in resolved.gpg_keys.iter().filter(|_| !skip_secrets) {
+        let fingerprint = &key.path;
+        if config.secrets_disabled(&key.profile, &options.no_secrets_for) {
+            continue;
+        }
+        if crate::gnupg::has_secret_key(&gnupg_home, fingerprint) {
+            if verbose {
+                eprintln!("  GPG key {fingerprint}: Already imported");
+            }
+            continue;
+        }
+        let stored = crate::gnupg::stored_key_path(dotfiles_dir, &key.profile, fingerprint);
+        if !stored.exists() {
+            eprintln!(
+                "⚠️  Warning: GPG key not found in the repository: {}",
+                stored.display()
+            );
+            continue;
+        }
+        if dry_run {
+            eprintln!(
+                "DRY-RUN: Would import GPG key {fingerprint} into {}",
+                gnupg_home.display()
+            );
+            continue;
+        }
+        let question = format!(
+            "Import GPG secret key {fingerprint} into {} and trust it ultimately?",
+            gnupg_home.display()
+        );
+        if !crate::prompt::confirm(&question, false) {
+            eprintln!("Skipped importing GPG key {fingerprint}; apply with --yes to import it");
+            continue;
+        }
+        let imported = crate::secrets::decrypt_tracked_file(&stored)
+            .and_then(|armored| crate::gnupg::import(&gnupg_home, fingerprint, &armored));
+        match imported {
+            Ok(()) => {
+                if !quiet {
+                    eprintln!("Imported GPG key {fingerprint}");
+                }
+                report.gpg_keys_imported.push(fingerprint.clone());
+            }
+            Err(e) => eprintln!("⚠️  Warning: Failed to import GPG key {fingerprint}: {e}"),
+        }
+    }
+    if let Some(key) = &resolved.signing_key {
+        let git_config = crate::gnupg::git_config_path(home_dir);
+        if dry_run {
+            eprintln!(
+                "DRY-RUN: Would set git to sign commits and tags with {key} in {}",
+                git_config.display()
+            );
+        } else {
+            match crate::gnupg::configure_git_signing(&git_config, key) {
+                Ok(written) => {
+                    for setting in written.iter().filter(|_| !quiet) {
+                        eprintln!("Set {setting} in {}", git_config.display());
+                    }
+                }
+                Err(e) => eprintln!("⚠️  Warning: Failed to set up git signing: {e}"),
+            }
+        }
+    }
+
+    // Install Homebrew packages if not skipped
+    if !skip_brew {
+        use crate::brew::BrewManager;
+
+        // Check if Homebrew is installed
+        if BrewManager::check_homebrew_installed() {
+            let brew_manager = BrewManager::new(dry_run);
+
+            if !quiet {
+                eprintln!("Installing Homebrew packages for profile '{profile}'");
+            }
+
+            match brew_manager.install_resolved(profile, &resolved).await {
+                Err(e) => {
+                    if !quiet {
+                        eprintln!("Warning: Failed to install Homebrew packages: {e}");
+                    }
+                }
+                Ok(brew_report) => {
+                    report.brew_failed = brew_report
+                        .results
+                        .iter()
+                        .filter(|result| result.error.is_some())
+                        .map(|result| result.name.clone())
+                        .collect();
+                    if !quiet {
+                        if !brew_report.is_empty() {
+                            eprintln!("{}", brew_report.table());
+                        }
+                        if report.brew_failed.is_empty() {
+                            eprintln!("✅ Homebrew packages installed successfully");
+                        } else {
+                            eprintln!(
+                                "⚠️  {} Homebrew package(s) failed to install; the rest of the apply continues",
+                                report.brew_failed.len()
+                            );
+                        }
+                    }
+                }
+            }
+            if config.brew.warn_outdated && !quiet {
+                match brew_manager.outdated_resolved(&resolved) {
+                    Ok(outdated) => {
+                        if let Some(notice) = crate::brew::outdated_notice(profile, &outdated) {
+                            eprintln!("⚠️  Warning: {notice}");
+                        }
+                    }
+                    Err(e) => eprintln!("⚠️  Warning: Failed to check for outdated packages: {e}"),
+                }
+            }
+        } else {
+            if !quiet {
+                eprintln!("⚠️  Homebrew not installed - skipping package installation");
+            }
+            info!("Homebrew not installed, skipping package installation");
+        }
+    } else {
+        info!("Skipped Homebrew package installation");
+        if !quiet {
+            eprintln!("Skipped Homebrew package installation");
+        }
+    }
+
+    // Write macOS defaults if not skipped
+    if !skip_defaults && !resolved.defaults.is_empty() {
+        use crate::defaults::DefaultsManager;
+
+        if DefaultsManager::is_available() {
+            let defaults_manager = DefaultsManager::new(dry_run);
+            match defaults_manager.apply(&resolved.defaults) {
+                Ok(changes) => {
+                    report.defaults_written = changes.len();
+                    if !quiet && !changes.is_empty() {
+                        eprintln!("✅ Applied {} macOS default(s)", changes.len());
+                    }
+                }
+                Err(e) => {
+                    if !quiet {
+                        eprintln!("Warning: Failed to apply macOS defaults: {e}");
+                    }
+                }
+            }
+        } else if !quiet {
+            eprintln!("⚠️  'defaults' command not found - skipping macOS defaults");
+        }
+    } else if skip_defaults {
+        info!("Skipped macOS defaults");
+        if !quiet {
+            eprintln!("Skipped macOS defaults");
+        }
+    }
+
+    // Install launchd agents; a changed plist is unloaded and loaded again
+    if !resolved.launch_agents.is_empty() {
+        use crate::launchd::{InstallOutcome, LaunchdManager};
+
+        let launchd_manager = LaunchdManager::new(dry_run);
+        let agents_dir = crate::launchd::agents_dir(home_dir);
+        for agent in &resolved.launch_agents {
+            let stored = crate::launchd::stored_path(dotfiles_dir, &agent.profile, &agent.path);
+            if !stored.exists() {
+                eprintln!(
+                    "⚠️  Warning: launchd agent not found in the repository: {}",
+                    stored.display()
+                );
+                continue;
+            }
+            match launchd_manager.install(&stored, &agents_dir) {
+                Ok(InstallOutcome::Unchanged) => {}
+                Ok(outcome) => {
+                    let installed = agents_dir.join(&agent.path);
+                    if !quiet {
+                        let verb = match (dry_run, outcome) {
+                            (true, InstallOutcome::Updated) => "Would update",
+                            (true, _) => "Would install",
+                            (false, InstallOutcome::Updated) => "Updated",
+                            (false, _) => "Installed",
+                        };
+                        eprintln!("{verb} launchd agent: {}", installed.display());
+                    }
+                    if !dry_run {
+                        report.launch_agents_installed.push(installed);
+                    }
+                }
+                Err(e) => {
+                    if !quiet {
+                        eprintln!(
+                            "Warning: Failed to install launchd agent {}: {e}",
+                            agent.path
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    report.journal_entries = journal.actions.len();
+
+    // Enforce the backup retention policy now that this apply's backups exist
+    let pruned = crate::backups::prune(&backup_dir, &config.global.backup_retention, false)?;
+    report.backups_pruned = pruned.len();
+    if !quiet && !pruned.is_empty() {
+        eprintln!("Pruned {} old backup(s)", pruned.len());
+    }
+
+    hook_runner.run(&resolved.hooks, HookEvent::PostApply, profile)?;
+
+    let commit = crate::git::GitManager::new(dotfiles_dir.to_path_buf())
+        .head_commit()
+        .unwrap_or(None);
+    // Applying part of a profile doesn't count as applying it
+    if !options.filter.is_partial() {
+        for profile in profiles {
+            state.record_apply(profile, dotfiles_dir, commit.clone());
+        }
+        if options.machine.is_some() {
+            state.machine = options.machine.clone();
+        }
+    }
+    state.save(&options.home_dir)?;
+    info!("Apply completed");
+    if options.strict && !report.brew_failed.is_empty() {
+        return Err(OrdinatorError::BrewPackagesFailed(report.brew_failed.len()).into());
+    }
+    Ok(report)
+}
+
+/// Where a link target stands before apply changes anything
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LinkState {
+    /// The tracked copy is missing from the repository
+    MissingSource,
+
+    /// Nothing (or a dangling symlink) at the destination
+    Absent,
+
+    /// A file or directory that is not a symlink is in the way
+    Conflict,
+
+    /// A symlink to the tracked copy
+    Linked,
+
+    /// A symlink that points somewhere else
+    Stale,
+}
+
+struct LinkInspection {
+    dest: PathBuf,
+    state: LinkState,
+
+    /// The current user can't write the destination's directory
+    needs_privileges: bool,
+}
+
+/// Destinations apply can't write. Links made with sudo (files with
+/// `requires_sudo`, and links outside the user's home) only count when even
+/// sudo can't write them.
+fn permission_problems(
+    config: &Config,
+    link_targets: &[(String, String, PathBuf)],
+    home_dir: &Path,
+) -> Vec<crate::permissions::Problem> {
+    let home = crate::utils::get_home_dir().unwrap_or_else(|_| home_dir.to_path_buf());
+    link_targets
+        .iter()
+        .filter_map(|(file, source_profile, _)| {
+            let dest = crate::paths::destination(home_dir, crate::template::rendered_path(file));
+            let file_options = config.file_options(source_profile, file);
+            let in_place = file_options.encrypt
+                || file_options.managed_block
+                || crate::template::is_template(file);
+            let with_sudo = !in_place && (file_options.requires_sudo || !dest.starts_with(&home));
+            let blocker = crate::permissions::check(&dest, in_place)
+                .filter(|blocker| !(with_sudo && blocker.sudo_can_fix()))?;
+            Some(crate::permissions::Problem { dest, blocker })
+        })
+        .collect()
+}
+
+/// Destinations `apply --snapshot` saves: every link target, and every
+/// secret unless secrets are skipped
+fn snapshot_paths(
+    link_targets: &[(String, String, PathBuf)],
+    resolved: &ResolvedProfile,
+    skip_secrets: bool,
+    home_dir: &Path,
+) -> Vec<PathBuf> {
+    let files = link_targets.iter().map(|(file, _, _)| {
+        crate::paths::destination(home_dir, crate::template::rendered_path(file))
+    });
+    let secrets = resolved
+        .secrets
+        .iter()
+        .filter(|_| !skip_secrets)
+        .map(|entry| crate::paths::destination(home_dir, &entry.path));
+    let mut paths: Vec<PathBuf> = files.chain(secrets).collect();
+    paths.dedup();
+    paths
+}
+
+/// Check one link target without changing it
+fn inspect_link(source: &Path, dest: &Path, probe_privileges: bool) -> LinkInspection {
+    use crate::utils::is_symlink;
+    let state = if !source.exists() {
+        LinkState::MissingSource
+    } else if !dest.exists() {
+        LinkState::Absent
+    } else if !is_symlink(dest) {
+        LinkState::Conflict
+    } else if crate::utils::symlink_points_to(dest, source) {
+        LinkState::Linked
+    } else {
+        LinkState::Stale
+    };
+    LinkInspection {
+        dest: dest.to_path_buf(),
+        state,
+        needs_privileges: probe_privileges && crate::sudo::needs_privileges(dest),
+    }
+}
+
+/// Ask whether to replace a file that's in the way of a link. `--yes`
+/// answers for a dry run, which otherwise never asks.
+fn confirm_replace(progress: &Progress, dest: &Path, dry_run: bool) -> bool {
+    if dry_run {
+        return crate::prompt::assume_yes();
+    }
+    progress.suspend(|| {
+        crate::prompt::confirm(
+            &format!(
+                "{} already exists and is not a symlink. Replace it?",
+                dest.display()
+            ),
+            false,
+        )
+    })
+}
+
+/// Create the missing parent directories of `dest` with their configured
+/// modes, journaling each so rollback and uninstall can remove them
+fn create_parents(
+    modes: &crate::parents::DirectoryModes,
+    dest: &Path,
+    journal: &mut crate::journal::ApplyJournal,
+    report: &mut ApplyReport,
+) -> Result<()> {
+    for (path, mode) in modes.create_parents(dest)? {
+        info!("Created directory: {}", path.display());
+        journal.record(crate::journal::JournalAction::DirectoryCreated {
+            path: path.clone(),
+            mode: mode.map(crate::config::format_mode),
+        })?;
+        report.directories_created.push(path);
+    }
+    Ok(())
+}
+
+/// Put `contents` in the managed block of `dest`, keeping the rest of the
+/// file. A symlink there is replaced by a file holding only the block.
+/// Returns the change to journal, none when the block is up to date; with
+/// `dry_run` nothing is written.
+fn write_block(
+    dest: &Path,
+    contents: &str,
+    dry_run: bool,
+) -> Result<Option<crate::journal::JournalAction>> {
+    let previous_target = crate::utils::is_symlink(dest)
+        .then(|| crate::utils::get_symlink_target(dest).ok())
+        .flatten();
+    let text = if previous_target.is_some() || !dest.exists() {
+        String::new()
+    } else {
+        std::fs::read_to_string(dest).with_context(|| {
+            format!(
+                "{} is not a text file, so it can't hold a managed block",
+                dest.display()
+            )
+        })?
+    };
+    let updated = crate::block::upsert(&text, contents);
+    if previous_target.is_none() && updated == text {
+        return Ok(None);
+    }
+    if !dry_run {
+        if previous_target.is_some() {
+            std::fs::remove_file(dest)?;
+        }
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(dest, updated)?;
+    }
+    Ok(Some(crate::journal::JournalAction::BlockWritten {
+        path: dest.to_path_buf(),
+        previous: crate::block::contents(&text).map(String::from),
+        previous_target,
+    }))
+}
+
+/// Whether `dest` is a regular file whose contents hash the same as `plaintext`
+fn secret_up_to_date(dest: &Path, plaintext: &[u8]) -> bool {
+    dest.symlink_metadata()
+        .is_ok_and(|metadata| metadata.file_type().is_file())
+        && std::fs::read(dest).is_ok_and(|current| {
+            crate::drift::hash_bytes(&current) == crate::drift::hash_bytes(plaintext)
+        })
+}
+
+/// Explain a tracked file missing from the repository, and build the error
+/// apply fails with; its hint says how to fix it
+fn missing_source_file_error(source_path: &Path, dest: &Path) -> anyhow::Error {
+    let msg = "This file may have been moved or deleted from the dotfiles repository.";
+    if color_enabled() {
+        eprintln!("{}", msg.yellow());
+    } else {
+        eprintln!("{msg}");
+    }
+    eprintln!("Expected location: {}", source_path.display());
+    eprintln!("Target location: {}", dest.display());
+    OrdinatorError::SourceMissing(source_path.to_path_buf()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProfileConfig;
+    use tempfile::tempdir;
+
+    fn options(profile: &str, home_dir: &Path) -> ApplyOptions {
+        ApplyOptions {
+            profiles: vec![profile.to_string()],
+            machine: None,
+            home_dir: home_dir.to_path_buf(),
+            filter: PathFilter::default(),
+            skip_bootstrap: true,
+            skip_secrets: true,
+            skip_brew: true,
+            skip_defaults: true,
+            no_secrets_for: Vec::new(),
+            shared: None,
+            frozen: false,
+            force: false,
+            sudo_script: None,
+            snapshot: None,
+            strict: false,
+            jobs: 2,
+            progress: ProgressMode::Plain,
+            dry_run: false,
+            quiet: true,
+            verbose: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_apply_rejects_unknown_profile() {
+        let temp = tempdir().unwrap();
+        let config = Config::default();
+        let err = run(
+            &config,
+            &temp.path().join("ordinator.toml"),
+            &options("nope", temp.path()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Profile 'nope' does not exist.");
+    }
+
+    #[tokio::test]
+    async fn test_apply_runs_hooks_and_reports_changes() {
+        let temp = tempdir().unwrap();
+        std::fs::write(temp.path().join("post.sh"), "touch post-ran\n").unwrap();
+        let mut config = Config::default();
+        let mut profile = ProfileConfig::default();
+        profile.hooks.post_apply = vec!["post.sh".to_string()];
+        config.profiles.insert("default".to_string(), profile);
+        let config_path = temp.path().join("ordinator.toml");
+
+        let mut dry_run = options("default", temp.path());
+        dry_run.dry_run = true;
+        let report = run(&config, &config_path, &dry_run).await.unwrap();
+        assert!(report.dry_run);
+        assert!(!temp.path().join("post-ran").exists());
+
+        let report = run(&config, &config_path, &options("default", temp.path()))
+            .await
+            .unwrap();
+        assert!(!report.dry_run);
+        assert!(report.symlinked.is_empty());
+        assert_eq!(report.journal_entries, 0);
+        assert!(temp.path().join("post-ran").exists());
+    }
+
+    #[test]
+    fn test_secret_up_to_date_compares_regular_files_only() {
+        let temp = tempdir().unwrap();
+        let dest = temp.path().join(".npmrc");
+        assert!(!secret_up_to_date(&dest, b"token=abc\n"));
+        std::fs::write(&dest, "token=abc\n").unwrap();
+        assert!(secret_up_to_date(&dest, b"token=abc\n"));
+        assert!(!secret_up_to_date(&dest, b"token=xyz\n"));
+
+        let link = temp.path().join(".npmrc-link");
+        std::os::unix::fs::symlink(&dest, &link).unwrap();
+        assert!(!secret_up_to_date(&link, b"token=abc\n"));
+    }
+}
@@ -0,0 +1,427 @@
+//! Backups of files apply, repair, and restore replace. They are kept under
+//! the backup directory (`[global] backup_dir`, by default next to the
+//! machine state file, outside the repository) in the layout of the files
+//! themselves: `home/.config/nvim/init.lua.backup.1.20250101-120000` for a
+//! file under the home directory (or `target_root`), `root/Library/...` for
+//! one outside it, so same-named files never share a directory.
+
+use anyhow::{Context, Result};
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::journal::ApplyJournal;
+
+/// Directory (relative to the dotfiles repository) backups went to before
+/// `backup_dir`, one flat directory keyed by file name
+const LEGACY_BACKUP_DIR: &str = "backups";
+
+/// Subdirectory of the backup directory mirroring the home directory
+const HOME_DIR: &str = "home";
+
+/// Subdirectory of the backup directory mirroring `/`, for files outside the home directory
+const ROOT_DIR: &str = "root";
+
+/// How many backups to keep. Unset limits keep backups forever.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackupRetention {
+    /// Newest backups to keep per file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_count: Option<usize>,
+
+    /// Delete backups older than this many days
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u32>,
+}
+
+impl BackupRetention {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_count.is_none() && self.max_age_days.is_none()
+    }
+}
+
+/// A backup made before apply, repair, or restore replaced a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backup {
+    pub path: PathBuf,
+
+    /// File that was backed up, relative to the home directory
+    /// (`.config/nvim/init.lua`) or absolute outside it. Backups in the old
+    /// flat directory only know the file name.
+    pub file: String,
+
+    /// Where the backed-up file lived; unknown for backups in the old flat directory
+    pub original: Option<PathBuf>,
+
+    /// Backup name, e.g. `.zshrc.backup.1.20250101-120000`
+    pub name: String,
+
+    pub created: NaiveDateTime,
+}
+
+/// Split `<file>.backup.<n>.<YYYYmmdd-HHMMSS>` into the file name and creation time
+fn parse_backup_name(name: &str) -> Option<(String, NaiveDateTime)> {
+    let (rest, timestamp) = name.rsplit_once('.')?;
+    let (file_name, number) = rest.rsplit_once(".backup.")?;
+    number.parse::<u32>().ok()?;
+    let created = NaiveDateTime::parse_from_str(timestamp, "%Y%m%d-%H%M%S").ok()?;
+    Some((file_name.to_string(), created))
+}
+
+/// Where backups are kept, and the home directory (or `target_root`) whose
+/// layout they mirror
+#[derive(Debug, Clone)]
+pub struct BackupDir {
+    root: PathBuf,
+    home_dir: PathBuf,
+    dotfiles_dir: PathBuf,
+}
+
+impl BackupDir {
+    /// The backup directory `config` names for files under `home_dir`
+    pub fn new(config: &Config, dotfiles_dir: &Path, home_dir: &Path) -> Result<Self> {
+        let root = match config.global.backup_dir.as_deref() {
+            None => crate::state::MachineState::path(home_dir).with_file_name("backups"),
+            Some("~") => crate::utils::get_home_dir()?,
+            Some(dir) => match dir.strip_prefix("~/") {
+                Some(rest) => crate::utils::get_home_dir()?.join(rest),
+                None => dotfiles_dir.join(dir),
+            },
+        };
+        Ok(Self {
+            root,
+            home_dir: home_dir.to_path_buf(),
+            dotfiles_dir: dotfiles_dir.to_path_buf(),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Directory a backup of `original` goes in
+    fn dir_for(&self, original: &Path) -> PathBuf {
+        let parent = original.parent().unwrap_or(Path::new("/"));
+        match parent.strip_prefix(&self.home_dir) {
+            Ok(relative) => self.root.join(HOME_DIR).join(relative),
+            Err(_) => self
+                .root
+                .join(ROOT_DIR)
+                .join(parent.strip_prefix("/").unwrap_or(parent)),
+        }
+    }
+
+    /// Copy `original` (a file or directory) into the backup directory,
+    /// returning the backup's path
+    pub fn backup(&self, original: &Path) -> Result<PathBuf> {
+        self.ignore_in_repository()?;
+        crate::utils::backup_into(original, &self.dir_for(original))
+    }
+
+    /// Keep backups out of commits when the backup directory is inside the
+    /// dotfiles repository, by listing it in the repository's `.gitignore`
+    fn ignore_in_repository(&self) -> Result<()> {
+        let Ok(relative) = self.root.strip_prefix(&self.dotfiles_dir) else {
+            return Ok(());
+        };
+        if relative.as_os_str().is_empty() {
+            return Ok(());
+        }
+        let entry = format!("/{}/", relative.display());
+        let gitignore = self.dotfiles_dir.join(".gitignore");
+        let mut content = fs::read_to_string(&gitignore).unwrap_or_default();
+        if content.lines().any(|line| line.trim() == entry) {
+            return Ok(());
+        }
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&format!(
+            "\n# Backups of replaced files (machine-specific)\n{entry}\n"
+        ));
+        fs::write(&gitignore, content)
+            .with_context(|| format!("Failed to update {}", gitignore.display()))
+    }
+
+    /// Every backup, grouped by file and newest first. Backups in the
+    /// repository's old flat `backups` directory are included.
+    pub fn list(&self) -> Result<Vec<Backup>> {
+        let mut backups = Vec::new();
+        for (subdir, base) in [
+            (HOME_DIR, self.home_dir.clone()),
+            (ROOT_DIR, PathBuf::from("/")),
+        ] {
+            let dir = self.root.join(subdir);
+            let mut entries = walkdir::WalkDir::new(&dir).min_depth(1).into_iter();
+            while let Some(entry) = entries.next() {
+                let Ok(entry) = entry else { continue };
+                let name = entry.file_name().to_string_lossy();
+                let Some((file_name, created)) = parse_backup_name(&name) else {
+                    continue;
+                };
+                // A directory's backup is itself a directory; its contents aren't backups
+                if entry.file_type().is_dir() {
+                    entries.skip_current_dir();
+                }
+                let relative = entry.path().strip_prefix(&dir)?.with_file_name(&file_name);
+                let file = if subdir == HOME_DIR {
+                    relative.to_string_lossy().into_owned()
+                } else {
+                    format!("/{}", relative.display())
+                };
+                backups.push(Backup {
+                    name: name.into_owned(),
+                    path: entry.path().to_path_buf(),
+                    file,
+                    original: Some(base.join(relative)),
+                    created,
+                });
+            }
+        }
+
+        let legacy = self.dotfiles_dir.join(LEGACY_BACKUP_DIR);
+        if legacy.is_dir() {
+            for entry in fs::read_dir(&legacy)
+                .with_context(|| format!("Failed to read {}", legacy.display()))?
+            {
+                let path = entry?.path();
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                if let Some((file, created)) = parse_backup_name(&name) {
+                    backups.push(Backup {
+                        name: name.into_owned(),
+                        path,
+                        file,
+                        original: None,
+                        created,
+                    });
+                }
+            }
+        }
+
+        backups.sort_by(|a, b| {
+            a.file
+                .cmp(&b.file)
+                .then(b.created.cmp(&a.created))
+                .then(b.name.cmp(&a.name))
+        });
+        Ok(backups)
+    }
+
+    /// Backups of one tracked path (`.zshrc`, `~/.config/nvim/init.lua`),
+    /// newest first. Backups in the old flat directory match by file name.
+    pub fn backups_for(&self, file: &str) -> Result<Vec<Backup>> {
+        let original = crate::paths::destination(&self.home_dir, file);
+        let file_name = original
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(self
+            .list()?
+            .into_iter()
+            .filter(|b| match &b.original {
+                Some(path) => *path == original,
+                None => b.file == file_name,
+            })
+            .collect())
+    }
+}
+
+/// Put the backup at `backup` (a file or directory) back at `target`,
+/// creating its parent directories
+pub fn copy_back(backup: &Path, target: &Path) -> Result<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    if backup.is_dir() {
+        crate::utils::copy_dir_recursive(backup, target)
+    } else {
+        fs::copy(backup, target)
+            .with_context(|| format!("Failed to restore {}", target.display()))?;
+        Ok(())
+    }
+}
+
+/// Backups the retention policy removes. `backups` must be sorted as
+/// returned by [`BackupDir::list`]; `protected` backups are always kept.
+pub fn select_expired<'a>(
+    backups: &'a [Backup],
+    retention: &BackupRetention,
+    now: NaiveDateTime,
+    protected: &[PathBuf],
+) -> Vec<&'a Backup> {
+    let cutoff = retention
+        .max_age_days
+        .map(|days| now - Duration::days(i64::from(days)));
+    let mut expired = Vec::new();
+    let mut kept_for_file = 0;
+    for (i, backup) in backups.iter().enumerate() {
+        if i == 0 || backups[i - 1].file != backup.file {
+            kept_for_file = 0;
+        }
+        if protected.contains(&backup.path) {
+            continue;
+        }
+        let too_many = retention.max_count.is_some_and(|max| kept_for_file >= max);
+        let too_old = cutoff.is_some_and(|cutoff| backup.created < cutoff);
+        if too_many || too_old {
+            expired.push(backup);
+        } else {
+            kept_for_file += 1;
+        }
+    }
+    expired
+}
+
+/// Backups recorded by the most recent apply, which `ordinator rollback` needs
+fn rollback_backups(dotfiles_dir: &Path) -> Result<Vec<PathBuf>> {
+    Ok(ApplyJournal::latest(dotfiles_dir)?
+        .map(|journal| {
+            journal
+                .actions
+                .iter()
+                .filter_map(|action| action.backup().map(Path::to_path_buf))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Delete backups outside the retention policy, keeping any the latest apply
+/// needs for rollback. Returns the deleted (or, in dry-run mode, deletable) backups.
+pub fn prune(
+    backup_dir: &BackupDir,
+    retention: &BackupRetention,
+    dry_run: bool,
+) -> Result<Vec<Backup>> {
+    if retention.is_unlimited() {
+        return Ok(Vec::new());
+    }
+    let backups = backup_dir.list()?;
+    let protected = rollback_backups(&backup_dir.dotfiles_dir)?;
+    let now = chrono::Local::now().naive_local();
+    let expired: Vec<Backup> = select_expired(&backups, retention, now, &protected)
+        .into_iter()
+        .cloned()
+        .collect();
+    if !dry_run {
+        for backup in &expired {
+            let result = if backup.path.is_dir() {
+                fs::remove_dir_all(&backup.path)
+            } else {
+                fs::remove_file(&backup.path)
+            };
+            result.with_context(|| format!("Failed to delete {}", backup.path.display()))?;
+        }
+    }
+    Ok(expired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backup(file_name: &str, number: u32, created: &str) -> Backup {
+        let name = format!("{file_name}.backup.{number}.{created}");
+        let (_, created) = parse_backup_name(&name).unwrap();
+        Backup {
+            path: PathBuf::from("backups").join(&name),
+            file: file_name.to_string(),
+            original: None,
+            name,
+            created,
+        }
+    }
+
+    #[test]
+    fn test_parse_backup_name() {
+        let (file, created) = parse_backup_name(".zshrc.backup.2.20250102-030405").unwrap();
+        assert_eq!(file, ".zshrc");
+        assert_eq!(created.to_string(), "2025-01-02 03:04:05");
+        assert!(parse_backup_name("notes.txt").is_none());
+        assert!(parse_backup_name(".zshrc-20250102-030405").is_none());
+    }
+
+    #[test]
+    fn test_select_expired() {
+        let backups = vec![
+            backup(".vimrc", 1, "20250110-000000"),
+            backup(".zshrc", 1, "20250110-000000"),
+            backup(".zshrc", 1, "20250105-000000"),
+            backup(".zshrc", 1, "20241201-000000"),
+        ];
+        let now = parse_backup_name("x.backup.1.20250111-000000").unwrap().1;
+
+        let by_count = BackupRetention {
+            max_count: Some(1),
+            max_age_days: None,
+        };
+        let expired: Vec<&str> = select_expired(&backups, &by_count, now, &[])
+            .iter()
+            .map(|b| b.name.as_str())
+            .collect();
+        assert_eq!(
+            expired,
+            [
+                ".zshrc.backup.1.20250105-000000",
+                ".zshrc.backup.1.20241201-000000"
+            ]
+        );
+
+        let by_age = BackupRetention {
+            max_count: None,
+            max_age_days: Some(30),
+        };
+        let expired = select_expired(&backups, &by_age, now, &[]);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].name, ".zshrc.backup.1.20241201-000000");
+
+        // Protected backups are kept on top of max_count
+        let protected = vec![backups[3].path.clone()];
+        let expired = select_expired(&backups, &by_count, now, &protected);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].name, ".zshrc.backup.1.20250105-000000");
+    }
+
+    #[test]
+    fn test_backup_dir_mirrors_layout() {
+        let temp = tempfile::tempdir().unwrap();
+        let home = temp.path().join("home");
+        let repo = temp.path().join("repo");
+        fs::create_dir_all(home.join(".config/a")).unwrap();
+        fs::create_dir_all(home.join(".config/b")).unwrap();
+        fs::create_dir_all(repo.join(LEGACY_BACKUP_DIR)).unwrap();
+        fs::write(home.join(".config/a/init.lua"), "a").unwrap();
+        fs::write(home.join(".config/b/init.lua"), "b").unwrap();
+        fs::write(
+            repo.join("backups/init.lua.backup.1.20250101-120000"),
+            "old",
+        )
+        .unwrap();
+
+        let mut config = Config::default();
+        config.global.backup_dir = Some("local/backups".to_string());
+        let backup_dir = BackupDir::new(&config, &repo, &home).unwrap();
+        assert_eq!(backup_dir.root(), repo.join("local/backups"));
+        let a = home.join(".config/a/init.lua");
+        let first = backup_dir.backup(&a).unwrap();
+        let second = backup_dir.backup(&a).unwrap();
+        backup_dir.backup(&home.join(".config/b/init.lua")).unwrap();
+        assert!(first.starts_with(repo.join("local/backups/home/.config/a")));
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(&second).unwrap(), "a");
+
+        let backups = backup_dir.backups_for(".config/a/init.lua").unwrap();
+        assert_eq!(backups.len(), 3);
+        assert_eq!(backups[0].file, ".config/a/init.lua");
+        assert_eq!(backups[0].original.as_deref(), Some(a.as_path()));
+        assert_eq!(backups[2].original, None);
+        assert_eq!(backup_dir.list().unwrap().len(), 4);
+
+        // Inside the repository, the backup directory is gitignored once
+        let gitignore = fs::read_to_string(repo.join(".gitignore")).unwrap();
+        assert_eq!(gitignore.matches("/local/backups/").count(), 1);
+    }
+}
@@ -0,0 +1,141 @@
+//! Managed blocks: for a tracked file with `managed_block = true`, ordinator
+//! keeps its stored copy between two marker lines inside the destination
+//! instead of owning the whole file, so a shell rc file can keep lines that
+//! installers and other tools add to it.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// First line of a managed block
+pub const BEGIN: &str = "# >>> ordinator >>>";
+
+/// Last line of a managed block
+pub const END: &str = "# <<< ordinator <<<";
+
+/// Byte ranges of the block in `text`: from the start of its first marker
+/// line to the end of its last, and the contents between them
+fn find(text: &str) -> Option<(usize, usize, usize, usize)> {
+    let mut offset = 0;
+    let mut begin: Option<(usize, usize)> = None;
+    for line in text.split_inclusive('\n') {
+        let end = offset + line.len();
+        match begin {
+            None if line.trim_end() == BEGIN => begin = Some((offset, end)),
+            Some((start, inner_start)) if line.trim_end() == END => {
+                return Some((start, inner_start, offset, end));
+            }
+            _ => {}
+        }
+        offset = end;
+    }
+    None
+}
+
+/// The lines between the markers, or `None` when `text` has no block
+pub fn contents(text: &str) -> Option<&str> {
+    find(text).map(|(_, inner_start, inner_end, _)| &text[inner_start..inner_end])
+}
+
+/// `contents` as it's written into a block: ending with a newline unless empty
+pub fn normalize(contents: &str) -> String {
+    if contents.is_empty() || contents.ends_with('\n') {
+        contents.to_string()
+    } else {
+        format!("{contents}\n")
+    }
+}
+
+/// `text` with its block holding `contents`: the block is replaced where it
+/// is, or appended after a blank line when there's none yet
+pub fn upsert(text: &str, contents: &str) -> String {
+    let block = format!("{BEGIN}\n{}{END}\n", normalize(contents));
+    if let Some((start, _, _, end)) = find(text) {
+        return format!("{}{block}{}", &text[..start], &text[end..]);
+    }
+    let mut updated = text.to_string();
+    if !updated.is_empty() {
+        if !updated.ends_with('\n') {
+            updated.push('\n');
+        }
+        updated.push('\n');
+    }
+    updated.push_str(&block);
+    updated
+}
+
+/// `text` without its block, and the blank line [`upsert`] put before it;
+/// `None` when there's no block
+pub fn remove(text: &str) -> Option<String> {
+    let (start, _, _, end) = find(text)?;
+    let before = &text[..start];
+    let after = &text[end..];
+    let before = if after.is_empty() {
+        before.strip_suffix("\n\n").map_or(before, |trimmed| {
+            // Keep the newline ending the last line before the blank one
+            &before[..trimmed.len() + 1]
+        })
+    } else {
+        before
+    };
+    Some(format!("{before}{after}"))
+}
+
+/// Contents of the block in the file at `path`; `None` when the file or its
+/// block doesn't exist
+pub fn read(path: &Path) -> Result<Option<String>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {} for its managed block", path.display()))?;
+    Ok(contents(&text).map(String::from))
+}
+
+/// Hash of block contents, as compared with the file's stored copy
+pub fn hash(contents: &[u8]) -> String {
+    crate::drift::hash_bytes(normalize(&String::from_utf8_lossy(contents)).as_bytes())
+}
+
+/// [`hash`] of the block in the file at `path`; empty, matching no stored
+/// copy, when it has no block
+pub fn live_hash(path: &Path) -> Result<String> {
+    Ok(read(path)?.map_or_else(String::new, |contents| hash(contents.as_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upsert_and_remove() {
+        let rc = "export PATH=/opt/bin:$PATH\n";
+        let added = upsert(rc, "alias ll='ls -l'");
+        assert_eq!(
+            added,
+            "export PATH=/opt/bin:$PATH\n\n# >>> ordinator >>>\nalias ll='ls -l'\n# <<< ordinator <<<\n"
+        );
+        assert_eq!(contents(&added), Some("alias ll='ls -l'\n"));
+
+        // Updating in place leaves the lines around the block alone, and is idempotent
+        let around = format!("{added}eval \"$(rbenv init -)\"\n");
+        let updated = upsert(&around, "alias ll='ls -la'\n");
+        assert_eq!(
+            updated,
+            "export PATH=/opt/bin:$PATH\n\n# >>> ordinator >>>\nalias ll='ls -la'\n# <<< ordinator <<<\neval \"$(rbenv init -)\"\n"
+        );
+        assert_eq!(upsert(&updated, "alias ll='ls -la'\n"), updated);
+
+        assert_eq!(remove(&added).as_deref(), Some(rc));
+        assert_eq!(
+            remove(&updated).as_deref(),
+            Some("export PATH=/opt/bin:$PATH\n\neval \"$(rbenv init -)\"\n")
+        );
+        assert_eq!(remove(rc), None);
+        assert_eq!(upsert("", "x"), format!("{BEGIN}\nx\n{END}\n"));
+        assert_eq!(remove(&upsert("", "x")).as_deref(), Some(""));
+
+        // An unterminated block isn't a block
+        assert_eq!(contents("# >>> ordinator >>>\nx\n"), None);
+        assert_eq!(hash(b"x"), hash(b"x\n"));
+    }
+}
@@ -1,8 +1,15 @@
 use crate::config::Config;
+use crate::error::OrdinatorError;
 use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tracing::info;
 
+/// How long `ordinator bootstrap run` lets a script run when no timeout is configured
+pub const DEFAULT_TIMEOUT_SECS: u64 = 1800;
+
 /// Safety level for bootstrap scripts
 #[derive(Debug, Clone, PartialEq)]
 pub enum SafetyLevel {
@@ -12,6 +19,27 @@ pub enum SafetyLevel {
     Blocked,
 }
 
+/// How a script started by `BootstrapManager::run_logged` ended
+#[derive(Debug)]
+pub struct RunOutcome {
+    /// Exit code, or `None` if the script was killed
+    pub exit_code: Option<i32>,
+
+    /// The script ran past its timeout and was stopped
+    pub timed_out: bool,
+
+    pub duration: Duration,
+
+    /// Timestamped output and exit status of this run
+    pub log_path: PathBuf,
+}
+
+impl RunOutcome {
+    pub fn success(&self) -> bool {
+        !self.timed_out && self.exit_code == Some(0)
+    }
+}
+
 /// Bootstrap manager for running setup scripts and commands
 #[allow(dead_code)]
 pub struct BootstrapManager {
@@ -193,6 +221,7 @@ fi
 # npm install -g typescript
 # git config --global user.name "Your Name"
 # git config --global user.email "your.email@example.com"
+# ordinator ssh keygen --profile {profile} --if-missing
 
 # --- End User Customization Section ---
 
@@ -264,6 +293,102 @@ echo "========================================"
         Ok(())
     }
 
+    /// Run a bootstrap script for `profile`, streaming its output with
+    /// timestamps and copying it to a log in `log_dir`. Blocked scripts are
+    /// refused; a script still running after `timeout` is stopped.
+    pub fn run_logged(
+        &self,
+        script_path: &Path,
+        profile: &str,
+        timeout: Duration,
+        log_dir: &Path,
+    ) -> Result<RunOutcome> {
+        use std::os::unix::process::CommandExt;
+        use std::process::{Command, Stdio};
+
+        if !script_path.is_file() {
+            return Err(anyhow::anyhow!(
+                "Bootstrap script does not exist: {}",
+                script_path.display()
+            ));
+        }
+        if self.get_script_safety_level(script_path) == SafetyLevel::Blocked {
+            return Err(anyhow::anyhow!(
+                "Bootstrap script {} was not run: it contains blocked commands (e.g. rm -rf /)",
+                script_path.display()
+            ));
+        }
+
+        std::fs::create_dir_all(log_dir)?;
+        let started = chrono::Local::now();
+        let log_path = log_dir.join(format!("{profile}-{}.log", started.format("%Y%m%d-%H%M%S")));
+        let mut log = std::fs::File::create(&log_path)
+            .with_context(|| format!("Failed to create {}", log_path.display()))?;
+        writeln!(
+            log,
+            "# {} (profile '{profile}') started {}",
+            script_path.display(),
+            started.format("%Y-%m-%d %H:%M:%S")
+        )?;
+        let log = Arc::new(Mutex::new(log));
+
+        info!("Running bootstrap script: {}", script_path.display());
+        // Its own process group, so a timeout stops everything the script started
+        let mut child = Command::new("bash")
+            .arg(script_path)
+            .current_dir(script_path.parent().unwrap_or_else(|| Path::new(".")))
+            .env("ORDINATOR_PROFILE", profile)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .process_group(0)
+            .spawn()
+            .with_context(|| {
+                format!(
+                    "Failed to execute bootstrap script: {}",
+                    script_path.display()
+                )
+            })?;
+        let readers = [
+            stream_lines(child.stdout.take(), false, Arc::clone(&log)),
+            stream_lines(child.stderr.take(), true, Arc::clone(&log)),
+        ];
+
+        let start = Instant::now();
+        let mut timed_out = false;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if start.elapsed() >= timeout {
+                timed_out = true;
+                let _ = Command::new("kill")
+                    .args(["-KILL", "--", &format!("-{}", child.id())])
+                    .status();
+                let _ = child.kill();
+                break child.wait()?;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        };
+        for reader in readers {
+            let _ = reader.join();
+        }
+        let duration = start.elapsed();
+
+        let mut log = log.lock().unwrap_or_else(|e| e.into_inner());
+        if timed_out {
+            writeln!(log, "# timed out after {}s", timeout.as_secs())?;
+        } else {
+            writeln!(log, "# exit status: {status}")?;
+        }
+        writeln!(log, "# took {}s", duration.as_secs())?;
+        Ok(RunOutcome {
+            exit_code: status.code(),
+            timed_out,
+            duration,
+            log_path,
+        })
+    }
+
     /// Install Homebrew packages
     pub fn install_homebrew_packages(&self, packages: &[String]) -> Result<()> {
         info!("Installing Homebrew packages: {:?}", packages);
@@ -289,16 +414,235 @@ echo "========================================"
         Ok(())
     }
 
-    /// Generate system script for manual execution
+    /// Generate a standalone system script for manual execution on machines without ordinator
     pub fn generate_system_script(
         &self,
-        _commands: &[String],
+        profile: &str,
+        config: &Config,
+        repo_url: Option<&str>,
         output_path: &std::path::Path,
     ) -> Result<()> {
         info!("Generating system script: {:?}", output_path);
-        // TODO: Implement system script generation
+        if self.dry_run {
+            info!("[DRY RUN] Would generate system script: {:?}", output_path);
+            return Ok(());
+        }
+
+        let content = self.create_system_script_content(profile, config, repo_url)?;
+        if let Some(parent) = output_path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent).with_context(|| {
+                    format!("Failed to create output directory: {}", parent.display())
+                })?;
+            }
+        }
+        std::fs::write(output_path, content)
+            .with_context(|| format!("Failed to write system script: {}", output_path.display()))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(output_path, std::fs::Permissions::from_mode(0o755))
+                .with_context(|| {
+                    format!(
+                        "Failed to make script executable: {}",
+                        output_path.display()
+                    )
+                })?;
+        }
+
         Ok(())
     }
+
+    /// Build the system script: symlinks, Homebrew packages, and the bootstrap script
+    fn create_system_script_content(
+        &self,
+        profile: &str,
+        config: &Config,
+        repo_url: Option<&str>,
+    ) -> Result<String> {
+        let profile_config = config
+            .get_profile(profile)
+            .ok_or_else(|| OrdinatorError::ProfileNotFound(profile.to_string()))?;
+
+        let mut script = format!(
+            r#"#!/usr/bin/env bash
+# Ordinator System Script for the '{profile}' profile
+# Generated by ordinator. This script does not require ordinator to be installed.
+# It links tracked dotfiles, installs Homebrew packages, and runs the bootstrap script.
+# Encrypted secrets are not decrypted by this script; run 'ordinator apply' for those.
+
+set -euo pipefail
+
+DOTFILES_DIR="${{DOTFILES_DIR:-$HOME/.dotfiles}}"
+REPO_URL={repo_url}
+export ORDINATOR_HOME="${{ORDINATOR_HOME:-$DOTFILES_DIR}}"
+
+if [ ! -d "$DOTFILES_DIR" ]; then
+  if [ -z "$REPO_URL" ]; then
+    echo "Dotfiles directory not found: $DOTFILES_DIR" >&2
+    echo "Set DOTFILES_DIR or clone your dotfiles repository first." >&2
+    exit 1
+  fi
+  echo "Cloning $REPO_URL into $DOTFILES_DIR"
+  git clone "$REPO_URL" "$DOTFILES_DIR"
+fi
+
+link_file() {{
+  local source="$DOTFILES_DIR/$1"
+  local dest="$2"
+  if [ ! -e "$source" ]; then
+    echo "Skipping missing source file: $source" >&2
+    return 0
+  fi
+  mkdir -p "$(dirname "$dest")"
+  if [ -e "$dest" ] && [ ! -L "$dest" ]; then
+    local backup="$dest.backup.$(date +%Y%m%d-%H%M%S)"
+    mv "$dest" "$backup"
+    echo "Backed up $dest to $backup"
+  fi
+  ln -sfn "$source" "$dest"
+  echo "Linked $dest -> $source"
+}}
+"#,
+            repo_url = shell_quote(repo_url.unwrap_or(""))
+        );
+
+        script.push_str("\necho \"==> Linking dotfiles\"\n");
+        if profile_config.files.is_empty() {
+            script.push_str("echo \"No tracked files for this profile\"\n");
+        }
+        for file in &profile_config.files {
+            if crate::template::is_template(file) {
+                // Rendering needs the profile's variables, which only ordinator has
+                script.push_str(&format!(
+                    "echo {}\n",
+                    shell_quote(&format!(
+                        "Skipping template {file}; run 'ordinator apply' to render it"
+                    ))
+                ));
+                continue;
+            }
+            if profile_config
+                .file_options
+                .get(file)
+                .is_some_and(|options| options.encrypt)
+            {
+                script.push_str(&format!(
+                    "echo {}\n",
+                    shell_quote(&format!(
+                        "Skipping encrypted file {file}; run 'ordinator apply' to decrypt it"
+                    ))
+                ));
+                continue;
+            }
+            let source = profile_config
+                .file_mappings
+                .iter()
+                .find(|(_, original)| *original == file)
+                .map(|(hash_filename, _)| format!("files/{profile}/{hash_filename}"))
+                .unwrap_or_else(|| format!("files/{profile}/{file}"));
+            let dest = if let Some(rest) = file.strip_prefix("~/") {
+                format!("\"$HOME\"/{}", shell_quote(rest))
+            } else if file.starts_with('/') {
+                shell_quote(file)
+            } else {
+                format!("\"$HOME\"/{}", shell_quote(file))
+            };
+            script.push_str(&format!("link_file {} {dest}\n", shell_quote(&source)));
+        }
+
+        if !profile_config.homebrew_formulas.is_empty() || !profile_config.homebrew_casks.is_empty()
+        {
+            script.push_str(
+                r#"
+echo "==> Installing Homebrew packages"
+if ! command -v brew >/dev/null 2>&1; then
+  echo "Homebrew not found. Installing Homebrew..."
+  /bin/bash -c "$(curl -fsSL https://raw.githubusercontent.com/Homebrew/install/HEAD/install.sh)"
+  if [ -x /opt/homebrew/bin/brew ]; then
+    eval "$(/opt/homebrew/bin/brew shellenv)"
+  elif [ -x /usr/local/bin/brew ]; then
+    eval "$(/usr/local/bin/brew shellenv)"
+  fi
+fi
+"#,
+            );
+            if !profile_config.homebrew_formulas.is_empty() {
+                let formulas: Vec<String> = profile_config
+                    .homebrew_formulas
+                    .iter()
+                    .map(|f| shell_quote(f))
+                    .collect();
+                script.push_str(&format!("brew install {}\n", formulas.join(" ")));
+            }
+            if !profile_config.homebrew_casks.is_empty() {
+                let casks: Vec<String> = profile_config
+                    .homebrew_casks
+                    .iter()
+                    .map(|c| shell_quote(c))
+                    .collect();
+                script.push_str(&format!("brew install --cask {}\n", casks.join(" ")));
+            }
+        }
+
+        if let Some(bootstrap_script) = &profile_config.bootstrap_script {
+            let script_path = if bootstrap_script.starts_with('/') {
+                shell_quote(bootstrap_script)
+            } else {
+                format!("\"$DOTFILES_DIR\"/{}", shell_quote(bootstrap_script))
+            };
+            script.push_str(&format!(
+                r#"
+echo "==> Running bootstrap script"
+BOOTSTRAP_SCRIPT={script_path}
+if [ -f "$BOOTSTRAP_SCRIPT" ]; then
+  bash "$BOOTSTRAP_SCRIPT"
+else
+  echo "Bootstrap script not found: $BOOTSTRAP_SCRIPT" >&2
+fi
+"#
+            ));
+        }
+
+        script.push_str(&format!(
+            "\necho \"==> Ordinator system script complete ({profile} profile)\"\n"
+        ));
+        Ok(script)
+    }
+}
+
+/// Quote a string for safe use as a single shell word
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Copy each line from a child's output to our stdout (or stderr) and the
+/// log, prefixed with the time it arrived
+fn stream_lines<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    is_stderr: bool,
+    log: Arc<Mutex<std::fs::File>>,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let Some(pipe) = pipe else {
+            return;
+        };
+        for line in BufReader::new(pipe).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            let line = format!("[{}] {line}", chrono::Local::now().format("%H:%M:%S"));
+            if is_stderr {
+                eprintln!("{line}");
+            } else {
+                println!("{line}");
+            }
+            if let Ok(mut log) = log.lock() {
+                let _ = writeln!(log, "{line}");
+            }
+        }
+    })
 }
 
 #[cfg(test)]
@@ -521,6 +865,39 @@ exit 1
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_run_logged_records_exit_status_and_timeout() {
+        let temp_dir = tempdir().unwrap();
+        let manager = BootstrapManager::new(false);
+        let log_dir = temp_dir.path().join("logs");
+
+        let script_path = temp_dir.path().join("fails.sh");
+        fs::write(&script_path, "echo \"step one\"\nexit 3\n").unwrap();
+        let outcome = manager
+            .run_logged(&script_path, "work", Duration::from_secs(30), &log_dir)
+            .unwrap();
+        assert!(!outcome.success());
+        assert_eq!(outcome.exit_code, Some(3));
+        let log = fs::read_to_string(&outcome.log_path).unwrap();
+        assert!(log.contains("] step one"));
+        assert!(log.contains("# exit status:"));
+
+        let script_path = temp_dir.path().join("hangs.sh");
+        fs::write(&script_path, "sleep 30\n").unwrap();
+        let outcome = manager
+            .run_logged(&script_path, "work", Duration::from_millis(200), &log_dir)
+            .unwrap();
+        assert!(outcome.timed_out);
+        assert!(outcome.duration < Duration::from_secs(10));
+
+        let script_path = temp_dir.path().join("blocked.sh");
+        fs::write(&script_path, "rm -rf /\n").unwrap();
+        let error = manager
+            .run_logged(&script_path, "work", Duration::from_secs(30), &log_dir)
+            .unwrap_err();
+        assert!(error.to_string().contains("blocked commands"));
+    }
+
     #[test]
     fn test_create_default_script_content() {
         let manager = BootstrapManager::new(false);
@@ -547,10 +924,10 @@ exit 1
         let extensions = vec!["ms-vscode.vscode-rust".to_string()];
         assert!(manager.install_vscode_extensions(&extensions).is_ok());
 
-        let commands = vec!["defaults write test".to_string()];
+        let config = Config::create_default();
         let output_path = temp_dir.path().join("test.sh");
         assert!(manager
-            .generate_system_script(&commands, &output_path)
+            .generate_system_script("default", &config, None, &output_path)
             .is_ok());
     }
 
@@ -771,4 +1148,77 @@ rm -rf /tmp/test
             fs::set_permissions(temp_dir.path(), perms).unwrap();
         }
     }
+
+    #[test]
+    fn test_generate_system_script() {
+        let temp_dir = tempdir().unwrap();
+        let manager = BootstrapManager::new(false);
+        let mut config = Config::create_default();
+        {
+            let profile = config.profiles.get_mut("work").unwrap();
+            profile.files.push(".zshrc".to_string());
+            profile
+                .file_mappings
+                .insert("abc123_.zshrc".to_string(), ".zshrc".to_string());
+            profile.homebrew_formulas = vec!["git".to_string(), "ripgrep".to_string()];
+            profile.homebrew_casks = vec!["iterm2".to_string()];
+            profile.bootstrap_script = Some("scripts/work/bootstrap.sh".to_string());
+        }
+
+        let output_path = temp_dir.path().join("ordinator-system.sh");
+        manager
+            .generate_system_script(
+                "work",
+                &config,
+                Some("https://github.com/user/dotfiles.git"),
+                &output_path,
+            )
+            .unwrap();
+
+        let content = fs::read_to_string(&output_path).unwrap();
+        assert!(content.starts_with("#!/usr/bin/env bash"));
+        assert!(content.contains("REPO_URL='https://github.com/user/dotfiles.git'"));
+        assert!(content.contains("link_file 'files/work/abc123_.zshrc' \"$HOME\"/'.zshrc'"));
+        assert!(content.contains("brew install 'git' 'ripgrep'"));
+        assert!(content.contains("brew install --cask 'iterm2'"));
+        assert!(content.contains("BOOTSTRAP_SCRIPT=\"$DOTFILES_DIR\"/'scripts/work/bootstrap.sh'"));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert!(fs::metadata(&output_path).unwrap().permissions().mode() & 0o111 != 0);
+        }
+    }
+
+    #[test]
+    fn test_generate_system_script_invalid_profile() {
+        let temp_dir = tempdir().unwrap();
+        let manager = BootstrapManager::new(false);
+        let config = Config::create_default();
+        let output_path = temp_dir.path().join("ordinator-system.sh");
+
+        let result = manager.generate_system_script("nonexistent", &config, None, &output_path);
+        assert!(result.is_err());
+        assert!(!output_path.exists());
+    }
+
+    #[test]
+    fn test_generate_system_script_dry_run() {
+        let temp_dir = tempdir().unwrap();
+        let manager = BootstrapManager::new(true);
+        let config = Config::create_default();
+        let output_path = temp_dir.path().join("ordinator-system.sh");
+
+        manager
+            .generate_system_script("default", &config, None, &output_path)
+            .unwrap();
+        assert!(!output_path.exists());
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("simple"), "'simple'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote("$HOME"), "'$HOME'");
+    }
 }
@@ -1,16 +1,441 @@
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use std::process::Command;
 use tracing::info;
 
 use crate::config::Config;
+use crate::environment::{self, Effect};
+use crate::error::OrdinatorError;
+
+/// Homebrew settings, under `[brew]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BrewConfig {
+    /// Whether `status` and `apply` mention declared packages that are outdated
+    #[serde(default)]
+    pub warn_outdated: bool,
+}
+
+impl BrewConfig {
+    pub fn is_empty(&self) -> bool {
+        !self.warn_outdated
+    }
+}
+
+/// A Mac App Store app installed with `mas`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MasApp {
+    /// App name as shown in the App Store
+    pub name: String,
+
+    /// App Store identifier
+    pub id: u64,
+}
+
+/// Packages described by a Brewfile (`brew bundle` format)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Brewfile {
+    /// Third-party taps (`tap "..."`)
+    pub taps: Vec<String>,
+
+    /// Formulas (`brew "..."`)
+    pub formulas: Vec<String>,
+
+    /// Casks (`cask "..."`)
+    pub casks: Vec<String>,
+
+    /// Mac App Store apps (`mas "...", id: ...`)
+    pub mas_apps: Vec<MasApp>,
+
+    /// Entries ordinator does not manage (e.g. `vscode`, `whalebrew`), kept for reporting
+    pub skipped: Vec<String>,
+}
+
+impl Brewfile {
+    /// Parse Brewfile contents. Options after the package name (e.g.
+    /// `restart_service: true`, `args: [...]`) are ignored.
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut brewfile = Self::default();
+        for (index, raw_line) in content.lines().enumerate() {
+            let line = raw_line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (kind, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let name = first_quoted(rest).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Brewfile line {}: expected a quoted name: {}",
+                    index + 1,
+                    line
+                )
+            })?;
+            match kind {
+                "tap" => push_unique(&mut brewfile.taps, name),
+                "brew" => push_unique(&mut brewfile.formulas, name),
+                "cask" => push_unique(&mut brewfile.casks, name),
+                "mas" => {
+                    let id = rest
+                        .split_once("id:")
+                        .and_then(|(_, id)| id.trim().split(|c: char| !c.is_ascii_digit()).next())
+                        .and_then(|id| id.parse::<u64>().ok())
+                        .ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "Brewfile line {}: mas entry needs a numeric id: {}",
+                                index + 1,
+                                line
+                            )
+                        })?;
+                    if !brewfile.mas_apps.iter().any(|app| app.id == id) {
+                        brewfile.mas_apps.push(MasApp { name, id });
+                    }
+                }
+                _ => brewfile.skipped.push(line.to_string()),
+            }
+        }
+        Ok(brewfile)
+    }
+
+    /// Render in Brewfile format, grouped as `brew bundle dump` does
+    pub fn to_brewfile_string(&self) -> String {
+        let mut out = String::new();
+        for tap in &self.taps {
+            out.push_str(&format!("tap \"{tap}\"\n"));
+        }
+        for formula in &self.formulas {
+            out.push_str(&format!("brew \"{formula}\"\n"));
+        }
+        for cask in &self.casks {
+            out.push_str(&format!("cask \"{cask}\"\n"));
+        }
+        for app in &self.mas_apps {
+            out.push_str(&format!("mas \"{}\", id: {}\n", app.name, app.id));
+        }
+        out
+    }
+}
+
+/// Return the first double-quoted string in `text`
+fn first_quoted(text: &str) -> Option<String> {
+    let start = text.find('"')? + 1;
+    let len = text[start..].find('"')?;
+    Some(text[start..start + len].to_string())
+}
+
+fn push_unique(list: &mut Vec<String>, value: String) {
+    if !list.contains(&value) {
+        list.push(value);
+    }
+}
+
+/// Parse `mas list` output lines such as `497799835  Xcode  (15.0)`
+fn parse_mas_list(output: &str) -> Vec<MasApp> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (id, rest) = line.trim().split_once(char::is_whitespace)?;
+            let id = id.parse::<u64>().ok()?;
+            let name = match rest.rfind('(') {
+                Some(paren) => &rest[..paren],
+                None => rest,
+            };
+            Some(MasApp {
+                name: name.trim().to_string(),
+                id,
+            })
+        })
+        .collect()
+}
+
+/// A declared formula or cask with a newer version available
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutdatedPackage {
+    pub name: String,
+    pub installed: String,
+    pub latest: String,
+    pub cask: bool,
+}
+
+/// How the installed formulas and casks compare with a profile
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageDiff {
+    pub missing_formulas: Vec<String>,
+    pub missing_casks: Vec<String>,
+
+    /// Installed on purpose (`brew leaves`) but not in the profile
+    pub extra_formulas: Vec<String>,
+    pub extra_casks: Vec<String>,
+
+    pub outdated: Vec<OutdatedPackage>,
+}
+
+impl PackageDiff {
+    pub fn is_empty(&self) -> bool {
+        self.missing_formulas.is_empty()
+            && self.missing_casks.is_empty()
+            && self.extra_formulas.is_empty()
+            && self.extra_casks.is_empty()
+            && self.outdated.is_empty()
+    }
+}
+
+/// Name without its tap, so `homebrew/core/git` and `git` compare equal
+fn short_name(name: &str) -> &str {
+    name.rsplit('/').next().unwrap_or(name)
+}
+
+/// Compare declared and installed packages. Only declared packages are
+/// reported as outdated.
+fn compare_packages(
+    declared: (&[String], &[String]),
+    installed: (&[String], &[String]),
+    outdated: Vec<OutdatedPackage>,
+) -> PackageDiff {
+    let (declared_formulas, declared_casks) = declared;
+    let (installed_formulas, installed_casks) = installed;
+    let missing = |declared: &[String], installed: &[String]| -> Vec<String> {
+        declared
+            .iter()
+            .filter(|name| !installed.contains(name))
+            .cloned()
+            .collect()
+    };
+    PackageDiff {
+        missing_formulas: missing(declared_formulas, installed_formulas),
+        missing_casks: missing(declared_casks, installed_casks),
+        extra_formulas: missing(installed_formulas, declared_formulas),
+        extra_casks: missing(installed_casks, declared_casks),
+        outdated: declared_outdated(declared, outdated),
+    }
+}
+
+/// The outdated packages among the declared formulas and casks
+fn declared_outdated(
+    declared: (&[String], &[String]),
+    outdated: Vec<OutdatedPackage>,
+) -> Vec<OutdatedPackage> {
+    let (declared_formulas, declared_casks) = declared;
+    outdated
+        .into_iter()
+        .filter(|package| {
+            let declared = if package.cask {
+                declared_casks
+            } else {
+                declared_formulas
+            };
+            declared
+                .iter()
+                .any(|name| short_name(name) == short_name(&package.name))
+        })
+        .collect()
+}
+
+/// The notice `status` and `apply` give when packages `profile` declares
+/// are outdated
+pub fn outdated_notice(profile: &str, outdated: &[OutdatedPackage]) -> Option<String> {
+    if outdated.is_empty() {
+        return None;
+    }
+    let names: Vec<&str> = outdated.iter().map(|p| p.name.as_str()).collect();
+    Some(format!(
+        "{} Homebrew package(s) declared in profile '{profile}' are outdated ({}). Run: ordinator brew outdated --profile {profile}",
+        outdated.len(),
+        names.join(", ")
+    ))
+}
+
+/// [`outdated_notice`] for `profile`, when `[brew] warn_outdated` is on and
+/// Homebrew is installed
+pub fn outdated_warning(config: &Config, profile: &str) -> Result<Option<String>> {
+    if !config.brew.warn_outdated || !BrewManager::check_homebrew_installed() {
+        return Ok(None);
+    }
+    let resolved = config.resolve_profile(profile)?;
+    let outdated = BrewManager::new(false).outdated_resolved(&resolved)?;
+    Ok(outdated_notice(profile, &outdated))
+}
+
+/// Parse `brew outdated --json=v2`
+fn parse_outdated(json: &str) -> Result<Vec<OutdatedPackage>> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).context("Failed to parse 'brew outdated' output")?;
+    // Casks report installed_versions as a string in some Homebrew versions
+    let version = |value: &serde_json::Value| match value {
+        serde_json::Value::Array(versions) => versions
+            .last()
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        other => other.as_str().unwrap_or_default().to_string(),
+    };
+    let mut outdated = Vec::new();
+    for (key, cask) in [("formulae", false), ("casks", true)] {
+        let Some(entries) = value.get(key).and_then(|v| v.as_array()) else {
+            continue;
+        };
+        for entry in entries {
+            let Some(name) = entry.get("name").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            outdated.push(OutdatedPackage {
+                name: name.to_string(),
+                installed: entry
+                    .get("installed_versions")
+                    .map(version)
+                    .unwrap_or_default(),
+                latest: entry
+                    .get("current_version")
+                    .map(version)
+                    .unwrap_or_default(),
+                cask,
+            });
+        }
+    }
+    Ok(outdated)
+}
+
+/// Packages `ordinator apply` would install
+#[derive(Debug, Default)]
+pub struct MissingPackages {
+    pub taps: Vec<String>,
+    pub formulas: Vec<String>,
+    pub casks: Vec<String>,
+    pub mas_apps: Vec<MasApp>,
+}
+
+/// What an installed package is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageKind {
+    Tap,
+    Formula,
+    Cask,
+    MasApp,
+}
+
+impl PackageKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PackageKind::Tap => "tap",
+            PackageKind::Formula => "formula",
+            PackageKind::Cask => "cask",
+            PackageKind::MasApp => "App Store app",
+        }
+    }
+}
+
+/// How installing one package went
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageResult {
+    pub kind: PackageKind,
+    pub name: String,
+
+    /// Last line of brew's error output, when it failed
+    pub error: Option<String>,
+}
+
+/// Every package an install tried, in order. Empty when nothing was missing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstallReport {
+    pub results: Vec<PackageResult>,
+}
+
+impl InstallReport {
+    fn push(&mut self, kind: PackageKind, name: &str, result: Result<()>) {
+        self.results.push(PackageResult {
+            kind,
+            name: name.to_string(),
+            error: result.err().map(|e| e.to_string()),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    pub fn failures(&self) -> usize {
+        self.results.iter().filter(|r| r.error.is_some()).count()
+    }
+
+    /// One row per package: name, kind, and result
+    pub fn table(&self) -> String {
+        let width = self
+            .results
+            .iter()
+            .map(|r| r.name.len())
+            .max()
+            .unwrap_or(0)
+            .max("Package".len());
+        let mut table = format!("{:<width$}  {:<13}  Result", "Package", "Kind");
+        for result in &self.results {
+            let outcome = match &result.error {
+                None => "installed".to_string(),
+                Some(error) => format!("failed: {error}"),
+            };
+            table.push_str(&format!(
+                "\n{:<width$}  {:<13}  {outcome}",
+                result.name,
+                result.kind.label()
+            ));
+        }
+        table
+    }
+}
+
+/// Run a `brew` (or `mas`) command, turning a failure into its last line of stderr
+fn run_brew(cmd: &mut Command) -> Result<()> {
+    let program = cmd.get_program().to_string_lossy().into_owned();
+    let output = environment::output(cmd, Effect::Changes)
+        .with_context(|| format!("Failed to run {program}"))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let reason = stderr
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .map(|line| line.trim().trim_start_matches("Error: ").to_string())
+        .unwrap_or_else(|| format!("exited with {}", output.status));
+    Err(anyhow::anyhow!(reason))
+}
+
+/// Most `brew fetch` commands run at once
+const FETCH_JOBS: usize = 4;
+
+/// Download formulas and casks ahead of installing them, several at once.
+/// Failures are left for the install to report.
+fn prefetch(packages: &[(PackageKind, &str)]) {
+    if packages.len() < 2 {
+        return;
+    }
+    for chunk in packages.chunks(FETCH_JOBS) {
+        std::thread::scope(|scope| {
+            for (kind, name) in chunk {
+                scope.spawn(move || {
+                    let mut cmd = Command::new("brew");
+                    cmd.arg("fetch");
+                    match kind {
+                        PackageKind::Cask => cmd.arg("--cask"),
+                        _ => cmd.arg("--deps"),
+                    };
+                    let _ = environment::output(cmd.arg(name), Effect::Changes);
+                });
+            }
+        });
+    }
+}
 
 pub struct BrewManager {
     dry_run: bool,
 }
 
 impl BrewManager {
+    /// Under `--simulate` the install and uninstall commands are built and
+    /// shown by the environment rather than skipped
     pub fn new(dry_run: bool) -> Self {
-        Self { dry_run }
+        Self {
+            dry_run: dry_run && !environment::current().simulated(),
+        }
     }
 
     /// Export current Homebrew packages to config
@@ -18,10 +443,14 @@ impl BrewManager {
         info!("Exporting Homebrew packages for profile: {}", profile);
 
         let (formulas, casks) = self.get_current_packages().await?;
+        let taps = self.get_current_taps()?;
+        let mas_apps = self.get_current_mas_apps()?;
 
         if let Some(profile_config) = config.get_profile_mut(profile) {
             profile_config.homebrew_formulas = formulas;
             profile_config.homebrew_casks = casks;
+            profile_config.homebrew_taps = taps;
+            profile_config.mas_apps = mas_apps;
             info!(
                 "Exported {} formulas and {} casks to profile '{}'",
                 profile_config.homebrew_formulas.len(),
@@ -29,19 +458,98 @@ impl BrewManager {
                 profile
             );
         } else {
-            return Err(anyhow::anyhow!("Profile '{}' not found", profile));
+            return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
         }
 
         Ok(())
     }
 
+    /// Capture current Homebrew packages, taps, and App Store apps as a Brewfile
+    pub async fn export_brewfile(&self) -> Result<Brewfile> {
+        info!("Capturing current Homebrew packages as a Brewfile");
+        let (formulas, casks) = self.get_current_packages().await?;
+        Ok(Brewfile {
+            taps: self.get_current_taps()?,
+            formulas,
+            casks,
+            mas_apps: self.get_current_mas_apps()?,
+            skipped: Vec::new(),
+        })
+    }
+
+    /// Merge (or with `replace`, overwrite) a profile's packages with a Brewfile's
+    pub fn import_brewfile(
+        &self,
+        profile: &str,
+        brewfile: &Brewfile,
+        replace: bool,
+        config: &mut Config,
+    ) -> Result<()> {
+        info!("Importing Brewfile into profile: {}", profile);
+        let profile_config = config
+            .get_profile_mut(profile)
+            .ok_or_else(|| OrdinatorError::ProfileNotFound(profile.to_string()))?;
+
+        if replace {
+            profile_config.homebrew_taps.clear();
+            profile_config.homebrew_formulas.clear();
+            profile_config.homebrew_casks.clear();
+            profile_config.mas_apps.clear();
+        }
+        for tap in &brewfile.taps {
+            push_unique(&mut profile_config.homebrew_taps, tap.clone());
+        }
+        for formula in &brewfile.formulas {
+            push_unique(&mut profile_config.homebrew_formulas, formula.clone());
+        }
+        for cask in &brewfile.casks {
+            push_unique(&mut profile_config.homebrew_casks, cask.clone());
+        }
+        for app in &brewfile.mas_apps {
+            if !profile_config.mas_apps.iter().any(|a| a.id == app.id) {
+                profile_config.mas_apps.push(app.clone());
+            }
+        }
+        Ok(())
+    }
+
     /// Install Homebrew packages from config
-    pub async fn install_packages(&self, profile: &str, config: &Config) -> anyhow::Result<()> {
-        use anyhow::Context;
+    pub async fn install_packages(&self, profile: &str, config: &Config) -> Result<InstallReport> {
+        // Install the merged package set, including packages inherited via `extends`
+        let resolved = config.resolve_profile(profile)?;
+        self.install_resolved(profile, &resolved).await
+    }
+
+    /// Install the packages of an already resolved profile, or of several
+    /// profiles resolved together; `profile` names them in messages. Each
+    /// package is installed on its own, so one failure doesn't stop the rest;
+    /// the report says how each went.
+    pub async fn install_resolved(
+        &self,
+        profile: &str,
+        resolved: &crate::config::ResolvedProfile,
+    ) -> Result<InstallReport> {
         tracing::info!("Installing Homebrew packages for profile: {}", profile);
-        let profile_config = config
-            .get_profile(profile)
-            .ok_or_else(|| anyhow::anyhow!("Profile '{}' not found", profile))?;
+        let profile_config = crate::config::ProfileConfig {
+            homebrew_formulas: resolved.homebrew_formulas.clone(),
+            homebrew_casks: resolved.homebrew_casks.clone(),
+            ..Default::default()
+        };
+        let mut report = InstallReport::default();
+        self.install_taps(&resolved.homebrew_taps, &mut report)?;
+        self.install_formulas_and_casks(profile, &profile_config, &mut report)
+            .await?;
+        // App Store apps go last: `mas` itself is usually installed as a formula
+        self.install_mas_apps(&resolved.mas_apps, &mut report)?;
+        Ok(report)
+    }
+
+    async fn install_formulas_and_casks(
+        &self,
+        profile: &str,
+        profile_config: &crate::config::ProfileConfig,
+        report: &mut InstallReport,
+    ) -> Result<()> {
         if profile_config.homebrew_formulas.is_empty() && profile_config.homebrew_casks.is_empty() {
             tracing::info!(
                 "No Homebrew formulas or casks defined for profile '{}'",
@@ -56,96 +564,275 @@ impl BrewManager {
             );
             return Ok(());
         }
-        // Install missing formulas in one command
-        if !missing_formulas.is_empty() {
-            if self.dry_run {
+        if self.dry_run {
+            if !missing_formulas.is_empty() {
                 println!(
                     "[DRY-RUN] Would install formulas: {}",
                     missing_formulas.join(" ")
                 );
-            } else {
-                let mut cmd = std::process::Command::new("brew");
-                cmd.arg("install");
-                for formula in &missing_formulas {
-                    cmd.arg(formula);
-                }
-                let output = cmd.output().with_context(|| {
-                    format!(
-                        "Failed to run brew install for formulas: {}",
-                        missing_formulas.join(", ")
-                    )
-                })?;
-                if output.status.success() {
-                    tracing::info!("Installed formulas: {}", missing_formulas.join(", "));
-                } else {
-                    let error = String::from_utf8_lossy(&output.stderr);
-                    tracing::warn!(
-                        "Failed to install formulas: {}: {}",
-                        missing_formulas.join(", "),
-                        error
-                    );
-                }
             }
-        }
-        // Install missing casks in one command
-        if !missing_casks.is_empty() {
-            if self.dry_run {
+            if !missing_casks.is_empty() {
                 println!("[DRY-RUN] Would install casks: {}", missing_casks.join(" "));
-            } else {
-                let mut cmd = std::process::Command::new("brew");
-                cmd.arg("install");
+            }
+            return Ok(());
+        }
+
+        let packages: Vec<(PackageKind, &str)> = missing_formulas
+            .iter()
+            .map(|formula| (PackageKind::Formula, formula.as_str()))
+            .chain(
+                missing_casks
+                    .iter()
+                    .map(|cask| (PackageKind::Cask, cask.as_str())),
+            )
+            .collect();
+        let names: Vec<&str> = packages.iter().map(|(_, name)| *name).collect();
+        eprintln!("Missing Homebrew packages: {}", names.join(", "));
+        if !crate::prompt::confirm(&format!("Install {} package(s)?", packages.len()), true) {
+            eprintln!("Skipped installing Homebrew packages for profile '{profile}'.");
+            return Ok(());
+        }
+
+        // Downloads can run side by side; installs take brew's lock, so they go one at a time
+        prefetch(&packages);
+        for (kind, name) in packages {
+            let mut cmd = Command::new("brew");
+            cmd.arg("install");
+            if kind == PackageKind::Cask {
                 cmd.arg("--cask");
-                for cask in &missing_casks {
-                    cmd.arg(cask);
-                }
-                let output = cmd.output().with_context(|| {
-                    format!(
-                        "Failed to run brew install --cask for casks: {}",
-                        missing_casks.join(", ")
-                    )
-                })?;
-                if output.status.success() {
-                    tracing::info!("Installed casks: {}", missing_casks.join(", "));
-                } else {
-                    let error = String::from_utf8_lossy(&output.stderr);
-                    tracing::warn!(
-                        "Failed to install casks: {}: {}",
-                        missing_casks.join(", "),
-                        error
-                    );
-                }
             }
+            let result = run_brew(cmd.arg(name));
+            match &result {
+                Ok(()) => tracing::info!("Installed {} {}", kind.label(), name),
+                Err(e) => tracing::warn!("Failed to install {} {}: {}", kind.label(), name, e),
+            }
+            report.push(kind, name, result);
+        }
+        Ok(())
+    }
+
+    /// Tap any taps that are not already tapped
+    fn install_taps(&self, taps: &[String], report: &mut InstallReport) -> Result<()> {
+        if taps.is_empty() {
+            return Ok(());
+        }
+        let current = self.get_current_taps()?;
+        for tap in taps.iter().filter(|t| !current.contains(t)) {
+            if self.dry_run {
+                println!("[DRY-RUN] Would tap: {tap}");
+                continue;
+            }
+            let result = run_brew(Command::new("brew").args(["tap", tap]));
+            match &result {
+                Ok(()) => tracing::info!("Tapped {}", tap),
+                Err(e) => tracing::warn!("Failed to tap {}: {}", tap, e),
+            }
+            report.push(PackageKind::Tap, tap, result);
         }
         Ok(())
     }
 
+    /// Install Mac App Store apps with `mas`, skipping ones already installed
+    fn install_mas_apps(&self, apps: &[MasApp], report: &mut InstallReport) -> Result<()> {
+        if apps.is_empty() {
+            return Ok(());
+        }
+        if which::which("mas").is_err() {
+            println!(
+                "Skipping {} Mac App Store app(s): 'mas' is not installed (brew install mas)",
+                apps.len()
+            );
+            return Ok(());
+        }
+        let installed = self.get_current_mas_apps()?;
+        for app in apps
+            .iter()
+            .filter(|app| !installed.iter().any(|i| i.id == app.id))
+        {
+            if self.dry_run {
+                println!(
+                    "[DRY-RUN] Would install App Store app: {} ({})",
+                    app.name, app.id
+                );
+                continue;
+            }
+            let result = run_brew(Command::new("mas").args(["install", &app.id.to_string()]));
+            match &result {
+                Ok(()) => tracing::info!("Installed App Store app {} ({})", app.name, app.id),
+                Err(e) => tracing::warn!(
+                    "Failed to install App Store app {} ({}): {}",
+                    app.name,
+                    app.id,
+                    e
+                ),
+            }
+            report.push(
+                PackageKind::MasApp,
+                &format!("{} ({})", app.name, app.id),
+                result,
+            );
+        }
+        Ok(())
+    }
+
+    /// Compare installed formulas and casks with a profile
+    pub async fn diff_packages(&self, profile: &str, config: &Config) -> Result<PackageDiff> {
+        let resolved = config.resolve_profile(profile)?;
+        let (formulas, casks) = self.get_current_packages().await?;
+        Ok(compare_packages(
+            (&resolved.homebrew_formulas, &resolved.homebrew_casks),
+            (&formulas, &casks),
+            self.get_outdated()?,
+        ))
+    }
+
+    /// Declared formulas and casks of a resolved profile with newer versions
+    /// available
+    pub fn outdated_resolved(
+        &self,
+        resolved: &crate::config::ResolvedProfile,
+    ) -> Result<Vec<OutdatedPackage>> {
+        Ok(declared_outdated(
+            (&resolved.homebrew_formulas, &resolved.homebrew_casks),
+            self.get_outdated()?,
+        ))
+    }
+
+    /// Install what a profile is missing and, with `remove_extra`, uninstall
+    /// formulas and casks it doesn't list. Returns the diff from before, and
+    /// how the installs went.
+    pub async fn sync_packages(
+        &self,
+        profile: &str,
+        config: &Config,
+        remove_extra: bool,
+    ) -> Result<(PackageDiff, InstallReport)> {
+        let diff = self.diff_packages(profile, config).await?;
+        let report = self.install_packages(profile, config).await?;
+        if remove_extra {
+            self.uninstall(&diff.extra_formulas, false)?;
+            self.uninstall(&diff.extra_casks, true)?;
+        }
+        Ok((diff, report))
+    }
+
+    /// Uninstall formulas and casks, e.g. ones only a profile being switched
+    /// away from lists. Ones that aren't installed are skipped.
+    pub async fn uninstall_packages(&self, formulas: &[String], casks: &[String]) -> Result<()> {
+        let (installed_formulas, installed_casks) = self.get_current_packages().await?;
+        let installed = |names: &[String], installed: &[String]| -> Vec<String> {
+            names
+                .iter()
+                .filter(|name| installed.contains(name))
+                .cloned()
+                .collect()
+        };
+        self.uninstall(&installed(formulas, &installed_formulas), false)?;
+        self.uninstall(&installed(casks, &installed_casks), true)
+    }
+
+    fn uninstall(&self, names: &[String], cask: bool) -> Result<()> {
+        if names.is_empty() {
+            return Ok(());
+        }
+        let kind = if cask { "casks" } else { "formulas" };
+        if self.dry_run {
+            println!("[DRY-RUN] Would uninstall {kind}: {}", names.join(" "));
+            return Ok(());
+        }
+        let mut cmd = Command::new("brew");
+        cmd.arg("uninstall");
+        if cask {
+            cmd.arg("--cask");
+        }
+        let output = environment::output(cmd.args(names), Effect::Changes)
+            .with_context(|| format!("Failed to run brew uninstall for {kind}"))?;
+        if !output.status.success() {
+            return Err(OrdinatorError::Brew {
+                command: format!(
+                    "uninstall {}{}",
+                    if cask { "--cask " } else { "" },
+                    names.join(" ")
+                ),
+                reason: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            }
+            .into());
+        }
+        tracing::info!("Uninstalled {}: {}", kind, names.join(", "));
+        Ok(())
+    }
+
+    /// Formulas and casks with newer versions available
+    fn get_outdated(&self) -> Result<Vec<OutdatedPackage>> {
+        let output = environment::output(
+            Command::new("brew").args(["outdated", "--json=v2"]),
+            Effect::Reads,
+        )
+        .with_context(|| "Failed to run 'brew outdated'")?;
+        if !output.status.success() || output.stdout.is_empty() {
+            return Ok(Vec::new());
+        }
+        parse_outdated(&String::from_utf8_lossy(&output.stdout))
+    }
+
     /// List packages for a profile
     pub fn list_packages(&self, profile: &str, config: &Config) -> Result<()> {
-        let profile_config = config
-            .get_profile(profile)
-            .ok_or_else(|| anyhow::anyhow!("Profile '{}' not found", profile))?;
+        let resolved = config.resolve_profile(profile)?;
 
         println!("Homebrew packages for profile '{profile}':");
-        for formula in &profile_config.homebrew_formulas {
+        for tap in &resolved.homebrew_taps {
+            println!("  - {tap} (tap)");
+        }
+        for formula in &resolved.homebrew_formulas {
             println!("  - {formula}");
         }
-        for cask in &profile_config.homebrew_casks {
+        for cask in &resolved.homebrew_casks {
             println!("  - {cask}");
         }
+        for app in &resolved.mas_apps {
+            println!("  - {} (App Store {})", app.name, app.id);
+        }
 
         Ok(())
     }
 
+    /// Get currently tapped repositories
+    fn get_current_taps(&self) -> Result<Vec<String>> {
+        let output = environment::output(Command::new("brew").arg("tap"), Effect::Reads)
+            .with_context(|| "Failed to run 'brew tap'")?;
+        if !output.status.success() {
+            return Ok(Vec::new());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Get installed Mac App Store apps (empty if `mas` is not installed)
+    fn get_current_mas_apps(&self) -> Result<Vec<MasApp>> {
+        if which::which("mas").is_err() {
+            return Ok(Vec::new());
+        }
+        let output = environment::output(Command::new("mas").arg("list"), Effect::Reads)
+            .with_context(|| "Failed to run 'mas list'")?;
+        if !output.status.success() {
+            return Ok(Vec::new());
+        }
+        Ok(parse_mas_list(&String::from_utf8_lossy(&output.stdout)))
+    }
+
     /// Get current Homebrew formulas and casks
     async fn get_current_packages(&self) -> Result<(Vec<String>, Vec<String>)> {
         let mut formulas = Vec::new();
         let mut casks = Vec::new();
 
         // Get user-installed formulas
-        let formulae_output = Command::new("brew")
-            .args(["leaves", "-r"])
-            .output()
-            .with_context(|| "Failed to run 'brew leaves -r'")?;
+        let formulae_output =
+            environment::output(Command::new("brew").args(["leaves", "-r"]), Effect::Reads)
+                .with_context(|| "Failed to run 'brew leaves -r'")?;
 
         if formulae_output.status.success() {
             let formulae_str = String::from_utf8(formulae_output.stdout)
@@ -160,10 +847,9 @@ impl BrewManager {
         }
 
         // Get installed casks
-        let casks_output = Command::new("brew")
-            .args(["list", "--cask"])
-            .output()
-            .with_context(|| "Failed to run 'brew list --cask'")?;
+        let casks_output =
+            environment::output(Command::new("brew").args(["list", "--cask"]), Effect::Reads)
+                .with_context(|| "Failed to run 'brew list --cask'")?;
 
         if casks_output.status.success() {
             let casks_str = String::from_utf8(casks_output.stdout)
@@ -201,11 +887,40 @@ impl BrewManager {
         Ok((missing_formulas, missing_casks))
     }
 
+    /// Taps, formulas, casks, and App Store apps of a profile that are not installed yet
+    pub async fn missing_packages(
+        &self,
+        resolved: &crate::config::ResolvedProfile,
+    ) -> Result<MissingPackages> {
+        let current_taps = self.get_current_taps()?;
+        let profile_config = crate::config::ProfileConfig {
+            homebrew_formulas: resolved.homebrew_formulas.clone(),
+            homebrew_casks: resolved.homebrew_casks.clone(),
+            ..Default::default()
+        };
+        let (formulas, casks) = self.get_missing_packages(&profile_config).await?;
+        let installed_apps = self.get_current_mas_apps()?;
+        Ok(MissingPackages {
+            taps: resolved
+                .homebrew_taps
+                .iter()
+                .filter(|t| !current_taps.contains(t))
+                .cloned()
+                .collect(),
+            formulas,
+            casks,
+            mas_apps: resolved
+                .mas_apps
+                .iter()
+                .filter(|app| !installed_apps.iter().any(|i| i.id == app.id))
+                .cloned()
+                .collect(),
+        })
+    }
+
     /// Check if Homebrew is installed
     pub fn check_homebrew_installed() -> bool {
-        Command::new("brew")
-            .arg("--version")
-            .output()
+        environment::output(Command::new("brew").arg("--version"), Effect::Reads)
             .map(|output| output.status.success())
             .unwrap_or(false)
     }
@@ -348,6 +1063,131 @@ mod tests {
         assert_eq!(parsed_profile.homebrew_casks[0], "dummycask");
     }
 
+    #[test]
+    fn test_parse_brewfile() {
+        let content = r#"
+# Taps
+tap "homebrew/bundle"
+tap "user/tools", "https://example.com/user/tools.git"
+brew "git"
+brew "postgresql@14", restart_service: true
+cask "iterm2"
+cask "font-fira-code", args: { appdir: "~/Applications" }
+mas "Xcode", id: 497799835
+vscode "rust-lang.rust-analyzer"
+"#;
+        let brewfile = Brewfile::parse(content).unwrap();
+        assert_eq!(brewfile.taps, vec!["homebrew/bundle", "user/tools"]);
+        assert_eq!(brewfile.formulas, vec!["git", "postgresql@14"]);
+        assert_eq!(brewfile.casks, vec!["iterm2", "font-fira-code"]);
+        assert_eq!(
+            brewfile.mas_apps,
+            vec![MasApp {
+                name: "Xcode".to_string(),
+                id: 497799835
+            }]
+        );
+        assert_eq!(brewfile.skipped, vec!["vscode \"rust-lang.rust-analyzer\""]);
+    }
+
+    #[test]
+    fn test_parse_brewfile_errors() {
+        assert!(Brewfile::parse("brew git").is_err());
+        assert!(Brewfile::parse("mas \"Xcode\"").is_err());
+    }
+
+    #[test]
+    fn test_brewfile_roundtrip() {
+        let brewfile = Brewfile {
+            taps: vec!["homebrew/cask-fonts".to_string()],
+            formulas: vec!["ripgrep".to_string()],
+            casks: vec!["raycast".to_string()],
+            mas_apps: vec![MasApp {
+                name: "Things 3".to_string(),
+                id: 904280696,
+            }],
+            skipped: Vec::new(),
+        };
+        let rendered = brewfile.to_brewfile_string();
+        assert!(rendered.contains("mas \"Things 3\", id: 904280696"));
+        assert_eq!(Brewfile::parse(&rendered).unwrap(), brewfile);
+    }
+
+    #[test]
+    fn test_import_brewfile_merge_and_replace() {
+        let mut config = Config::create_default();
+        config.get_profile_mut("default").unwrap().homebrew_formulas = vec!["git".to_string()];
+        let brewfile = Brewfile::parse("brew \"git\"\nbrew \"jq\"\ntap \"a/b\"\n").unwrap();
+        let manager = BrewManager::new(false);
+
+        manager
+            .import_brewfile("default", &brewfile, false, &mut config)
+            .unwrap();
+        let profile = config.get_profile("default").unwrap();
+        assert_eq!(profile.homebrew_formulas, vec!["git", "jq"]);
+        assert_eq!(profile.homebrew_taps, vec!["a/b"]);
+
+        let brewfile = Brewfile::parse("cask \"zed\"\n").unwrap();
+        manager
+            .import_brewfile("default", &brewfile, true, &mut config)
+            .unwrap();
+        let profile = config.get_profile("default").unwrap();
+        assert!(profile.homebrew_formulas.is_empty());
+        assert_eq!(profile.homebrew_casks, vec!["zed"]);
+
+        assert!(manager
+            .import_brewfile("missing", &brewfile, false, &mut config)
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_mas_list() {
+        let apps = parse_mas_list("497799835  Xcode  (15.0)\n904280696  Things 3 (3.20)\n\n");
+        assert_eq!(apps.len(), 2);
+        assert_eq!(apps[1].name, "Things 3");
+        assert_eq!(apps[1].id, 904280696);
+    }
+
+    #[test]
+    fn test_compare_packages_and_outdated() {
+        let outdated = parse_outdated(
+            r#"{
+  "formulae": [
+    {"name": "git", "installed_versions": ["2.40.0"], "current_version": "2.41.0"},
+    {"name": "openssl@3", "installed_versions": ["3.1.0"], "current_version": "3.1.1"}
+  ],
+  "casks": [{"name": "firefox", "installed_versions": "115.0", "current_version": "116.0"}]
+}"#,
+        )
+        .unwrap();
+        assert_eq!(outdated.len(), 3);
+        assert_eq!(outdated[2].installed, "115.0");
+
+        let strings = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let diff = compare_packages(
+            (
+                &strings(&["homebrew/core/git", "awscli"]),
+                &strings(&["firefox", "docker"]),
+            ),
+            (
+                &strings(&["homebrew/core/git", "wget"]),
+                &strings(&["firefox"]),
+            ),
+            outdated,
+        );
+        assert_eq!(diff.missing_formulas, ["awscli"]);
+        assert_eq!(diff.missing_casks, ["docker"]);
+        assert_eq!(diff.extra_formulas, ["wget"]);
+        assert!(diff.extra_casks.is_empty());
+        let outdated: Vec<&str> = diff.outdated.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(outdated, ["git", "firefox"]);
+        assert_eq!(
+            outdated_notice("work", &diff.outdated).unwrap(),
+            "2 Homebrew package(s) declared in profile 'work' are outdated (git, firefox). Run: ordinator brew outdated --profile work"
+        );
+        assert_eq!(outdated_notice("work", &[]), None);
+    }
+
     #[tokio::test]
     async fn test_get_missing_packages() {
         let mut config = Config::create_default();
@@ -361,6 +1201,22 @@ mod tests {
         assert_eq!(missing_casks, vec!["alfred"]);
     }
 
+    #[test]
+    fn test_install_report_table() {
+        let mut report = InstallReport::default();
+        report.push(PackageKind::Formula, "ripgrep", Ok(()));
+        let failed =
+            run_brew(Command::new("sh").args(["-c", "echo 'Error: no bottle' >&2; exit 1"]));
+        report.push(PackageKind::Cask, "firefox", failed);
+        assert_eq!(report.failures(), 1);
+        assert_eq!(
+            report.table(),
+            "Package  Kind           Result\n\
+             ripgrep  formula        installed\n\
+             firefox  cask           failed: no bottle"
+        );
+    }
+
     #[tokio::test]
     async fn test_install_packages_dry_run_and_noop() {
         let mut config = Config::create_default();
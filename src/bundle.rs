@@ -0,0 +1,337 @@
+//! `ordinator export` and `ordinator import`: the dotfiles repository, and
+//! optionally the age keys, in one age-encrypted tar archive for moving to a
+//! machine without access to the git remote.
+
+use anyhow::{Context, Result};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::environment::{self, Effect};
+use crate::error::OrdinatorError;
+
+/// Top-level directory of the repository inside a bundle
+const REPO_DIR: &str = "dotfiles";
+
+/// Top-level directory of the age keys inside a bundle
+const KEYS_DIR: &str = "age";
+
+/// Machine-specific directories left out of a bundle (see the generated .gitignore)
+const SKIPPED_DIRS: &[&str] = crate::config::MACHINE_STATE_DIRS;
+
+/// What went into a bundle
+#[derive(Debug, Default)]
+pub struct ExportSummary {
+    /// Repository files in the bundle
+    pub files: usize,
+
+    /// Age key files in the bundle
+    pub keys: usize,
+}
+
+/// What an import wrote
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    /// Repository files written to the target directory
+    pub files: usize,
+
+    /// Age keys written to the key directory
+    pub keys: Vec<PathBuf>,
+
+    /// Age keys left alone because a key with the same name already exists
+    pub skipped_keys: Vec<PathBuf>,
+}
+
+/// Directory holding the age keys, e.g. `~/.config/ordinator/age`
+pub fn keys_dir() -> PathBuf {
+    let key = crate::secrets::get_age_key_path("default");
+    key.parent().map(Path::to_path_buf).unwrap_or(key)
+}
+
+/// Write `dotfiles_dir` (and the age keys in `keys_dir`, if given) as a tar
+/// archive encrypted with age to `output`. With no `recipients`, age asks
+/// for a passphrase.
+pub fn export(
+    dotfiles_dir: &Path,
+    keys_dir: Option<&Path>,
+    output: &Path,
+    recipients: &[String],
+) -> Result<ExportSummary> {
+    if recipients.is_empty() && !crate::prompt::interactive() {
+        return Err(anyhow::anyhow!(
+            "Encrypting with a passphrase needs a terminal. Pass --recipient <age public key> instead."
+        ));
+    }
+    let output = std::path::absolute(output)?;
+    let (archive, summary) = build_archive(dotfiles_dir, keys_dir, Some(&output))?;
+
+    let mut command = age_command()?;
+    command.arg("--encrypt");
+    if recipients.is_empty() {
+        command.arg("--passphrase");
+    }
+    for recipient in recipients {
+        command.args(["--recipient", recipient]);
+    }
+    command.arg("--output").arg(&output);
+    let encrypted = environment::output_with_input(&mut command, &archive, Effect::Changes)
+        .context("Failed to run age")?;
+    if !encrypted.status.success() {
+        return Err(anyhow::anyhow!(
+            "age failed to encrypt {}",
+            output.display()
+        ));
+    }
+    Ok(summary)
+}
+
+/// Decrypt `bundle` and write its repository to `target` and its age keys to
+/// `keys_dir`. age asks for the passphrase unless an `identity` is given.
+pub fn import(
+    bundle: &Path,
+    target: &Path,
+    keys_dir: &Path,
+    identity: Option<&Path>,
+    force: bool,
+) -> Result<ImportSummary> {
+    if !bundle.is_file() {
+        return Err(anyhow::anyhow!("Bundle '{}' not found", bundle.display()));
+    }
+    let occupied = target
+        .read_dir()
+        .is_ok_and(|mut entries| entries.next().is_some());
+    if occupied && !force {
+        return Err(anyhow::anyhow!(
+            "{} is not empty. Use --force to import into it anyway.",
+            target.display()
+        ));
+    }
+
+    let mut command = age_command()?;
+    command.arg("--decrypt");
+    if let Some(identity) = identity {
+        command.arg("--identity").arg(identity);
+    }
+    command.arg(bundle).stderr(Stdio::inherit());
+    let output = environment::output(&mut command, Effect::Reads).context("Failed to run age")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "age failed to decrypt {}",
+            bundle.display()
+        ));
+    }
+    extract_archive(&output.stdout, target, keys_dir, force)
+        .with_context(|| format!("{} is not a valid ordinator bundle", bundle.display()))
+}
+
+fn age_command() -> Result<Command> {
+    let age = which::which("age").map_err(|_| OrdinatorError::AgeNotInstalled)?;
+    Ok(Command::new(age))
+}
+
+/// Tar `dotfiles_dir` under `dotfiles/` and the files in `keys_dir` under
+/// `age/`, leaving out machine-specific directories and `skip` (the bundle
+/// itself when it's written inside the repository)
+fn build_archive(
+    dotfiles_dir: &Path,
+    keys_dir: Option<&Path>,
+    skip: Option<&Path>,
+) -> Result<(Vec<u8>, ExportSummary)> {
+    let mut summary = ExportSummary::default();
+    let mut builder = tar::Builder::new(Vec::new());
+    builder.follow_symlinks(false);
+
+    let entries = walkdir::WalkDir::new(dotfiles_dir)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|entry| {
+            let top_level_skip = entry.depth() == 1
+                && entry.file_type().is_dir()
+                && SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref());
+            !top_level_skip && Some(entry.path()) != skip
+        });
+    for entry in entries {
+        let entry = entry?;
+        let name = Path::new(REPO_DIR).join(entry.path().strip_prefix(dotfiles_dir)?);
+        builder
+            .append_path_with_name(entry.path(), &name)
+            .with_context(|| format!("Failed to archive {}", entry.path().display()))?;
+        if !entry.file_type().is_dir() {
+            summary.files += 1;
+        }
+    }
+
+    if let Some(keys_dir) = keys_dir.filter(|dir| dir.is_dir()) {
+        for entry in std::fs::read_dir(keys_dir)? {
+            let path = entry?.path();
+            if path.is_file() {
+                let name = Path::new(KEYS_DIR).join(path.file_name().unwrap_or_default());
+                builder.append_path_with_name(&path, name)?;
+                summary.keys += 1;
+            }
+        }
+    }
+    Ok((builder.into_inner()?, summary))
+}
+
+/// Refuse to write `rest` under `root` through a symlink, such as one an
+/// earlier entry in the bundle created, which could point anywhere
+fn check_inside(root: &Path, rest: &Path) -> Result<()> {
+    let mut dir = root.to_path_buf();
+    for component in rest.parent().into_iter().flat_map(Path::components) {
+        dir.push(component);
+        if crate::utils::is_symlink(&dir) {
+            return Err(anyhow::anyhow!(
+                "Unsafe path in bundle: {} is written through the symlink {}",
+                rest.display(),
+                dir.display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Unpack an archive made by `build_archive`
+fn extract_archive(
+    archive: &[u8],
+    target: &Path,
+    keys_dir: &Path,
+    force: bool,
+) -> Result<ImportSummary> {
+    let mut summary = ImportSummary::default();
+    let mut found_repo = false;
+    let mut archive = tar::Archive::new(archive);
+    archive.set_preserve_permissions(true);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(anyhow::anyhow!("Unsafe path in bundle: {}", path.display()));
+        }
+        let kind = entry.header().entry_type();
+        if kind.is_hard_link() {
+            return Err(anyhow::anyhow!(
+                "Unsafe hard link in bundle: {}",
+                path.display()
+            ));
+        }
+        let is_file = !kind.is_dir();
+        let dest = if let Ok(rest) = path.strip_prefix(REPO_DIR) {
+            found_repo = true;
+            check_inside(target, rest)?;
+            if is_file {
+                summary.files += 1;
+            }
+            target.join(rest)
+        } else if let Ok(rest) = path.strip_prefix(KEYS_DIR) {
+            // Keys are plain files; export never archives links there
+            if kind.is_symlink() {
+                return Err(anyhow::anyhow!(
+                    "Unsafe symlink in bundle: {}",
+                    path.display()
+                ));
+            }
+            check_inside(keys_dir, rest)?;
+            let dest = keys_dir.join(rest);
+            if dest.exists() && !force {
+                summary.skipped_keys.push(dest);
+                continue;
+            }
+            if is_file {
+                summary.keys.push(dest.clone());
+            }
+            dest
+        } else {
+            continue;
+        };
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        entry
+            .unpack(&dest)
+            .with_context(|| format!("Failed to write {}", dest.display()))?;
+    }
+    if !found_repo {
+        return Err(anyhow::anyhow!("it has no {REPO_DIR}/ directory"));
+    }
+    for key in &summary.keys {
+        crate::utils::set_file_mode(key, 0o600)?;
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_archive_roundtrip_skips_machine_specific_files() {
+        let source = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("ordinator.toml"), "[global]\n").unwrap();
+        fs::create_dir_all(source.path().join("files/default")).unwrap();
+        fs::write(source.path().join("files/default/abc123_.zshrc"), "zsh").unwrap();
+        fs::create_dir_all(source.path().join("cache")).unwrap();
+        fs::write(source.path().join("cache/hashes.json"), "{}").unwrap();
+        let keys = tempfile::tempdir().unwrap();
+        fs::write(keys.path().join("key.txt"), "AGE-SECRET-KEY-1").unwrap();
+
+        let (archive, exported) = build_archive(source.path(), Some(keys.path()), None).unwrap();
+        assert_eq!(exported.files, 2);
+        assert_eq!(exported.keys, 1);
+
+        let target = tempfile::tempdir().unwrap();
+        let new_keys = tempfile::tempdir().unwrap();
+        fs::write(new_keys.path().join("key.txt"), "existing").unwrap();
+        let imported = extract_archive(&archive, target.path(), new_keys.path(), false).unwrap();
+        assert_eq!(imported.files, 2);
+        assert_eq!(imported.skipped_keys.len(), 1);
+        assert_eq!(
+            fs::read_to_string(target.path().join("files/default/abc123_.zshrc")).unwrap(),
+            "zsh"
+        );
+        assert!(!target.path().join("cache").exists());
+        assert_eq!(
+            fs::read_to_string(new_keys.path().join("key.txt")).unwrap(),
+            "existing"
+        );
+
+        let imported = extract_archive(&archive, target.path(), new_keys.path(), true).unwrap();
+        assert_eq!(imported.keys.len(), 1);
+        assert_eq!(
+            crate::utils::file_mode(&new_keys.path().join("key.txt")).unwrap(),
+            0o600
+        );
+
+        let mut builder = tar::Builder::new(Vec::new());
+        builder
+            .append_path_with_name(keys.path().join("key.txt"), "key.txt")
+            .unwrap();
+        let stray = builder.into_inner().unwrap();
+        assert!(extract_archive(&stray, target.path(), new_keys.path(), true).is_err());
+    }
+
+    #[test]
+    fn test_extract_refuses_writing_through_a_symlink_entry() {
+        let outside = tempfile::tempdir().unwrap();
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut link = tar::Header::new_gnu();
+        link.set_entry_type(tar::EntryType::Symlink);
+        link.set_size(0);
+        builder
+            .append_link(&mut link, "dotfiles/escape", outside.path())
+            .unwrap();
+        let mut file = tar::Header::new_gnu();
+        file.set_size(4);
+        file.set_mode(0o644);
+        builder
+            .append_data(&mut file, "dotfiles/escape/evil", &b"evil"[..])
+            .unwrap();
+        let archive = builder.into_inner().unwrap();
+
+        let target = tempfile::tempdir().unwrap();
+        let keys = tempfile::tempdir().unwrap();
+        let error = extract_archive(&archive, target.path(), keys.path(), true).unwrap_err();
+        assert!(error.to_string().contains("symlink"), "{error}");
+        assert!(!outside.path().join("evil").exists());
+    }
+}
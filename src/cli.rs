@@ -1,18 +1,17 @@
-use std::io::{self, Write};
 use std::os::unix::fs::PermissionsExt;
-use std::path::PathBuf;
-use std::time::SystemTime;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use colored::*;
-use is_terminal::IsTerminal;
 
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 
 use crate::config::Config;
+use crate::error::OrdinatorError;
 use crate::git::GitManager;
-use crate::utils::generate_file_hash;
+use crate::hooks::HookEvent;
+use crate::utils::{color_enabled, generate_file_hash};
 
 #[derive(Parser)]
 #[command(name = "ordinator")]
@@ -26,6 +25,10 @@ pub struct Args {
     #[arg(long, global = true)]
     pub dry_run: bool,
 
+    /// Like --dry-run, but Homebrew, defaults, launchctl, sudo, and SOPS commands that would change this machine are built and shown exactly as they would run
+    #[arg(long, global = true)]
+    pub simulate: bool,
+
     /// Enable verbose logging
     #[arg(short, long, global = true)]
     pub verbose: bool,
@@ -33,6 +36,32 @@ pub struct Args {
     /// Suppress status messages (only show errors)
     #[arg(short, long, global = true)]
     pub quiet: bool,
+
+    /// How to report progress through many files: auto, plain, or json
+    #[arg(long, global = true, default_value = "auto")]
+    pub progress: crate::progress::ProgressMode,
+
+    /// Answer yes to every confirmation (also ORDINATOR_ASSUME_YES=1)
+    #[arg(short = 'y', long, global = true)]
+    pub yes: bool,
+
+    /// Never prompt; every question takes its default answer
+    #[arg(long, global = true)]
+    pub no_input: bool,
+
+    /// Append debug logs, one JSON object per line, to this file (for bug reports)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+
+    /// Treat a symlink as correct only if its target is stored exactly as
+    /// ordinator would write it, not just the same file (e.g. via /private/var)
+    #[arg(long, global = true)]
+    pub strict_paths: bool,
+
+    /// Delete files that are replaced without a backup, and links that are
+    /// uninstalled, instead of moving them to the Trash
+    #[arg(long, global = true)]
+    pub permanent: bool,
 }
 
 #[derive(Subcommand)]
@@ -54,6 +83,50 @@ pub enum Commands {
         /// Force overwrite existing directory
         #[arg(long)]
         force: bool,
+
+        /// Clone only the last N commits (e.g. --depth 1 for a large repository)
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+        depth: Option<u32>,
+
+        /// Times to retry a clone that fails on a flaky network or a rate limit
+        #[arg(long, value_name = "N", default_value_t = crate::transfer::DEFAULT_RETRIES)]
+        retries: u32,
+
+        /// Ask how to set up the repository: new or cloned, profiles, secrets, Homebrew packages, and common dotfiles
+        #[arg(long, conflicts_with = "repo_url")]
+        interactive: bool,
+    },
+
+    /// Set up this machine from a dotfiles repository: clone, age keys, Homebrew, bootstrap, apply.
+    /// If a step fails, run the same command again to resume from it.
+    Setup {
+        /// Repository URL to clone from (GitHub HTTPS or SSH)
+        #[arg(value_name = "REPO_URL")]
+        repo_url: String,
+
+        /// Profile to set up (defaults to the profile whose match rules fit this machine, then default_profile)
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Skip age key setup and secrets decryption
+        #[arg(long)]
+        skip_secrets: bool,
+
+        /// Skip Homebrew package installation
+        #[arg(long)]
+        skip_brew: bool,
+
+        /// Skip running the bootstrap script
+        #[arg(long)]
+        skip_bootstrap: bool,
+
+        /// Overwrite existing files while applying (use with caution)
+        #[arg(long)]
+        force: bool,
+
+        /// Discard an unfinished setup and start from the first step
+        #[arg(long)]
+        restart: bool,
     },
 
     /// Start tracking a file in the dotfiles repository
@@ -65,6 +138,75 @@ pub enum Commands {
         /// Profile to associate with this file
         #[arg(long)]
         profile: Option<String>,
+
+        /// For directories: only track files matching this glob (repeatable)
+        #[arg(long)]
+        include: Vec<String>,
+
+        /// For directories: never track files matching this glob (repeatable)
+        #[arg(long)]
+        exclude: Vec<String>,
+
+        /// For directories: symlink each file ("files") or the whole directory ("directory")
+        #[arg(long, default_value = "files")]
+        link: String,
+
+        /// For directories: link the whole directory as one symlink (same as --link directory)
+        #[arg(long, conflicts_with = "link")]
+        dir_link: bool,
+
+        /// Link this path with sudo during apply (for destinations like /etc/hosts)
+        #[arg(long)]
+        sudo: bool,
+
+        /// Store the file encrypted with SOPS/age; apply decrypts it instead of symlinking
+        #[arg(long, conflicts_with = "sudo")]
+        encrypt: bool,
+
+        /// Manage only the lines between `# >>> ordinator >>>` and
+        /// `# <<< ordinator <<<` in the file, leaving the rest of it alone
+        #[arg(long, conflicts_with_all = ["sudo", "dir_link"])]
+        managed_block: bool,
+
+        /// Don't commit the change, even if auto_commit is enabled
+        #[arg(long)]
+        no_commit: bool,
+    },
+
+    /// Import dotfiles already in your home directory, including symlinks into
+    /// another dotfiles repository (stow, chezmoi, ...)
+    Adopt {
+        /// Directory to search (defaults to the home directory)
+        #[arg(value_name = "PATH")]
+        path: Option<String>,
+
+        /// Profile to import into
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Only adopt symlinks, not plain dotfiles
+        #[arg(long)]
+        symlinks_only: bool,
+    },
+
+    /// Import a chezmoi source directory, stow directory, or yadm repository,
+    /// reporting anything that can't be translated
+    Migrate {
+        /// Tool the repository comes from: chezmoi, stow, or yadm
+        #[arg(long, value_name = "TOOL")]
+        from: crate::migrate::Tool,
+
+        /// The tool's repository (defaults to where the tool keeps it)
+        #[arg(value_name = "PATH")]
+        path: Option<String>,
+
+        /// Profile to import into
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Don't commit the change, even if auto_commit is enabled
+        #[arg(long)]
+        no_commit: bool,
     },
 
     /// Stop tracking a file in the dotfiles repository
@@ -76,6 +218,22 @@ pub enum Commands {
         /// Profile to remove this file from
         #[arg(long)]
         profile: Option<String>,
+
+        /// Stop tracking, but keep the repository copy; the symlink keeps working
+        #[arg(long, conflicts_with_all = ["restore", "purge"])]
+        keep_file: bool,
+
+        /// Replace the symlink with a real copy of the file, then delete the repository copy
+        #[arg(long, conflicts_with = "purge")]
+        restore: bool,
+
+        /// Delete the repository copy and the file apply put in the home directory
+        #[arg(long)]
+        purge: bool,
+
+        /// Don't commit the change, even if auto_commit is enabled
+        #[arg(long)]
+        no_commit: bool,
     },
 
     /// Update tracked files with current content
@@ -88,9 +246,17 @@ pub enum Commands {
         #[arg(long)]
         profile: Option<String>,
 
-        /// Update all tracked files for the profile
+        /// Update all tracked files and directories for the profile
         #[arg(long)]
         all: bool,
+
+        /// Store the file as an override for one host (default: this machine), used instead of the profile's copy there
+        #[arg(long, value_name = "HOSTNAME", num_args = 0..=1, conflicts_with = "all")]
+        host: Option<Option<String>>,
+
+        /// Don't commit the change, even if auto_commit is enabled
+        #[arg(long)]
+        no_commit: bool,
     },
 
     /// Commit changes to the repository
@@ -99,9 +265,33 @@ pub enum Commands {
         #[arg(short, long, required = true)]
         message: String,
 
+        /// Only stage changes under these files or directories (default: everything)
+        #[arg(value_name = "PATH")]
+        paths: Vec<PathBuf>,
+
+        /// Also stage changes to encrypted secrets under secrets/ when PATHs
+        /// are given; without PATHs they are staged anyway
+        #[arg(long, conflicts_with = "exclude_secrets")]
+        include_secrets: bool,
+
+        /// Leave changes to encrypted secrets under secrets/ unstaged
+        #[arg(long)]
+        exclude_secrets: bool,
+
         /// Skip secrets scanning and commit anyway
         #[arg(long)]
         force: bool,
+
+        /// Don't sign this commit, even if sign_commits is enabled
+        #[arg(long)]
+        no_sign: bool,
+    },
+
+    /// Record file, secret, and package hashes in ordinator.lock
+    Lock {
+        /// Only report how the repository differs from the lock; exits with status 2 if it does
+        #[arg(long)]
+        check: bool,
     },
 
     /// Push changes to remote repository
@@ -110,9 +300,21 @@ pub enum Commands {
         #[arg(value_name = "REPO_URL")]
         repo_url: Option<String>,
 
-        /// Force push (use with caution)
+        /// Force push (use with caution); asks for the branch name unless protect_force_push = false
         #[arg(long)]
         force: bool,
+
+        /// Force push without typing the branch name, for scripts
+        #[arg(long = "i-know-what-im-doing", requires = "force")]
+        i_know_what_im_doing: bool,
+
+        /// Branch to push (defaults to the checked-out branch)
+        #[arg(long)]
+        branch: Option<String>,
+
+        /// Also push to every remote under [[git.remotes]]
+        #[arg(long)]
+        all_remotes: bool,
     },
 
     /// Pull changes from remote repository
@@ -120,13 +322,39 @@ pub enum Commands {
         /// Rebase on pull
         #[arg(long)]
         rebase: bool,
+
+        /// Branch to pull (defaults to the checked-out branch)
+        #[arg(long)]
+        branch: Option<String>,
+
+        /// How conflicting ordinator.toml values are settled: merge (ask), ours, or theirs
+        #[arg(long, default_value = "merge")]
+        strategy: String,
     },
 
     /// Sync with remote repository (pull then push)
     Sync {
-        /// Force push after sync
+        /// Force push after sync; asks for the branch name unless protect_force_push = false
         #[arg(long)]
         force: bool,
+
+        /// Force push without typing the branch name, for scripts
+        #[arg(long = "i-know-what-im-doing", requires = "force")]
+        i_know_what_im_doing: bool,
+
+        /// Branch to sync (defaults to the checked-out branch)
+        #[arg(long)]
+        branch: Option<String>,
+
+        /// Also push to every remote under [[git.remotes]]
+        #[arg(long)]
+        all_remotes: bool,
+    },
+
+    /// Manage branches in the dotfiles repository (e.g. one per machine)
+    Branch {
+        #[command(subcommand)]
+        subcommand: BranchCommands,
     },
 
     /// Show repository status
@@ -134,14 +362,124 @@ pub enum Commands {
         /// Show detailed status
         #[arg(long)]
         verbose: bool,
+
+        /// Profile to check for drift (defaults to the default profile)
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Only check for drift, and exit 2 if files drifted or 3 if any changed on both sides
+        #[arg(long)]
+        check: bool,
+
+        /// One line per drifted file on stdout, like `git status -s`
+        #[arg(long, short)]
+        short: bool,
+
+        /// Only check tracked paths matching this glob (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        only: Vec<String>,
+
+        /// Leave out tracked paths matching this glob (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        skip: Vec<String>,
+
+        /// Only show how the repository compares with origin: commits ahead and behind, when this machine last pulled and pushed, and whether ordinator.toml differs
+        #[arg(long, conflicts_with_all = ["check", "short"])]
+        remote: bool,
+
+        /// With --remote, fetch from origin first instead of using what the last fetch saw
+        #[arg(long, requires = "remote")]
+        fetch: bool,
     },
 
-    /// Apply dotfiles to the current system
-    Apply {
-        /// Profile to apply
+    /// Show differences between home directory files and their tracked copies
+    Diff {
+        /// Tracked file to diff (defaults to every file in the profile)
+        path: Option<String>,
+
+        /// Profile whose tracked files to compare
         #[arg(long, default_value = "default")]
         profile: String,
 
+        /// Show a per-file summary of changed lines instead of the full diff
+        #[arg(long)]
+        stat: bool,
+
+        /// Only diff tracked paths matching this glob (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        only: Vec<String>,
+
+        /// Leave out tracked paths matching this glob (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        skip: Vec<String>,
+    },
+
+    /// Search the repository copies of tracked files for a pattern
+    Search {
+        /// Regular expression to search for
+        pattern: String,
+
+        /// Only search files this profile tracks, inherited ones included (default: every profile)
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Match case-insensitively
+        #[arg(short, long)]
+        ignore_case: bool,
+
+        /// Treat the pattern as a literal string
+        #[arg(short = 'F', long)]
+        fixed_strings: bool,
+
+        /// Also decrypt tracked secrets in memory and search them (asks first)
+        #[arg(long)]
+        secrets: bool,
+    },
+
+    /// List tracked files with their repository copy, destination, and state
+    #[command(visible_alias = "ls")]
+    Files {
+        /// Profile to list (defaults to the applied profile, then the default profile)
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Only list files that are modified, broken, or missing
+        #[arg(long, value_name = "modified|broken|missing")]
+        filter: Option<crate::listing::FileFilter>,
+
+        /// Print the list as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Explain what ordinator knows about a path: the profile that tracks it, its stored
+    /// copy, when it was last added and applied, and its current state
+    Why {
+        /// Destination to explain, e.g. ~/.zshrc
+        path: String,
+
+        /// Print the details as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Apply dotfiles to the current system
+    Apply {
+        /// Profile to apply (defaults to the profile whose match rules fit this machine, then default_profile).
+        /// Repeat it, or pass a comma-separated list, to apply several; later profiles win where they overlap.
+        /// A glob such as 'work*' stands for each matching profile, sorted by name.
+        #[arg(long, visible_alias = "profiles", value_delimiter = ',')]
+        profile: Vec<String>,
+
+        /// Apply every profile, sorted by name
+        #[arg(long, conflicts_with = "profile")]
+        all_profiles: bool,
+
+        /// Apply a machine role from [machines]: its profiles, in order, with its settings.
+        /// Remembered, so later applies without --profile use it again.
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["profile", "all_profiles"])]
+        machine: Option<String>,
+
         /// Skip bootstrap script execution
         #[arg(long)]
         skip_bootstrap: bool,
@@ -150,21 +488,99 @@ pub enum Commands {
         #[arg(long)]
         skip_secrets: bool,
 
+        /// Leave this profile's secrets encrypted, inherited ones included (repeatable)
+        #[arg(long, value_name = "PROFILE", value_delimiter = ',')]
+        no_secrets_for: Vec<String>,
+
+        /// Mark this machine as shared: no secrets are decrypted on it, now or on later applies
+        #[arg(long, conflicts_with = "not_shared")]
+        shared: bool,
+
+        /// Clear the shared mark, so secrets are decrypted again
+        #[arg(long)]
+        not_shared: bool,
+
         /// Skip Homebrew package installation
         #[arg(long)]
         skip_brew: bool,
 
+        /// Skip writing macOS defaults
+        #[arg(long)]
+        skip_defaults: bool,
+
+        /// Refuse to apply if the repository doesn't match ordinator.lock
+        #[arg(long)]
+        frozen: bool,
+
         /// Force overwrite existing files (use with caution)
         #[arg(long)]
         force: bool,
+
+        /// Write links that need sudo (e.g. under /etc) to this script instead of running sudo
+        #[arg(long, value_name = "PATH")]
+        sudo_script: Option<PathBuf>,
+
+        /// Save every destination apply may touch in a snapshot first, for
+        /// 'ordinator snapshots restore'; 'apfs' also takes an APFS local snapshot
+        #[arg(long, value_name = "KIND", num_args = 0..=1, default_missing_value = "copy")]
+        snapshot: Option<crate::snapshot::SnapshotKind>,
+
+        /// Exit with an error if a Homebrew package fails to install (by default
+        /// failures are reported and the apply carries on)
+        #[arg(long)]
+        strict: bool,
+
+        /// Most files to check or hash at once (default: number of CPUs, up to 16)
+        #[arg(long, short = 'j')]
+        jobs: Option<usize>,
+
+        /// Keep running and re-render or re-link tracked files as their sources change
+        #[arg(long)]
+        watch: bool,
+
+        /// Only apply tracked paths matching this glob, e.g. '.config/nvim/**'; skips
+        /// bootstrap, Homebrew, macOS defaults, launchd agents, and GPG keys (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        only: Vec<String>,
+
+        /// Leave out tracked paths matching this glob, e.g. '.gnupg/**' (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        skip: Vec<String>,
+    },
+
+    /// Preview the changes apply would make; exits with status 2 if any are pending
+    Plan {
+        /// Profile to plan (chosen like apply when omitted)
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Leave secrets out of the plan
+        #[arg(long)]
+        skip_secrets: bool,
+
+        /// Leave Homebrew packages out of the plan
+        #[arg(long)]
+        skip_brew: bool,
+
+        /// Leave macOS defaults out of the plan
+        #[arg(long)]
+        skip_defaults: bool,
+
+        /// Print only the summary, and exit 2 if changes are pending or 3 if any conflict
+        #[arg(long)]
+        check: bool,
     },
 
     /// Uninstall dotfiles and restore original configuration
     Uninstall {
-        /// Profile to uninstall (defaults to all profiles)
-        #[arg(long)]
+        /// Profile to uninstall (defaults to the profiles applied on this machine)
+        #[arg(long, conflicts_with = "all")]
         profile: Option<String>,
 
+        /// Uninstall every profile in the config, applied or not
+        #[arg(long)]
+        all: bool,
+
         /// Restore original files from backups
         #[arg(long)]
         restore_backups: bool,
@@ -172,6 +588,44 @@ pub enum Commands {
         /// Skip interactive confirmations
         #[arg(long)]
         force: bool,
+
+        /// Overwrite decrypted secrets with random data before removing them
+        #[arg(long)]
+        shred: bool,
+
+        /// Delete the dotfiles repository too, once everything else is removed
+        #[arg(long)]
+        purge_repo: bool,
+    },
+
+    /// Revert the most recent apply using its transaction journal
+    Rollback {
+        /// List recorded applies instead of rolling back
+        #[arg(long)]
+        list: bool,
+    },
+
+    /// List, prune, and restore backups of replaced files
+    Backups {
+        #[command(subcommand)]
+        subcommand: BackupCommands,
+    },
+
+    /// List and restore snapshots taken by 'apply --snapshot'
+    Snapshots {
+        #[command(subcommand)]
+        subcommand: SnapshotCommands,
+    },
+
+    /// Show what earlier runs did, from the run logs
+    Logs {
+        /// Show the events of the most recent run (of --command, if given)
+        #[arg(long)]
+        last: bool,
+
+        /// Only runs of this command (e.g. apply, or 'secrets watch')
+        #[arg(long, value_name = "COMMAND")]
+        command: Option<String>,
     },
 
     /// Repair broken symlinks
@@ -183,6 +637,30 @@ pub enum Commands {
         /// Show detailed repair information
         #[arg(long)]
         verbose: bool,
+
+        /// Drop tracked files and directories whose stored copy is gone from the repository
+        #[arg(long)]
+        prune: bool,
+
+        /// With --prune, also delete files under files/<profile>/ that nothing tracks
+        #[arg(long, requires = "prune")]
+        orphans: bool,
+
+        /// Only repair tracked paths matching this glob (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        only: Vec<String>,
+
+        /// Leave out tracked paths matching this glob (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        skip: Vec<String>,
+    },
+
+    /// Check the repository against ordinator.toml: stored copies, unreferenced
+    /// files, file mappings, encrypted secrets, and backups; exits with status 2 on problems
+    Fsck {
+        /// Drop stale file mappings and rename misnamed stored copies
+        #[arg(long)]
+        fix: bool,
     },
 
     /// List available profiles
@@ -190,6 +668,20 @@ pub enum Commands {
         /// Show detailed profile information
         #[arg(long)]
         verbose: bool,
+
+        /// Show this machine's details and which profile's match rules apply would use
+        #[arg(long)]
+        explain: bool,
+
+        /// Same as 'ordinator profile <COMMAND>'
+        #[command(subcommand)]
+        subcommand: Option<ProfileCommands>,
+    },
+
+    /// Add, remove, rename, or describe profiles
+    Profile {
+        #[command(subcommand)]
+        subcommand: ProfileCommands,
     },
 
     /// Manage secrets
@@ -221,6 +713,12 @@ pub enum Commands {
         subcommand: BrewCommands,
     },
 
+    /// Manage editor extensions (VS Code, Cursor, VSCodium)
+    Extensions {
+        #[command(subcommand)]
+        subcommand: ExtensionsCommands,
+    },
+
     /// Manage README generation
     Readme {
         #[command(subcommand)]
@@ -233,45 +731,381 @@ pub enum Commands {
         subcommand: AgeCommands,
     },
 
+    /// SSH config and keys: track, generate, and print public keys
+    Ssh {
+        #[command(subcommand)]
+        subcommand: SshCommands,
+    },
+
+    /// GnuPG config and keys: track them, and sign git commits on apply
+    Gpg {
+        #[command(subcommand)]
+        subcommand: GpgCommands,
+    },
+
+    /// Git remote credentials: check which SSH keys and tokens work
+    Auth {
+        #[command(subcommand)]
+        subcommand: AuthCommands,
+    },
+
+    /// launchd agents: track plists per profile and install them on apply
+    Launchd {
+        #[command(subcommand)]
+        subcommand: LaunchdCommands,
+    },
+
     /// Generate a replicate.sh script for easy repo replication
     ReplicateScript {
         /// Force overwrite if replicate.sh already exists
         #[arg(long)]
         force: bool,
     },
-}
 
-#[derive(Subcommand)]
-pub enum BootstrapCommands {
-    /// Show bootstrap script information for a profile
-    Show {
-        /// Profile to show bootstrap script for
-        #[arg(long, default_value = "default")]
-        profile: String,
-    },
+    /// Write the dotfiles repository to an age-encrypted archive, for machines
+    /// without access to the git remote
+    Export {
+        /// Where to write the bundle (e.g. bundle.tar.age)
+        #[arg(long, short)]
+        output: String,
 
-    /// Generate a new bootstrap script for a profile
-    Generate {
-        /// Profile to generate bootstrap script for
-        #[arg(long, default_value = "default")]
-        profile: String,
+        /// Encrypt to this age public key instead of a passphrase (repeatable)
+        #[arg(long = "recipient", short, value_name = "PUBLIC_KEY")]
+        recipients: Vec<String>,
 
-        /// Force overwrite existing script
+        /// Include the age keys from ~/.config/ordinator/age
+        #[arg(long)]
+        include_keys: bool,
+
+        /// Overwrite the output file if it exists
         #[arg(long)]
         force: bool,
     },
 
-    /// Edit bootstrap script for a profile
-    Edit {
-        /// Profile to edit bootstrap script for
-        #[arg(long, default_value = "default")]
-        profile: String,
-    },
-}
+    /// Restore a dotfiles repository from a bundle written by `ordinator export`
+    Import {
+        /// Bundle to import
+        bundle: String,
 
-#[derive(Subcommand)]
-pub enum SecretCommands {
-    /// Start tracking a file for encryption
+        /// Directory to import into (defaults to ~/.dotfiles)
+        #[arg(long)]
+        target_dir: Option<String>,
+
+        /// age identity file, for bundles encrypted with --recipient
+        #[arg(long, short)]
+        identity: Option<String>,
+
+        /// Import into a non-empty directory and replace existing age keys
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Manage the launchd agent that keeps dotfiles in sync
+    Daemon {
+        #[command(subcommand)]
+        subcommand: DaemonCommands,
+    },
+
+    /// Manage macOS defaults (Dock, Finder, keyboard, ...)
+    Defaults {
+        #[command(subcommand)]
+        subcommand: DefaultsCommands,
+    },
+
+    /// Read, change, edit, or check ordinator.toml
+    Config {
+        #[command(subcommand)]
+        subcommand: ConfigCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Print the value of a key (e.g. global.default_profile)
+    Get {
+        /// Dotted key; quote parts that contain dots, e.g. 'profiles.work.file_options."/etc/hosts"'
+        key: String,
+    },
+
+    /// Change the value of a key (e.g. profiles.work.description "Work laptop")
+    Set {
+        /// Dotted key; list items are addressed by index, e.g. git.remotes.0.url
+        key: String,
+
+        /// New value, read as TOML (true, 30, ["a", "b"]) or else as a string
+        value: String,
+
+        /// Add the value to a list, such as exclude
+        #[arg(long, conflicts_with = "remove")]
+        append: bool,
+
+        /// Remove the value from a list
+        #[arg(long)]
+        remove: bool,
+    },
+
+    /// Open ordinator.toml in $EDITOR and validate it after saving
+    Edit,
+
+    /// Report unknown keys, type mistakes, and deprecated keys in the config file
+    Validate {
+        /// Config file to check (defaults to the one ordinator would load)
+        #[arg(value_name = "PATH")]
+        path: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BranchCommands {
+    /// Create a branch and switch to it
+    Create {
+        /// Branch name
+        name: String,
+
+        /// Branch or commit to start from (defaults to the current commit)
+        #[arg(long)]
+        from: Option<String>,
+    },
+
+    /// Switch to a branch (fetched branches on origin are created locally)
+    Switch {
+        /// Branch name
+        name: String,
+    },
+
+    /// List local branches
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum SnapshotCommands {
+    /// List snapshots, newest first
+    List,
+
+    /// Put every destination a snapshot saved back as it was
+    Restore {
+        /// Snapshot to restore, as shown by `ordinator snapshots list`, or 'latest'
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BackupCommands {
+    /// List backups, newest first
+    List {
+        /// Only list backups of this file (e.g. .zshrc)
+        file: Option<String>,
+    },
+
+    /// Delete backups outside the retention policy
+    Prune {
+        /// Newest backups to keep per file (overrides backup_retention.max_count)
+        #[arg(long)]
+        max_count: Option<usize>,
+
+        /// Delete backups older than this many days (overrides backup_retention.max_age_days)
+        #[arg(long)]
+        max_age_days: Option<u32>,
+    },
+
+    /// Restore a file in the home directory from a backup
+    Restore {
+        /// File to restore, relative to the home directory (e.g. .zshrc)
+        file: String,
+
+        /// Backup to restore from, as shown by `ordinator backups list` (defaults to the newest)
+        #[arg(long)]
+        backup: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DefaultsCommands {
+    /// Capture the current settings of one or more defaults domains into a profile
+    Export {
+        /// Domains to capture (e.g. com.apple.dock NSGlobalDomain)
+        #[arg(required = true)]
+        domains: Vec<String>,
+
+        /// Profile to export settings to
+        #[arg(long, default_value = "default")]
+        profile: String,
+    },
+
+    /// Write a profile's defaults settings that differ from the current ones
+    Apply {
+        /// Profile whose settings to apply
+        #[arg(long, default_value = "default")]
+        profile: String,
+    },
+
+    /// Show settings that differ from the profile config
+    Diff {
+        /// Profile to compare against
+        #[arg(long, default_value = "default")]
+        profile: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DaemonCommands {
+    /// Install and load the launchd sync agent
+    Install {
+        /// Minutes between sync runs (saved to config)
+        #[arg(long)]
+        interval: Option<u64>,
+
+        /// Also sync whenever a tracked file changes
+        #[arg(long)]
+        watch_files: bool,
+
+        /// Profile to apply after each pull (saved to config)
+        #[arg(long)]
+        apply_profile: Option<String>,
+    },
+
+    /// Unload and remove the launchd sync agent
+    Uninstall,
+
+    /// Show whether the sync agent is installed and loaded
+    Status,
+
+    /// Run a single sync cycle (invoked by launchd)
+    Run,
+}
+
+#[derive(Subcommand)]
+pub enum ProfileCommands {
+    /// Create a new profile
+    Add {
+        /// Name of the profile to create
+        #[arg(required = true)]
+        name: String,
+
+        /// Description for the new profile
+        #[arg(long)]
+        description: Option<String>,
+    },
+
+    /// Remove a profile and its stored files from the repository
+    Remove {
+        /// Name of the profile to remove
+        #[arg(required = true)]
+        name: String,
+
+        /// Skip confirmation when the profile has tracked files
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Rename a profile, migrating its tracked files
+    Rename {
+        /// Current profile name
+        #[arg(required = true)]
+        old_name: String,
+
+        /// New profile name
+        #[arg(required = true)]
+        new_name: String,
+    },
+
+    /// Set the description of a profile
+    Describe {
+        /// Profile to describe
+        #[arg(required = true)]
+        name: String,
+
+        /// New description
+        #[arg(required = true)]
+        description: String,
+    },
+
+    /// Switch this machine to another profile, removing links only the previous profile made
+    Switch {
+        /// Profile to switch to
+        #[arg(required = true)]
+        name: String,
+
+        /// Profile to switch from (defaults to the profile applied most recently)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Also uninstall formulas and casks only the previous profile lists, and install the new profile's
+        #[arg(long)]
+        brew: bool,
+
+        /// Skip secrets decryption
+        #[arg(long)]
+        skip_secrets: bool,
+
+        /// Skip bootstrap script execution
+        #[arg(long)]
+        skip_bootstrap: bool,
+
+        /// Force overwrite existing files (use with caution)
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BootstrapCommands {
+    /// Show bootstrap script information for a profile
+    Show {
+        /// Profile to show bootstrap script for
+        #[arg(long, default_value = "default")]
+        profile: String,
+    },
+
+    /// Generate a new bootstrap script for a profile
+    Generate {
+        /// Profile to generate bootstrap script for
+        #[arg(long, default_value = "default")]
+        profile: String,
+
+        /// Force overwrite existing script
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Edit bootstrap script for a profile
+    Edit {
+        /// Profile to edit bootstrap script for
+        #[arg(long, default_value = "default")]
+        profile: String,
+    },
+
+    /// Run the bootstrap script for a profile, logging its output
+    Run {
+        /// Profile whose bootstrap script to run, or a glob such as 'work*' for each matching one
+        #[arg(long, default_value = "default")]
+        profile: String,
+
+        /// Run the bootstrap script of every profile that has one, one after another
+        #[arg(long, conflicts_with = "profile")]
+        all_profiles: bool,
+
+        /// Seconds to let the script run before stopping it
+        /// (default: bootstrap_timeout_secs, or 1800)
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
+    },
+
+    /// List past bootstrap runs on this machine, newest first
+    History {
+        /// Only show runs of this profile's script
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Show at most this many runs
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SecretCommands {
+    /// Start tracking a file for encryption
     Watch {
         /// File to start tracking for encryption
         #[arg(required = true)]
@@ -312,6 +1146,56 @@ pub enum SecretCommands {
         force: bool,
     },
 
+    /// Decrypt a tracked secret, edit it in $EDITOR, and re-encrypt it on save
+    Edit {
+        /// Tracked secret to edit
+        file: String,
+
+        /// Profile the secret is tracked in (defaults to the default profile,
+        /// then whichever profile tracks it)
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    /// Store a secret value, used by templates and 'secrets env'. The value
+    /// is asked for, or read from stdin when it's piped.
+    Set {
+        /// Profile to store it in
+        profile: String,
+
+        /// Name of the value, e.g. GITHUB_TOKEN
+        key: String,
+    },
+
+    /// Remove a secret value
+    Unset {
+        /// Profile it's stored in
+        profile: String,
+
+        /// Name of the value
+        key: String,
+    },
+
+    /// Print a profile's secret values as export lines, for
+    /// eval "$(ordinator secrets env --profile work)"
+    Env {
+        /// Profile whose values to print, with the ones it inherits (defaults
+        /// to the default profile)
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    /// Remove the decrypted secrets apply wrote to disk
+    Clean {
+        /// Only remove secrets decrypted by applies of this profile
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Overwrite each file with random data before removing it
+        #[arg(long)]
+        shred: bool,
+    },
+
     /// List encrypted files
     List {
         /// Show file paths only
@@ -328,6 +1212,14 @@ pub enum SecretCommands {
         /// Show detailed information about found secrets
         #[arg(long)]
         verbose: bool,
+
+        /// Record every current finding in the baseline so it no longer fails scans or commits
+        #[arg(long)]
+        update_baseline: bool,
+
+        /// Exit 4 instead of 1 when new findings are found
+        #[arg(long, conflicts_with = "update_baseline")]
+        check: bool,
     },
 
     /// Check for SOPS and age installation
@@ -343,49 +1235,198 @@ pub enum SecretCommands {
         #[arg(long)]
         force: bool,
     },
+
+    /// Manage the age public keys secrets are encrypted for
+    Recipients {
+        #[command(subcommand)]
+        subcommand: RecipientCommands,
+    },
+
+    /// Replace the age key and re-encrypt every secret for it
+    Rotate {
+        /// Re-encrypt the secrets for the current recipients, keeping the key
+        #[arg(long)]
+        re_encrypt_only: bool,
+
+        /// Only this profile's secrets (and, when rotating, its key)
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Keep a copy of the old key as <key>.bak
+        #[arg(long, conflicts_with = "re_encrypt_only")]
+        backup_old_key: bool,
+    },
+
+    /// Re-encrypt every secret with another backend and switch to it
+    Migrate {
+        /// Backend to move to: 'native' (built-in age) or 'sops'
+        #[arg(long)]
+        to: String,
+    },
 }
 
 #[derive(Subcommand)]
-pub enum BrewCommands {
-    /// Export current Homebrew packages to config
+pub enum RecipientCommands {
+    /// Add an age public key and re-encrypt existing secrets for it
+    Add {
+        /// Age public key (age1...)
+        pubkey: String,
+    },
+
+    /// Remove an age public key and re-encrypt existing secrets without it
+    Remove {
+        /// Age public key (age1...)
+        pubkey: String,
+    },
+
+    /// List the age public keys secrets are encrypted for
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum ExtensionsCommands {
+    /// Export an editor's installed extensions to a profile
     Export {
-        /// Profile to export packages for
+        /// Editor: vscode, cursor, or vscodium
+        #[arg(long, default_value = "vscode")]
+        tool: String,
+
+        /// Profile to export extensions to
         #[arg(long, default_value = "default")]
         profile: String,
 
-        /// Include version information
+        /// Overwrite the profile's existing list for this editor
         #[arg(long)]
-        with_versions: bool,
+        force: bool,
 
-        /// Force overwrite existing package list
+        /// Don't commit the change, even if auto_commit is enabled
         #[arg(long)]
-        force: bool,
+        no_commit: bool,
     },
 
-    /// Install Homebrew packages for a profile
+    /// Install the profile's extensions an editor doesn't have yet
     Install {
-        /// Profile to install packages for
-        #[arg(long, default_value = "default")]
-        profile: String,
-
-        /// Skip interactive prompts
+        /// Only this editor (default: every editor the profile lists)
         #[arg(long)]
-        non_interactive: bool,
+        tool: Option<String>,
 
-        /// Force installation without confirmation
-        #[arg(long)]
-        force: bool,
+        /// Profile to install extensions for
+        #[arg(long, default_value = "default")]
+        profile: String,
     },
 
-    /// List Homebrew packages for a profile
+    /// List a profile's extensions, including inherited ones
     List {
-        /// Profile to list packages for
+        /// Only this editor
+        #[arg(long)]
+        tool: Option<String>,
+
+        /// Profile to list extensions for
         #[arg(long, default_value = "default")]
         profile: String,
-
-        /// Show detailed package information
-        #[arg(long)]
-        verbose: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BrewCommands {
+    /// Export current Homebrew packages to config
+    Export {
+        /// Profile to export packages for
+        #[arg(long, default_value = "default")]
+        profile: String,
+
+        /// Include version information
+        #[arg(long)]
+        with_versions: bool,
+
+        /// Force overwrite existing package list (or Brewfile)
+        #[arg(long)]
+        force: bool,
+
+        /// Export format: toml (into the profile config) or brewfile
+        #[arg(long, default_value = "toml")]
+        format: String,
+
+        /// Output path when exporting as a Brewfile
+        #[arg(long, default_value = "Brewfile")]
+        output: String,
+
+        /// Don't commit the change, even if auto_commit is enabled
+        #[arg(long)]
+        no_commit: bool,
+    },
+
+    /// Import taps, formulas, casks, and mas apps from a Brewfile into a profile
+    Import {
+        /// Path to the Brewfile
+        file: String,
+
+        /// Profile to import packages into
+        #[arg(long, default_value = "default")]
+        profile: String,
+
+        /// Replace the profile's package lists instead of merging
+        #[arg(long)]
+        replace: bool,
+    },
+
+    /// Install Homebrew packages for a profile
+    Install {
+        /// Profile to install packages for, or a glob such as 'work*' for each matching one
+        #[arg(long, default_value = "default")]
+        profile: String,
+
+        /// Install packages for every profile, one after another
+        #[arg(long, conflicts_with = "profile")]
+        all_profiles: bool,
+
+        /// Skip interactive prompts
+        #[arg(long)]
+        non_interactive: bool,
+
+        /// Force installation without confirmation
+        #[arg(long)]
+        force: bool,
+
+        /// Exit with an error if any package fails to install
+        #[arg(long)]
+        strict: bool,
+    },
+
+    /// Compare installed formulas and casks with a profile
+    Diff {
+        /// Profile to compare against
+        #[arg(long, default_value = "default")]
+        profile: String,
+    },
+
+    /// List the formulas and casks a profile declares that have newer versions
+    Outdated {
+        /// Profile whose packages to check
+        #[arg(long, default_value = "default")]
+        profile: String,
+    },
+
+    /// Install what a profile is missing, optionally uninstalling extras
+    Sync {
+        /// Profile to converge to
+        #[arg(long, default_value = "default")]
+        profile: String,
+
+        /// Uninstall formulas and casks the profile doesn't list
+        #[arg(long)]
+        remove_extra: bool,
+    },
+
+    /// List Homebrew packages for a profile
+    List {
+        /// Profile to list packages for
+        #[arg(long, default_value = "default")]
+        profile: String,
+
+        /// Show detailed package information
+        #[arg(long)]
+        verbose: bool,
     },
 }
 
@@ -430,10 +1471,14 @@ pub enum AgeCommands {
 
     /// Set up age encryption for a profile
     Setup {
-        /// Profile to set up (default: "default")
+        /// Profile to set up (default: "default"), or a glob such as 'work*' for each matching one
         #[arg(long, default_value = "default")]
         profile: String,
 
+        /// Set up every profile, one after another
+        #[arg(long, conflicts_with = "profile")]
+        all_profiles: bool,
+
         /// Force overwrite existing configuration
         #[arg(long)]
         force: bool,
@@ -468,162 +1513,1284 @@ pub enum AgeCommands {
         #[arg(long)]
         dry_run: bool,
     },
+
+    /// Import, export, or move the age private key
+    Key {
+        #[command(subcommand)]
+        subcommand: AgeKeyCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SshCommands {
+    /// Track ~/.ssh/config as a template and store private keys as encrypted secrets
+    Track {
+        /// Profile to track them in
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Only track ~/.ssh/config
+        #[arg(long)]
+        no_keys: bool,
+    },
+
+    /// Generate a new SSH key, store it as an encrypted secret, and print its public key
+    Keygen {
+        /// Profile the key is for
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Key type passed to ssh-keygen
+        #[arg(long = "type", default_value = "ed25519")]
+        key_type: String,
+
+        /// Key comment (default: ssh-keygen's user@host)
+        #[arg(long)]
+        comment: Option<String>,
+
+        /// Key file name in ~/.ssh (default: id_<type>, or id_<type>_<profile> for other profiles)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Do nothing if the key already exists (for bootstrap scripts)
+        #[arg(long, conflicts_with = "force")]
+        if_missing: bool,
+
+        /// Replace an existing key
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Print a public key, for adding to GitHub
+    Pubkey {
+        /// Profile whose default key to print
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Key file name in ~/.ssh
+        #[arg(long)]
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum GpgCommands {
+    /// Track gpg.conf and gpg-agent.conf, and store private keys as encrypted secrets
+    Track {
+        /// Profile to track them in
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Secret key to export and store, by id, fingerprint, or email (repeatable)
+        #[arg(long = "key", value_name = "KEY")]
+        keys: Vec<String>,
+
+        /// Key git signs commits and tags with once the profile is applied
+        #[arg(long, value_name = "KEY")]
+        signing_key: Option<String>,
+
+        /// Don't track gpg.conf and gpg-agent.conf
+        #[arg(long)]
+        no_conf: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AuthCommands {
+    /// Connect to a remote with the available SSH agent, keys, or token
+    Test {
+        /// Remote URL (default: the dotfiles repository's origin)
+        url: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum LaunchdCommands {
+    /// Track a launch agent plist, e.g. one in ~/Library/LaunchAgents
+    Track {
+        /// The plist to track
+        plist: PathBuf,
+
+        /// Profile to track it in
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    /// Stop tracking a launch agent, and unload and remove it from this machine
+    Untrack {
+        /// Plist file name, e.g. com.example.backup.plist
+        name: String,
+
+        /// Profile it's tracked in
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    /// List tracked launch agents and whether they're installed here
+    List {
+        /// Only list this profile's agents
+        #[arg(long)]
+        profile: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AgeKeyCommands {
+    /// Import an age private key from a file
+    Import {
+        /// Key file to import (`-` reads standard input)
+        file: PathBuf,
+
+        /// Profile the key is for (default: "default")
+        #[arg(long, default_value = "default")]
+        profile: String,
+
+        /// Where to keep the key: file, passphrase, or keychain (default: the configured storage)
+        #[arg(long)]
+        storage: Option<String>,
+
+        /// Replace a key that is already stored
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Print the age private key, or write it to a file
+    Export {
+        /// File to write the key to (mode 0600) instead of standard output
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Move the age private key into the macOS Keychain
+    Keychain,
+
+    /// Encrypt the age private key file with a passphrase
+    Passphrase,
+}
+
+/// `profile`, or the one the user picks; it must exist
+fn existing_profile(config: &Config, profile: Option<String>) -> Result<String> {
+    let profile_name = match profile {
+        Some(p) => p,
+        None => prompt_for_profile(&config.list_profiles(), &config.global.default_profile),
+    };
+    if !config.profiles.contains_key(&profile_name) {
+        return Err(OrdinatorError::ProfileNotFound(profile_name.to_string()).into());
+    }
+    Ok(profile_name)
+}
+
+/// The profiles `--profile` (a name, or a glob such as `work*`) and
+/// `--all-profiles` select, in the order commands run for them
+fn selected_profiles(config: &Config, profile: &str, all_profiles: bool) -> Result<Vec<String>> {
+    config.select_profiles(&[profile.to_string()], all_profiles)
 }
 
-fn check_file_conflicts(config: &Config, file_path: &str, target_profile: &str) -> Vec<String> {
-    let mut conflicts = Vec::new();
-    for (profile_name, profile_config) in &config.profiles {
-        if profile_name != target_profile && profile_config.files.contains(&file_path.to_string()) {
-            conflicts.push(profile_name.clone());
+/// After a command ran for several profiles: how it went for each
+fn print_profile_summary(results: &[(String, std::result::Result<String, String>)]) {
+    eprintln!("\nSummary:");
+    for (profile, result) in results {
+        match result {
+            Ok(outcome) => eprintln!("  ✅ {profile}: {outcome}"),
+            Err(error) => eprintln!("  ❌ {profile}: {error}"),
         }
     }
-    conflicts
 }
 
-fn prompt_for_conflict_resolution(
-    file_path: &str,
-    conflicts: &[String],
-    _target_profile: &str,
-) -> bool {
-    if conflicts.is_empty() {
-        return true;
+/// Run `profile`'s bootstrap script, logging its output under `logs/bootstrap`
+fn run_bootstrap(
+    config: &Config,
+    dotfiles_dir: &Path,
+    profile: &str,
+    timeout: Option<u64>,
+    dry_run: bool,
+) -> Result<()> {
+    use crate::bootstrap::{BootstrapManager, SafetyLevel, DEFAULT_TIMEOUT_SECS};
+
+    let script_path = config.get_bootstrap_script(profile).ok_or_else(|| {
+        anyhow::anyhow!(
+            "No bootstrap script configured for profile '{profile}'. To generate one, run: ordinator bootstrap generate --profile {profile}"
+        )
+    })?;
+    let full_script_path = dotfiles_dir.join(&script_path);
+    let timeout = timeout
+        .or(config.global.bootstrap_timeout_secs)
+        .unwrap_or(DEFAULT_TIMEOUT_SECS);
+    if timeout == 0 {
+        return Err(anyhow::anyhow!("Timeout must be at least 1 second."));
     }
 
-    eprintln!("⚠️  Warning: File '{file_path}' already exists in other profiles:");
-    for conflict in conflicts {
-        eprintln!("   - {conflict}");
+    let bootstrap_manager = BootstrapManager::new(dry_run);
+    match bootstrap_manager.get_script_safety_level(&full_script_path) {
+        SafetyLevel::Dangerous => {
+            eprintln!("⚠️  Script is DANGEROUS: Contains commands like 'sudo'.");
+        }
+        SafetyLevel::Warning => {
+            eprintln!("⚠️  Script contains potentially risky commands.");
+        }
+        SafetyLevel::Safe | SafetyLevel::Blocked => {}
+    }
+    if dry_run {
+        eprintln!(
+            "DRY-RUN: Would run {} for profile '{profile}' (timeout {timeout}s)",
+            full_script_path.display()
+        );
+        return Ok(());
     }
-    eprintln!("   This will create separate copies for each profile.");
 
-    if io::stdin().is_terminal() {
-        eprint!("Continue? [y/N]: ");
-        io::stdout().flush().unwrap();
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        let input = input.trim().to_lowercase();
-        input == "y" || input == "yes"
+    eprintln!(
+        "Running bootstrap script {} (timeout {timeout}s)",
+        full_script_path.display()
+    );
+    let content = std::fs::read(&full_script_path).unwrap_or_default();
+    let receipt = crate::receipts::Receipt::new(profile, &script_path.to_string_lossy(), &content);
+    let outcome = bootstrap_manager.run_logged(
+        &full_script_path,
+        profile,
+        std::time::Duration::from_secs(timeout),
+        &dotfiles_dir.join("logs").join("bootstrap"),
+    )?;
+    let receipt = receipt.finish(&outcome);
+    if let Err(e) = crate::receipts::record(&config.target_root()?, &receipt) {
+        eprintln!("⚠️  Warning: Failed to record the bootstrap receipt: {e}");
+    }
+    if outcome.timed_out {
+        return Err(anyhow::anyhow!(
+            "Bootstrap script timed out after {timeout}s and was stopped. Log: {}",
+            outcome.log_path.display()
+        ));
+    }
+    if !outcome.success() {
+        return Err(anyhow::anyhow!(
+            "Bootstrap script failed with exit code {}. Log: {}",
+            outcome
+                .exit_code
+                .map_or("unknown".to_string(), |code| code.to_string()),
+            outcome.log_path.display()
+        ));
+    }
+    let msg = format!(
+        "✅ Bootstrap script finished in {}s. Log: {}",
+        outcome.duration.as_secs(),
+        outcome.log_path.display()
+    );
+    if color_enabled() {
+        eprintln!("{}", msg.green());
     } else {
-        eprintln!("[WARN] Non-interactive mode. Proceeding with separate copies.");
-        true
+        eprintln!("{msg}");
+    }
+    Ok(())
+}
+
+/// Record the mode apply gives a tracked file, e.g. `"600"` for SSH keys
+fn set_tracked_mode(config: &mut Config, profile_name: &str, tracked_path: &str, mode: &str) {
+    if let Some(profile) = config.get_profile_mut(profile_name) {
+        profile
+            .file_modes
+            .insert(tracked_path.to_string(), mode.to_string());
+    }
+}
+
+/// Re-store the configured age key as `storage` and record that in ordinator.toml
+fn move_age_key(storage: crate::age_key::KeyStorage, dry_run: bool) -> Result<()> {
+    let (mut config, config_path) = Config::load()?;
+    let key_file = configured_age_key(&config)?;
+    let current = config.secrets.age_key_storage;
+    if current == storage {
+        println!("The age key is already kept in {}.", storage.description());
+        return Ok(());
+    }
+    if dry_run {
+        println!(
+            "DRY-RUN: Would move the age key from {} to {}",
+            current.description(),
+            storage.description()
+        );
+        return Ok(());
+    }
+    let key = crate::age_key::read(&key_file, current)?;
+    crate::age_key::store(&key_file, storage, &key)?;
+    config.secrets.age_key_storage = storage;
+    config.save_to_file(&config_path)?;
+    println!(
+        "✅ Moved the age key {} to {}",
+        key_file.display(),
+        storage.description()
+    );
+    Ok(())
+}
+
+fn configured_age_key(config: &Config) -> Result<PathBuf> {
+    config
+        .secrets
+        .age_key_file
+        .clone()
+        .ok_or_else(|| OrdinatorError::NoAgeKey.into())
+}
+
+fn confirm_profile_removal(profile: &str, tracked_count: usize) -> bool {
+    eprintln!("⚠️  Warning: Profile '{profile}' has {tracked_count} tracked file(s).");
+    eprintln!("   Removing it deletes the profile's stored files from the dotfiles repository.");
+    eprintln!("   Run 'ordinator uninstall --profile {profile}' first to remove its symlinks.");
+
+    if !crate::prompt::assume_yes() && !crate::prompt::interactive() {
+        eprintln!(
+            "[WARN] Non-interactive mode. Use --force or --yes to remove a profile with tracked files."
+        );
+    }
+    crate::prompt::confirm(&format!("Remove profile '{profile}'?"), false)
+}
+
+/// The profile to use when `--profile` is omitted: the one whose match rules
+/// fit this machine, else `default_profile`
+/// Print scan findings grouped by file, with line numbers when `verbose`
+/// The pre-commit summary: each staged path, then counts and total size
+fn print_staged_changes(staged: &crate::git::StagedChanges) {
+    eprintln!("Staged changes:");
+    for (label, paths) in [
+        ("added", &staged.added),
+        ("modified", &staged.modified),
+        ("deleted", &staged.deleted),
+    ] {
+        for path in paths {
+            eprintln!("  {label:<9}{path}");
+        }
+    }
+    eprintln!(
+        "{} added, {} modified, {} deleted ({})",
+        staged.added.len(),
+        staged.modified.len(),
+        staged.deleted.len(),
+        crate::utils::format_size(staged.bytes)
+    );
+}
+
+fn print_scan_findings(findings: &[crate::scan::Finding], verbose: bool) {
+    let mut current_file = None;
+    for finding in findings {
+        if current_file != Some(&finding.file) {
+            current_file = Some(&finding.file);
+            if verbose {
+                eprintln!("⚠️  Potential secrets found in: {}", finding.file);
+            } else {
+                eprintln!("⚠️  {}", finding.file);
+            }
+        }
+        if verbose {
+            eprintln!("   line {}: {}", finding.line, finding.rule);
+        }
+    }
+}
+
+/// The `[machines]` role called `name`
+fn machine_role<'a>(config: &'a Config, name: &str) -> Result<&'a crate::machine::MachineRole> {
+    config.machines.get(name).ok_or_else(|| {
+        OrdinatorError::MachineRoleNotFound {
+            name: name.to_string(),
+            known: config.machines.keys().cloned().collect(),
+        }
+        .into()
+    })
+}
+
+fn auto_select_profile(config: &Config, quiet: bool) -> String {
+    let machine = crate::machine::MachineInfo::detect();
+    match crate::machine::matching_profile(config, &machine) {
+        Some(name) => {
+            if !quiet {
+                eprintln!("Using profile '{name}' (its match rules fit this machine)");
+            }
+            name.to_string()
+        }
+        None => config.global.default_profile.clone(),
+    }
+}
+
+/// Print this machine's details and how each profile's match rules fare
+fn explain_profile_selection(config: &Config) {
+    let machine = crate::machine::MachineInfo::detect();
+    println!("This machine:");
+    println!(
+        "  hostname: {}",
+        machine.hostname.as_deref().unwrap_or("unknown")
+    );
+    println!(
+        "  username: {}",
+        machine.username.as_deref().unwrap_or("unknown")
+    );
+    println!("  arch: {}", machine.arch);
+    println!(
+        "  macos_version: {}",
+        machine.macos_version.as_deref().unwrap_or("unknown")
+    );
+
+    let mut names: Vec<&String> = config.profiles.keys().collect();
+    names.sort();
+    for name in names {
+        let profile = &config.profiles[name];
+        if profile.match_rules.is_empty() {
+            continue;
+        }
+        let status = if !profile.enabled {
+            "disabled"
+        } else if profile.match_rules.matches(&machine) {
+            "matches"
+        } else {
+            "does not match"
+        };
+        println!("Profile '{name}' ({status}):");
+        for result in profile.match_rules.evaluate(&machine) {
+            println!("  {result}");
+        }
+    }
+
+    match crate::machine::matching_profile(config, &machine) {
+        Some(name) => println!("Apply without --profile uses '{name}'"),
+        None => println!(
+            "No profile's match rules fit; apply without --profile uses default_profile '{}'",
+            config.global.default_profile
+        ),
     }
 }
 
 fn prompt_for_profile(profiles: &[&String], default_profile: &str) -> String {
     if profiles.is_empty() {
         eprintln!("No profiles are defined. Please add a profile first.");
-        std::process::exit(1);
+        crate::run_log::exit(1);
     }
     if profiles.len() == 1 {
         return profiles[0].clone();
     }
-    // Check if stdin is a tty
-    if io::stdin().is_terminal() {
-        eprintln!("Select a profile to add this file to:");
-        for (i, profile) in profiles.iter().enumerate() {
-            eprintln!("  {}. {profile}", i + 1);
-        }
-        eprint!("Enter number (default: {default_profile}): ");
-        io::stdout().flush().unwrap();
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        let input = input.trim();
-        if input.is_empty() {
-            return default_profile.to_string();
-        }
-        if let Ok(idx) = input.parse::<usize>() {
-            if idx > 0 && idx <= profiles.len() {
-                return profiles[idx - 1].clone();
-            }
+    let mut options: Vec<String> = profiles.iter().map(|name| name.to_string()).collect();
+    let default = match options.iter().position(|name| name == default_profile) {
+        Some(index) => index,
+        None => {
+            options.push(default_profile.to_string());
+            options.len() - 1
         }
-        eprintln!("Invalid selection. Using default profile: {default_profile}");
-        default_profile.to_string()
-    } else {
+    };
+    if !crate::prompt::interactive() {
         eprintln!("[WARN] No profile specified and not running interactively. Using default profile: {default_profile}");
-        default_profile.to_string()
     }
+    let index = crate::prompt::choose("Select a profile to add this file to:", &options, default);
+    options.swap_remove(index)
 }
 
-fn color_enabled() -> bool {
-    io::stdout().is_terminal()
+/// Warn when a file being imported is the rendered output of a tracked template
+fn warn_if_rendered_template(file_path: &str, tracked: &[String]) {
+    let template = format!("{file_path}{}", crate::template::TEMPLATE_SUFFIX);
+    if tracked.contains(&template) {
+        eprintln!("⚠️  Warning: '{file_path}' is rendered from template '{template}'.");
+        eprintln!("   Importing it stores this machine's values; edit the template instead.");
+    }
 }
 
-fn handle_missing_source_file(
-    _file: &str,
-    source_path: &std::path::Path,
-    dest: &std::path::Path,
-) -> anyhow::Result<()> {
-    let msg = format!("Source file not found: {}", source_path.display());
+/// Print a one-line summary of a directory sync, plus each file with --verbose
+fn print_sync_report(path: &str, report: &crate::directory::SyncReport, verbose: bool) {
+    if report.is_empty() {
+        println!("'{path}' is up to date");
+        return;
+    }
+    let msg = format!(
+        "Synced '{path}': {} added, {} updated, {} removed",
+        report.added.len(),
+        report.updated.len(),
+        report.removed.len()
+    );
     if color_enabled() {
-        eprintln!("{msg}", msg = msg.red());
-        eprintln!(
-            "{}",
-            "This file may have been moved or deleted from the dotfiles repository.".yellow()
-        );
-        eprintln!("Expected location: {}", source_path.display());
-        eprintln!("Target location: {}", dest.display());
-        eprintln!(
-            "{}",
-            "Run 'ordinator add <file> --profile <profile>' to re-add the file.".yellow()
-        );
+        println!("{}", msg.green());
     } else {
-        eprintln!("{msg}");
-        eprintln!("This file may have been moved or deleted from the dotfiles repository.");
-        eprintln!("Expected location: {}", source_path.display());
-        eprintln!("Target location: {}", dest.display());
-        eprintln!("Run 'ordinator add <file> --profile <profile>' to re-add the file.");
-    }
-    Err(anyhow::anyhow!(
-        "Source file not found: {}",
-        source_path.display()
-    ))
+        println!("{msg}");
+    }
+    if verbose {
+        for file in &report.added {
+            println!("  + {}", file.display());
+        }
+        for file in &report.updated {
+            println!("  ~ {}", file.display());
+        }
+        for file in &report.removed {
+            println!("  - {}", file.display());
+        }
+    }
 }
 
-pub async fn run(args: Args) -> Result<()> {
-    eprintln!("[DEBUG] args.verbose: {}", args.verbose);
-    eprintln!(
-        "[DEBUG] std::env::args: {:?}",
-        std::env::args().collect::<Vec<_>>()
-    );
-    // Setup logging based on verbose flag
-    let log_level = if args.verbose {
-        tracing::Level::DEBUG
-    } else {
-        tracing::Level::INFO
-    };
-
-    // Only initialize if not already initialized
-    let _ = tracing_subscriber::fmt()
-        .with_max_level(log_level)
-        .try_init();
-
-    info!("Starting Ordinator");
-
-    if args.dry_run {
-        warn!("Running in DRY-RUN mode - no changes will be made");
-        eprintln!("DRY-RUN: No changes will be made");
+/// Warn about files the secrets scan gave up on
+fn warn_scan_timeouts(report: &crate::scan::ScanReport) {
+    for file in &report.timed_out {
+        eprintln!(
+            "⚠️  Warning: Not scanned for secrets: {file} took longer than [secrets.scan] timeout"
+        );
     }
+}
 
-    match args.command {
-        Commands::Init {
+/// `[global] auto_commit`: commit everything a command changed in the
+/// repository, then push when `auto_push` is set too. Failing `pre_commit`
+/// hooks or a failed push only warn; the command's change is already made.
+async fn auto_commit(
+    config: &Config,
+    config_path: &std::path::Path,
+    quiet: bool,
+    no_commit: bool,
+    message: &str,
+) -> Result<()> {
+    if no_commit || !config.global.auto_commit {
+        return Ok(());
+    }
+    let dotfiles_path = config_path.parent().unwrap().to_path_buf();
+    crate::lock::refresh(config, &dotfiles_path)?;
+    let git_manager = GitManager::new(dotfiles_path.clone()).with_config(config.git.clone());
+    if !git_manager.exists() {
+        return Ok(());
+    }
+    match crate::commit::commit_all(config, &git_manager, &dotfiles_path, message, false).await {
+        Ok(crate::commit::CommitOutcome::Clean) => return Ok(()),
+        Ok(crate::commit::CommitOutcome::HeldBack(scan)) => {
+            warn_scan_timeouts(&scan);
+            if scan.findings.is_empty() {
+                eprintln!("⚠️  Warning: Not committing automatically: some tracked files couldn't be scanned for secrets");
+            } else {
+                eprintln!("⚠️  Warning: Not committing automatically: plaintext secrets detected in tracked files");
+            }
+            eprintln!("   Review them with 'ordinator secrets scan', then run 'ordinator commit'");
+            return Ok(());
+        }
+        Ok(crate::commit::CommitOutcome::Committed) => {}
+        Err(e) => {
+            eprintln!("⚠️  Warning: Not committing automatically: {e:#}");
+            return Ok(());
+        }
+    }
+    if !quiet {
+        eprintln!("Committed: {message}");
+    }
+
+    if !config.global.auto_push || git_manager.get_origin_url()?.is_none() {
+        return Ok(());
+    }
+    let all_remotes = config.git.push_all_remotes;
+    match crate::push::push(
+        config,
+        &git_manager,
+        &dotfiles_path,
+        all_remotes,
+        false,
+        None,
+        quiet,
+    ) {
+        Ok(()) if !all_remotes && !quiet => eprintln!("Pushed to 'origin'"),
+        Ok(()) => {}
+        Err(e) => eprintln!("⚠️  Warning: {e:#}"),
+    }
+    Ok(())
+}
+
+/// The value for `secrets set`: asked for without echoing it, or the first
+/// line of stdin when it's piped
+fn read_secret_value(key: &str) -> Result<String> {
+    use is_terminal::IsTerminal;
+
+    if let Some(value) = crate::prompt::secret(&format!("Value for {key}")) {
+        return Ok(value);
+    }
+    let mut value = String::new();
+    if std::io::stdin().is_terminal() || std::io::stdin().read_line(&mut value)? == 0 {
+        return Err(anyhow::anyhow!(
+            "No value given for '{key}'. Pipe it on stdin, e.g. pbpaste | ordinator secrets set <profile> {key}"
+        ));
+    }
+    Ok(value.trim_end_matches(['\n', '\r']).to_string())
+}
+
+/// `repair --prune`: drop entries whose stored copy is gone and, with
+/// `orphans`, delete stored files nothing tracks. Asks before each change.
+fn prune_profiles(
+    config: &mut Config,
+    config_path: &std::path::Path,
+    profiles: &[String],
+    orphans: bool,
+    dry_run: bool,
+    quiet: bool,
+) -> Result<()> {
+    let mut pruned = 0;
+    let mut deleted = 0;
+    for profile_name in profiles {
+        let missing = config.missing_sources(profile_name)?;
+        if !missing.is_empty() {
+            for path in &missing {
+                eprintln!("Source missing: {path} (profile '{profile_name}')");
+            }
+            if dry_run {
+                eprintln!(
+                    "DRY-RUN: Would remove {} tracked path(s) from profile '{profile_name}'",
+                    missing.len()
+                );
+            } else if crate::prompt::confirm(
+                &format!(
+                    "Remove {} tracked path(s) with missing sources from profile '{profile_name}'?",
+                    missing.len()
+                ),
+                false,
+            ) {
+                for path in &missing {
+                    config.prune_entry(profile_name, path)?;
+                }
+                pruned += missing.len();
+            } else {
+                eprintln!("Skipped pruning profile '{profile_name}'");
+            }
+        }
+
+        if !orphans {
+            continue;
+        }
+        let orphaned = config.orphaned_files(profile_name)?;
+        if orphaned.is_empty() {
+            continue;
+        }
+        for path in &orphaned {
+            eprintln!("Orphaned: {}", path.display());
+        }
+        if dry_run {
+            eprintln!(
+                "DRY-RUN: Would delete {} orphaned file(s) of profile '{profile_name}'",
+                orphaned.len()
+            );
+        } else if crate::prompt::confirm(
+            &format!(
+                "Delete {} orphaned file(s) of profile '{profile_name}'?",
+                orphaned.len()
+            ),
+            false,
+        ) {
+            for path in &orphaned {
+                std::fs::remove_file(path)
+                    .map_err(|e| anyhow::anyhow!("Failed to delete {}: {}", path.display(), e))?;
+            }
+            deleted += orphaned.len();
+        } else {
+            eprintln!("Skipped deleting orphaned files of profile '{profile_name}'");
+        }
+    }
+
+    if pruned > 0 {
+        config.save_to_file(config_path)?;
+    }
+    if !quiet && !dry_run {
+        eprintln!("Pruned {pruned} tracked path(s), deleted {deleted} orphaned file(s)");
+    }
+    Ok(())
+}
+
+/// Print each remote and how far the checked-out branch is from it
+fn report_remotes(git_manager: &GitManager) -> Result<()> {
+    let remotes: Vec<_> = git_manager
+        .remote_statuses()?
+        .into_iter()
+        .filter(|remote| remote.url.is_some())
+        .collect();
+    if remotes.is_empty() {
+        return Ok(());
+    }
+    eprintln!("\nRemotes:");
+    let name_width = remotes
+        .iter()
+        .map(|remote| remote.name.chars().count() + 1)
+        .max()
+        .unwrap_or(0);
+    let url_width = remotes
+        .iter()
+        .map(|remote| remote.url.as_deref().unwrap_or_default().chars().count())
+        .max()
+        .unwrap_or(0);
+    for remote in remotes {
+        let (state, tone) = ahead_behind_state(remote.ahead_behind);
+        eprintln!(
+            "  {:name_width$} {:url_width$} ({}{})",
+            format!("{}:", remote.name),
+            remote.url.as_deref().unwrap_or_default(),
+            paint(&state, tone),
+            if remote.force { ", force-pushed" } else { "" }
+        );
+    }
+    Ok(())
+}
+
+/// How a branch compares with a remote's copy of it, for `status`
+fn ahead_behind_state(ahead_behind: Option<(usize, usize)>) -> (String, Tone) {
+    match ahead_behind {
+        Some((0, 0)) => ("up to date".to_string(), Tone::Good),
+        Some((ahead, 0)) => (format!("{ahead} ahead"), Tone::Drift),
+        Some((0, behind)) => (format!("{behind} behind"), Tone::Drift),
+        Some((ahead, behind)) => (format!("{ahead} ahead, {behind} behind"), Tone::Broken),
+        None => ("not pushed yet".to_string(), Tone::Drift),
+    }
+}
+
+/// `status --remote`: the checked-out branch against origin, when this
+/// machine last pulled and pushed, and whether `ordinator.toml` differs from
+/// origin's copy. Without `fetch`, origin is as the last fetch or push saw it.
+fn report_remote_sync(
+    git_manager: &GitManager,
+    state: &crate::state::MachineState,
+    fetch: bool,
+    quiet: bool,
+) -> Result<()> {
+    use crate::config_merge::CONFIG_FILE;
+    if !git_manager.exists() {
+        return Err(OrdinatorError::RepositoryNotFound.into());
+    }
+    if fetch {
+        if !quiet {
+            eprintln!("Fetching from origin...");
+        }
+        git_manager.fetch(None)?;
+    }
+
+    let branch = git_manager.resolve_branch(None);
+    let origin = git_manager
+        .remote_statuses()?
+        .into_iter()
+        .find(|remote| remote.name == "origin");
+    let ahead_behind = origin.as_ref().and_then(|origin| origin.ahead_behind);
+    let url = match origin.and_then(|origin| origin.url) {
+        Some(url) => Some(url),
+        None => git_manager.get_origin_url()?,
+    };
+    eprintln!("Branch: {branch}");
+    match url {
+        Some(url) => {
+            let (sync, tone) = ahead_behind_state(ahead_behind);
+            let as_of = if fetch { "" } else { ", as of the last fetch" };
+            eprintln!("Origin: {url} ({}{as_of})", paint(&sync, tone));
+        }
+        None => eprintln!("Origin: not set (run 'ordinator push <url>')"),
+    }
+    let last = |time: &Option<String>| {
+        time.as_deref()
+            .map(crate::utils::format_timestamp)
+            .unwrap_or_else(|| "never".to_string())
+    };
+    eprintln!("Last pull: {}", last(&state.last_pull));
+    eprintln!("Last push: {}", last(&state.last_push));
+    match git_manager.config_differs_from_remote()? {
+        Some(true) => eprintln!(
+            "{CONFIG_FILE}: {}",
+            paint(&format!("differs from origin/{branch}"), Tone::Drift)
+        ),
+        Some(false) => eprintln!(
+            "{CONFIG_FILE}: {}",
+            paint(&format!("same as origin/{branch}"), Tone::Good)
+        ),
+        None => eprintln!("{CONFIG_FILE}: no copy on origin to compare with"),
+    }
+    if let Some((_, behind)) = ahead_behind.filter(|(_, behind)| *behind > 0) {
+        eprintln!(
+            "⚠️  Warning: This machine is {behind} commit(s) behind origin/{branch}. Run: ordinator pull"
+        );
+    }
+    Ok(())
+}
+
+/// Color semantics shared by `status` and `profiles`
+#[derive(Debug, Clone, Copy)]
+enum Tone {
+    /// In sync, valid, up to date
+    Good,
+
+    /// Drifted or pending, fixed by `add`, `apply`, or `push`
+    Drift,
+
+    /// Broken or conflicting, needs a decision
+    Broken,
+}
+
+/// Color `text` by tone when color is enabled
+fn paint(text: &str, tone: Tone) -> String {
+    if !color_enabled() {
+        return text.to_string();
+    }
+    match tone {
+        Tone::Good => text.green().to_string(),
+        Tone::Drift => text.yellow().to_string(),
+        Tone::Broken => text.red().to_string(),
+    }
+}
+
+fn drift_tone(drift: crate::drift::Drift) -> Tone {
+    use crate::drift::Drift;
+    match drift {
+        Drift::InSync => Tone::Good,
+        Drift::ModifiedBoth | Drift::MissingInRepo => Tone::Broken,
+        _ => Tone::Drift,
+    }
+}
+
+/// Print what `ordinator why` found about one tracked file or secret
+fn print_provenance(provenance: &crate::why::Provenance) {
+    use crate::utils::format_timestamp;
+
+    let entry = &provenance.entry;
+    let profile = match provenance.used_by.as_slice() {
+        [only] if *only == entry.profile => entry.profile.clone(),
+        used_by => format!("{} (applied through {})", entry.profile, used_by.join(", ")),
+    };
+    let kind = match entry.secret {
+        Some(crate::listing::SecretState::Encrypted) => "secret, encrypted",
+        Some(crate::listing::SecretState::NotEncrypted) => "secret, not encrypted",
+        None if provenance.options.encrypt => "file, stored encrypted",
+        None if provenance.options.managed_block => "managed block",
+        None if provenance.template => "template",
+        None => "file",
+    };
+    let stored = if entry.in_repo {
+        entry.repo_path.display().to_string()
+    } else {
+        format!("{} (missing)", entry.repo_path.display())
+    };
+    let mut rows = vec![
+        ("Profile", profile),
+        ("Tracked as", format!("{} ({kind})", entry.path)),
+        ("Stored at", stored),
+    ];
+    if let Some(mapping) = &provenance.mapping {
+        rows.push(("Mapping", format!("{mapping} -> {}", entry.path)));
+    }
+    let mut options = Vec::new();
+    if provenance.options.requires_sudo {
+        options.push("requires sudo".to_string());
+    }
+    if let Some(mode) = &provenance.options.permissions {
+        options.push(format!("permissions {mode}"));
+    }
+    if !options.is_empty() {
+        rows.push(("Options", options.join(", ")));
+    }
+    rows.push((
+        "Last added",
+        entry
+            .added_at
+            .as_deref()
+            .map(format_timestamp)
+            .unwrap_or_else(|| "not recorded on this machine".to_string()),
+    ));
+    let applied: Vec<String> = provenance
+        .applied_at
+        .iter()
+        .map(|(profile, at)| format!("{} ({profile})", format_timestamp(at)))
+        .collect();
+    rows.push((
+        "Last applied",
+        if applied.is_empty() {
+            "never on this machine".to_string()
+        } else {
+            applied.join(", ")
+        },
+    ));
+    let state = match provenance.drift {
+        Some(drift) => format!(
+            "{}, {}",
+            entry.state.label(),
+            paint(drift.label(), drift_tone(drift))
+        ),
+        None => entry.state.label().to_string(),
+    };
+    rows.push(("State", state));
+
+    let width = rows
+        .iter()
+        .map(|(label, _)| label.len() + 1)
+        .max()
+        .unwrap_or(0);
+    for (label, value) in rows {
+        println!("  {:width$} {value}", format!("{label}:"));
+    }
+    let hint = match provenance.drift.and_then(|drift| drift.hint()) {
+        Some("ordinator add") if provenance.template => format!("edit {} instead", entry.path),
+        Some("ordinator add") => format!(
+            "run 'ordinator add {} --profile {}'",
+            entry.path, entry.profile
+        ),
+        Some(hint) => format!("run '{hint} --profile {}'", provenance.used_by[0]),
+        None => return,
+    };
+    println!("  To fix: {hint}");
+}
+
+/// Print `(path, state, tone, note)` rows as `path: state note`, states aligned
+fn print_status_rows(indent: &str, rows: &[(String, String, Tone, String)]) {
+    let width = rows
+        .iter()
+        .map(|(path, ..)| path.chars().count() + 1)
+        .max()
+        .unwrap_or(0);
+    for (path, state, tone, note) in rows {
+        eprintln!(
+            "{indent}{:width$} {}{note}",
+            format!("{path}:"),
+            paint(state, *tone)
+        );
+    }
+}
+
+/// Print how each deployed file of a profile compares with its tracked copy,
+/// or with `short`, one `git status -s` style line per drifted file on stdout
+fn report_drift(
+    config: &Config,
+    profile: &str,
+    dotfiles_dir: &std::path::Path,
+    filter: &crate::filter::PathFilter,
+    verbose: bool,
+    short: bool,
+) -> Result<Vec<(crate::drift::Drift, usize)>> {
+    use crate::drift::{classify, hash_bytes, Drift, HashCache};
+    use crate::template::{is_template, render_file, rendered_path};
+
+    let home_dir = config.target_root()?;
+    let mut resolved = config.resolve_profile(profile)?;
+    if resolved.files.iter().any(|entry| is_template(&entry.path)) {
+        let sources = config.secret_value_profiles(&[], &[], &resolved.chain)?;
+        crate::secrets::add_secret_values(dotfiles_dir, &sources, &mut resolved.vars)?;
+    }
+    let mut cache = HashCache::load(dotfiles_dir);
+    let mut counts: Vec<(Drift, usize)> = Vec::new();
+    let mut rows = Vec::new();
+
+    if !short {
+        eprintln!("\nFile drift (profile '{profile}'):");
+    }
+    for (file, source_profile, source) in config.link_targets(&resolved, true)? {
+        if !filter.matches(&file) {
+            continue;
+        }
+        let live = crate::paths::destination(&home_dir, rendered_path(&file));
+        let drift = if !source.exists() {
+            Drift::MissingInRepo
+        } else if !live.exists() {
+            Drift::MissingLocally
+        } else {
+            // Templates are compared by their rendered output, encrypted files by
+            // their plaintext, and managed blocks by the block alone
+            let managed = config.file_options(&source_profile, &file).managed_block;
+            let written = if config.file_options(&source_profile, &file).encrypt {
+                Some(crate::secrets::decrypt_tracked_file(&source))
+            } else if is_template(&file) {
+                Some(render_file(&source, &resolved.vars).map(String::into_bytes))
+            } else if managed {
+                Some(std::fs::read(&source).map_err(Into::into))
+            } else {
+                None
+            };
+            let repo_hash = if let Some(written) = written {
+                match written {
+                    Ok(content) if managed => crate::block::hash(&content),
+                    Ok(content) => hash_bytes(&content),
+                    Err(e) => {
+                        if !short {
+                            rows.push((file.clone(), e.to_string(), Tone::Broken, String::new()));
+                        }
+                        continue;
+                    }
+                }
+            } else {
+                cache.hash_file(&source)?
+            };
+            let live_hash = if managed {
+                crate::block::live_hash(&live)?
+            } else {
+                cache.hash_file(&live)?
+            };
+            classify(
+                &live_hash,
+                &repo_hash,
+                cache.synced_hash(&source_profile, &file),
+            )
+        };
+
+        match counts.iter_mut().find(|(d, _)| *d == drift) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((drift, 1)),
+        }
+        if drift == Drift::InSync && !verbose {
+            continue;
+        }
+        let display = rendered_path(&file).to_string();
+        if short {
+            rows.push((
+                display,
+                drift.short_code().to_string(),
+                Tone::Good,
+                String::new(),
+            ));
+            continue;
+        }
+        let note = match drift.hint() {
+            // Rendered files are updated by editing the template, not by `add`
+            Some(_) if drift == Drift::ModifiedLocally && is_template(&file) => {
+                format!(" (edit {file} instead)")
+            }
+            Some("ordinator add") => {
+                format!(" (run 'ordinator add {file} --profile {source_profile}')")
+            }
+            Some(hint) => format!(" (run '{hint} --profile {profile}')"),
+            None => String::new(),
+        };
+        rows.push((display, drift.label().to_string(), drift_tone(drift), note));
+    }
+    cache.save()?;
+
+    if short {
+        for (display, code, ..) in &rows {
+            println!("{code} {display}");
+        }
+        return Ok(counts);
+    }
+    print_status_rows("  ", &rows);
+    if counts.is_empty() {
+        eprintln!("  No tracked files");
+    } else {
+        let summary: Vec<String> = counts
+            .iter()
+            .map(|(drift, count)| paint(&format!("{count} {}", drift.label()), drift_tone(*drift)))
+            .collect();
+        eprintln!("  {}", summary.join(", "));
+    }
+    Ok(counts)
+}
+
+/// Start this run's log (see [`crate::run_log`]). A run that can't be
+/// logged still runs.
+fn start_run_log() -> Option<crate::run_log::RunLog> {
+    use clap::CommandFactory;
+    let matches = Args::command()
+        .try_get_matches_from(std::env::args_os())
+        .ok()?;
+    let mut names = Vec::new();
+    let mut current = &matches;
+    while let Some((name, sub)) = current.subcommand() {
+        names.push(name);
+        current = sub;
+    }
+    let home_dir = crate::utils::get_home_dir().ok()?;
+    let args = std::env::args().skip(1).collect();
+    match crate::run_log::RunLog::create(&home_dir, &names.join(" "), args) {
+        Ok(log) => Some(log),
+        Err(e) => {
+            eprintln!("Warning: Not logging this run: {e}");
+            None
+        }
+    }
+}
+
+/// Run a command, recording it in the run log
+/// `ordinator init --interactive`: ask the wizard's questions, then clone
+/// or create the repository and set it up from the answers
+async fn run_init_wizard(
+    target_dir: Option<String>,
+    force: bool,
+    depth: Option<u32>,
+    retries: u32,
+    dry_run: bool,
+    quiet: bool,
+) -> Result<()> {
+    use crate::brew::BrewManager;
+
+    if let Some(existing) = Config::find_config_file()? {
+        if !force {
+            return Err(anyhow::anyhow!(
+                "{} already exists. Use --force to start over.",
+                existing.display()
+            ));
+        }
+    }
+    if !crate::prompt::interactive() && !quiet {
+        eprintln!("Not running interactively; using the default answers");
+    }
+    let home_dir = crate::utils::get_home_dir()?;
+    let answers = crate::wizard::ask(&home_dir, BrewManager::check_homebrew_installed())?;
+
+    if let Some(url) = &answers.clone_url {
+        let target_path = match &target_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => std::env::current_dir()?,
+        };
+        if dry_run {
+            eprintln!("DRY-RUN: Would clone {url} into {}", target_path.display());
+            return Ok(());
+        }
+        let repo_manager = crate::repo::RepoManager::new(target_path)
+            .with_depth(depth)
+            .with_retries(retries);
+        repo_manager
+            .parse_github_url(url)
+            .map_err(|e| anyhow::anyhow!("Invalid GitHub URL '{url}': {e}"))?;
+        repo_manager.init_from_url(url, force).await?;
+        eprintln!("Repository initialized successfully from {url}");
+        if answers.setup_secrets {
+            let (config, _) = Config::load()?;
+            crate::secrets::setup_sops_and_age(&config.global.default_profile, false)?;
+        }
+        eprintln!("Next steps:");
+        eprintln!("  1. Review the configuration: cat ordinator.toml");
+        eprintln!("  2. Apply the dotfiles: ordinator apply");
+        return Ok(());
+    }
+
+    let first = answers.profiles[0].clone();
+    if dry_run {
+        eprintln!(
+            "DRY-RUN: Would create ordinator.toml with profile(s): {}",
+            answers.profiles.join(", ")
+        );
+        if answers.export_brew {
+            eprintln!("DRY-RUN: Would export Homebrew packages into profile '{first}'");
+        }
+        for file in &answers.dotfiles {
+            eprintln!("DRY-RUN: Would track ~/{file} in profile '{first}'");
+        }
+        if answers.setup_secrets {
+            eprintln!("DRY-RUN: Would set up age encryption for profile '{first}'");
+        }
+        return Ok(());
+    }
+
+    let test_name = std::env::var("ORDINATOR_TEST_NAME").ok();
+    let config_path = Config::init_dotfiles_repository_with_test_name(test_name.as_deref())?;
+    let dotfiles_path = config_path.parent().unwrap().to_path_buf();
+    let git_manager = GitManager::new(dotfiles_path.clone());
+    if !git_manager.exists() {
+        git_manager.init()?;
+    }
+    let mut config = Config::from_file(&config_path)?;
+    crate::wizard::apply_answers(&mut config, &answers)?;
+
+    if answers.export_brew {
+        BrewManager::new(false)
+            .export_packages(&first, &mut config)
+            .await?;
+        eprintln!("Exported Homebrew packages into profile '{first}'");
+    }
+    let mut cache = crate::drift::HashCache::load(&dotfiles_path);
+    for file in &answers.dotfiles {
+        let live = home_dir.join(file);
+        let origin = match std::fs::canonicalize(&live) {
+            Ok(target) if live.is_symlink() => crate::adopt::Origin::Symlink(target),
+            _ => crate::adopt::Origin::File,
+        };
+        let candidate = crate::adopt::Candidate {
+            path: file.clone(),
+            origin,
+        };
+        crate::adopt::adopt(&mut config, &dotfiles_path, &first, &candidate, &home_dir)?;
+        cache.record_added(&first, file);
+        let hash = cache.hash_file(&live)?;
+        cache.record_sync(&first, file, hash);
+        eprintln!("Tracking ~/{file} in profile '{first}'");
+    }
+    cache.save()?;
+    config.save_to_file(&config_path)?;
+
+    // Updates the saved config with the key it creates
+    if answers.setup_secrets {
+        crate::secrets::setup_sops_and_age(&first, false)?;
+    }
+
+    eprintln!(
+        "✅ Created {} with profile(s): {}",
+        config_path.display(),
+        answers.profiles.join(", ")
+    );
+    eprintln!("Next steps:");
+    eprintln!(
+        "  1. Review the configuration: cat {}",
+        config_path.display()
+    );
+    eprintln!("  2. Apply your configuration: ordinator apply --profile {first}");
+    eprintln!("  3. Commit and push: ordinator commit -m 'Initial setup' && ordinator push");
+    Ok(())
+}
+
+pub async fn run(args: Args) -> Result<()> {
+    // Reading the run logs doesn't add to them
+    let run_log = if matches!(args.command, Commands::Logs { .. }) {
+        None
+    } else {
+        start_run_log()
+    };
+    let events = run_log.as_ref().and_then(|log| log.events().ok());
+    if let Some(log) = run_log {
+        crate::run_log::begin(log);
+    }
+    let result = match crate::logging::init(args.verbose, args.log_file.as_deref(), events) {
+        Ok(()) => run_command(args).await,
+        Err(e) => Err(e),
+    };
+    match &result {
+        Ok(()) => crate::run_log::end(crate::exit_code::OK, None),
+        Err(e) => crate::run_log::end(crate::exit_code::ERROR, Some(crate::error::report(e))),
+    }
+    result
+}
+
+async fn run_command(mut args: Args) -> Result<()> {
+    debug!("Arguments: {:?}", std::env::args().collect::<Vec<_>>());
+
+    info!("Starting Ordinator");
+    crate::prompt::set_assume_yes(args.yes);
+    crate::prompt::set_no_input(args.no_input);
+    crate::transfer::set_quiet(args.quiet);
+    crate::utils::set_strict_paths(args.strict_paths);
+    crate::discard::set_permanent(args.permanent);
+
+    if args.simulate {
+        args.dry_run = true;
+        crate::environment::install(std::sync::Arc::new(crate::environment::Simulated::new(
+            crate::environment::current(),
+        )));
+        warn!("Running in SIMULATE mode - no changes will be made");
+        eprintln!("SIMULATE: No changes will be made; commands that would change this machine are shown instead");
+    } else if args.dry_run {
+        warn!("Running in DRY-RUN mode - no changes will be made");
+        eprintln!("DRY-RUN: No changes will be made");
+    }
+
+    // 'profiles <command>' is 'profile <command>'
+    if let Commands::Profiles { subcommand, .. } = &mut args.command {
+        if let Some(subcommand) = subcommand.take() {
+            args.command = Commands::Profile { subcommand };
+        }
+    }
+
+    match args.command {
+        Commands::Init {
             repo_url,
             target_dir,
             profile,
             force,
+            depth,
+            retries,
+            interactive,
         } => {
+            if interactive {
+                return run_init_wizard(
+                    target_dir,
+                    force,
+                    depth,
+                    retries,
+                    args.dry_run,
+                    args.quiet,
+                )
+                .await;
+            }
             if let Some(url) = &repo_url {
-                // Validate the repository URL format first
-                let is_test_mode = std::env::var("ORDINATOR_TEST_MODE").unwrap_or_default() == "1";
-                let target_path = if let Some(dir) = &target_dir {
-                    PathBuf::from(dir)
-                } else if is_test_mode && repo_url.is_none() {
-                    // In test mode, require explicit target directory only when not cloning from URL
-                    return Err(anyhow::anyhow!(
-                        "Test mode requires explicit target directory to be specified"
-                    ));
-                } else {
-                    std::env::current_dir()?
+                let target_path = match &target_dir {
+                    Some(dir) => PathBuf::from(dir),
+                    None => std::env::current_dir()?,
                 };
-                let repo_manager = crate::repo::RepoManager::new(target_path.clone());
+                // Validate the repository URL format first
+                let repo_manager = crate::repo::RepoManager::new(target_path.clone())
+                    .with_depth(depth)
+                    .with_retries(retries);
                 if let Err(e) = repo_manager.parse_github_url(url) {
                     return Err(anyhow::anyhow!("Invalid GitHub URL '{}': {}", url, e));
                 }
@@ -685,8 +2852,10 @@ pub async fn run(args: Args) -> Result<()> {
                             );
                         }
 
-                        // Set the remote URL
-                        git_manager.add_remote("origin", url)?;
+                        // Set the remote URL, unless an earlier init already did
+                        if git_manager.get_origin_url()?.as_deref() != Some(url.as_str()) {
+                            git_manager.add_remote("origin", url)?;
+                        }
                         info!("Set remote 'origin' to: {}", url);
                         eprintln!("Set remote 'origin' to: {url}");
 
@@ -769,7 +2938,23 @@ pub async fn run(args: Args) -> Result<()> {
                 Ok(())
             }
         }
-        Commands::Watch { path, profile } => {
+        Commands::Watch {
+            path,
+            profile,
+            include,
+            exclude,
+            link,
+            dir_link,
+            sudo,
+            encrypt,
+            managed_block,
+            no_commit,
+        } => {
+            let link: crate::directory::DirectoryLink = if dir_link {
+                crate::directory::DirectoryLink::Directory
+            } else {
+                link.parse()?
+            };
             let (mut config, config_path) = Config::load()?;
             let profile_name = match profile {
                 Some(p) => p,
@@ -778,172 +2963,297 @@ pub async fn run(args: Args) -> Result<()> {
                     prompt_for_profile(&profiles, &config.global.default_profile)
                 }
             };
+            let options = crate::watch::WatchOptions {
+                path,
+                include,
+                exclude,
+                link,
+                sudo,
+                encrypt,
+                managed_block,
+                progress: args.progress,
+                dry_run: args.dry_run,
+                quiet: args.quiet,
+                verbose: args.verbose,
+            };
+            let Some(path) =
+                crate::watch::watch(&mut config, &config_path, &profile_name, &options)?
+            else {
+                return Ok(());
+            };
+
+            // Auto-update README if needed
+            let dotfiles_dir = config_path.parent().unwrap();
+            if crate::readme::readme_needs_update(&config, dotfiles_dir) {
+                if config.readme.auto_update {
+                    if let Err(e) = crate::readme::auto_update_readme(&config, dotfiles_dir) {
+                        if !args.quiet {
+                            eprintln!("Warning: Failed to auto-update README: {e}");
+                        }
+                    }
+                } else {
+                    eprintln!(
+                        "⚠️  Ordinator config changed ({}). Your README.md may be out of date.",
+                        config.readme.update_on_changes.join(", ")
+                    );
+                    eprintln!("   Run: ordinator readme default   (or ordinator readme preview)");
+                }
+            }
+
+            auto_commit(
+                &config,
+                &config_path,
+                args.quiet,
+                no_commit,
+                &format!("ordinator: watch {path} in {profile_name}"),
+            )
+            .await
+        }
+        Commands::Adopt {
+            path,
+            profile,
+            symlinks_only,
+        } => {
+            use crate::adopt::Origin;
 
+            let (mut config, config_path) = Config::load()?;
+            let profile_name = match profile {
+                Some(p) => p,
+                None => {
+                    let profiles = config.list_profiles();
+                    prompt_for_profile(&profiles, &config.global.default_profile)
+                }
+            };
             if !config.profiles.contains_key(&profile_name) {
-                return Err(anyhow::anyhow!(
-                    "Profile '{}' does not exist. To create it, run: ordinator profile add {}",
-                    profile_name,
-                    profile_name
-                ));
+                return Err(OrdinatorError::ProfileNotFound(profile_name.to_string()).into());
             }
 
-            // Exclusion check
-            let exclusion_set = config.exclusion_set_for_profile(&profile_name)?;
-            if exclusion_set.is_match(&path) {
-                return Err(anyhow::anyhow!(
-                    "Path '{}' matches an exclusion pattern and cannot be tracked.",
-                    path
-                ));
+            let home_dir = config.target_root()?;
+            let root = match &path {
+                Some(path) => std::path::PathBuf::from(shellexpand::tilde(path).as_ref()),
+                None => home_dir.clone(),
+            };
+            if !root.is_dir() {
+                return Err(anyhow::anyhow!("'{}' is not a directory.", root.display()));
             }
+            let dotfiles_dir = config_path.parent().unwrap().to_path_buf();
+            let managed = [
+                dotfiles_dir.clone(),
+                crate::utils::get_home_dir()?.join(".dotfiles"),
+            ];
+            let tracked: std::collections::BTreeSet<String> = config
+                .profiles
+                .values()
+                .flat_map(|profile| profile.files.iter().cloned())
+                .collect();
+            let exclusion_set = config.exclusion_set_for_profile(&profile_name)?;
+            let candidates: Vec<crate::adopt::Candidate> =
+                crate::adopt::find_candidates(&home_dir, &root, &managed, &tracked)?
+                    .into_iter()
+                    .filter(|c| !symlinks_only || matches!(c.origin, Origin::Symlink(_)))
+                    .filter(|c| !exclusion_set.is_match(&c.path))
+                    .collect();
 
-            if args.dry_run {
-                println!("DRY-RUN: Would start watching '{path}' for profile '{profile_name}'");
+            if candidates.is_empty() {
+                if !args.quiet {
+                    eprintln!("No dotfiles to adopt under {}", root.display());
+                }
                 return Ok(());
             }
-
-            let path_obj = std::path::Path::new(&path);
-            if !path_obj.exists() {
-                return Err(anyhow::anyhow!("Path '{}' does not exist on disk.", path));
+            println!("Found {} file(s) to adopt:", candidates.len());
+            for candidate in &candidates {
+                match &candidate.origin {
+                    Origin::Symlink(target) => {
+                        println!("  {} -> {} (symlink)", candidate.path, target.display())
+                    }
+                    Origin::File => println!("  {} (file)", candidate.path),
+                }
             }
 
-            // Check for conflicts with other profiles
-            let conflicts = check_file_conflicts(&config, &path, &profile_name);
-            if !conflicts.is_empty()
-                && !prompt_for_conflict_resolution(&path, &conflicts, &profile_name)
-            {
-                eprintln!("Operation cancelled by user.");
+            if args.dry_run {
+                eprintln!(
+                    "DRY-RUN: Would adopt {} file(s) into profile '{profile_name}'",
+                    candidates.len()
+                );
                 return Ok(());
             }
 
-            // Get the profile-specific file path
-            let profile_file_path = config.get_profile_file_path(&profile_name, &path)?;
-
-            // Create the profile directory if it doesn't exist
-            if let Some(parent) = profile_file_path.parent() {
-                std::fs::create_dir_all(parent)?;
-            }
+            let selected: Vec<&crate::adopt::Candidate> =
+                if crate::prompt::assume_yes() || crate::prompt::interactive() {
+                    candidates
+                        .iter()
+                        .filter(|candidate| {
+                            crate::prompt::confirm(&format!("Adopt {}?", candidate.path), false)
+                        })
+                        .collect()
+                } else {
+                    return Err(anyhow::anyhow!(
+                        "Not running interactively. Re-run with --yes to adopt all {} file(s).",
+                        candidates.len()
+                    ));
+                };
 
-            // Copy the file to the profile-specific location
-            if path_obj.is_file() {
-                std::fs::copy(path_obj, &profile_file_path)?;
+            let mut cache = crate::drift::HashCache::load(&dotfiles_dir);
+            for candidate in &selected {
+                crate::adopt::adopt(
+                    &mut config,
+                    &dotfiles_dir,
+                    &profile_name,
+                    candidate,
+                    &home_dir,
+                )?;
+                cache.record_added(&profile_name, &candidate.path);
+                if !crate::template::is_template(&candidate.path) {
+                    let hash = cache.hash_file(&home_dir.join(&candidate.path))?;
+                    cache.record_sync(&profile_name, &candidate.path, hash);
+                }
                 if !args.quiet {
-                    let msg =
-                        format!("[1/1] Started watching '{path}' for profile '{profile_name}'");
+                    let msg = match candidate.origin {
+                        Origin::Symlink(_) => {
+                            format!("Adopted '{}' (symlink replaced)", candidate.path)
+                        }
+                        Origin::File => format!("Adopted '{}'", candidate.path),
+                    };
                     if color_enabled() {
                         println!("{}", msg.green());
                     } else {
                         println!("{msg}");
                     }
                 }
-            } else if path_obj.is_dir() {
-                // For directories, we need to copy recursively
-                let mut file_count = 0;
-                for entry in walkdir::WalkDir::new(path_obj).into_iter().flatten() {
-                    if entry.path().is_file() {
-                        file_count += 1;
-                    }
+            }
+            cache.save()?;
+            config.save_to_file(&config_path)?;
+
+            if !args.quiet {
+                eprintln!(
+                    "Adopted {} file(s) into profile '{profile_name}'",
+                    selected.len()
+                );
+                if selected.iter().any(|c| c.origin == Origin::File) {
+                    eprintln!("Run 'ordinator apply --profile {profile_name}' to link the adopted plain files.");
                 }
-                let mut copied = 0;
-                for entry in walkdir::WalkDir::new(path_obj) {
-                    let entry = entry?;
-                    let src_path = entry.path();
-                    if src_path.is_file() {
-                        let rel_path = src_path.strip_prefix(path_obj).unwrap();
-                        let dst_path = profile_file_path.join(rel_path);
-                        if let Some(parent) = dst_path.parent() {
-                            std::fs::create_dir_all(parent)?;
-                        }
-                        std::fs::copy(src_path, &dst_path)?;
-                        copied += 1;
-                        if !args.quiet && color_enabled() {
-                            println!(
-                                "[{} / {}] {}",
-                                copied,
-                                file_count,
-                                dst_path.display().to_string().cyan()
-                            );
-                        } else if !args.quiet {
-                            println!("[{} / {}] {}", copied, file_count, dst_path.display());
-                        }
-                    }
-                }
-                if !args.quiet {
-                    let msg =
-                        format!("Started watching directory '{path}' for profile '{profile_name}'");
-                    if color_enabled() {
-                        println!("{}", msg.green());
-                    } else {
-                        println!("{msg}");
-                    }
+            }
+            Ok(())
+        }
+        Commands::Migrate {
+            from,
+            path,
+            profile,
+            no_commit,
+        } => {
+            let (mut config, config_path) = Config::load()?;
+            let profile_name = match profile {
+                Some(p) => p,
+                None => {
+                    let profiles = config.list_profiles();
+                    prompt_for_profile(&profiles, &config.global.default_profile)
                 }
+            };
+            if !config.profiles.contains_key(&profile_name) {
+                return Err(OrdinatorError::ProfileNotFound(profile_name.to_string()).into());
             }
 
-            config.add_file_to_profile(&profile_name, path.clone())?;
-            config.save_to_file(&config_path)?;
-
-            // Automatically scan the added file for secrets
-            if path_obj.is_file() {
-                let base_dir = config_path.parent().unwrap().to_path_buf();
-                let manager = crate::secrets::SecretsManager::new(
-                    None,
-                    None,
-                    config.clone(),
-                    base_dir.clone(),
-                );
-
-                match manager.check_for_plaintext_secrets(path_obj) {
-                    Ok(has_secrets) => {
-                        if has_secrets {
-                            eprintln!("⚠️  Warning: '{path}' contains potential secrets");
-                            match manager.get_secrets_info(path_obj) {
-                                Ok(secret_types) => {
-                                    eprintln!("   Found: {}", secret_types.join(", "));
-                                }
-                                Err(_) => {
-                                    eprintln!("   Found: potential secrets");
-                                }
-                            }
-                            eprintln!(
-                                "   Consider using: ordinator secrets watch {path} --profile {profile_name}"
-                            );
-                            eprintln!("   Use 'ordinator commit --force' to commit anyway");
-                        }
-                    }
-                    Err(e) => {
-                        if args.verbose {
-                            eprintln!("Warning: Could not scan '{path}' for secrets: {e}");
-                        }
+            let home_dir = config.target_root()?;
+            let repo = match &path {
+                Some(path) => std::path::PathBuf::from(shellexpand::tilde(path).as_ref()),
+                None => from.default_path(&home_dir),
+            };
+            let migration = crate::migrate::scan(from, &repo)?;
+            let tracked = config
+                .get_profile(&profile_name)
+                .map(|profile| profile.files.clone())
+                .unwrap_or_default();
+            let mut untranslated = migration.untranslated.clone();
+            let entries: Vec<&crate::migrate::Entry> = migration
+                .entries
+                .iter()
+                .filter(|entry| {
+                    // Host overrides of already-tracked files are still stored
+                    let keep = entry.host.is_some() || !tracked.contains(&entry.path);
+                    if !keep {
+                        untranslated.push(crate::migrate::Untranslated {
+                            source: entry.source.clone(),
+                            reason: format!("'{}' is already tracked", entry.path),
+                        });
                     }
+                    keep
+                })
+                .collect();
+
+            let verb = if args.dry_run {
+                "Would migrate"
+            } else {
+                "Migrating"
+            };
+            println!(
+                "{verb} {} file(s) from {from} into profile '{profile_name}':",
+                entries.len()
+            );
+            for entry in &entries {
+                let mut line = format!("  {}", entry.path);
+                if let Some(host) = &entry.host {
+                    line.push_str(&format!(" on host {host}"));
+                }
+                if entry.source != entry.path {
+                    line.push_str(&format!(" (from {})", entry.source));
                 }
+                println!("{line}");
+            }
+            if !untranslated.is_empty() {
+                println!("Could not translate {} file(s):", untranslated.len());
+                for skipped in &untranslated {
+                    println!("  {skipped}");
+                }
+            }
+            if args.dry_run || entries.is_empty() {
+                return Ok(());
             }
 
-            println!("Started watching '{path}' for profile '{profile_name}'");
-
-            // Auto-update README if needed
-            if !args.dry_run {
-                let dotfiles_dir = config_path.parent().unwrap();
-                if crate::readme::readme_needs_update(&config, dotfiles_dir) {
-                    if config.readme.auto_update {
-                        if let Err(e) = crate::readme::auto_update_readme(&config, dotfiles_dir) {
-                            if !args.quiet {
-                                eprintln!("Warning: Failed to auto-update README: {e}");
-                            }
-                        }
-                    } else {
-                        eprintln!(
-                            "⚠️  Ordinator config changed ({}). Your README.md may be out of date.",
-                            config.readme.update_on_changes.join(", ")
-                        );
-                        eprintln!(
-                            "   Run: ordinator readme default   (or ordinator readme preview)"
-                        );
-                    }
+            let dotfiles_dir = config_path.parent().unwrap().to_path_buf();
+            let mut cache = crate::drift::HashCache::load(&dotfiles_dir);
+            for entry in &entries {
+                crate::migrate::import(
+                    &mut config,
+                    &dotfiles_dir,
+                    &profile_name,
+                    &migration,
+                    entry,
+                )?;
+                if entry.host.is_none() {
+                    cache.record_added(&profile_name, &entry.path);
                 }
             }
+            cache.save()?;
+            config.save_to_file(&config_path)?;
 
-            Ok(())
+            if !args.quiet {
+                eprintln!(
+                    "Migrated {} file(s) from {from} into profile '{profile_name}'",
+                    entries.len()
+                );
+                eprintln!(
+                    "Run 'ordinator plan --profile {profile_name}' to see what apply will change, then 'ordinator apply --force --profile {profile_name}' to replace the files {from} put in place (they're backed up first)."
+                );
+            }
+            auto_commit(
+                &config,
+                &config_path,
+                args.quiet,
+                no_commit,
+                &format!("ordinator: migrate from {from} into {profile_name}"),
+            )
+            .await
         }
-        Commands::Unwatch { path, profile } => {
+        Commands::Unwatch {
+            path,
+            profile,
+            keep_file,
+            restore,
+            purge,
+            no_commit,
+        } => {
+            use crate::unwatch::{Placement, UnwatchMode};
+
             let (mut config, config_path) = Config::load()?;
             let profile_name = match profile {
                 Some(p) => p,
@@ -954,24 +3264,117 @@ pub async fn run(args: Args) -> Result<()> {
             };
 
             if !config.profiles.contains_key(&profile_name) {
-                return Err(anyhow::anyhow!(
-                    "Profile '{}' does not exist. To create it, run: ordinator profile add {}",
-                    profile_name,
-                    profile_name
-                ));
+                return Err(OrdinatorError::ProfileNotFound(profile_name.to_string()).into());
             }
 
+            let tracked = config.get_directory(&profile_name, &path).is_some()
+                || config
+                    .get_profile(&profile_name)
+                    .is_some_and(|profile| profile.files.contains(&path));
+            let mode = if keep_file {
+                UnwatchMode::KeepFile
+            } else if restore {
+                UnwatchMode::Restore
+            } else if purge {
+                UnwatchMode::Purge
+            } else if tracked && !args.dry_run {
+                let choices: Vec<String> = UnwatchMode::ALL
+                    .iter()
+                    .map(|mode| mode.describe().to_string())
+                    .collect();
+                UnwatchMode::ALL[crate::prompt::choose(
+                    &format!("How should '{path}' be unwatched?"),
+                    &choices,
+                    0,
+                )]
+            } else {
+                UnwatchMode::Restore
+            };
+
             if args.dry_run {
-                println!("DRY-RUN: Would stop watching '{path}' for profile '{profile_name}'");
+                println!(
+                    "DRY-RUN: Would stop watching '{path}' for profile '{profile_name}'. {}",
+                    mode.describe()
+                );
                 return Ok(());
             }
 
+            let home_dir = config.target_root()?;
+            let report = |dest: &std::path::Path, outcome: crate::unwatch::Outcome| {
+                use crate::unwatch::Outcome;
+                let msg = match outcome {
+                    Outcome::Restored => {
+                        format!("Replaced the symlink at {} with a copy", dest.display())
+                    }
+                    Outcome::Removed => format!("Removed {}", dest.display()),
+                    Outcome::StillLinked => {
+                        format!("{} still links to the kept repository copy", dest.display())
+                    }
+                    Outcome::Untouched => return,
+                };
+                if !args.quiet {
+                    println!("{msg}");
+                }
+            };
+
+            // Tracked directories keep their copy under a hashed name
+            if let Some(directory) = config.remove_directory_from_profile(&profile_name, &path)? {
+                let storage = config.get_directory_storage_path(&profile_name, &directory)?;
+                let dest = crate::paths::destination(&home_dir, &directory.path);
+                if storage.is_dir() {
+                    for (file, outcome) in crate::unwatch::release_directory(&dest, &storage, mode)?
+                    {
+                        report(&file, outcome);
+                    }
+                    if mode.deletes_stored() {
+                        std::fs::remove_dir_all(&storage)?;
+                    }
+                }
+                config.save_to_file(&config_path)?;
+                if !args.quiet {
+                    let msg = format!(
+                        "Stopped watching directory '{}' for profile '{profile_name}'",
+                        directory.path
+                    );
+                    if color_enabled() {
+                        println!("{}", msg.green());
+                    } else {
+                        println!("{msg}");
+                    }
+                }
+                return auto_commit(
+                    &config,
+                    &config_path,
+                    args.quiet,
+                    no_commit,
+                    &format!("ordinator: unwatch {path} from {profile_name}"),
+                )
+                .await;
+            }
+
+            // The copy apply links to can be this machine's host override
+            let profile_file_path = config.get_profile_file_path(&profile_name, &path)?;
+            let source_path = config.get_source_file_path(&profile_name, &path)?;
+            let options = config.file_options(&profile_name, &path);
+            let placement = if options.managed_block {
+                Placement::Block
+            } else if options.encrypt || crate::template::is_template(&path) {
+                Placement::Written
+            } else {
+                Placement::Linked
+            };
+            if tracked && source_path.exists() {
+                let dest =
+                    crate::paths::destination(&home_dir, crate::template::rendered_path(&path));
+                let outcome = crate::unwatch::release(&dest, &source_path, placement, mode)?;
+                report(&dest, outcome);
+            }
+
             // Remove from tracking
             config.remove_file_from_profile(&profile_name, &path)?;
 
             // Remove from filesystem
-            let profile_file_path = config.get_profile_file_path(&profile_name, &path)?;
-            if profile_file_path.exists() {
+            if mode.deletes_stored() && profile_file_path.exists() {
                 if profile_file_path.is_file() {
                     std::fs::remove_file(&profile_file_path)?;
                 } else if profile_file_path.is_dir() {
@@ -990,9 +3393,22 @@ pub async fn run(args: Args) -> Result<()> {
                 }
             }
 
-            Ok(())
+            auto_commit(
+                &config,
+                &config_path,
+                args.quiet,
+                no_commit,
+                &format!("ordinator: unwatch {path} from {profile_name}"),
+            )
+            .await
         }
-        Commands::Add { path, profile, all } => {
+        Commands::Add {
+            path,
+            profile,
+            all,
+            host,
+            no_commit,
+        } => {
             let (mut config, _config_path) = Config::load()?;
             let profile_name = match profile {
                 Some(p) => p,
@@ -1003,11 +3419,7 @@ pub async fn run(args: Args) -> Result<()> {
             };
 
             if !config.profiles.contains_key(&profile_name) {
-                return Err(anyhow::anyhow!(
-                    "Profile '{}' does not exist. To create it, run: ordinator profile add {}",
-                    profile_name,
-                    profile_name
-                ));
+                return Err(OrdinatorError::ProfileNotFound(profile_name.to_string()).into());
             }
 
             if args.dry_run {
@@ -1015,6 +3427,21 @@ pub async fn run(args: Args) -> Result<()> {
                     println!(
                         "DRY-RUN: Would update all tracked files for profile '{profile_name}'"
                     );
+                    for directory in &config.get_profile(&profile_name).unwrap().directories {
+                        let report = crate::watch::sync_tracked_directory(
+                            &config,
+                            &profile_name,
+                            directory,
+                            true,
+                        )?;
+                        println!(
+                            "DRY-RUN: '{}': {} new, {} changed, {} deleted file(s)",
+                            directory.path,
+                            report.added.len(),
+                            report.updated.len(),
+                            report.removed.len()
+                        );
+                    }
                 } else {
                     let path_str = path.as_ref().ok_or_else(|| {
                         anyhow::anyhow!("Path is required when not using --all flag")
@@ -1024,39 +3451,106 @@ pub async fn run(args: Args) -> Result<()> {
                 return Ok(());
             }
 
+            // A host override needs a host name even when none was given
+            let host = match host {
+                Some(Some(name)) => Some(name),
+                Some(None) => Some(crate::machine::host_name().ok_or_else(|| {
+                    anyhow::anyhow!("Could not determine this machine's hostname. Pass it with --host <HOSTNAME>.")
+                })?),
+                None => None,
+            };
+
             if all {
                 // Update all tracked files for the profile
+                let large_files = config.global.large_files;
+                let home_dir = config.target_root()?;
                 let profile = config.get_profile_mut(&profile_name).unwrap();
                 let mut updated_count = 0;
-                let total_files = profile.files.len();
+                let mut progress = crate::progress::Progress::start(
+                    args.progress,
+                    args.quiet,
+                    "add",
+                    profile.files.len(),
+                );
 
                 for file_path in &profile.files {
-                    let source_path = std::path::Path::new(file_path);
+                    progress.advance(file_path);
+                    let live_path = crate::paths::live(&home_dir, file_path);
+                    let source_path = live_path.as_path();
                     if source_path.exists() {
+                        warn_if_rendered_template(file_path, &profile.files);
                         let hash = generate_file_hash(file_path);
                         let filename = source_path.file_name().unwrap().to_string_lossy();
                         let hash_filename = format!("{hash}_{filename}");
-                        let dotfiles_dir = crate::utils::get_dotfiles_dir()?;
+                        let dotfiles_dir = crate::config::get_dotfiles_dir()?;
                         let profile_files_dir = dotfiles_dir.join("files").join(&profile_name);
                         std::fs::create_dir_all(&profile_files_dir)?;
                         let profile_file_path = profile_files_dir.join(&hash_filename);
-                        std::fs::copy(source_path, &profile_file_path)?;
+                        crate::watch::store_tracked_copy(
+                            &large_files,
+                            &dotfiles_dir,
+                            source_path,
+                            &profile_file_path,
+                            file_path,
+                            &profile
+                                .file_options
+                                .get(file_path)
+                                .cloned()
+                                .unwrap_or_default(),
+                        )?;
                         profile
                             .file_mappings
                             .insert(hash_filename.clone(), file_path.clone());
+                        profile.file_modes.insert(
+                            file_path.clone(),
+                            crate::config::format_mode(crate::utils::file_mode(source_path)?),
+                        );
+                        let managed_block = profile
+                            .file_options
+                            .get(file_path)
+                            .is_some_and(|options| options.managed_block);
+                        crate::watch::record_sync_baseline(
+                            &_config_path,
+                            &profile_name,
+                            file_path,
+                            source_path,
+                            managed_block,
+                        )?;
                         updated_count += 1;
-                        if !args.quiet {
-                            let msg = format!("[{updated_count}/{total_files}] Updated '{file_path}' as '{hash_filename}'");
-                            if color_enabled() {
-                                println!("{}", msg.green());
-                            } else {
-                                println!("{msg}");
-                            }
+                        let msg = format!("Updated '{file_path}' as '{hash_filename}'");
+                        if color_enabled() {
+                            progress.println(msg.green().to_string());
+                        } else {
+                            progress.println(msg);
                         }
                     } else if !args.quiet {
                         eprintln!("Warning: Source file '{file_path}' does not exist");
                     }
                 }
+                progress.finish();
+
+                // Pick up new and deleted files inside tracked directories
+                let directories = profile.directories.clone();
+                for directory in &directories {
+                    if !crate::paths::live(&home_dir, &directory.path).is_dir() {
+                        if !args.quiet {
+                            eprintln!(
+                                "Warning: Tracked directory '{}' does not exist",
+                                directory.path
+                            );
+                        }
+                        continue;
+                    }
+                    let report = crate::watch::sync_tracked_directory(
+                        &config,
+                        &profile_name,
+                        directory,
+                        false,
+                    )?;
+                    if !args.quiet {
+                        print_sync_report(&directory.path, &report, args.verbose);
+                    }
+                }
                 config.save_to_file(&_config_path)?;
                 if !args.quiet {
                     let msg = format!("Updated {updated_count} files for profile '{profile_name}'");
@@ -1068,39 +3562,100 @@ pub async fn run(args: Args) -> Result<()> {
                 }
             } else {
                 // Update a specific tracked file
-                let path_str = path
+                let requested = path
                     .as_ref()
                     .ok_or_else(|| anyhow::anyhow!("Path is required when not using --all flag"))?;
+                let path_str = &crate::paths::normalize(requested)?;
+                let home_dir = config.target_root()?;
+
+                if let Some(directory) = config.get_directory(&profile_name, path_str).cloned() {
+                    if host.is_some() {
+                        return Err(anyhow::anyhow!(
+                            "Host overrides are for tracked files; '{}' is a tracked directory.",
+                            directory.path
+                        ));
+                    }
+                    if !crate::paths::live(&home_dir, &directory.path).is_dir() {
+                        return Err(anyhow::anyhow!(
+                            "Source directory '{}' does not exist.",
+                            directory.path
+                        ));
+                    }
+                    let report = crate::watch::sync_tracked_directory(
+                        &config,
+                        &profile_name,
+                        &directory,
+                        false,
+                    )?;
+                    if !args.quiet {
+                        print_sync_report(&directory.path, &report, args.verbose);
+                    }
+                    return auto_commit(
+                        &config,
+                        &_config_path,
+                        args.quiet,
+                        no_commit,
+                        &format!("ordinator: add {path_str} to {profile_name}"),
+                    )
+                    .await;
+                }
 
                 let profile = config.get_profile_mut(&profile_name).unwrap();
                 if !profile.files.contains(path_str) {
-                    return Err(anyhow::anyhow!(
-                        "File '{}' is not tracked for profile '{}'. Use 'ordinator watch {} --profile {}' to start tracking it.",
-                        path_str, profile_name, path_str, profile_name
-                    ));
+                    return Err(OrdinatorError::FileNotTracked {
+                        path: requested.to_string(),
+                        profile: profile_name,
+                    }
+                    .into());
                 }
 
-                let path_obj = std::path::Path::new(path_str);
+                let live_path = crate::paths::live(&home_dir, path_str);
+                let path_obj = live_path.as_path();
                 if !path_obj.exists() {
                     return Err(anyhow::anyhow!("Source file '{path_str}' does not exist."));
                 }
+                warn_if_rendered_template(path_str, &profile.files);
 
                 let hash = generate_file_hash(path_str);
                 let filename = path_obj.file_name().unwrap().to_string_lossy();
                 let hash_filename = format!("{hash}_{filename}");
-                let dotfiles_dir = crate::utils::get_dotfiles_dir()?;
-                let profile_files_dir = dotfiles_dir.join("files").join(&profile_name);
+                let dotfiles_dir = crate::config::get_dotfiles_dir()?;
+                let mut profile_files_dir = dotfiles_dir.join("files").join(&profile_name);
+                if let Some(host) = &host {
+                    profile_files_dir = config.get_host_override_dir(&profile_name, host)?;
+                }
                 std::fs::create_dir_all(&profile_files_dir)?;
                 let profile_file_path = profile_files_dir.join(&hash_filename);
-                std::fs::copy(path_obj, &profile_file_path)?;
+                crate::watch::store_tracked_copy(
+                    &config.global.large_files,
+                    &dotfiles_dir,
+                    path_obj,
+                    &profile_file_path,
+                    path_str,
+                    &config.file_options(&profile_name, path_str),
+                )?;
+                let profile = config.get_profile_mut(&profile_name).unwrap();
                 profile
                     .file_mappings
                     .insert(hash_filename.clone(), path_str.clone());
+                config.record_file_mode(&profile_name, path_str, path_obj)?;
                 config.save_to_file(&_config_path)?;
+                crate::watch::record_sync_baseline(
+                    &_config_path,
+                    &profile_name,
+                    path_str,
+                    path_obj,
+                    config.file_options(&profile_name, path_str).managed_block,
+                )?;
                 if !args.quiet {
-                    let msg = format!(
-                        "Updated '{path_str}' for profile '{profile_name}' as '{hash_filename}'"
-                    );
+                    let msg = match &host {
+                        Some(host) => format!(
+                            "Updated '{path_str}' for profile '{profile_name}' on host '{host}' as '{host}/{hash_filename}'"
+                        ),
+                        None => format!(
+                            "Updated '{path_str}' for profile '{profile_name}' as '{hash_filename}'"
+                        ),
+                    };
                     if color_enabled() {
                         println!("{}", msg.green());
                     } else {
@@ -1108,10 +3663,22 @@ pub async fn run(args: Args) -> Result<()> {
                     }
                 }
             }
-            Ok(())
+
+            let message = match &path {
+                Some(path) if !all => format!("ordinator: add {path} to {profile_name}"),
+                _ => format!("ordinator: add all files to {profile_name}"),
+            };
+            auto_commit(&config, &_config_path, args.quiet, no_commit, &message).await
         }
 
-        Commands::Commit { message, force } => {
+        Commands::Commit {
+            message,
+            paths,
+            include_secrets,
+            exclude_secrets,
+            force,
+            no_sign,
+        } => {
             info!("Committing with message: {}", message);
             eprintln!("Committing with message: {message}");
 
@@ -1123,92 +3690,142 @@ pub async fn run(args: Args) -> Result<()> {
 
             if message.trim().is_empty() {
                 eprintln!("Commit message cannot be empty.");
-                std::process::exit(1);
+                crate::run_log::exit(1);
             }
 
             // Load config and get dotfiles repo path
             let (config, config_path) = Config::load()?;
             let dotfiles_path = config_path.parent().unwrap().to_path_buf();
-            let git_manager = GitManager::new(dotfiles_path.clone());
+            let mut git_config = config.git.clone();
+            if no_sign {
+                git_config.sign_commits = false;
+            }
+            let git_manager = GitManager::new(dotfiles_path.clone()).with_config(git_config);
             if !git_manager.exists() {
-                return Err(anyhow::anyhow!(
-                    "No Git repository found. Run 'ordinator init' first."
-                ));
+                return Err(OrdinatorError::RepositoryNotFound.into());
             }
 
-            // Scan for secrets before committing (unless --force is used)
-            if !force {
-                eprintln!("[DEBUG] Scanning for secrets before commit...");
-                let base_dir = config_path.parent().unwrap().to_path_buf();
-                let manager = crate::secrets::SecretsManager::new(
-                    None,
-                    None,
-                    config.clone(),
-                    base_dir.clone(),
-                );
-
-                let mut found_secrets = false;
-                let mut files_with_secrets = Vec::new();
-
-                // Scan all tracked files for secrets
-                for profile in config.profiles.values() {
-                    for file_path in &profile.files {
-                        let full_path = base_dir.join(file_path);
-                        eprintln!("[DEBUG] Scanning file: {full_path:?}");
-                        if full_path.exists() && full_path.is_file() {
-                            match manager.check_for_plaintext_secrets(&full_path) {
-                                Ok(has_secrets) => {
-                                    eprintln!(
-                                        "[DEBUG] File {full_path:?} has secrets: {has_secrets}"
-                                    );
-                                    if has_secrets {
-                                        found_secrets = true;
-                                        files_with_secrets.push(file_path.clone());
-                                        eprintln!(
-                                            "⚠️  Warning: '{file_path}' contains potential secrets"
-                                        );
-                                        match manager.get_secrets_info(&full_path) {
-                                            Ok(secret_types) => {
-                                                eprintln!("   Found: {}", secret_types.join(", "));
-                                            }
-                                            Err(_) => {
-                                                eprintln!("   Found: potential secrets");
-                                            }
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    eprintln!("[DEBUG] Error scanning file {full_path:?}: {e}");
-                                    if args.verbose {
-                                        eprintln!(
-                                            "Warning: Could not scan '{file_path}' for secrets: {e}"
-                                        );
-                                    }
-                                }
-                            }
-                        } else {
-                            eprintln!(
-                                "[DEBUG] File does not exist or is not a file: {full_path:?}"
-                            );
-                        }
+            // Hooks may reformat or regenerate files, so they run before the secrets scan
+            crate::hooks::run_for_default_profile(
+                &config,
+                &dotfiles_path,
+                HookEvent::PreCommit,
+                false,
+            )?;
+
+            // Paths that exist from here are taken relative to the current
+            // directory, anything else relative to the repository
+            let cwd = std::env::current_dir()?;
+            let paths: Vec<PathBuf> = paths
+                .into_iter()
+                .map(|path| {
+                    let from_cwd = cwd.join(&path);
+                    if path.is_relative() && from_cwd.exists() {
+                        from_cwd.canonicalize().unwrap_or(from_cwd)
+                    } else {
+                        path
                     }
-                }
+                })
+                .collect();
+            let repo_root = dotfiles_path
+                .canonicalize()
+                .unwrap_or(dotfiles_path.clone());
+            let mut paths: Vec<PathBuf> = paths
+                .into_iter()
+                .map(|path| match path.strip_prefix(&repo_root) {
+                    Ok(relative) => relative.to_path_buf(),
+                    Err(_) => path,
+                })
+                .collect();
+
+            // Keep the lock in step with what's committed
+            if crate::lock::refresh(&config, &dotfiles_path)? && !paths.is_empty() {
+                paths.push(PathBuf::from(crate::lock::LOCK_FILE));
+            }
+            if include_secrets && !paths.is_empty() {
+                paths.push(PathBuf::from("secrets"));
+            }
 
-                eprintln!("[DEBUG] Found secrets: {found_secrets}");
-                if found_secrets {
+            // Scan what's about to be staged for secrets (unless --force is used)
+            let pending = git_manager.pending_changes(&paths, !exclude_secrets)?;
+            if !force {
+                let scan =
+                    crate::scan::unaccepted_staged_findings(&config, &dotfiles_path, &pending)
+                        .await?;
+                warn_scan_timeouts(&scan);
+                if !scan.findings.is_empty() {
+                    print_scan_findings(&scan.findings, true);
                     eprintln!("⚠️  Plaintext secrets detected in tracked files");
                     eprintln!("   Consider encrypting with: ordinator secrets encrypt <file>");
+                    eprintln!(
+                        "   Or accept them with '# {}' or 'ordinator secrets scan --update-baseline'",
+                        crate::scan::ALLOW_MARKER
+                    );
+                }
+                if !scan.timed_out.is_empty() {
+                    eprintln!("⚠️  Not committing files that couldn't be scanned for secrets");
+                    eprintln!(
+                        "   Raise [secrets.scan] timeout, or list them in [secrets.scan] allow_files"
+                    );
+                }
+                if scan.blocks_commit() {
                     eprintln!("   Use --force to commit anyway");
-                    std::process::exit(1);
+                    crate::run_log::exit(1);
                 }
             }
+            if pending.is_empty() {
+                eprintln!("Nothing to commit");
+                return Ok(());
+            }
+            let staged = git_manager.stage(&paths, !exclude_secrets)?;
+            print_staged_changes(&staged);
+            if !crate::prompt::confirm("Commit these changes?", true) {
+                eprintln!("Commit cancelled; the changes are still staged");
+                return Ok(());
+            }
 
-            git_manager.commit(&message)?;
+            git_manager.commit_staged(&message)?;
             info!("Changes committed successfully");
             eprintln!("Changes committed successfully");
             Ok(())
         }
-        Commands::Push { repo_url, force } => {
+        Commands::Lock { check } => {
+            let (config, config_path) = Config::load()?;
+            let dotfiles_path = config_path.parent().unwrap();
+            if check {
+                let diff = crate::lock::check(&config, dotfiles_path)?;
+                if diff.is_empty() {
+                    eprintln!("✅ The repository matches {}", crate::lock::LOCK_FILE);
+                    return Ok(());
+                }
+                for line in diff.lines() {
+                    println!("{line}");
+                }
+                crate::run_log::exit(crate::exit_code::DRIFT);
+            }
+            if args.dry_run {
+                eprintln!("DRY-RUN: Would write {}", crate::lock::LOCK_FILE);
+                return Ok(());
+            }
+            let lock = crate::lock::write(&config, dotfiles_path)?;
+            if !args.quiet {
+                eprintln!(
+                    "✅ Wrote {} ({} files, {} secrets, {} packages)",
+                    crate::lock::LOCK_FILE,
+                    lock.files.len(),
+                    lock.secrets.len(),
+                    lock.packages.len()
+                );
+            }
+            Ok(())
+        }
+        Commands::Push {
+            repo_url,
+            force,
+            i_know_what_im_doing,
+            branch,
+            all_remotes,
+        } => {
             info!("Pushing changes{}", if force { " (force)" } else { "" });
             eprintln!("Pushing changes{}", if force { " (force)" } else { "" });
 
@@ -1224,40 +3841,27 @@ pub async fn run(args: Args) -> Result<()> {
                 return Ok(());
             }
 
-            // Load config and get dotfiles repo path
-            let (_config, config_path) = Config::load()?;
-            let dotfiles_path = config_path.parent().unwrap().to_path_buf();
-            let git_manager = GitManager::new(dotfiles_path.clone());
-            if !git_manager.exists() {
-                return Err(anyhow::anyhow!(
-                    "No Git repository found. Run 'ordinator init' first."
-                ));
-            }
-
-            // If repository URL is provided, validate it first
-            if let Some(url) = &repo_url {
-                // Validate the repository URL format
-                let repo_manager = crate::repo::RepoManager::new(dotfiles_path.clone());
-                match repo_manager.parse_github_url(url) {
-                    Ok(_) => {
-                        info!("Setting remote 'origin' to: {}", url);
-                        if !args.quiet {
-                            eprintln!("Setting remote 'origin' to: {url}");
-                        }
-                        git_manager.add_remote("origin", url)?;
-                    }
-                    Err(e) => {
-                        return Err(anyhow::anyhow!("Invalid repository URL '{}': {}", url, e));
-                    }
-                }
-            }
-
-            git_manager.push(force)?;
+            let (config, config_path) = Config::load()?;
+            let options = crate::push::PushOptions {
+                repo_url,
+                pull: false,
+                force,
+                skip_force_confirmation: i_know_what_im_doing,
+                branch,
+                all_remotes,
+                quiet: args.quiet,
+            };
+            crate::push::run(&config, config_path.parent().unwrap(), &options)?;
             info!("Changes pushed successfully");
             eprintln!("Changes pushed successfully");
             Ok(())
         }
-        Commands::Pull { rebase } => {
+        Commands::Pull {
+            rebase,
+            branch,
+            strategy,
+        } => {
+            let strategy: crate::config_merge::MergeStrategy = strategy.parse()?;
             info!("Pulling changes{}", if rebase { " (rebase)" } else { "" });
             eprintln!("Pulling changes{}", if rebase { " (rebase)" } else { "" });
 
@@ -1274,20 +3878,25 @@ pub async fn run(args: Args) -> Result<()> {
             }
 
             // Load config and get dotfiles repo path
-            let (_config, config_path) = Config::load()?;
+            let (config, config_path) = Config::load()?;
             let dotfiles_path = config_path.parent().unwrap().to_path_buf();
-            let git_manager = GitManager::new(dotfiles_path.clone());
+            let git_manager =
+                GitManager::new(dotfiles_path.clone()).with_config(config.git.clone());
             if !git_manager.exists() {
-                return Err(anyhow::anyhow!(
-                    "No Git repository found. Run 'ordinator init' first."
-                ));
+                return Err(OrdinatorError::RepositoryNotFound.into());
             }
-            git_manager.pull(rebase)?;
+            git_manager.pull_with_strategy(rebase, branch.as_deref(), strategy)?;
+            crate::push::record_sync(&config, true, false)?;
             info!("Changes pulled successfully");
             eprintln!("Changes pulled successfully");
             Ok(())
         }
-        Commands::Sync { force } => {
+        Commands::Sync {
+            force,
+            i_know_what_im_doing,
+            branch,
+            all_remotes,
+        } => {
             info!("Syncing repository{}", if force { " (force)" } else { "" });
             eprintln!("Syncing repository{}", if force { " (force)" } else { "" });
 
@@ -1303,25 +3912,72 @@ pub async fn run(args: Args) -> Result<()> {
                 return Ok(());
             }
 
-            // Load config and get dotfiles repo path
-            let (_config, config_path) = Config::load()?;
+            let (config, config_path) = Config::load()?;
+            let options = crate::push::PushOptions {
+                repo_url: None,
+                pull: true,
+                force,
+                skip_force_confirmation: i_know_what_im_doing,
+                branch,
+                all_remotes,
+                quiet: args.quiet,
+            };
+            crate::push::run(&config, config_path.parent().unwrap(), &options)?;
+            info!("Repository synced successfully");
+            eprintln!("Repository synced successfully");
+            Ok(())
+        }
+        Commands::Branch { subcommand } => {
+            let (config, config_path) = Config::load()?;
             let dotfiles_path = config_path.parent().unwrap().to_path_buf();
-            let git_manager = GitManager::new(dotfiles_path.clone());
+            let git_manager = GitManager::new(dotfiles_path).with_config(config.git);
             if !git_manager.exists() {
-                return Err(anyhow::anyhow!(
-                    "No Git repository found. Run 'ordinator init' first."
-                ));
+                return Err(OrdinatorError::RepositoryNotFound.into());
+            }
+            match subcommand {
+                BranchCommands::Create { name, from } => {
+                    if args.dry_run {
+                        eprintln!(
+                            "DRY-RUN: Would create branch '{name}' from {} and switch to it",
+                            from.as_deref().unwrap_or("the current commit")
+                        );
+                        return Ok(());
+                    }
+                    git_manager.create_branch(&name, from.as_deref())?;
+                    eprintln!("Created and switched to branch '{name}'");
+                }
+                BranchCommands::Switch { name } => {
+                    if args.dry_run {
+                        eprintln!("DRY-RUN: Would switch to branch '{name}'");
+                        return Ok(());
+                    }
+                    git_manager.switch_branch(&name)?;
+                    eprintln!("Switched to branch '{name}'");
+                }
+                BranchCommands::List => {
+                    for (name, current) in git_manager.list_branches()? {
+                        if current {
+                            println!("* {name}");
+                        } else {
+                            println!("  {name}");
+                        }
+                    }
+                }
             }
-            // Pull first, then push
-            git_manager.pull(false)?;
-            git_manager.push(force)?;
-            info!("Repository synced successfully");
-            eprintln!("Repository synced successfully");
             Ok(())
         }
-        Commands::Status { verbose } => {
+        Commands::Status {
+            verbose,
+            profile,
+            check,
+            short,
+            only,
+            skip,
+            remote,
+            fetch,
+        } => {
             info!("Showing status{}", if verbose { " (verbose)" } else { "" });
-            if !args.quiet {
+            if !args.quiet && !short {
                 eprintln!("Showing status{}", if verbose { " (verbose)" } else { "" });
             }
 
@@ -1340,559 +3996,785 @@ pub async fn run(args: Args) -> Result<()> {
             // Load config and get dotfiles repo path
             let (config, config_path) = Config::load()?;
             let dotfiles_path = config_path.parent().unwrap().to_path_buf();
-            let git_manager = GitManager::new(dotfiles_path.clone());
+            let state = crate::state::MachineState::load(&config.target_root()?)?;
+            let filter = crate::filter::PathFilter::new(&only, &skip)?;
+            let drift_profile = match profile {
+                Some(profile) if !config.profiles.contains_key(&profile) => {
+                    return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+                }
+                Some(profile) => Some(profile),
+                None => [
+                    state.current_profile.clone(),
+                    Some(config.global.default_profile.clone()),
+                ]
+                .into_iter()
+                .flatten()
+                .find(|p| config.profiles.contains_key(p)),
+            };
+
+            if remote {
+                let git_manager =
+                    GitManager::new(dotfiles_path.clone()).with_config(config.git.clone());
+                return report_remote_sync(&git_manager, &state, fetch, args.quiet);
+            }
+
+            // --check and --short only look at drift; with --check it decides the exit code
+            if check || short {
+                use crate::drift::Drift;
+                let Some(drift_profile) = drift_profile else {
+                    return Ok(());
+                };
+                let counts = report_drift(
+                    &config,
+                    &drift_profile,
+                    &dotfiles_path,
+                    &filter,
+                    verbose,
+                    short,
+                )?;
+                if !check {
+                    return Ok(());
+                }
+                if counts
+                    .iter()
+                    .any(|(drift, _)| *drift == Drift::ModifiedBoth)
+                {
+                    crate::run_log::exit(crate::exit_code::CONFLICTS);
+                }
+                if counts.iter().any(|(drift, _)| *drift != Drift::InSync) {
+                    crate::run_log::exit(crate::exit_code::DRIFT);
+                }
+                return Ok(());
+            }
+
+            let git_manager =
+                GitManager::new(dotfiles_path.clone()).with_config(config.git.clone());
 
-            // In test mode, treat .git dir as valid for status
-            let is_test_mode = std::env::var("ORDINATOR_TEST_MODE").unwrap_or_default() == "1";
-            let git_exists = dotfiles_path.join(".git").exists();
-            if is_test_mode && git_exists {
-                eprintln!("[TEST MODE] .git directory exists, simulating git status.");
-            } else if git_manager.exists() {
+            if git_manager.exists() {
                 let status = git_manager.status()?;
                 eprintln!("{status}");
+                report_remotes(&git_manager)?;
             } else {
                 eprintln!("No Git repository found. Showing symlink status only.");
             }
 
+            // What has been applied to this machine, from the state file
+            match state.current() {
+                Some((name, applied)) => {
+                    let commit = applied
+                        .commit
+                        .as_ref()
+                        .map(|commit| format!(", commit {commit}"))
+                        .unwrap_or_default();
+                    eprintln!(
+                        "Applied profile: {name} (applied {}{commit})",
+                        applied.applied_at_display()
+                    );
+                    let others: Vec<&str> = state
+                        .applied
+                        .keys()
+                        .map(String::as_str)
+                        .filter(|other| *other != name)
+                        .collect();
+                    if !others.is_empty() {
+                        eprintln!("Also applied: {}", others.join(", "));
+                    }
+                }
+                None => eprintln!("Applied profile: none (run 'ordinator apply')"),
+            }
+            if let Some(warning) = crate::rotation::key_age_warning(&config, &dotfiles_path)? {
+                eprintln!("⚠️  Warning: {warning}");
+            }
+            if let Some(profile) = &drift_profile {
+                if let Some(warning) = crate::brew::outdated_warning(&config, profile)? {
+                    eprintln!("⚠️  Warning: {warning}");
+                }
+            }
+
+            // Compare deployed files with their tracked copies
+            if let Some(drift_profile) = drift_profile {
+                report_drift(
+                    &config,
+                    &drift_profile,
+                    &dotfiles_path,
+                    &filter,
+                    verbose,
+                    false,
+                )?;
+            }
+
             // Show symlink status if verbose
             if verbose {
                 eprintln!("\nSymlink Status:");
-                use crate::utils::{get_home_dir, is_broken_symlink, is_symlink};
-                let home_dir = get_home_dir()?;
+                use crate::utils::{is_broken_symlink, is_symlink};
+                let home_dir = config.target_root()?;
                 let _dotfiles_dir = config_path.parent().unwrap();
                 let mut total_files = 0;
                 let mut valid_symlinks = 0;
                 let mut broken_symlinks = 0;
                 let mut missing_files = 0;
+                let mut rendered_templates = 0;
+                let mut decrypted_files = 0;
+                let mut managed_blocks = 0;
 
                 for profile_name in config.list_profiles() {
-                    if let Some(profile_cfg) = config.get_profile(profile_name) {
-                        eprintln!("  Profile: {profile_name}");
-                        for file in &profile_cfg.files {
-                            total_files += 1;
-                            let dest = home_dir.join(file);
-
-                            if !dest.exists() {
-                                eprintln!("    {}: Missing", dest.display());
-                                missing_files += 1;
-                            } else if is_broken_symlink(&dest) {
-                                eprintln!("    {}: Broken symlink", dest.display());
-                                broken_symlinks += 1;
-                            } else if is_symlink(&dest) {
-                                eprintln!("    {}: Valid symlink", dest.display());
-                                valid_symlinks += 1;
+                    let resolved = config.resolve_profile(profile_name)?;
+                    eprintln!("  Profile: {profile_name}");
+                    let mut rows = Vec::new();
+                    for entry in &resolved.files {
+                        if !filter.matches(&entry.path) {
+                            continue;
+                        }
+                        total_files += 1;
+                        let dest = crate::paths::destination(
+                            &home_dir,
+                            crate::template::rendered_path(&entry.path),
+                        );
+                        let provenance = if &entry.profile != profile_name {
+                            format!(" (inherited from '{}')", entry.profile)
+                        } else {
+                            String::new()
+                        };
+
+                        let (state, tone) = if !dest.exists() {
+                            missing_files += 1;
+                            ("Missing", Tone::Drift)
+                        } else if is_broken_symlink(&dest) {
+                            broken_symlinks += 1;
+                            ("Broken symlink", Tone::Broken)
+                        } else if is_symlink(&dest)
+                            && !config
+                                .get_source_file_path(&entry.profile, &entry.path)
+                                .is_ok_and(|source| crate::utils::symlink_points_to(&dest, &source))
+                        {
+                            broken_symlinks += 1;
+                            ("Symlink to another location", Tone::Broken)
+                        } else if is_symlink(&dest) {
+                            valid_symlinks += 1;
+                            ("Valid symlink", Tone::Good)
+                        } else if config
+                            .file_options(&entry.profile, &entry.path)
+                            .managed_block
+                        {
+                            if crate::block::read(&dest)?.is_some() {
+                                managed_blocks += 1;
+                                ("Managed block", Tone::Good)
                             } else {
-                                eprintln!("    {}: File (not symlinked)", dest.display());
                                 missing_files += 1;
+                                ("File (no managed block)", Tone::Drift)
                             }
-                        }
+                        } else if crate::template::is_template(&entry.path) {
+                            rendered_templates += 1;
+                            ("Rendered template", Tone::Good)
+                        } else if config.file_options(&entry.profile, &entry.path).encrypt {
+                            decrypted_files += 1;
+                            ("Decrypted file", Tone::Good)
+                        } else {
+                            missing_files += 1;
+                            ("File (not symlinked)", Tone::Drift)
+                        };
+                        rows.push((
+                            dest.display().to_string(),
+                            state.to_string(),
+                            tone,
+                            provenance,
+                        ));
                     }
+                    print_status_rows("    ", &rows);
                 }
 
                 eprintln!("\nSummary:");
                 eprintln!("  Total tracked files: {total_files}");
-                eprintln!("  Valid symlinks: {valid_symlinks}");
-                eprintln!("  Broken symlinks: {broken_symlinks}");
-                eprintln!("  Missing/not symlinked: {missing_files}");
+                let count = |count: usize, tone: Tone| {
+                    if count == 0 {
+                        count.to_string()
+                    } else {
+                        paint(&count.to_string(), tone)
+                    }
+                };
+                eprintln!("  Valid symlinks: {}", count(valid_symlinks, Tone::Good));
+                eprintln!(
+                    "  Broken symlinks: {}",
+                    count(broken_symlinks, Tone::Broken)
+                );
+                eprintln!("  Rendered templates: {rendered_templates}");
+                if decrypted_files > 0 {
+                    eprintln!("  Decrypted files: {decrypted_files}");
+                }
+                if managed_blocks > 0 {
+                    eprintln!("  Managed blocks: {managed_blocks}");
+                }
+                eprintln!(
+                    "  Missing/not symlinked: {}",
+                    count(missing_files, Tone::Drift)
+                );
             }
 
             Ok(())
         }
-        Commands::Apply {
+        Commands::Diff {
+            path,
             profile,
-            skip_bootstrap,
-            skip_secrets,
-            skip_brew,
-            force,
+            stat,
+            only,
+            skip,
         } => {
-            let (config, config_path) = Config::load()?;
-            if !config.profiles.contains_key(&profile) {
-                return Err(anyhow::anyhow!("Profile '{profile}' does not exist."));
-            }
-            info!("Applying profile: {}", profile);
-            if !args.quiet {
-                eprintln!("Applying profile: {profile}");
-            }
-            if skip_bootstrap {
-                info!("Skipping bootstrap");
-                if !args.quiet {
-                    eprintln!("Skipping bootstrap");
-                }
-            }
-            if skip_secrets {
-                info!("Skipping secrets");
-                if !args.quiet {
-                    eprintln!("Skipping secrets");
-                }
-            }
-
+            info!("Showing diff for profile: {}", profile);
             if args.dry_run {
-                info!("[DRY RUN] Would apply profile: {}", profile);
-                eprintln!("DRY-RUN: Would apply profile: {profile}");
-                if skip_bootstrap {
-                    info!("[DRY RUN] Would skip bootstrap");
-                    eprintln!("DRY-RUN: Would skip bootstrap");
-                }
-                if skip_secrets {
-                    info!("[DRY RUN] Would skip secrets");
-                    eprintln!("DRY-RUN: Would skip secrets");
-                }
-                if skip_brew {
-                    info!("[DRY RUN] Would skip brew");
-                    eprintln!("DRY-RUN: Would skip brew");
-                }
+                eprintln!("DRY-RUN: Would show diff for profile '{profile}'");
                 return Ok(());
             }
 
-            // Debug: print config information
-            eprintln!("[DEBUG] Config loaded from: {}", config_path.display());
-            eprintln!("[DEBUG] Requested profile: '{profile}'");
-            eprintln!(
-                "[DEBUG] Profile exists: {}",
-                config.profiles.contains_key(&profile)
-            );
-            eprintln!("[DEBUG] Available profiles: {:?}", config.list_profiles());
-            eprintln!(
-                "[DEBUG] Profile files count: {}",
-                config.get_profile(&profile).unwrap().files.len()
-            );
-            eprintln!(
-                "[DEBUG] Profile files: {:?}",
-                config.get_profile(&profile).unwrap().files
-            );
-
-            // Debug: print config file content
-            match std::fs::read_to_string(&config_path) {
-                Ok(content) => {
-                    eprintln!("[DEBUG] Config file content:");
-                    eprintln!("{content}");
-                }
-                Err(e) => {
-                    eprintln!("[DEBUG] Failed to read config file: {e}");
-                }
+            let (config, config_path) = Config::load()?;
+            if !config.profiles.contains_key(&profile) {
+                return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
             }
 
-            // For each tracked file, symlink with enhanced conflict resolution
-            use crate::utils::{
-                create_symlink_with_conflict_resolution, get_home_dir, get_symlink_target,
-                is_symlink,
-            };
-            let home_dir = get_home_dir()?;
-            let _dotfiles_dir = config_path.parent().unwrap();
-
-            // Debug: print profile file list
-            eprintln!(
-                "[DEBUG] Profile '{}' has {} files:",
-                profile,
-                config.get_profile(&profile).unwrap().files.len()
-            );
-            for file in &config.get_profile(&profile).unwrap().files {
-                eprintln!("[DEBUG]   - {file}");
+            let home_dir = config.target_root()?;
+            let mut resolved = config.resolve_profile(&profile)?;
+            if resolved
+                .files
+                .iter()
+                .any(|entry| crate::template::is_template(&entry.path))
+            {
+                let sources = config.secret_value_profiles(&[], &[], &resolved.chain)?;
+                let dotfiles_dir = config_path.parent().unwrap();
+                crate::secrets::add_secret_values(dotfiles_dir, &sources, &mut resolved.vars)?;
             }
-
-            for file in &config.get_profile(&profile).unwrap().files {
-                // Get the source file path (profile-specific or fallback to flat structure)
-                let source_path = config.get_source_file_path(&profile, file)?;
-                let dest = home_dir.join(file);
-
-                eprintln!("[DEBUG] Checking file: {file}");
-                eprintln!("[DEBUG] Source: {}", source_path.display());
-                eprintln!("[DEBUG] Dest: {}", dest.display());
-
-                if !source_path.exists() {
-                    return handle_missing_source_file(file, &source_path, &dest);
-                }
-
-                if !dest.exists() {
-                    // Create new symlink
-                    let msg = format!(
-                        "[{}] Symlinking {} -> {}",
-                        file,
-                        dest.display(),
-                        source_path.display()
-                    );
-                    if !args.quiet && color_enabled() {
-                        println!("{}", msg.cyan());
-                    } else if !args.quiet {
-                        println!("{msg}");
-                    }
-                    if args.dry_run {
-                        let msg = format!(
-                            "DRY-RUN: Would create symlink {} -> {}",
-                            dest.display(),
-                            source_path.display()
-                        );
-                        if color_enabled() {
-                            println!("{}", msg.yellow());
-                        } else {
-                            println!("{msg}");
-                        }
-                    } else {
-                        create_symlink_with_conflict_resolution(
-                            &source_path,
-                            &dest,
-                            force,
-                            config.global.create_backups.unwrap_or(true),
-                            &config_path,
-                        )?;
-                        if !args.quiet {
-                            let msg = format!(
-                                "Symlinked: {} -> {}",
-                                dest.display(),
-                                source_path.display()
-                            );
-                            if color_enabled() {
-                                println!("{}", msg.green());
-                            } else {
-                                println!("{msg}");
-                            }
+            let entries: Vec<_> = match &path {
+                Some(path) => {
+                    let wanted = crate::paths::destination(&home_dir, path);
+                    let matches: Vec<_> = resolved
+                        .files
+                        .iter()
+                        .filter(|entry| {
+                            let rendered = crate::template::rendered_path(&entry.path);
+                            entry.path == *path
+                                || rendered == path
+                                || crate::paths::destination(&home_dir, rendered) == wanted
+                        })
+                        .collect();
+                    if matches.is_empty() {
+                        return Err(OrdinatorError::FileNotTracked {
+                            path: path.to_string(),
+                            profile: profile.to_string(),
                         }
+                        .into());
                     }
+                    matches
+                }
+                None => resolved.files.iter().collect(),
+            };
+            let filter = crate::filter::PathFilter::new(&only, &skip)?;
+            let entries = entries
+                .into_iter()
+                .filter(|entry| filter.matches(&entry.path));
+
+            let mut files_changed = 0;
+            let mut total = crate::diff::DiffStat::default();
+            for entry in entries {
+                // Templates are compared by their rendered output
+                let display_path = crate::template::rendered_path(&entry.path);
+                let live = crate::paths::destination(&home_dir, display_path);
+                let tracked = config.get_source_file_path(&entry.profile, &entry.path)?;
+
+                if !tracked.exists() {
+                    eprintln!(
+                        "{}: tracked copy missing ({})",
+                        entry.path,
+                        tracked.display()
+                    );
+                    continue;
+                }
+                if !live.exists() {
+                    eprintln!("{display_path}: missing from home directory");
                     continue;
                 }
 
-                if !is_symlink(&dest) {
-                    // Handle non-symlink conflict
-                    let msg = format!(
-                        "Conflict: {} already exists and is not a symlink",
-                        dest.display()
+                let old_bytes = if config.file_options(&entry.profile, &entry.path).encrypt {
+                    crate::secrets::decrypt_tracked_file(&tracked)?
+                } else if crate::template::is_template(&entry.path) {
+                    crate::template::render_file(&tracked, &resolved.vars)?.into_bytes()
+                } else {
+                    std::fs::read(&tracked)?
+                };
+                // A managed block is compared without the rest of the file
+                let (old_bytes, new_bytes) = if config
+                    .file_options(&entry.profile, &entry.path)
+                    .managed_block
+                {
+                    (
+                        crate::block::normalize(&String::from_utf8_lossy(&old_bytes)).into_bytes(),
+                        crate::block::read(&live)?.unwrap_or_default().into_bytes(),
+                    )
+                } else {
+                    (old_bytes, std::fs::read(&live)?)
+                };
+                if old_bytes == new_bytes {
+                    continue;
+                }
+                files_changed += 1;
+
+                let (Ok(old), Ok(new)) =
+                    (String::from_utf8(old_bytes), String::from_utf8(new_bytes))
+                else {
+                    println!(
+                        "Binary files {} and {} differ",
+                        tracked.display(),
+                        live.display()
                     );
+                    continue;
+                };
+
+                if stat {
+                    let file_stat = crate::diff::diff_stat(&old, &new);
+                    total.insertions += file_stat.insertions;
+                    total.deletions += file_stat.deletions;
+                    let plus = "+".repeat(file_stat.insertions.min(40));
+                    let minus = "-".repeat(file_stat.deletions.min(40));
+                    let changes = file_stat.insertions + file_stat.deletions;
                     if color_enabled() {
-                        eprintln!("{}", msg.red());
-                        eprintln!(
-                            "{}",
-                            "Use --force to overwrite, or manually remove the file first.".yellow()
-                        );
-                    } else {
-                        eprintln!("{msg}");
-                        eprintln!("Use --force to overwrite, or manually remove the file first.");
-                    }
-                    if !force {
-                        return Err(anyhow::anyhow!(
-                            "Target {} already exists and is not a symlink. Use --force to overwrite.",
-                            dest.display()
-                        ));
-                    }
-                    // Force overwrite - create symlink
-                    let msg = format!(
-                        "Force creating symlink: {} -> {}",
-                        dest.display(),
-                        source_path.display()
-                    );
-                    if !args.quiet && color_enabled() {
-                        println!("{}", msg.yellow());
-                    } else if !args.quiet {
-                        println!("{msg}");
-                    }
-                    if args.dry_run {
-                        let msg = format!(
-                            "DRY-RUN: Would force create symlink {} -> {}",
-                            dest.display(),
-                            source_path.display()
+                        println!(
+                            " {display_path} | {changes} {}{}",
+                            plus.green(),
+                            minus.red()
                         );
-                        if color_enabled() {
-                            println!("{}", msg.yellow());
-                        } else {
-                            println!("{msg}");
-                        }
                     } else {
-                        create_symlink_with_conflict_resolution(
-                            &source_path,
-                            &dest,
-                            force,
-                            config.global.create_backups.unwrap_or(true),
-                            &config_path,
-                        )?;
-                        if !args.quiet {
-                            let msg = format!(
-                                "Symlinked: {} -> {}",
-                                dest.display(),
-                                source_path.display()
-                            );
-                            if color_enabled() {
-                                println!("{}", msg.green());
-                            } else {
-                                println!("{msg}");
-                            }
-                        }
+                        println!(" {display_path} | {changes} {plus}{minus}");
                     }
                     continue;
                 }
 
-                // Check if existing symlink is broken or points to wrong target
-                let needs_repair = match get_symlink_target(&dest) {
-                    Ok(actual_target) => {
-                        eprintln!("[DEBUG] actual_target: {}", actual_target.display());
-                        eprintln!("[DEBUG] expected source: {}", source_path.display());
-                        eprintln!("[DEBUG] actual_target.exists(): {}", actual_target.exists());
-                        let needs = actual_target != source_path || !actual_target.exists();
-                        eprintln!("[DEBUG] needs_repair: {needs}");
-                        needs
-                    }
-                    Err(e) => {
-                        eprintln!(
-                            "[DEBUG] Could not read symlink target for {}: {}",
-                            dest.display(),
-                            e
-                        );
-                        true // Can't read symlink target, assume broken
-                    }
-                };
-
-                eprintln!(
-                    "[DEBUG] After needs_repair check: {} => {}",
-                    dest.display(),
-                    needs_repair
+                let diff = crate::diff::unified_diff(
+                    &format!("a/{display_path}"),
+                    &format!("b/{display_path}"),
+                    &old,
+                    &new,
                 );
-
-                if needs_repair {
-                    eprintln!("[DEBUG] Entering repair branch for {}", dest.display());
-                    if args.dry_run {
-                        eprintln!("DRY-RUN: Would repair {}", dest.display());
+                for line in diff.lines() {
+                    if !color_enabled() {
+                        println!("{line}");
+                    } else if line.starts_with("+++") || line.starts_with("---") {
+                        println!("{}", line.bold());
+                    } else if line.starts_with('+') {
+                        println!("{}", line.green());
+                    } else if line.starts_with('-') {
+                        println!("{}", line.red());
+                    } else if line.starts_with("@@") {
+                        println!("{}", line.cyan());
                     } else {
-                        use crate::utils::repair_symlink;
-                        repair_symlink(&dest, &source_path)?;
-                        if !args.quiet {
-                            eprintln!("Repaired: {} -> {}", dest.display(), source_path.display());
-                        }
+                        println!("{line}");
                     }
-                } else if args.verbose {
-                    eprintln!("  {}: Valid symlink", dest.display());
                 }
             }
 
-            // Check for bootstrap script if not skipped
-            if !skip_bootstrap {
-                if let Some(script_path) = config.get_bootstrap_script(&profile) {
-                    let full_script_path = _dotfiles_dir.join(&script_path);
+            if files_changed == 0 {
+                eprintln!("No differences for profile '{profile}'.");
+            } else if stat {
+                println!(
+                    " {files_changed} file(s) changed, {} insertion(s)(+), {} deletion(s)(-)",
+                    total.insertions, total.deletions
+                );
+            }
 
-                    if full_script_path.exists() {
-                        if !args.quiet {
-                            eprintln!("Bootstrap script found: {}", full_script_path.display());
-                            eprintln!("To run the bootstrap script, execute:");
-                            eprintln!("  bash {}", full_script_path.display());
-                        }
-                        info!("Bootstrap script available: {}", full_script_path.display());
-                    } else {
-                        if !args.quiet {
-                            eprintln!("No bootstrap script found for profile '{profile}'");
-                            eprintln!("To create one, run: ordinator bootstrap generate --profile {profile}");
-                        }
-                        info!("No bootstrap script found for profile '{}'", profile);
-                    }
+            Ok(())
+        }
+        Commands::Plan {
+            profile,
+            skip_secrets,
+            skip_brew,
+            skip_defaults,
+            check,
+        } => {
+            let (config, _) = Config::load()?;
+            let profile = profile.unwrap_or_else(|| auto_select_profile(&config, args.quiet));
+            if !config.profiles.contains_key(&profile) {
+                return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+            }
+            let home_dir = config.target_root()?;
+            let plan = crate::plan::build(
+                &config,
+                &profile,
+                &home_dir,
+                skip_secrets,
+                skip_brew,
+                skip_defaults,
+            )
+            .await?;
+            for skipped in &plan.shells_skipped {
+                eprintln!("Skipping {} file(s): {skipped}", skipped.files.len());
+            }
+            if plan.is_empty() {
+                eprintln!("No changes. Profile '{profile}' is up to date.");
+                return Ok(());
+            }
+            if check {
+                eprintln!("Plan: {}", plan.summary());
+                let conflict = plan
+                    .changes
+                    .iter()
+                    .any(|change| change.action == crate::plan::PlanAction::Conflict);
+                crate::run_log::exit(if conflict {
+                    crate::exit_code::CONFLICTS
                 } else {
-                    if !args.quiet {
-                        eprintln!("No bootstrap script configured for profile '{profile}'");
-                        eprintln!(
-                            "To create one, run: ordinator bootstrap generate --profile {profile}"
-                        );
-                    }
-                    info!("No bootstrap script configured for profile '{}'", profile);
-                }
+                    crate::exit_code::DRIFT
+                });
+            }
+            println!("Plan for profile '{profile}':");
+            for change in &plan.changes {
+                println!("  {change}");
+            }
+            let summary = format!("Plan: {}", plan.summary());
+            if color_enabled() {
+                println!("{}", summary.yellow());
             } else {
-                info!("Skipped bootstrap script check");
-                if !args.quiet {
-                    eprintln!("Skipped bootstrap script check");
+                println!("{summary}");
+            }
+            // Distinct from errors (1) so CI can tell "out of date" from "broken"
+            crate::run_log::exit(crate::exit_code::DRIFT);
+        }
+        Commands::Why { path, json } => {
+            let (config, config_path) = Config::load()?;
+            let home_dir = config.target_root()?;
+            let dotfiles_dir = config_path.parent().unwrap();
+            let target = crate::why::resolve_path(&path, &home_dir)?;
+            let state = crate::state::MachineState::load(&home_dir)?;
+            let found = crate::why::explain(&config, &target, &home_dir, dotfiles_dir, &state)?;
+
+            if found.is_empty() {
+                let mut message = format!("'{}' isn't tracked by any profile.", target.display());
+                let tracked_dir = config.profiles.values().any(|profile| {
+                    profile.directories.iter().any(|directory| {
+                        crate::utils::same_path(
+                            &crate::paths::destination(&home_dir, &directory.path),
+                            &target,
+                        )
+                    })
+                });
+                if tracked_dir {
+                    message.push_str(" It's a tracked directory; ask about a file inside it.");
+                } else if let Some(link) =
+                    crate::utils::get_symlink_target(&target)
+                        .ok()
+                        .filter(|link| {
+                            crate::utils::canonical_path(link)
+                                .starts_with(crate::utils::canonical_path(dotfiles_dir))
+                        })
+                {
+                    message.push_str(&format!(
+                        " It's a symlink to {}, a copy in the repository that no profile tracks anymore.",
+                        link.display()
+                    ));
                 }
+                return Err(anyhow::anyhow!(message));
+            }
+            if json {
+                println!("{}", serde_json::to_string_pretty(&found)?);
+                return Ok(());
+            }
+            println!("{}", target.display());
+            for (index, provenance) in found.iter().enumerate() {
+                if index > 0 {
+                    println!();
+                }
+                print_provenance(provenance);
+            }
+            Ok(())
+        }
+        Commands::Files {
+            profile,
+            filter,
+            json,
+        } => {
+            let (config, config_path) = Config::load()?;
+            let home_dir = config.target_root()?;
+            let profile = match profile {
+                Some(profile) => profile,
+                None => {
+                    let state = crate::state::MachineState::load(&home_dir)?;
+                    state
+                        .current_profile
+                        .filter(|p| config.profiles.contains_key(p))
+                        .unwrap_or_else(|| config.global.default_profile.clone())
+                }
+            };
+            if !config.profiles.contains_key(&profile) {
+                return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+            }
+            let dotfiles_dir = config_path.parent().unwrap();
+            let mut entries = crate::listing::list(&config, &profile, &home_dir, dotfiles_dir)?;
+            if let Some(filter) = filter {
+                entries.retain(|entry| entry.matches(filter));
             }
 
-            // Handle secrets decryption if not skipped
-            if !skip_secrets {
-                use crate::secrets::{
-                    age_key_exists, decrypt_file_with_sops, handle_interactive_age_key_setup,
-                    is_file_encrypted,
-                };
-                use std::fs;
-
-                let profile_config = config.get_profile(&profile).unwrap();
-                let mut skip_secrets_decryption = false;
-                if !profile_config.secrets.is_empty() {
-                    // Check if age key exists before attempting decryption
-                    if !age_key_exists(&profile) {
-                        if !args.quiet {
-                            eprintln!("AGE key not found for profile '{profile}'");
-                        }
-
-                        if args.dry_run {
-                            eprintln!("DRY-RUN: Would prompt for age key setup");
-                        } else {
-                            // Handle interactive age key setup
-                            match handle_interactive_age_key_setup(&profile) {
-                                Ok(()) => {
-                                    if !args.quiet {
-                                        eprintln!("AGE key setup completed successfully");
-                                    }
-                                }
-                                Err(e) => {
-                                    if !args.quiet {
-                                        eprintln!("AGE key setup failed: {e}");
-                                        eprintln!(
-                                            "Continuing with apply without secrets decryption"
-                                        );
-                                    }
-                                    // Set flag to skip secrets decryption
-                                    skip_secrets_decryption = true;
-                                }
-                            }
-                        }
+            if json {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+                return Ok(());
+            }
+            if entries.is_empty() {
+                if filter.is_some() {
+                    eprintln!("No tracked files in profile '{profile}' match the filter.");
+                } else {
+                    eprintln!("No tracked files in profile '{profile}'.");
+                }
+                return Ok(());
+            }
+            let rows: Vec<[String; 6]> = entries
+                .iter()
+                .map(|entry| {
+                    let repo_path = if entry.in_repo {
+                        entry.repo_path.display().to_string()
+                    } else {
+                        format!("{} (missing)", entry.repo_path.display())
+                    };
+                    let hash = match entry.hash_match {
+                        Some(true) => "match",
+                        Some(false) => "differs",
+                        None => "-",
+                    };
+                    let secret = match entry.secret {
+                        Some(crate::listing::SecretState::Encrypted) => "encrypted",
+                        Some(crate::listing::SecretState::NotEncrypted) => "not encrypted",
+                        None => "-",
+                    };
+                    [
+                        repo_path,
+                        entry.target.display().to_string(),
+                        entry.state.label().to_string(),
+                        entry
+                            .added_at
+                            .as_deref()
+                            .map(crate::utils::format_timestamp)
+                            .unwrap_or_else(|| "-".to_string()),
+                        hash.to_string(),
+                        secret.to_string(),
+                    ]
+                })
+                .collect();
+            let header = ["REPO PATH", "TARGET", "STATE", "ADDED", "HASH", "SECRET"];
+            let mut widths = header.map(str::len);
+            for row in &rows {
+                for (width, cell) in widths.iter_mut().zip(row) {
+                    *width = (*width).max(cell.chars().count());
+                }
+            }
+            let line = |cells: [&str; 6]| {
+                let padded: Vec<String> = cells
+                    .iter()
+                    .zip(widths)
+                    .map(|(cell, width)| format!("{cell:width$}"))
+                    .collect();
+                padded.join("  ").trim_end().to_string()
+            };
+            println!("{}", line(header));
+            for (row, entry) in rows.iter().zip(&entries) {
+                let text = line(row.each_ref().map(String::as_str));
+                let needs_attention = entry.hash_match == Some(false)
+                    || entry.matches(crate::listing::FileFilter::Broken)
+                    || entry.matches(crate::listing::FileFilter::Missing)
+                    || entry.secret == Some(crate::listing::SecretState::NotEncrypted);
+                if needs_attention && color_enabled() {
+                    println!("{}", text.yellow());
+                } else {
+                    println!("{text}");
+                }
+            }
+            Ok(())
+        }
+        Commands::Search {
+            pattern,
+            profile,
+            ignore_case,
+            fixed_strings,
+            secrets,
+        } => {
+            let (config, config_path) = Config::load()?;
+            let regex = crate::search::build_pattern(&pattern, ignore_case, fixed_strings)?;
+            let profiles = match profile {
+                Some(profile) => {
+                    if !config.profiles.contains_key(&profile) {
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
                     }
+                    vec![profile]
+                }
+                None => {
+                    let mut names: Vec<String> = config.profiles.keys().cloned().collect();
+                    names.sort();
+                    names
+                }
+            };
 
-                    if !skip_secrets_decryption {
-                        if !args.quiet {
-                            eprintln!("Decrypting secrets for profile '{profile}'");
-                        }
-
-                        for secret_path in &profile_config.secrets {
-                            // Get the encrypted file path in the repository
-                            let encrypted_file_path =
-                                config.get_source_file_path(&profile, secret_path)?;
-
-                            if !encrypted_file_path.exists() {
-                                if !args.quiet {
-                                    eprintln!(
-                                        "Warning: Encrypted secret file not found: {}",
-                                        encrypted_file_path.display()
-                                    );
-                                }
-                                continue;
-                            }
-
-                            // Check if the file is actually encrypted
-                            if !is_file_encrypted(&encrypted_file_path) {
-                                if !args.quiet {
-                                    eprintln!(
-                                        "Warning: File does not appear to be encrypted: {}",
-                                        encrypted_file_path.display()
-                                    );
-                                }
-                                continue;
-                            }
-
-                            // Determine the target path where the decrypted file should be placed
-                            let home_dir = crate::utils::get_home_dir()?;
-                            let target_path = home_dir.join(secret_path);
-
-                            if args.dry_run {
-                                if !args.quiet {
-                                    eprintln!(
-                                        "DRY-RUN: Would decrypt {} to {}",
-                                        encrypted_file_path.display(),
-                                        target_path.display()
-                                    );
-                                }
-                            } else {
-                                // Create a temporary file for decryption
-                                let temp_dir = tempfile::tempdir()?;
-                                let temp_decrypted = temp_dir.path().join("decrypted_secret");
-
-                                // Copy the encrypted file to temp location
-                                fs::copy(&encrypted_file_path, &temp_decrypted)?;
-
-                                // Decrypt the temporary file
-                                let decryption_result = match decrypt_file_with_sops(
-                                    temp_decrypted.to_str().unwrap(),
-                                ) {
-                                    Ok(()) => Ok(()),
-                                    Err(e) => {
-                                        // Check if this is a key mismatch error
-                                        use crate::secrets::handle_key_mismatch_error;
-                                        match handle_key_mismatch_error(&encrypted_file_path, &e) {
-                                            Ok(true) => {
-                                                // Skip this file
-                                                continue;
-                                            }
-                                            Ok(false) => {
-                                                // Retry decryption with new key
-                                                decrypt_file_with_sops(
-                                                    temp_decrypted.to_str().unwrap(),
-                                                )
-                                            }
-                                            Err(e) => {
-                                                return Err(e);
-                                            }
-                                        }
-                                    }
-                                };
-
-                                decryption_result?;
-
-                                // Read the decrypted content
-                                let decrypted_content = fs::read_to_string(&temp_decrypted)?;
-
-                                // Ensure the target directory exists
-                                if let Some(parent) = target_path.parent() {
-                                    fs::create_dir_all(parent)?;
-                                }
-
-                                // Copy the decrypted content to the target location
-                                fs::write(&target_path, decrypted_content)?;
-
-                                // Set appropriate permissions (600 for secrets)
-                                let mut perms = fs::metadata(&target_path)?.permissions();
-                                perms.set_mode(0o600);
-                                fs::set_permissions(&target_path, perms)?;
-
-                                if !args.quiet {
-                                    eprintln!(
-                                        "Decrypted: {} -> {}",
-                                        encrypted_file_path.display(),
-                                        target_path.display()
-                                    );
-                                }
-                            }
-                        }
-                    }
-                } else if !args.quiet {
-                    eprintln!("No secrets configured for profile '{profile}'");
+            let mut matches = crate::search::search_files(&config, &profiles, &regex)?;
+            if secrets {
+                let entries = crate::search::secret_entries(&config, &profiles)?;
+                if entries.is_empty() {
+                    eprintln!("No tracked secrets to search");
+                } else if args.dry_run {
+                    eprintln!(
+                        "DRY-RUN: Would decrypt {} secret(s) in memory to search them",
+                        entries.len()
+                    );
+                } else if crate::prompt::confirm(
+                    &format!(
+                        "Decrypt {} secret(s) in memory to search them?",
+                        entries.len()
+                    ),
+                    false,
+                ) {
+                    let base_dir = config_path.parent().unwrap();
+                    matches.extend(crate::search::search_secrets(
+                        &config, base_dir, &entries, &regex,
+                    )?);
+                } else {
+                    eprintln!("Skipping secrets; pass --yes to decrypt them without asking");
                 }
-            } else {
-                info!("Skipped secrets decryption");
+            }
+
+            if matches.is_empty() {
                 if !args.quiet {
-                    eprintln!("Skipped secrets decryption");
+                    eprintln!("No matches for '{pattern}'");
                 }
+                crate::run_log::exit(1);
             }
-
-            // Install Homebrew packages if not skipped
-            if !skip_brew {
-                use crate::brew::BrewManager;
-
-                // Check if Homebrew is installed
-                if BrewManager::check_homebrew_installed() {
-                    let brew_manager = BrewManager::new(args.dry_run);
-
-                    if !args.quiet {
-                        eprintln!("Installing Homebrew packages for profile '{profile}'");
-                    }
-
-                    if let Err(e) = brew_manager.install_packages(&profile, &config).await {
-                        if !args.quiet {
-                            eprintln!("Warning: Failed to install Homebrew packages: {e}");
-                        }
-                    } else if !args.quiet {
-                        eprintln!("✅ Homebrew packages installed successfully");
-                    }
+            for found in &matches {
+                let location = if found.secret {
+                    format!("{}:{} (secret)", found.profile, found.path)
                 } else {
-                    if !args.quiet {
-                        eprintln!("⚠️  Homebrew not installed - skipping package installation");
+                    format!("{}:{}", found.profile, found.path)
+                };
+                if color_enabled() {
+                    println!(
+                        "{}:{}:{}",
+                        location.magenta(),
+                        found.line.to_string().green(),
+                        found.text
+                    );
+                } else {
+                    println!("{location}:{}:{}", found.line, found.text);
+                }
+            }
+            Ok(())
+        }
+        Commands::Setup {
+            repo_url,
+            profile,
+            skip_secrets,
+            skip_brew,
+            skip_bootstrap,
+            force,
+            restart,
+        } => {
+            let options = crate::setup::SetupOptions {
+                repo_url,
+                // Where apply and the other commands look for tracked files
+                dotfiles_dir: crate::config::get_dotfiles_dir()?,
+                profile,
+                skip_secrets,
+                skip_brew,
+                skip_bootstrap,
+                force,
+                restart,
+                jobs: crate::utils::default_jobs(),
+                progress: args.progress,
+                dry_run: args.dry_run,
+                quiet: args.quiet,
+                verbose: args.verbose,
+            };
+            crate::setup::run(&options).await
+        }
+        Commands::Apply {
+            profile,
+            all_profiles,
+            machine,
+            skip_bootstrap,
+            skip_secrets,
+            no_secrets_for,
+            shared,
+            not_shared,
+            skip_brew,
+            skip_defaults,
+            frozen,
+            force,
+            sudo_script,
+            snapshot,
+            strict,
+            jobs,
+            watch,
+            only,
+            skip,
+        } => {
+            let (config, config_path) = Config::load()?;
+            let home_dir = config.target_root()?;
+            let machine = match machine {
+                Some(name) => Some(name),
+                None if profile.is_empty() && !all_profiles => {
+                    let remembered = crate::state::MachineState::load(&home_dir)?.machine;
+                    let remembered = remembered.filter(|name| config.machines.contains_key(name));
+                    if let (Some(name), false) = (&remembered, args.quiet) {
+                        eprintln!("Using machine role '{name}' (applied here before)");
                     }
-                    info!("Homebrew not installed, skipping package installation");
+                    remembered
                 }
+                None => None,
+            };
+            let role = match &machine {
+                Some(name) => Some(machine_role(&config, name)?),
+                None => None,
+            };
+            let profiles = if let Some(role) = role {
+                role.profiles.clone()
+            } else if profile.is_empty() && !all_profiles {
+                vec![auto_select_profile(&config, args.quiet)]
             } else {
-                info!("Skipped Homebrew package installation");
-                if !args.quiet {
-                    eprintln!("Skipped Homebrew package installation");
-                }
+                config.select_profiles(&profile, all_profiles)?
+            };
+            // A role turns steps off; it never turns back on one skipped here
+            let role_skips = |enabled: fn(&crate::machine::MachineRole) -> Option<bool>| {
+                role.is_some_and(|role| enabled(role) == Some(false))
+            };
+            let options = crate::apply::ApplyOptions {
+                profiles,
+                machine,
+                home_dir,
+                filter: crate::filter::PathFilter::new(&only, &skip)?,
+                skip_bootstrap,
+                skip_secrets: skip_secrets || role_skips(|role| role.secrets),
+                skip_brew: skip_brew || role_skips(|role| role.brew),
+                skip_defaults: skip_defaults || role_skips(|role| role.defaults),
+                no_secrets_for,
+                shared: (shared || not_shared).then_some(shared),
+                frozen,
+                force,
+                sudo_script,
+                snapshot,
+                strict,
+                jobs: jobs.unwrap_or_else(crate::utils::default_jobs),
+                progress: args.progress,
+                dry_run: args.dry_run,
+                quiet: args.quiet,
+                verbose: args.verbose,
+            };
+            let report = crate::apply::run(&config, &config_path, &options).await?;
+            if report.dry_run {
+                return Ok(());
             }
-
-            info!("Apply completed");
             if !args.quiet {
                 eprintln!("Apply completed");
+                if report.journal_entries > 0 {
+                    eprintln!("To undo this apply, run: ordinator rollback");
+                }
+                if let Some(id) = &report.snapshot {
+                    eprintln!(
+                        "To put back everything it touched, run: ordinator snapshots restore {id}"
+                    );
+                }
             }
 
             // Auto-update README if needed
-            if !args.dry_run && crate::readme::readme_needs_update(&config, _dotfiles_dir) {
+            let dotfiles_dir = config_path.parent().unwrap();
+            if crate::readme::readme_needs_update(&config, dotfiles_dir) {
                 if config.readme.auto_update {
-                    if let Err(e) = crate::readme::auto_update_readme(&config, _dotfiles_dir) {
+                    if let Err(e) = crate::readme::auto_update_readme(&config, dotfiles_dir) {
                         if !args.quiet {
                             eprintln!("Warning: Failed to auto-update README: {e}");
                         }
@@ -1906,24 +4788,27 @@ pub async fn run(args: Args) -> Result<()> {
                 }
             }
 
+            if watch {
+                crate::live::run(
+                    &config,
+                    &options.profiles,
+                    &options.home_dir,
+                    dotfiles_dir,
+                    &options.filter,
+                    args.quiet,
+                )?;
+            }
+
             Ok(())
         }
         Commands::Uninstall {
             profile,
+            all,
             restore_backups,
             force,
+            shred,
+            purge_repo,
         } => {
-            info!(
-                "Uninstalling dotfiles for profile: {}",
-                profile.as_deref().unwrap_or("all")
-            );
-            if !args.quiet {
-                eprintln!(
-                    "Uninstalling dotfiles for profile: {}",
-                    profile.as_deref().unwrap_or("all")
-                );
-            }
-
             let (config, config_path) = match Config::load() {
                 Ok(val) => val,
                 Err(e) => {
@@ -1931,287 +4816,367 @@ pub async fn run(args: Args) -> Result<()> {
                     return Err(e);
                 }
             };
+            let options = crate::uninstall::UninstallOptions {
+                profile,
+                all,
+                restore_backups,
+                force,
+                shred,
+                purge_repo,
+                dry_run: args.dry_run,
+                quiet: args.quiet,
+            };
+            crate::uninstall::run(&config, &config_path, &options)
+        }
+        Commands::Rollback { list } => {
+            use crate::journal::ApplyJournal;
+
+            let (_config, config_path) = Config::load()?;
             let dotfiles_dir = config_path.parent().unwrap();
-            let home_dir = crate::utils::get_home_dir()?;
 
-            let profiles_to_uninstall = if let Some(profile_name) = profile {
-                if !config.profiles.contains_key(&profile_name) {
-                    eprintln!("Error: Profile '{profile_name}' does not exist in config.");
-                    return Err(anyhow::anyhow!("Profile '{profile_name}' does not exist."));
+            if list {
+                let journals = ApplyJournal::list(dotfiles_dir)?;
+                if journals.is_empty() {
+                    eprintln!("No applies have been recorded.");
                 }
-                vec![profile_name]
-            } else {
-                let profiles = config.list_profiles();
-                if profiles.is_empty() {
-                    eprintln!("Error: No profiles found in config. Nothing to uninstall.");
-                    return Err(anyhow::anyhow!("No profiles found in config."));
+                for journal in journals.iter().rev() {
+                    eprintln!(
+                        "{}  profile '{}'  {} change(s){}",
+                        journal.id,
+                        journal.profile,
+                        journal.actions.len(),
+                        if journal.rolled_back {
+                            " (rolled back)"
+                        } else {
+                            ""
+                        }
+                    );
                 }
-                profiles.into_iter().map(|s| s.to_string()).collect()
+                return Ok(());
+            }
+
+            let Some(mut journal) = ApplyJournal::latest(dotfiles_dir)? else {
+                eprintln!("Nothing to roll back.");
+                return Ok(());
             };
 
-            let mut total_symlinks_removed = 0;
-            let mut total_backups_restored = 0;
-            let mut total_profiles_processed = 0;
+            if args.dry_run {
+                eprintln!(
+                    "DRY-RUN: Would roll back apply {} of profile '{}':",
+                    journal.id, journal.profile
+                );
+                for action in journal.actions.iter().rev() {
+                    eprintln!("  undo: {}", action.describe());
+                }
+                return Ok(());
+            }
+
+            info!("Rolling back apply {}", journal.id);
+            if !args.quiet {
+                eprintln!(
+                    "Rolling back apply {} of profile '{}'",
+                    journal.id, journal.profile
+                );
+            }
+            if args.verbose {
+                for action in journal.actions.iter().rev() {
+                    eprintln!("  undo: {}", action.describe());
+                }
+            }
 
-            let dry_run = args.dry_run;
+            let warnings = journal.rollback()?;
+            for warning in &warnings {
+                if color_enabled() {
+                    eprintln!("{}", format!("Warning: {warning}").yellow());
+                } else {
+                    eprintln!("Warning: {warning}");
+                }
+            }
+            if !args.quiet {
+                eprintln!(
+                    "Rolled back {} change(s)",
+                    journal.actions.len() - warnings.len()
+                );
+            }
+            Ok(())
+        }
+        Commands::Snapshots { subcommand } => {
+            use crate::snapshot::{Snapshot, SnapshotKind};
 
-            for profile_name in &profiles_to_uninstall {
-                if let Some(profile_cfg) = config.get_profile(profile_name) {
-                    if !args.quiet {
-                        if color_enabled() {
-                            eprintln!("🔧 Processing profile: {}", profile_name.cyan());
-                        } else {
-                            eprintln!("Processing profile: {profile_name}");
+            let (config, _config_path) = Config::load()?;
+            let home_dir = config.target_root()?;
+            match subcommand {
+                SnapshotCommands::List => {
+                    let snapshots = Snapshot::list(&home_dir)?;
+                    if snapshots.is_empty() {
+                        eprintln!("No snapshots found. Take one with: ordinator apply --snapshot");
+                    }
+                    for snapshot in snapshots.iter().rev() {
+                        let mut line = format!(
+                            "{}  profile '{}'  {} destination(s)",
+                            snapshot.id,
+                            snapshot.profile,
+                            snapshot.entries.len()
+                        );
+                        if let Some(note) = &snapshot.note {
+                            line.push_str(&format!("  ({note})"));
                         }
+                        if let Some(date) = &snapshot.apfs {
+                            line.push_str(&format!("  APFS snapshot {date}"));
+                        }
+                        println!("{line}");
                     }
-
-                    if profile_cfg.files.is_empty() {
-                        eprintln!("Info: Profile '{profile_name}' has no tracked files. Nothing to uninstall.");
-                        continue;
+                }
+                SnapshotCommands::Restore { id } => {
+                    let snapshot = Snapshot::find(&home_dir, &id)?;
+                    let pending = snapshot.pending();
+                    if pending.is_empty() {
+                        eprintln!("Nothing changed since snapshot {}", snapshot.id);
+                        return Ok(());
                     }
-
-                    let mut profile_symlinks_removed = 0;
-                    let mut profile_backups_restored = 0;
-
-                    // Collect files with backups for progress indicator
-                    let mut files_with_backups = Vec::new();
-                    if restore_backups {
-                        let backup_dir = dotfiles_dir.join("backups");
-                        for file_path in &profile_cfg.files {
-                            let target_path = home_dir.join(file_path);
-                            let filename = target_path.file_name().unwrap_or_default();
-                            let mut backup_files = Vec::new();
-                            if backup_dir.exists() {
-                                if let Ok(entries) = std::fs::read_dir(&backup_dir) {
-                                    for entry in entries.flatten() {
-                                        let path = entry.path();
-                                        if let Some(name) = path.file_name() {
-                                            let name_str = name.to_string_lossy();
-                                            if name_str.starts_with(&format!(
-                                                "{}.backup.",
-                                                filename.to_string_lossy()
-                                            )) {
-                                                backup_files.push(path);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            if !backup_files.is_empty() {
-                                files_with_backups.push((file_path.clone(), backup_files));
-                            }
+                    if args.dry_run {
+                        eprintln!("DRY-RUN: Would restore from snapshot {}:", snapshot.id);
+                        for entry in &pending {
+                            eprintln!("  {}", entry.path.display());
                         }
+                        return Ok(());
                     }
-                    let total_to_restore = files_with_backups.len();
-                    let mut restore_idx = 0;
 
-                    for file_path in &profile_cfg.files {
-                        let target_path = home_dir.join(file_path);
-                        if !args.quiet {
-                            if color_enabled() {
-                                eprintln!(
-                                    "  📁 Checking: {}",
-                                    target_path.display().to_string().yellow()
-                                );
-                            } else {
-                                eprintln!("  Checking: {}", target_path.display());
-                            }
-                        }
-                        if crate::utils::is_symlink(&target_path) {
-                            if force {
-                                if dry_run {
-                                    eprintln!("Would remove symlink: {}", target_path.display());
-                                    profile_symlinks_removed += 1;
-                                } else if std::fs::remove_file(&target_path).is_ok() {
-                                    eprintln!("Removed symlink: {}", target_path.display());
-                                    profile_symlinks_removed += 1;
-                                } else {
-                                    eprintln!(
-                                        "Error: Failed to remove symlink: {}",
-                                        target_path.display()
-                                    );
-                                }
-                            } else if dry_run {
-                                eprintln!(
-                                    "Would prompt to remove symlink: {}",
-                                    target_path.display()
-                                );
-                                profile_symlinks_removed += 1;
-                            } else {
-                                use std::io::Write;
-                                eprint!("Remove symlink at {}? [y/N]: ", target_path.display());
-                                std::io::stdout().flush().ok();
-                                let mut input = String::new();
-                                if std::io::stdin().read_line(&mut input).is_ok() {
-                                    if input.trim().eq_ignore_ascii_case("y") {
-                                        if std::fs::remove_file(&target_path).is_ok() {
-                                            eprintln!("Removed symlink: {}", target_path.display());
-                                            profile_symlinks_removed += 1;
-                                        } else {
-                                            eprintln!(
-                                                "Error: Failed to remove symlink: {}",
-                                                target_path.display()
-                                            );
-                                        }
-                                    } else {
-                                        eprintln!(
-                                            "Skipped symlink removal: {}",
-                                            target_path.display()
-                                        );
-                                    }
-                                }
-                            }
-                        } else if target_path.exists() {
-                            eprintln!(
-                                "File exists (not a symlink): {}. Skipping.",
-                                target_path.display()
-                            );
+                    // The current state is saved first, so the restore can be undone
+                    let paths: Vec<std::path::PathBuf> =
+                        pending.iter().map(|entry| entry.path.clone()).collect();
+                    let (before, _) = Snapshot::take(
+                        &home_dir,
+                        &snapshot.profile,
+                        Some(format!("before restoring {}", snapshot.id)),
+                        &paths,
+                        SnapshotKind::Copy,
+                    )?;
+                    let restored = snapshot.restore()?;
+                    for path in &restored {
+                        let msg = format!("Restored {}", path.display());
+                        if color_enabled() {
+                            println!("{}", msg.green());
                         } else {
-                            eprintln!("File does not exist: {}. Skipping.", target_path.display());
-                        }
-
-                        // Handle backup restoration if requested
-                        if restore_backups {
-                            if let Some((_, _backup_files)) =
-                                files_with_backups.iter().find(|(f, _)| f == file_path)
-                            {
-                                restore_idx += 1;
-                                let msg = format!(
-                                    "Restoring backup {}/{} for profile {}: {}",
-                                    restore_idx,
-                                    total_to_restore,
-                                    profile_name,
-                                    target_path.display()
-                                );
-                                eprintln!("{msg}");
-                            }
-                            if let Some((_, backup_files)) =
-                                files_with_backups.iter().find(|(f, _)| f == file_path)
-                            {
-                                if !backup_files.is_empty() {
-                                    let latest_backup = backup_files.iter().max_by_key(|p| {
-                                        p.metadata()
-                                            .map(|m| m.modified().unwrap_or(SystemTime::UNIX_EPOCH))
-                                            .unwrap_or(SystemTime::UNIX_EPOCH)
-                                    });
-                                    if let Some(latest_backup) = latest_backup {
-                                        if force {
-                                            if dry_run {
-                                                eprintln!(
-                                                    "Would restore from backup: {}",
-                                                    target_path.display()
-                                                );
-                                                profile_backups_restored += 1;
-                                            } else {
-                                                if let Some(parent) = target_path.parent() {
-                                                    let _ = std::fs::create_dir_all(parent);
-                                                }
-                                                if std::fs::copy(latest_backup, &target_path)
-                                                    .is_ok()
-                                                {
-                                                    eprintln!(
-                                                        "Restored from backup: {}",
-                                                        target_path.display()
-                                                    );
-                                                    profile_backups_restored += 1;
-                                                } else {
-                                                    eprintln!(
-                                                        "Error: Failed to restore from backup: {}",
-                                                        target_path.display()
-                                                    );
-                                                }
-                                            }
-                                        } else if dry_run {
-                                            eprintln!(
-                                                "Would prompt to restore from backup: {}",
-                                                target_path.display()
-                                            );
-                                            profile_backups_restored += 1;
-                                        } else {
-                                            use std::io::Write;
-                                            eprint!(
-                                                "Restore backup to {}? [y/N]: ",
-                                                target_path.display()
-                                            );
-                                            std::io::stdout().flush().ok();
-                                            let mut input = String::new();
-                                            if std::io::stdin().read_line(&mut input).is_ok() {
-                                                if input.trim().eq_ignore_ascii_case("y") {
-                                                    if let Some(parent) = target_path.parent() {
-                                                        let _ = std::fs::create_dir_all(parent);
-                                                    }
-                                                    if std::fs::copy(latest_backup, &target_path)
-                                                        .is_ok()
-                                                    {
-                                                        eprintln!(
-                                                            "Restored from backup: {}",
-                                                            target_path.display()
-                                                        );
-                                                        profile_backups_restored += 1;
-                                                    } else {
-                                                        eprintln!("Error: Failed to restore from backup: {}", target_path.display());
-                                                    }
-                                                } else {
-                                                    eprintln!(
-                                                        "Skipped backup restoration: {}",
-                                                        target_path.display()
-                                                    );
-                                                }
-                                            }
-                                        }
-                                    }
-                                } else {
-                                    eprintln!(
-                                        "No backup found for: {}. Skipping restore.",
-                                        target_path.display()
-                                    );
-                                }
-                            } else if !config.global.create_backups.unwrap_or(true) {
-                                eprintln!(
-                                    "Backups are disabled in config. No backups will be restored."
-                                );
-                            }
+                            println!("{msg}");
                         }
                     }
-
-                    total_symlinks_removed += profile_symlinks_removed;
-                    total_backups_restored += profile_backups_restored;
-                    total_profiles_processed += 1;
-
-                    eprintln!("Profile '{profile_name}' summary: {profile_symlinks_removed} symlinks removed, {profile_backups_restored} backups restored");
+                    if !args.quiet {
+                        eprintln!(
+                            "Restored {} destination(s) from snapshot {}. To undo, run: ordinator snapshots restore {}",
+                            restored.len(),
+                            snapshot.id,
+                            before.id
+                        );
+                    }
                 }
             }
-
-            // Summary
-            eprintln!();
-            eprintln!("Uninstall Summary:");
-            eprintln!("  Profiles processed: {total_profiles_processed}");
-            eprintln!("  Symlinks removed: {total_symlinks_removed}");
-            eprintln!("  Backups restored: {total_backups_restored}");
-
             Ok(())
         }
-        Commands::Repair { profile, verbose } => {
-            info!("Repairing broken symlinks");
-            if !args.quiet {
-                eprintln!("Repairing broken symlinks");
+        Commands::Logs { last, command } => {
+            let home_dir = crate::utils::get_home_dir()?;
+            let mut runs = crate::run_log::Run::list(&home_dir)?;
+            if let Some(command) = &command {
+                runs.retain(|run| run.is_command(command));
             }
-
-            if args.dry_run {
-                info!("[DRY RUN] Would repair broken symlinks");
-                eprintln!("DRY-RUN: Would repair broken symlinks");
+            let Some(latest) = runs.last() else {
+                match &command {
+                    Some(command) => eprintln!("No runs of '{command}' logged"),
+                    None => eprintln!("No runs logged"),
+                }
                 return Ok(());
-            }
-
-            // Load config
-            let (config, config_path) = Config::load()?;
-            use crate::utils::{get_home_dir, get_symlink_target, is_symlink};
-            let home_dir = get_home_dir()?;
-            let _dotfiles_dir = config_path.parent().unwrap();
+            };
+            let local_time = |timestamp: &str, format: &str| {
+                chrono::DateTime::parse_from_rfc3339(timestamp)
+                    .map(|time| time.format(format).to_string())
+                    .unwrap_or_else(|_| timestamp.to_string())
+            };
+            let duration = |run: &crate::run_log::Run| match run.duration_ms {
+                Some(ms) if ms < 1000 => format!(" in {ms}ms"),
+                Some(ms) => format!(" in {:.1}s", ms as f64 / 1000.0),
+                None => String::new(),
+            };
+
+            if !last {
+                for run in runs.iter().rev() {
+                    println!(
+                        "{}  ordinator {}  {}{}",
+                        local_time(&run.started_at, "%Y-%m-%d %H:%M:%S"),
+                        run.args.join(" "),
+                        run.outcome(),
+                        duration(run)
+                    );
+                }
+                if !args.quiet {
+                    eprintln!("Show what the last one did with: ordinator logs --last");
+                }
+                return Ok(());
+            }
+
+            println!(
+                "ordinator {} ({})",
+                latest.args.join(" "),
+                local_time(&latest.started_at, "%Y-%m-%d %H:%M:%S")
+            );
+            for event in &latest.events {
+                // Debug events are kept, but shown only with --verbose
+                if event.level == "debug" && !args.verbose || event.level == "trace" {
+                    continue;
+                }
+                println!(
+                    "  {} {:<5} {}",
+                    local_time(&event.timestamp, "%H:%M:%S"),
+                    event.level.to_uppercase(),
+                    event.message
+                );
+            }
+            let outcome = format!("Result: {}{}", latest.outcome(), duration(latest));
+            match latest.exit_code {
+                Some(0) if color_enabled() => println!("{}", outcome.green()),
+                Some(_) if color_enabled() => println!("{}", outcome.red()),
+                _ => println!("{outcome}"),
+            }
+            if let Some(error) = &latest.error {
+                for line in error.lines() {
+                    println!("  {line}");
+                }
+            }
+            Ok(())
+        }
+        Commands::Backups { subcommand } => {
+            use crate::backups::{prune, BackupDir};
+
+            let (config, config_path) = Config::load()?;
+            let dotfiles_dir = config_path.parent().unwrap();
+            let backup_dir = BackupDir::new(&config, dotfiles_dir, &config.target_root()?)?;
+            match subcommand {
+                BackupCommands::List { file } => {
+                    let backups = match &file {
+                        Some(file) => backup_dir.backups_for(file)?,
+                        None => backup_dir.list()?,
+                    };
+                    if backups.is_empty() {
+                        eprintln!("No backups found.");
+                    }
+                    let mut current_file = None;
+                    for backup in &backups {
+                        if current_file != Some(&backup.file) {
+                            println!("{}:", backup.file);
+                            current_file = Some(&backup.file);
+                        }
+                        println!(
+                            "  {}  ({})",
+                            backup.name,
+                            backup.created.format("%Y-%m-%d %H:%M:%S")
+                        );
+                    }
+                }
+                BackupCommands::Prune {
+                    max_count,
+                    max_age_days,
+                } => {
+                    let mut retention = config.global.backup_retention;
+                    if max_count.is_some() {
+                        retention.max_count = max_count;
+                    }
+                    if max_age_days.is_some() {
+                        retention.max_age_days = max_age_days;
+                    }
+                    if retention.is_unlimited() {
+                        return Err(anyhow::anyhow!(
+                            "No backup retention configured. Set backup_retention under [global] or pass --max-count or --max-age-days."
+                        ));
+                    }
+                    let pruned = prune(&backup_dir, &retention, args.dry_run)?;
+                    for backup in &pruned {
+                        if args.dry_run {
+                            eprintln!("DRY-RUN: Would delete {}", backup.name);
+                        } else if args.verbose {
+                            eprintln!("Deleted {}", backup.name);
+                        }
+                    }
+                    if !args.dry_run {
+                        eprintln!("Pruned {} backup(s)", pruned.len());
+                    }
+                }
+                BackupCommands::Restore { file, backup } => {
+                    let backups = backup_dir.backups_for(&file)?;
+                    let chosen = match &backup {
+                        Some(name) => backups.iter().find(|b| &b.name == name).ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "Backup '{name}' not found for '{file}'. Run 'ordinator backups list {file}' to see available backups."
+                            )
+                        })?,
+                        None => backups
+                            .first()
+                            .ok_or_else(|| anyhow::anyhow!("No backups found for '{file}'"))?,
+                    };
+                    let home_dir = config.target_root()?;
+                    let target = crate::paths::destination(&home_dir, &file);
+
+                    if args.dry_run {
+                        eprintln!(
+                            "DRY-RUN: Would restore {} from {}",
+                            target.display(),
+                            chosen.name
+                        );
+                        return Ok(());
+                    }
+
+                    if crate::utils::is_symlink(&target) {
+                        std::fs::remove_file(&target)?;
+                    } else if target.exists() {
+                        // Keep whatever is there now, in case the wrong backup was picked
+                        let saved = backup_dir.backup(&target)?;
+                        eprintln!("Backed up {} to {}", target.display(), saved.display());
+                        if target.is_dir() {
+                            std::fs::remove_dir_all(&target)?;
+                        } else {
+                            std::fs::remove_file(&target)?;
+                        }
+                    }
+                    crate::backups::copy_back(&chosen.path, &target)?;
+                    if !chosen.path.is_dir() {
+                        let mode = config
+                            .profiles
+                            .keys()
+                            .find_map(|name| config.file_mode(name, &file));
+                        if let Some(mode) = mode {
+                            crate::utils::set_file_mode(&target, mode)?;
+                        }
+                    }
+                    let msg = format!("Restored {} from {}", target.display(), chosen.name);
+                    if color_enabled() {
+                        println!("{}", msg.green());
+                    } else {
+                        println!("{msg}");
+                    }
+                }
+            }
+            Ok(())
+        }
+        Commands::Repair {
+            profile,
+            verbose,
+            prune,
+            orphans,
+            only,
+            skip,
+        } => {
+            info!("Repairing broken symlinks");
+            if !args.quiet {
+                eprintln!("Repairing broken symlinks");
+            }
+
+            // Load config
+            let (mut config, config_path) = Config::load()?;
+            use crate::utils::{get_symlink_target, is_symlink};
+            let home_dir = config.target_root()?;
+            let filter = crate::filter::PathFilter::new(&only, &skip)?;
 
             let profiles_to_repair = if let Some(profile_name) = profile {
                 if !config.profiles.contains_key(&profile_name) {
-                    return Err(anyhow::anyhow!("Profile '{profile_name}' does not exist."));
+                    return Err(OrdinatorError::ProfileNotFound(profile_name.to_string()).into());
                 }
                 vec![profile_name]
             } else {
@@ -2222,150 +5187,535 @@ pub async fn run(args: Args) -> Result<()> {
                     .collect()
             };
 
+            if prune {
+                prune_profiles(
+                    &mut config,
+                    &config_path,
+                    &profiles_to_repair,
+                    orphans,
+                    args.dry_run,
+                    args.quiet,
+                )?;
+            }
+
+            if args.dry_run {
+                info!("[DRY RUN] Would repair broken symlinks");
+                eprintln!("DRY-RUN: Would repair broken symlinks");
+                return Ok(());
+            }
+
             let mut total_checked = 0;
             let mut total_repaired = 0;
 
             for profile_name in &profiles_to_repair {
-                if let Some(profile_cfg) = config.get_profile(profile_name) {
-                    if verbose {
-                        eprintln!("Checking profile: {profile_name}");
+                let resolved = config.resolve_profile(profile_name)?;
+                if verbose {
+                    eprintln!("Checking profile: {profile_name}");
+                }
+
+                // Directories linked as a whole are checked as one directory symlink
+                let directory_links: Vec<&str> = resolved
+                    .directories
+                    .iter()
+                    .filter(|entry| {
+                        entry.directory.link == crate::directory::DirectoryLink::Directory
+                    })
+                    .map(|entry| entry.directory.path.as_str())
+                    .collect();
+                for (file, source_profile, expected_source) in
+                    config.link_targets(&resolved, false)?
+                {
+                    if !filter.matches(&file) {
+                        continue;
+                    }
+                    if crate::template::is_template(&file) {
+                        if verbose {
+                            eprintln!("  {file}: Template rendered by apply (skipping)");
+                        }
+                        continue;
+                    }
+                    if config.file_options(&source_profile, &file).encrypt {
+                        if verbose {
+                            eprintln!("  {file}: Encrypted file decrypted by apply (skipping)");
+                        }
+                        continue;
+                    }
+                    if config.file_options(&source_profile, &file).managed_block {
+                        if verbose {
+                            eprintln!("  {file}: Managed block written by apply (skipping)");
+                        }
+                        continue;
+                    }
+                    total_checked += 1;
+                    let dest = crate::paths::destination(&home_dir, &file);
+                    let kind = if directory_links.contains(&file.as_str()) {
+                        "directory symlink"
+                    } else {
+                        "symlink"
+                    };
+                    if verbose && &source_profile != profile_name {
+                        eprintln!("  {file}: inherited from profile '{source_profile}'");
                     }
 
-                    for file in &profile_cfg.files {
-                        total_checked += 1;
-                        let dest = home_dir.join(file);
+                    debug!(
+                        "Checking {file}: {} (expected source {})",
+                        dest.display(),
+                        expected_source.display()
+                    );
 
-                        eprintln!("[DEBUG] Checking file: {file}");
-                        eprintln!("[DEBUG] Dest: {}", dest.display());
-                        eprintln!("[DEBUG] home_dir: {}", home_dir.display());
-                        eprintln!("[DEBUG] dotfiles_dir: {}", _dotfiles_dir.display());
+                    // Check if destination exists or is a symlink (even if broken)
+                    if !dest.exists() && !is_symlink(&dest) {
+                        debug!("{}: nothing there, nothing to repair", dest.display());
+                        continue; // File doesn't exist and is not a symlink, nothing to repair
+                    }
 
-                        // Check if destination exists or is a symlink (even if broken)
-                        if !dest.exists() && !is_symlink(&dest) {
-                            eprintln!(
-                                "[DEBUG] Destination does not exist and is not a symlink: {}",
-                                dest.display()
-                            );
-                            continue; // File doesn't exist and is not a symlink, nothing to repair
+                    if !is_symlink(&dest) {
+                        if verbose {
+                            eprintln!("  {}: Not a {kind} (skipping)", dest.display());
                         }
+                        continue;
+                    }
 
-                        if !is_symlink(&dest) {
-                            eprintln!("[DEBUG] Not a symlink: {}", dest.display());
-                            if verbose {
-                                eprintln!("  {}: Not a symlink (skipping)", dest.display());
-                            }
-                            continue;
+                    // Check if symlink is broken or points to wrong target
+                    let needs_repair = match get_symlink_target(&dest) {
+                        Ok(actual_target) => {
+                            debug!(
+                                "{} -> {} (exists: {})",
+                                dest.display(),
+                                actual_target.display(),
+                                actual_target.exists()
+                            );
+                            !crate::utils::symlink_points_to(&dest, &expected_source)
+                                || !dest.exists()
                         }
-                        eprintln!("[DEBUG] Is a symlink, proceeding to check target");
+                        Err(e) => {
+                            debug!(
+                                "Could not read symlink target for {}: {}",
+                                dest.display(),
+                                e
+                            );
+                            true // Can't read symlink target, assume broken
+                        }
+                    };
 
-                        // Check if symlink is broken or points to wrong target
-                        eprintln!(
-                            "[DEBUG] About to check symlink target for: {}",
-                            dest.display()
-                        );
-                        let needs_repair = match get_symlink_target(&dest) {
-                            Ok(actual_target) => {
-                                eprintln!("[DEBUG] actual_target: {}", actual_target.display());
-                                eprintln!(
-                                    "[DEBUG] expected source: {}",
-                                    _dotfiles_dir.join("files").join(file).display()
-                                );
-                                eprintln!(
-                                    "[DEBUG] actual_target.exists(): {}",
-                                    actual_target.exists()
-                                );
-                                let needs = actual_target != _dotfiles_dir.join("files").join(file)
-                                    || !actual_target.exists();
-                                eprintln!("[DEBUG] needs_repair: {needs}");
-                                needs
-                            }
-                            Err(e) => {
+                    if needs_repair {
+                        if args.dry_run {
+                            eprintln!("DRY-RUN: Would repair {}", dest.display());
+                        } else {
+                            use crate::utils::repair_symlink;
+                            repair_symlink(&dest, &expected_source)?;
+                            if !args.quiet {
+                                let what = if kind == "symlink" {
+                                    String::new()
+                                } else {
+                                    format!(" {kind}")
+                                };
                                 eprintln!(
-                                    "[DEBUG] Could not read symlink target for {}: {}",
+                                    "Repaired{what}: {} -> {}",
                                     dest.display(),
-                                    e
+                                    expected_source.display()
                                 );
-                                true // Can't read symlink target, assume broken
                             }
-                        };
+                            total_repaired += 1;
+                        }
+                    } else if verbose {
+                        eprintln!("  {}: Valid {kind}", dest.display());
+                    }
+                }
+            }
+
+            if !args.quiet {
+                eprintln!("Repair completed: {total_checked} checked, {total_repaired} repaired");
+            }
+            info!("Repair completed: {total_checked} checked, {total_repaired} repaired");
+            Ok(())
+        }
+        Commands::Fsck { fix } => {
+            let (mut config, config_path) = Config::load()?;
+            let dotfiles_path = config_path.parent().unwrap();
+            let mut issues = crate::fsck::check(&config, dotfiles_path)?;
+            if fix && issues.iter().any(|issue| issue.fix.is_some()) {
+                if args.dry_run {
+                    for issue in issues.iter().filter(|issue| issue.fix.is_some()) {
+                        eprintln!("DRY-RUN: Would fix {}", issue.message);
+                    }
+                } else {
+                    let fixed = crate::fsck::apply_fixes(&mut config, &issues)?;
+                    config.save_to_file(&config_path)?;
+                    if !args.quiet {
+                        eprintln!("✅ Fixed {fixed} problem(s)");
+                    }
+                    issues = crate::fsck::check(&config, dotfiles_path)?;
+                }
+            }
+            if issues.is_empty() {
+                eprintln!("✅ The repository matches {}", config_path.display());
+                return Ok(());
+            }
+            for issue in &issues {
+                println!("{}", issue.line());
+            }
+            if !args.quiet {
+                let fixable = issues.iter().filter(|issue| issue.fix.is_some()).count();
+                eprintln!(
+                    "{} problem(s) found, {fixable} fixable with --fix",
+                    issues.len()
+                );
+                if issues
+                    .iter()
+                    .any(|issue| matches!(issue.check, "missing" | "unreferenced"))
+                {
+                    eprintln!("Run 'ordinator repair --prune --orphans' to stop tracking paths whose copy is gone and delete unreferenced files.");
+                }
+            }
+            crate::run_log::exit(crate::exit_code::DRIFT);
+        }
+        Commands::Profiles {
+            verbose, explain, ..
+        } => {
+            info!(
+                "Listing profiles{}",
+                if verbose { " (verbose)" } else { "" }
+            );
+            eprintln!(
+                "Listing profiles{}",
+                if verbose { " (verbose)" } else { "" }
+            );
+
+            if args.dry_run {
+                info!(
+                    "[DRY RUN] Would list profiles{}",
+                    if verbose { " (verbose)" } else { "" }
+                );
+                eprintln!(
+                    "DRY-RUN: Would list profiles{}",
+                    if verbose { " (verbose)" } else { "" }
+                );
+                return Ok(());
+            }
+
+            // Load and display profiles
+            let (config, _config_path) = Config::load()?;
+            let profiles = config.list_profiles();
+            if profiles.is_empty() {
+                return Err(OrdinatorError::NoProfiles.into());
+            }
+            let state = crate::state::MachineState::load(&config.target_root()?)?;
+            let width = profiles
+                .iter()
+                .map(|name| name.chars().count() + 1)
+                .max()
+                .unwrap_or(0);
+            eprintln!("Available profiles:");
+            for profile_name in profiles {
+                if let Some(profile) = config.get_profile(profile_name) {
+                    let mut tags = Vec::new();
+                    if *profile_name == config.global.default_profile {
+                        tags.push("default");
+                    }
+                    if state.applied.contains_key(profile_name) {
+                        tags.push("applied");
+                    }
+                    let tags = if tags.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" {}", paint(&format!("({})", tags.join(", ")), Tone::Good))
+                    };
+                    eprintln!(
+                        "  {:width$} {}{tags}",
+                        format!("{profile_name}:"),
+                        profile.description.as_deref().unwrap_or("No description")
+                    );
+                    if verbose {
+                        if !profile.extends.is_empty() {
+                            eprintln!("    extends: {}", profile.extends.join(", "));
+                        }
+                        eprintln!(
+                            "    files: {}, directories: {}, secrets: {}",
+                            profile.files.len(),
+                            profile.directories.len(),
+                            profile.secrets.len()
+                        );
+                    }
+                }
+            }
+            if !config.machines.is_empty() {
+                eprintln!("Machine roles:");
+                for (name, role) in &config.machines {
+                    let tag = if state.machine.as_deref() == Some(name.as_str()) {
+                        format!(" {}", paint("(this machine)", Tone::Good))
+                    } else {
+                        String::new()
+                    };
+                    eprintln!("  {name}: {}{tag}", role.describe());
+                    if let (true, Some(description)) = (verbose, &role.description) {
+                        eprintln!("    {description}");
+                    }
+                }
+            }
+            if explain {
+                explain_profile_selection(&config);
+            }
+            Ok(())
+        }
+        Commands::Profile { subcommand } => match subcommand {
+            ProfileCommands::Add { name, description } => {
+                info!("Adding profile: {}", name);
+                if !args.quiet {
+                    eprintln!("Adding profile: {name}");
+                }
+
+                let (mut config, config_path) = Config::load()?;
+                if config.profiles.contains_key(&name) {
+                    return Err(anyhow::anyhow!("Profile '{name}' already exists."));
+                }
+                Config::validate_profile_name(&name)?;
+
+                if args.dry_run {
+                    eprintln!("DRY-RUN: Would add profile '{name}'");
+                    return Ok(());
+                }
+
+                let profile_config = crate::config::ProfileConfig {
+                    enabled: true,
+                    description,
+                    ..Default::default()
+                };
+                config.add_profile(name.clone(), profile_config)?;
+                config.save_to_file(&config_path)?;
+
+                if !args.quiet {
+                    let msg = format!("Added profile '{name}'");
+                    if color_enabled() {
+                        println!("{}", msg.green());
+                    } else {
+                        println!("{msg}");
+                    }
+                }
+                Ok(())
+            }
+            ProfileCommands::Remove { name, force } => {
+                info!("Removing profile: {}", name);
+                if !args.quiet {
+                    eprintln!("Removing profile: {name}");
+                }
+
+                let (mut config, config_path) = Config::load()?;
+                let profile_config = config
+                    .get_profile(&name)
+                    .ok_or_else(|| OrdinatorError::ProfileNotFound(name.to_string()))?;
+                if config.global.default_profile == name {
+                    return Err(OrdinatorError::ProfileInUse {
+                        name,
+                        by: "the default profile".to_string(),
+                    }
+                    .into());
+                }
+                let children = config.profiles_extending(&name);
+                if !children.is_empty() {
+                    let children: Vec<&str> = children.iter().map(|c| c.as_str()).collect();
+                    return Err(OrdinatorError::ProfileInUse {
+                        name,
+                        by: format!("extended by {}", children.join(", ")),
+                    }
+                    .into());
+                }
+                let machines = config.machines_using(&name);
+                if !machines.is_empty() {
+                    let machines: Vec<&str> = machines.iter().map(|m| m.as_str()).collect();
+                    return Err(OrdinatorError::ProfileInUse {
+                        name,
+                        by: format!("used by machine role(s) {}", machines.join(", ")),
+                    }
+                    .into());
+                }
+
+                let tracked_count = profile_config.files.len() + profile_config.secrets.len();
+                if args.dry_run {
+                    eprintln!(
+                        "DRY-RUN: Would remove profile '{name}' ({tracked_count} tracked file(s))"
+                    );
+                    return Ok(());
+                }
+
+                if tracked_count > 0 && !force && !confirm_profile_removal(&name, tracked_count) {
+                    eprintln!("Operation cancelled by user.");
+                    return Ok(());
+                }
+
+                // Remove the profile's storage from the dotfiles repository
+                let dotfiles_dir = config_path.parent().unwrap();
+                for storage in ["files", "secrets", "scripts"] {
+                    let profile_dir = dotfiles_dir.join(storage).join(&name);
+                    if profile_dir.exists() {
+                        std::fs::remove_dir_all(&profile_dir)?;
+                    }
+                }
+
+                config.remove_profile(&name);
+                config.save_to_file(&config_path)?;
+
+                if !args.quiet {
+                    let msg = format!("Removed profile '{name}'");
+                    if color_enabled() {
+                        println!("{}", msg.green());
+                    } else {
+                        println!("{msg}");
+                    }
+                }
+                Ok(())
+            }
+            ProfileCommands::Rename { old_name, new_name } => {
+                info!("Renaming profile: {} -> {}", old_name, new_name);
+                if !args.quiet {
+                    eprintln!("Renaming profile: {old_name} -> {new_name}");
+                }
+
+                let (mut config, config_path) = Config::load()?;
+                if !config.profiles.contains_key(&old_name) {
+                    return Err(OrdinatorError::ProfileNotFound(old_name.to_string()).into());
+                }
+                if config.profiles.contains_key(&new_name) {
+                    return Err(anyhow::anyhow!("Profile '{new_name}' already exists."));
+                }
+                Config::validate_profile_name(&new_name)?;
+
+                let dotfiles_dir = config_path.parent().unwrap().to_path_buf();
+                let storage_moves: Vec<(PathBuf, PathBuf)> =
+                    ["files", "secrets", "scripts", "launchd"]
+                        .iter()
+                        .map(|storage| {
+                            (
+                                dotfiles_dir.join(storage).join(&old_name),
+                                dotfiles_dir.join(storage).join(&new_name),
+                            )
+                        })
+                        .filter(|(from, _)| from.exists())
+                        .collect();
+                for (_, to) in &storage_moves {
+                    if to.exists() {
+                        return Err(anyhow::anyhow!(
+                            "Cannot rename profile: '{}' already exists.",
+                            to.display()
+                        ));
+                    }
+                }
+
+                if args.dry_run {
+                    for (from, to) in &storage_moves {
+                        eprintln!("DRY-RUN: Would move {} -> {}", from.display(), to.display());
+                    }
+                    eprintln!("DRY-RUN: Would rename profile '{old_name}' to '{new_name}'");
+                    return Ok(());
+                }
+
+                // Remember where existing symlinks point so they can be re-targeted after the move
+                use crate::utils::{is_symlink, repair_symlink};
+                let home_dir = config.target_root()?;
+                let tracked_files = config
+                    .get_profile(&old_name)
+                    .map(|p| p.files.clone())
+                    .unwrap_or_default();
+                let mut old_sources = Vec::new();
+                for file in &tracked_files {
+                    old_sources.push((file.clone(), config.get_source_file_path(&old_name, file)?));
+                }
+
+                for (from, to) in &storage_moves {
+                    std::fs::rename(from, to)?;
+                }
+                config.rename_profile(&old_name, &new_name)?;
+                config.save_to_file(&config_path)?;
 
-                        eprintln!(
-                            "[DEBUG] After needs_repair check: {} => {}",
-                            dest.display(),
-                            needs_repair
-                        );
+                let mut relinked = 0;
+                for (file, old_source) in old_sources {
+                    let dest = crate::paths::destination(&home_dir, &file);
+                    if !is_symlink(&dest) {
+                        continue;
+                    }
+                    if crate::utils::symlink_points_to(&dest, &old_source) {
+                        let new_source = config.get_source_file_path(&new_name, &file)?;
+                        repair_symlink(&dest, &new_source)?;
+                        relinked += 1;
+                    }
+                }
 
-                        if needs_repair {
-                            eprintln!("[DEBUG] Entering repair branch for {}", dest.display());
-                            if args.dry_run {
-                                eprintln!("DRY-RUN: Would repair {}", dest.display());
-                            } else {
-                                use crate::utils::repair_symlink;
-                                repair_symlink(&dest, &_dotfiles_dir.join("files").join(file))?;
-                                if !args.quiet {
-                                    eprintln!(
-                                        "Repaired: {} -> {}",
-                                        dest.display(),
-                                        _dotfiles_dir.join("files").join(file).display()
-                                    );
-                                }
-                                total_repaired += 1;
-                            }
-                        } else if verbose {
-                            eprintln!("  {}: Valid symlink", dest.display());
-                        }
+                if !args.quiet {
+                    let msg = format!(
+                        "Renamed profile '{old_name}' to '{new_name}' ({relinked} symlink(s) updated)"
+                    );
+                    if color_enabled() {
+                        println!("{}", msg.green());
+                    } else {
+                        println!("{msg}");
                     }
                 }
+                Ok(())
             }
-
-            if !args.quiet {
-                eprintln!("Repair completed: {total_checked} checked, {total_repaired} repaired");
+            ProfileCommands::Switch {
+                name,
+                from,
+                brew,
+                skip_secrets,
+                skip_bootstrap,
+                force,
+            } => {
+                let (config, config_path) = Config::load()?;
+                let options = crate::switch::SwitchOptions {
+                    from,
+                    to: name.clone(),
+                    brew,
+                    skip_secrets,
+                    skip_bootstrap,
+                    force,
+                    jobs: crate::utils::default_jobs(),
+                    progress: args.progress,
+                    dry_run: args.dry_run,
+                    quiet: args.quiet,
+                    verbose: args.verbose,
+                };
+                let previous = crate::switch::run(&config, &config_path, &options).await?;
+                if !args.dry_run && !args.quiet {
+                    let msg = format!("Switched from '{previous}' to '{name}'");
+                    if color_enabled() {
+                        println!("{}", msg.green());
+                    } else {
+                        println!("{msg}");
+                    }
+                }
+                Ok(())
             }
-            info!("Repair completed: {total_checked} checked, {total_repaired} repaired");
-            Ok(())
-        }
-        Commands::Profiles { verbose } => {
-            info!(
-                "Listing profiles{}",
-                if verbose { " (verbose)" } else { "" }
-            );
-            eprintln!(
-                "Listing profiles{}",
-                if verbose { " (verbose)" } else { "" }
-            );
+            ProfileCommands::Describe { name, description } => {
+                info!("Updating description for profile: {}", name);
 
-            if args.dry_run {
-                info!(
-                    "[DRY RUN] Would list profiles{}",
-                    if verbose { " (verbose)" } else { "" }
-                );
-                eprintln!(
-                    "DRY-RUN: Would list profiles{}",
-                    if verbose { " (verbose)" } else { "" }
-                );
-                return Ok(());
-            }
+                let (mut config, config_path) = Config::load()?;
+                if !config.profiles.contains_key(&name) {
+                    return Err(OrdinatorError::ProfileNotFound(name.to_string()).into());
+                }
 
-            // Load and display profiles
-            let (config, _config_path) = Config::load()?;
-            let profiles = config.list_profiles();
-            if profiles.is_empty() {
-                return Err(anyhow::anyhow!(
-                    "No profiles found in configuration. Run 'ordinator init' first."
-                ));
-            }
-            eprintln!("Available profiles:");
-            for profile_name in profiles {
-                if let Some(profile) = config.get_profile(profile_name) {
+                if args.dry_run {
                     eprintln!(
-                        "  {}: {}",
-                        profile_name,
-                        profile.description.as_deref().unwrap_or("No description")
+                        "DRY-RUN: Would set description of profile '{name}' to '{description}'"
                     );
+                    return Ok(());
+                }
+
+                if let Some(profile_config) = config.get_profile_mut(&name) {
+                    profile_config.description = Some(description.clone());
+                }
+                config.save_to_file(&config_path)?;
+
+                if !args.quiet {
+                    let msg = format!("Updated description for profile '{name}'");
+                    if color_enabled() {
+                        println!("{}", msg.green());
+                    } else {
+                        println!("{msg}");
+                    }
                 }
+                Ok(())
             }
-            Ok(())
-        }
+        },
         Commands::Secrets { subcommand } => match subcommand {
             SecretCommands::Watch { file, profile } => {
                 let (mut config, config_path) = Config::load()?;
@@ -2378,11 +5728,7 @@ pub async fn run(args: Args) -> Result<()> {
                 };
 
                 if !config.profiles.contains_key(&profile_name) {
-                    return Err(anyhow::anyhow!(
-                        "Profile '{}' does not exist. To create it, run: ordinator profile add {}",
-                        profile_name,
-                        profile_name
-                    ));
+                    return Err(OrdinatorError::ProfileNotFound(profile_name.to_string()).into());
                 }
 
                 let file_path = std::path::Path::new(&file);
@@ -2444,11 +5790,7 @@ pub async fn run(args: Args) -> Result<()> {
                 };
 
                 if !config.profiles.contains_key(&profile_name) {
-                    return Err(anyhow::anyhow!(
-                        "Profile '{}' does not exist. To create it, run: ordinator profile add {}",
-                        profile_name,
-                        profile_name
-                    ));
+                    return Err(OrdinatorError::ProfileNotFound(profile_name.to_string()).into());
                 }
 
                 if args.dry_run {
@@ -2489,11 +5831,7 @@ pub async fn run(args: Args) -> Result<()> {
                 };
 
                 if !config.profiles.contains_key(&profile_name) {
-                    return Err(anyhow::anyhow!(
-                        "Profile '{}' does not exist. To create it, run: ordinator profile add {}",
-                        profile_name,
-                        profile_name
-                    ));
+                    return Err(OrdinatorError::ProfileNotFound(profile_name.to_string()).into());
                 }
 
                 // Check if key rotation is needed
@@ -2518,14 +5856,19 @@ pub async fn run(args: Args) -> Result<()> {
                     // Update all tracked secret files for the profile
                     let profile = config.get_profile_mut(&profile_name).unwrap();
                     let mut updated_count = 0;
-                    let total_secrets = profile.secrets.len();
+                    let mut progress = crate::progress::Progress::start(
+                        args.progress,
+                        args.quiet,
+                        "secrets add",
+                        profile.secrets.len(),
+                    );
 
                     for secret_path in &profile.secrets {
+                        progress.advance(secret_path);
                         let source_path = std::path::Path::new(secret_path);
                         if source_path.exists() {
                             let file_content = std::fs::read_to_string(source_path)?;
-                            let encrypted_content =
-                                crate::secrets::encrypt_content_with_sops(&file_content)?;
+                            let encrypted_content = crate::secrets::encrypt_content(&file_content)?;
                             let hash = generate_file_hash(secret_path);
                             let filename = source_path.file_name().unwrap().to_string_lossy();
                             let hash_filename = format!("{hash}_{filename}.enc");
@@ -2534,22 +5877,25 @@ pub async fn run(args: Args) -> Result<()> {
                             std::fs::create_dir_all(&secrets_dir)?;
                             let encrypted_file_path = secrets_dir.join(&hash_filename);
                             std::fs::write(&encrypted_file_path, encrypted_content)?;
+                            crate::secret_meta::write(
+                                &encrypted_file_path,
+                                &crate::secret_meta::SecretMeta::of(source_path, secret_path),
+                            )?;
                             profile
                                 .file_mappings
                                 .insert(hash_filename.clone(), secret_path.clone());
                             updated_count += 1;
-                            if !args.quiet {
-                                let msg = format!("[{updated_count}/{total_secrets}] Re-encrypted '{secret_path}' as '{hash_filename}'");
-                                if color_enabled() {
-                                    println!("{}", msg.green());
-                                } else {
-                                    println!("{msg}");
-                                }
+                            let msg = format!("Re-encrypted '{secret_path}' as '{hash_filename}'");
+                            if color_enabled() {
+                                progress.println(msg.green().to_string());
+                            } else {
+                                progress.println(msg);
                             }
                         } else if !args.quiet {
                             eprintln!("Warning: Source file '{secret_path}' does not exist");
                         }
                     }
+                    progress.finish();
                     config.save_to_file(&config_path)?;
                     if !args.quiet {
                         let msg = format!("Re-encrypted {updated_count} secret files for profile '{profile_name}'");
@@ -2574,22 +5920,18 @@ pub async fn run(args: Args) -> Result<()> {
                             file_str
                         ));
                     }
-                    config.add_secret_to_profile(&profile_name, file_str.clone())?;
-                    let profile = config.get_profile_mut(&profile_name).unwrap();
-                    let file_content = std::fs::read_to_string(file_path)?;
-                    let encrypted_content =
-                        crate::secrets::encrypt_content_with_sops(&file_content)?;
-                    let hash = generate_file_hash(file_str);
-                    let filename = file_path.file_name().unwrap().to_string_lossy();
-                    let hash_filename = format!("{hash}_{filename}.enc");
                     let base_dir = config_path.parent().unwrap().to_path_buf();
-                    let secrets_dir = base_dir.join("secrets").join(&profile_name);
-                    std::fs::create_dir_all(&secrets_dir)?;
-                    let encrypted_file_path = secrets_dir.join(&hash_filename);
-                    std::fs::write(&encrypted_file_path, encrypted_content)?;
-                    profile
-                        .file_mappings
-                        .insert(hash_filename.clone(), file_str.clone());
+                    let hash_filename = crate::secrets::store_secret(
+                        &mut config,
+                        &base_dir,
+                        &profile_name,
+                        file_str,
+                        file_path,
+                    )?;
+                    let encrypted_file_path = base_dir
+                        .join("secrets")
+                        .join(&profile_name)
+                        .join(&hash_filename);
                     config.save_to_file(&config_path)?;
                     if !args.quiet {
                         let msg = format!(
@@ -2610,10 +5952,197 @@ pub async fn run(args: Args) -> Result<()> {
                 Ok(())
             }
 
+            SecretCommands::Edit { file, profile } => {
+                let (config, config_path) = Config::load()?;
+                let base_dir = config_path.parent().unwrap().to_path_buf();
+                let home_dir = config.target_root()?;
+                let is_match = |secret: &str| {
+                    secret == file
+                        || crate::paths::destination(&home_dir, secret)
+                            == crate::paths::destination(&home_dir, &file)
+                };
+
+                // Inherited secrets are stored with the profile that tracks them
+                let profiles: Vec<String> = match &profile {
+                    Some(profile) if !config.profiles.contains_key(profile) => {
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+                    }
+                    Some(profile) => vec![profile.clone()],
+                    None => std::iter::once(config.global.default_profile.clone())
+                        .chain(config.list_profiles().into_iter().cloned())
+                        .filter(|p| config.profiles.contains_key(p))
+                        .collect(),
+                };
+                let mut found = None;
+                for name in &profiles {
+                    let resolved = config.resolve_profile(name)?;
+                    if let Some(entry) = resolved.secrets.iter().find(|e| is_match(&e.path)) {
+                        found = Some((entry.path.clone(), entry.profile.clone()));
+                        break;
+                    }
+                }
+                let Some((secret, owner)) = found else {
+                    return Err(OrdinatorError::SecretNotTracked { file, profile }.into());
+                };
+
+                let stored =
+                    crate::secrets::stored_secret_path(&config, &base_dir, &owner, &secret)?;
+                if !stored.exists() {
+                    return Err(anyhow::anyhow!(
+                        "No encrypted copy of '{secret}' found at {}. Run 'ordinator secrets add {secret} --profile {owner}' first.",
+                        stored.display()
+                    ));
+                }
+                if args.dry_run {
+                    eprintln!(
+                        "DRY-RUN: Would decrypt {} for editing and re-encrypt it on save",
+                        stored.display()
+                    );
+                    return Ok(());
+                }
+
+                let name = std::path::Path::new(&secret)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "secret".to_string());
+                let editor = std::env::var("EDITOR").unwrap_or_else(|_| "nano".to_string());
+                let changed = crate::secrets::edit_secret(&stored, &name, &editor)?;
+                if changed && !args.quiet {
+                    let msg = format!("Re-encrypted '{secret}' in profile '{owner}'");
+                    if color_enabled() {
+                        eprintln!("{}", msg.green());
+                    } else {
+                        eprintln!("{msg}");
+                    }
+                    eprintln!("   Run 'ordinator apply' to deploy it, then commit the change");
+                } else if !args.quiet {
+                    eprintln!("No changes to '{secret}'");
+                }
+                Ok(())
+            }
+
+            SecretCommands::Set { profile, key } => {
+                let (config, config_path) = Config::load()?;
+                if !config.profiles.contains_key(&profile) {
+                    return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+                }
+                crate::secrets::validate_secret_key(&key)?;
+                if args.dry_run {
+                    eprintln!("DRY-RUN: Would store secret value '{key}' in profile '{profile}'");
+                    return Ok(());
+                }
+                let value = read_secret_value(&key)?;
+                let base_dir = config_path.parent().unwrap();
+                let changed =
+                    crate::secrets::set_secret_value(base_dir, &profile, &key, Some(&value))?;
+                if !args.quiet {
+                    if changed {
+                        let msg = format!("Stored secret value '{key}' in profile '{profile}'");
+                        if color_enabled() {
+                            eprintln!("{}", msg.green());
+                        } else {
+                            eprintln!("{msg}");
+                        }
+                        eprintln!(
+                            "   Use it in templates as {{{{ {key} }}}}, then commit the change"
+                        );
+                    } else {
+                        eprintln!("No changes to '{key}'");
+                    }
+                }
+                Ok(())
+            }
+
+            SecretCommands::Unset { profile, key } => {
+                let (config, config_path) = Config::load()?;
+                if !config.profiles.contains_key(&profile) {
+                    return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+                }
+                if args.dry_run {
+                    eprintln!(
+                        "DRY-RUN: Would remove secret value '{key}' from profile '{profile}'"
+                    );
+                    return Ok(());
+                }
+                let base_dir = config_path.parent().unwrap();
+                if !crate::secrets::set_secret_value(base_dir, &profile, &key, None)? {
+                    return Err(anyhow::anyhow!(
+                        "Profile '{profile}' has no secret value '{key}'."
+                    ));
+                }
+                if !args.quiet {
+                    eprintln!("Removed secret value '{key}' from profile '{profile}'");
+                }
+                Ok(())
+            }
+
+            SecretCommands::Env { profile } => {
+                let (config, config_path) = Config::load()?;
+                let profile = profile.unwrap_or_else(|| config.global.default_profile.clone());
+                if !config.profiles.contains_key(&profile) {
+                    return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+                }
+                let chain = config.resolve_profile(&profile)?.chain;
+                let sources = config.secret_value_profiles(&[], &[], &chain)?;
+                let mut values = std::collections::HashMap::new();
+                crate::secrets::add_secret_values(
+                    config_path.parent().unwrap(),
+                    &sources,
+                    &mut values,
+                )?;
+                let mut values: Vec<_> = values.into_iter().collect();
+                values.sort();
+                for (key, value) in values {
+                    println!("export {key}='{}'", value.replace('\'', "'\\''"));
+                }
+                Ok(())
+            }
+
+            SecretCommands::Clean { profile, shred } => {
+                let (config, config_path) = Config::load()?;
+                if let Some(profile) = profile.as_ref() {
+                    if !config.profiles.contains_key(profile) {
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+                    }
+                }
+                let dotfiles_dir = config_path.parent().unwrap();
+                let secrets: Vec<_> =
+                    crate::journal::ApplyJournal::decrypted_secrets(dotfiles_dir)?
+                        .into_iter()
+                        .filter(|secret| profile.as_ref().is_none_or(|p| secret.applied_with(p)))
+                        .collect();
+                if secrets.is_empty() {
+                    eprintln!("No decrypted secrets on disk.");
+                    return Ok(());
+                }
+                let removed = crate::secrets::remove_decrypted_secrets(
+                    &secrets,
+                    shred,
+                    args.dry_run,
+                    args.quiet,
+                );
+                if args.dry_run {
+                    eprintln!(
+                        "DRY-RUN: Would remove {} decrypted secret(s)",
+                        removed.len()
+                    );
+                } else {
+                    eprintln!("Removed {} decrypted secret(s)", removed.len());
+                    if removed.len() < secrets.len() {
+                        return Err(anyhow::anyhow!(
+                            "Failed to remove {} decrypted secret(s)",
+                            secrets.len() - removed.len()
+                        ));
+                    }
+                }
+                Ok(())
+            }
+
             SecretCommands::List { paths_only } => {
                 let (config, config_path) = Config::load()?;
                 let base_dir = config_path.parent().unwrap().to_path_buf();
-                let manager = crate::secrets::SecretsManager::new(None, None, config, base_dir);
+                let manager =
+                    crate::secrets::SecretsManager::new(None, None, config, base_dir.clone());
                 let files = manager.list_encrypted_files()?;
                 if files.is_empty() {
                     println!("No files match the encryption patterns.");
@@ -2629,55 +6158,248 @@ pub async fn run(args: Args) -> Result<()> {
                         println!("{:<50} | {}", path.display(), status);
                     }
                 }
+
+                // Where each secret stored by 'secrets add' decrypts to
+                let stored = crate::secrets::stored_secret_files(&base_dir, None);
+                if !paths_only && !stored.is_empty() {
+                    println!();
+                    println!("Stored secrets:");
+                    for path in stored {
+                        let relative = path.strip_prefix(&base_dir).unwrap_or(&path);
+                        let values = path
+                            .file_name()
+                            .is_some_and(|name| name == crate::secrets::SECRET_VALUES_FILE);
+                        let target = match crate::secret_meta::read(&path) {
+                            Ok(Some(meta)) => meta.describe(),
+                            Ok(None) if values => "secret values ('secrets set')".to_string(),
+                            Ok(None) => "no metadata (added by an older version)".to_string(),
+                            Err(e) => format!("unreadable metadata: {e}"),
+                        };
+                        println!("  {} -> {target}", relative.display());
+                    }
+                }
+                Ok(())
+            }
+            SecretCommands::Check => {
+                use crate::secrets::check_sops_and_age;
+                let config = Config::from_file_or_default()?;
+                if config.secrets.backend == crate::config::SecretsBackend::Native {
+                    println!("Secrets use the native age backend; SOPS and age aren't needed.");
+                    return Ok(());
+                }
+                match check_sops_and_age() {
+                    Ok(()) => {
+                        println!("SOPS and age are both installed and available in PATH.");
+                    }
+                    Err(e) => {
+                        eprintln!("{e}");
+                        crate::run_log::exit(1);
+                    }
+                }
+                Ok(())
+            }
+            SecretCommands::Setup { profile, force } => {
+                use crate::secrets::setup_sops_and_age;
+                // Check for valid profile name (no slashes, etc.)
+                if profile.contains('/') || profile.contains('\\') {
+                    eprintln!("Setup failed: Invalid profile name '{profile}'");
+                    crate::run_log::exit(1);
+                }
+                match setup_sops_and_age(&profile, force) {
+                    Ok(()) => {
+                        println!(
+                            "✅ SOPS and age setup completed successfully for profile: {profile}"
+                        );
+                    }
+                    Err(e) => {
+                        eprintln!("Setup failed: {e}");
+                        crate::run_log::exit(1);
+                    }
+                }
+                Ok(())
+            }
+            SecretCommands::Recipients { subcommand } => {
+                use crate::secrets::{encrypted_files, secrets_recipients, validate_age_recipient};
+                let (mut config, config_path) = Config::load()?;
+                let base_dir = config_path.parent().unwrap().to_path_buf();
+                let secrets = encrypted_files(&config, &base_dir, None);
+
+                let message = match subcommand {
+                    RecipientCommands::List => {
+                        let recipients = secrets_recipients(&config);
+                        if recipients.is_empty() {
+                            println!("No age recipients configured.");
+                        }
+                        for recipient in recipients {
+                            if config.secrets.recipients.contains(&recipient) {
+                                println!("{recipient}");
+                            } else {
+                                println!("{recipient} (age_key_file)");
+                            }
+                        }
+                        return Ok(());
+                    }
+                    RecipientCommands::Add { pubkey } => {
+                        validate_age_recipient(&pubkey)?;
+                        if secrets_recipients(&config).contains(&pubkey) {
+                            return Err(OrdinatorError::RecipientExists(pubkey).into());
+                        }
+                        if args.dry_run {
+                            println!(
+                                "DRY-RUN: Would add recipient '{pubkey}' and re-encrypt {} secret(s)",
+                                secrets.len()
+                            );
+                            return Ok(());
+                        }
+                        config.secrets.recipients.push(pubkey.clone());
+                        format!("Added recipient '{pubkey}'")
+                    }
+                    RecipientCommands::Remove { pubkey } => {
+                        if !config.secrets.recipients.contains(&pubkey) {
+                            return Err(OrdinatorError::RecipientNotFound(pubkey).into());
+                        }
+                        if args.dry_run {
+                            println!(
+                                "DRY-RUN: Would remove recipient '{pubkey}' and re-encrypt {} secret(s)",
+                                secrets.len()
+                            );
+                            return Ok(());
+                        }
+                        config.secrets.recipients.retain(|r| r != &pubkey);
+                        eprintln!(
+                            "Note: '{pubkey}' can still decrypt copies of these secrets in Git history. Rotate the secret values if that key is compromised."
+                        );
+                        format!("Removed recipient '{pubkey}'")
+                    }
+                };
+
+                // Regenerate the SOPS config, then re-encrypt every encrypted file for the new list
+                crate::secrets::reencrypt_secrets(&mut config, &config_path, None)?;
+                config.save_to_file(&config_path)?;
+                if args.verbose {
+                    for (file, _) in &secrets {
+                        eprintln!("  Re-encrypted {}", file.display());
+                    }
+                }
+                if !args.quiet {
+                    let msg = format!(
+                        "{message}. Re-encrypted {} secret(s) for {} recipient(s).",
+                        secrets.len(),
+                        secrets_recipients(&config).len()
+                    );
+                    if color_enabled() {
+                        println!("{}", msg.green());
+                    } else {
+                        println!("{msg}");
+                    }
+                }
                 Ok(())
             }
-            SecretCommands::Check => {
-                use crate::secrets::check_sops_and_age;
-                match check_sops_and_age() {
-                    Ok(()) => {
-                        println!("SOPS and age are both installed and available in PATH.");
+            SecretCommands::Rotate {
+                re_encrypt_only,
+                profile,
+                backup_old_key,
+            } => {
+                let (mut config, config_path) = Config::load()?;
+                let base_dir = config_path.parent().unwrap().to_path_buf();
+                if let Some(profile) = &profile {
+                    if !config.profiles.contains_key(profile) {
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
                     }
-                    Err(e) => {
-                        eprintln!("{e}");
-                        std::process::exit(1);
+                }
+                if args.dry_run {
+                    let secrets =
+                        crate::secrets::encrypted_files(&config, &base_dir, profile.as_deref());
+                    let action = if re_encrypt_only {
+                        "re-encrypt"
+                    } else {
+                        "rotate the age key and re-encrypt"
+                    };
+                    println!("DRY-RUN: Would {action} {} secret(s)", secrets.len());
+                    for (file, _) in secrets {
+                        let relative = file.strip_prefix(&base_dir).unwrap_or(&file);
+                        println!("  {}", relative.display());
+                    }
+                    return Ok(());
+                }
+
+                if !re_encrypt_only {
+                    let profiles: Vec<String> = match profile {
+                        Some(profile) => vec![profile],
+                        None => config.list_profiles().into_iter().cloned().collect(),
+                    };
+                    for profile in profiles {
+                        crate::secrets::rotate_age_keys(&profile, backup_old_key, false)?;
+                    }
+                    return Ok(());
+                }
+
+                let secrets = crate::secrets::reencrypt_secrets(
+                    &mut config,
+                    &config_path,
+                    profile.as_deref(),
+                )?;
+                if args.verbose {
+                    for file in &secrets {
+                        eprintln!("  Re-encrypted {}", file.display());
                     }
                 }
+                if !args.quiet {
+                    println!(
+                        "✅ Re-encrypted {} secret(s) for {} recipient(s); recorded in {}",
+                        secrets.len(),
+                        crate::secrets::secrets_recipients(&config).len(),
+                        crate::rotation::ROTATION_FILE
+                    );
+                }
                 Ok(())
             }
-            SecretCommands::Setup { profile, force } => {
-                use crate::secrets::setup_sops_and_age;
-                // Check for valid profile name (no slashes, etc.)
-                if profile.contains('/') || profile.contains('\\') {
-                    eprintln!("Setup failed: Invalid profile name '{profile}'");
-                    std::process::exit(1);
-                }
-                match setup_sops_and_age(&profile, force) {
-                    Ok(()) => {
-                        println!(
-                            "✅ SOPS and age setup completed successfully for profile: {profile}"
-                        );
+            SecretCommands::Migrate { to } => {
+                let backend: crate::config::SecretsBackend = to.parse()?;
+                let (mut config, config_path) = Config::load()?;
+                let base_dir = config_path.parent().unwrap().to_path_buf();
+                if args.dry_run {
+                    let files = crate::secrets::files_to_migrate(&config, &base_dir, backend);
+                    println!(
+                        "DRY-RUN: Would re-encrypt {} file(s) with the {} backend",
+                        files.len(),
+                        backend.name()
+                    );
+                    for file in files {
+                        let relative = file.strip_prefix(&base_dir).unwrap_or(&file);
+                        println!("  {}", relative.display());
                     }
-                    Err(e) => {
-                        eprintln!("Setup failed: {e}");
-                        std::process::exit(1);
+                    return Ok(());
+                }
+                let files = crate::secrets::migrate_backend(&mut config, &config_path, backend)?;
+                if args.verbose {
+                    for file in &files {
+                        let relative = file.strip_prefix(&base_dir).unwrap_or(file);
+                        eprintln!("  Re-encrypted {}", relative.display());
                     }
                 }
+                if !args.quiet {
+                    println!(
+                        "✅ Re-encrypted {} file(s); secrets now use the {} backend",
+                        files.len(),
+                        backend.name()
+                    );
+                }
                 Ok(())
             }
-            SecretCommands::Scan { profile, verbose } => {
+            SecretCommands::Scan {
+                profile,
+                verbose,
+                update_baseline,
+                check,
+            } => {
                 let (config, config_path) = Config::load()?;
                 let base_dir = config_path.parent().unwrap().to_path_buf();
-                let manager = crate::secrets::SecretsManager::new(
-                    None,
-                    None,
-                    config.clone(),
-                    base_dir.clone(),
-                );
 
                 let profiles_to_scan = if let Some(profile_name) = profile {
                     if !config.profiles.contains_key(&profile_name) {
                         eprintln!("Scan failed: Profile '{profile_name}' does not exist.");
-                        std::process::exit(1);
+                        crate::run_log::exit(1);
                     }
                     vec![profile_name]
                 } else {
@@ -2687,64 +6409,60 @@ pub async fn run(args: Args) -> Result<()> {
                         .map(|s| s.to_string())
                         .collect()
                 };
+                if !args.quiet {
+                    for profile_name in &profiles_to_scan {
+                        eprintln!("Scanning profile: {profile_name}");
+                    }
+                }
 
-                let mut found_secrets = false;
-                let mut total_files_scanned = 0;
+                let report =
+                    crate::scan::scan_profiles(&config, &base_dir, &profiles_to_scan).await?;
+                warn_scan_timeouts(&report);
+                let (findings, total_files_scanned) = (report.findings, report.scanned);
+                let baseline_path = crate::scan::Baseline::path(&base_dir, &config.secrets.scan);
 
-                for profile_name in profiles_to_scan {
-                    if let Some(profile) = config.get_profile(&profile_name) {
+                if update_baseline {
+                    let count = findings.len();
+                    if args.dry_run {
+                        eprintln!(
+                            "DRY-RUN: Would write {count} finding(s) to {}",
+                            baseline_path.display()
+                        );
+                    } else {
+                        crate::scan::Baseline { findings }.save(&baseline_path)?;
                         if !args.quiet {
-                            eprintln!("Scanning profile: {profile_name}");
-                        }
-
-                        for file_path in &profile.files {
-                            total_files_scanned += 1;
-                            let full_path = base_dir.join(file_path);
-
-                            if full_path.exists() && full_path.is_file() {
-                                match manager.check_for_plaintext_secrets(&full_path) {
-                                    Ok(has_secrets) => {
-                                        if has_secrets {
-                                            found_secrets = true;
-                                            if verbose {
-                                                // Get detailed info about what types of secrets were found
-                                                match manager.get_secrets_info(&full_path) {
-                                                    Ok(secret_types) => {
-                                                        eprintln!("⚠️  Potential secrets found in: {} ({})", 
-                                                                 file_path, secret_types.join(", "));
-                                                    }
-                                                    Err(_) => {
-                                                        eprintln!(
-                                                            "⚠️  Potential secrets found in: {file_path}"
-                                                        );
-                                                    }
-                                                }
-                                            } else {
-                                                eprintln!("⚠️  {file_path}");
-                                            }
-                                        }
-                                    }
-                                    Err(e) => {
-                                        if verbose {
-                                            eprintln!("Error scanning {file_path}: {e}");
-                                        }
-                                    }
-                                }
-                            }
+                            eprintln!("Wrote {count} finding(s) to {}", baseline_path.display());
                         }
                     }
+                    return Ok(());
+                }
+
+                let baseline = crate::scan::Baseline::load(&baseline_path)?;
+                let (new_findings, known) = baseline.partition(findings);
+                print_scan_findings(&new_findings, verbose);
+                if !known.is_empty() && !args.quiet {
+                    eprintln!(
+                        "{} known finding(s) ignored (in {})",
+                        known.len(),
+                        baseline_path.display()
+                    );
                 }
 
-                if !found_secrets {
+                if new_findings.is_empty() {
                     if !args.quiet {
                         eprintln!("✅ No plaintext secrets found in {total_files_scanned} files");
                     }
                 } else {
                     if !args.quiet {
                         eprintln!("⚠️  Plaintext secrets detected in tracked files. Consider encrypting them with 'ordinator secrets encrypt <file>'");
+                        eprintln!("   To accept known findings, add '# {}' to the line or run 'ordinator secrets scan --update-baseline'", crate::scan::ALLOW_MARKER);
                     }
                     // Always exit with error code when secrets are found
-                    std::process::exit(1);
+                    crate::run_log::exit(if check {
+                        crate::exit_code::SECRETS_FOUND
+                    } else {
+                        crate::exit_code::ERROR
+                    });
                 }
                 Ok(())
             }
@@ -2765,9 +6483,33 @@ pub async fn run(args: Args) -> Result<()> {
                 return Ok(());
             }
 
-            // TODO: Implement actual script generation logic
-            info!("Script generation not yet implemented");
-            eprintln!("Script generation not yet implemented");
+            let (config, config_path) = Config::load()?;
+            if !config.profiles.contains_key(&profile) {
+                return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+            }
+
+            let dotfiles_dir = config_path.parent().unwrap().to_path_buf();
+            let repo_url = GitManager::new(dotfiles_dir).get_origin_url()?;
+
+            use crate::bootstrap::BootstrapManager;
+            let bootstrap_manager = BootstrapManager::new(args.dry_run);
+            let output_path = PathBuf::from(&output);
+            bootstrap_manager.generate_system_script(
+                &profile,
+                &config,
+                repo_url.as_deref(),
+                &output_path,
+            )?;
+
+            if !args.quiet {
+                let msg = format!("Generated system script: {}", output_path.display());
+                if color_enabled() {
+                    println!("{}", msg.green());
+                } else {
+                    println!("{msg}");
+                }
+                println!("   Run it on the target machine with: bash {output}");
+            }
             Ok(())
         }
         Commands::Bootstrap { subcommand } => {
@@ -2780,7 +6522,7 @@ pub async fn run(args: Args) -> Result<()> {
 
                     let (config, config_path) = Config::load()?;
                     if !config.profiles.contains_key(&profile) {
-                        return Err(anyhow::anyhow!("Profile '{profile}' does not exist."));
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
                     }
 
                     let dotfiles_dir = config_path.parent().unwrap();
@@ -2818,7 +6560,8 @@ pub async fn run(args: Args) -> Result<()> {
 
                             // Always print the command for the user to run
                             eprintln!("\nTo run the bootstrap script, execute:");
-                            eprintln!("  bash {}", full_script_path.display());
+                            eprintln!("  ordinator bootstrap run --profile {profile}");
+                            eprintln!("  (or: bash {})", full_script_path.display());
                             eprintln!("\nOr review and edit the script before running as needed.");
                         } else if !args.quiet {
                             eprintln!("Bootstrap script not found: {}", full_script_path.display());
@@ -2839,7 +6582,7 @@ pub async fn run(args: Args) -> Result<()> {
 
                     let (mut config, config_path) = Config::load()?;
                     if !config.profiles.contains_key(&profile) {
-                        return Err(anyhow::anyhow!("Profile '{profile}' does not exist."));
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
                     }
 
                     let dotfiles_dir = config_path.parent().unwrap();
@@ -2925,7 +6668,7 @@ pub async fn run(args: Args) -> Result<()> {
 
                     let (config, config_path) = Config::load()?;
                     if !config.profiles.contains_key(&profile) {
-                        return Err(anyhow::anyhow!("Profile '{profile}' does not exist."));
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
                     }
 
                     let dotfiles_dir = config_path.parent().unwrap();
@@ -2966,24 +6709,157 @@ pub async fn run(args: Args) -> Result<()> {
 
                     Ok(())
                 }
+                BootstrapCommands::Run {
+                    profile,
+                    all_profiles,
+                    timeout,
+                } => {
+                    let (config, config_path) = Config::load()?;
+                    let dotfiles_dir = config_path.parent().unwrap();
+                    let mut profiles = selected_profiles(&config, &profile, all_profiles)?;
+                    if profiles.len() == 1 {
+                        return run_bootstrap(
+                            &config,
+                            dotfiles_dir,
+                            &profiles[0],
+                            timeout,
+                            args.dry_run,
+                        );
+                    }
+                    profiles.retain(|profile| {
+                        let configured = config.get_bootstrap_script(profile).is_some();
+                        if !configured && !args.quiet {
+                            eprintln!("No bootstrap script configured for profile '{profile}'; skipping it");
+                        }
+                        configured
+                    });
+                    let mut results = Vec::new();
+                    for profile in profiles {
+                        let result =
+                            run_bootstrap(&config, dotfiles_dir, &profile, timeout, args.dry_run);
+                        if let Err(e) = &result {
+                            eprintln!("❌ {e}");
+                        }
+                        let outcome = result
+                            .map(|()| "bootstrap script finished".to_string())
+                            .map_err(|e| e.to_string());
+                        results.push((profile, outcome));
+                    }
+                    if !args.quiet && !args.dry_run && !results.is_empty() {
+                        print_profile_summary(&results);
+                    }
+                    let failed = results.iter().filter(|(_, result)| result.is_err()).count();
+                    if failed > 0 {
+                        return Err(anyhow::anyhow!(
+                            "Bootstrap scripts failed for {failed} profile(s)"
+                        ));
+                    }
+                    Ok(())
+                }
+                BootstrapCommands::History { profile, limit } => {
+                    let (config, _) = Config::load()?;
+                    let mut receipts = crate::receipts::load(&config.target_root()?)?;
+                    if let Some(profile) = &profile {
+                        receipts.retain(|receipt| &receipt.profile == profile);
+                    }
+                    if receipts.is_empty() {
+                        match &profile {
+                            Some(profile) => eprintln!(
+                                "No bootstrap runs recorded for profile '{profile}' on this machine"
+                            ),
+                            None => eprintln!("No bootstrap runs recorded on this machine"),
+                        }
+                        return Ok(());
+                    }
+                    for receipt in receipts.iter().rev().take(limit.unwrap_or(usize::MAX)) {
+                        let outcome = format!("{:<9}", receipt.outcome());
+                        let outcome = if receipt.success() {
+                            paint(&outcome, Tone::Good)
+                        } else {
+                            paint(&outcome, Tone::Broken)
+                        };
+                        println!(
+                            "{}  {}  {outcome}  {:.1}s  {}  {}",
+                            crate::utils::format_timestamp(&receipt.ran_at),
+                            receipt.profile,
+                            receipt.duration_ms as f64 / 1000.0,
+                            receipt.script,
+                            &receipt.script_hash[..12.min(receipt.script_hash.len())]
+                        );
+                    }
+                    Ok(())
+                }
             }
         }
         Commands::Brew { subcommand } => {
             use crate::brew::BrewManager;
 
-            // Check if Homebrew is installed
-            if !BrewManager::check_homebrew_installed() {
+            // Check if Homebrew is installed (importing a Brewfile only edits config)
+            if !matches!(subcommand, BrewCommands::Import { .. })
+                && !BrewManager::check_homebrew_installed()
+            {
                 eprintln!("❌ Homebrew is not installed. Please install Homebrew first:");
                 eprintln!("   /bin/bash -c \"$(curl -fsSL https://raw.githubusercontent.com/Homebrew/install/HEAD/install.sh)\"");
-                std::process::exit(1);
+                crate::run_log::exit(1);
             }
 
             match subcommand {
+                BrewCommands::Export {
+                    profile: _,
+                    with_versions: _,
+                    force,
+                    format,
+                    output,
+                    no_commit: _,
+                } if format == "brewfile" => {
+                    let output_path = std::path::PathBuf::from(&output);
+                    if output_path.exists() && !force {
+                        return Err(anyhow::anyhow!(
+                            "{} already exists. Use --force to overwrite it.",
+                            output_path.display()
+                        ));
+                    }
+
+                    if args.dry_run {
+                        eprintln!(
+                            "DRY-RUN: Would export Homebrew packages to {}",
+                            output_path.display()
+                        );
+                        return Ok(());
+                    }
+
+                    let brew_manager = BrewManager::new(args.dry_run);
+                    let brewfile = brew_manager.export_brewfile().await?;
+                    std::fs::write(&output_path, brewfile.to_brewfile_string()).map_err(|e| {
+                        anyhow::anyhow!("Failed to write {}: {}", output_path.display(), e)
+                    })?;
+
+                    if !args.quiet {
+                        eprintln!(
+                            "✅ Exported {} tap(s), {} formula(s), {} cask(s), and {} App Store app(s) to {}",
+                            brewfile.taps.len(),
+                            brewfile.formulas.len(),
+                            brewfile.casks.len(),
+                            brewfile.mas_apps.len(),
+                            output_path.display()
+                        );
+                    }
+                    Ok(())
+                }
                 BrewCommands::Export {
                     profile,
                     with_versions: _,
                     force,
+                    format,
+                    output: _,
+                    no_commit,
                 } => {
+                    if format != "toml" {
+                        return Err(anyhow::anyhow!(
+                            "Unknown export format '{}'. Use 'toml' or 'brewfile'.",
+                            format
+                        ));
+                    }
                     info!("Exporting Homebrew packages for profile: {}", profile);
                     if !args.quiet {
                         eprintln!("Exporting Homebrew packages for profile: {profile}");
@@ -2992,7 +6868,7 @@ pub async fn run(args: Args) -> Result<()> {
                     let (mut config, config_path) = Config::load()?;
 
                     if !config.profiles.contains_key(&profile) {
-                        return Err(anyhow::anyhow!("Profile '{}' does not exist.", profile));
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
                     }
 
                     // Check if packages already exist and force is not set
@@ -3000,7 +6876,7 @@ pub async fn run(args: Args) -> Result<()> {
                     if !profile_config.homebrew_formulas.is_empty() && !force {
                         eprintln!("⚠️  Profile '{profile}' already has Homebrew formulas defined.");
                         eprintln!("   Use --force to overwrite existing package list.");
-                        std::process::exit(1);
+                        crate::run_log::exit(1);
                     }
 
                     if args.dry_run {
@@ -3019,6 +6895,15 @@ pub async fn run(args: Args) -> Result<()> {
 
                     if !args.quiet {
                         eprintln!("✅ Exported Homebrew packages to profile '{profile}'");
+                        if let Some(exported) = config.get_profile(&profile) {
+                            eprintln!(
+                                "   {} tap(s), {} formula(s), {} cask(s), {} App Store app(s)",
+                                exported.homebrew_taps.len(),
+                                exported.homebrew_formulas.len(),
+                                exported.homebrew_casks.len(),
+                                exported.mas_apps.len()
+                            );
+                        }
                     }
 
                     // Auto-update README if needed
@@ -3040,33 +6925,270 @@ pub async fn run(args: Args) -> Result<()> {
                         }
                     }
 
+                    auto_commit(
+                        &config,
+                        &config_path,
+                        args.quiet,
+                        no_commit,
+                        &format!("ordinator: export Homebrew packages to {profile}"),
+                    )
+                    .await
+                }
+                BrewCommands::Import {
+                    file,
+                    profile,
+                    replace,
+                } => {
+                    info!("Importing Brewfile {} into profile: {}", file, profile);
+                    let (mut config, config_path) = Config::load()?;
+
+                    if !config.profiles.contains_key(&profile) {
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+                    }
+
+                    let content = std::fs::read_to_string(&file).map_err(|e| {
+                        anyhow::anyhow!("Failed to read Brewfile '{}': {}", file, e)
+                    })?;
+                    let brewfile = crate::brew::Brewfile::parse(&content)?;
+
+                    if args.dry_run {
+                        eprintln!(
+                            "DRY-RUN: Would import {} tap(s), {} formula(s), {} cask(s), and {} App Store app(s) into profile '{profile}'",
+                            brewfile.taps.len(),
+                            brewfile.formulas.len(),
+                            brewfile.casks.len(),
+                            brewfile.mas_apps.len()
+                        );
+                        return Ok(());
+                    }
+
+                    let brew_manager = BrewManager::new(args.dry_run);
+                    brew_manager.import_brewfile(&profile, &brewfile, replace, &mut config)?;
+                    config.save_to_file(&config_path)?;
+
+                    if !args.quiet {
+                        eprintln!(
+                            "✅ Imported {} tap(s), {} formula(s), {} cask(s), and {} App Store app(s) into profile '{profile}'",
+                            brewfile.taps.len(),
+                            brewfile.formulas.len(),
+                            brewfile.casks.len(),
+                            brewfile.mas_apps.len()
+                        );
+                        if !brewfile.skipped.is_empty() {
+                            eprintln!(
+                                "⚠️  Skipped entries ordinator does not manage: {}",
+                                brewfile.skipped.join(", ")
+                            );
+                        }
+                        eprintln!(
+                            "   Run 'ordinator brew install --profile {profile}' to install them."
+                        );
+                    }
                     Ok(())
                 }
                 BrewCommands::Install {
                     profile,
-                    non_interactive: _,
-                    force: _,
+                    all_profiles,
+                    non_interactive,
+                    force,
+                    strict,
                 } => {
-                    info!("Installing Homebrew packages for profile: {}", profile);
-                    if !args.quiet {
-                        eprintln!("Installing Homebrew packages for profile: {profile}");
+                    if non_interactive {
+                        crate::prompt::set_no_input(true);
+                    }
+                    if force {
+                        crate::prompt::set_assume_yes(true);
+                    }
+
+                    let (config, _) = Config::load()?;
+                    let profiles = selected_profiles(&config, &profile, all_profiles)?;
+
+                    let brew_manager = BrewManager::new(args.dry_run);
+                    let many = profiles.len() > 1;
+                    let mut results = Vec::new();
+                    let (mut total_failed, mut errors) = (0, 0);
+                    for profile in profiles {
+                        info!("Installing Homebrew packages for profile: {}", profile);
+                        if !args.quiet {
+                            eprintln!("Installing Homebrew packages for profile: {profile}");
+                        }
+                        let report = match brew_manager.install_packages(&profile, &config).await {
+                            Ok(report) => report,
+                            Err(e) if many => {
+                                eprintln!("❌ {e}");
+                                results.push((profile, Err(e.to_string())));
+                                errors += 1;
+                                continue;
+                            }
+                            Err(e) => return Err(e),
+                        };
+                        if !args.quiet && !report.is_empty() {
+                            eprintln!("{}", report.table());
+                        }
+                        let failed = report.failures();
+                        if failed > 0 && strict && !many {
+                            return Err(OrdinatorError::BrewPackagesFailed(failed).into());
+                        }
+                        total_failed += failed;
+                        if !args.quiet {
+                            if failed > 0 {
+                                eprintln!(
+                                    "⚠️  {failed} Homebrew package(s) failed to install for profile '{profile}'"
+                                );
+                            } else {
+                                eprintln!(
+                                    "✅ Homebrew package installation complete for profile '{profile}'"
+                                );
+                            }
+                        }
+                        let installed = report.results.len() - failed;
+                        results.push((
+                            profile,
+                            if failed > 0 {
+                                Err(format!("{installed} installed, {failed} failed"))
+                            } else {
+                                Ok(format!("{installed} installed"))
+                            },
+                        ));
+                    }
+                    if many && !args.quiet {
+                        print_profile_summary(&results);
+                    }
+                    if total_failed > 0 && strict {
+                        return Err(OrdinatorError::BrewPackagesFailed(total_failed).into());
+                    }
+                    if errors > 0 {
+                        return Err(anyhow::anyhow!(
+                            "Homebrew packages could not be installed for {errors} profile(s)"
+                        ));
                     }
 
+                    Ok(())
+                }
+                BrewCommands::Diff { profile } => {
                     let (config, _) = Config::load()?;
+                    if !config.profiles.contains_key(&profile) {
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+                    }
+                    if !BrewManager::check_homebrew_installed() {
+                        return Err(OrdinatorError::BrewNotInstalled.into());
+                    }
 
+                    let diff = BrewManager::new(args.dry_run)
+                        .diff_packages(&profile, &config)
+                        .await?;
+                    if diff.is_empty() {
+                        println!("Homebrew packages match profile '{profile}'");
+                        return Ok(());
+                    }
+                    println!("Homebrew packages compared with profile '{profile}':");
+                    let mut lines = Vec::new();
+                    for (names, kind) in [
+                        (&diff.missing_formulas, "formula"),
+                        (&diff.missing_casks, "cask"),
+                    ] {
+                        for name in names {
+                            lines.push((format!("  + {name} ({kind}, not installed)"), "green"));
+                        }
+                    }
+                    for (names, kind) in [
+                        (&diff.extra_formulas, "formula"),
+                        (&diff.extra_casks, "cask"),
+                    ] {
+                        for name in names {
+                            lines.push((format!("  - {name} ({kind}, not in profile)"), "red"));
+                        }
+                    }
+                    for package in &diff.outdated {
+                        lines.push((
+                            format!(
+                                "  ~ {} {} -> {} (outdated)",
+                                package.name, package.installed, package.latest
+                            ),
+                            "yellow",
+                        ));
+                    }
+                    for (line, color) in lines {
+                        if color_enabled() {
+                            println!("{}", line.color(color));
+                        } else {
+                            println!("{line}");
+                        }
+                    }
+                    println!(
+                        "{} missing, {} extra, {} outdated",
+                        diff.missing_formulas.len() + diff.missing_casks.len(),
+                        diff.extra_formulas.len() + diff.extra_casks.len(),
+                        diff.outdated.len()
+                    );
+                    Ok(())
+                }
+                BrewCommands::Outdated { profile } => {
+                    let (config, _) = Config::load()?;
                     if !config.profiles.contains_key(&profile) {
-                        return Err(anyhow::anyhow!("Profile '{}' does not exist.", profile));
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
                     }
 
-                    let brew_manager = BrewManager::new(args.dry_run);
-                    brew_manager.install_packages(&profile, &config).await?;
+                    let resolved = config.resolve_profile(&profile)?;
+                    let outdated = BrewManager::new(args.dry_run).outdated_resolved(&resolved)?;
+                    if outdated.is_empty() {
+                        println!(
+                            "Homebrew packages declared in profile '{profile}' are up to date"
+                        );
+                        return Ok(());
+                    }
+                    for package in &outdated {
+                        let line = format!(
+                            "{} {} -> {} ({})",
+                            package.name,
+                            package.installed,
+                            package.latest,
+                            if package.cask { "cask" } else { "formula" }
+                        );
+                        if color_enabled() {
+                            println!("{}", line.yellow());
+                        } else {
+                            println!("{line}");
+                        }
+                    }
+                    println!(
+                        "{} outdated package(s) in profile '{profile}'",
+                        outdated.len()
+                    );
+                    Ok(())
+                }
+                BrewCommands::Sync {
+                    profile,
+                    remove_extra,
+                } => {
+                    let (config, _) = Config::load()?;
+                    if !config.profiles.contains_key(&profile) {
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+                    }
+                    if !BrewManager::check_homebrew_installed() {
+                        return Err(OrdinatorError::BrewNotInstalled.into());
+                    }
+                    if !args.quiet {
+                        eprintln!("Syncing Homebrew packages with profile '{profile}'");
+                    }
 
+                    let (diff, report) = BrewManager::new(args.dry_run)
+                        .sync_packages(&profile, &config, remove_extra)
+                        .await?;
+                    let extra = diff.extra_formulas.len() + diff.extra_casks.len();
                     if !args.quiet {
-                        eprintln!(
-                            "✅ Homebrew package installation complete for profile '{profile}'"
-                        );
+                        if !report.is_empty() {
+                            eprintln!("{}", report.table());
+                        }
+                        if extra > 0 && !remove_extra {
+                            eprintln!(
+                                "{extra} package(s) not in the profile were left installed. Use --remove-extra to uninstall them."
+                            );
+                        }
+                        if !args.dry_run {
+                            eprintln!("✅ Homebrew packages synced with profile '{profile}'");
+                        }
                     }
-
                     Ok(())
                 }
                 BrewCommands::List {
@@ -3081,7 +7203,7 @@ pub async fn run(args: Args) -> Result<()> {
                     let (config, _) = Config::load()?;
 
                     if !config.profiles.contains_key(&profile) {
-                        return Err(anyhow::anyhow!("Profile '{}' does not exist.", profile));
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
                     }
 
                     let brew_manager = BrewManager::new(args.dry_run);
@@ -3091,6 +7213,135 @@ pub async fn run(args: Args) -> Result<()> {
                 }
             }
         }
+        Commands::Extensions { subcommand } => {
+            use crate::extensions::{EditorTool, ExtensionManager};
+
+            let (mut config, config_path) = Config::load()?;
+            let manager = ExtensionManager::new(args.dry_run);
+            match subcommand {
+                ExtensionsCommands::Export {
+                    tool,
+                    profile,
+                    force,
+                    no_commit,
+                } => {
+                    let tool: EditorTool = tool.parse()?;
+                    let Some(profile_config) = config.get_profile(&profile) else {
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+                    };
+                    if profile_config
+                        .extensions
+                        .get(tool.name())
+                        .is_some_and(|ids| !ids.is_empty())
+                        && !force
+                    {
+                        return Err(anyhow::anyhow!(
+                            "Profile '{}' already has {} extensions. Use --force to overwrite them.",
+                            profile,
+                            tool.name()
+                        ));
+                    }
+                    if !tool.is_available() {
+                        return Err(anyhow::anyhow!(
+                            "'{}' is not installed or not found in PATH.",
+                            tool.command()
+                        ));
+                    }
+                    if args.dry_run {
+                        eprintln!(
+                            "DRY-RUN: Would export {} extensions to profile '{profile}'",
+                            tool.name()
+                        );
+                        return Ok(());
+                    }
+
+                    let count = manager.export(tool, &profile, &mut config)?;
+                    config.save_to_file(&config_path)?;
+                    if !args.quiet {
+                        eprintln!(
+                            "✅ Exported {count} {} extension(s) to profile '{profile}'",
+                            tool.name()
+                        );
+                    }
+                    auto_commit(
+                        &config,
+                        &config_path,
+                        args.quiet,
+                        no_commit,
+                        &format!("ordinator: export {} extensions to {profile}", tool.name()),
+                    )
+                    .await
+                }
+                ExtensionsCommands::Install { tool, profile } => {
+                    if !config.profiles.contains_key(&profile) {
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+                    }
+                    let only = tool.map(|tool| tool.parse::<EditorTool>()).transpose()?;
+                    let resolved = config.resolve_profile(&profile)?;
+                    let mut total = 0;
+                    for (name, ids) in &resolved.extensions {
+                        let tool: EditorTool = name.parse()?;
+                        if only.is_some_and(|only| only != tool) || ids.is_empty() {
+                            continue;
+                        }
+                        if !tool.is_available() {
+                            if only.is_some() {
+                                return Err(anyhow::anyhow!(
+                                    "'{}' is not installed or not found in PATH.",
+                                    tool.command()
+                                ));
+                            }
+                            eprintln!(
+                                "⚠️  Warning: '{}' not found - skipping {} extensions",
+                                tool.command(),
+                                tool.name()
+                            );
+                            continue;
+                        }
+                        let installed = manager.install(tool, ids)?;
+                        for id in &installed {
+                            if args.dry_run {
+                                eprintln!("DRY-RUN: Would install {} extension: {id}", tool.name());
+                            } else if !args.quiet {
+                                eprintln!("Installed {} extension: {id}", tool.name());
+                            }
+                        }
+                        total += installed.len();
+                    }
+                    if !args.quiet && !args.dry_run {
+                        if total == 0 {
+                            eprintln!("All extensions for profile '{profile}' are installed");
+                        } else {
+                            eprintln!("✅ Installed {total} extension(s)");
+                        }
+                    }
+                    Ok(())
+                }
+                ExtensionsCommands::List { tool, profile } => {
+                    if !config.profiles.contains_key(&profile) {
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+                    }
+                    let only = tool.map(|tool| tool.parse::<EditorTool>()).transpose()?;
+                    let resolved = config.resolve_profile(&profile)?;
+                    let mut any = false;
+                    for (name, ids) in &resolved.extensions {
+                        let tool: EditorTool = name.parse()?;
+                        if only.is_some_and(|only| only != tool) || ids.is_empty() {
+                            continue;
+                        }
+                        any = true;
+                        println!("{} extensions for profile '{profile}':", tool.name());
+                        for id in ids {
+                            println!("  - {id}");
+                        }
+                    }
+                    if !any {
+                        eprintln!("No editor extensions listed for profile '{profile}'");
+                    }
+                    Ok(())
+                }
+            }
+        }
         Commands::Age { subcommand } => match subcommand {
             AgeCommands::Encrypt { file, dry_run } => {
                 let file_path = std::path::Path::new(&file);
@@ -3120,7 +7371,7 @@ pub async fn run(args: Args) -> Result<()> {
                     }
                     Err(e) => {
                         eprintln!("Encryption failed: {e}");
-                        std::process::exit(1);
+                        crate::run_log::exit(1);
                     }
                 }
                 Ok(())
@@ -3153,16 +7404,44 @@ pub async fn run(args: Args) -> Result<()> {
                     }
                     Err(e) => {
                         eprintln!("Decryption failed: {e}");
-                        std::process::exit(1);
+                        crate::run_log::exit(1);
                     }
                 }
                 Ok(())
             }
             AgeCommands::Setup {
                 profile,
+                all_profiles,
                 force,
                 dry_run,
             } => {
+                if all_profiles || crate::config::is_profile_glob(&profile) {
+                    let (config, _) = Config::load()?;
+                    let mut results = Vec::new();
+                    for profile in selected_profiles(&config, &profile, all_profiles)? {
+                        if args.dry_run || dry_run {
+                            println!(
+                                "DRY-RUN: Would set up age encryption for profile '{profile}'"
+                            );
+                            continue;
+                        }
+                        let outcome = crate::secrets::setup_sops_and_age(&profile, force)
+                            .map(|()| "age encryption set up".to_string())
+                            .map_err(|e| e.to_string());
+                        if let Err(e) = &outcome {
+                            eprintln!("Setup failed for profile '{profile}': {e}");
+                        }
+                        results.push((profile, outcome));
+                    }
+                    if results.is_empty() {
+                        return Ok(());
+                    }
+                    print_profile_summary(&results);
+                    if results.iter().any(|(_, outcome)| outcome.is_err()) {
+                        crate::run_log::exit(1);
+                    }
+                    return Ok(());
+                }
                 if args.dry_run || dry_run {
                     println!("DRY-RUN: Would set up age encryption for profile '{profile}'");
                     return Ok(());
@@ -3171,7 +7450,7 @@ pub async fn run(args: Args) -> Result<()> {
                 // Check for valid profile name (no slashes, etc.)
                 if profile.contains('/') || profile.contains('\\') {
                     eprintln!("Setup failed: Invalid profile name '{profile}'");
-                    std::process::exit(1);
+                    crate::run_log::exit(1);
                 }
 
                 // Check if key rotation is needed for this profile
@@ -3187,23 +7466,28 @@ pub async fn run(args: Args) -> Result<()> {
                     }
                     Err(e) => {
                         eprintln!("Setup failed: {e}");
-                        std::process::exit(1);
+                        crate::run_log::exit(1);
                     }
                 }
                 Ok(())
             }
             AgeCommands::Validate { profile } => {
-                let (config, _) = Config::load()?;
+                let (config, config_path) = Config::load()?;
+                let key_age_warning =
+                    crate::rotation::key_age_warning(&config, config_path.parent().unwrap())?;
                 let base_dir = std::path::PathBuf::from(".");
                 let manager = crate::secrets::SecretsManager::new(None, None, config, base_dir);
 
                 match manager.validate_installation() {
                     Ok(()) => {
                         println!("✅ Age encryption setup is valid for profile: {profile}");
+                        if let Some(warning) = key_age_warning {
+                            eprintln!("⚠️  Warning: {warning}");
+                        }
                     }
                     Err(e) => {
                         eprintln!("Validation failed: {e}");
-                        std::process::exit(1);
+                        crate::run_log::exit(1);
                     }
                 }
                 Ok(())
@@ -3214,29 +7498,536 @@ pub async fn run(args: Args) -> Result<()> {
                 force,
                 dry_run,
             } => {
+                let (config, config_path) = Config::load()?;
+                let target_profiles: Vec<String> = if let Some(p) = profile {
+                    vec![p]
+                } else {
+                    config
+                        .list_profiles()
+                        .into_iter()
+                        .map(|s| s.to_string())
+                        .collect()
+                };
                 if args.dry_run || dry_run {
+                    let base_dir = config_path.parent().unwrap();
+                    for prof in &target_profiles {
+                        let secrets =
+                            crate::secrets::encrypted_files(&config, base_dir, Some(prof));
+                        println!(
+                            "DRY-RUN: Would rotate age keys for profile '{prof}' and re-encrypt {} secret(s)",
+                            secrets.len()
+                        );
+                        for (file, _) in secrets {
+                            let relative = file.strip_prefix(base_dir).unwrap_or(&file);
+                            println!("  {}", relative.display());
+                        }
+                    }
+                    return Ok(());
+                }
+                for prof in target_profiles {
+                    crate::secrets::rotate_age_keys(&prof, backup_old_key, force)?;
+                }
+                Ok(())
+            }
+            AgeCommands::Key { subcommand } => match subcommand {
+                AgeKeyCommands::Import {
+                    file,
+                    profile,
+                    storage,
+                    force,
+                } => {
+                    let (mut config, config_path) = Config::load()?;
+                    let storage = match storage {
+                        Some(storage) => storage.parse()?,
+                        None => config.secrets.age_key_storage,
+                    };
+                    let key_file = crate::secrets::get_age_key_path(&profile);
+                    if crate::age_key::exists(&key_file) && !force {
+                        return Err(anyhow::anyhow!(
+                            "An age key for profile '{}' is already stored. Use --force to replace it.",
+                            profile
+                        ));
+                    }
+                    if args.dry_run {
+                        println!(
+                            "DRY-RUN: Would import the age key from '{}' into {}",
+                            file.display(),
+                            storage.description()
+                        );
+                        return Ok(());
+                    }
+                    let key = if file.as_os_str() == "-" {
+                        std::io::read_to_string(std::io::stdin())?
+                    } else {
+                        std::fs::read_to_string(&file).map_err(|e| {
+                            anyhow::anyhow!("Failed to read {}: {}", file.display(), e)
+                        })?
+                    };
+                    crate::age_key::store(&key_file, storage, &key)?;
+                    config.secrets.age_key_file = Some(key_file);
+                    config.secrets.age_key_storage = storage;
+                    config.save_to_file(&config_path)?;
                     println!(
-                        "DRY-RUN: Would rotate age keys for profile '{}'",
-                        profile.as_deref().unwrap_or("all")
+                        "✅ Imported the age key for profile '{profile}' into {}",
+                        storage.description()
                     );
-                    return Ok(());
+                    Ok(())
+                }
+                AgeKeyCommands::Export { output } => {
+                    let (config, _) = Config::load()?;
+                    let key_file = configured_age_key(&config)?;
+                    let key = crate::age_key::read(&key_file, config.secrets.age_key_storage)?;
+                    match output {
+                        Some(output) => {
+                            use std::io::Write;
+                            use std::os::unix::fs::OpenOptionsExt;
+                            let mut file = std::fs::OpenOptions::new()
+                                .write(true)
+                                .create_new(true)
+                                .mode(0o600)
+                                .open(&output)
+                                .map_err(|e| {
+                                    anyhow::anyhow!("Failed to create {}: {}", output.display(), e)
+                                })?;
+                            file.write_all(key.as_bytes())?;
+                            eprintln!("✅ Exported the age key to {}", output.display());
+                        }
+                        None => print!("{key}"),
+                    }
+                    Ok(())
+                }
+                AgeKeyCommands::Keychain => {
+                    move_age_key(crate::age_key::KeyStorage::Keychain, args.dry_run)
+                }
+                AgeKeyCommands::Passphrase => {
+                    move_age_key(crate::age_key::KeyStorage::Passphrase, args.dry_run)
+                }
+            },
+        },
+        Commands::Ssh { subcommand } => {
+            let (mut config, config_path) = Config::load()?;
+            let ssh_dir = config.target_root()?.join(".ssh");
+            let base_dir = config_path.parent().unwrap().to_path_buf();
+            match subcommand {
+                SshCommands::Track { profile, no_keys } => {
+                    let profile_name = existing_profile(&config, profile)?;
+                    let live_config = ssh_dir.join("config");
+                    let keys = if no_keys {
+                        Vec::new()
+                    } else {
+                        crate::ssh::private_keys(&ssh_dir)?
+                    };
+                    if !live_config.is_file() && keys.is_empty() {
+                        return Err(anyhow::anyhow!(
+                            "Nothing to track: {} has no config file or private keys.",
+                            ssh_dir.display()
+                        ));
+                    }
+                    if !keys.is_empty() && config.secrets.age_key_file.is_none() {
+                        return Err(anyhow::anyhow!(
+                            "Private keys are stored as encrypted secrets, which aren't set up yet. Run 'ordinator age setup' first, or pass --no-keys."
+                        ));
+                    }
+                    if args.dry_run {
+                        if live_config.is_file() {
+                            println!(
+                                "DRY-RUN: Would track {} as '{}' in profile '{profile_name}'",
+                                live_config.display(),
+                                crate::ssh::CONFIG_TEMPLATE
+                            );
+                        }
+                        for key in &keys {
+                            println!(
+                                "DRY-RUN: Would store {} as an encrypted secret",
+                                key.display()
+                            );
+                        }
+                        return Ok(());
+                    }
+
+                    if live_config.is_file() {
+                        let template = crate::ssh::CONFIG_TEMPLATE;
+                        let tracked = config
+                            .get_profile(&profile_name)
+                            .is_some_and(|p| p.files.iter().any(|f| f == template));
+                        if tracked {
+                            eprintln!(
+                                "'{template}' is already tracked in profile '{profile_name}'; edit the template instead."
+                            );
+                        } else {
+                            let stored = config.get_profile_file_path(&profile_name, template)?;
+                            if let Some(parent) = stored.parent() {
+                                std::fs::create_dir_all(parent)?;
+                            }
+                            std::fs::copy(&live_config, &stored)?;
+                            config.add_file_to_profile(&profile_name, template.to_string())?;
+                            set_tracked_mode(&mut config, &profile_name, template, "600");
+                            crate::watch::record_sync_baseline(
+                                &config_path,
+                                &profile_name,
+                                template,
+                                &live_config,
+                                false,
+                            )?;
+                            println!(
+                                "✅ Tracking {} as template '{template}' in profile '{profile_name}'",
+                                live_config.display()
+                            );
+                        }
+                    }
+                    for key in &keys {
+                        let tracked =
+                            format!(".ssh/{}", key.file_name().unwrap().to_string_lossy());
+                        let stored = crate::secrets::store_secret(
+                            &mut config,
+                            &base_dir,
+                            &profile_name,
+                            &tracked,
+                            key,
+                        )?;
+                        set_tracked_mode(&mut config, &profile_name, &tracked, "600");
+                        println!(
+                            "✅ Stored private key '{tracked}' as encrypted secret '{stored}'"
+                        );
+                    }
+                    config.save_to_file(&config_path)?;
+                    for path in crate::ssh::secure(&ssh_dir)? {
+                        println!("Restricted permissions of {}", path.display());
+                    }
+                    Ok(())
+                }
+                SshCommands::Keygen {
+                    profile,
+                    key_type,
+                    comment,
+                    name,
+                    if_missing,
+                    force,
+                } => {
+                    let profile_name = existing_profile(&config, profile)?;
+                    let name = name
+                        .unwrap_or_else(|| crate::ssh::default_key_name(&profile_name, &key_type));
+                    let key_path = ssh_dir.join(&name);
+                    if key_path.exists() {
+                        if if_missing {
+                            eprintln!("SSH key {} already exists", key_path.display());
+                            return Ok(());
+                        }
+                        if !force {
+                            return Err(anyhow::anyhow!(
+                                "SSH key {} already exists. Use --force to replace it, or --if-missing to keep it.",
+                                key_path.display()
+                            ));
+                        }
+                    }
+                    if args.dry_run {
+                        println!(
+                            "DRY-RUN: Would generate a {key_type} SSH key at {} for profile '{profile_name}'",
+                            key_path.display()
+                        );
+                        return Ok(());
+                    }
+                    if key_path.exists() {
+                        std::fs::remove_file(&key_path)?;
+                        let mut public_path = key_path.as_os_str().to_owned();
+                        public_path.push(".pub");
+                        let _ = std::fs::remove_file(public_path);
+                    }
+                    crate::ssh::keygen(&key_path, &key_type, comment.as_deref())?;
+                    crate::ssh::secure(&ssh_dir)?;
+                    eprintln!("✅ Generated SSH key {}", key_path.display());
+
+                    let tracked = format!(".ssh/{name}");
+                    if config.secrets.age_key_file.is_some() {
+                        crate::secrets::store_secret(
+                            &mut config,
+                            &base_dir,
+                            &profile_name,
+                            &tracked,
+                            &key_path,
+                        )?;
+                        set_tracked_mode(&mut config, &profile_name, &tracked, "600");
+                        config.save_to_file(&config_path)?;
+                        eprintln!(
+                            "✅ Stored '{tracked}' as an encrypted secret in profile '{profile_name}'"
+                        );
+                    } else {
+                        eprintln!(
+                            "⚠️  Warning: Secrets aren't set up, so the key was not stored. Run 'ordinator age setup', then 'ordinator ssh track --profile {profile_name}'."
+                        );
+                    }
+                    println!("{}", crate::ssh::public_key(&key_path)?);
+                    eprintln!("Add it to GitHub: {}", crate::ssh::GITHUB_NEW_KEY_URL);
+                    Ok(())
+                }
+                SshCommands::Pubkey { profile, name } => {
+                    let profile_name = existing_profile(&config, profile)?;
+                    let name = name
+                        .unwrap_or_else(|| crate::ssh::default_key_name(&profile_name, "ed25519"));
+                    println!("{}", crate::ssh::public_key(&ssh_dir.join(name))?);
+                    eprintln!("Add it to GitHub: {}", crate::ssh::GITHUB_NEW_KEY_URL);
+                    Ok(())
+                }
+            }
+        }
+        Commands::Gpg { subcommand } => {
+            let (mut config, config_path) = Config::load()?;
+            let target_root = config.target_root()?;
+            let gnupg_home = crate::gnupg::home_dir(&target_root);
+            let base_dir = config_path.parent().unwrap().to_path_buf();
+            match subcommand {
+                GpgCommands::Track {
+                    profile,
+                    keys,
+                    signing_key,
+                    no_conf,
+                } => {
+                    let profile_name = existing_profile(&config, profile)?;
+                    let conf_files: Vec<&str> = crate::gnupg::CONF_FILES
+                        .into_iter()
+                        .filter(|file| !no_conf && target_root.join(file).is_file())
+                        .collect();
+                    if conf_files.is_empty() && keys.is_empty() && signing_key.is_none() {
+                        return Err(anyhow::anyhow!(
+                            "Nothing to track: {} has no gpg.conf or gpg-agent.conf. Pass --key to store a private key.",
+                            gnupg_home.display()
+                        ));
+                    }
+                    if !keys.is_empty() && config.secrets.age_key_file.is_none() {
+                        return Err(anyhow::anyhow!(
+                            "Private keys are stored as encrypted secrets, which aren't set up yet. Run 'ordinator age setup' first."
+                        ));
+                    }
+                    let fingerprints = keys
+                        .iter()
+                        .map(|key| crate::gnupg::fingerprint(&gnupg_home, key))
+                        .collect::<Result<Vec<_>>>()?;
+                    // A signing key can live on a smartcard, outside the keyring
+                    let signing_key = signing_key
+                        .map(|key| crate::gnupg::fingerprint(&gnupg_home, &key).unwrap_or(key));
+                    if args.dry_run {
+                        for file in &conf_files {
+                            println!("DRY-RUN: Would track '{file}' in profile '{profile_name}'");
+                        }
+                        for fingerprint in &fingerprints {
+                            println!(
+                                "DRY-RUN: Would store GPG key {fingerprint} as an encrypted secret"
+                            );
+                        }
+                        if let Some(key) = &signing_key {
+                            println!("DRY-RUN: Would sign git commits with {key}");
+                        }
+                        return Ok(());
+                    }
+
+                    for file in conf_files {
+                        let tracked = config
+                            .get_profile(&profile_name)
+                            .is_some_and(|p| p.files.iter().any(|f| f == file));
+                        if tracked {
+                            eprintln!("'{file}' is already tracked in profile '{profile_name}'");
+                            continue;
+                        }
+                        let stored = config.get_profile_file_path(&profile_name, file)?;
+                        if let Some(parent) = stored.parent() {
+                            std::fs::create_dir_all(parent)?;
+                        }
+                        let live = target_root.join(file);
+                        std::fs::copy(&live, &stored)?;
+                        config.add_file_to_profile(&profile_name, file.to_string())?;
+                        set_tracked_mode(&mut config, &profile_name, file, "600");
+                        crate::watch::record_sync_baseline(
+                            &config_path,
+                            &profile_name,
+                            file,
+                            &live,
+                            false,
+                        )?;
+                        println!("✅ Tracking '{file}' in profile '{profile_name}'");
+                    }
+                    for fingerprint in fingerprints {
+                        let armored = crate::gnupg::export_secret_key(&gnupg_home, &fingerprint)?;
+                        let temp = crate::secrets::private_temp_dir()?;
+                        let plaintext = temp.path().join(format!("{fingerprint}.asc"));
+                        std::fs::write(&plaintext, armored)?;
+                        let stored =
+                            crate::gnupg::stored_key_path(&base_dir, &profile_name, &fingerprint);
+                        if let Some(parent) = stored.parent() {
+                            std::fs::create_dir_all(parent)?;
+                        }
+                        let encrypted = crate::secrets::encrypt_tracked_file(&plaintext, &stored);
+                        let _ = std::fs::remove_file(&plaintext);
+                        encrypted?;
+                        let gpg = &mut config
+                            .get_profile_mut(&profile_name)
+                            .ok_or_else(|| OrdinatorError::ProfileNotFound(profile_name.clone()))?
+                            .gpg;
+                        if !gpg.keys.contains(&fingerprint) {
+                            gpg.keys.push(fingerprint.clone());
+                        }
+                        println!(
+                            "✅ Stored GPG key {fingerprint} as encrypted secret '{}'",
+                            stored.strip_prefix(&base_dir).unwrap_or(&stored).display()
+                        );
+                    }
+                    if let Some(key) = signing_key {
+                        if let Some(profile) = config.get_profile_mut(&profile_name) {
+                            profile.gpg.signing_key = Some(key.clone());
+                        }
+                        println!("✅ Git will sign commits and tags with {key} once '{profile_name}' is applied");
+                    }
+                    config.save_to_file(&config_path)?;
+                    Ok(())
+                }
+            }
+        }
+        Commands::Auth { subcommand } => match subcommand {
+            AuthCommands::Test { url } => {
+                let url = match url {
+                    Some(url) => url,
+                    None => {
+                        let (_, config_path) = Config::load()?;
+                        GitManager::new(config_path.parent().unwrap().to_path_buf())
+                            .get_origin_url()?
+                            .ok_or_else(|| {
+                                anyhow::anyhow!(
+                                    "No URL given and the dotfiles repository has no 'origin' remote."
+                                )
+                            })?
+                    }
+                };
+                // Outside a dotfiles repository, only github.com gets the token
+                let token_hosts = Config::load()
+                    .map(|(config, _)| config.git.token_hosts)
+                    .unwrap_or_default();
+                let methods = crate::auth::AuthMethods::detect(&crate::utils::get_home_dir()?)
+                    .with_token_hosts(&token_hosts);
+                if !args.quiet {
+                    eprintln!(
+                        "SSH agent: {}",
+                        if methods.ssh_agent {
+                            "running"
+                        } else {
+                            "not running (SSH_AUTH_SOCK is unset)"
+                        }
+                    );
+                    let keys: Vec<_> = methods
+                        .ssh_keys
+                        .iter()
+                        .filter_map(|key| key.file_name())
+                        .map(|name| format!("~/.ssh/{}", name.to_string_lossy()))
+                        .collect();
+                    eprintln!(
+                        "SSH keys: {}",
+                        if keys.is_empty() {
+                            "none in ~/.ssh".to_string()
+                        } else {
+                            keys.join(", ")
+                        }
+                    );
+                    let token = match methods.token_var {
+                        Some(var)
+                            if crate::auth::is_http_url(&url) && !methods.sends_token_to(&url) =>
+                        {
+                            format!("{var}, not sent to this host (only to github.com or [git] token_hosts, over https://)")
+                        }
+                        Some(var) => var.to_string(),
+                        None => format!("none (checked {})", crate::auth::TOKEN_VARS.join(", ")),
+                    };
+                    eprintln!("HTTPS token: {token}");
+                }
+                crate::auth::test_connection(&url, &methods)?;
+                eprintln!("✅ Connected to {url}");
+                Ok(())
+            }
+        },
+
+        Commands::Launchd { subcommand } => {
+            let (mut config, config_path) = Config::load()?;
+            let base_dir = config_path.parent().unwrap().to_path_buf();
+            let agents_dir = crate::launchd::agents_dir(&config.target_root()?);
+            match subcommand {
+                LaunchdCommands::Track { plist, profile } => {
+                    let profile_name = existing_profile(&config, profile)?;
+                    let label = crate::launchd::label(&plist)?;
+                    if args.dry_run {
+                        println!(
+                            "DRY-RUN: Would track launchd agent '{label}' ({}) in profile '{profile_name}'",
+                            plist.display()
+                        );
+                        return Ok(());
+                    }
+                    let name = crate::launchd::track(&base_dir, &profile_name, &plist)?;
+                    let agents = &mut config.get_profile_mut(&profile_name).unwrap().launch_agents;
+                    if !agents.contains(&name) {
+                        agents.push(name.clone());
+                    }
+                    config.save_to_file(&config_path)?;
+                    println!("✅ Tracking launchd agent '{label}' as '{name}' in profile '{profile_name}'");
+                    Ok(())
+                }
+                LaunchdCommands::Untrack { name, profile } => {
+                    let profile_name = existing_profile(&config, profile)?;
+                    let agents = &mut config.get_profile_mut(&profile_name).unwrap().launch_agents;
+                    if !agents.contains(&name) {
+                        return Err(anyhow::anyhow!(
+                            "launchd agent '{name}' is not tracked in profile '{profile_name}'."
+                        ));
+                    }
+                    if args.dry_run {
+                        println!(
+                            "DRY-RUN: Would untrack launchd agent '{name}' from profile '{profile_name}'"
+                        );
+                        return Ok(());
+                    }
+                    agents.retain(|agent| agent != &name);
+                    config.save_to_file(&config_path)?;
+                    let stored = crate::launchd::stored_path(&base_dir, &profile_name, &name);
+                    if stored.exists() {
+                        std::fs::remove_file(&stored)?;
+                    }
+                    if crate::launchd::LaunchdManager::new(false).remove(&name, &agents_dir)? {
+                        println!("Removed {}", agents_dir.join(&name).display());
+                    }
+                    println!("✅ Untracked launchd agent '{name}' from profile '{profile_name}'");
+                    Ok(())
                 }
-                let target_profiles: Vec<String> = if let Some(p) = profile {
-                    vec![p]
-                } else {
-                    let (config, _) = Config::load()?;
-                    config
-                        .list_profiles()
-                        .into_iter()
-                        .map(|s| s.to_string())
-                        .collect()
-                };
-                for prof in target_profiles {
-                    crate::secrets::rotate_age_keys(&prof, backup_old_key, force)?;
+                LaunchdCommands::List { profile } => {
+                    let mut profiles: Vec<&String> = match &profile {
+                        Some(name) => vec![
+                            config
+                                .profiles
+                                .get_key_value(name)
+                                .ok_or_else(|| OrdinatorError::ProfileNotFound(name.to_string()))?
+                                .0,
+                        ],
+                        None => config.list_profiles(),
+                    };
+                    profiles.sort();
+                    let mut any = false;
+                    for profile_name in profiles {
+                        let agents = &config.profiles[profile_name].launch_agents;
+                        if agents.is_empty() {
+                            continue;
+                        }
+                        any = true;
+                        println!("{profile_name}:");
+                        for name in agents {
+                            let state = if agents_dir.join(name).exists() {
+                                "installed"
+                            } else {
+                                "not installed"
+                            };
+                            println!("  {name} ({state})");
+                        }
+                    }
+                    if !any {
+                        eprintln!("No launchd agents tracked.");
+                    }
+                    Ok(())
                 }
-                Ok(())
             }
-        },
+        }
         Commands::Readme { subcommand } => {
             match subcommand {
                 ReadmeCommands::Default => {
@@ -3371,5 +8162,505 @@ pub async fn run(args: Args) -> Result<()> {
             println!("Generated replicate.sh at {}", replicate_path.display());
             Ok(())
         }
+        Commands::Export {
+            output,
+            recipients,
+            include_keys,
+            force,
+        } => {
+            let (_config, config_path) = Config::load()?;
+            let dotfiles_dir = config_path.parent().unwrap();
+            let output = PathBuf::from(output);
+            if output.exists() && !force {
+                return Err(anyhow::anyhow!(
+                    "{} already exists. Use --force to overwrite it.",
+                    output.display()
+                ));
+            }
+            let keys_dir = include_keys.then(crate::bundle::keys_dir);
+            if args.dry_run {
+                eprintln!(
+                    "DRY-RUN: Would export {}{} to {}",
+                    dotfiles_dir.display(),
+                    if include_keys { " and age keys" } else { "" },
+                    output.display()
+                );
+                return Ok(());
+            }
+            let summary =
+                crate::bundle::export(dotfiles_dir, keys_dir.as_deref(), &output, &recipients)?;
+            if include_keys && summary.keys == 0 {
+                eprintln!("Warning: No age keys found to include");
+            }
+            if !args.quiet {
+                println!(
+                    "✅ Exported {} file(s) and {} age key(s) to {}",
+                    summary.files,
+                    summary.keys,
+                    output.display()
+                );
+            }
+            Ok(())
+        }
+        Commands::Import {
+            bundle,
+            target_dir,
+            identity,
+            force,
+        } => {
+            let target = match target_dir {
+                Some(dir) => PathBuf::from(dir),
+                None => crate::config::get_dotfiles_dir()?,
+            };
+            if args.dry_run {
+                eprintln!("DRY-RUN: Would import {bundle} into {}", target.display());
+                return Ok(());
+            }
+            let keys_dir = crate::bundle::keys_dir();
+            let summary = crate::bundle::import(
+                std::path::Path::new(&bundle),
+                &target,
+                &keys_dir,
+                identity.as_deref().map(std::path::Path::new),
+                force,
+            )?;
+            for key in &summary.skipped_keys {
+                eprintln!(
+                    "Warning: Kept existing age key {}. Use --force to replace it.",
+                    key.display()
+                );
+            }
+            if !args.quiet {
+                println!(
+                    "✅ Imported {} file(s) and {} age key(s) into {}",
+                    summary.files,
+                    summary.keys.len(),
+                    target.display()
+                );
+                eprintln!("Next steps:");
+                eprintln!("  1. cd {}", target.display());
+                eprintln!("  2. Apply the dotfiles: ordinator apply");
+            }
+            Ok(())
+        }
+        Commands::Daemon { subcommand } => {
+            use crate::daemon::DaemonManager;
+
+            let manager = DaemonManager::new(args.dry_run);
+            match subcommand {
+                DaemonCommands::Install {
+                    interval,
+                    watch_files,
+                    apply_profile,
+                } => {
+                    let (mut config, config_path) = Config::load()?;
+                    if let Some(profile) = &apply_profile {
+                        if !config.profiles.contains_key(profile) {
+                            return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+                        }
+                    }
+                    if interval == Some(0) {
+                        return Err(anyhow::anyhow!("Interval must be at least 1 minute."));
+                    }
+
+                    if args.dry_run {
+                        eprintln!(
+                            "DRY-RUN: Would install launch agent at {}",
+                            DaemonManager::plist_path()?.display()
+                        );
+                        return Ok(());
+                    }
+
+                    if let Some(minutes) = interval {
+                        config.daemon.interval_minutes = minutes;
+                    }
+                    if watch_files {
+                        config.daemon.watch_files = true;
+                    }
+                    if apply_profile.is_some() {
+                        config.daemon.apply_profile = apply_profile;
+                    }
+                    config.save_to_file(&config_path)?;
+
+                    let plist_path = manager.install(&config, &config_path)?;
+                    eprintln!("Installed launch agent: {}", plist_path.display());
+                    eprintln!(
+                        "Syncing every {} minute(s){}",
+                        config.daemon.interval_minutes,
+                        if config.daemon.watch_files {
+                            " and whenever a tracked file changes"
+                        } else {
+                            ""
+                        }
+                    );
+                    eprintln!("Logs: {}", DaemonManager::log_path()?.display());
+                    Ok(())
+                }
+                DaemonCommands::Uninstall => {
+                    if args.dry_run {
+                        eprintln!(
+                            "DRY-RUN: Would remove launch agent at {}",
+                            DaemonManager::plist_path()?.display()
+                        );
+                        return Ok(());
+                    }
+                    if manager.uninstall()? {
+                        eprintln!("Launch agent removed.");
+                    } else {
+                        eprintln!("Launch agent is not installed.");
+                    }
+                    Ok(())
+                }
+                DaemonCommands::Status => {
+                    let (config, _) = Config::load()?;
+                    let plist_path = DaemonManager::plist_path()?;
+                    if DaemonManager::is_installed()? {
+                        eprintln!("Launch agent: installed ({})", plist_path.display());
+                        eprintln!(
+                            "Loaded: {}",
+                            if DaemonManager::is_loaded() {
+                                "yes"
+                            } else {
+                                "no"
+                            }
+                        );
+                    } else {
+                        eprintln!("Launch agent: not installed");
+                    }
+                    eprintln!("Interval: {} minute(s)", config.daemon.interval_minutes);
+                    eprintln!(
+                        "Watch tracked files: {}",
+                        if config.daemon.watch_files {
+                            "yes"
+                        } else {
+                            "no"
+                        }
+                    );
+                    eprintln!(
+                        "Commit message template: {}",
+                        config.daemon.commit_message_template
+                    );
+                    if let Some(profile) = &config.daemon.apply_profile {
+                        eprintln!("Apply profile after pull: {profile}");
+                    }
+                    Ok(())
+                }
+                DaemonCommands::Run => {
+                    let (config, config_path) = Config::load()?;
+                    let dotfiles_path = config_path.parent().unwrap().to_path_buf();
+                    eprintln!(
+                        "[{}] Starting sync",
+                        chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+                    );
+                    let summary = manager.run_once(&config, &dotfiles_path).await?;
+                    let prefix = if args.dry_run {
+                        "DRY-RUN: Would have "
+                    } else {
+                        ""
+                    };
+                    match &summary.committed {
+                        Some(message) => eprintln!("{prefix}Committed local changes: {message}"),
+                        None if summary.held_back.is_empty() => {
+                            eprintln!("No local changes to commit")
+                        }
+                        None => {
+                            eprintln!("⚠️  Warning: Not committing or pushing local changes: plaintext secrets detected, or the scan timed out, in:");
+                            for file in &summary.held_back {
+                                eprintln!("   {file}");
+                            }
+                            eprintln!("   Review them with 'ordinator secrets scan', then run 'ordinator commit'");
+                        }
+                    }
+                    if summary.pulled {
+                        eprintln!("Pulled from remote");
+                    }
+                    if summary.pushed {
+                        eprintln!("Pushed to remote");
+                    }
+                    if let Some(profile) = &summary.applied {
+                        eprintln!("Applied profile '{profile}'");
+                    }
+                    eprintln!("Sync complete");
+                    Ok(())
+                }
+            }
+        }
+        Commands::Defaults { subcommand } => {
+            use crate::defaults::DefaultsManager;
+            let manager = DefaultsManager::new(args.dry_run);
+
+            match subcommand {
+                DefaultsCommands::Export { domains, profile } => {
+                    let (mut config, config_path) = Config::load()?;
+                    if !config.profiles.contains_key(&profile) {
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+                    }
+
+                    let mut captured = Vec::new();
+                    for domain in &domains {
+                        let (settings, skipped) = manager.read_domain(domain)?;
+                        if !args.quiet {
+                            eprintln!(
+                                "{}Captured {} setting(s) from {domain}",
+                                if args.dry_run {
+                                    "DRY-RUN: Would have "
+                                } else {
+                                    ""
+                                },
+                                settings.len()
+                            );
+                            if !skipped.is_empty() {
+                                eprintln!(
+                                    "   Skipped {} key(s) with array, dictionary, date, or data values",
+                                    skipped.len()
+                                );
+                                if args.verbose {
+                                    eprintln!("   {}", skipped.join(", "));
+                                }
+                            }
+                        }
+                        captured.push((domain.clone(), settings));
+                    }
+
+                    if args.dry_run {
+                        return Ok(());
+                    }
+
+                    let profile_config = config.get_profile_mut(&profile).unwrap();
+                    for (domain, settings) in captured {
+                        profile_config.defaults.insert(domain, settings);
+                    }
+                    config.save_to_file(&config_path)?;
+                    if !args.quiet {
+                        eprintln!("✅ Saved macOS defaults to profile '{profile}'");
+                    }
+                    Ok(())
+                }
+                DefaultsCommands::Apply { profile } => {
+                    let (config, _) = Config::load()?;
+                    if !config.profiles.contains_key(&profile) {
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+                    }
+                    let resolved = config.resolve_profile(&profile)?;
+                    if resolved.defaults.is_empty() {
+                        eprintln!("No macOS defaults configured for profile '{profile}'.");
+                        return Ok(());
+                    }
+
+                    let changes = manager.apply(&resolved.defaults)?;
+                    if !args.quiet {
+                        if changes.is_empty() {
+                            eprintln!(
+                                "macOS defaults for profile '{profile}' are already up to date."
+                            );
+                        } else if args.dry_run {
+                            eprintln!("DRY-RUN: Would apply {} macOS default(s)", changes.len());
+                        } else {
+                            eprintln!("✅ Applied {} macOS default(s)", changes.len());
+                        }
+                    }
+                    Ok(())
+                }
+                DefaultsCommands::Diff { profile } => {
+                    let (config, _) = Config::load()?;
+                    if !config.profiles.contains_key(&profile) {
+                        return Err(OrdinatorError::ProfileNotFound(profile.to_string()).into());
+                    }
+                    let resolved = config.resolve_profile(&profile)?;
+                    let changes = manager.diff(&resolved.defaults)?;
+                    if changes.is_empty() {
+                        eprintln!("No differences for profile '{profile}'.");
+                        return Ok(());
+                    }
+                    for change in &changes {
+                        let current = change
+                            .current
+                            .as_ref()
+                            .map(|value| value.to_string())
+                            .unwrap_or_else(|| "(unset)".to_string());
+                        if color_enabled() {
+                            println!(
+                                "{} {}: {} -> {}",
+                                change.domain,
+                                change.key.bold(),
+                                current.red(),
+                                change.desired.to_string().green()
+                            );
+                        } else {
+                            println!(
+                                "{} {}: {} -> {}",
+                                change.domain, change.key, current, change.desired
+                            );
+                        }
+                    }
+                    Ok(())
+                }
+            }
+        }
+        Commands::Config { subcommand } => match subcommand {
+            ConfigCommands::Get { key } => {
+                let (config, _) = Config::load()?;
+                match config.get_value(&key)? {
+                    Some(toml::Value::String(value)) => println!("{value}"),
+                    Some(toml::Value::Table(table)) => {
+                        print!("{}", toml::to_string_pretty(&table)?)
+                    }
+                    Some(value) => println!("{value}"),
+                    None => return Err(anyhow::anyhow!("'{key}' is not set")),
+                }
+                Ok(())
+            }
+            ConfigCommands::Set {
+                key,
+                value,
+                append,
+                remove,
+            } => {
+                use crate::config::SetMode;
+                let (mut config, config_path) = Config::load()?;
+                let mode = if append {
+                    SetMode::Append
+                } else if remove {
+                    SetMode::Remove
+                } else {
+                    SetMode::Replace
+                };
+                config.set_value(&key, &value, mode)?;
+
+                let (would, done) = match mode {
+                    SetMode::Replace => ("set", "Set"),
+                    SetMode::Append => ("add", "Added"),
+                    SetMode::Remove => ("remove", "Removed"),
+                };
+                let change = match mode {
+                    SetMode::Replace => format!("{key} = {value}"),
+                    SetMode::Append => format!("{value} to {key}"),
+                    SetMode::Remove => format!("{value} from {key}"),
+                };
+                if args.dry_run {
+                    eprintln!("DRY-RUN: Would {would} {change}");
+                    return Ok(());
+                }
+                config.save_to_file(&config_path)?;
+                if !args.quiet {
+                    eprintln!("✅ {done} {change}");
+                }
+                Ok(())
+            }
+            ConfigCommands::Edit => {
+                let config_path =
+                    Config::find_config_file()?.ok_or(OrdinatorError::ConfigNotFound)?;
+                let original = std::fs::read_to_string(&config_path).map_err(|e| {
+                    anyhow::anyhow!(
+                        "Failed to read config file {}: {}",
+                        config_path.display(),
+                        e
+                    )
+                })?;
+                if args.dry_run {
+                    eprintln!(
+                        "DRY-RUN: Would open {} in $EDITOR and validate it",
+                        config_path.display()
+                    );
+                    return Ok(());
+                }
+
+                let editor = std::env::var("EDITOR").unwrap_or_else(|_| "nano".to_string());
+                loop {
+                    let status = std::process::Command::new(&editor)
+                        .arg(&config_path)
+                        .status()
+                        .map_err(|e| anyhow::anyhow!("Failed to run editor '{editor}': {e}"))?;
+                    if !status.success() {
+                        std::fs::write(&config_path, &original)?;
+                        return Err(anyhow::anyhow!(
+                            "Editor exited with status: {status}. The config was not changed."
+                        ));
+                    }
+
+                    let content = std::fs::read_to_string(&config_path)?;
+                    let validation = Config::validate_str(&content);
+                    for warning in &validation.warnings {
+                        eprintln!("warning: {}: {warning}", config_path.display());
+                    }
+                    if validation.errors.is_empty() {
+                        if !args.quiet {
+                            if content == original {
+                                eprintln!("No changes made to {}", config_path.display());
+                            } else {
+                                eprintln!("✅ Saved {}", config_path.display());
+                            }
+                        }
+                        return Ok(());
+                    }
+
+                    for error in &validation.errors {
+                        eprintln!("error: {}: {error}", config_path.display());
+                    }
+                    // `--yes` can't fix the file, so it doesn't reopen the editor
+                    let again = crate::prompt::interactive()
+                        && !crate::prompt::assume_yes()
+                        && crate::prompt::confirm(
+                            "Edit again? Otherwise your changes are discarded.",
+                            true,
+                        );
+                    if !again {
+                        std::fs::write(&config_path, &original)?;
+                        return Err(anyhow::anyhow!(
+                            "Discarded invalid changes to {}",
+                            config_path.display()
+                        ));
+                    }
+                }
+            }
+            ConfigCommands::Validate { path } => {
+                let path = match path {
+                    Some(path) => PathBuf::from(path),
+                    None => Config::find_config_file()?.ok_or(OrdinatorError::ConfigNotFound)?,
+                };
+                let content = std::fs::read_to_string(&path).map_err(|e| {
+                    anyhow::anyhow!("Failed to read config file {}: {}", path.display(), e)
+                })?;
+                let validation = Config::validate_str(&content);
+
+                let label = |kind: &str| {
+                    if color_enabled() {
+                        match kind {
+                            "error" => kind.red().bold().to_string(),
+                            _ => kind.yellow().bold().to_string(),
+                        }
+                    } else {
+                        kind.to_string()
+                    }
+                };
+                for (kind, issues) in [
+                    ("error", &validation.errors),
+                    ("warning", &validation.warnings),
+                ] {
+                    for issue in issues {
+                        eprintln!("{}: {}: {issue}", label(kind), path.display());
+                    }
+                }
+
+                if !validation.errors.is_empty() {
+                    return Err(anyhow::anyhow!(
+                        "{} is not a valid ordinator config",
+                        path.display()
+                    ));
+                }
+                if !args.quiet {
+                    if validation.warnings.is_empty() {
+                        eprintln!("✅ {} is valid", path.display());
+                    } else {
+                        eprintln!(
+                            "✅ {} is valid ({} warning(s))",
+                            path.display(),
+                            validation.warnings.len()
+                        );
+                    }
+                }
+                Ok(())
+            }
+        },
     }
 }
@@ -0,0 +1,54 @@
+//! Committing everything pending in the dotfiles repository, for
+//! `[global] auto_commit` and the sync daemon. The default profile's
+//! `pre_commit` hooks run first, since they may reformat or regenerate files,
+//! then the secrets scan, which can hold the commit back.
+
+use anyhow::Result;
+use std::path::Path;
+use tracing::info;
+
+use crate::config::Config;
+use crate::git::GitManager;
+use crate::hooks::{self, HookEvent};
+use crate::scan::ScanReport;
+
+/// What [`commit_all`] did
+#[derive(Debug)]
+pub enum CommitOutcome {
+    /// Nothing was pending
+    Clean,
+
+    /// Plaintext secrets, or files that couldn't be scanned in time, kept the
+    /// changes from being committed
+    HeldBack(ScanReport),
+
+    Committed,
+}
+
+/// Run the `pre_commit` hooks, scan the pending changes for secrets, and
+/// commit them all with `message` unless the scan objects. With `dry_run` the
+/// hooks and the commit are only described.
+pub async fn commit_all(
+    config: &Config,
+    git_manager: &GitManager,
+    dotfiles_dir: &Path,
+    message: &str,
+    dry_run: bool,
+) -> Result<CommitOutcome> {
+    if !git_manager.has_changes()? {
+        return Ok(CommitOutcome::Clean);
+    }
+    hooks::run_for_default_profile(config, dotfiles_dir, HookEvent::PreCommit, dry_run)?;
+
+    let pending = git_manager.pending_changes(&[], true)?;
+    let scan = crate::scan::unaccepted_staged_findings(config, dotfiles_dir, &pending).await?;
+    if scan.blocks_commit() {
+        return Ok(CommitOutcome::HeldBack(scan));
+    }
+    if dry_run {
+        info!("[DRY RUN] Would commit local changes: {}", message);
+    } else {
+        git_manager.commit(message)?;
+    }
+    Ok(CommitOutcome::Committed)
+}
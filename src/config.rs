@@ -1,13 +1,28 @@
 use anyhow::{Context, Result};
 use globset::{Glob, GlobSet, GlobSetBuilder};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::path::{Path, PathBuf};
 
+use crate::age_key::KeyStorage;
+use crate::backups::BackupRetention;
+use crate::brew::{BrewConfig, MasApp};
+use crate::daemon::DaemonConfig;
+use crate::defaults::DomainSettings;
+use crate::directory::TrackedDirectory;
+use crate::error::OrdinatorError;
+use crate::git::GitConfig;
+use crate::gnupg::GpgConfig;
+use crate::hooks::HooksConfig;
+use crate::large_files::LargeFilesConfig;
+use crate::machine::{MachineRole, ProfileMatch};
 use crate::readme::ReadmeConfig;
+use crate::scan::ScanConfig;
+use crate::shell::{ShellFiles, Shells, SkippedShell};
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     /// Global configuration
     #[serde(default)]
@@ -25,42 +40,102 @@ pub struct Config {
     #[serde(default)]
     pub readme: ReadmeConfig,
 
+    /// Background sync agent configuration
+    #[serde(default)]
+    pub daemon: DaemonConfig,
+
+    /// Commit author and signing configuration
+    #[serde(default)]
+    pub git: GitConfig,
+
+    /// Hook scripts run for every profile
+    #[serde(default, skip_serializing_if = "HooksConfig::is_empty")]
+    pub hooks: HooksConfig,
+
+    /// Homebrew settings
+    #[serde(default, skip_serializing_if = "BrewConfig::is_empty")]
+    pub brew: BrewConfig,
+
+    /// Machine roles by name, for `apply --machine`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub machines: BTreeMap<String, MachineRole>,
+
     /// Unique identifier for this configuration (used for debugging)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub identifier: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct GlobalConfig {
     /// Default profile to use
     #[serde(default = "default_profile")]
     pub default_profile: String,
 
-    /// Whether to auto-push after successful operations
+    /// Whether to commit after `add`, `watch`, `unwatch`, and `brew export`
     #[serde(default)]
-    pub auto_push: bool,
+    pub auto_commit: bool,
 
-    /// Whether to create backups before making changes
+    /// Whether to push the commits `auto_commit` makes
     #[serde(default)]
+    pub auto_push: bool,
+
+    /// Whether to create backups before making changes (`backup_existing` is deprecated)
+    #[serde(default, alias = "backup_existing")]
     pub create_backups: Option<bool>,
 
+    /// How many backups to keep; enforced after each apply
+    #[serde(default, skip_serializing_if = "BackupRetention::is_unlimited")]
+    pub backup_retention: BackupRetention,
+
+    /// Where backups go: absolute, under `~/`, or relative to the dotfiles
+    /// repository. Defaults to `backups` next to the machine state file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup_dir: Option<String>,
+
     /// Patterns for files/directories to exclude globally
     #[serde(default)]
     pub exclude: Vec<String>,
+
+    /// Directory tracked files are placed under instead of the home directory
+    /// (e.g. a mounted backup or another user's home)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_root: Option<String>,
+
+    /// Seconds `ordinator bootstrap run` lets a script run before stopping it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bootstrap_timeout_secs: Option<u64>,
+
+    /// Size limit for tracked files, and whether to warn or use Git LFS above it
+    #[serde(default, skip_serializing_if = "LargeFilesConfig::is_default")]
+    pub large_files: LargeFilesConfig,
+
+    /// Octal modes for directories apply creates, keyed by path under the
+    /// target root (e.g. `".gnupg" = "700"`); `.ssh` and `.gnupg` default to 700
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub directory_modes: BTreeMap<String, String>,
 }
 
 impl Default for GlobalConfig {
     fn default() -> Self {
         Self {
             default_profile: default_profile(),
+            auto_commit: false,
             auto_push: false,
             create_backups: Some(default_backup()),
+            backup_retention: BackupRetention::default(),
+            backup_dir: None,
             exclude: Vec::new(),
+            target_root: None,
+            bootstrap_timeout_secs: None,
+            large_files: LargeFilesConfig::default(),
+            directory_modes: BTreeMap::new(),
         }
     }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
 pub struct ProfileConfig {
     /// Files to track for this profile
     #[serde(default)]
@@ -68,7 +143,7 @@ pub struct ProfileConfig {
 
     /// Directories to track for this profile
     #[serde(default)]
-    pub directories: Vec<String>,
+    pub directories: Vec<TrackedDirectory>,
 
     /// Secret files to track for this profile (direct paths to source files)
     #[serde(default)]
@@ -81,21 +156,79 @@ pub struct ProfileConfig {
     #[serde(default = "default_enabled")]
     pub enabled: bool,
 
+    /// Set to false to never decrypt this profile's secrets on apply, e.g. for
+    /// a profile used on shared or loaner machines
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secrets_enabled: Option<bool>,
+
     /// Profile description
     pub description: Option<String>,
 
+    /// Profiles whose files, secrets, and Homebrew packages this profile inherits
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extends: Vec<String>,
+
     /// Patterns for files/directories to exclude in this profile
     #[serde(default)]
     pub exclude: Vec<String>,
 
-    /// Homebrew formulas for this profile
-    #[serde(default)]
+    /// Homebrew formulas for this profile (`homebrew_packages` is deprecated)
+    #[serde(default, alias = "homebrew_packages")]
     pub homebrew_formulas: Vec<String>,
 
     /// Homebrew casks for this profile
     #[serde(default)]
     pub homebrew_casks: Vec<String>,
 
+    /// Homebrew taps for this profile (`taps` is accepted too)
+    #[serde(default, alias = "taps", skip_serializing_if = "Vec::is_empty")]
+    pub homebrew_taps: Vec<String>,
+
+    /// Mac App Store apps for this profile (installed with `mas`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mas_apps: Vec<MasApp>,
+
+    /// Variables available to `.tmpl` files rendered during apply
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub vars: HashMap<String, String>,
+
+    /// macOS `defaults` settings by domain, written during apply
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub defaults: BTreeMap<String, DomainSettings>,
+
+    /// launchd agent plists stored under `launchd/<profile>/`, by file name,
+    /// installed to `~/Library/LaunchAgents` during apply
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub launch_agents: Vec<String>,
+
+    /// Editor extension ids by editor (`vscode`, `cursor`, `vscodium`), for `ordinator extensions`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extensions: BTreeMap<String, Vec<String>>,
+
+    /// Hook scripts run only for this profile, after the global `[hooks]`
+    #[serde(default, skip_serializing_if = "HooksConfig::is_empty")]
+    pub hooks: HooksConfig,
+
+    /// Tracked paths applied only for one shell, by shell name (`fish`, `zsh`, `bash`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub shell: BTreeMap<String, ShellFiles>,
+
+    /// GPG private keys imported on apply, and the key git signs with
+    #[serde(default, skip_serializing_if = "GpgConfig::is_empty")]
+    pub gpg: GpgConfig,
+
+    /// Machine rules that select this profile when apply is run without `--profile`
+    #[serde(
+        default,
+        rename = "match",
+        skip_serializing_if = "ProfileMatch::is_empty"
+    )]
+    pub match_rules: ProfileMatch,
+
+    /// Per-file settings, keyed by tracked path (e.g. `"/etc/hosts"`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub file_options: BTreeMap<String, FileOptions>,
+
     /// Date/time when the age key was created (ISO 8601 string)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_on: Option<String>,
@@ -103,18 +236,146 @@ pub struct ProfileConfig {
     /// Hash-based filename mappings: "hash_filename" = "original_path"
     #[serde(default)]
     pub file_mappings: HashMap<String, String>,
+
+    /// Permissions recorded when files were added: "original_path" = "octal mode"
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub file_modes: BTreeMap<String, String>,
+}
+
+/// Settings for one tracked file, under `[profiles.<name>.file_options."<path>"]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileOptions {
+    /// Link the file with `sudo`, for destinations outside the home directory
+    /// the current user can't write (e.g. `/etc/hosts`, `/Library/LaunchDaemons`)
+    #[serde(default)]
+    pub requires_sudo: bool,
+
+    /// Octal permissions (e.g. `"600"`) set on apply instead of the recorded mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<String>,
+
+    /// Store the file encrypted with SOPS/age in `files/`; apply decrypts it
+    /// to the destination instead of symlinking
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub encrypt: bool,
+
+    /// Keep the stored copy in a `# >>> ordinator >>>` block inside the
+    /// destination, leaving the rest of the file alone, instead of symlinking
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub managed_block: bool,
+}
+
+/// A tracked path together with the profile that defines it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedEntry {
+    /// Tracked path as written in the defining profile
+    pub path: String,
+
+    /// Profile whose `files`/`secrets` list contains this path
+    pub profile: String,
+}
+
+/// A tracked directory together with the profile that defines it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedDirectory {
+    /// Directory entry as written in the defining profile
+    pub directory: TrackedDirectory,
+
+    /// Profile whose `directories` list contains this entry
+    pub profile: String,
+}
+
+/// A path tracked by more than one of the profiles applied together, each
+/// with its own copy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProfileOverlap {
+    /// Tracked path, file, secret, or directory
+    pub path: String,
+
+    /// Profile whose copy is used: the one defining it for the last profile requested
+    pub winner: String,
+
+    /// Profiles whose copies are ignored, in the order requested
+    pub overridden: Vec<String>,
+}
+
+/// Merged view of a profile after applying its `extends` chain
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedProfile {
+    /// Tracked files, parents first; a child's entry replaces an inherited one
+    pub files: Vec<ResolvedEntry>,
+
+    /// Tracked secrets, parents first; a child's entry replaces an inherited one
+    pub secrets: Vec<ResolvedEntry>,
+
+    /// Tracked directories, parents first; a child's entry replaces an inherited one
+    pub directories: Vec<ResolvedDirectory>,
+
+    /// Homebrew formulas from the whole chain, deduplicated
+    pub homebrew_formulas: Vec<String>,
+
+    /// Homebrew casks from the whole chain, deduplicated
+    pub homebrew_casks: Vec<String>,
+
+    /// Homebrew taps from the whole chain, deduplicated
+    pub homebrew_taps: Vec<String>,
+
+    /// Mac App Store apps from the whole chain, deduplicated by id
+    pub mas_apps: Vec<MasApp>,
+
+    /// Template variables from the whole chain; a child's value overrides an inherited one
+    pub vars: HashMap<String, String>,
+
+    /// macOS defaults from the whole chain; a child's value overrides an inherited one per key
+    pub defaults: BTreeMap<String, DomainSettings>,
+
+    /// launchd agents, by plist file name; a child's entry replaces an inherited one
+    pub launch_agents: Vec<ResolvedEntry>,
+
+    /// Editor extensions from the whole chain by editor, deduplicated
+    pub extensions: BTreeMap<String, Vec<String>>,
+
+    /// Global hooks, then hooks from the whole chain, parents first
+    pub hooks: HooksConfig,
+
+    /// Per-shell paths from the whole chain by shell, deduplicated; a child's
+    /// `when` overrides an inherited one
+    pub shell: BTreeMap<String, ShellFiles>,
+
+    /// GPG keys by fingerprint, from the whole chain; a child's entry replaces an inherited one
+    pub gpg_keys: Vec<ResolvedEntry>,
+
+    /// Key git signs with; a child's overrides an inherited one
+    pub signing_key: Option<String>,
+
+    /// The profiles merged into this view, parents first
+    pub chain: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
 pub struct SecretsConfig {
+    /// What encrypts secrets: `sops` (the `sops` and `age` binaries) or `native` (built in)
+    #[serde(default, skip_serializing_if = "SecretsBackend::is_sops")]
+    pub backend: SecretsBackend,
+
     /// Age key file path
     pub age_key_file: Option<PathBuf>,
 
+    /// How the age key is kept on this machine: `file`, `passphrase`, or `keychain`
+    #[serde(default, skip_serializing_if = "KeyStorage::is_file")]
+    pub age_key_storage: KeyStorage,
+
     /// SOPS configuration file path
     pub sops_config: Option<PathBuf>,
 
-    /// Patterns for files that should be encrypted
-    #[serde(default)]
+    /// Extra age public keys (teammates, other machines) that secrets are encrypted for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<String>,
+
+    /// Patterns for files that should be encrypted (`encrypted_patterns` is deprecated)
+    #[serde(default, alias = "encrypted_patterns")]
     pub encrypt_patterns: Vec<String>,
 
     /// Patterns for files that should not be encrypted
@@ -124,19 +385,477 @@ pub struct SecretsConfig {
     /// Key rotation interval in days (e.g., 90 = 3 months)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub key_rotation_interval_days: Option<u32>,
+
+    /// Oldest the age key may get before `status` and `age validate` warn about it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_max_age_days: Option<u32>,
+
+    /// Custom rules, allowlist, and baseline for the plaintext secret scanner
+    #[serde(default, skip_serializing_if = "ScanConfig::is_empty")]
+    pub scan: ScanConfig,
+}
+
+/// What `[secrets] backend` encrypts with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretsBackend {
+    /// The `sops` CLI, with age keys
+    #[default]
+    Sops,
+
+    /// Built-in age encryption; files are plain age files, no binaries needed
+    Native,
+}
+
+impl SecretsBackend {
+    pub fn is_sops(&self) -> bool {
+        *self == SecretsBackend::Sops
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SecretsBackend::Sops => "sops",
+            SecretsBackend::Native => "native",
+        }
+    }
+}
+
+impl std::str::FromStr for SecretsBackend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "sops" => Ok(SecretsBackend::Sops),
+            "native" => Ok(SecretsBackend::Native),
+            other => Err(OrdinatorError::UnknownSecretsBackend(other.to_string()).into()),
+        }
+    }
+}
+
+/// Keys that still load but have been replaced: (table, old key, new key).
+/// `profiles.*` matches every profile.
+const DEPRECATED_KEYS: &[(&str, &str, &str)] = &[
+    ("global", "backup_existing", "create_backups"),
+    ("profiles.*", "homebrew_packages", "homebrew_formulas"),
+    ("secrets", "encrypted_patterns", "encrypt_patterns"),
+];
+
+/// A problem found in `ordinator.toml`, with its 1-based position when known
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+
+    /// Closest known key, for misspelled ones
+    pub suggestion: Option<String>,
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, "line {line}, column {column}: ")?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " (did you mean `{suggestion}`?)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Result of checking a config file with [`Config::validate_str`]
+#[derive(Debug, Clone, Default)]
+pub struct ConfigValidation {
+    /// Problems that stop the file from loading
+    pub errors: Vec<ConfigIssue>,
+
+    /// Deprecated keys that still load
+    pub warnings: Vec<ConfigIssue>,
+}
+
+/// 1-based line and column of a byte offset in `content`
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |s| s.chars().count()) + 1;
+    (line, column)
+}
+
+/// The known key closest to the unknown one in a serde "unknown field"
+/// message, which lists the accepted keys in backticks
+fn suggest_key(message: &str) -> Option<String> {
+    let rest = message.strip_prefix("unknown field `")?;
+    let (unknown, expected) = rest.split_once('`')?;
+    expected
+        .split('`')
+        .skip(1)
+        .step_by(2)
+        .filter(|key| !DEPRECATED_KEYS.iter().any(|(_, old, _)| old == key))
+        .map(|key| (strsim::levenshtein(unknown, key), key))
+        .filter(|(distance, key)| *distance <= (key.len() / 3).max(2))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, key)| key.to_string())
+}
+
+/// How `ordinator config set` changes a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetMode {
+    /// Replace the value
+    Replace,
+
+    /// Add the value to a list, unless it's already there
+    Append,
+
+    /// Remove the value from a list
+    Remove,
+}
+
+/// Split a dotted key such as `profiles.work.file_options."/etc/hosts".requires_sudo`
+fn key_segments(key: &str) -> Result<Vec<String>> {
+    let invalid = || anyhow::Error::from(OrdinatorError::ConfigKeyInvalid(key.to_string()));
+    let mut segments = Vec::new();
+    let mut rest = key;
+    loop {
+        let (segment, after) = match rest.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"').ok_or_else(invalid)?;
+                (&quoted[..end], &quoted[end + 1..])
+            }
+            None => rest.split_at(rest.find('.').unwrap_or(rest.len())),
+        };
+        if segment.is_empty() {
+            return Err(invalid());
+        }
+        segments.push(segment.to_string());
+        if after.is_empty() {
+            return Ok(segments);
+        }
+        rest = after.strip_prefix('.').ok_or_else(invalid)?;
+    }
+}
+
+/// Walk to the value at `segments`, creating missing tables on the way.
+/// List items are addressed by index, e.g. `git.remotes.0.url`.
+fn value_entry<'a>(
+    mut value: &'a mut toml::Value,
+    segments: &[String],
+    key: &str,
+) -> Result<&'a mut toml::Value> {
+    for segment in segments {
+        value = match value {
+            toml::Value::Table(table) => table
+                .entry(segment.clone())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new())),
+            toml::Value::Array(items) => segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get_mut(index))
+                .ok_or_else(|| anyhow::anyhow!("'{key}' has no item {segment}"))?,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "'{key}' is inside a value that isn't a table"
+                ))
+            }
+        };
+    }
+    Ok(value)
+}
+
+/// A value typed on the command line, read as TOML (`true`, `30`,
+/// `["a", "b"]`) when it parses and as a string otherwise
+fn parse_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {raw}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+/// Whether a `[table]` header name matches a pattern from [`DEPRECATED_KEYS`]
+/// Parse octal permissions such as `"600"`, `"0644"` or `"0o755"`
+pub fn parse_mode(value: &str) -> Result<u32> {
+    let digits = value.strip_prefix("0o").unwrap_or(value);
+    u32::from_str_radix(digits, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o7777)
+        .ok_or_else(|| anyhow::anyhow!("'{value}' is not an octal file mode such as \"644\""))
+}
+
+/// Format permissions the way `file_modes` stores them, e.g. `"644"`
+pub fn format_mode(mode: u32) -> String {
+    format!("{:o}", mode & 0o7777)
+}
+
+/// Whether a `--profile` value is a glob such as `work*` rather than a name
+pub fn is_profile_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '[', '{'])
+}
+
+fn table_matches(table: &str, pattern: &str) -> bool {
+    match pattern.strip_suffix(".*") {
+        Some(prefix) => table
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_prefix('.'))
+            .is_some_and(|name| !name.is_empty() && !name.contains('.')),
+        None => table == pattern,
+    }
+}
+
+/// Warnings for deprecated keys, found by scanning table headers and `key =` lines
+fn deprecated_keys(content: &str) -> Vec<ConfigIssue> {
+    let mut warnings = Vec::new();
+    let mut table = String::new();
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if let Some(header) = trimmed.strip_prefix('[') {
+            table = header
+                .trim_start_matches('[')
+                .split(']')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .replace('"', "");
+            continue;
+        }
+        let Some((key, _)) = trimmed.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        for (pattern, old, new) in DEPRECATED_KEYS {
+            if key == *old && table_matches(&table, pattern) {
+                warnings.push(ConfigIssue {
+                    line: Some(index + 1),
+                    column: Some(line.len() - line.trim_start().len() + 1),
+                    message: format!("`{old}` in [{table}] is deprecated; use `{new}` instead"),
+                    suggestion: None,
+                });
+            }
+        }
+    }
+    warnings
 }
 
 impl Config {
+    /// Parse `ordinator.toml` content, reporting a failure with its position
+    /// and a suggestion for misspelled keys. Profiles are not resolved.
+    pub fn parse_str(content: &str) -> std::result::Result<Self, ConfigIssue> {
+        let config: Config = toml::from_str(content).map_err(|e| {
+            let (line, column) = match e.span() {
+                Some(span) => {
+                    let (line, column) = line_column(content, span.start);
+                    (Some(line), Some(column))
+                }
+                None => (None, None),
+            };
+            ConfigIssue {
+                line,
+                column,
+                message: e.message().trim().to_string(),
+                suggestion: suggest_key(e.message()),
+            }
+        })?;
+        Ok(config)
+    }
+
+    /// Tidy every tracked path with [`crate::paths::normalize`], in the
+    /// lists and in the tables keyed by tracked path, so each is written one way
+    fn normalize_paths(&mut self) -> Result<()> {
+        use crate::paths::normalize;
+        let normalize_all = |paths: &mut Vec<String>| -> Result<()> {
+            for path in paths.iter_mut() {
+                *path = normalize(path)?;
+            }
+            Ok(())
+        };
+        for (profile_name, profile) in self.profiles.iter_mut() {
+            let context = || format!("Invalid tracked path in profile '{profile_name}'");
+            normalize_all(&mut profile.files).with_context(context)?;
+            normalize_all(&mut profile.secrets).with_context(context)?;
+            for directory in &mut profile.directories {
+                directory.path = normalize(&directory.path).with_context(context)?;
+            }
+            for shell in profile.shell.values_mut() {
+                normalize_all(&mut shell.files).with_context(context)?;
+            }
+            for original in profile.file_mappings.values_mut() {
+                *original = normalize(original).with_context(context)?;
+            }
+            profile.file_options = std::mem::take(&mut profile.file_options)
+                .into_iter()
+                .map(|(path, options)| Ok((normalize(&path)?, options)))
+                .collect::<Result<_>>()
+                .with_context(context)?;
+            profile.file_modes = std::mem::take(&mut profile.file_modes)
+                .into_iter()
+                .map(|(path, mode)| Ok((normalize(&path)?, mode)))
+                .collect::<Result<_>>()
+                .with_context(context)?;
+        }
+        Ok(())
+    }
+
+    /// Resolve every inheritance chain and check file and directory
+    /// permissions and machine roles, so broken `extends`, modes, and roles
+    /// fail at load time
+    fn check_profiles(&self) -> Result<()> {
+        for (name, role) in &self.machines {
+            if role.profiles.is_empty() {
+                return Err(anyhow::anyhow!("Machine role '{name}' lists no profiles"));
+            }
+            if let Some(missing) = role
+                .profiles
+                .iter()
+                .find(|profile| !self.profiles.contains_key(*profile))
+            {
+                return Err(anyhow::anyhow!(
+                    "Machine role '{name}' uses unknown profile '{missing}'"
+                ));
+            }
+        }
+        for (dir, mode) in &self.global.directory_modes {
+            parse_mode(mode)
+                .with_context(|| format!("Invalid mode for '{dir}' in directory_modes"))?;
+        }
+        let mut names: Vec<&String> = self.profiles.keys().collect();
+        names.sort();
+        for profile_name in names {
+            self.resolve_profile(profile_name)?;
+            let profile = &self.profiles[profile_name];
+            let overrides = profile
+                .file_options
+                .iter()
+                .filter_map(|(path, options)| Some((path, options.permissions.as_ref()?)));
+            for (path, mode) in profile.file_modes.iter().chain(overrides) {
+                parse_mode(mode).with_context(|| {
+                    format!("Invalid permissions for '{path}' in profile '{profile_name}'")
+                })?;
+            }
+            for tool in profile.extensions.keys() {
+                tool.parse::<crate::extensions::EditorTool>()
+                    .with_context(|| format!("Invalid extensions in profile '{profile_name}'"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check `ordinator.toml` content without loading it, for `ordinator config validate`
+    pub fn validate_str(content: &str) -> ConfigValidation {
+        let error = match Self::parse_str(content) {
+            Ok(mut config) => config
+                .normalize_paths()
+                .and_then(|()| config.check_profiles())
+                .err()
+                .map(|e| ConfigIssue {
+                    line: None,
+                    column: None,
+                    message: e.to_string(),
+                    suggestion: None,
+                }),
+            Err(issue) => Some(issue),
+        };
+        ConfigValidation {
+            errors: error.into_iter().collect(),
+            warnings: deprecated_keys(content),
+        }
+    }
+
+    /// The value at a dotted key such as `global.default_profile`, or `None` if it isn't set
+    pub fn get_value(&self, key: &str) -> Result<Option<toml::Value>> {
+        let segments = key_segments(key)?;
+        let root = toml::Value::try_from(self).context("Failed to serialize config")?;
+        Ok(segments
+            .iter()
+            .try_fold(&root, |value, segment| match value {
+                toml::Value::Table(table) => table.get(segment),
+                toml::Value::Array(items) => segment
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| items.get(index)),
+                _ => None,
+            })
+            .cloned())
+    }
+
+    /// Change the value at a dotted key. `raw` is read as TOML, or as a
+    /// string when the key holds strings. The result must still be a valid config.
+    pub fn set_value(&mut self, key: &str, raw: &str, mode: SetMode) -> Result<()> {
+        let segments = key_segments(key)?;
+        let mut first_error = None;
+        for value in [parse_value(raw), toml::Value::String(raw.to_string())] {
+            match self.with_value(&segments, key, value, mode) {
+                Ok(updated) => {
+                    *self = updated;
+                    return Ok(());
+                }
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        Err(first_error.unwrap())
+    }
+
+    /// A copy of this config with one key changed
+    fn with_value(
+        &self,
+        segments: &[String],
+        key: &str,
+        value: toml::Value,
+        mode: SetMode,
+    ) -> Result<Config> {
+        let mut root = toml::Value::try_from(self).context("Failed to serialize config")?;
+        let (last, parents) = segments.split_last().expect("keys have a segment");
+        let toml::Value::Table(table) = value_entry(&mut root, parents, key)? else {
+            return Err(anyhow::anyhow!("'{key}' is not a key in a table"));
+        };
+        let not_a_list = || anyhow::anyhow!("'{key}' is not a list");
+        match mode {
+            SetMode::Replace => {
+                table.insert(last.clone(), value);
+            }
+            SetMode::Append => {
+                let toml::Value::Array(items) = table
+                    .entry(last.clone())
+                    .or_insert_with(|| toml::Value::Array(Vec::new()))
+                else {
+                    return Err(not_a_list());
+                };
+                if !items.contains(&value) {
+                    items.push(value);
+                }
+            }
+            SetMode::Remove => {
+                let Some(toml::Value::Array(items)) = table.get_mut(last) else {
+                    return Err(not_a_list());
+                };
+                let before = items.len();
+                items.retain(|item| item != &value);
+                if items.len() == before {
+                    return Err(anyhow::anyhow!("{value} is not in '{key}'"));
+                }
+            }
+        }
+
+        let updated: Config = root.try_into().map_err(|e: toml::de::Error| {
+            let issue = ConfigIssue {
+                line: None,
+                column: None,
+                message: e.message().trim().to_string(),
+                suggestion: suggest_key(e.message()),
+            };
+            anyhow::anyhow!("Can't set '{key}': {issue}")
+        })?;
+        updated.check_profiles()?;
+        Ok(updated)
+    }
+
     /// Validate that a profile name is filesystem-safe
     pub fn validate_profile_name(profile_name: &str) -> Result<()> {
         // Check if profile name contains only allowed characters: letters, numbers, dash, underscore
         let valid_pattern = regex::Regex::new(r"^[a-zA-Z0-9_-]+$").unwrap();
 
         if !valid_pattern.is_match(profile_name) {
-            return Err(anyhow::anyhow!(
-                "Profile name '{}' is not filesystem-safe. Allowed characters: letters, numbers, dash (-), underscore (_).",
-                profile_name
-            ));
+            return Err(OrdinatorError::InvalidProfileName(profile_name.to_string()).into());
         }
 
         Ok(())
@@ -147,14 +866,28 @@ impl Config {
         let content = std::fs::read_to_string(path)
             .with_context(|| format!("Failed to read config file: {}", path.display()))?;
 
-        let config: Config = toml::from_str(&content)
-            .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+        let mut config = Self::parse_str(&content).map_err(|e| OrdinatorError::ConfigParse {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+        for warning in deprecated_keys(&content) {
+            eprintln!("Warning: {}: {warning}", path.display());
+        }
 
-        // Print identifier if present (for debugging)
         if let Some(identifier) = &config.identifier {
-            eprintln!("[DEBUG] Loaded config: {identifier}");
+            tracing::debug!("Loaded config: {identifier}");
         }
 
+        // Tidy tracked paths and resolve every inheritance chain up front so
+        // bad paths and broken `extends` fail early
+        config
+            .normalize_paths()
+            .and_then(|()| config.check_profiles())
+            .map_err(|e| OrdinatorError::ConfigInvalid {
+                path: path.to_path_buf(),
+                reason: format!("{e:#}"),
+            })?;
+
         Ok(config)
     }
 
@@ -212,9 +945,7 @@ impl Config {
         if let Some(config_path) = Self::find_config_file()? {
             Ok((Self::from_file(&config_path)?, config_path))
         } else {
-            Err(anyhow::anyhow!(
-                "No configuration file found. Run 'ordinator init' first."
-            ))
+            Err(OrdinatorError::ConfigNotFound.into())
         }
     }
 
@@ -244,13 +975,27 @@ impl Config {
                 directories: vec![],
                 bootstrap_script: None,
                 enabled: true,
+                secrets_enabled: None,
                 description: Some("Default profile for basic dotfiles".to_string()),
+                extends: Vec::new(),
+                vars: HashMap::new(),
+                homebrew_taps: Vec::new(),
+                mas_apps: Vec::new(),
+                defaults: BTreeMap::new(),
+                launch_agents: Vec::new(),
+                extensions: BTreeMap::new(),
+                hooks: HooksConfig::default(),
+                shell: BTreeMap::new(),
+                match_rules: ProfileMatch::default(),
+                file_options: BTreeMap::new(),
                 exclude: Vec::new(),
                 homebrew_formulas: Vec::new(),
                 homebrew_casks: Vec::new(),
                 secrets: Vec::new(),
                 created_on: None,
                 file_mappings: HashMap::new(),
+                file_modes: BTreeMap::new(),
+                gpg: GpgConfig::default(),
             },
         );
 
@@ -262,13 +1007,27 @@ impl Config {
                 directories: vec![],
                 bootstrap_script: None,
                 enabled: true,
+                secrets_enabled: None,
                 description: Some("Work environment profile".to_string()),
+                extends: Vec::new(),
+                vars: HashMap::new(),
+                homebrew_taps: Vec::new(),
+                mas_apps: Vec::new(),
+                defaults: BTreeMap::new(),
+                launch_agents: Vec::new(),
+                extensions: BTreeMap::new(),
+                hooks: HooksConfig::default(),
+                shell: BTreeMap::new(),
+                match_rules: ProfileMatch::default(),
+                file_options: BTreeMap::new(),
                 exclude: Vec::new(),
                 homebrew_formulas: Vec::new(),
                 homebrew_casks: Vec::new(),
                 secrets: Vec::new(),
                 created_on: None,
                 file_mappings: HashMap::new(),
+                file_modes: BTreeMap::new(),
+                gpg: GpgConfig::default(),
             },
         );
 
@@ -280,13 +1039,27 @@ impl Config {
                 directories: vec![],
                 bootstrap_script: None,
                 enabled: true,
+                secrets_enabled: None,
                 description: Some("Personal environment profile".to_string()),
+                extends: Vec::new(),
+                vars: HashMap::new(),
+                homebrew_taps: Vec::new(),
+                mas_apps: Vec::new(),
+                defaults: BTreeMap::new(),
+                launch_agents: Vec::new(),
+                extensions: BTreeMap::new(),
+                hooks: HooksConfig::default(),
+                shell: BTreeMap::new(),
+                match_rules: ProfileMatch::default(),
+                file_options: BTreeMap::new(),
                 exclude: Vec::new(),
                 homebrew_formulas: Vec::new(),
                 homebrew_casks: Vec::new(),
                 secrets: Vec::new(),
                 created_on: None,
                 file_mappings: HashMap::new(),
+                file_modes: BTreeMap::new(),
+                gpg: GpgConfig::default(),
             },
         );
 
@@ -295,6 +1068,11 @@ impl Config {
             profiles,
             secrets: SecretsConfig::default(),
             readme: ReadmeConfig::default(),
+            daemon: DaemonConfig::default(),
+            git: GitConfig::default(),
+            hooks: HooksConfig::default(),
+            brew: BrewConfig::default(),
+            machines: BTreeMap::new(),
             identifier: test_name.map(|name| format!("test: {name}")),
         }
     }
@@ -373,28 +1151,476 @@ impl Config {
         Ok(config_path)
     }
 
+    /// Directory tracked paths are resolved against: `[global] target_root` if
+    /// set, else the home directory (`ORDINATOR_HOME` overrides the latter)
+    pub fn target_root(&self) -> Result<PathBuf> {
+        let home_dir = crate::utils::get_home_dir()?;
+        let root = match self.global.target_root.as_deref() {
+            None | Some("~") => return Ok(home_dir),
+            Some(root) => match root.strip_prefix("~/") {
+                Some(rest) => home_dir.join(rest),
+                None => PathBuf::from(root),
+            },
+        };
+        if !root.is_absolute() {
+            return Err(anyhow::anyhow!(
+                "target_root '{}' must be an absolute path or start with ~/",
+                root.display()
+            ));
+        }
+        Ok(root)
+    }
+
     /// Get a profile configuration
     pub fn get_profile(&self, profile_name: &str) -> Option<&ProfileConfig> {
         self.profiles.get(profile_name)
     }
 
+    /// Settings for a tracked file in the profile that defines it. Files inside
+    /// a tracked directory use the directory's settings.
+    pub fn file_options(&self, profile_name: &str, file_path: &str) -> FileOptions {
+        let Some(profile) = self.get_profile(profile_name) else {
+            return FileOptions::default();
+        };
+        profile
+            .file_options
+            .iter()
+            .find(|(path, _)| {
+                file_path == path.as_str()
+                    || file_path
+                        .strip_prefix(path.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .map(|(_, options)| options.clone())
+            .unwrap_or_default()
+    }
+
+    /// Permissions to give a tracked file on apply: the `permissions` override
+    /// in `file_options`, else the mode recorded when the file was added
+    pub fn file_mode(&self, profile_name: &str, file_path: &str) -> Option<u32> {
+        let mode = self
+            .file_options(profile_name, file_path)
+            .permissions
+            .or_else(|| {
+                self.get_profile(profile_name)?
+                    .file_modes
+                    .get(file_path)
+                    .cloned()
+            })?;
+        parse_mode(&mode).ok()
+    }
+
+    /// Record the permissions of `source` for a file tracked in `profile_name`
+    pub fn record_file_mode(
+        &mut self,
+        profile_name: &str,
+        file_path: &str,
+        source: &Path,
+    ) -> Result<()> {
+        let mode = crate::utils::file_mode(source)?;
+        let profile = self
+            .get_profile_mut(profile_name)
+            .ok_or_else(|| OrdinatorError::ProfileNotFound(profile_name.to_string()))?;
+        profile
+            .file_modes
+            .insert(file_path.to_string(), format_mode(mode));
+        Ok(())
+    }
+
     /// Get a mutable profile configuration
     pub fn get_profile_mut(&mut self, profile_name: &str) -> Option<&mut ProfileConfig> {
         self.profiles.get_mut(profile_name)
     }
 
-    /// Add a new profile
-    #[allow(dead_code)]
-    pub fn add_profile(&mut self, name: String, config: ProfileConfig) -> Result<()> {
-        Self::validate_profile_name(&name)?;
-        self.profiles.insert(name, config);
-        Ok(())
+    /// Add a new profile
+    pub fn add_profile(&mut self, name: String, config: ProfileConfig) -> Result<()> {
+        Self::validate_profile_name(&name)?;
+        self.profiles.insert(name, config);
+        Ok(())
+    }
+
+    /// Remove a profile
+    pub fn remove_profile(&mut self, name: &str) -> Option<ProfileConfig> {
+        self.profiles.remove(name)
+    }
+
+    /// Whether secrets are off for a profile: `secrets_enabled = false`, or
+    /// listed in `disabled` (from `apply --no-secrets-for`)
+    pub fn secrets_disabled(&self, profile_name: &str, disabled: &[String]) -> bool {
+        disabled.iter().any(|name| name == profile_name)
+            || self
+                .profiles
+                .get(profile_name)
+                .is_some_and(|profile| profile.secrets_enabled == Some(false))
+    }
+
+    /// Take out of `secrets` every secret a profile with secrets off brings
+    /// in, whether one of `profiles` inherits it or its own profile defines it.
+    /// Returns the secrets taken out.
+    pub fn withhold_secrets(
+        &self,
+        profiles: &[String],
+        disabled: &[String],
+        secrets: &mut Vec<ResolvedEntry>,
+    ) -> Result<Vec<ResolvedEntry>> {
+        let mut withheld = HashSet::new();
+        for profile in profiles {
+            if self.secrets_disabled(profile, disabled) {
+                for entry in self.resolve_profile(profile)?.secrets {
+                    withheld.insert((entry.profile, entry.path));
+                }
+            }
+        }
+        let (taken, kept) = std::mem::take(secrets).into_iter().partition(|entry| {
+            self.secrets_disabled(&entry.profile, disabled)
+                || withheld.contains(&(entry.profile.clone(), entry.path.clone()))
+        });
+        *secrets = kept;
+        Ok(taken)
+    }
+
+    /// Take out of `resolved` the files and directories of every shell in its
+    /// `shell` section that this machine doesn't have. A listed directory
+    /// takes the tracked files inside it too. Returns what was taken out, by shell.
+    pub fn withhold_shell_files(
+        &self,
+        resolved: &mut ResolvedProfile,
+        shells: &Shells,
+    ) -> Vec<SkippedShell> {
+        let mut skipped = Vec::new();
+        for (shell, files) in &resolved.shell {
+            if shells.has(shell, files.when) {
+                continue;
+            }
+            let listed = |path: &str| {
+                files.files.iter().any(|listed| {
+                    let listed = listed.trim_end_matches('/');
+                    path == listed
+                        || path
+                            .strip_prefix(listed)
+                            .is_some_and(|rest| rest.starts_with('/'))
+                })
+            };
+            let mut taken: Vec<String> = Vec::new();
+            resolved.files.retain(|entry| {
+                let keep = !listed(&entry.path);
+                if !keep {
+                    taken.push(entry.path.clone());
+                }
+                keep
+            });
+            resolved.directories.retain(|entry| {
+                let keep = !listed(&entry.directory.path);
+                if !keep {
+                    taken.push(entry.directory.path.clone());
+                }
+                keep
+            });
+            if !taken.is_empty() {
+                skipped.push(SkippedShell {
+                    shell: shell.clone(),
+                    when: files.when,
+                    files: taken,
+                });
+            }
+        }
+        skipped
+    }
+
+    /// Profiles in `chain` whose secret values templates get: all but those
+    /// [`Config::withhold_secrets`] would take secrets from
+    pub fn secret_value_profiles(
+        &self,
+        profiles: &[String],
+        disabled: &[String],
+        chain: &[String],
+    ) -> Result<Vec<String>> {
+        let mut withheld = HashSet::new();
+        for profile in profiles {
+            if self.secrets_disabled(profile, disabled) {
+                withheld.extend(self.resolve_profile(profile)?.chain);
+            }
+        }
+        Ok(chain
+            .iter()
+            .filter(|name| !withheld.contains(*name) && !self.secrets_disabled(name, disabled))
+            .cloned()
+            .collect())
+    }
+
+    /// Resolve a profile's `extends` chain into a merged view with provenance
+    pub fn resolve_profile(&self, profile_name: &str) -> Result<ResolvedProfile> {
+        let mut resolved = ResolvedProfile {
+            hooks: self.hooks.clone(),
+            ..Default::default()
+        };
+        let mut merged = Vec::new();
+        self.resolve_profile_into(profile_name, &mut Vec::new(), &mut merged, &mut resolved)?;
+        resolved.chain = merged;
+        Ok(resolved)
+    }
+
+    /// Resolve several profiles applied together into one merged view, as if
+    /// each extended the ones before it: where two track the same path, the
+    /// later profile's copy wins. Overlapping paths are returned for
+    /// reporting; a path tracked as a file by one profile and a secret or
+    /// directory by another is an error.
+    pub fn resolve_profiles(
+        &self,
+        profile_names: &[String],
+    ) -> Result<(ResolvedProfile, Vec<ProfileOverlap>)> {
+        let mut resolved = ResolvedProfile {
+            hooks: self.hooks.clone(),
+            ..Default::default()
+        };
+        let mut merged = Vec::new();
+        // Path -> (kind, defining profile per requested profile)
+        let mut tracked: BTreeMap<String, (&str, Vec<String>)> = BTreeMap::new();
+        for name in profile_names {
+            let own = self.resolve_profile(name)?;
+            let entries = own
+                .files
+                .iter()
+                .map(|entry| ("file", &entry.path, &entry.profile))
+                .chain(own.secrets.iter().map(|e| ("secret", &e.path, &e.profile)))
+                .chain(
+                    own.directories
+                        .iter()
+                        .map(|e| ("directory", &e.directory.path, &e.profile)),
+                );
+            for (kind, path, defined_in) in entries {
+                let (tracked_as, sources) = tracked
+                    .entry(path.clone())
+                    .or_insert_with(|| (kind, Vec::new()));
+                if *tracked_as != kind {
+                    return Err(anyhow::anyhow!(
+                        "Profiles can't be applied together: {path} is a {tracked_as} in '{}' and a {kind} in '{defined_in}'",
+                        sources.last().map(String::as_str).unwrap_or_default()
+                    ));
+                }
+                sources.retain(|source| source != defined_in);
+                sources.push(defined_in.clone());
+            }
+            self.resolve_profile_into(name, &mut Vec::new(), &mut merged, &mut resolved)?;
+        }
+        resolved.chain = merged;
+        let winners: HashMap<&String, &String> = resolved
+            .files
+            .iter()
+            .chain(&resolved.secrets)
+            .map(|entry| (&entry.path, &entry.profile))
+            .chain(
+                resolved
+                    .directories
+                    .iter()
+                    .map(|entry| (&entry.directory.path, &entry.profile)),
+            )
+            .collect();
+        let overlaps = tracked
+            .into_iter()
+            .filter(|(_, (_, sources))| sources.len() > 1)
+            .map(|(path, (_, mut sources))| {
+                let winner = winners.get(&path).map(|p| (*p).clone()).unwrap_or_default();
+                sources.retain(|source| *source != winner);
+                ProfileOverlap {
+                    path,
+                    winner,
+                    overridden: sources,
+                }
+            })
+            .collect();
+        Ok((resolved, overlaps))
+    }
+
+    fn resolve_profile_into(
+        &self,
+        profile_name: &str,
+        chain: &mut Vec<String>,
+        merged: &mut Vec<String>,
+        resolved: &mut ResolvedProfile,
+    ) -> Result<()> {
+        if chain.iter().any(|p| p == profile_name) {
+            return Err(OrdinatorError::ProfileCycle(format!(
+                "{} -> {}",
+                chain.join(" -> "),
+                profile_name
+            ))
+            .into());
+        }
+        // A profile reached twice (a shared parent) is merged once, the first time
+        if merged.iter().any(|p| p == profile_name) {
+            return Ok(());
+        }
+        let profile = self
+            .get_profile(profile_name)
+            .ok_or_else(|| match chain.last() {
+                Some(child) => OrdinatorError::UnknownParentProfile {
+                    child: child.clone(),
+                    parent: profile_name.to_string(),
+                },
+                None => OrdinatorError::ProfileNotFound(profile_name.to_string()),
+            })?;
+
+        chain.push(profile_name.to_string());
+        for parent in &profile.extends {
+            self.resolve_profile_into(parent, chain, merged, resolved)?;
+        }
+        chain.pop();
+        merged.push(profile_name.to_string());
+
+        for file in &profile.files {
+            resolved.files.retain(|entry| &entry.path != file);
+            resolved.files.push(ResolvedEntry {
+                path: file.clone(),
+                profile: profile_name.to_string(),
+            });
+        }
+        for directory in &profile.directories {
+            resolved
+                .directories
+                .retain(|entry| entry.directory.path != directory.path);
+            resolved.directories.push(ResolvedDirectory {
+                directory: directory.clone(),
+                profile: profile_name.to_string(),
+            });
+        }
+        for secret in &profile.secrets {
+            resolved.secrets.retain(|entry| &entry.path != secret);
+            resolved.secrets.push(ResolvedEntry {
+                path: secret.clone(),
+                profile: profile_name.to_string(),
+            });
+        }
+        for formula in &profile.homebrew_formulas {
+            if !resolved.homebrew_formulas.contains(formula) {
+                resolved.homebrew_formulas.push(formula.clone());
+            }
+        }
+        for cask in &profile.homebrew_casks {
+            if !resolved.homebrew_casks.contains(cask) {
+                resolved.homebrew_casks.push(cask.clone());
+            }
+        }
+        for tap in &profile.homebrew_taps {
+            if !resolved.homebrew_taps.contains(tap) {
+                resolved.homebrew_taps.push(tap.clone());
+            }
+        }
+        for app in &profile.mas_apps {
+            if !resolved.mas_apps.iter().any(|a| a.id == app.id) {
+                resolved.mas_apps.push(app.clone());
+            }
+        }
+        resolved.vars.extend(
+            profile
+                .vars
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+        for (domain, settings) in &profile.defaults {
+            resolved
+                .defaults
+                .entry(domain.clone())
+                .or_default()
+                .extend(settings.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        for agent in &profile.launch_agents {
+            resolved.launch_agents.retain(|entry| &entry.path != agent);
+            resolved.launch_agents.push(ResolvedEntry {
+                path: agent.clone(),
+                profile: profile_name.to_string(),
+            });
+        }
+        for (tool, ids) in &profile.extensions {
+            let resolved_ids = resolved.extensions.entry(tool.clone()).or_default();
+            for id in ids {
+                if !resolved_ids.contains(id) {
+                    resolved_ids.push(id.clone());
+                }
+            }
+        }
+        resolved.hooks.merge(&profile.hooks);
+        for (shell, own) in &profile.shell {
+            let merged = resolved.shell.entry(shell.clone()).or_default();
+            for file in &own.files {
+                if !merged.files.contains(file) {
+                    merged.files.push(file.clone());
+                }
+            }
+            merged.when = own.when;
+        }
+        for key in &profile.gpg.keys {
+            resolved.gpg_keys.retain(|entry| &entry.path != key);
+            resolved.gpg_keys.push(ResolvedEntry {
+                path: key.clone(),
+                profile: profile_name.to_string(),
+            });
+        }
+        if let Some(key) = &profile.gpg.signing_key {
+            resolved.signing_key = Some(key.clone());
+        }
+        Ok(())
+    }
+
+    /// List the profiles that directly extend the given profile
+    pub fn profiles_extending(&self, profile_name: &str) -> Vec<&String> {
+        let mut children: Vec<&String> = self
+            .profiles
+            .iter()
+            .filter(|(_, p)| p.extends.iter().any(|parent| parent == profile_name))
+            .map(|(name, _)| name)
+            .collect();
+        children.sort();
+        children
+    }
+
+    /// Machine roles that list `profile_name`, sorted
+    pub fn machines_using(&self, profile_name: &str) -> Vec<&String> {
+        self.machines
+            .iter()
+            .filter(|(_, role)| role.profiles.iter().any(|name| name == profile_name))
+            .map(|(name, _)| name)
+            .collect()
     }
 
-    /// Remove a profile
-    #[allow(dead_code)]
-    pub fn remove_profile(&mut self, name: &str) -> Option<ProfileConfig> {
-        self.profiles.remove(name)
+    /// Rename a profile, rewriting profile-scoped paths, machine roles, and the default profile
+    pub fn rename_profile(&mut self, old_name: &str, new_name: &str) -> Result<()> {
+        Self::validate_profile_name(new_name)?;
+        if self.profiles.contains_key(new_name) {
+            return Err(OrdinatorError::ProfileExists(new_name.to_string()).into());
+        }
+        let mut profile = self
+            .profiles
+            .remove(old_name)
+            .ok_or_else(|| OrdinatorError::ProfileNotFound(old_name.to_string()))?;
+
+        // Bootstrap scripts live under scripts/<profile>/ and move with the profile
+        if let Some(script) = &profile.bootstrap_script {
+            let old_prefix = format!("scripts/{old_name}/");
+            if let Some(rest) = script.strip_prefix(&old_prefix) {
+                profile.bootstrap_script = Some(format!("scripts/{new_name}/{rest}"));
+            }
+        }
+
+        self.profiles.insert(new_name.to_string(), profile);
+        for other in self.profiles.values_mut() {
+            for parent in other.extends.iter_mut() {
+                if parent == old_name {
+                    *parent = new_name.to_string();
+                }
+            }
+        }
+        for role in self.machines.values_mut() {
+            for profile in role.profiles.iter_mut() {
+                if profile == old_name {
+                    *profile = new_name.to_string();
+                }
+            }
+        }
+        if self.global.default_profile == old_name {
+            self.global.default_profile = new_name.to_string();
+        }
+        Ok(())
     }
 
     /// List all profile names
@@ -402,6 +1628,44 @@ impl Config {
         self.profiles.keys().collect()
     }
 
+    /// The profiles `patterns` name, in order: a glob such as `work*` stands
+    /// for each matching profile, sorted by name, and a plain name must
+    /// exist. `all` selects every profile, sorted by name.
+    pub fn select_profiles(&self, patterns: &[String], all: bool) -> Result<Vec<String>> {
+        let mut names: Vec<&String> = self.profiles.keys().collect();
+        names.sort();
+        if all {
+            return Ok(names.into_iter().cloned().collect());
+        }
+        let mut selected: Vec<String> = Vec::new();
+        for pattern in patterns {
+            let matched: Vec<String> = if is_profile_glob(pattern) {
+                let glob = Glob::new(pattern)
+                    .with_context(|| format!("Invalid profile pattern '{pattern}'"))?
+                    .compile_matcher();
+                let matched: Vec<String> = names
+                    .iter()
+                    .filter(|name| glob.is_match(name.as_str()))
+                    .map(|name| name.to_string())
+                    .collect();
+                if matched.is_empty() {
+                    return Err(OrdinatorError::NoProfilesMatch(pattern.clone()).into());
+                }
+                matched
+            } else if self.profiles.contains_key(pattern) {
+                vec![pattern.clone()]
+            } else {
+                return Err(OrdinatorError::ProfileNotFound(pattern.clone()).into());
+            };
+            for name in matched {
+                if !selected.contains(&name) {
+                    selected.push(name);
+                }
+            }
+        }
+        Ok(selected)
+    }
+
     /// Check if a profile exists
     #[allow(dead_code)]
     pub fn has_profile(&self, name: &str) -> bool {
@@ -425,7 +1689,7 @@ impl Config {
         let profile = self
             .profiles
             .get_mut(profile_name)
-            .ok_or_else(|| anyhow::anyhow!("Profile '{}' does not exist", profile_name))?;
+            .ok_or_else(|| OrdinatorError::ProfileNotFound(profile_name.to_string()))?;
 
         if !profile.files.contains(&file_path) {
             profile.files.push(file_path);
@@ -434,12 +1698,138 @@ impl Config {
         Ok(())
     }
 
+    /// Add a directory to a profile, replacing an existing entry for the same path
+    pub fn add_directory_to_profile(
+        &mut self,
+        profile_name: &str,
+        directory: TrackedDirectory,
+    ) -> Result<()> {
+        let profile = self
+            .profiles
+            .get_mut(profile_name)
+            .ok_or_else(|| OrdinatorError::ProfileNotFound(profile_name.to_string()))?;
+
+        profile
+            .file_mappings
+            .insert(directory.storage_name(), directory.path.clone());
+        match profile
+            .directories
+            .iter_mut()
+            .find(|d| d.path == directory.path)
+        {
+            Some(existing) => *existing = directory,
+            None => profile.directories.push(directory),
+        }
+
+        Ok(())
+    }
+
+    /// Remove a directory from a profile, returning the removed entry
+    pub fn remove_directory_from_profile(
+        &mut self,
+        profile_name: &str,
+        path: &str,
+    ) -> Result<Option<TrackedDirectory>> {
+        let profile = self
+            .get_profile_mut(profile_name)
+            .ok_or_else(|| OrdinatorError::ProfileNotFound(profile_name.to_string()))?;
+        let path = crate::directory::normalize_path(path);
+        let Some(index) = profile.directories.iter().position(|d| d.path == path) else {
+            return Ok(None);
+        };
+        let directory = profile.directories.remove(index);
+        profile.file_mappings.remove(&directory.storage_name());
+        Ok(Some(directory))
+    }
+
+    /// Find a tracked directory entry in a profile
+    pub fn get_directory(&self, profile_name: &str, path: &str) -> Option<&TrackedDirectory> {
+        let path = crate::directory::normalize_path(path);
+        self.get_profile(profile_name)?
+            .directories
+            .iter()
+            .find(|d| d.path == path)
+    }
+
+    /// Where a tracked directory's copy is stored: `files/<profile>/<hash>_<name>`
+    pub fn get_directory_storage_path(
+        &self,
+        profile_name: &str,
+        directory: &TrackedDirectory,
+    ) -> Result<PathBuf> {
+        Ok(get_dotfiles_dir()?
+            .join("files")
+            .join(profile_name)
+            .join(directory.storage_name()))
+    }
+
+    /// Link targets (tracked path, defining profile, source) for every tracked
+    /// file and directory in a resolved profile
+    pub fn link_targets(
+        &self,
+        resolved: &ResolvedProfile,
+        expand_directories: bool,
+    ) -> Result<Vec<(String, String, PathBuf)>> {
+        let mut targets = Vec::new();
+        for entry in &resolved.files {
+            // Get the source file path from the profile that defines the file
+            let source_path = self.get_source_file_path(&entry.profile, &entry.path)?;
+            targets.push((entry.path.clone(), entry.profile.clone(), source_path));
+        }
+        for entry in &resolved.directories {
+            targets.extend(self.directory_link_targets(entry, expand_directories)?);
+        }
+        Ok(targets)
+    }
+
+    /// File filter for a tracked directory: its own patterns, the global and
+    /// profile exclusions, and the repository's `.ordinatorignore`
+    pub fn directory_filter(
+        &self,
+        profile_name: &str,
+        directory: &TrackedDirectory,
+    ) -> Result<crate::directory::DirectoryFilter> {
+        let filter = directory.filter(&self.exclusion_set_for_profile(profile_name)?)?;
+        Ok(filter.with_repo_ignore(get_dotfiles_dir()?.join(crate::directory::IGNORE_FILE)))
+    }
+
+    /// Link targets for a tracked directory: one per stored file, or the
+    /// directory itself when it is linked as a whole and `expand` is false
+    pub fn directory_link_targets(
+        &self,
+        entry: &ResolvedDirectory,
+        expand: bool,
+    ) -> Result<Vec<(String, String, PathBuf)>> {
+        use crate::directory::DirectoryLink;
+        let directory = &entry.directory;
+        let storage = self.get_directory_storage_path(&entry.profile, directory)?;
+        if (directory.link == DirectoryLink::Directory && !expand) || !storage.is_dir() {
+            // A missing stored copy is reported like a missing tracked file
+            return Ok(vec![(
+                directory.path.clone(),
+                entry.profile.clone(),
+                storage,
+            )]);
+        }
+        let filter = self.directory_filter(&entry.profile, directory)?;
+        Ok(crate::directory::list_files(&storage, &filter)?
+            .into_iter()
+            .map(|relative| {
+                (
+                    format!("{}/{}", directory.path, relative.display()),
+                    entry.profile.clone(),
+                    storage.join(relative),
+                )
+            })
+            .collect())
+    }
+
     /// Add a secret file to a profile's tracked secrets
     pub fn add_secret_to_profile(&mut self, profile_name: &str, secret_path: String) -> Result<()> {
         let profile = self
             .profiles
             .get_mut(profile_name)
-            .ok_or_else(|| anyhow::anyhow!("Profile '{}' does not exist", profile_name))?;
+            .ok_or_else(|| OrdinatorError::ProfileNotFound(profile_name.to_string()))?;
 
         if !profile.secrets.contains(&secret_path) {
             profile.secrets.push(secret_path);
@@ -451,21 +1841,65 @@ impl Config {
     /// Get the profile-specific file path for a tracked file
     pub fn get_profile_file_path(&self, profile_name: &str, file_path: &str) -> Result<PathBuf> {
         if !self.has_profile(profile_name) {
-            return Err(anyhow::anyhow!("Profile '{}' not found", profile_name));
+            return Err(OrdinatorError::ProfileNotFound(profile_name.to_string()).into());
         }
 
         // For profile-specific storage, files are stored as files/<profile>/<file>
         let dotfiles_dir = get_dotfiles_dir()?;
         let profile_files_dir = dotfiles_dir.join("files").join(profile_name);
-        let profile_file_path = profile_files_dir.join(file_path);
+        let profile_file_path = profile_files_dir.join(storage_relative_path(file_path));
 
         Ok(profile_file_path)
     }
 
-    /// Get the source file path for symlinking (handles hash-based mapping and legacy structures)
+    /// Directory holding a profile's overrides for one host: `files/<profile>/<host>`
+    pub fn get_host_override_dir(&self, profile_name: &str, host: &str) -> Result<PathBuf> {
+        if host.is_empty() || host.contains(['/', '\\']) || host.starts_with('.') {
+            return Err(anyhow::anyhow!("Invalid host name '{}'", host));
+        }
+        Ok(get_dotfiles_dir()?
+            .join("files")
+            .join(profile_name)
+            .join(host))
+    }
+
+    /// Where a host override of a tracked file is stored:
+    /// `files/<profile>/<host>/<hash>_<name>`
+    pub fn get_host_override_path(
+        &self,
+        profile_name: &str,
+        file_path: &str,
+        host: &str,
+    ) -> Result<PathBuf> {
+        let stored_name = self
+            .get_profile(profile_name)
+            .and_then(|profile| {
+                profile
+                    .file_mappings
+                    .iter()
+                    .find(|(_, v)| v == &file_path)
+                    .map(|(hash_filename, _)| hash_filename.clone())
+            })
+            .unwrap_or_else(|| storage_relative_path(file_path).to_string());
+        Ok(self
+            .get_host_override_dir(profile_name, host)?
+            .join(stored_name))
+    }
+
+    /// Get the source file path for symlinking (handles hash-based mapping and legacy structures).
+    /// A host override for this machine wins over the profile's copy, which wins over a
+    /// shared copy directly under `files/`.
     pub fn get_source_file_path(&self, profile_name: &str, file_path: &str) -> Result<PathBuf> {
         let dotfiles_dir = get_dotfiles_dir()?;
 
+        if let Some(host) = crate::machine::host_name() {
+            if let Ok(host_path) = self.get_host_override_path(profile_name, file_path, &host) {
+                if host_path.exists() {
+                    return Ok(host_path);
+                }
+            }
+        }
+
         // First, try to use the hash-based mapping if it exists
         if let Some(profile) = self.get_profile(profile_name) {
             // Find the hash_filename for this file_path
@@ -486,13 +1920,15 @@ impl Config {
         let profile_file_path = dotfiles_dir
             .join("files")
             .join(profile_name)
-            .join(file_path);
+            .join(storage_relative_path(file_path));
         if profile_file_path.exists() {
             return Ok(profile_file_path);
         }
 
         // Fallback: flat structure (legacy)
-        let flat_file_path = dotfiles_dir.join("files").join(file_path);
+        let flat_file_path = dotfiles_dir
+            .join("files")
+            .join(storage_relative_path(file_path));
         if flat_file_path.exists() {
             return Ok(flat_file_path);
         }
@@ -516,10 +1952,117 @@ impl Config {
     pub fn remove_file_from_profile(&mut self, profile_name: &str, file_path: &str) -> Result<()> {
         if let Some(profile) = self.get_profile_mut(profile_name) {
             profile.files.retain(|f| f != file_path);
+            profile.file_options.remove(file_path);
+            profile.file_modes.remove(file_path);
             Ok(())
         } else {
-            Err(anyhow::anyhow!("Profile '{}' not found", profile_name))
+            Err(OrdinatorError::ProfileNotFound(profile_name.to_string()).into())
+        }
+    }
+
+    /// Tracked files and directories of a profile (its own, not inherited)
+    /// whose stored copy is gone from the repository
+    pub fn missing_sources(&self, profile_name: &str) -> Result<Vec<String>> {
+        let profile = self
+            .get_profile(profile_name)
+            .ok_or_else(|| OrdinatorError::ProfileNotFound(profile_name.to_string()))?;
+        let mut missing = Vec::new();
+        for file in &profile.files {
+            if !self.get_source_file_path(profile_name, file)?.exists() {
+                missing.push(file.clone());
+            }
+        }
+        for directory in &profile.directories {
+            if !self
+                .get_directory_storage_path(profile_name, directory)?
+                .exists()
+            {
+                missing.push(directory.path.clone());
+            }
+        }
+        Ok(missing)
+    }
+
+    /// Drop a tracked file or directory from a profile, with its stored-name mapping
+    pub fn prune_entry(&mut self, profile_name: &str, path: &str) -> Result<()> {
+        if self
+            .remove_directory_from_profile(profile_name, path)?
+            .is_some()
+        {
+            return Ok(());
+        }
+        self.remove_file_from_profile(profile_name, path)?;
+        if let Some(profile) = self.get_profile_mut(profile_name) {
+            profile.file_mappings.retain(|_, original| original != path);
+        }
+        Ok(())
+    }
+
+    /// Files under `files/<profile>/` that no tracked file or directory of the
+    /// profile refers to. Host overrides of tracked files are not orphans.
+    /// The tracked path whose copy is stored at `repo_path`, a path in the
+    /// repository like `files/<profile>/<hash>_.env`, if its profile tracks it
+    pub fn tracked_path_for_stored(&self, repo_path: &Path) -> Option<String> {
+        let mut components = repo_path.strip_prefix("files").ok()?.components();
+        let profile = self.get_profile(components.next()?.as_os_str().to_str()?)?;
+        let stored = components.as_path();
+        if let Some(original) = stored
+            .to_str()
+            .and_then(|name| profile.file_mappings.get(name))
+        {
+            return Some(original.clone());
+        }
+        profile
+            .files
+            .iter()
+            .find(|file| Path::new(storage_relative_path(file)) == stored)
+            .cloned()
+    }
+
+    pub fn orphaned_files(&self, profile_name: &str) -> Result<Vec<PathBuf>> {
+        let profile = self
+            .get_profile(profile_name)
+            .ok_or_else(|| OrdinatorError::ProfileNotFound(profile_name.to_string()))?;
+        let root = get_dotfiles_dir()?.join("files").join(profile_name);
+        if !root.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut stored: HashSet<PathBuf> = HashSet::new();
+        for file in &profile.files {
+            stored.insert(PathBuf::from(storage_relative_path(file)));
+        }
+        for (stored_name, original) in &profile.file_mappings {
+            if profile.files.contains(original) {
+                stored.insert(PathBuf::from(stored_name));
+            }
+        }
+        let directories: Vec<PathBuf> = profile
+            .directories
+            .iter()
+            .map(|directory| PathBuf::from(directory.storage_name()))
+            .collect();
+        let referenced = |relative: &Path| {
+            stored.contains(relative) || directories.iter().any(|dir| relative.starts_with(dir))
+        };
+
+        let mut orphans = Vec::new();
+        for entry in walkdir::WalkDir::new(&root).sort_by_file_name() {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(&root)?;
+            // files/<profile>/<host>/<stored name> overrides a tracked file
+            let below_host: PathBuf = relative.components().skip(1).collect();
+            if referenced(relative)
+                || (!below_host.as_os_str().is_empty() && referenced(&below_host))
+            {
+                continue;
+            }
+            orphans.push(entry.path().to_path_buf());
         }
+        Ok(orphans)
     }
 
     /// Remove a secret file from a profile
@@ -532,7 +2075,7 @@ impl Config {
             profile.secrets.retain(|s| s != secret_path);
             Ok(())
         } else {
-            Err(anyhow::anyhow!("Profile '{}' not found", profile_name))
+            Err(OrdinatorError::ProfileNotFound(profile_name.to_string()).into())
         }
     }
 
@@ -629,6 +2172,12 @@ Thumbs.db
 *.log
 logs/
 
+# Apply transaction journals (machine-specific)
+transactions/
+
+# Content-hash cache used by `ordinator status` (machine-specific)
+cache/
+
 # Temporary files
 *.tmp
 *.temp
@@ -661,25 +2210,56 @@ fn default_enabled() -> bool {
     true
 }
 
-/// Get the dotfiles directory path
-fn get_dotfiles_dir() -> Result<PathBuf> {
-    // Check if we're in test mode
-    let is_test_mode = std::env::var("ORDINATOR_TEST_MODE").unwrap_or_default() == "1";
-
-    if let Ok(ordinator_home) = std::env::var("ORDINATOR_HOME") {
-        return Ok(PathBuf::from(ordinator_home));
+/// Machine-specific directories in the dotfiles repository, kept out of git
+pub const MACHINE_STATE_DIRS: &[&str] = &["transactions", "cache"];
+
+/// List [`MACHINE_STATE_DIRS`] in the repository's `.gitignore`, for
+/// repositories created before they were all in the generated one
+pub fn ignore_machine_state(dotfiles_dir: &Path) -> Result<()> {
+    let gitignore = dotfiles_dir.join(".gitignore");
+    let mut content = std::fs::read_to_string(&gitignore).unwrap_or_default();
+    let missing: Vec<String> = MACHINE_STATE_DIRS
+        .iter()
+        .map(|dir| format!("{dir}/"))
+        .filter(|entry| {
+            !content.lines().any(|line| {
+                let line = line.trim();
+                line == entry || line.trim_start_matches('/') == entry
+            })
+        })
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
     }
+    content.push_str("\n# Machine-specific state (journals, caches)\n");
+    for entry in missing {
+        content.push_str(&entry);
+        content.push('\n');
+    }
+    std::fs::write(&gitignore, content)
+        .with_context(|| format!("Failed to update {}", gitignore.display()))
+}
 
-    // In test mode, require explicit configuration to prevent accidental use of real directories
-    if is_test_mode {
-        return Err(anyhow::anyhow!(
-            "Test mode requires ORDINATOR_HOME or ORDINATOR_CONFIG to be set for proper isolation"
-        ));
+/// The dotfiles repository tracked files are read from and stored in: the
+/// directory holding `ORDINATOR_CONFIG` when that is set, else `.dotfiles`
+/// in the home directory (`ORDINATOR_HOME` when that is set)
+pub fn get_dotfiles_dir() -> Result<PathBuf> {
+    if let Ok(path) = env::var("ORDINATOR_CONFIG") {
+        return match Path::new(&path).parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => Ok(dir.to_path_buf()),
+            _ => Ok(env::current_dir()?),
+        };
     }
+    crate::environment::current().default_dotfiles_dir()
+}
 
-    let home_dir =
-        dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))?;
-    Ok(home_dir.join(".dotfiles"))
+/// Where a tracked path lives under `files/<profile>/`. Absolute destinations
+/// (e.g. `/etc/hosts`) are stored by their path below `/`.
+fn storage_relative_path(file_path: &str) -> &str {
+    file_path.trim_start_matches('/')
 }
 
 #[cfg(test)]
@@ -688,6 +2268,42 @@ mod tests {
     use std::io::Write;
     use tempfile::NamedTempFile;
 
+    #[test]
+    fn test_ignore_machine_state_adds_missing_entries_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let gitignore = dir.path().join(".gitignore");
+        std::fs::write(&gitignore, "*.log\n/transactions/").unwrap();
+        ignore_machine_state(dir.path()).unwrap();
+        ignore_machine_state(dir.path()).unwrap();
+        let content = std::fs::read_to_string(&gitignore).unwrap();
+        assert_eq!(content.matches("cache/").count(), 1);
+        assert_eq!(content.matches("transactions/").count(), 1);
+        assert!(content.starts_with("*.log\n/transactions/\n"));
+    }
+
+    #[test]
+    fn test_get_dotfiles_dir() {
+        let original_home = env::var_os("ORDINATOR_HOME");
+        let original_config = env::var_os("ORDINATOR_CONFIG");
+        env::set_var("ORDINATOR_HOME", "/test/home");
+        env::remove_var("ORDINATOR_CONFIG");
+        assert_eq!(
+            get_dotfiles_dir().unwrap(),
+            PathBuf::from("/test/home/.dotfiles")
+        );
+        env::set_var("ORDINATOR_CONFIG", "/test/repo/ordinator.toml");
+        assert_eq!(get_dotfiles_dir().unwrap(), PathBuf::from("/test/repo"));
+        for (key, value) in [
+            ("ORDINATOR_HOME", original_home),
+            ("ORDINATOR_CONFIG", original_config),
+        ] {
+            match value {
+                Some(value) => env::set_var(key, value),
+                None => env::remove_var(key),
+            }
+        }
+    }
+
     #[test]
     fn test_config_creation() {
         let config = Config::create_default();
@@ -712,6 +2328,28 @@ mod tests {
         assert_eq!(config.profiles.len(), loaded_config.profiles.len());
     }
 
+    #[test]
+    fn test_taps_and_mas_apps_parse() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(
+            temp_file,
+            r#"
+[global]
+default_profile = "default"
+
+[profiles.default]
+files = []
+taps = ["homebrew/cask-fonts"]
+mas_apps = [{{ id = 497799835, name = "Xcode" }}]
+"#
+        )
+        .unwrap();
+        let config = Config::from_file(temp_file.path()).unwrap();
+        let profile = config.get_profile("default").unwrap();
+        assert_eq!(profile.homebrew_taps, ["homebrew/cask-fonts"]);
+        assert_eq!(profile.mas_apps[0].id, 497799835);
+    }
+
     #[test]
     fn test_profile_management() {
         let mut config = Config::create_default();
@@ -722,13 +2360,27 @@ mod tests {
             directories: vec![],
             bootstrap_script: None,
             enabled: true,
+            secrets_enabled: None,
             description: Some("Test profile".to_string()),
+            extends: Vec::new(),
+            vars: HashMap::new(),
+            homebrew_taps: Vec::new(),
+            mas_apps: Vec::new(),
+            defaults: BTreeMap::new(),
+            launch_agents: Vec::new(),
+            extensions: BTreeMap::new(),
+            hooks: HooksConfig::default(),
+            shell: BTreeMap::new(),
+            match_rules: ProfileMatch::default(),
+            file_options: BTreeMap::new(),
             exclude: Vec::new(),
             homebrew_formulas: Vec::new(),
             homebrew_casks: Vec::new(),
             secrets: Vec::new(),
             created_on: None,
             file_mappings: HashMap::new(),
+            file_modes: BTreeMap::new(),
+            gpg: GpgConfig::default(),
         };
 
         let _ = config.add_profile("test".to_string(), new_profile);
@@ -888,13 +2540,27 @@ mod tests {
             directories: vec![],
             bootstrap_script: None,
             enabled: true,
+            secrets_enabled: None,
             description: Some("Test profile".to_string()),
+            extends: Vec::new(),
+            vars: HashMap::new(),
+            homebrew_taps: Vec::new(),
+            mas_apps: Vec::new(),
+            defaults: BTreeMap::new(),
+            launch_agents: Vec::new(),
+            extensions: BTreeMap::new(),
+            hooks: HooksConfig::default(),
+            shell: BTreeMap::new(),
+            match_rules: ProfileMatch::default(),
+            file_options: BTreeMap::new(),
             exclude: Vec::new(),
             homebrew_formulas: Vec::new(),
             homebrew_casks: Vec::new(),
             secrets: Vec::new(),
             created_on: None,
             file_mappings: HashMap::new(),
+            file_modes: BTreeMap::new(),
+            gpg: GpgConfig::default(),
         };
 
         assert!(config
@@ -906,4 +2572,385 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("filesystem-safe"));
     }
+
+    #[test]
+    fn test_rename_profile() {
+        let mut config = Config::create_default();
+        config.global.default_profile = "work".to_string();
+        config.get_profile_mut("work").unwrap().bootstrap_script =
+            Some("scripts/work/bootstrap.sh".to_string());
+        config.get_profile_mut("personal").unwrap().extends = vec!["work".to_string()];
+        config
+            .add_file_to_profile("work", "~/.gitconfig".to_string())
+            .unwrap();
+
+        config.rename_profile("work", "office").unwrap();
+
+        assert!(!config.has_profile("work"));
+        let profile = config.get_profile("office").unwrap();
+        assert_eq!(profile.files, vec!["~/.gitconfig".to_string()]);
+        assert_eq!(
+            profile.bootstrap_script.as_deref(),
+            Some("scripts/office/bootstrap.sh")
+        );
+        assert_eq!(config.default_profile(), "office");
+        assert_eq!(
+            config.get_profile("personal").unwrap().extends,
+            vec!["office".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_resolve_profile_inheritance() {
+        let mut config = Config::create_default();
+        {
+            let base = config.get_profile_mut("default").unwrap();
+            base.files = vec![".zshrc".to_string(), ".gitconfig".to_string()];
+            base.secrets = vec![".ssh/config".to_string()];
+            base.homebrew_formulas = vec!["git".to_string()];
+            base.vars
+                .insert("email".to_string(), "me@home.example".to_string());
+            base.vars.insert("editor".to_string(), "vim".to_string());
+        }
+        {
+            let work = config.get_profile_mut("work").unwrap();
+            work.extends = vec!["default".to_string()];
+            work.files = vec![".gitconfig".to_string(), ".work_aliases".to_string()];
+            work.homebrew_formulas = vec!["git".to_string(), "awscli".to_string()];
+            work.vars
+                .insert("email".to_string(), "me@work.example".to_string());
+        }
+
+        let resolved = config.resolve_profile("work").unwrap();
+        let files: Vec<(&str, &str)> = resolved
+            .files
+            .iter()
+            .map(|e| (e.path.as_str(), e.profile.as_str()))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                (".zshrc", "default"),
+                (".gitconfig", "work"),
+                (".work_aliases", "work")
+            ]
+        );
+        assert_eq!(resolved.secrets[0].profile, "default");
+        assert_eq!(resolved.homebrew_formulas, vec!["git", "awscli"]);
+        assert_eq!(resolved.vars["email"], "me@work.example");
+        assert_eq!(resolved.vars["editor"], "vim");
+        assert_eq!(config.profiles_extending("default"), vec!["work"]);
+    }
+
+    #[test]
+    fn test_resolve_profile_errors() {
+        let mut config = Config::create_default();
+        config.get_profile_mut("work").unwrap().extends = vec!["missing".to_string()];
+        let err = config.resolve_profile("work").unwrap_err().to_string();
+        assert!(err.contains("extends unknown profile 'missing'"));
+
+        config.get_profile_mut("work").unwrap().extends = vec!["personal".to_string()];
+        config.get_profile_mut("personal").unwrap().extends = vec!["work".to_string()];
+        let err = config.resolve_profile("work").unwrap_err().to_string();
+        assert!(err.contains("cycle"));
+    }
+
+    #[test]
+    fn test_withhold_secrets_for_profiles_with_secrets_off() {
+        let mut config = Config::create_default();
+        config.get_profile_mut("default").unwrap().secrets = vec![".npmrc".to_string()];
+        {
+            let work = config.get_profile_mut("work").unwrap();
+            work.extends = vec!["default".to_string()];
+            work.secrets = vec![".aws/credentials".to_string()];
+        }
+        config.get_profile_mut("personal").unwrap().secrets = vec![".netrc".to_string()];
+        let names = ["work".to_string(), "personal".to_string()];
+        let paths = |entries: &[ResolvedEntry]| {
+            entries
+                .iter()
+                .map(|entry| entry.path.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let mut secrets = config.resolve_profiles(&names).unwrap().0.secrets;
+        let withheld = config.withhold_secrets(&names, &[], &mut secrets).unwrap();
+        assert!(withheld.is_empty());
+        assert_eq!(secrets.len(), 3);
+
+        // Inherited secrets are withheld along with the profile's own
+        config.get_profile_mut("work").unwrap().secrets_enabled = Some(false);
+        let withheld = config.withhold_secrets(&names, &[], &mut secrets).unwrap();
+        assert_eq!(paths(&withheld), [".npmrc", ".aws/credentials"]);
+        assert_eq!(paths(&secrets), [".netrc"]);
+
+        let withheld = config
+            .withhold_secrets(&names, &["personal".to_string()], &mut secrets)
+            .unwrap();
+        assert_eq!(paths(&withheld), [".netrc"]);
+        assert!(secrets.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_profiles_later_profile_wins() {
+        let mut config = Config::create_default();
+        {
+            let base = config.get_profile_mut("default").unwrap();
+            base.files = vec![".zshrc".to_string()];
+            base.homebrew_formulas = vec!["git".to_string()];
+            base.hooks.post_apply = vec!["base.sh".to_string()];
+        }
+        {
+            let work = config.get_profile_mut("work").unwrap();
+            work.extends = vec!["default".to_string()];
+            work.files = vec![".gitconfig".to_string(), ".npmrc".to_string()];
+            work.homebrew_formulas = vec!["awscli".to_string()];
+        }
+        {
+            let personal = config.get_profile_mut("personal").unwrap();
+            personal.extends = vec!["default".to_string()];
+            personal.files = vec![".gitconfig".to_string()];
+            personal.homebrew_formulas = vec!["git".to_string(), "hugo".to_string()];
+        }
+
+        let names = ["work".to_string(), "personal".to_string()];
+        let (resolved, overlaps) = config.resolve_profiles(&names).unwrap();
+        let files: Vec<(&str, &str)> = resolved
+            .files
+            .iter()
+            .map(|e| (e.path.as_str(), e.profile.as_str()))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                (".zshrc", "default"),
+                (".npmrc", "work"),
+                (".gitconfig", "personal")
+            ]
+        );
+        assert_eq!(resolved.homebrew_formulas, vec!["git", "awscli", "hugo"]);
+        // The shared parent's hooks run once
+        assert_eq!(resolved.hooks.post_apply, vec!["base.sh"]);
+        assert_eq!(
+            overlaps,
+            vec![ProfileOverlap {
+                path: ".gitconfig".to_string(),
+                winner: "personal".to_string(),
+                overridden: vec!["work".to_string()],
+            }]
+        );
+
+        config.get_profile_mut("personal").unwrap().secrets = vec![".npmrc".to_string()];
+        let err = config.resolve_profiles(&names).unwrap_err().to_string();
+        assert!(err.contains(".npmrc is a file in 'work' and a secret in 'personal'"));
+    }
+
+    #[test]
+    fn test_from_file_rejects_inheritance_cycle() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("ordinator.toml");
+        std::fs::write(
+            &config_path,
+            "[profiles.a]\nextends = [\"b\"]\n\n[profiles.b]\nextends = [\"a\"]\n",
+        )
+        .unwrap();
+        assert!(Config::from_file(&config_path).is_err());
+    }
+
+    #[test]
+    fn test_validate_reports_position_and_suggestion() {
+        let validation = Config::validate_str("[global]\nauto_push = true\ncreate_backup = true\n");
+        assert_eq!(validation.errors.len(), 1);
+        let error = &validation.errors[0];
+        assert_eq!((error.line, error.column), (Some(3), Some(1)));
+        assert_eq!(error.suggestion.as_deref(), Some("create_backups"));
+        assert!(error.to_string().contains("did you mean `create_backups`?"));
+
+        let validation = Config::validate_str("[global]\nauto_push = \"yes\"\n");
+        assert_eq!(validation.errors[0].line, Some(2));
+        assert!(validation.errors[0].message.contains("invalid type"));
+        assert!(validation.errors[0].suggestion.is_none());
+
+        let content =
+            "[global]\nbackup_existing = false\n\n[profiles.work]\nhomebrew_packages = [\"git\"]\n";
+        let validation = Config::validate_str(content);
+        assert!(validation.errors.is_empty());
+        let lines: Vec<Option<usize>> = validation.warnings.iter().map(|w| w.line).collect();
+        assert_eq!(lines, [Some(2), Some(5)]);
+        let config = Config::parse_str(content).unwrap();
+        assert_eq!(config.global.create_backups, Some(false));
+        assert_eq!(config.profiles["work"].homebrew_formulas, ["git"]);
+    }
+
+    #[test]
+    fn test_get_and_set_values() {
+        let mut config = Config::create_default();
+        assert_eq!(
+            config.get_value("global.default_profile").unwrap(),
+            Some(toml::Value::String("default".to_string()))
+        );
+        assert_eq!(config.get_value("profiles.missing.files").unwrap(), None);
+
+        config
+            .set_value("global.auto_push", "true", SetMode::Replace)
+            .unwrap();
+        assert!(config.global.auto_push);
+        // A value that reads as TOML is kept as a string where one is expected
+        config
+            .set_value("profiles.default.description", "2024", SetMode::Replace)
+            .unwrap();
+        assert_eq!(
+            config.profiles["default"].description.as_deref(),
+            Some("2024")
+        );
+
+        config
+            .set_value("global.exclude", "*.log", SetMode::Append)
+            .unwrap();
+        config
+            .set_value("global.exclude", "*.log", SetMode::Append)
+            .unwrap();
+        assert_eq!(config.global.exclude, ["*.log"]);
+        config
+            .set_value("global.exclude", "*.log", SetMode::Remove)
+            .unwrap();
+        assert!(config.global.exclude.is_empty());
+        assert!(config
+            .set_value("global.exclude", "*.log", SetMode::Remove)
+            .is_err());
+
+        let err = config
+            .set_value("global.create_backup", "true", SetMode::Replace)
+            .unwrap_err();
+        assert!(err.to_string().contains("did you mean `create_backups`?"));
+        assert!(config
+            .set_value("global.auto_push", "sometimes", SetMode::Replace)
+            .is_err());
+        assert!(config
+            .set_value(
+                "profiles.default.extends",
+                "[\"default\"]",
+                SetMode::Replace
+            )
+            .is_err());
+        assert!(config.profiles["default"].extends.is_empty());
+    }
+
+    #[test]
+    fn test_machine_roles_are_checked_and_follow_renames() {
+        let content = "[profiles.base]\n[profiles.work]\n\n[machines]\nstudio = { profiles = [\"base\", \"work\"], secrets = false }\n";
+        let mut config = Config::parse_str(content).unwrap();
+        assert_eq!(
+            config.machines["studio"].describe(),
+            "base, work (no secrets)"
+        );
+        assert!(Config::validate_str(content).errors.is_empty());
+
+        config.rename_profile("work", "office").unwrap();
+        assert_eq!(config.machines["studio"].profiles, ["base", "office"]);
+        assert_eq!(config.machines_using("office"), ["studio"]);
+
+        let missing = content.replace("\"work\"]", "\"gpu\"]");
+        let validation = Config::validate_str(&missing);
+        assert!(validation.errors[0]
+            .message
+            .contains("Machine role 'studio' uses unknown profile 'gpu'"));
+        let empty = content.replace("[\"base\", \"work\"]", "[]");
+        assert!(!Config::validate_str(&empty).errors.is_empty());
+    }
+
+    #[test]
+    fn test_rename_profile_errors() {
+        let mut config = Config::create_default();
+        assert!(config.rename_profile("missing", "other").is_err());
+        assert!(config.rename_profile("work", "personal").is_err());
+        assert!(config.rename_profile("work", "bad name").is_err());
+        assert!(config.has_profile("work"));
+    }
+
+    #[test]
+    fn test_select_profiles_expands_globs_in_order() {
+        let mut config = Config::create_default();
+        config
+            .add_profile("work-laptop".to_string(), ProfileConfig::default())
+            .unwrap();
+        let names = |patterns: &[&str]| {
+            let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+            config.select_profiles(&patterns, false)
+        };
+        assert_eq!(names(&["work*"]).unwrap(), ["work", "work-laptop"]);
+        assert_eq!(
+            names(&["personal", "w*", "work"]).unwrap(),
+            ["personal", "work", "work-laptop"]
+        );
+        assert_eq!(
+            config.select_profiles(&[], true).unwrap(),
+            ["default", "personal", "work", "work-laptop"]
+        );
+        assert!(names(&["home*"]).is_err());
+        assert!(names(&["home"]).is_err());
+    }
+
+    #[test]
+    fn test_file_options_match_file_or_directory() {
+        let mut config = Config::create_default();
+        let sudo = FileOptions {
+            requires_sudo: true,
+            ..FileOptions::default()
+        };
+        let work = config.get_profile_mut("work").unwrap();
+        work.file_options
+            .insert("/etc/hosts".to_string(), sudo.clone());
+        work.file_options
+            .insert("/Library/LaunchDaemons".to_string(), sudo.clone());
+
+        assert_eq!(config.file_options("work", "/etc/hosts"), sudo);
+        assert_eq!(
+            config.file_options("work", "/Library/LaunchDaemons/local.backup.plist"),
+            sudo
+        );
+        assert_eq!(
+            config.file_options("work", "/Library/LaunchDaemonsOld/x.plist"),
+            FileOptions::default()
+        );
+        assert_eq!(
+            config.file_options("default", "/etc/hosts"),
+            FileOptions::default()
+        );
+        assert_eq!(storage_relative_path("/etc/hosts"), "etc/hosts");
+        assert_eq!(storage_relative_path(".zshrc"), ".zshrc");
+    }
+
+    #[test]
+    fn test_file_mode_prefers_permissions_override() {
+        let mut config = Config::create_default();
+        let profile = config.get_profile_mut("default").unwrap();
+        profile
+            .file_modes
+            .insert("~/.ssh/config".to_string(), "600".to_string());
+        profile
+            .file_modes
+            .insert("~/.zshrc".to_string(), "644".to_string());
+        profile.file_options.insert(
+            "~/.zshrc".to_string(),
+            FileOptions {
+                permissions: Some("0o640".to_string()),
+                ..FileOptions::default()
+            },
+        );
+
+        assert_eq!(config.file_mode("default", "~/.ssh/config"), Some(0o600));
+        assert_eq!(config.file_mode("default", "~/.zshrc"), Some(0o640));
+        assert_eq!(config.file_mode("default", "~/.gitconfig"), None);
+        assert_eq!(format_mode(0o100755), "755");
+        assert!(parse_mode("0644").is_ok());
+        assert!(parse_mode("rw-r--r--").is_err());
+        assert!(parse_mode("17777").is_err());
+
+        let content = "[global]\ndefault_profile = \"default\"\n\n[profiles.default]\nfiles = [\"~/.zshrc\"]\n\n[profiles.default.file_modes]\n\"~/.zshrc\" = \"999\"\n";
+        let validation = Config::validate_str(content);
+        assert!(validation.errors[0]
+            .message
+            .contains("Invalid permissions for '~/.zshrc'"));
+    }
 }
@@ -0,0 +1,441 @@
+//! Three-way merge of `ordinator.toml` for `ordinator pull`. Lists such as
+//! tracked files and Homebrew packages are union-merged; a key both sides set
+//! to different values is a conflict, settled by the pull strategy. The
+//! result is written over our version of the file, so comments and layout
+//! survive wherever the merge didn't change a value.
+
+use anyhow::{Context, Result};
+use toml::{Table, Value};
+use toml_edit::{DocumentMut, Item};
+
+/// The config file in the dotfiles repository that gets a semantic merge
+pub const CONFIG_FILE: &str = "ordinator.toml";
+
+/// How `ordinator pull` settles conflicting config values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Ask about each conflict
+    #[default]
+    Merge,
+
+    /// Keep this machine's value
+    Ours,
+
+    /// Take the pulled value
+    Theirs,
+}
+
+impl std::str::FromStr for MergeStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "merge" => Ok(MergeStrategy::Merge),
+            "ours" => Ok(MergeStrategy::Ours),
+            "theirs" => Ok(MergeStrategy::Theirs),
+            other => Err(anyhow::anyhow!(
+                "Unknown merge strategy '{}'. Use 'merge', 'ours', or 'theirs'.",
+                other
+            )),
+        }
+    }
+}
+
+/// A key both sides changed to different values. `None` means removed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// Table names down to the key, e.g. `["global", "default_profile"]`.
+    /// Kept apart because keys such as `file_modes` paths contain dots.
+    pub path: Vec<String>,
+    pub ours: Option<Value>,
+    pub theirs: Option<Value>,
+}
+
+impl Conflict {
+    /// Dotted key for messages, e.g. `global.default_profile`
+    pub fn key(&self) -> String {
+        self.path.join(".")
+    }
+}
+
+/// Merge `ours` and `theirs`, which both started from `base`. Returns the
+/// merged table, holding our value wherever there's a conflict, and the
+/// conflicts.
+pub fn merge(base: &Table, ours: &Table, theirs: &Table) -> (Table, Vec<Conflict>) {
+    let mut conflicts = Vec::new();
+    let merged = merge_tables(&[], base, ours, theirs, &mut conflicts);
+    (merged, conflicts)
+}
+
+/// Merge the three versions of the config file, settling conflicts with
+/// `strategy`. Returns the merged file contents, laid out like `ours`.
+pub fn merge_files(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    strategy: MergeStrategy,
+) -> Result<String> {
+    let parse = |content: &str, side: &str| {
+        toml::from_str::<Table>(content)
+            .with_context(|| format!("Failed to parse {side} version of {CONFIG_FILE}"))
+    };
+    let mut document: DocumentMut = ours
+        .parse()
+        .with_context(|| format!("Failed to parse the local version of {CONFIG_FILE}"))?;
+    let ours = parse(ours, "the local")?;
+    let (mut merged, conflicts) = merge(
+        &parse(base, "the common")?,
+        &ours,
+        &parse(theirs, "the pulled")?,
+    );
+    for conflict in &conflicts {
+        let value = match resolve(conflict, strategy)? {
+            Side::Ours => &conflict.ours,
+            Side::Theirs => &conflict.theirs,
+        };
+        set(&mut merged, &conflict.path, value.clone());
+    }
+
+    let mut next_position = last_position(document.as_table()) + 1;
+    update_item(document.as_item_mut(), &ours, &merged, &mut next_position)?;
+    let content = document.to_string();
+    if let Some(issue) = crate::config::Config::validate_str(&content).errors.first() {
+        return Err(anyhow::anyhow!(
+            "The merged {CONFIG_FILE} is not a valid config: {issue}"
+        ));
+    }
+    Ok(content)
+}
+
+enum Side {
+    Ours,
+    Theirs,
+}
+
+fn resolve(conflict: &Conflict, strategy: MergeStrategy) -> Result<Side> {
+    match strategy {
+        MergeStrategy::Ours => return Ok(Side::Ours),
+        MergeStrategy::Theirs => return Ok(Side::Theirs),
+        MergeStrategy::Merge => {}
+    }
+    // --yes can't pick a side; without a terminal the user has to
+    if !crate::prompt::interactive() {
+        return Err(anyhow::anyhow!(
+            "{CONFIG_FILE} has conflicting values for '{}'. Re-run with --strategy ours or --strategy theirs.",
+            conflict.key()
+        ));
+    }
+    eprintln!("Conflict in {CONFIG_FILE} at '{}':", conflict.key());
+    let options = [
+        format!("Keep ours:   {}", describe(&conflict.ours)),
+        format!("Take theirs: {}", describe(&conflict.theirs)),
+    ];
+    match crate::prompt::choose("Which value should be kept?", &options, 0) {
+        0 => Ok(Side::Ours),
+        _ => Ok(Side::Theirs),
+    }
+}
+
+fn describe(value: &Option<Value>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "(removed)".to_string(),
+    }
+}
+
+fn merge_tables(
+    prefix: &[String],
+    base: &Table,
+    ours: &Table,
+    theirs: &Table,
+    conflicts: &mut Vec<Conflict>,
+) -> Table {
+    let mut keys: Vec<&String> = ours.keys().collect();
+    keys.extend(theirs.keys().filter(|key| !ours.contains_key(*key)));
+
+    let mut merged = Table::new();
+    for key in keys {
+        let mut path = prefix.to_vec();
+        path.push(key.clone());
+        if let Some(value) = merge_values(
+            &path,
+            base.get(key),
+            ours.get(key),
+            theirs.get(key),
+            conflicts,
+        ) {
+            merged.insert(key.clone(), value);
+        }
+    }
+    merged
+}
+
+fn merge_values(
+    path: &[String],
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    conflicts: &mut Vec<Conflict>,
+) -> Option<Value> {
+    if ours == theirs || base == theirs {
+        return ours.cloned();
+    }
+    if base == ours {
+        return theirs.cloned();
+    }
+    let empty = Table::new();
+    match (ours, theirs) {
+        (Some(Value::Table(ours)), Some(Value::Table(theirs))) => {
+            let base = match base {
+                Some(Value::Table(base)) => base,
+                _ => &empty,
+            };
+            Some(Value::Table(merge_tables(
+                path, base, ours, theirs, conflicts,
+            )))
+        }
+        (Some(Value::Array(ours)), Some(Value::Array(theirs))) => {
+            let base = match base {
+                Some(Value::Array(base)) => base.as_slice(),
+                _ => &[],
+            };
+            Some(Value::Array(merge_arrays(base, ours, theirs)))
+        }
+        _ => {
+            conflicts.push(Conflict {
+                path: path.to_vec(),
+                ours: ours.cloned(),
+                theirs: theirs.cloned(),
+            });
+            ours.cloned()
+        }
+    }
+}
+
+/// Our items minus the ones they removed, then the ones they added
+fn merge_arrays(base: &[Value], ours: &[Value], theirs: &[Value]) -> Vec<Value> {
+    let mut merged: Vec<Value> = ours
+        .iter()
+        .filter(|item| !base.contains(item) || theirs.contains(item))
+        .cloned()
+        .collect();
+    for item in theirs {
+        if !base.contains(item) && !merged.contains(item) {
+            merged.push(item.clone());
+        }
+    }
+    merged
+}
+
+/// Set (or with `None`, remove) the value at `path` in `table`
+fn set(table: &mut Table, path: &[String], value: Option<Value>) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut table = table;
+    for segment in parents {
+        let Some(Value::Table(next)) = table.get_mut(segment) else {
+            return;
+        };
+        table = next;
+    }
+    match value {
+        Some(value) => {
+            table.insert(last.clone(), value);
+        }
+        None => {
+            table.remove(last);
+        }
+    }
+}
+
+/// Make `item`, a table parsed from our file, hold `merged`. `ours` is what
+/// it parsed to; only keys whose values differ from it are rewritten. Tables
+/// the merge adds are placed from `next_position` on.
+fn update_item(
+    item: &mut Item,
+    ours: &Table,
+    merged: &Table,
+    next_position: &mut usize,
+) -> Result<()> {
+    let inline = item.is_inline_table();
+    let Some(table) = item.as_table_like_mut() else {
+        return Ok(());
+    };
+    for key in ours.keys().filter(|key| !merged.contains_key(*key)) {
+        table.remove(key);
+    }
+    for (key, value) in merged {
+        let old = ours.get(key);
+        if old == Some(value) {
+            continue;
+        }
+        if let (Some(Value::Table(old)), Value::Table(new), Some(child)) =
+            (old, value, table.get_mut(key))
+        {
+            if child.is_table_like() {
+                update_item(child, old, new, next_position)?;
+                continue;
+            }
+        }
+        let mut new_item = to_item(key, value)?;
+        place_tables(&mut new_item, next_position);
+        if inline || table.get(key).is_some_and(Item::is_value) {
+            new_item = Item::Value(new_item.into_value().map_err(|_| {
+                anyhow::anyhow!("Failed to write the merged value of '{key}' in {CONFIG_FILE}")
+            })?);
+        }
+        // A changed value keeps its key's comments and the comment after it
+        match table.get_mut(key) {
+            Some(slot) => {
+                if let (Item::Value(old), Item::Value(new)) = (&*slot, &mut new_item) {
+                    *new.decor_mut() = old.decor().clone();
+                }
+                *slot = new_item;
+            }
+            None => {
+                table.insert(key, new_item);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Number the tables in a new item from `next_position`, so they follow the
+/// file's existing tables, each after a blank line
+fn place_tables(item: &mut Item, next_position: &mut usize) {
+    let Item::Table(table) = item else {
+        return;
+    };
+    table.set_position(*next_position);
+    *next_position += 1;
+    if !table.is_implicit() {
+        table.decor_mut().set_prefix("\n");
+    }
+    for (_, child) in table.iter_mut() {
+        place_tables(child, next_position);
+    }
+}
+
+/// The highest position of any table in `table`
+fn last_position(table: &toml_edit::Table) -> usize {
+    table
+        .iter()
+        .filter_map(|(_, item)| item.as_table())
+        .map(|child| child.position().unwrap_or(0).max(last_position(child)))
+        .max()
+        .unwrap_or(0)
+}
+
+/// `value` as an item to store under `key`, formatted as `toml` would write it
+fn to_item(key: &str, value: &Value) -> Result<Item> {
+    let mut wrapper = Table::new();
+    wrapper.insert(key.to_string(), value.clone());
+    let mut document: DocumentMut = toml::to_string_pretty(&wrapper)?.parse()?;
+    Ok(document.remove(key).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(content: &str) -> Table {
+        toml::from_str(content).unwrap()
+    }
+
+    #[test]
+    fn test_lists_are_union_merged() {
+        let base = table("[profiles.default]\nfiles = [\"~/.zshrc\", \"~/.vimrc\"]\n");
+        let ours =
+            table("[profiles.default]\nfiles = [\"~/.zshrc\", \"~/.vimrc\", \"~/.tmux.conf\"]\n");
+        let theirs = table("[profiles.default]\nfiles = [\"~/.zshrc\", \"~/.gitconfig\"]\n");
+        let (merged, conflicts) = merge(&base, &ours, &theirs);
+        assert!(conflicts.is_empty());
+        assert_eq!(
+            merged["profiles"]["default"]["files"],
+            Value::Array(vec![
+                "~/.zshrc".into(),
+                "~/.tmux.conf".into(),
+                "~/.gitconfig".into()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_different_values_conflict() {
+        let base = table("[global]\ndefault_profile = \"default\"\n[profiles.work]\n");
+        let ours = table("[global]\ndefault_profile = \"work\"\n[profiles.work]\n");
+        let theirs = table(
+            "[global]\ndefault_profile = \"personal\"\n[profiles.work]\n[profiles.personal]\n",
+        );
+        let (merged, conflicts) = merge(&base, &ours, &theirs);
+        assert_eq!(
+            conflicts,
+            vec![Conflict {
+                path: vec!["global".to_string(), "default_profile".to_string()],
+                ours: Some("work".into()),
+                theirs: Some("personal".into()),
+            }]
+        );
+        assert_eq!(merged["global"]["default_profile"], Value::from("work"));
+        assert!(merged["profiles"].get("personal").is_some());
+
+        assert_eq!(conflicts[0].key(), "global.default_profile");
+        let mut resolved = merged.clone();
+        set(&mut resolved, &conflicts[0].path, Some("personal".into()));
+        assert_eq!(
+            resolved["global"]["default_profile"],
+            Value::from("personal")
+        );
+        set(&mut resolved, &conflicts[0].path, None);
+        assert!(resolved["global"].get("default_profile").is_none());
+    }
+
+    #[test]
+    fn test_one_sided_changes_and_removals_merge_cleanly() {
+        let base = table("[global]\nauto_push = false\n[profiles.work]\n[profiles.old]\n");
+        let ours = table("[global]\nauto_push = true\n[profiles.work]\n[profiles.old]\n");
+        let theirs = table("[global]\nauto_push = false\n[profiles.work]\n");
+        let (merged, conflicts) = merge(&base, &ours, &theirs);
+        assert!(conflicts.is_empty());
+        assert_eq!(merged["global"]["auto_push"], Value::Boolean(true));
+        assert!(merged["profiles"].get("old").is_none());
+    }
+
+    #[test]
+    fn test_merge_files_keeps_comments_and_layout() {
+        let base = "# My dotfiles\n[global]\ndefault_profile = \"default\" # this machine\nauto_push = false\n\n[profiles.default]\n# Shell first\nfiles = [\"~/.zshrc\"]\n";
+        let ours = "# My dotfiles\n[global]\ndefault_profile = \"work\" # this machine\nauto_push = false\n\n[profiles.default]\n# Shell first\nfiles = [\"~/.zshrc\"]\n";
+        let theirs = "[global]\ndefault_profile = \"default\"\nauto_push = true\n\n[profiles.default]\nfiles = [\"~/.zshrc\", \"~/.vimrc\"]\n\n[profiles.personal]\nfiles = []\n";
+        let merged = merge_files(base, ours, theirs, MergeStrategy::Ours).unwrap();
+        assert!(merged.starts_with("# My dotfiles\n[global]\ndefault_profile = \"work\" # this machine\nauto_push = true\n"));
+        assert!(merged.contains("\n[profiles.default]\n# Shell first\nfiles = ["));
+        assert!(merged.contains("\"~/.vimrc\""));
+        assert!(merged.ends_with("\n\n[profiles.personal]\nfiles = []\n"));
+
+        let merged: Table = toml::from_str(&merged).unwrap();
+        assert_eq!(merged["global"]["auto_push"], Value::Boolean(true));
+        assert_eq!(
+            merged["profiles"]["default"]["files"],
+            Value::Array(vec!["~/.zshrc".into(), "~/.vimrc".into()])
+        );
+    }
+
+    #[test]
+    fn test_conflict_without_a_terminal_fails_even_with_yes() {
+        let _settings = crate::prompt::TEST_SETTINGS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        crate::prompt::set_no_input(true);
+        crate::prompt::set_assume_yes(true);
+        let base = "[global]\ndefault_profile = \"default\"\n";
+        let ours = "[global]\ndefault_profile = \"work\"\n";
+        let theirs = "[global]\ndefault_profile = \"personal\"\n";
+        let result = merge_files(base, ours, theirs, MergeStrategy::Merge);
+        crate::prompt::set_assume_yes(false);
+        crate::prompt::set_no_input(false);
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("conflicting values for 'global.default_profile'"));
+        assert!(error.contains("Re-run with --strategy ours or --strategy theirs."));
+    }
+}
@@ -0,0 +1,402 @@
+//! `ordinator daemon`: a launchd agent that runs `ordinator daemon run` every
+//! `[daemon] interval_minutes`, and on file changes with `watch_files`. Each
+//! run commits local changes, pulls, pushes, and can apply a profile.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::info;
+
+use crate::commit::CommitOutcome;
+use crate::config::Config;
+use crate::environment::{self, Effect};
+use crate::error::OrdinatorError;
+use crate::git::GitManager;
+
+/// launchd label for the sync agent
+pub const DAEMON_LABEL: &str = "com.ordinator.sync";
+
+/// Configuration for the background sync agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DaemonConfig {
+    /// Minutes between sync runs
+    #[serde(default = "default_interval_minutes")]
+    pub interval_minutes: u64,
+
+    /// Commit message for auto-commits; supports {hostname}, {date}, and {profile}
+    #[serde(default = "default_commit_message_template")]
+    pub commit_message_template: String,
+
+    /// Also run a sync whenever a tracked file changes (launchd WatchPaths)
+    #[serde(default)]
+    pub watch_files: bool,
+
+    /// Push auto-commits to the remote
+    #[serde(default = "default_push")]
+    pub push: bool,
+
+    /// Profile to apply after pulling (no apply when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apply_profile: Option<String>,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            interval_minutes: default_interval_minutes(),
+            commit_message_template: default_commit_message_template(),
+            watch_files: false,
+            push: default_push(),
+            apply_profile: None,
+        }
+    }
+}
+
+fn default_interval_minutes() -> u64 {
+    30
+}
+
+fn default_commit_message_template() -> String {
+    "Auto-sync from {hostname} at {date}".to_string()
+}
+
+fn default_push() -> bool {
+    true
+}
+
+/// Manager for the launchd agent that keeps dotfiles in sync
+pub struct DaemonManager {
+    dry_run: bool,
+}
+
+impl DaemonManager {
+    /// Create a new daemon manager
+    pub fn new(dry_run: bool) -> Self {
+        Self { dry_run }
+    }
+
+    /// Path of the launch agent plist
+    pub fn plist_path() -> Result<PathBuf> {
+        Ok(crate::utils::get_home_dir()?
+            .join("Library")
+            .join("LaunchAgents")
+            .join(format!("{DAEMON_LABEL}.plist")))
+    }
+
+    /// Path of the log file the agent writes to
+    pub fn log_path() -> Result<PathBuf> {
+        Ok(crate::utils::get_home_dir()?
+            .join("Library")
+            .join("Logs")
+            .join("ordinator-sync.log"))
+    }
+
+    /// Whether the launch agent plist is installed
+    pub fn is_installed() -> Result<bool> {
+        Ok(Self::plist_path()?.exists())
+    }
+
+    /// Write the launch agent plist and load it with launchctl
+    pub fn install(&self, config: &Config, config_path: &Path) -> Result<PathBuf> {
+        let plist_path = Self::plist_path()?;
+        if self.dry_run {
+            info!(
+                "[DRY RUN] Would install launch agent at {}",
+                plist_path.display()
+            );
+            return Ok(plist_path);
+        }
+
+        let exe = std::env::current_exe().context("Failed to locate ordinator executable")?;
+        let watch_paths = if config.daemon.watch_files {
+            watched_paths(config)?
+        } else {
+            Vec::new()
+        };
+        let content = create_plist_content(
+            &exe,
+            config_path,
+            config.daemon.interval_minutes,
+            &watch_paths,
+            &Self::log_path()?,
+        );
+
+        if let Some(parent) = plist_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if let Some(parent) = Self::log_path()?.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Reload so an existing agent picks up the new interval
+        if plist_path.exists() {
+            crate::launchd::launchctl("unload", &plist_path)?;
+        }
+        fs::write(&plist_path, content)
+            .with_context(|| format!("Failed to write {}", plist_path.display()))?;
+        crate::launchd::launchctl("load", &plist_path)?;
+
+        info!("Installed launch agent at {}", plist_path.display());
+        Ok(plist_path)
+    }
+
+    /// Unload and remove the launch agent; returns false if it was not installed
+    pub fn uninstall(&self) -> Result<bool> {
+        let plist_path = Self::plist_path()?;
+        if !plist_path.exists() {
+            return Ok(false);
+        }
+        if self.dry_run {
+            info!(
+                "[DRY RUN] Would remove launch agent at {}",
+                plist_path.display()
+            );
+            return Ok(true);
+        }
+
+        crate::launchd::launchctl("unload", &plist_path)?;
+        fs::remove_file(&plist_path)
+            .with_context(|| format!("Failed to remove {}", plist_path.display()))?;
+        info!("Removed launch agent at {}", plist_path.display());
+        Ok(true)
+    }
+
+    /// Whether launchd currently has the agent loaded
+    pub fn is_loaded() -> bool {
+        environment::service(
+            Command::new("launchctl").args(["list", DAEMON_LABEL]),
+            Effect::Reads,
+        )
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+    }
+
+    /// Run one sync cycle: commit local changes, pull, push, and optionally
+    /// apply. Commits and pushes run their hooks like any other. Local changes
+    /// the secrets scan objects to are neither committed nor pushed.
+    pub async fn run_once(&self, config: &Config, dotfiles_path: &Path) -> Result<SyncSummary> {
+        let git_manager =
+            GitManager::new(dotfiles_path.to_path_buf()).with_config(config.git.clone());
+        if !git_manager.exists() {
+            return Err(OrdinatorError::RepositoryNotFound.into());
+        }
+
+        let mut summary = SyncSummary::default();
+        let profile = config
+            .daemon
+            .apply_profile
+            .as_deref()
+            .unwrap_or(config.default_profile());
+
+        let message = render_commit_message(&config.daemon.commit_message_template, profile);
+        match crate::commit::commit_all(config, &git_manager, dotfiles_path, &message, self.dry_run)
+            .await?
+        {
+            CommitOutcome::Clean => {}
+            CommitOutcome::HeldBack(scan) => {
+                let mut files: Vec<String> = scan
+                    .findings
+                    .into_iter()
+                    .map(|finding| finding.file)
+                    .chain(scan.timed_out)
+                    .collect();
+                files.sort();
+                files.dedup();
+                summary.held_back = files;
+            }
+            CommitOutcome::Committed => summary.committed = Some(message),
+        }
+
+        if self.dry_run {
+            info!("[DRY RUN] Would pull and push");
+            return Ok(summary);
+        }
+
+        git_manager.pull(false, None)?;
+        summary.pulled = true;
+
+        if config.daemon.push
+            && summary.held_back.is_empty()
+            && git_manager.get_origin_url()?.is_some()
+        {
+            crate::push::push(
+                config,
+                &git_manager,
+                dotfiles_path,
+                config.git.push_all_remotes,
+                false,
+                None,
+                true,
+            )?;
+            summary.pushed = true;
+        }
+
+        if let Some(apply_profile) = &config.daemon.apply_profile {
+            let exe = std::env::current_exe().context("Failed to locate ordinator executable")?;
+            let status = Command::new(exe)
+                .args(["apply", "--profile", apply_profile, "--skip-bootstrap"])
+                .status()
+                .context("Failed to run ordinator apply")?;
+            if !status.success() {
+                return Err(anyhow::anyhow!(
+                    "Applying profile '{apply_profile}' failed during sync"
+                ));
+            }
+            summary.applied = Some(apply_profile.clone());
+        }
+
+        Ok(summary)
+    }
+}
+
+/// What a single sync cycle did
+#[derive(Debug, Default)]
+pub struct SyncSummary {
+    pub committed: Option<String>,
+
+    /// Files whose plaintext secrets, or unfinished secrets scan, kept local
+    /// changes from being committed and pushed
+    pub held_back: Vec<String>,
+
+    pub pulled: bool,
+    pub pushed: bool,
+    pub applied: Option<String>,
+}
+
+/// Expand `{hostname}`, `{date}`, and `{profile}` in a commit message template
+pub fn render_commit_message(template: &str, profile: &str) -> String {
+    let hostname = crate::machine::host_name().unwrap_or_else(|| "unknown-host".to_string());
+    let date = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    template
+        .replace("{hostname}", &hostname)
+        .replace("{date}", &date)
+        .replace("{profile}", profile)
+}
+
+/// Home directory paths of every tracked file, for launchd WatchPaths
+fn watched_paths(config: &Config) -> Result<Vec<PathBuf>> {
+    let home_dir = config.target_root()?;
+    let mut paths: Vec<PathBuf> = config
+        .profiles
+        .values()
+        .flat_map(|profile| profile.files.iter())
+        .map(|file| crate::paths::destination(&home_dir, file))
+        .collect();
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Build the launch agent plist that runs `ordinator daemon run`
+fn create_plist_content(
+    exe: &Path,
+    config_path: &Path,
+    interval_minutes: u64,
+    watch_paths: &[PathBuf],
+    log_path: &Path,
+) -> String {
+    let string =
+        |value: &Path| format!("<string>{}</string>", xml_escape(&value.to_string_lossy()));
+
+    let mut plist = String::new();
+    plist.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    plist.push_str("<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n");
+    plist.push_str("<plist version=\"1.0\">\n<dict>\n");
+    plist.push_str(&format!(
+        "  <key>Label</key>\n  <string>{DAEMON_LABEL}</string>\n"
+    ));
+    plist.push_str("  <key>ProgramArguments</key>\n  <array>\n");
+    plist.push_str(&format!("    {}\n", string(exe)));
+    plist.push_str("    <string>daemon</string>\n    <string>run</string>\n  </array>\n");
+    plist.push_str("  <key>EnvironmentVariables</key>\n  <dict>\n");
+    plist.push_str(&format!(
+        "    <key>ORDINATOR_CONFIG</key>\n    {}\n",
+        string(config_path)
+    ));
+    plist.push_str("  </dict>\n");
+    plist.push_str(&format!(
+        "  <key>StartInterval</key>\n  <integer>{}</integer>\n",
+        interval_minutes.max(1) * 60
+    ));
+    if !watch_paths.is_empty() {
+        plist.push_str("  <key>WatchPaths</key>\n  <array>\n");
+        for path in watch_paths {
+            plist.push_str(&format!("    {}\n", string(path)));
+        }
+        plist.push_str("  </array>\n");
+    }
+    plist.push_str("  <key>RunAtLoad</key>\n  <true/>\n");
+    plist.push_str(&format!(
+        "  <key>StandardOutPath</key>\n  {}\n",
+        string(log_path)
+    ));
+    plist.push_str(&format!(
+        "  <key>StandardErrorPath</key>\n  {}\n",
+        string(log_path)
+    ));
+    plist.push_str("</dict>\n</plist>\n");
+    plist
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_plist_content() {
+        let plist = create_plist_content(
+            Path::new("/usr/local/bin/ordinator"),
+            Path::new("/Users/me/.dotfiles/ordinator.toml"),
+            15,
+            &[PathBuf::from("/Users/me/.zshrc")],
+            Path::new("/Users/me/Library/Logs/ordinator-sync.log"),
+        );
+        assert!(plist.contains("<string>com.ordinator.sync</string>"));
+        assert!(plist.contains("<string>/usr/local/bin/ordinator</string>"));
+        assert!(plist.contains("<string>daemon</string>\n    <string>run</string>"));
+        assert!(plist.contains("<integer>900</integer>"));
+        assert!(plist.contains("<key>WatchPaths</key>"));
+        assert!(plist.contains("<string>/Users/me/.zshrc</string>"));
+    }
+
+    #[test]
+    fn test_create_plist_content_without_watch_paths() {
+        let plist = create_plist_content(
+            Path::new("/bin/ordinator"),
+            Path::new("/tmp/a&b.toml"),
+            0,
+            &[],
+            Path::new("/tmp/sync.log"),
+        );
+        assert!(!plist.contains("WatchPaths"));
+        // Interval is clamped to at least one minute
+        assert!(plist.contains("<integer>60</integer>"));
+        assert!(plist.contains("/tmp/a&amp;b.toml"));
+    }
+
+    #[test]
+    fn test_render_commit_message() {
+        let message = render_commit_message("Sync {profile} on {hostname} at {date}", "work");
+        assert!(message.starts_with("Sync work on "));
+        assert!(!message.contains('{'));
+    }
+
+    #[test]
+    fn test_daemon_config_defaults() {
+        let config: DaemonConfig = toml::from_str("").unwrap();
+        assert_eq!(config.interval_minutes, 30);
+        assert!(config.push);
+        assert!(!config.watch_files);
+        assert!(config.apply_profile.is_none());
+    }
+}
@@ -0,0 +1,328 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::process::Command;
+use tracing::{info, warn};
+
+use crate::environment::{self, Effect};
+
+/// Keys and values captured for one `defaults` domain
+pub type DomainSettings = BTreeMap<String, DefaultsValue>;
+
+/// A scalar `defaults` value. Arrays, dictionaries, dates, and data are not managed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DefaultsValue {
+    /// Written with `-bool`
+    Bool(bool),
+
+    /// Written with `-int`
+    Int(i64),
+
+    /// Written with `-float`
+    Float(f64),
+
+    /// Written with `-string`
+    String(String),
+}
+
+impl DefaultsValue {
+    /// Type flag passed to `defaults write`
+    pub fn type_flag(&self) -> &'static str {
+        match self {
+            DefaultsValue::Bool(_) => "-bool",
+            DefaultsValue::Int(_) => "-int",
+            DefaultsValue::Float(_) => "-float",
+            DefaultsValue::String(_) => "-string",
+        }
+    }
+
+    fn from_plist(value: &plist::Value) -> Option<Self> {
+        match value {
+            plist::Value::Boolean(b) => Some(DefaultsValue::Bool(*b)),
+            plist::Value::Integer(i) => i.as_signed().map(DefaultsValue::Int),
+            plist::Value::Real(f) => Some(DefaultsValue::Float(*f)),
+            plist::Value::String(s) => Some(DefaultsValue::String(s.clone())),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DefaultsValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DefaultsValue::Bool(b) => write!(f, "{b}"),
+            DefaultsValue::Int(i) => write!(f, "{i}"),
+            DefaultsValue::Float(x) => write!(f, "{x}"),
+            DefaultsValue::String(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+/// A key whose live value differs from the profile config
+#[derive(Debug, Clone, PartialEq)]
+pub struct DefaultsChange {
+    /// Domain, e.g. `com.apple.dock`
+    pub domain: String,
+
+    /// Key within the domain
+    pub key: String,
+
+    /// Value from the profile config
+    pub desired: DefaultsValue,
+
+    /// Live value, if the key is set
+    pub current: Option<DefaultsValue>,
+}
+
+/// Parse the XML plist printed by `defaults export <domain> -`.
+/// Returns the scalar settings and the keys that were skipped because their
+/// values are arrays, dictionaries, dates, or data.
+pub fn parse_domain_plist(content: &[u8]) -> Result<(DomainSettings, Vec<String>)> {
+    let value = plist::Value::from_reader(std::io::Cursor::new(content))
+        .context("Failed to parse defaults plist")?;
+    let dict = value
+        .as_dictionary()
+        .ok_or_else(|| anyhow::anyhow!("Expected a dictionary at the top of the defaults plist"))?;
+
+    let mut settings = DomainSettings::new();
+    let mut skipped = Vec::new();
+    for (key, value) in dict {
+        match DefaultsValue::from_plist(value) {
+            Some(value) => {
+                settings.insert(key.clone(), value);
+            }
+            None => skipped.push(key.clone()),
+        }
+    }
+    Ok((settings, skipped))
+}
+
+/// Compare desired settings against live ones
+pub fn compute_changes(
+    domain: &str,
+    desired: &DomainSettings,
+    current: &DomainSettings,
+) -> Vec<DefaultsChange> {
+    desired
+        .iter()
+        .filter(|(key, value)| current.get(*key) != Some(*value))
+        .map(|(key, value)| DefaultsChange {
+            domain: domain.to_string(),
+            key: key.clone(),
+            desired: value.clone(),
+            current: current.get(key).cloned(),
+        })
+        .collect()
+}
+
+/// Process to restart so changes in a domain take effect
+fn process_for_domain(domain: &str) -> Option<&'static str> {
+    match domain {
+        "com.apple.dock" => Some("Dock"),
+        "com.apple.finder" => Some("Finder"),
+        "com.apple.SystemUIServer" | "com.apple.menuextra.clock" => Some("SystemUIServer"),
+        _ => None,
+    }
+}
+
+pub struct DefaultsManager {
+    dry_run: bool,
+}
+
+impl DefaultsManager {
+    /// Under `--simulate` the writes are built and shown by the environment
+    /// rather than skipped
+    pub fn new(dry_run: bool) -> Self {
+        Self {
+            dry_run: dry_run && !environment::current().simulated(),
+        }
+    }
+
+    /// Whether the macOS `defaults` tool is available
+    pub fn is_available() -> bool {
+        which::which("defaults").is_ok()
+    }
+
+    fn ensure_available() -> Result<()> {
+        if !Self::is_available() {
+            return Err(anyhow::anyhow!(
+                "The 'defaults' command was not found. macOS defaults can only be managed on macOS."
+            ));
+        }
+        Ok(())
+    }
+
+    /// Read the scalar settings of a domain, plus the keys that were skipped
+    pub fn read_domain(&self, domain: &str) -> Result<(DomainSettings, Vec<String>)> {
+        Self::ensure_available()?;
+        info!("Reading defaults domain: {}", domain);
+        let output = environment::output(
+            Command::new("defaults").args(["export", domain, "-"]),
+            Effect::Reads,
+        )
+        .with_context(|| format!("Failed to run defaults export {domain}"))?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "defaults export {} failed: {}",
+                domain,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        parse_domain_plist(&output.stdout)
+            .with_context(|| format!("Failed to read defaults domain {domain}"))
+    }
+
+    /// List every configured key whose live value differs
+    pub fn diff(&self, domains: &BTreeMap<String, DomainSettings>) -> Result<Vec<DefaultsChange>> {
+        let mut changes = Vec::new();
+        for (domain, desired) in domains {
+            let (current, _) = self.read_domain(domain)?;
+            changes.extend(compute_changes(domain, desired, &current));
+        }
+        Ok(changes)
+    }
+
+    /// Write every configured key whose live value differs, then restart the
+    /// affected system processes (Dock, Finder, ...). Returns the changes made
+    /// (or, in dry-run mode, the changes that would be made).
+    pub fn apply(&self, domains: &BTreeMap<String, DomainSettings>) -> Result<Vec<DefaultsChange>> {
+        let changes = self.diff(domains)?;
+        for change in &changes {
+            if self.dry_run {
+                println!(
+                    "[DRY-RUN] Would run: defaults write {} {} {} {}",
+                    change.domain,
+                    change.key,
+                    change.desired.type_flag(),
+                    change.desired
+                );
+                continue;
+            }
+            self.write(change)?;
+        }
+
+        if !self.dry_run {
+            let mut restarted = Vec::new();
+            for change in &changes {
+                if let Some(process) = process_for_domain(&change.domain) {
+                    if !restarted.contains(&process) {
+                        self.restart(process);
+                        restarted.push(process);
+                    }
+                }
+            }
+        }
+        Ok(changes)
+    }
+
+    fn write(&self, change: &DefaultsChange) -> Result<()> {
+        info!(
+            "defaults write {} {} {} {}",
+            change.domain,
+            change.key,
+            change.desired.type_flag(),
+            change.desired
+        );
+        let output = environment::output(
+            Command::new("defaults")
+                .args(["write", &change.domain, &change.key])
+                .arg(change.desired.type_flag())
+                .arg(change.desired.to_string()),
+            Effect::Changes,
+        )
+        .with_context(|| format!("Failed to run defaults write {}", change.domain))?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "defaults write {} {} failed: {}",
+                change.domain,
+                change.key,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    fn restart(&self, process: &str) {
+        // The process is relaunched automatically by launchd
+        if let Err(e) = environment::service(Command::new("killall").arg(process), Effect::Changes)
+        {
+            warn!("Failed to restart {}: {}", process, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCK_PLIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>autohide</key>
+	<true/>
+	<key>tilesize</key>
+	<integer>48</integer>
+	<key>autohide-delay</key>
+	<real>0.25</real>
+	<key>orientation</key>
+	<string>left</string>
+	<key>persistent-apps</key>
+	<array/>
+</dict>
+</plist>
+"#;
+
+    #[test]
+    fn test_parse_domain_plist() {
+        let (settings, skipped) = parse_domain_plist(DOCK_PLIST.as_bytes()).unwrap();
+        assert_eq!(settings.get("autohide"), Some(&DefaultsValue::Bool(true)));
+        assert_eq!(settings.get("tilesize"), Some(&DefaultsValue::Int(48)));
+        assert_eq!(
+            settings.get("autohide-delay"),
+            Some(&DefaultsValue::Float(0.25))
+        );
+        assert_eq!(
+            settings.get("orientation"),
+            Some(&DefaultsValue::String("left".to_string()))
+        );
+        assert_eq!(skipped, vec!["persistent-apps".to_string()]);
+    }
+
+    #[test]
+    fn test_compute_changes() {
+        let desired = DomainSettings::from([
+            ("autohide".to_string(), DefaultsValue::Bool(true)),
+            ("tilesize".to_string(), DefaultsValue::Int(36)),
+            ("magnification".to_string(), DefaultsValue::Bool(false)),
+        ]);
+        let current = DomainSettings::from([
+            ("autohide".to_string(), DefaultsValue::Bool(true)),
+            ("tilesize".to_string(), DefaultsValue::Int(48)),
+        ]);
+        let changes = compute_changes("com.apple.dock", &desired, &current);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].key, "magnification");
+        assert_eq!(changes[0].current, None);
+        assert_eq!(changes[1].key, "tilesize");
+        assert_eq!(changes[1].current, Some(DefaultsValue::Int(48)));
+    }
+
+    #[test]
+    fn test_defaults_value_toml_roundtrip() {
+        let settings = DomainSettings::from([
+            ("autohide".to_string(), DefaultsValue::Bool(true)),
+            ("tilesize".to_string(), DefaultsValue::Int(48)),
+            ("delay".to_string(), DefaultsValue::Float(0.5)),
+            (
+                "orientation".to_string(),
+                DefaultsValue::String("left".into()),
+            ),
+        ]);
+        let toml = toml::to_string(&settings).unwrap();
+        let parsed: DomainSettings = toml::from_str(&toml).unwrap();
+        assert_eq!(parsed, settings);
+    }
+}
@@ -0,0 +1,62 @@
+use similar::{ChangeTag, TextDiff};
+
+/// Line counts for a single file's changes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffStat {
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// Render a unified diff (3 lines of context) from `old` to `new`
+pub fn unified_diff(old_label: &str, new_label: &str, old: &str, new: &str) -> String {
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(old_label, new_label)
+        .to_string()
+}
+
+/// Count inserted and deleted lines between `old` and `new`
+pub fn diff_stat(old: &str, new: &str) -> DiffStat {
+    let mut stat = DiffStat::default();
+    for change in TextDiff::from_lines(old, new).iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Insert => stat.insertions += 1,
+            ChangeTag::Delete => stat.deletions += 1,
+            ChangeTag::Equal => {}
+        }
+    }
+    stat
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff_identical() {
+        assert!(unified_diff("a", "b", "same\n", "same\n").is_empty());
+    }
+
+    #[test]
+    fn test_unified_diff_changes() {
+        let diff = unified_diff("tracked", "live", "one\ntwo\n", "one\nthree\n");
+        assert!(diff.contains("--- tracked"));
+        assert!(diff.contains("+++ live"));
+        assert!(diff.contains("-two"));
+        assert!(diff.contains("+three"));
+    }
+
+    #[test]
+    fn test_diff_stat() {
+        let stat = diff_stat("one\ntwo\n", "one\nthree\nfour\n");
+        assert_eq!(
+            stat,
+            DiffStat {
+                insertions: 2,
+                deletions: 1
+            }
+        );
+        assert_eq!(diff_stat("x\n", "x\n"), DiffStat::default());
+    }
+}
@@ -0,0 +1,423 @@
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::GitignoreBuilder;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::utils::generate_file_hash;
+
+/// Gitignore-style file, at the repository root or anywhere inside a tracked
+/// directory, naming files directory tracking skips
+pub const IGNORE_FILE: &str = ".ordinatorignore";
+
+/// How `ordinator apply` links a tracked directory into the home directory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DirectoryLink {
+    /// Symlink each tracked file, leaving untracked files in the directory alone
+    #[default]
+    Files,
+
+    /// Replace the whole directory with a single symlink
+    Directory,
+}
+
+impl std::str::FromStr for DirectoryLink {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "files" => Ok(DirectoryLink::Files),
+            "directory" => Ok(DirectoryLink::Directory),
+            other => Err(anyhow::anyhow!(
+                "Unknown link mode '{}'. Use 'files' or 'directory'.",
+                other
+            )),
+        }
+    }
+}
+
+/// A directory tracked by a profile. In `ordinator.toml` this is either a
+/// plain path or a table with `path`, `include`, `exclude`, and `link`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "DirectoryEntry")]
+pub struct TrackedDirectory {
+    /// Directory path, as given to `ordinator watch`
+    pub path: String,
+
+    /// Glob patterns (relative to the directory) a file must match to be tracked; empty tracks everything
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+
+    /// Glob patterns (relative to the directory) for files that are never tracked
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+
+    /// Whether apply symlinks each file or the whole directory
+    #[serde(default)]
+    pub link: DirectoryLink,
+}
+
+/// Accepted TOML forms for a `directories` entry
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DirectoryEntry {
+    Path(String),
+    Table {
+        path: String,
+        #[serde(default)]
+        include: Vec<String>,
+        #[serde(default)]
+        exclude: Vec<String>,
+        #[serde(default)]
+        link: DirectoryLink,
+    },
+}
+
+impl From<DirectoryEntry> for TrackedDirectory {
+    fn from(entry: DirectoryEntry) -> Self {
+        match entry {
+            DirectoryEntry::Path(path) => TrackedDirectory::new(&path),
+            DirectoryEntry::Table {
+                path,
+                include,
+                exclude,
+                link,
+            } => TrackedDirectory {
+                include,
+                exclude,
+                link,
+                ..TrackedDirectory::new(&path)
+            },
+        }
+    }
+}
+
+impl TrackedDirectory {
+    /// Track every file under `path`, linking files individually
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: normalize_path(path),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            link: DirectoryLink::Files,
+        }
+    }
+
+    /// Name of the directory's copy under `files/<profile>/`
+    pub fn storage_name(&self) -> String {
+        let name = Path::new(&self.path)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        format!("{}_{}", generate_file_hash(&self.path), name)
+    }
+
+    /// Build the file filter for this directory. `extra_exclude` holds the
+    /// global and profile exclusion patterns.
+    pub fn filter(&self, extra_exclude: &GlobSet) -> Result<DirectoryFilter> {
+        let include = if self.include.is_empty() {
+            None
+        } else {
+            Some(build_glob_set(&self.include)?)
+        };
+        Ok(DirectoryFilter {
+            include,
+            exclude: build_glob_set(&self.exclude)?,
+            extra_exclude: extra_exclude.clone(),
+            repo_ignore: None,
+        })
+    }
+}
+
+/// Strip trailing slashes so `nvim/` and `nvim` are the same entry
+pub fn normalize_path(path: &str) -> String {
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() {
+        path.to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder
+            .add(Glob::new(pattern).with_context(|| format!("Invalid glob pattern '{pattern}'"))?);
+    }
+    Ok(builder.build()?)
+}
+
+/// Decides which files inside a tracked directory are tracked
+pub struct DirectoryFilter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    extra_exclude: GlobSet,
+    repo_ignore: Option<PathBuf>,
+}
+
+impl DirectoryFilter {
+    /// Also skip what the `.ordinatorignore` at `path` names. Its patterns
+    /// apply inside every tracked directory, relative to the directory.
+    pub fn with_repo_ignore(mut self, path: PathBuf) -> Self {
+        self.repo_ignore = path.is_file().then_some(path);
+        self
+    }
+
+    /// Whether a path relative to the directory root is tracked
+    pub fn matches(&self, relative: &Path) -> bool {
+        if let Some(include) = &self.include {
+            if !include.is_match(relative) {
+                return false;
+            }
+        }
+        if self.exclude.is_match(relative) || self.extra_exclude.is_match(relative) {
+            return false;
+        }
+        // Global patterns such as "*.bak" are written against file names
+        !relative
+            .file_name()
+            .is_some_and(|name| self.extra_exclude.is_match(name))
+    }
+}
+
+/// List the files under `root` (relative to it) that pass the filter, sorted.
+/// `.ordinatorignore` files inside `root` are honored like `.gitignore`.
+pub fn list_files(root: &Path, filter: &DirectoryFilter) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if !root.is_dir() {
+        return Ok(files);
+    }
+    let mut repo_ignore = GitignoreBuilder::new(root);
+    if let Some(path) = &filter.repo_ignore {
+        if let Some(e) = repo_ignore.add(path) {
+            return Err(anyhow::anyhow!("Invalid {}: {e}", path.display()));
+        }
+    }
+    let repo_ignore = repo_ignore.build()?;
+
+    let mut walker = ignore::WalkBuilder::new(root);
+    // Only .ordinatorignore counts: not .gitignore, hidden files, or parents of root
+    walker
+        .standard_filters(false)
+        .add_custom_ignore_filename(IGNORE_FILE)
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            entry.depth() == 0 || !repo_ignore.matched(entry.path(), is_dir).is_ignore()
+        });
+    for entry in walker.build() {
+        let entry = entry?;
+        // Follow symlinks: after apply, tracked files in the live directory link into storage
+        if !entry.path().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(root)?.to_path_buf();
+        if filter.matches(&relative) {
+            files.push(relative);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Files changed by syncing a live directory into its stored copy
+#[derive(Debug, Default, PartialEq)]
+pub struct SyncReport {
+    /// Files new in the live directory
+    pub added: Vec<PathBuf>,
+
+    /// Files whose contents changed
+    pub updated: Vec<PathBuf>,
+
+    /// Files no longer present (or no longer matching the filter)
+    pub removed: Vec<PathBuf>,
+}
+
+impl SyncReport {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Make `storage` mirror the tracked files of `live`: copy new and changed
+/// files in, and delete stored files that no longer exist or no longer match
+/// the filter. With `dry_run`, only report what would change.
+pub fn sync_into_storage(
+    live: &Path,
+    storage: &Path,
+    filter: &DirectoryFilter,
+    dry_run: bool,
+) -> Result<SyncReport> {
+    let live_files = list_files(live, filter)?;
+    let mut stored_files = Vec::new();
+    if storage.is_dir() {
+        for entry in walkdir::WalkDir::new(storage) {
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                stored_files.push(entry.path().strip_prefix(storage)?.to_path_buf());
+            }
+        }
+    }
+
+    let mut report = SyncReport::default();
+    for relative in &live_files {
+        let src = live.join(relative);
+        let dst = storage.join(relative);
+        if !stored_files.contains(relative) {
+            report.added.push(relative.clone());
+        } else if fs::read(&src)? != fs::read(&dst)? {
+            report.updated.push(relative.clone());
+        } else {
+            continue;
+        }
+        if !dry_run {
+            if let Some(parent) = dst.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(&src, &dst).with_context(|| {
+                format!("Failed to copy {} to {}", src.display(), dst.display())
+            })?;
+        }
+    }
+
+    for relative in stored_files {
+        if live_files.contains(&relative) {
+            continue;
+        }
+        if !dry_run {
+            let path = storage.join(&relative);
+            fs::remove_file(&path)?;
+            remove_empty_parents(&path, storage);
+        }
+        report.removed.push(relative);
+    }
+    report.removed.sort();
+
+    if !dry_run {
+        fs::create_dir_all(storage)?;
+    }
+    Ok(report)
+}
+
+/// Remove directories left empty after deleting `path`, stopping at `root`
+fn remove_empty_parents(path: &Path, root: &Path) {
+    let mut current = path.parent();
+    while let Some(dir) = current {
+        if dir == root || fs::remove_dir(dir).is_err() {
+            break;
+        }
+        current = dir.parent();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_directory_entry_forms() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            directories: Vec<TrackedDirectory>,
+        }
+        let parsed: Wrapper = toml::from_str(
+            r#"directories = [".config/nvim/", { path = ".config/fish", include = ["*.fish"], link = "directory" }]"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.directories[0], TrackedDirectory::new(".config/nvim"));
+        assert_eq!(parsed.directories[1].path, ".config/fish");
+        assert_eq!(parsed.directories[1].include, vec!["*.fish".to_string()]);
+        assert_eq!(parsed.directories[1].link, DirectoryLink::Directory);
+    }
+
+    #[test]
+    fn test_filter_include_exclude() {
+        let mut dir = TrackedDirectory::new(".config/nvim");
+        dir.include = vec!["**/*.lua".to_string()];
+        dir.exclude = vec!["plugin/**".to_string()];
+        let extra = build_glob_set(&["*.bak".to_string()]).unwrap();
+        let filter = dir.filter(&extra).unwrap();
+        assert!(filter.matches(Path::new("init.lua")));
+        assert!(filter.matches(Path::new("lua/options.lua")));
+        assert!(!filter.matches(Path::new("plugin/packer_compiled.lua")));
+        assert!(!filter.matches(Path::new("README.md")));
+        assert!(!filter.matches(Path::new("lua/old.lua.bak")));
+    }
+
+    #[test]
+    fn test_list_files_honors_ordinatorignore() {
+        let temp = tempdir().unwrap();
+        let live = temp.path().join("nvim");
+        write(&live.join("init.lua"), "");
+        write(&live.join(".netrwhist"), "");
+        write(&live.join("node_modules/pkg/index.js"), "");
+        write(&live.join("lua/cache/state.json"), "");
+        write(&live.join("lua/plugins.lua"), "");
+        write(&live.join(IGNORE_FILE), "node_modules/\n");
+        write(&live.join("lua").join(IGNORE_FILE), "cache/\n");
+        write(&temp.path().join("repo-ignore"), ".netrwhist\n");
+
+        let filter = TrackedDirectory::new("nvim")
+            .filter(&GlobSet::empty())
+            .unwrap()
+            .with_repo_ignore(temp.path().join("repo-ignore"));
+        assert_eq!(
+            list_files(&live, &filter).unwrap(),
+            [
+                PathBuf::from(IGNORE_FILE),
+                PathBuf::from("init.lua"),
+                Path::new("lua").join(IGNORE_FILE),
+                PathBuf::from("lua/plugins.lua"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sync_into_storage() {
+        let temp = tempdir().unwrap();
+        let live = temp.path().join("live");
+        let storage = temp.path().join("storage");
+        write(&live.join("a.txt"), "a");
+        write(&live.join("sub/b.txt"), "b");
+        let filter = TrackedDirectory::new("live")
+            .filter(&GlobSet::empty())
+            .unwrap();
+
+        let report = sync_into_storage(&live, &storage, &filter, false).unwrap();
+        assert_eq!(
+            report.added,
+            vec![PathBuf::from("a.txt"), PathBuf::from("sub/b.txt")]
+        );
+
+        // Edit one file, delete another, add a third
+        write(&live.join("a.txt"), "changed");
+        fs::remove_file(live.join("sub/b.txt")).unwrap();
+        write(&live.join("c.txt"), "c");
+
+        let dry = sync_into_storage(&live, &storage, &filter, true).unwrap();
+        assert!(storage.join("sub/b.txt").exists());
+
+        let report = sync_into_storage(&live, &storage, &filter, false).unwrap();
+        assert_eq!(dry, report);
+        assert_eq!(report.added, vec![PathBuf::from("c.txt")]);
+        assert_eq!(report.updated, vec![PathBuf::from("a.txt")]);
+        assert_eq!(report.removed, vec![PathBuf::from("sub/b.txt")]);
+        assert_eq!(
+            fs::read_to_string(storage.join("a.txt")).unwrap(),
+            "changed"
+        );
+        assert!(!storage.join("sub").exists());
+
+        assert!(sync_into_storage(&live, &storage, &filter, false)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,94 @@
+//! Getting rid of what's in the way. Files and symlinks that apply replaces
+//! without a backup go to the Trash so a mistaken `--force` can be undone;
+//! `--permanent` deletes them outright. Ordinator's own links are just
+//! deleted with [`delete`].
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static PERMANENT: AtomicBool = AtomicBool::new(false);
+
+/// Delete instead of moving to the Trash (`--permanent`)
+pub fn set_permanent(permanent: bool) {
+    PERMANENT.store(permanent, Ordering::Relaxed);
+}
+
+/// Whether `--permanent` was given
+pub fn permanent() -> bool {
+    PERMANENT.load(Ordering::Relaxed)
+}
+
+/// Where a discarded path went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Discarded {
+    Trashed,
+    Deleted,
+}
+
+/// Move `path` to the Trash, or delete it with `--permanent`. A symlink is
+/// discarded itself, never what it points to.
+pub fn discard(path: &Path) -> Result<Discarded> {
+    if !permanent() {
+        crate::environment::current().trash(path).with_context(|| {
+            format!(
+                "Failed to move {} to the Trash. Use --permanent to delete it instead.",
+                path.display()
+            )
+        })?;
+        return Ok(Discarded::Trashed);
+    }
+    delete(path)?;
+    Ok(Discarded::Deleted)
+}
+
+/// Remove `path` for good: a real directory with everything in it, else the
+/// file or symlink
+pub fn delete(path: &Path) -> Result<()> {
+    let is_dir = fs::symlink_metadata(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?
+        .is_dir();
+    if is_dir {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+    .with_context(|| format!("Failed to remove {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::{Fake, Override};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_discard_moves_to_trash_and_numbers_names() {
+        let home = tempfile::tempdir().unwrap();
+        let _env = Override::new(Arc::new(Fake::new(home.path())));
+        let file = home.path().join(".zshrc");
+        let link = home.path().join("link");
+        fs::write(&file, "first").unwrap();
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+
+        assert_eq!(discard(&link).unwrap(), Discarded::Trashed);
+        assert!(file.exists(), "the link's target stays");
+        assert!(home.path().join(".Trash/link").is_symlink());
+
+        assert_eq!(discard(&file).unwrap(), Discarded::Trashed);
+        fs::write(&file, "second").unwrap();
+        discard(&file).unwrap();
+        let trash = home.path().join(".Trash");
+        assert_eq!(fs::read_to_string(trash.join(".zshrc")).unwrap(), "first");
+        assert_eq!(
+            fs::read_to_string(trash.join(".zshrc 2")).unwrap(),
+            "second"
+        );
+
+        let dir = home.path().join("dir");
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        delete(&dir).unwrap();
+        assert!(!dir.exists());
+    }
+}
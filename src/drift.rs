@@ -0,0 +1,332 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Cache file (relative to the dotfiles repository); machine-specific, so gitignored
+const CACHE_FILE: &str = "cache/content-hashes.json";
+
+/// SHA-256 of some content, hex encoded
+pub fn hash_bytes(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// Resolve a file and read its size and modification time; the hash is left empty
+fn fingerprint(path: &Path) -> Result<(PathBuf, CachedHash)> {
+    let canonical =
+        fs::canonicalize(path).with_context(|| format!("Failed to resolve {}", path.display()))?;
+    let metadata = fs::metadata(&canonical)?;
+    let modified_nanos = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    Ok((
+        canonical,
+        CachedHash {
+            size: metadata.len(),
+            modified_nanos,
+            hash: String::new(),
+        },
+    ))
+}
+
+fn read_and_hash(path: &Path) -> Result<String> {
+    let content = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(hash_bytes(&content))
+}
+
+/// A file hash, valid while the file's size and modification time are unchanged
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct CachedHash {
+    size: u64,
+    modified_nanos: u128,
+    hash: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheData {
+    /// Content hashes keyed by canonical file path
+    #[serde(default)]
+    hashes: BTreeMap<String, CachedHash>,
+
+    /// Hash of each tracked file when the live and repo copies were last in
+    /// sync (after watch, add, or apply), keyed by "<profile>:<tracked path>"
+    #[serde(default)]
+    synced: BTreeMap<String, String>,
+
+    /// When each tracked file was last stored by watch, add, or adopt
+    /// (RFC 3339), keyed like `synced`
+    #[serde(default)]
+    added: BTreeMap<String, String>,
+}
+
+/// Content-hash cache so `ordinator status` only re-reads files that changed
+pub struct HashCache {
+    path: PathBuf,
+    data: CacheData,
+    dirty: bool,
+}
+
+impl HashCache {
+    /// Load the cache for a dotfiles repository. A missing or unreadable cache starts empty.
+    pub fn load(dotfiles_dir: &Path) -> Self {
+        let path = dotfiles_dir.join(CACHE_FILE);
+        let data = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            data,
+            dirty: false,
+        }
+    }
+
+    /// Hash a file's content (following symlinks), reusing the cached hash
+    /// when the file's size and modification time have not changed
+    pub fn hash_file(&mut self, path: &Path) -> Result<String> {
+        let (canonical, mut entry) = fingerprint(path)?;
+        let key = canonical.to_string_lossy().into_owned();
+        if let Some(hash) = self.fresh_hash(&key, &entry) {
+            return Ok(hash);
+        }
+
+        entry.hash = read_and_hash(&canonical)?;
+        let hash = entry.hash.clone();
+        self.data.hashes.insert(key, entry);
+        self.dirty = true;
+        Ok(hash)
+    }
+
+    /// Hash several files like [`hash_file`](Self::hash_file), reading at most
+    /// `jobs` uncached files at once. Hashes come back in the order of `paths`.
+    pub async fn hash_files(&mut self, paths: &[PathBuf], jobs: usize) -> Result<Vec<String>> {
+        let mut hashes = Vec::with_capacity(paths.len());
+        let mut stale = Vec::new();
+        for (index, path) in paths.iter().enumerate() {
+            let (canonical, entry) = fingerprint(path)?;
+            let key = canonical.to_string_lossy().into_owned();
+            match self.fresh_hash(&key, &entry) {
+                Some(hash) => hashes.push(hash),
+                None => {
+                    hashes.push(String::new());
+                    stale.push((index, key, canonical, entry));
+                }
+            }
+        }
+
+        let read = crate::utils::map_concurrent(stale, jobs, |(index, key, canonical, entry)| {
+            read_and_hash(&canonical).map(|hash| (index, key, entry, hash))
+        })
+        .await?;
+        for result in read {
+            let (index, key, mut entry, hash) = result?;
+            entry.hash = hash.clone();
+            hashes[index] = hash;
+            self.data.hashes.insert(key, entry);
+            self.dirty = true;
+        }
+        Ok(hashes)
+    }
+
+    /// The cached hash for `key`, if the file still has the same size and modification time
+    fn fresh_hash(&self, key: &str, current: &CachedHash) -> Option<String> {
+        self.data
+            .hashes
+            .get(key)
+            .filter(|cached| {
+                cached.size == current.size && cached.modified_nanos == current.modified_nanos
+            })
+            .map(|cached| cached.hash.clone())
+    }
+
+    /// Record that a tracked file's live and repo copies both have `hash`
+    pub fn record_sync(&mut self, profile: &str, tracked_path: &str, hash: String) {
+        let key = format!("{profile}:{tracked_path}");
+        if self.data.synced.get(&key) != Some(&hash) {
+            self.data.synced.insert(key, hash);
+            self.dirty = true;
+        }
+    }
+
+    /// Hash recorded the last time a tracked file was in sync
+    pub fn synced_hash(&self, profile: &str, tracked_path: &str) -> Option<&str> {
+        self.data
+            .synced
+            .get(&format!("{profile}:{tracked_path}"))
+            .map(String::as_str)
+    }
+
+    /// Record that `watch`, `add`, or `adopt` just stored a tracked file
+    pub fn record_added(&mut self, profile: &str, tracked_path: &str) {
+        self.data.added.insert(
+            format!("{profile}:{tracked_path}"),
+            chrono::Local::now().to_rfc3339(),
+        );
+        self.dirty = true;
+    }
+
+    /// When a tracked file was last stored on this machine, if recorded
+    pub fn added_at(&self, profile: &str, tracked_path: &str) -> Option<&str> {
+        self.data
+            .added
+            .get(&format!("{profile}:{tracked_path}"))
+            .map(String::as_str)
+    }
+
+    /// Write the cache if anything changed
+    pub fn save(&self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.data)?)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+/// How a deployed file relates to its tracked copy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Drift {
+    /// Live and repo contents match
+    InSync,
+
+    /// The live file changed since the last sync; run `ordinator add`
+    ModifiedLocally,
+
+    /// The tracked copy changed since the last sync; run `ordinator apply`
+    ModifiedInRepo,
+
+    /// Both sides changed since the last sync
+    ModifiedBoth,
+
+    /// Contents differ and there is no sync record to tell which side changed
+    Differs,
+
+    /// The file is missing from the home directory
+    MissingLocally,
+
+    /// The tracked copy is missing from the repository
+    MissingInRepo,
+}
+
+impl Drift {
+    /// Short label shown by `ordinator status`
+    pub fn label(&self) -> &'static str {
+        match self {
+            Drift::InSync => "in sync",
+            Drift::ModifiedLocally => "modified locally",
+            Drift::ModifiedInRepo => "modified in repo",
+            Drift::ModifiedBoth => "modified locally and in repo",
+            Drift::Differs => "differs",
+            Drift::MissingLocally => "missing locally",
+            Drift::MissingInRepo => "missing in repo",
+        }
+    }
+
+    /// Two-character code for `status --short`: the home directory side, then
+    /// the repository side. `~~` means the copies differ with no sync record.
+    pub fn short_code(&self) -> &'static str {
+        match self {
+            Drift::InSync => "  ",
+            Drift::ModifiedLocally => "M ",
+            Drift::ModifiedInRepo => " M",
+            Drift::ModifiedBoth => "MM",
+            Drift::Differs => "~~",
+            Drift::MissingLocally => "D ",
+            Drift::MissingInRepo => " D",
+        }
+    }
+
+    /// Command that resolves the drift, if there is an obvious one
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Drift::ModifiedLocally => Some("ordinator add"),
+            Drift::ModifiedInRepo | Drift::MissingLocally => Some("ordinator apply"),
+            _ => None,
+        }
+    }
+}
+
+/// Classify drift from the live hash, repo hash, and the hash at the last sync
+pub fn classify(live: &str, repo: &str, synced: Option<&str>) -> Drift {
+    if live == repo {
+        return Drift::InSync;
+    }
+    match synced {
+        Some(synced) if synced == repo => Drift::ModifiedLocally,
+        Some(synced) if synced == live => Drift::ModifiedInRepo,
+        Some(_) => Drift::ModifiedBoth,
+        None => Drift::Differs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify("a", "a", None), Drift::InSync);
+        assert_eq!(classify("b", "a", Some("a")), Drift::ModifiedLocally);
+        assert_eq!(classify("a", "b", Some("a")), Drift::ModifiedInRepo);
+        assert_eq!(classify("b", "c", Some("a")), Drift::ModifiedBoth);
+        assert_eq!(classify("b", "c", None), Drift::Differs);
+        assert_eq!(Drift::ModifiedLocally.short_code(), "M ");
+        assert_eq!(Drift::ModifiedInRepo.short_code(), " M");
+    }
+
+    #[test]
+    fn test_hash_cache_roundtrip() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("file.txt");
+        fs::write(&file, "one").unwrap();
+
+        let mut cache = HashCache::load(dir.path());
+        let hash = cache.hash_file(&file).unwrap();
+        assert_eq!(hash, hash_bytes(b"one"));
+        cache.record_sync("default", "file.txt", hash.clone());
+        cache.save().unwrap();
+
+        let mut cache = HashCache::load(dir.path());
+        assert_eq!(
+            cache.synced_hash("default", "file.txt"),
+            Some(hash.as_str())
+        );
+        assert_eq!(cache.hash_file(&file).unwrap(), hash);
+        assert!(!cache.dirty);
+
+        // A change in size invalidates the cached hash
+        fs::write(&file, "three").unwrap();
+        assert_eq!(cache.hash_file(&file).unwrap(), hash_bytes(b"three"));
+    }
+
+    #[tokio::test]
+    async fn test_hash_files_mixes_cached_and_fresh() {
+        let dir = tempdir().unwrap();
+        let paths: Vec<PathBuf> = (0..5)
+            .map(|i| dir.path().join(format!("{i}.txt")))
+            .collect();
+        for (i, path) in paths.iter().enumerate() {
+            fs::write(path, i.to_string()).unwrap();
+        }
+
+        let mut cache = HashCache::load(dir.path());
+        cache.hash_file(&paths[2]).unwrap();
+        let hashes = cache.hash_files(&paths, 2).await.unwrap();
+        let expected: Vec<String> = (0..5)
+            .map(|i| hash_bytes(i.to_string().as_bytes()))
+            .collect();
+        assert_eq!(hashes, expected);
+        assert_eq!(cache.hash_file(&paths[4]).unwrap(), expected[4]);
+    }
+}
@@ -0,0 +1,640 @@
+//! The machine ordinator runs against. The home directory, the Trash, and
+//! every external command that reads or changes the system (Homebrew,
+//! launchctl, defaults, SOPS, age, gpg, security, sudo) go through the
+//! current [`Environment`],
+//! so tests can swap in a [`Fake`] instead of env flags and dummy binaries,
+//! and `--simulate` can show the commands that would change the machine
+//! instead of running them.
+//!
+//! [`System`] is the real machine. [`Isolated`] is the one the integration
+//! tests run in (`ORDINATOR_TEST_MODE=1`): commands and local git still run,
+//! but remotes are never reached (those in `ORDINATOR_TEST_UNREACHABLE`
+//! fail, as if offline), launchd and running apps are left alone,
+//! the Trash is `~/.Trash` under the test's home, and the dotfiles directory
+//! must be given explicitly.
+
+use anyhow::Result;
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Output};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Environment variable that selects [`Isolated`] for the integration tests
+pub const TEST_MODE_ENV: &str = "ORDINATOR_TEST_MODE";
+
+/// Environment variable listing, comma-separated, the remotes (names or
+/// URLs) [`Isolated`] fails to reach, for tests of the failure paths
+pub const UNREACHABLE_ENV: &str = "ORDINATOR_TEST_UNREACHABLE";
+
+/// Whether a command only looks at the system or changes it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    /// Reads state, like `brew list`; runs even under `--simulate`
+    Reads,
+
+    /// Changes the machine, like `brew install`
+    Changes,
+}
+
+/// The machine ordinator runs against
+pub trait Environment: Send + Sync {
+    /// The home directory, and the default target root
+    fn home_dir(&self) -> Result<PathBuf>;
+
+    /// The dotfiles repository when `ORDINATOR_CONFIG` doesn't name one
+    fn default_dotfiles_dir(&self) -> Result<PathBuf> {
+        Ok(self.home_dir()?.join(".dotfiles"))
+    }
+
+    /// Whether commands that change the machine are shown instead of run
+    fn simulated(&self) -> bool {
+        false
+    }
+
+    /// Run `command` and collect its output
+    fn output(&self, command: &mut Command, effect: Effect) -> io::Result<Output>;
+
+    /// Run `command` with the terminal attached, for ones that may prompt
+    fn status(&self, command: &mut Command, effect: Effect) -> io::Result<ExitStatus>;
+
+    /// Run `command` with `input` on its stdin, collecting its stdout, and
+    /// its stderr when the command pipes it. For secrets that mustn't show
+    /// up in `ps`, like a key handed to `security`.
+    fn output_with_input(
+        &self,
+        command: &mut Command,
+        input: &[u8],
+        effect: Effect,
+    ) -> io::Result<Output>;
+
+    /// Run a command that talks to the system's services rather than files,
+    /// like `launchctl` or `killall`
+    fn service(&self, command: &mut Command, effect: Effect) -> io::Result<Output> {
+        self.output(command, effect)
+    }
+
+    /// Whether to clone, fetch, push, or download from `remote`, a git remote
+    /// or URL. `Ok(false)` skips the transfer as if it had succeeded; an error
+    /// is the remote being unreachable. See [`remote`].
+    fn reach_remote(&self, _remote: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    /// Move `path` (a symlink itself, not what it points to) to the Trash
+    fn trash(&self, path: &Path) -> io::Result<()> {
+        system_trash(path)
+    }
+}
+
+/// The user's Trash, through `NSFileManager` on macOS: no Finder
+/// automation prompt or sound, though Finder won't offer "Put Back"
+fn system_trash(path: &Path) -> io::Result<()> {
+    #[allow(unused_mut)]
+    let mut context = trash::TrashContext::default();
+    #[cfg(target_os = "macos")]
+    {
+        use trash::macos::{DeleteMethod, TrashContextExtMacos};
+        context.set_delete_method(DeleteMethod::NsFileManager);
+    }
+    context.delete(path).map_err(io::Error::other)
+}
+
+/// Move `path` into `home/.Trash`, numbering the name like Finder when it's taken
+fn trash_under(home: &Path, path: &Path) -> io::Result<()> {
+    let trash = home.join(".Trash");
+    std::fs::create_dir_all(&trash)?;
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::other(format!("{} has no file name", path.display())))?
+        .to_string_lossy()
+        .into_owned();
+    let mut dest = trash.join(&name);
+    let mut n = 2;
+    while dest.symlink_metadata().is_ok() {
+        dest = trash.join(format!("{name} {n}"));
+        n += 1;
+    }
+    std::fs::rename(path, dest)
+}
+
+/// Spawn `command`, write `input` to its stdin, and wait for its output
+fn run_with_input(command: &mut Command, input: &[u8]) -> io::Result<Output> {
+    let mut child = command
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input)?;
+    }
+    child.wait_with_output()
+}
+
+/// `ORDINATOR_HOME`, else the user's home directory
+fn default_home_dir() -> Result<PathBuf> {
+    if let Ok(path) = std::env::var("ORDINATOR_HOME") {
+        return Ok(PathBuf::from(path));
+    }
+    dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))
+}
+
+/// The real machine
+#[derive(Debug, Default)]
+pub struct System;
+
+impl Environment for System {
+    fn home_dir(&self) -> Result<PathBuf> {
+        default_home_dir()
+    }
+
+    fn output(&self, command: &mut Command, _effect: Effect) -> io::Result<Output> {
+        command.output()
+    }
+
+    fn status(&self, command: &mut Command, _effect: Effect) -> io::Result<ExitStatus> {
+        command.status()
+    }
+
+    fn output_with_input(
+        &self,
+        command: &mut Command,
+        input: &[u8],
+        _effect: Effect,
+    ) -> io::Result<Output> {
+        run_with_input(command, input)
+    }
+}
+
+/// The machine the integration tests run on: commands run (so stand-in
+/// binaries on `PATH` answer them), but nothing leaves the test's directory
+#[derive(Debug, Default)]
+pub struct Isolated {
+    unreachable: Vec<String>,
+}
+
+impl Isolated {
+    /// Fail to reach `remotes` (names or URLs), as if offline
+    pub fn with_unreachable(mut self, remotes: impl IntoIterator<Item = String>) -> Self {
+        self.unreachable.extend(remotes);
+        self
+    }
+}
+
+impl Environment for Isolated {
+    fn home_dir(&self) -> Result<PathBuf> {
+        default_home_dir()
+    }
+
+    /// Only `ORDINATOR_HOME`'s, so a test never finds the real one
+    fn default_dotfiles_dir(&self) -> Result<PathBuf> {
+        match std::env::var_os("ORDINATOR_HOME") {
+            Some(home) => Ok(PathBuf::from(home).join(".dotfiles")),
+            None => Err(anyhow::anyhow!(
+                "Test mode requires ORDINATOR_HOME or ORDINATOR_CONFIG to be set for proper isolation"
+            )),
+        }
+    }
+
+    fn output(&self, command: &mut Command, _effect: Effect) -> io::Result<Output> {
+        command.output()
+    }
+
+    fn status(&self, command: &mut Command, _effect: Effect) -> io::Result<ExitStatus> {
+        command.status()
+    }
+
+    fn output_with_input(
+        &self,
+        command: &mut Command,
+        input: &[u8],
+        _effect: Effect,
+    ) -> io::Result<Output> {
+        run_with_input(command, input)
+    }
+
+    /// Skipped: changes succeed, and queries find nothing loaded or running
+    fn service(&self, command: &mut Command, effect: Effect) -> io::Result<Output> {
+        tracing::info!("[TEST MODE] Skipping {}", command_line(command));
+        Ok(match effect {
+            Effect::Reads => failure(Vec::new()),
+            Effect::Changes => success(Vec::new()),
+        })
+    }
+
+    /// Skipped, except that the unreachable remotes fail
+    fn reach_remote(&self, remote: &str) -> Result<bool> {
+        if self
+            .unreachable
+            .iter()
+            .any(|unreachable| unreachable == remote)
+        {
+            return Err(anyhow::anyhow!("Repository not found: {remote}"));
+        }
+        tracing::info!("[TEST MODE] Not reaching {remote}");
+        Ok(false)
+    }
+
+    fn trash(&self, path: &Path) -> io::Result<()> {
+        trash_under(&self.home_dir().map_err(io::Error::other)?, path)
+    }
+}
+
+/// `--simulate`: commands that read run on `inner`; commands that would
+/// change the machine are printed and treated as having succeeded
+pub struct Simulated {
+    inner: Arc<dyn Environment>,
+}
+
+impl Simulated {
+    pub fn new(inner: Arc<dyn Environment>) -> Self {
+        Self { inner }
+    }
+
+    fn show(command: &Command) {
+        eprintln!("SIMULATE: Would run: {}", command_line(command));
+    }
+}
+
+impl Environment for Simulated {
+    fn home_dir(&self) -> Result<PathBuf> {
+        self.inner.home_dir()
+    }
+
+    fn default_dotfiles_dir(&self) -> Result<PathBuf> {
+        self.inner.default_dotfiles_dir()
+    }
+
+    fn simulated(&self) -> bool {
+        true
+    }
+
+    fn output(&self, command: &mut Command, effect: Effect) -> io::Result<Output> {
+        match effect {
+            Effect::Reads => self.inner.output(command, effect),
+            Effect::Changes => {
+                Self::show(command);
+                Ok(success(Vec::new()))
+            }
+        }
+    }
+
+    fn status(&self, command: &mut Command, effect: Effect) -> io::Result<ExitStatus> {
+        match effect {
+            Effect::Reads => self.inner.status(command, effect),
+            Effect::Changes => {
+                Self::show(command);
+                Ok(ExitStatus::from_raw(0))
+            }
+        }
+    }
+
+    fn output_with_input(
+        &self,
+        command: &mut Command,
+        input: &[u8],
+        effect: Effect,
+    ) -> io::Result<Output> {
+        match effect {
+            Effect::Reads => self.inner.output_with_input(command, input, effect),
+            Effect::Changes => {
+                Self::show(command);
+                Ok(success(Vec::new()))
+            }
+        }
+    }
+
+    fn service(&self, command: &mut Command, effect: Effect) -> io::Result<Output> {
+        match effect {
+            Effect::Reads => self.inner.service(command, effect),
+            Effect::Changes => {
+                Self::show(command);
+                Ok(success(Vec::new()))
+            }
+        }
+    }
+
+    fn reach_remote(&self, remote: &str) -> Result<bool> {
+        self.inner.reach_remote(remote)
+    }
+
+    fn trash(&self, path: &Path) -> io::Result<()> {
+        eprintln!("SIMULATE: Would move {} to the Trash", path.display());
+        Ok(())
+    }
+}
+
+/// A scripted machine for tests: commands are answered from
+/// [`Fake::respond`] without running, and every one is recorded
+#[derive(Debug, Default)]
+pub struct Fake {
+    home: PathBuf,
+    responses: Vec<(String, Output)>,
+    calls: Mutex<Vec<(String, Effect)>>,
+}
+
+impl Fake {
+    /// A fake machine whose home directory is `home`
+    pub fn new(home: impl Into<PathBuf>) -> Self {
+        Self {
+            home: home.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Answer commands whose line starts with `prefix` with `stdout` and
+    /// success. Commands nothing answers succeed with no output.
+    pub fn respond(mut self, prefix: &str, stdout: &str) -> Self {
+        self.responses
+            .push((prefix.to_string(), success(stdout.as_bytes().to_vec())));
+        self
+    }
+
+    /// Make commands whose line starts with `prefix` fail with `stderr`
+    pub fn fail(mut self, prefix: &str, stderr: &str) -> Self {
+        self.responses
+            .push((prefix.to_string(), failure(stderr.as_bytes().to_vec())));
+        self
+    }
+
+    /// Command lines run so far, in order
+    pub fn calls(&self) -> Vec<(String, Effect)> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    fn answer(&self, command: &Command, effect: Effect) -> Output {
+        let line = command_line(command);
+        let output = self
+            .responses
+            .iter()
+            .find(|(prefix, _)| line.starts_with(prefix.as_str()))
+            .map_or_else(|| success(Vec::new()), |(_, output)| output.clone());
+        self.calls.lock().unwrap().push((line, effect));
+        output
+    }
+}
+
+impl Environment for Fake {
+    fn home_dir(&self) -> Result<PathBuf> {
+        Ok(self.home.clone())
+    }
+
+    fn output(&self, command: &mut Command, effect: Effect) -> io::Result<Output> {
+        Ok(self.answer(command, effect))
+    }
+
+    fn status(&self, command: &mut Command, effect: Effect) -> io::Result<ExitStatus> {
+        Ok(self.answer(command, effect).status)
+    }
+
+    /// Answered like [`Environment::output`]; the input is dropped
+    fn output_with_input(
+        &self,
+        command: &mut Command,
+        _input: &[u8],
+        effect: Effect,
+    ) -> io::Result<Output> {
+        Ok(self.answer(command, effect))
+    }
+
+    /// Never reached; answered and recorded like a `git remote <remote>` command
+    fn reach_remote(&self, remote: &str) -> Result<bool> {
+        let output = self.answer(
+            Command::new("git").args(["remote", remote]),
+            Effect::Changes,
+        );
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "{}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(false)
+    }
+
+    fn trash(&self, path: &Path) -> io::Result<()> {
+        trash_under(&self.home, path)
+    }
+}
+
+fn success(stdout: Vec<u8>) -> Output {
+    Output {
+        status: ExitStatus::from_raw(0),
+        stdout,
+        stderr: Vec::new(),
+    }
+}
+
+fn failure(stderr: Vec<u8>) -> Output {
+    Output {
+        status: ExitStatus::from_raw(1 << 8),
+        stdout: Vec::new(),
+        stderr,
+    }
+}
+
+/// `program arg ...` as it would be typed, for messages and matching
+pub fn command_line(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|part| {
+            let part = part.to_string_lossy();
+            if part.is_empty() || part.contains(char::is_whitespace) {
+                format!("'{part}'")
+            } else {
+                part.into_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+static CURRENT: RwLock<Option<Arc<dyn Environment>>> = RwLock::new(None);
+
+thread_local! {
+    static THREAD_OVERRIDE: RefCell<Option<Arc<dyn Environment>>> = const { RefCell::new(None) };
+}
+
+/// The environment `ORDINATOR_TEST_MODE` selects: [`Isolated`] when it is
+/// `1`, with the remotes in `ORDINATOR_TEST_UNREACHABLE`, else [`System`]
+pub fn from_env() -> Arc<dyn Environment> {
+    if std::env::var(TEST_MODE_ENV).is_ok_and(|value| value == "1") {
+        let unreachable = std::env::var(UNREACHABLE_ENV).unwrap_or_default();
+        Arc::new(
+            Isolated::default().with_unreachable(
+                unreachable
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|remote| !remote.is_empty())
+                    .map(str::to_string),
+            ),
+        )
+    } else {
+        Arc::new(System)
+    }
+}
+
+/// The environment in use: this thread's [`Override`], else the one
+/// [`install`]ed, else [`from_env`]
+pub fn current() -> Arc<dyn Environment> {
+    if let Some(env) = THREAD_OVERRIDE.with(|current| current.borrow().clone()) {
+        return env;
+    }
+    if let Some(env) = CURRENT.read().unwrap().as_ref() {
+        return env.clone();
+    }
+    CURRENT
+        .write()
+        .unwrap()
+        .get_or_insert_with(from_env)
+        .clone()
+}
+
+/// Use `env` for the rest of the process, e.g. [`Simulated`] for `--simulate`
+pub fn install(env: Arc<dyn Environment>) {
+    *CURRENT.write().unwrap() = Some(env);
+}
+
+/// Uses an environment on the current thread until dropped, so tests
+/// running side by side each get their own
+pub struct Override {
+    previous: Option<Arc<dyn Environment>>,
+}
+
+impl Override {
+    pub fn new(env: Arc<dyn Environment>) -> Self {
+        let previous = THREAD_OVERRIDE.with(|current| current.borrow_mut().replace(env));
+        Self { previous }
+    }
+}
+
+impl Drop for Override {
+    fn drop(&mut self) {
+        THREAD_OVERRIDE.with(|current| *current.borrow_mut() = self.previous.take());
+    }
+}
+
+/// Run a command through the current environment, collecting its output
+pub fn output(command: &mut Command, effect: Effect) -> io::Result<Output> {
+    current().output(command, effect)
+}
+
+/// Run a command through the current environment with `input` on its stdin
+pub fn output_with_input(
+    command: &mut Command,
+    input: &[u8],
+    effect: Effect,
+) -> io::Result<Output> {
+    current().output_with_input(command, input, effect)
+}
+
+/// Run a command through the current environment with the terminal attached
+pub fn status(command: &mut Command, effect: Effect) -> io::Result<ExitStatus> {
+    current().status(command, effect)
+}
+
+/// Run a system service command through the current environment
+pub fn service(command: &mut Command, effect: Effect) -> io::Result<Output> {
+    current().service(command, effect)
+}
+
+/// Clone, fetch, push, or download from `remote` with `run`, unless the
+/// current environment answers for it
+pub fn remote(remote: &str, run: impl FnOnce() -> Result<()>) -> Result<()> {
+    if current().reach_remote(remote)? {
+        run()?;
+    }
+    Ok(())
+}
+
+/// [`remote`] for transfers that are futures
+pub async fn remote_async(
+    remote: &str,
+    run: impl std::future::Future<Output = Result<()>>,
+) -> Result<()> {
+    if current().reach_remote(remote)? {
+        run.await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fake_and_simulated_environments() {
+        let fake = Arc::new(
+            Fake::new("/Users/test")
+                .respond("brew list", "git\n")
+                .fail("brew install wget", "Error: no bottle"),
+        );
+        let _guard = Override::new(fake.clone());
+        assert_eq!(current().home_dir().unwrap(), PathBuf::from("/Users/test"));
+        let listed = output(
+            Command::new("brew").args(["list", "--formula"]),
+            Effect::Reads,
+        )
+        .unwrap();
+        assert_eq!(listed.stdout, b"git\n");
+        let failed = output(
+            Command::new("brew").args(["install", "wget"]),
+            Effect::Changes,
+        )
+        .unwrap();
+        assert!(!failed.status.success());
+
+        let simulated = Simulated::new(fake.clone());
+        let installed = simulated
+            .output(
+                Command::new("brew").args(["install", "jq"]),
+                Effect::Changes,
+            )
+            .unwrap();
+        assert!(installed.status.success());
+        assert_eq!(
+            fake.calls(),
+            [
+                ("brew list --formula".to_string(), Effect::Reads),
+                ("brew install wget".to_string(), Effect::Changes),
+            ]
+        );
+        assert_eq!(
+            command_line(Command::new("defaults").args(["write", "com.apple.dock", "a b"])),
+            "defaults write com.apple.dock 'a b'"
+        );
+    }
+
+    #[test]
+    fn test_simulated_leaves_files_services_and_remotes_alone() {
+        let home = tempfile::tempdir().unwrap();
+        let fake = Arc::new(Fake::new(home.path()).fail("git remote private", "denied"));
+        let simulated = Simulated::new(fake.clone());
+        let file = home.path().join(".zshrc");
+        std::fs::write(&file, "export A=1").unwrap();
+        simulated.trash(&file).unwrap();
+        assert!(file.exists());
+        assert!(!home.path().join(".Trash").exists());
+
+        let killed = simulated
+            .service(Command::new("killall").arg("Dock"), Effect::Changes)
+            .unwrap();
+        assert!(killed.status.success());
+        assert!(!simulated.reach_remote("origin").unwrap());
+        assert!(simulated.reach_remote("private").is_err());
+
+        let isolated = Isolated::default().with_unreachable(["backup".to_string()]);
+        assert!(!isolated.reach_remote("origin").unwrap());
+        assert!(!isolated
+            .reach_remote("https://github.com/private/repo.git")
+            .unwrap());
+        assert!(isolated.reach_remote("backup").is_err());
+        assert_eq!(
+            fake.calls(),
+            [
+                ("git remote origin".to_string(), Effect::Changes),
+                ("git remote private".to_string(), Effect::Changes),
+            ]
+        );
+    }
+}
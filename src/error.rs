@@ -0,0 +1,388 @@
+//! Errors with stable codes and a hint at the fix, for the failures users
+//! run into most. Each code (e.g. `E0102`) is printed with the error and
+//! listed in COMMANDS.md, so scripts and docs can refer to it; the first two
+//! digits name the area: 01 config, 02 Git, 03 secrets, 04 symlinks, 05 Homebrew.
+
+use std::path::PathBuf;
+
+/// An error ordinator knows how to explain
+#[derive(Debug, thiserror::Error)]
+pub enum OrdinatorError {
+    /// No `ordinator.toml` where ordinator looks for one
+    #[error("No configuration file found.")]
+    ConfigNotFound,
+
+    /// A profile named on the command line isn't in the config
+    #[error("Profile '{0}' does not exist.")]
+    ProfileNotFound(String),
+
+    /// A `--profile` glob that matches no profile
+    #[error("No profile matches '{0}'.")]
+    NoProfilesMatch(String),
+
+    /// `ordinator.toml` isn't valid TOML, or has fields ordinator doesn't know
+    #[error("Failed to parse config file {}: {reason}", path.display())]
+    ConfigParse { path: PathBuf, reason: String },
+
+    /// `ordinator.toml` parses, but a tracked path or profile in it is wrong
+    #[error("Invalid config file {}: {reason}", path.display())]
+    ConfigInvalid { path: PathBuf, reason: String },
+
+    /// A `config get` or `config set` key that isn't a dotted path
+    #[error("Invalid config key '{0}'")]
+    ConfigKeyInvalid(String),
+
+    #[error("Profile '{0}' already exists")]
+    ProfileExists(String),
+
+    #[error("Profile name '{0}' is not filesystem-safe.")]
+    InvalidProfileName(String),
+
+    /// Profiles that extend each other, in the order they were followed
+    #[error("Profile inheritance cycle detected: {0}")]
+    ProfileCycle(String),
+
+    #[error("Profile '{child}' extends unknown profile '{parent}'")]
+    UnknownParentProfile { child: String, parent: String },
+
+    /// A profile `profile remove` can't remove; `by` says what refers to it
+    #[error("Profile '{name}' is {by} and cannot be removed.")]
+    ProfileInUse { name: String, by: String },
+
+    #[error("No profiles found in config.")]
+    NoProfiles,
+
+    /// `profile switch` without `--from` before anything was applied; holds
+    /// the profile to switch to
+    #[error("No profile has been applied here yet.")]
+    NoProfileApplied(String),
+
+    #[error("'{0}' is already the current profile.")]
+    AlreadyCurrentProfile(String),
+
+    /// An `apply --machine` role that isn't under `[machines]`
+    #[error("Machine role '{name}' not found{}", known_roles(known))]
+    MachineRoleNotFound { name: String, known: Vec<String> },
+
+    /// A path named on the command line that the profile doesn't track
+    #[error("File '{path}' is not tracked in profile '{profile}'.")]
+    FileNotTracked { path: String, profile: String },
+
+    /// The dotfiles directory isn't a Git repository
+    #[error("No Git repository found.")]
+    RepositoryNotFound,
+
+    /// A remote that's neither in the repository nor in `[[git.remotes]]`
+    #[error("No remote '{0}' found.")]
+    RemoteNotFound(String),
+
+    /// A branch switch or pull that would overwrite changes not yet committed
+    #[error("You have uncommitted changes.")]
+    UncommittedChanges,
+
+    #[error("Branch '{0}' does not exist.")]
+    BranchNotFound(String),
+
+    #[error("Branch '{0}' already exists.")]
+    BranchExists(String),
+
+    #[error("'{0}' is not a valid branch name")]
+    InvalidBranchName(String),
+
+    /// A pull that merged with conflicts in these paths
+    #[error("Merge conflicts in {}.", paths.join(", "))]
+    MergeConflicts { paths: Vec<String>, repo: PathBuf },
+
+    #[error("Failed to push to remote '{remote}': {reason}")]
+    PushFailed { remote: String, reason: String },
+
+    #[error("Failed to fetch from remote: {0}")]
+    FetchFailed(String),
+
+    #[error("SOPS is not installed or not found in PATH.")]
+    SopsNotInstalled,
+
+    #[error("age is not installed or not found in PATH.")]
+    AgeNotInstalled,
+
+    /// `secrets.age_key_file` isn't set
+    #[error("No age key file configured.")]
+    NoAgeKey,
+
+    /// sops couldn't encrypt or decrypt a file
+    #[error("sops failed to {action} {}: {reason}", path.display())]
+    Sops {
+        action: &'static str,
+        path: PathBuf,
+        reason: String,
+    },
+
+    /// The built-in age backend couldn't encrypt or decrypt a file
+    #[error("Failed to {action} {} with the native age backend: {reason}", path.display())]
+    Age {
+        action: &'static str,
+        path: PathBuf,
+        reason: String,
+    },
+
+    /// A key to store that has no `AGE-SECRET-KEY-` line
+    #[error("Invalid AGE key format.")]
+    InvalidAgeKey,
+
+    #[error("'{0}' is not a valid age public key (expected 'age1' followed by 58 characters)")]
+    InvalidRecipient(String),
+
+    #[error("Recipient '{0}' is already configured.")]
+    RecipientExists(String),
+
+    #[error("Recipient '{0}' is not in [secrets] recipients.")]
+    RecipientNotFound(String),
+
+    /// Nothing to encrypt secrets for: no age key and no `[secrets] recipients`
+    #[error("No age recipients configured.")]
+    NoRecipients,
+
+    #[error("Unknown secrets backend '{0}'.")]
+    UnknownSecretsBackend(String),
+
+    #[error("Unknown key storage '{0}'.")]
+    UnknownKeyStorage(String),
+
+    /// `age_key_storage = "keychain"` without the `security` command
+    #[error("The macOS Keychain is not available: the 'security' command was not found")]
+    KeychainUnavailable,
+
+    #[error("No age key for {} in the Keychain: {reason}", key_file.display())]
+    KeychainKeyMissing { key_file: PathBuf, reason: String },
+
+    #[error("Protecting the age key with a passphrase needs a terminal.")]
+    PassphraseNeedsTerminal,
+
+    /// A secret named on the command line that isn't tracked, in `profile` if given
+    #[error("'{file}' is not a tracked secret{}.", profile.as_ref().map(|p| format!(" in profile '{p}'")).unwrap_or_default())]
+    SecretNotTracked {
+        file: String,
+        profile: Option<String>,
+    },
+
+    /// A `secrets set` key that can't be an environment variable name
+    #[error("Invalid secret name '{0}'.")]
+    InvalidSecretName(String),
+
+    /// A regular file or directory is where a symlink should go
+    #[error("Target {} already exists and is not a symlink.", .0.display())]
+    TargetExists(PathBuf),
+
+    /// A tracked file's stored copy is gone from the repository
+    #[error("Source file not found: {}", .0.display())]
+    SourceMissing(PathBuf),
+
+    /// The pre-flight check found destinations apply can't write
+    #[error("Apply can't write {0} destination(s); nothing was changed.")]
+    NotWritable(usize),
+
+    #[error("Homebrew is not installed.")]
+    BrewNotInstalled,
+
+    /// A `brew` command exited with an error
+    #[error("brew {command} failed: {reason}")]
+    Brew { command: String, reason: String },
+
+    /// Some packages failed to install, and `--strict` was given
+    #[error("{0} Homebrew package(s) failed to install.")]
+    BrewPackagesFailed(usize),
+}
+
+impl OrdinatorError {
+    /// The error's stable code
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::ConfigNotFound => "E0101",
+            Self::ProfileNotFound(_) => "E0102",
+            Self::NoProfilesMatch(_) => "E0103",
+            Self::ConfigParse { .. } => "E0104",
+            Self::ConfigInvalid { .. } => "E0105",
+            Self::ConfigKeyInvalid(_) => "E0106",
+            Self::ProfileExists(_) => "E0107",
+            Self::InvalidProfileName(_) => "E0108",
+            Self::ProfileCycle(_) => "E0109",
+            Self::UnknownParentProfile { .. } => "E0110",
+            Self::ProfileInUse { .. } => "E0111",
+            Self::NoProfiles => "E0112",
+            Self::NoProfileApplied(_) => "E0113",
+            Self::AlreadyCurrentProfile(_) => "E0114",
+            Self::MachineRoleNotFound { .. } => "E0115",
+            Self::FileNotTracked { .. } => "E0116",
+            Self::RepositoryNotFound => "E0201",
+            Self::RemoteNotFound(_) => "E0202",
+            Self::UncommittedChanges => "E0203",
+            Self::BranchNotFound(_) => "E0204",
+            Self::BranchExists(_) => "E0205",
+            Self::InvalidBranchName(_) => "E0206",
+            Self::MergeConflicts { .. } => "E0207",
+            Self::PushFailed { .. } => "E0208",
+            Self::FetchFailed(_) => "E0209",
+            Self::SopsNotInstalled => "E0301",
+            Self::AgeNotInstalled => "E0302",
+            Self::NoAgeKey => "E0303",
+            Self::Sops { .. } => "E0304",
+            Self::Age { .. } => "E0305",
+            Self::InvalidAgeKey => "E0306",
+            Self::InvalidRecipient(_) => "E0307",
+            Self::RecipientExists(_) => "E0308",
+            Self::RecipientNotFound(_) => "E0309",
+            Self::NoRecipients => "E0310",
+            Self::UnknownSecretsBackend(_) => "E0311",
+            Self::UnknownKeyStorage(_) => "E0312",
+            Self::KeychainUnavailable => "E0313",
+            Self::KeychainKeyMissing { .. } => "E0314",
+            Self::PassphraseNeedsTerminal => "E0315",
+            Self::SecretNotTracked { .. } => "E0316",
+            Self::InvalidSecretName(_) => "E0317",
+            Self::TargetExists(_) => "E0401",
+            Self::SourceMissing(_) => "E0402",
+            Self::NotWritable(_) => "E0403",
+            Self::BrewNotInstalled => "E0501",
+            Self::Brew { .. } => "E0502",
+            Self::BrewPackagesFailed(_) => "E0503",
+        }
+    }
+
+    /// What to do about it
+    pub fn hint(&self) -> String {
+        match self {
+            Self::ConfigNotFound => "Run 'ordinator init' first, or set ORDINATOR_CONFIG to the path of an ordinator.toml.".to_string(),
+            Self::ProfileNotFound(name) => format!(
+                "To create it, run: ordinator profile add {name}. 'ordinator profiles' lists the existing ones."
+            ),
+            Self::NoProfilesMatch(_) => "'ordinator profiles' lists the existing ones. Quote the pattern so the shell doesn't expand it.".to_string(),
+            Self::ConfigParse { .. } => "Fix the line shown, or run 'ordinator config edit' to edit the file and check it before it's saved.".to_string(),
+            Self::ConfigInvalid { .. } => "Fix the entry shown; 'ordinator config validate' checks the file without running anything else.".to_string(),
+            Self::ConfigKeyInvalid(_) => "Keys are dotted paths such as global.default_profile; quote parts with dots in them, e.g. profiles.work.file_options.\"/etc/hosts\".requires_sudo.".to_string(),
+            Self::ProfileExists(_) => "Pick another name, or rename the existing profile with 'ordinator profile rename'.".to_string(),
+            Self::InvalidProfileName(_) => "Use only letters, numbers, dash (-), and underscore (_).".to_string(),
+            Self::ProfileCycle(_) => "Remove one of these profiles from another's `extends`.".to_string(),
+            Self::UnknownParentProfile { child, parent } => format!(
+                "Create it with 'ordinator profile add {parent}', or remove it from the `extends` of '{child}'."
+            ),
+            Self::ProfileInUse { .. } => "Change default_profile, and remove the profile from other profiles' `extends` and from [machines], first.".to_string(),
+            Self::NoProfiles => "Create one with 'ordinator profile add <name>', or run 'ordinator init' first.".to_string(),
+            Self::NoProfileApplied(to) => format!(
+                "Run 'ordinator apply --profile {to}' instead, or name the old profile with --from."
+            ),
+            Self::AlreadyCurrentProfile(_) => "Run 'ordinator apply' to reapply it.".to_string(),
+            Self::MachineRoleNotFound { name, .. } => format!(
+                "Define it under [machines.{name}] in ordinator.toml, listing the profiles it applies."
+            ),
+            Self::FileNotTracked { path, profile } => format!(
+                "Use 'ordinator watch {path} --profile {profile}' to start tracking it."
+            ),
+            Self::RepositoryNotFound => "Run 'ordinator init' first, or 'ordinator init <url>' to clone an existing repository.".to_string(),
+            Self::RemoteNotFound(_) => "Add it under [[git.remotes]] in ordinator.toml, or set origin with 'ordinator init <url>'.".to_string(),
+            Self::UncommittedChanges => "Commit them first: ordinator commit -m \"...\"".to_string(),
+            Self::BranchNotFound(name) => format!(
+                "'ordinator branch list' shows the branches. Create it with: ordinator branch create {name}"
+            ),
+            Self::BranchExists(name) => format!("Switch to it with: ordinator branch switch {name}"),
+            Self::InvalidBranchName(_) => "Branch names can't contain spaces, '..', '~', '^', ':', or '\\', nor end with '/' or '.lock'.".to_string(),
+            Self::MergeConflicts { repo, .. } => format!(
+                "Resolve them with git in {}, then commit.",
+                repo.display()
+            ),
+            Self::PushFailed { .. } => "If the remote has commits you don't, run 'ordinator pull' first. 'ordinator auth test' checks your credentials.".to_string(),
+            Self::FetchFailed(_) => "Check the remote's URL with 'ordinator status', and your credentials with 'ordinator auth test'.".to_string(),
+            Self::SopsNotInstalled => "Install it with 'brew install sops' or from https://github.com/mozilla/sops#downloads, or run 'ordinator secrets setup'.".to_string(),
+            Self::AgeNotInstalled => "Install it with 'brew install age' or from https://github.com/FiloSottile/age#installation.".to_string(),
+            Self::NoAgeKey => "Run 'ordinator secrets setup' to create a key, or 'ordinator config set secrets.age_key_file <path>' to use an existing one.".to_string(),
+            Self::Sops { .. } | Self::Age { .. } => "Check that your age key is one of the recipients ('ordinator secrets recipients list'). After adding a recipient, run 'ordinator secrets rotate --re-encrypt-only'.".to_string(),
+            Self::InvalidAgeKey => "The key must contain a line starting with 'AGE-SECRET-KEY-', as age-keygen writes. 'ordinator age setup' creates one.".to_string(),
+            Self::InvalidRecipient(_) => "Print the public key of an age key file with: age-keygen -y <key file>".to_string(),
+            Self::RecipientExists(_) | Self::RecipientNotFound(_) => "'ordinator secrets recipients list' shows the recipients.".to_string(),
+            Self::NoRecipients => "Run 'ordinator secrets setup' or 'ordinator secrets recipients add <pubkey>' first.".to_string(),
+            Self::UnknownSecretsBackend(_) => "Use 'sops' or 'native'.".to_string(),
+            Self::UnknownKeyStorage(_) => "Use 'file', 'passphrase', or 'keychain'.".to_string(),
+            Self::KeychainUnavailable => "The Keychain is only on macOS. Keep the key in a file with: ordinator age key import <file> --storage file".to_string(),
+            Self::KeychainKeyMissing { .. } => "Store the key again with: ordinator age key import <file> --storage keychain --force".to_string(),
+            Self::PassphraseNeedsTerminal => "Run it in a terminal, or use --storage file or --storage keychain.".to_string(),
+            Self::SecretNotTracked { file, .. } => format!("Track it with 'ordinator secrets add {file}'."),
+            Self::InvalidSecretName(_) => "Use letters, digits, and '_', not starting with a digit (e.g. GITHUB_TOKEN).".to_string(),
+            Self::TargetExists(_) => "Use --force to replace it (it's backed up first unless create_backups = false), or move it out of the way.".to_string(),
+            Self::SourceMissing(_) => "Run 'ordinator add <file> --profile <profile>' to store it again, or 'ordinator repair --prune' to stop tracking it.".to_string(),
+            Self::NotWritable(_) => "Each group above ends with its fix; apply again once they're done, or leave those paths out with --skip.".to_string(),
+            Self::BrewNotInstalled => "Install it from https://brew.sh, or leave packages out with --skip-brew.".to_string(),
+            Self::Brew { .. } => "Run the same brew command yourself to see its full output.".to_string(),
+            Self::BrewPackagesFailed(_) => "The table above shows why each one failed. Without --strict, failed packages are only reported.".to_string(),
+        }
+    }
+}
+
+/// `. Roles: a, b` after "Machine role 'x' not found", or why there are none
+fn known_roles(known: &[String]) -> String {
+    if known.is_empty() {
+        ": ordinator.toml has no [machines]".to_string()
+    } else {
+        format!(". Roles: {}", known.join(", "))
+    }
+}
+
+/// How `error` is shown to the user: `error[E0102]: ...` and a hint when
+/// an [`OrdinatorError`] caused it, the message alone otherwise. Either way
+/// the message carries the whole chain of causes, not just the outer context
+pub fn report(error: &anyhow::Error) -> String {
+    match error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<OrdinatorError>())
+    {
+        Some(known) => format!(
+            "error[{}]: {error:#}\n  hint: {}",
+            known.code(),
+            known.hint()
+        ),
+        None => format!("{error:#}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_report_shows_code_and_hint() {
+        let error = anyhow::Error::from(OrdinatorError::ProfileNotFound("work".into()));
+        assert_eq!(
+            report(&error),
+            "error[E0102]: Profile 'work' does not exist.\n  hint: To create it, run: ordinator profile add work. 'ordinator profiles' lists the existing ones."
+        );
+
+        // Context keeps its message, and the cause still supplies the code
+        let wrapped = Err::<(), _>(OrdinatorError::NoAgeKey)
+            .context("Failed to decrypt .env")
+            .unwrap_err();
+        assert!(report(&wrapped).starts_with(
+            "error[E0303]: Failed to decrypt .env: No age key file configured.\n  hint: "
+        ));
+
+        let role = anyhow::Error::from(OrdinatorError::MachineRoleNotFound {
+            name: "laptop".into(),
+            known: vec!["studio".into(), "desk".into()],
+        });
+        assert!(report(&role)
+            .starts_with("error[E0115]: Machine role 'laptop' not found. Roles: studio, desk\n"));
+
+        let plain = anyhow::anyhow!("something else");
+        assert_eq!(report(&plain), "something else");
+    }
+
+    #[test]
+    fn test_report_keeps_the_cause() {
+        let io = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "disk says no");
+        let wrapped = Err::<(), _>(io)
+            .context("Failed to write ordinator.toml")
+            .unwrap_err();
+        assert_eq!(
+            report(&wrapped),
+            "Failed to write ordinator.toml: disk says no"
+        );
+    }
+}
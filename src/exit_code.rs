@@ -0,0 +1,19 @@
+//! Exit codes ordinator returns, so scripts, CI, and shell prompts can tell
+//! "out of date" from "broken". The `--check` modes of `status`, `plan`,
+//! `lock`, and `secrets scan` use the full set.
+
+/// Nothing to report
+pub const OK: i32 = 0;
+
+/// The command failed
+pub const ERROR: i32 = 1;
+
+/// Files drifted, changes are pending, or the repository doesn't match its lock
+pub const DRIFT: i32 = 2;
+
+/// Changes that need a decision: a file in the way of a symlink, or a file
+/// modified both locally and in the repository
+pub const CONFLICTS: i32 = 3;
+
+/// Plaintext secrets found in tracked files
+pub const SECRETS_FOUND: i32 = 4;
@@ -0,0 +1,168 @@
+//! Editor extensions per profile. Each supported editor is driven through its
+//! command-line launcher (`code`, `cursor`, `codium`), which all take the same
+//! `--list-extensions` and `--install-extension` flags.
+
+use anyhow::{Context, Result};
+use std::process::Command;
+use tracing::info;
+
+use crate::config::Config;
+use crate::error::OrdinatorError;
+
+/// An editor whose extensions ordinator manages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorTool {
+    VsCode,
+    Cursor,
+    VsCodium,
+}
+
+impl EditorTool {
+    pub const ALL: [EditorTool; 3] = [EditorTool::VsCode, EditorTool::Cursor, EditorTool::VsCodium];
+
+    /// Key under `[profiles.<name>.extensions]` and the `--tool` value
+    pub fn name(&self) -> &'static str {
+        match self {
+            EditorTool::VsCode => "vscode",
+            EditorTool::Cursor => "cursor",
+            EditorTool::VsCodium => "vscodium",
+        }
+    }
+
+    /// The editor's command-line launcher
+    pub fn command(&self) -> &'static str {
+        match self {
+            EditorTool::VsCode => "code",
+            EditorTool::Cursor => "cursor",
+            EditorTool::VsCodium => "codium",
+        }
+    }
+
+    /// Whether the launcher is on PATH
+    pub fn is_available(&self) -> bool {
+        which::which(self.command()).is_ok()
+    }
+}
+
+impl std::str::FromStr for EditorTool {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "vscode" | "code" => Ok(EditorTool::VsCode),
+            "cursor" => Ok(EditorTool::Cursor),
+            "vscodium" | "codium" => Ok(EditorTool::VsCodium),
+            other => Err(anyhow::anyhow!(
+                "Unknown editor '{}'. Use 'vscode', 'cursor', or 'vscodium'.",
+                other
+            )),
+        }
+    }
+}
+
+/// Extension ids are case-insensitive; the marketplace and `--list-extensions`
+/// don't always agree on case
+fn same_extension(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+pub struct ExtensionManager {
+    dry_run: bool,
+}
+
+impl ExtensionManager {
+    pub fn new(dry_run: bool) -> Self {
+        Self { dry_run }
+    }
+
+    /// Extensions installed in `tool`
+    pub fn installed(&self, tool: EditorTool) -> Result<Vec<String>> {
+        let output = Command::new(tool.command())
+            .arg("--list-extensions")
+            .output()
+            .with_context(|| format!("Failed to run '{} --list-extensions'", tool.command()))?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "'{} --list-extensions' failed: {}",
+                tool.command(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Replace a profile's extension list for `tool` with what's installed
+    pub fn export(&self, tool: EditorTool, profile: &str, config: &mut Config) -> Result<usize> {
+        info!(
+            "Exporting {} extensions for profile: {}",
+            tool.name(),
+            profile
+        );
+        let installed = self.installed(tool)?;
+        let profile_config = config
+            .get_profile_mut(profile)
+            .ok_or_else(|| OrdinatorError::ProfileNotFound(profile.to_string()))?;
+        let count = installed.len();
+        profile_config
+            .extensions
+            .insert(tool.name().to_string(), installed);
+        Ok(count)
+    }
+
+    /// Extensions in `declared` that `tool` doesn't have
+    pub fn missing(&self, tool: EditorTool, declared: &[String]) -> Result<Vec<String>> {
+        let installed = self.installed(tool)?;
+        Ok(declared
+            .iter()
+            .filter(|id| !installed.iter().any(|i| same_extension(i, id)))
+            .cloned()
+            .collect())
+    }
+
+    /// Install the extensions in `declared` that `tool` doesn't have yet.
+    /// Returns the ones installed (or, in a dry run, that would be).
+    pub fn install(&self, tool: EditorTool, declared: &[String]) -> Result<Vec<String>> {
+        let missing = self.missing(tool, declared)?;
+        if self.dry_run {
+            return Ok(missing);
+        }
+        for id in &missing {
+            info!("Installing {} extension {}", tool.name(), id);
+            let output = Command::new(tool.command())
+                .args(["--install-extension", id])
+                .output()
+                .with_context(|| {
+                    format!("Failed to run '{} --install-extension'", tool.command())
+                })?;
+            if !output.status.success() {
+                return Err(anyhow::anyhow!(
+                    "Failed to install {} extension '{}': {}",
+                    tool.name(),
+                    id,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+        }
+        Ok(missing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_editor_tool_names() {
+        for tool in EditorTool::ALL {
+            assert_eq!(tool.name().parse::<EditorTool>().unwrap(), tool);
+            assert_eq!(tool.command().parse::<EditorTool>().unwrap(), tool);
+        }
+        assert!("emacs".parse::<EditorTool>().is_err());
+        assert!(same_extension("ms-python.Python", "ms-python.python"));
+    }
+}
@@ -0,0 +1,80 @@
+//! `--only` and `--skip`: limit apply, status, diff, and repair to the
+//! tracked paths that match (or don't match) glob patterns, e.g.
+//! `--only '.config/nvim/**'`.
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+/// Which tracked paths a command looks at
+#[derive(Debug, Clone, Default)]
+pub struct PathFilter {
+    only: Option<GlobSet>,
+    skip: Option<GlobSet>,
+}
+
+fn build(patterns: &[String]) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        // Patterns are written like the paths `ordinator files` shows
+        let pattern = pattern.strip_prefix("~/").unwrap_or(pattern);
+        builder
+            .add(Glob::new(pattern).with_context(|| format!("Invalid glob pattern '{pattern}'"))?);
+    }
+    Ok(Some(builder.build()?))
+}
+
+impl PathFilter {
+    /// Keep paths matching any of `only` (everything when it's empty), less
+    /// those matching any of `skip`
+    pub fn new(only: &[String], skip: &[String]) -> Result<Self> {
+        Ok(Self {
+            only: build(only)?,
+            skip: build(skip)?,
+        })
+    }
+
+    /// Whether `--only` was given, so only part of the tracked files are looked at
+    pub fn is_partial(&self) -> bool {
+        self.only.is_some()
+    }
+
+    /// Whether a tracked path passes. A template also matches by the path it
+    /// renders to, and a directory by the files inside it (`dir/**`).
+    pub fn matches(&self, path: &str) -> bool {
+        let rendered = crate::template::rendered_path(path);
+        let inside = format!("{}/", path.trim_end_matches('/'));
+        let hit =
+            |set: &GlobSet| set.is_match(path) || set.is_match(rendered) || set.is_match(&inside);
+        self.only.as_ref().is_none_or(hit) && !self.skip.as_ref().is_some_and(hit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_and_skip() {
+        let none = PathFilter::default();
+        assert!(none.matches(".zshrc"));
+        assert!(!none.is_partial());
+
+        let nvim = PathFilter::new(&["~/.config/nvim/**".to_string()], &[]).unwrap();
+        assert!(nvim.is_partial());
+        assert!(nvim.matches(".config/nvim/init.lua"));
+        assert!(nvim.matches(".config/nvim"));
+        assert!(!nvim.matches(".config/fish/config.fish"));
+
+        let skip =
+            PathFilter::new(&[], &[".gnupg/**".to_string(), ".gitconfig".to_string()]).unwrap();
+        assert!(!skip.matches(".gnupg/gpg.conf"));
+        assert!(!skip.matches(".gnupg"));
+        assert!(!skip.matches(".gitconfig.tmpl"));
+        assert!(skip.matches(".zshrc"));
+
+        assert!(PathFilter::new(&["a/[".to_string()], &[]).is_err());
+    }
+}
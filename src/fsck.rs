@@ -0,0 +1,410 @@
+//! `ordinator fsck`: checks the repository against `ordinator.toml`. Every
+//! tracked path needs its stored copy, every file under `files/<profile>/`
+//! must be referenced, `file_mappings` names must hash from the paths they
+//! map, stored secrets must be SOPS documents whose metadata agrees with
+//! their mapping, and backups must be of files something still tracks. Stale and misnamed mappings can be fixed.
+
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::utils::generate_file_hash;
+use walkdir::WalkDir;
+
+/// A problem `fsck` found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    /// Which check found it: `missing`, `unreferenced`, `mapping`, `secret`, or `backup`
+    pub check: &'static str,
+
+    pub message: String,
+
+    /// How `--fix` repairs it, if it can
+    pub fix: Option<Fix>,
+}
+
+/// A repair `--fix` makes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fix {
+    /// Drop `stored` from the profile's `file_mappings`
+    DropMapping { profile: String, stored: String },
+
+    /// Rename the stored copy `from` in `dir` (and its host overrides) to
+    /// `to`, the name its tracked path hashes to, and its mapping with it
+    Rename {
+        profile: String,
+        dir: PathBuf,
+        from: String,
+        to: String,
+    },
+}
+
+impl Issue {
+    /// `check: message`, noting whether `--fix` repairs it
+    pub fn line(&self) -> String {
+        let fixable = if self.fix.is_some() {
+            " (fixable with --fix)"
+        } else {
+            ""
+        };
+        format!("{}: {}{fixable}", self.check, self.message)
+    }
+}
+
+/// Run every check over the profiles in `config`, sorted by name
+pub fn check(config: &Config, dotfiles_dir: &Path) -> Result<Vec<Issue>> {
+    let mut profiles: Vec<&String> = config.profiles.keys().collect();
+    profiles.sort();
+    let mut issues = Vec::new();
+    for profile in &profiles {
+        check_sources(config, dotfiles_dir, profile, &mut issues)?;
+        for path in config.orphaned_files(profile)? {
+            let relative = path.strip_prefix(dotfiles_dir).unwrap_or(&path);
+            issues.push(Issue {
+                check: "unreferenced",
+                message: format!(
+                    "{} is not tracked by profile '{profile}'",
+                    relative.display()
+                ),
+                fix: None,
+            });
+        }
+        check_mappings(config, dotfiles_dir, profile, &mut issues)?;
+    }
+    check_secrets(config, dotfiles_dir, &mut issues)?;
+    check_backups(config, dotfiles_dir, &mut issues)?;
+    Ok(issues)
+}
+
+/// Tracked files, directories, and secrets whose stored copy is gone
+fn check_sources(
+    config: &Config,
+    dotfiles_dir: &Path,
+    profile: &str,
+    issues: &mut Vec<Issue>,
+) -> Result<()> {
+    for path in config.missing_sources(profile)? {
+        issues.push(Issue {
+            check: "missing",
+            message: format!(
+                "{path} is tracked by profile '{profile}', but its stored copy is gone"
+            ),
+            fix: None,
+        });
+    }
+    let Some(profile_config) = config.get_profile(profile) else {
+        return Ok(());
+    };
+    for secret in &profile_config.secrets {
+        if !crate::secrets::stored_secret_path(config, dotfiles_dir, profile, secret)?.exists() {
+            issues.push(Issue {
+                check: "missing",
+                message: format!(
+                    "secret {secret} is tracked by profile '{profile}', but its encrypted copy is gone"
+                ),
+                fix: None,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// `file_mappings` entries that map an untracked path, or whose stored name
+/// doesn't start with the hash of the path it maps
+fn check_mappings(
+    config: &Config,
+    dotfiles_dir: &Path,
+    profile: &str,
+    issues: &mut Vec<Issue>,
+) -> Result<()> {
+    let Some(profile_config) = config.get_profile(profile) else {
+        return Ok(());
+    };
+    let mut mappings: Vec<(&String, &String)> = profile_config.file_mappings.iter().collect();
+    mappings.sort();
+    for (stored, original) in mappings {
+        let secret = stored.ends_with(".enc");
+        let tracked = if secret {
+            profile_config.secrets.contains(original)
+        } else {
+            profile_config.files.contains(original)
+                || profile_config
+                    .directories
+                    .iter()
+                    .any(|directory| &directory.path == original)
+        };
+        if !tracked {
+            issues.push(Issue {
+                check: "mapping",
+                message: format!(
+                    "{stored} in profile '{profile}' maps {original}, which the profile no longer tracks"
+                ),
+                fix: Some(Fix::DropMapping {
+                    profile: profile.to_string(),
+                    stored: stored.clone(),
+                }),
+            });
+            continue;
+        }
+
+        let hash = generate_file_hash(original);
+        if stored.starts_with(&format!("{hash}_")) {
+            continue;
+        }
+        let name = stored
+            .split_once('_')
+            .map_or(stored.as_str(), |(_, name)| name);
+        let expected = format!("{hash}_{name}");
+        let dir = dotfiles_dir
+            .join(if secret { "secrets" } else { "files" })
+            .join(profile);
+        let fixable =
+            !profile_config.file_mappings.contains_key(&expected) && !dir.join(&expected).exists();
+        issues.push(Issue {
+            check: "mapping",
+            message: format!(
+                "{stored} in profile '{profile}' maps {original}, whose stored name should be {expected}"
+            ),
+            fix: fixable.then(|| Fix::Rename {
+                profile: profile.to_string(),
+                dir,
+                from: stored.clone(),
+                to: expected,
+            }),
+        });
+    }
+    Ok(())
+}
+
+/// Stored secrets that aren't SOPS documents
+fn check_secrets(config: &Config, dotfiles_dir: &Path, issues: &mut Vec<Issue>) -> Result<()> {
+    for path in crate::secrets::stored_secret_files(dotfiles_dir, None) {
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        let content =
+            fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let relative = path.strip_prefix(dotfiles_dir).unwrap_or(&path);
+        if let Some(problem) = sops_document_problem(&path, &content) {
+            issues.push(Issue {
+                check: "secret",
+                message: format!("{} {problem}", relative.display()),
+                fix: None,
+            });
+        }
+        if let Some(problem) = metadata_problem(config, relative, &path) {
+            issues.push(Issue {
+                check: "secret",
+                message: format!("{} {problem}", relative.display()),
+                fix: None,
+            });
+        }
+    }
+
+    for entry in WalkDir::new(dotfiles_dir.join("secrets"))
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        let sidecar = entry.path();
+        if crate::secret_meta::is_sidecar(sidecar) && !sidecar.with_extension("").exists() {
+            let relative = sidecar.strip_prefix(dotfiles_dir).unwrap_or(sidecar);
+            issues.push(Issue {
+                check: "secret",
+                message: format!(
+                    "{} is metadata for a secret that is gone",
+                    relative.display()
+                ),
+                fix: None,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Why the metadata beside the secret stored at `path` (`relative` to the
+/// repository) doesn't fit: it doesn't parse, or names a different target
+/// than the profile's `file_mappings`
+fn metadata_problem(config: &Config, relative: &Path, path: &Path) -> Option<String> {
+    let meta = match crate::secret_meta::read(path) {
+        Ok(meta) => meta?,
+        Err(e) => return Some(format!("has unreadable metadata: {e:#}")),
+    };
+    // secrets/<profile>/<name>
+    let mut components = relative.iter().skip(1);
+    let profile = components.next()?.to_string_lossy();
+    let name = path.file_name()?.to_string_lossy();
+    let mapped = config
+        .get_profile(&profile)?
+        .file_mappings
+        .get(name.as_ref())?;
+    (mapped != &meta.target).then(|| {
+        format!(
+            "is mapped to {mapped}, but its metadata says it decrypts to {}",
+            meta.target
+        )
+    })
+}
+
+/// Backups of files no profile tracks any more. Backups in the old flat
+/// directory only know the file name, so they're matched by it.
+fn check_backups(config: &Config, dotfiles_dir: &Path, issues: &mut Vec<Issue>) -> Result<()> {
+    let home_dir = config.target_root()?;
+    let mut tracked_names: HashSet<String> = HashSet::new();
+    let mut tracked_paths: Vec<PathBuf> = Vec::new();
+    for profile in config.profiles.values() {
+        let paths = profile
+            .files
+            .iter()
+            .chain(&profile.secrets)
+            .chain(profile.directories.iter().map(|directory| &directory.path));
+        for path in paths {
+            if let Some(name) = Path::new(path).file_name() {
+                tracked_names.insert(name.to_string_lossy().into_owned());
+            }
+            tracked_paths.push(crate::paths::destination(&home_dir, path));
+        }
+    }
+    let backup_dir = crate::backups::BackupDir::new(config, dotfiles_dir, &home_dir)?;
+    for backup in backup_dir.list()? {
+        let tracked = match &backup.original {
+            // A file inside a tracked directory counts as tracked
+            Some(original) => tracked_paths.iter().any(|path| original.starts_with(path)),
+            None => tracked_names.contains(&backup.file),
+        };
+        if !tracked {
+            let path = backup
+                .path
+                .strip_prefix(dotfiles_dir)
+                .unwrap_or(&backup.path);
+            issues.push(Issue {
+                check: "backup",
+                message: format!(
+                    "{} is a backup of {}, which no profile tracks",
+                    path.display(),
+                    backup.file
+                ),
+                fix: None,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Why `content`, stored at `path`, isn't a SOPS document: it needs SOPS
+/// metadata with a MAC, in the format the file's extension gives (`.enc`
+/// files hold the binary format, which SOPS stores as JSON). Age files,
+/// which the native backend writes, carry their own MAC and pass.
+pub fn sops_document_problem(path: &Path, content: &[u8]) -> Option<String> {
+    if crate::age::is_age(content) {
+        return None;
+    }
+    let Ok(text) = std::str::from_utf8(content) else {
+        return Some("is not text, so not a SOPS document".to_string());
+    };
+    let has_mac = match path.extension().and_then(|e| e.to_str()) {
+        Some("yaml" | "yml") => {
+            // The top-level `sops:` mapping, with `mac:` among its keys
+            text.lines()
+                .skip_while(|line| *line != "sops:")
+                .skip(1)
+                .take_while(|line| line.starts_with(' ') || line.is_empty())
+                .any(|line| line.trim_start().starts_with("mac:"))
+        }
+        Some("env") => text.lines().any(|line| line.starts_with("sops_mac=")),
+        Some("ini") => text
+            .lines()
+            .skip_while(|line| line.trim() != "[sops]")
+            .skip(1)
+            .take_while(|line| !line.trim_start().starts_with('['))
+            .any(|line| line.trim_start().starts_with("mac")),
+        _ => match serde_json::from_str::<serde_json::Value>(text) {
+            Ok(document) => document
+                .get("sops")
+                .and_then(|sops| sops.get("mac"))
+                .is_some(),
+            Err(e) => return Some(format!("is not valid JSON: {e}")),
+        },
+    };
+    (!has_mac).then(|| "has no SOPS metadata (a 'sops' section with a MAC)".to_string())
+}
+
+/// Make the repairs `issues` carry. Returns how many were made; the config
+/// is changed in memory and the caller saves it.
+pub fn apply_fixes(config: &mut Config, issues: &[Issue]) -> Result<usize> {
+    let mut fixed = 0;
+    for fix in issues.iter().filter_map(|issue| issue.fix.as_ref()) {
+        match fix {
+            Fix::DropMapping { profile, stored } => {
+                if let Some(profile) = config.get_profile_mut(profile) {
+                    profile.file_mappings.remove(stored);
+                }
+            }
+            Fix::Rename {
+                profile,
+                dir,
+                from,
+                to,
+            } => {
+                let mut copies = vec![dir.clone()];
+                if dir.is_dir() {
+                    for entry in fs::read_dir(dir)? {
+                        let host_dir = entry?.path();
+                        if host_dir.is_dir() {
+                            copies.push(host_dir);
+                        }
+                    }
+                }
+                for copy_dir in copies {
+                    let copy = copy_dir.join(from);
+                    if copy.exists() {
+                        fs::rename(&copy, copy_dir.join(to))
+                            .with_context(|| format!("Failed to rename {}", copy.display()))?;
+                    }
+                    let sidecar = crate::secret_meta::path(&copy);
+                    if sidecar.exists() {
+                        fs::rename(&sidecar, crate::secret_meta::path(&copy_dir.join(to)))
+                            .with_context(|| format!("Failed to rename {}", sidecar.display()))?;
+                    }
+                }
+                if let Some(profile) = config.get_profile_mut(profile) {
+                    if let Some(original) = profile.file_mappings.remove(from) {
+                        profile.file_mappings.insert(to.clone(), original);
+                    }
+                }
+            }
+        }
+        fixed += 1;
+    }
+    Ok(fixed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sops_document_problem() {
+        let json = br#"{"data": "ENC[AES256_GCM,data:abc]", "sops": {"mac": "ENC[x]", "version": "3.8.1"}}"#;
+        assert_eq!(sops_document_problem(Path::new("a_token.enc"), json), None);
+        assert!(sops_document_problem(Path::new("a_token.enc"), b"plain text").is_some());
+        assert!(sops_document_problem(Path::new("a_token.enc"), br#"{"data": "x"}"#).is_some());
+
+        let yaml = b"password: ENC[abc]\nsops:\n    age: []\n    mac: ENC[x]\n    version: 3.8.1\n";
+        assert_eq!(sops_document_problem(Path::new("db.yaml"), yaml), None);
+        assert!(sops_document_problem(Path::new("db.yaml"), b"password: hunter2\n").is_some());
+
+        let env = b"TOKEN=ENC[abc]\nsops_mac=ENC[x]\nsops_version=3.8.1\n";
+        assert_eq!(sops_document_problem(Path::new("app.env"), env), None);
+        let ini = b"[app]\ntoken = ENC[abc]\n[sops]\nmac = ENC[x]\n";
+        assert_eq!(sops_document_problem(Path::new("app.ini"), ini), None);
+        assert!(sops_document_problem(Path::new("app.ini"), b"[app]\ntoken = abc\n").is_some());
+
+        let age = b"-----BEGIN AGE ENCRYPTED FILE-----\nYWdl\n-----END AGE ENCRYPTED FILE-----\n";
+        assert_eq!(sops_document_problem(Path::new("values.env"), age), None);
+    }
+}
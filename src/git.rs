@@ -1,18 +1,147 @@
 use anyhow::{Context, Result};
 use git2::{Repository, RepositoryInitOptions};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use tracing::{info, warn};
 
+use crate::config_merge::{merge_files, MergeStrategy, CONFIG_FILE};
+use crate::error::OrdinatorError;
+
+/// How commits are signed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningFormat {
+    /// `gpg --detach-sign`
+    #[default]
+    Gpg,
+
+    /// `ssh-keygen -Y sign`
+    Ssh,
+}
+
+/// Author and signing settings for commits made by ordinator. Unset fields
+/// fall back to the ambient git configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GitConfig {
+    /// Commit author and committer name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_name: Option<String>,
+
+    /// Commit author and committer email
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_email: Option<String>,
+
+    /// GPG key ID, or path to the SSH private key when `signing_format = "ssh"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_key: Option<String>,
+
+    /// Whether `signing_key` is a GPG or SSH key
+    #[serde(default)]
+    pub signing_format: SigningFormat,
+
+    /// Sign every commit ordinator makes
+    #[serde(default)]
+    pub sign_commits: bool,
+
+    /// The shared branch (e.g. "main") that machine branches are merged into
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_branch: Option<String>,
+
+    /// Remotes besides `origin` (e.g. a private mirror), added to the
+    /// repository on first push
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remotes: Vec<RemoteConfig>,
+
+    /// Make `push` and `sync` push to every remote, not just `origin`
+    #[serde(default)]
+    pub push_all_remotes: bool,
+
+    /// Make `push --force` and `sync --force` ask for the branch name before
+    /// rewriting history on the remote. Unset means on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protect_force_push: Option<bool>,
+
+    /// Hosts besides github.com that the HTTPS token from the environment
+    /// may be sent to, e.g. a GitHub Enterprise server
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub token_hosts: Vec<String>,
+}
+
+impl GitConfig {
+    /// Whether a force push has to be confirmed by typing the branch name
+    pub fn force_push_protected(&self) -> bool {
+        self.protect_force_push != Some(false)
+    }
+}
+
+/// A remote under `[[git.remotes]]`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RemoteConfig {
+    pub name: String,
+    pub url: String,
+
+    /// Always force-push to this remote, e.g. a mirror nobody else pushes to
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// How a remote compares to the checked-out branch, for `ordinator status`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteStatus {
+    pub name: String,
+    pub url: Option<String>,
+
+    /// Force-pushed on every push
+    pub force: bool,
+
+    /// Commits the local branch has that the remote doesn't, and the other
+    /// way around, as of the last push or pull. None when the remote hasn't
+    /// been added yet or has no copy of the branch.
+    pub ahead_behind: Option<(usize, usize)>,
+}
+
+/// What the index holds compared with HEAD, i.e. what the next commit records
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StagedChanges {
+    pub added: Vec<String>,
+    pub modified: Vec<String>,
+    pub deleted: Vec<String>,
+
+    /// Total size of the added and modified files
+    pub bytes: u64,
+}
+
+impl StagedChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.modified.is_empty() && self.deleted.is_empty()
+    }
+}
+
 /// Git repository manager for Ordinator
 pub struct GitManager {
     repo_path: PathBuf,
+    config: GitConfig,
 }
 
 impl GitManager {
     /// Create a new Git manager
     pub fn new(repo_path: PathBuf) -> Self {
-        Self { repo_path }
+        Self {
+            repo_path,
+            config: GitConfig::default(),
+        }
+    }
+
+    /// Use the author and signing settings from `[git]`
+    pub fn with_config(mut self, config: GitConfig) -> Self {
+        self.config = config;
+        self
     }
 
     /// Get the repository path
@@ -21,24 +150,8 @@ impl GitManager {
         &self.repo_path
     }
 
-    fn is_test_mode() -> bool {
-        std::env::var("ORDINATOR_TEST_MODE").ok().as_deref() == Some("1")
-    }
-
     /// Initialize a new Git repository
     pub fn init(&self) -> Result<()> {
-        if Self::is_test_mode() {
-            info!(
-                "[TEST MODE] Skipping git init at {}",
-                self.repo_path.display()
-            );
-            // Create .git directory for test mode so exists() can find it
-            let git_dir = self.repo_path.join(".git");
-            if !git_dir.exists() {
-                fs::create_dir_all(&git_dir)?;
-            }
-            return Ok(());
-        }
         info!(
             "Initializing Git repository at: {}",
             self.repo_path.display()
@@ -57,9 +170,7 @@ impl GitManager {
         })?;
 
         // Set up the main branch
-        let signature = repo
-            .signature()
-            .unwrap_or_else(|_| git2::Signature::now("Ordinator", "ordinator@localhost").unwrap());
+        let signature = self.signature(&repo)?;
 
         // Create an empty tree for the initial commit
         let tree_id = repo.index().unwrap().write_tree().unwrap();
@@ -82,13 +193,6 @@ impl GitManager {
 
     /// Add a remote to the repository
     pub fn add_remote(&self, name: &str, url: &str) -> Result<()> {
-        if Self::is_test_mode() {
-            info!(
-                "[TEST MODE] Skipping git remote add '{}', url '{}'",
-                name, url
-            );
-            return Ok(());
-        }
         info!("Adding remote '{}' with URL: {}", name, url);
 
         let repo = Repository::open(&self.repo_path).with_context(|| {
@@ -102,35 +206,131 @@ impl GitManager {
         Ok(())
     }
 
-    /// Commit changes with a message
+    /// Stage every change and commit it
     pub fn commit(&self, message: &str) -> Result<()> {
-        // Always check for repo existence, even in test mode
+        self.stage(&[], true)?;
+        self.commit_staged(message)
+    }
+
+    /// Stage new, modified, and deleted files under `paths` (the whole
+    /// repository when empty). Paths are relative to the repository or
+    /// absolute inside it. Changes under `secrets/` are skipped unless
+    /// `include_secrets` is set. Returns everything now staged.
+    pub fn stage(&self, paths: &[PathBuf], include_secrets: bool) -> Result<StagedChanges> {
+        self.update_index(paths, include_secrets, true)
+    }
+
+    /// What [`stage`](Self::stage) would leave staged, without touching the index
+    pub fn pending_changes(
+        &self,
+        paths: &[PathBuf],
+        include_secrets: bool,
+    ) -> Result<StagedChanges> {
+        self.update_index(paths, include_secrets, false)
+    }
+
+    /// Add changes under `paths` to the index, writing it back when `write` is set
+    fn update_index(
+        &self,
+        paths: &[PathBuf],
+        include_secrets: bool,
+        write: bool,
+    ) -> Result<StagedChanges> {
         if !self.exists() {
-            return Err(anyhow::anyhow!(
-                "No Git repository found. Run 'ordinator init' first."
-            ));
+            return Err(OrdinatorError::RepositoryNotFound.into());
         }
-        if Self::is_test_mode() {
-            info!("[TEST MODE] Skipping git commit: {}", message);
-            return Ok(());
-        }
-        info!("Committing changes with message: {}", message);
-
         let repo = Repository::open(&self.repo_path).with_context(|| {
             format!("Failed to open repository at {}", self.repo_path.display())
         })?;
 
-        // Get the index
+        let mut pathspecs = Vec::new();
+        for path in paths {
+            let relative = if path.is_absolute() {
+                path.strip_prefix(&self.repo_path).map_err(|_| {
+                    anyhow::anyhow!(
+                        "{} is outside the dotfiles repository {}",
+                        path.display(),
+                        self.repo_path.display()
+                    )
+                })?
+            } else {
+                path.as_path()
+            };
+            pathspecs.push(relative.to_string_lossy().into_owned());
+        }
+        if pathspecs.is_empty() || pathspecs.iter().any(|spec| spec.is_empty()) {
+            pathspecs = vec!["*".to_string()];
+        }
+
+        // Journals and caches committed before they were gitignored stop being tracked
+        crate::config::ignore_machine_state(&self.repo_path)?;
         let mut index = repo
             .index()
             .with_context(|| "Failed to get repository index")?;
-
-        // Add all changes to the index
+        for dir in crate::config::MACHINE_STATE_DIRS {
+            index
+                .remove_dir(Path::new(dir), 0)
+                .with_context(|| format!("Failed to untrack {dir}/"))?;
+        }
+        let mut skip_secrets = |path: &std::path::Path, _: &[u8]| -> i32 {
+            i32::from(!include_secrets && path.starts_with("secrets"))
+        };
         index
-            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .add_all(
+                &pathspecs,
+                git2::IndexAddOption::DEFAULT,
+                Some(&mut skip_secrets),
+            )
             .with_context(|| "Failed to add files to index")?;
+        index
+            .update_all(&pathspecs, Some(&mut skip_secrets))
+            .with_context(|| "Failed to stage deleted files")?;
+        if write {
+            index.write().with_context(|| "Failed to write index")?;
+        }
+
+        let head_tree = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+        let diff = repo
+            .diff_tree_to_index(head_tree.as_ref(), Some(&index), None)
+            .with_context(|| "Failed to compare the index with HEAD")?;
+        let mut staged = StagedChanges::default();
+        for delta in diff.deltas() {
+            let file = if delta.status() == git2::Delta::Deleted {
+                delta.old_file()
+            } else {
+                delta.new_file()
+            };
+            let path = file
+                .path()
+                .map(|path| path.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            match delta.status() {
+                git2::Delta::Added => staged.added.push(path),
+                git2::Delta::Deleted => {
+                    staged.deleted.push(path);
+                    continue;
+                }
+                _ => staged.modified.push(path),
+            }
+            staged.bytes += file.size();
+        }
+        Ok(staged)
+    }
 
-        index.write().with_context(|| "Failed to write index")?;
+    /// Commit whatever is staged
+    pub fn commit_staged(&self, message: &str) -> Result<()> {
+        if !self.exists() {
+            return Err(OrdinatorError::RepositoryNotFound.into());
+        }
+        info!("Committing changes with message: {}", message);
+
+        let repo = Repository::open(&self.repo_path).with_context(|| {
+            format!("Failed to open repository at {}", self.repo_path.display())
+        })?;
+
+        let mut index = repo
+            .index()
+            .with_context(|| "Failed to get repository index")?;
 
         // Get the tree from the index
         let tree_id = index.write_tree().with_context(|| "Failed to write tree")?;
@@ -146,11 +346,12 @@ impl GitManager {
             .and_then(|oid| repo.find_commit(oid).ok());
 
         // Create the commit
-        let signature = repo
-            .signature()
-            .unwrap_or_else(|_| git2::Signature::now("Ordinator", "ordinator@localhost").unwrap());
+        let signature = self.signature(&repo)?;
 
-        let commit_id = if let Some(parent) = parent_commit {
+        let commit_id = if self.config.sign_commits {
+            let parents: Vec<&git2::Commit> = parent_commit.iter().collect();
+            self.create_signed_commit(&repo, &signature, message, &tree, &parents)?
+        } else if let Some(parent) = parent_commit {
             repo.commit(
                 Some("HEAD"),
                 &signature,
@@ -174,44 +375,201 @@ impl GitManager {
         Ok(())
     }
 
-    /// Push changes to remote
-    pub fn push(&self, force: bool) -> Result<()> {
-        if Self::is_test_mode() {
-            info!(
-                "[TEST MODE] Skipping git push{}",
-                if force { " (force)" } else { "" }
-            );
-            return Ok(());
+    /// Author/committer signature: `[git]` user settings first, then the
+    /// ambient git config, then a placeholder
+    fn signature(&self, repo: &Repository) -> Result<git2::Signature<'static>> {
+        let ambient = repo.signature().ok();
+        let name = self
+            .config
+            .user_name
+            .clone()
+            .or_else(|| ambient.as_ref().and_then(|s| s.name().map(String::from)))
+            .unwrap_or_else(|| "Ordinator".to_string());
+        let email = self
+            .config
+            .user_email
+            .clone()
+            .or_else(|| ambient.as_ref().and_then(|s| s.email().map(String::from)))
+            .unwrap_or_else(|| "ordinator@localhost".to_string());
+        git2::Signature::now(&name, &email)
+            .with_context(|| format!("Invalid commit author '{name} <{email}>'"))
+    }
+
+    /// Create a signed commit and move the current branch to it
+    fn create_signed_commit(
+        &self,
+        repo: &Repository,
+        signature: &git2::Signature,
+        message: &str,
+        tree: &git2::Tree,
+        parents: &[&git2::Commit],
+    ) -> Result<git2::Oid> {
+        let commit_id = self.signed_commit_object(repo, signature, message, tree, parents)?;
+
+        // commit_signed does not move any reference, so update the branch HEAD points at
+        let head = repo.find_reference("HEAD")?;
+        let branch = match head.symbolic_target() {
+            Some(branch) => branch.to_string(),
+            None => "HEAD".to_string(),
+        };
+        repo.reference(&branch, commit_id, true, message)
+            .with_context(|| format!("Failed to update {branch}"))?;
+        Ok(commit_id)
+    }
+
+    /// Produce a detached signature for commit content with the configured key
+    /// A signed commit that no reference points at yet
+    fn signed_commit_object(
+        &self,
+        repo: &Repository,
+        signature: &git2::Signature,
+        message: &str,
+        tree: &git2::Tree,
+        parents: &[&git2::Commit],
+    ) -> Result<git2::Oid> {
+        let buffer = repo
+            .commit_create_buffer(signature, signature, message, tree, parents)
+            .with_context(|| "Failed to create commit buffer")?;
+        let content = buffer
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Commit content is not valid UTF-8"))?;
+        let commit_signature = self.sign(content)?;
+        repo.commit_signed(content, &commit_signature, Some("gpgsig"))
+            .with_context(|| "Failed to create signed commit")
+    }
+
+    fn sign(&self, content: &str) -> Result<String> {
+        let mut command = match self.config.signing_format {
+            SigningFormat::Gpg => {
+                let mut command = Command::new("gpg");
+                command.args(["--detach-sign", "--armor"]);
+                if let Some(key) = &self.config.signing_key {
+                    command.args(["--local-user", key]);
+                }
+                command
+            }
+            SigningFormat::Ssh => {
+                let key = self.config.signing_key.as_deref().ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "SSH commit signing needs a key. Set signing_key under [git] in ordinator.toml."
+                    )
+                })?;
+                let key = match key.strip_prefix("~/") {
+                    Some(rest) => crate::utils::get_home_dir()?.join(rest),
+                    None => PathBuf::from(key),
+                };
+                let mut command = Command::new("ssh-keygen");
+                command.args(["-Y", "sign", "-n", "git", "-f"]).arg(key);
+                command
+            }
+        };
+        let program = command.get_program().to_string_lossy().into_owned();
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run {program} to sign the commit"))?;
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(content.as_bytes())?;
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "{} failed to sign the commit: {}",
+                program,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
         }
-        info!("Pushing changes to remote");
+        Ok(String::from_utf8(output.stdout)?)
+    }
+
+    /// Push a branch to `origin`. Without `branch`, pushes the checked-out branch.
+    pub fn push(&self, force: bool, branch: Option<&str>) -> Result<()> {
+        self.push_to("origin", force, branch)
+    }
+
+    /// `origin` followed by each configured remote, with whether it is
+    /// always force-pushed
+    pub fn push_remotes(&self) -> Vec<(String, bool)> {
+        let mut remotes = vec![("origin".to_string(), false)];
+        for remote in &self.config.remotes {
+            match remotes.iter_mut().find(|(name, _)| *name == remote.name) {
+                Some(existing) => existing.1 = remote.force,
+                None => remotes.push((remote.name.clone(), remote.force)),
+            }
+        }
+        remotes
+    }
+
+    /// Push a branch to `origin` and every configured remote. A failure on one
+    /// remote doesn't stop the others; each remote's result is returned.
+    pub fn push_all(&self, force: bool, branch: Option<&str>) -> Vec<(String, bool, Result<()>)> {
+        self.push_remotes()
+            .into_iter()
+            .map(|(name, always_force)| {
+                let force = force || always_force;
+                let result = self.push_to(&name, force, branch);
+                (name, force, result)
+            })
+            .collect()
+    }
+
+    /// Find a remote, adding it (or updating its URL) from `[[git.remotes]]`
+    fn configured_remote<'r>(&self, repo: &'r Repository, name: &str) -> Result<git2::Remote<'r>> {
+        let configured = self
+            .config
+            .remotes
+            .iter()
+            .find(|remote| remote.name == name);
+        match (repo.find_remote(name), configured) {
+            (Ok(remote), Some(configured)) if remote.url() != Some(configured.url.as_str()) => {
+                info!("Updating remote '{}' to {}", name, configured.url);
+                repo.remote_set_url(name, &configured.url)?;
+                Ok(repo.find_remote(name)?)
+            }
+            (Ok(remote), _) => Ok(remote),
+            (Err(_), Some(configured)) => {
+                info!("Adding remote '{}' with URL: {}", name, configured.url);
+                repo.remote(name, &configured.url).with_context(|| {
+                    format!(
+                        "Failed to add remote '{name}' with URL '{}'",
+                        configured.url
+                    )
+                })
+            }
+            (Err(_), None) => Err(OrdinatorError::RemoteNotFound(name.to_string()).into()),
+        }
+    }
+
+    /// Push a branch to the remote `name`. Without `branch`, pushes the
+    /// checked-out branch.
+    pub fn push_to(&self, name: &str, force: bool, branch: Option<&str>) -> Result<()> {
+        crate::environment::remote(name, || self.push_now(name, force, branch))
+    }
+
+    fn push_now(&self, name: &str, force: bool, branch: Option<&str>) -> Result<()> {
+        info!("Pushing changes to remote '{}'", name);
 
         let repo = Repository::open(&self.repo_path).with_context(|| {
             format!("Failed to open repository at {}", self.repo_path.display())
         })?;
 
-        let mut remote = repo
-            .find_remote("origin")
-            .with_context(|| "No remote 'origin' found")?;
-
-        let mut callbacks = git2::RemoteCallbacks::new();
-        callbacks.credentials(|_url, username_from_url, _allowed_types| {
-            git2::Cred::ssh_key(
-                username_from_url.unwrap_or("git"),
-                None,
-                std::path::Path::new(&format!(
-                    "{}/.ssh/id_rsa",
-                    std::env::var("HOME").unwrap_or_default()
-                )),
-                None,
-            )
-        });
+        let mut remote = self.configured_remote(&repo, name)?;
 
+        let url = remote.url().unwrap_or(name).to_string();
+        let methods = crate::auth::AuthMethods::detect(&crate::utils::get_home_dir()?)
+            .with_token_hosts(&self.config.token_hosts);
+        let attempts = RefCell::new(crate::auth::Attempts::default());
         let mut push_options = git2::PushOptions::new();
-        push_options.remote_callbacks(callbacks);
+        push_options.remote_callbacks(crate::auth::callbacks(&methods, &attempts));
 
-        let branch = self
-            .get_default_branch()
-            .unwrap_or_else(|_| "main".to_string());
+        let branch = self.resolve_branch(branch);
+        if repo.find_branch(&branch, git2::BranchType::Local).is_err() {
+            return Err(OrdinatorError::BranchNotFound(branch).into());
+        }
         let refspec = if force {
             format!("+refs/heads/{branch}:refs/heads/{branch}")
         } else {
@@ -220,21 +578,37 @@ impl GitManager {
 
         remote
             .push(&[&refspec], Some(&mut push_options))
-            .with_context(|| "Failed to push to remote")?;
+            .map_err(|e| {
+                let reason = crate::auth::explain(&url, &e, &methods, &attempts.borrow());
+                OrdinatorError::PushFailed {
+                    remote: name.to_string(),
+                    reason: reason.to_string(),
+                }
+            })?;
 
         info!("Changes pushed successfully");
         Ok(())
     }
 
-    /// Pull changes from remote
-    pub fn pull(&self, rebase: bool) -> Result<()> {
-        if Self::is_test_mode() {
-            info!(
-                "[TEST MODE] Skipping git pull{}",
-                if rebase { " (rebase)" } else { "" }
-            );
-            return Ok(());
-        }
+    /// Pull a branch from remote. Without `branch`, pulls the checked-out branch.
+    /// Another branch is fast-forwarded (or created) without switching to it.
+    pub fn pull(&self, rebase: bool, branch: Option<&str>) -> Result<()> {
+        self.pull_with_strategy(rebase, branch, MergeStrategy::Merge)
+    }
+
+    /// Pull like [`GitManager::pull`]. When the branch has diverged from
+    /// origin, the two are merged: `ordinator.toml` semantically, settling
+    /// conflicting values with `strategy`, and other files as git would.
+    pub fn pull_with_strategy(
+        &self,
+        rebase: bool,
+        branch: Option<&str>,
+        strategy: MergeStrategy,
+    ) -> Result<()> {
+        crate::environment::remote("origin", || self.pull_now(rebase, branch, strategy))
+    }
+
+    fn pull_now(&self, rebase: bool, branch: Option<&str>, strategy: MergeStrategy) -> Result<()> {
         info!(
             "Pulling changes from remote{}",
             if rebase { " (rebase)" } else { "" }
@@ -244,35 +618,8 @@ impl GitManager {
             format!("Failed to open repository at {}", self.repo_path.display())
         })?;
 
-        let mut remote = repo
-            .find_remote("origin")
-            .with_context(|| "No remote 'origin' found")?;
-
-        // Fetch from remote
-        let mut callbacks = git2::RemoteCallbacks::new();
-        callbacks.credentials(|_url, username_from_url, _allowed_types| {
-            git2::Cred::ssh_key(
-                username_from_url.unwrap_or("git"),
-                None,
-                std::path::Path::new(&format!(
-                    "{}/.ssh/id_rsa",
-                    std::env::var("HOME").unwrap_or_default()
-                )),
-                None,
-            )
-        });
-
-        let mut fetch_options = git2::FetchOptions::new();
-        fetch_options.remote_callbacks(callbacks);
-
-        let branch = self
-            .get_default_branch()
-            .unwrap_or_else(|_| "main".to_string());
-        let fetch_ref = format!("refs/heads/{branch}:refs/remotes/origin/{branch}");
-
-        remote
-            .fetch(&[&fetch_ref], Some(&mut fetch_options), None)
-            .with_context(|| "Failed to fetch from remote")?;
+        let branch = self.resolve_branch(branch);
+        self.fetch_branch(&repo, &branch, "pull")?;
 
         // Merge or rebase
         let fetch_head = repo
@@ -282,8 +629,28 @@ impl GitManager {
             .reference_to_annotated_commit(&fetch_head)
             .with_context(|| "Failed to get fetch commit")?;
 
+        let branch_ref = format!("refs/heads/{branch}");
+        let is_checked_out = repo
+            .find_reference("HEAD")
+            .ok()
+            .and_then(|head| head.symbolic_target().map(String::from))
+            .as_deref()
+            == Some(branch_ref.as_str());
+
+        let Ok(mut reference) = repo.find_reference(&branch_ref) else {
+            // New local branch at the fetched commit
+            repo.reference(&branch_ref, fetch_commit.id(), false, "Pulled from origin")
+                .with_context(|| format!("Failed to create {branch} branch"))?;
+            if is_checked_out {
+                repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))
+                    .with_context(|| "Failed to checkout HEAD")?;
+            }
+            info!("Created branch '{}' from origin", branch);
+            return Ok(());
+        };
+
         let analysis = repo
-            .merge_analysis(&[&fetch_commit])
+            .merge_analysis_for_ref(&reference, &[&fetch_commit])
             .with_context(|| "Failed to analyze merge")?;
 
         if analysis.0.is_up_to_date() {
@@ -292,27 +659,311 @@ impl GitManager {
         }
 
         if analysis.0.is_fast_forward() {
-            let branch_ref = format!("refs/heads/{branch}");
-            let mut reference = repo
-                .find_reference(&branch_ref)
-                .with_context(|| format!("Failed to find {branch} branch"))?;
             reference
                 .set_target(fetch_commit.id(), "Fast-forward merge")
                 .with_context(|| "Failed to update reference")?;
-            repo.set_head(&branch_ref)
-                .with_context(|| "Failed to set HEAD")?;
-            repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))
-                .with_context(|| "Failed to checkout HEAD")?;
+            if is_checked_out {
+                repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))
+                    .with_context(|| "Failed to checkout HEAD")?;
+            }
         } else {
-            // Handle merge conflicts (simplified for now)
-            warn!("Merge conflicts detected - manual resolution required");
-            return Err(anyhow::anyhow!("Merge conflicts detected"));
+            if is_checked_out && self.has_changes()? {
+                return Err(
+                    anyhow::Error::from(OrdinatorError::UncommittedChanges).context(format!(
+                        "'{branch}' has diverged from origin and there are uncommitted changes."
+                    )),
+                );
+            }
+            let merge_id =
+                self.merge_diverged(&repo, &reference, fetch_commit.id(), &branch, strategy)?;
+            reference
+                .set_target(merge_id, &format!("Merge origin/{branch}"))
+                .with_context(|| "Failed to update reference")?;
+            if is_checked_out {
+                repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))
+                    .with_context(|| "Failed to checkout HEAD")?;
+            }
         }
 
         info!("Changes pulled successfully");
         Ok(())
     }
 
+    /// Update `origin/<branch>` from origin without touching the local branch,
+    /// so [`GitManager::remote_statuses`] and
+    /// [`GitManager::config_differs_from_remote`] see what's there now
+    pub fn fetch(&self, branch: Option<&str>) -> Result<()> {
+        crate::environment::remote("origin", || {
+            let repo = Repository::open(&self.repo_path).with_context(|| {
+                format!("Failed to open repository at {}", self.repo_path.display())
+            })?;
+            self.fetch_branch(&repo, &self.resolve_branch(branch), "fetch")
+        })
+    }
+
+    /// Fetch `branch` from origin into `origin/<branch>` and `FETCH_HEAD`
+    fn fetch_branch(&self, repo: &Repository, branch: &str, label: &'static str) -> Result<()> {
+        let mut remote = repo
+            .find_remote("origin")
+            .with_context(|| "No remote 'origin' found")?;
+        let url = remote.url().unwrap_or("origin").to_string();
+        let methods = crate::auth::AuthMethods::detect(&crate::utils::get_home_dir()?)
+            .with_token_hosts(&self.config.token_hosts);
+        let attempts = RefCell::new(crate::auth::Attempts::default());
+        let fetch_ref = format!("refs/heads/{branch}:refs/remotes/origin/{branch}");
+
+        // Retried on flaky networks, with a progress bar for large fetches
+        let bar = crate::transfer::TransferBar::start(label);
+        let fetched = crate::transfer::retry(crate::transfer::DEFAULT_RETRIES, "Fetch", || {
+            *attempts.borrow_mut() = crate::auth::Attempts::default();
+            let mut callbacks = crate::auth::callbacks(&methods, &attempts);
+            bar.attach(&mut callbacks);
+            let mut fetch_options = git2::FetchOptions::new();
+            fetch_options.remote_callbacks(callbacks);
+            remote.fetch(&[&fetch_ref], Some(&mut fetch_options), None)
+        });
+        bar.finish();
+        fetched.map_err(|e| {
+            let reason = crate::auth::explain(&url, &e, &methods, &attempts.borrow());
+            OrdinatorError::FetchFailed(reason.to_string()).into()
+        })
+    }
+
+    /// Whether `ordinator.toml` in the working tree differs from the copy on
+    /// `origin/<branch>` for the checked-out branch, as of the last fetch or
+    /// push. None when there is no repository or origin has no copy of the branch.
+    pub fn config_differs_from_remote(&self) -> Result<Option<bool>> {
+        let Ok(repo) = Repository::open(&self.repo_path) else {
+            return Ok(None);
+        };
+        let branch = self.resolve_branch(None);
+        let Ok(tracking) = repo.find_reference(&format!("refs/remotes/origin/{branch}")) else {
+            return Ok(None);
+        };
+        let remote_config = match tracking
+            .peel_to_tree()?
+            .get_path(std::path::Path::new(CONFIG_FILE))
+        {
+            Ok(entry) => Some(repo.find_blob(entry.id())?.content().to_vec()),
+            Err(_) => None,
+        };
+        let local_config = fs::read(self.repo_path.join(CONFIG_FILE)).ok();
+        Ok(Some(remote_config != local_config))
+    }
+
+    /// Create the merge commit of `reference` and the fetched commit, without
+    /// moving any reference. Files other than `ordinator.toml` that conflict
+    /// stop the merge.
+    fn merge_diverged(
+        &self,
+        repo: &Repository,
+        reference: &git2::Reference,
+        fetched: git2::Oid,
+        branch: &str,
+        strategy: MergeStrategy,
+    ) -> Result<git2::Oid> {
+        let ours = reference
+            .peel_to_commit()
+            .with_context(|| format!("Failed to find the tip of '{branch}'"))?;
+        let theirs = repo.find_commit(fetched)?;
+        let mut index = repo
+            .merge_commits(&ours, &theirs, None)
+            .with_context(|| format!("Failed to merge origin/{branch}"))?;
+
+        // A line-by-line merge of the config can succeed and still leave
+        // duplicate tables, so it is always merged by key when both sides changed it
+        let config_path = std::path::Path::new(CONFIG_FILE);
+        let config_blob = |commit: &git2::Commit| -> Result<Option<String>> {
+            match commit.tree()?.get_path(config_path) {
+                Ok(entry) => {
+                    let blob = repo.find_blob(entry.id())?;
+                    Ok(Some(String::from_utf8_lossy(blob.content()).into_owned()))
+                }
+                Err(_) => Ok(None),
+            }
+        };
+        let base_id = repo.merge_base(ours.id(), theirs.id())?;
+        let base = config_blob(&repo.find_commit(base_id)?)?;
+        if let (Some(our_config), Some(their_config)) = (config_blob(&ours)?, config_blob(&theirs)?)
+        {
+            let base = base.unwrap_or_default();
+            if our_config != their_config && base != our_config && base != their_config {
+                let merged = merge_files(&base, &our_config, &their_config, strategy)?;
+                let mode = ours.tree()?.get_path(config_path)?.filemode() as u32;
+                let _ = index.conflict_remove(config_path);
+                let entry = git2::IndexEntry {
+                    ctime: git2::IndexTime::new(0, 0),
+                    mtime: git2::IndexTime::new(0, 0),
+                    dev: 0,
+                    ino: 0,
+                    mode,
+                    uid: 0,
+                    gid: 0,
+                    file_size: merged.len() as u32,
+                    id: repo.blob(merged.as_bytes())?,
+                    flags: 0,
+                    flags_extended: 0,
+                    path: CONFIG_FILE.as_bytes().to_vec(),
+                };
+                index
+                    .add(&entry)
+                    .with_context(|| format!("Failed to stage the merged {CONFIG_FILE}"))?;
+            }
+        }
+
+        if index.has_conflicts() {
+            let mut paths = Vec::new();
+            for conflict in index.conflicts()? {
+                let conflict = conflict?;
+                if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+                    paths.push(String::from_utf8_lossy(&entry.path).into_owned());
+                }
+            }
+            warn!("Merge conflicts in {}", paths.join(", "));
+            return Err(OrdinatorError::MergeConflicts {
+                paths,
+                repo: self.repo_path.clone(),
+            }
+            .into());
+        }
+
+        let tree_id = index
+            .write_tree_to(repo)
+            .with_context(|| "Failed to write the merged tree")?;
+        let tree = repo.find_tree(tree_id)?;
+        let signature = self.signature(repo)?;
+        let message = format!("Merge origin/{branch} into {branch}");
+        if self.config.sign_commits {
+            self.signed_commit_object(repo, &signature, &message, &tree, &[&ours, &theirs])
+        } else {
+            repo.commit(
+                None,
+                &signature,
+                &signature,
+                &message,
+                &tree,
+                &[&ours, &theirs],
+            )
+            .with_context(|| "Failed to create merge commit")
+        }
+    }
+
+    /// Branch to push or pull: the given one, else the checked-out branch,
+    /// else the default branch
+    pub fn resolve_branch(&self, branch: Option<&str>) -> String {
+        if let Some(branch) = branch {
+            return branch.to_string();
+        }
+        if let Ok(Some(current)) = self.current_branch() {
+            return current;
+        }
+        self.get_default_branch()
+            .unwrap_or_else(|_| "main".to_string())
+    }
+
+    /// Name of the checked-out branch (None when HEAD is detached)
+    pub fn current_branch(&self) -> Result<Option<String>> {
+        let repo = Repository::open(&self.repo_path).with_context(|| {
+            format!("Failed to open repository at {}", self.repo_path.display())
+        })?;
+        let head = repo.find_reference("HEAD")?;
+        Ok(head
+            .symbolic_target()
+            .and_then(|target| target.strip_prefix("refs/heads/"))
+            .map(String::from))
+    }
+
+    /// Short hash of the commit HEAD points at (None without commits)
+    pub fn head_commit(&self) -> Result<Option<String>> {
+        let repo = Repository::open(&self.repo_path).with_context(|| {
+            format!("Failed to open repository at {}", self.repo_path.display())
+        })?;
+        let Ok(commit) = repo.head().and_then(|head| head.peel_to_commit()) else {
+            return Ok(None);
+        };
+        Ok(Some(commit.id().to_string()[..7].to_string()))
+    }
+
+    /// Local branches, with whether each is checked out
+    pub fn list_branches(&self) -> Result<Vec<(String, bool)>> {
+        let repo = Repository::open(&self.repo_path).with_context(|| {
+            format!("Failed to open repository at {}", self.repo_path.display())
+        })?;
+        let mut branches = Vec::new();
+        for branch in repo.branches(Some(git2::BranchType::Local))? {
+            let (branch, _) = branch?;
+            if let Some(name) = branch.name()? {
+                branches.push((name.to_string(), branch.is_head()));
+            }
+        }
+        Ok(branches)
+    }
+
+    /// Create a branch at `from` (a branch or commit; defaults to HEAD) and switch to it
+    pub fn create_branch(&self, name: &str, from: Option<&str>) -> Result<()> {
+        if !git2::Branch::name_is_valid(name)? {
+            return Err(OrdinatorError::InvalidBranchName(name.to_string()).into());
+        }
+        self.ensure_clean()?;
+        let repo = Repository::open(&self.repo_path).with_context(|| {
+            format!("Failed to open repository at {}", self.repo_path.display())
+        })?;
+        if repo.find_branch(name, git2::BranchType::Local).is_ok() {
+            return Err(OrdinatorError::BranchExists(name.to_string()).into());
+        }
+        let start = match from {
+            Some(from) => repo
+                .revparse_single(from)
+                .and_then(|object| object.peel_to_commit())
+                .with_context(|| format!("Unknown branch or commit '{from}'"))?,
+            None => repo
+                .head()
+                .and_then(|head| head.peel_to_commit())
+                .with_context(|| "The repository has no commits yet")?,
+        };
+        repo.branch(name, &start, false)
+            .with_context(|| format!("Failed to create branch '{name}'"))?;
+        info!("Created branch '{}'", name);
+        self.switch_branch(name)
+    }
+
+    /// Refuse to switch branches over uncommitted changes
+    fn ensure_clean(&self) -> Result<()> {
+        if self.has_changes()? {
+            return Err(OrdinatorError::UncommittedChanges.into());
+        }
+        Ok(())
+    }
+
+    /// Check out an existing branch. A branch that only exists on origin is
+    /// created locally first.
+    pub fn switch_branch(&self, name: &str) -> Result<()> {
+        self.ensure_clean()?;
+        let repo = Repository::open(&self.repo_path).with_context(|| {
+            format!("Failed to open repository at {}", self.repo_path.display())
+        })?;
+        if repo.find_branch(name, git2::BranchType::Local).is_err() {
+            let remote = repo
+                .find_branch(&format!("origin/{name}"), git2::BranchType::Remote)
+                .map_err(|_| OrdinatorError::BranchNotFound(name.to_string()))?;
+            let commit = remote.get().peel_to_commit()?;
+            let mut local = repo.branch(name, &commit, false)?;
+            local.set_upstream(Some(&format!("origin/{name}")))?;
+        }
+
+        let branch_ref = format!("refs/heads/{name}");
+        let target = repo.revparse_single(&branch_ref)?;
+        repo.checkout_tree(
+            &target,
+            Some(git2::build::CheckoutBuilder::default().safe()),
+        )
+        .with_context(|| format!("Failed to check out '{name}'"))?;
+        repo.set_head(&branch_ref)
+            .with_context(|| "Failed to set HEAD")?;
+        info!("Switched to branch '{}'", name);
+        Ok(())
+    }
+
     /// Get repository status
     pub fn status(&self) -> Result<String> {
         let repo = Repository::open(&self.repo_path).with_context(|| {
@@ -370,23 +1021,72 @@ impl GitManager {
         Ok(output)
     }
 
+    /// `origin` and the configured remotes, compared with the checked-out
+    /// branch. Uses the remote-tracking branches, so nothing is fetched.
+    pub fn remote_statuses(&self) -> Result<Vec<RemoteStatus>> {
+        let configured_url = |name: &str| {
+            self.config
+                .remotes
+                .iter()
+                .find(|remote| remote.name == name)
+                .map(|remote| remote.url.clone())
+        };
+        let repo = Repository::open(&self.repo_path).with_context(|| {
+            format!("Failed to open repository at {}", self.repo_path.display())
+        })?;
+
+        let branch = self.resolve_branch(None);
+        let local = repo
+            .find_branch(&branch, git2::BranchType::Local)
+            .ok()
+            .and_then(|branch| branch.get().target());
+        let mut statuses = Vec::new();
+        for (name, force) in self.push_remotes() {
+            let url = match repo.find_remote(&name) {
+                Ok(remote) => remote.url().map(String::from),
+                Err(_) => configured_url(&name),
+            };
+            let tracking = repo
+                .find_reference(&format!("refs/remotes/{name}/{branch}"))
+                .ok()
+                .and_then(|reference| reference.target());
+            let ahead_behind = match (local, tracking) {
+                (Some(local), Some(tracking)) => repo.graph_ahead_behind(local, tracking).ok(),
+                _ => None,
+            };
+            statuses.push(RemoteStatus {
+                name,
+                url,
+                force,
+                ahead_behind,
+            });
+        }
+        Ok(statuses)
+    }
+
+    /// Check whether the working tree has uncommitted changes
+    pub fn has_changes(&self) -> Result<bool> {
+        let repo = Repository::open(&self.repo_path).with_context(|| {
+            format!("Failed to open repository at {}", self.repo_path.display())
+        })?;
+
+        let mut status_options = git2::StatusOptions::new();
+        status_options.include_untracked(true);
+        status_options.include_ignored(false);
+
+        let statuses = repo
+            .statuses(Some(&mut status_options))
+            .with_context(|| "Failed to get repository status")?;
+        Ok(!statuses.is_empty())
+    }
+
     /// Check if repository exists
     pub fn exists(&self) -> bool {
-        if Self::is_test_mode() {
-            // In test mode, just check if .git directory exists
-            // since init() skips actual Git repository creation
-            self.repo_path.join(".git").exists()
-        } else {
-            Repository::open(&self.repo_path).is_ok()
-        }
+        Repository::open(&self.repo_path).is_ok()
     }
 
     /// Get the origin remote URL
     pub fn get_origin_url(&self) -> Result<Option<String>> {
-        if Self::is_test_mode() {
-            return Ok(Some("https://github.com/testuser/dotfiles.git".to_string()));
-        }
-
         let repo = match Repository::open(&self.repo_path) {
             Ok(repo) => repo,
             Err(_) => return Ok(None),
@@ -402,10 +1102,6 @@ impl GitManager {
 
     /// Warn if no remote is set (for README generation)
     fn warn_if_no_remote_set(&self) {
-        if Self::is_test_mode() {
-            return;
-        }
-
         if let Ok(repo) = Repository::open(&self.repo_path) {
             if repo.find_remote("origin").is_err() {
                 eprintln!("⚠️  Warning: No remote 'origin' set");
@@ -416,11 +1112,10 @@ impl GitManager {
         }
     }
 
-    /// Detect the default branch name (remote HEAD or fallback to 'main')
+    /// Detect the default branch name (`[git] default_branch`, remote HEAD, or fallback to 'main')
     pub fn get_default_branch(&self) -> Result<String> {
-        if Self::is_test_mode() {
-            // In test mode, default to 'master' for legacy compatibility
-            return Ok("master".to_string());
+        if let Some(branch) = &self.config.default_branch {
+            return Ok(branch.clone());
         }
         let repo = Repository::open(&self.repo_path).with_context(|| {
             format!("Failed to open repository at {}", self.repo_path.display())
@@ -527,6 +1222,266 @@ mod tests {
         assert_eq!(commit.message().unwrap(), "Initial commit");
     }
 
+    #[test]
+    fn test_commit_untracks_machine_state_in_existing_repos() {
+        let temp_dir = tempdir().unwrap();
+        let git_manager = GitManager::new(temp_dir.path().to_path_buf());
+        git_manager.init().unwrap();
+        fs::write(temp_dir.path().join(".gitignore"), "*.log\n").unwrap();
+        fs::create_dir_all(temp_dir.path().join("cache")).unwrap();
+        fs::write(temp_dir.path().join("cache/secret-scan.json"), "{}").unwrap();
+        let repo = Repository::open(temp_dir.path()).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("cache/secret-scan.json")).unwrap();
+        index.write().unwrap();
+
+        fs::write(temp_dir.path().join("test.txt"), "test content").unwrap();
+        git_manager.commit("Add test file").unwrap();
+
+        let tree = repo.head().unwrap().peel_to_tree().unwrap();
+        assert!(tree.get_path(Path::new("test.txt")).is_ok());
+        assert!(tree.get_path(Path::new("cache/secret-scan.json")).is_err());
+        assert!(temp_dir.path().join("cache/secret-scan.json").exists());
+        let gitignore = fs::read_to_string(temp_dir.path().join(".gitignore")).unwrap();
+        assert!(gitignore.contains("\ncache/\n"));
+    }
+
+    #[test]
+    fn test_commit_uses_configured_author() {
+        let temp_dir = tempdir().unwrap();
+        let git_manager = GitManager::new(temp_dir.path().to_path_buf()).with_config(GitConfig {
+            user_name: Some("Dot Files".to_string()),
+            user_email: Some("dots@example.com".to_string()),
+            ..Default::default()
+        });
+        git_manager.init().unwrap();
+        fs::write(temp_dir.path().join("test.txt"), "test content").unwrap();
+        git_manager.commit("Add test file").unwrap();
+
+        let repo = Repository::open(temp_dir.path()).unwrap();
+        let commit = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(commit.author().name(), Some("Dot Files"));
+        assert_eq!(commit.committer().email(), Some("dots@example.com"));
+    }
+
+    #[test]
+    fn test_ssh_signing_requires_key() {
+        let temp_dir = tempdir().unwrap();
+        let git_manager = GitManager::new(temp_dir.path().to_path_buf()).with_config(GitConfig {
+            signing_format: SigningFormat::Ssh,
+            sign_commits: true,
+            ..Default::default()
+        });
+        git_manager.init().unwrap();
+        fs::write(temp_dir.path().join("test.txt"), "test content").unwrap();
+        let err = git_manager.commit("Signed").unwrap_err();
+        assert!(err.to_string().contains("SSH commit signing needs a key"));
+    }
+
+    #[test]
+    fn test_push_all_adds_configured_remotes() {
+        let temp_dir = tempdir().unwrap();
+        let origin_path = temp_dir.path().join("origin.git");
+        let mirror_path = temp_dir.path().join("mirror.git");
+        Repository::init_bare(&origin_path).unwrap();
+        Repository::init_bare(&mirror_path).unwrap();
+
+        let repo_path = temp_dir.path().join("dotfiles");
+        let git_manager = GitManager::new(repo_path.clone()).with_config(GitConfig {
+            remotes: vec![RemoteConfig {
+                name: "mirror".to_string(),
+                url: mirror_path.to_string_lossy().into_owned(),
+                force: true,
+            }],
+            ..Default::default()
+        });
+        git_manager.init().unwrap();
+        git_manager
+            .add_remote("origin", &origin_path.to_string_lossy())
+            .unwrap();
+        assert_eq!(
+            git_manager.push_remotes(),
+            [("origin".to_string(), false), ("mirror".to_string(), true)]
+        );
+        let statuses = git_manager.remote_statuses().unwrap();
+        assert_eq!(statuses[1].ahead_behind, None);
+
+        // The mirror is added on first push and force-pushed every time
+        for (name, forced, result) in git_manager.push_all(false, None) {
+            result.unwrap();
+            assert_eq!(forced, name == "mirror");
+        }
+        let mirror = Repository::open_bare(&mirror_path).unwrap();
+        assert!(mirror.find_reference("refs/heads/main").is_ok());
+
+        fs::write(repo_path.join("test.txt"), "test content").unwrap();
+        git_manager.commit("Add test file").unwrap();
+        let statuses = git_manager.remote_statuses().unwrap();
+        assert_eq!(statuses[0].ahead_behind, Some((1, 0)));
+        assert_eq!(statuses[1].ahead_behind, Some((1, 0)));
+        assert!(statuses[1].force);
+    }
+
+    #[test]
+    fn test_machine_branch_push_and_pull() {
+        let temp_dir = tempdir().unwrap();
+        let remote_path = temp_dir.path().join("remote.git");
+        Repository::init_bare(&remote_path).unwrap();
+        let remote_url = remote_path.to_string_lossy().into_owned();
+
+        // First machine: commit on main, then work on its own branch
+        let laptop_path = temp_dir.path().join("laptop");
+        let laptop = GitManager::new(laptop_path.clone());
+        laptop.init().unwrap();
+        laptop.add_remote("origin", &remote_url).unwrap();
+        fs::write(laptop_path.join("shared.txt"), "shared").unwrap();
+        laptop.commit("Add shared file").unwrap();
+        laptop.push(false, Some("main")).unwrap();
+
+        laptop.create_branch("laptop", None).unwrap();
+        assert_eq!(laptop.current_branch().unwrap().as_deref(), Some("laptop"));
+        assert!(laptop.create_branch("laptop", None).is_err());
+        assert!(laptop.create_branch("bad..name", None).is_err());
+        fs::write(laptop_path.join("laptop.txt"), "one").unwrap();
+        laptop.commit("Laptop change").unwrap();
+        laptop.push(false, None).unwrap();
+
+        let remote = Repository::open_bare(&remote_path).unwrap();
+        assert!(remote.find_reference("refs/heads/laptop").is_ok());
+        assert!(remote.find_reference("refs/heads/main").is_ok());
+
+        // Switching back to main leaves the branch's files behind
+        laptop.switch_branch("main").unwrap();
+        assert!(!laptop_path.join("laptop.txt").exists());
+        let branches = laptop.list_branches().unwrap();
+        assert!(branches.contains(&("main".to_string(), true)));
+        assert!(branches.contains(&("laptop".to_string(), false)));
+
+        // Second clone picks the branch up from origin and fast-forwards it
+        let clone_path = temp_dir.path().join("clone");
+        Repository::clone(&remote_url, &clone_path).unwrap();
+        let clone = GitManager::new(clone_path.clone());
+        clone.switch_branch("laptop").unwrap();
+        assert_eq!(
+            fs::read_to_string(clone_path.join("laptop.txt")).unwrap(),
+            "one"
+        );
+
+        laptop.switch_branch("laptop").unwrap();
+        fs::write(laptop_path.join("laptop.txt"), "two").unwrap();
+        laptop.commit("Another laptop change").unwrap();
+        laptop.push(false, None).unwrap();
+        clone.pull(false, None).unwrap();
+        assert_eq!(
+            fs::read_to_string(clone_path.join("laptop.txt")).unwrap(),
+            "two"
+        );
+
+        // Pulling another branch updates it without switching
+        clone.pull(false, Some("main")).unwrap();
+        assert_eq!(clone.current_branch().unwrap().as_deref(), Some("laptop"));
+    }
+
+    #[test]
+    fn test_pull_merges_diverged_config() {
+        let temp_dir = tempdir().unwrap();
+        let remote_path = temp_dir.path().join("remote.git");
+        Repository::init_bare(&remote_path).unwrap();
+        let remote_url = remote_path.to_string_lossy().into_owned();
+
+        let laptop_path = temp_dir.path().join("laptop");
+        let laptop = GitManager::new(laptop_path.clone());
+        laptop.init().unwrap();
+        laptop.add_remote("origin", &remote_url).unwrap();
+        let config = "[global]\ndefault_profile = \"default\"\n\n[profiles.default]\nfiles = [\"~/.zshrc\"]\n";
+        fs::write(laptop_path.join(CONFIG_FILE), config).unwrap();
+        laptop.commit("Add config").unwrap();
+        laptop.push(false, Some("main")).unwrap();
+
+        let desktop_path = temp_dir.path().join("desktop");
+        Repository::clone(&remote_url, &desktop_path).unwrap();
+        let desktop = GitManager::new(desktop_path.clone());
+        desktop.switch_branch("main").unwrap();
+
+        fs::write(
+            laptop_path.join(CONFIG_FILE),
+            config.replace("[\"~/.zshrc\"]", "[\"~/.zshrc\", \"~/.gitconfig\"]")
+                + "\n[profiles.work]\nfiles = []\n"
+                + "\n[git]\nuser_name = \"Laptop\"\n",
+        )
+        .unwrap();
+        laptop.commit("Track gitconfig").unwrap();
+        laptop.push(false, Some("main")).unwrap();
+
+        fs::write(
+            desktop_path.join(CONFIG_FILE),
+            config.replace("[\"~/.zshrc\"]", "[\"~/.zshrc\", \"~/.vimrc\"]")
+                + "\n[git]\nuser_name = \"Desktop\"\n",
+        )
+        .unwrap();
+        desktop.commit("Track vimrc").unwrap();
+
+        desktop
+            .pull_with_strategy(false, None, MergeStrategy::Theirs)
+            .unwrap();
+        let merged: crate::config::Config =
+            toml::from_str(&fs::read_to_string(desktop_path.join(CONFIG_FILE)).unwrap()).unwrap();
+        assert_eq!(
+            merged.profiles["default"].files,
+            ["~/.zshrc", "~/.vimrc", "~/.gitconfig"]
+        );
+        assert!(merged.profiles.contains_key("work"));
+        assert_eq!(merged.git.user_name.as_deref(), Some("Laptop"));
+
+        let repo = Repository::open(&desktop_path).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.parent_count(), 2);
+        assert!(!desktop.has_changes().unwrap());
+    }
+
+    #[test]
+    fn test_fetch_updates_remote_comparison() {
+        let temp_dir = tempdir().unwrap();
+        let remote_path = temp_dir.path().join("remote.git");
+        Repository::init_bare(&remote_path).unwrap();
+        let remote_url = remote_path.to_string_lossy().into_owned();
+
+        let laptop_path = temp_dir.path().join("laptop");
+        let laptop = GitManager::new(laptop_path.clone());
+        laptop.init().unwrap();
+        assert_eq!(laptop.config_differs_from_remote().unwrap(), None);
+        laptop.add_remote("origin", &remote_url).unwrap();
+        fs::write(laptop_path.join(CONFIG_FILE), "[profiles.default]\n").unwrap();
+        laptop.commit("Add config").unwrap();
+        laptop.push(false, Some("main")).unwrap();
+        assert_eq!(laptop.config_differs_from_remote().unwrap(), Some(false));
+
+        let desktop_path = temp_dir.path().join("desktop");
+        Repository::clone(&remote_url, &desktop_path).unwrap();
+        let desktop = GitManager::new(desktop_path.clone());
+        desktop.switch_branch("main").unwrap();
+
+        fs::write(
+            laptop_path.join(CONFIG_FILE),
+            "[profiles.default]\nfiles = [\"~/.zshrc\"]\n",
+        )
+        .unwrap();
+        laptop.commit("Track zshrc").unwrap();
+        laptop.push(false, Some("main")).unwrap();
+
+        // Nothing changes on the desktop until it fetches
+        let origin = |manager: &GitManager| manager.remote_statuses().unwrap()[0].ahead_behind;
+        assert_eq!(origin(&desktop), Some((0, 0)));
+        assert_eq!(desktop.config_differs_from_remote().unwrap(), Some(false));
+        desktop.fetch(None).unwrap();
+        assert_eq!(origin(&desktop), Some((0, 1)));
+        assert_eq!(desktop.config_differs_from_remote().unwrap(), Some(true));
+        assert_eq!(
+            fs::read_to_string(desktop_path.join(CONFIG_FILE)).unwrap(),
+            "[profiles.default]\n"
+        );
+    }
+
     #[test]
     fn test_status_functionality() {
         let temp_dir = tempdir().unwrap();
@@ -587,6 +1542,38 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_stage_selected_paths_and_skip_secrets() {
+        let temp_dir = tempdir().unwrap();
+        let git_manager = GitManager::new(temp_dir.path().to_path_buf());
+        git_manager.init().unwrap();
+        fs::create_dir_all(temp_dir.path().join("files")).unwrap();
+        fs::write(temp_dir.path().join("files/kept.txt"), "kept").unwrap();
+        fs::write(temp_dir.path().join("files/gone.txt"), "gone").unwrap();
+        git_manager.commit("Initial commit").unwrap();
+
+        fs::write(temp_dir.path().join("files/kept.txt"), "changed").unwrap();
+        fs::remove_file(temp_dir.path().join("files/gone.txt")).unwrap();
+        fs::write(temp_dir.path().join("files/new.txt"), "new file").unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "elsewhere").unwrap();
+        fs::create_dir_all(temp_dir.path().join("secrets/default")).unwrap();
+        fs::write(temp_dir.path().join("secrets/default/key.enc"), "sops").unwrap();
+
+        let staged = git_manager.stage(&[PathBuf::from("files")], false).unwrap();
+        assert_eq!(staged.added, vec!["files/new.txt"]);
+        assert_eq!(staged.modified, vec!["files/kept.txt"]);
+        assert_eq!(staged.deleted, vec!["files/gone.txt"]);
+        assert_eq!(staged.bytes, ("changed".len() + "new file".len()) as u64);
+
+        let staged = git_manager.stage(&[], false).unwrap();
+        assert!(staged.added.contains(&"notes.txt".to_string()));
+        assert!(!staged.added.iter().any(|path| path.starts_with("secrets/")));
+        let staged = git_manager.stage(&[], true).unwrap();
+        assert!(staged
+            .added
+            .contains(&"secrets/default/key.enc".to_string()));
+    }
+
     // Error handling and edge case tests
 
     #[test]
@@ -688,7 +1675,7 @@ mod tests {
         git_manager.init().unwrap();
 
         // Try to push without adding a remote
-        let result = git_manager.push(false);
+        let result = git_manager.push(false, None);
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -702,7 +1689,7 @@ mod tests {
         let git_manager = GitManager::new(temp_dir.path().to_path_buf());
 
         // Try to push without initializing repository
-        let result = git_manager.push(false);
+        let result = git_manager.push(false, None);
         assert!(result.is_err());
     }
 
@@ -714,7 +1701,7 @@ mod tests {
         git_manager.init().unwrap();
 
         // Try to pull without adding a remote
-        let result = git_manager.pull(false);
+        let result = git_manager.pull(false, None);
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -728,7 +1715,7 @@ mod tests {
         let git_manager = GitManager::new(temp_dir.path().to_path_buf());
 
         // Try to pull without initializing repository
-        let result = git_manager.pull(false);
+        let result = git_manager.pull(false, None);
         assert!(result.is_err());
     }
 
@@ -906,7 +1893,7 @@ mod tests {
 
         // The push will fail due to network issues, but we can verify the method handles the force flag
         if result.is_ok() {
-            let push_result = git_manager.push(true);
+            let push_result = git_manager.push(true, None);
             // Should fail due to network/authentication, but not due to force flag logic
             assert!(push_result.is_err());
         }
@@ -924,7 +1911,7 @@ mod tests {
 
         // The pull will fail due to network issues, but we can verify the method handles the rebase flag
         if result.is_ok() {
-            let pull_result = git_manager.pull(true);
+            let pull_result = git_manager.pull(true, None);
             // Should fail due to network/authentication, but not due to rebase flag logic
             assert!(pull_result.is_err());
         }
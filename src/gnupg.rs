@@ -0,0 +1,255 @@
+//! `ordinator gpg`: `gpg.conf` and `gpg-agent.conf` tracked like other files,
+//! private keys exported and kept as encrypted secrets under
+//! `secrets/<profile>/gnupg/`, and on apply imported into the keyring,
+//! trusted ultimately, and set up as git's signing key.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::environment::{self, Effect};
+
+/// Tracked paths of the GnuPG config files, relative to the home directory
+pub const CONF_FILES: [&str; 2] = [".gnupg/gpg.conf", ".gnupg/gpg-agent.conf"];
+
+/// Directory under `secrets/<profile>/` holding exported private keys
+pub const STORAGE_DIR: &str = "gnupg";
+
+/// GPG keys and git signing for a profile, under `[profiles.<name>.gpg]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GpgConfig {
+    /// Fingerprints of the private keys stored for this profile, imported on apply
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<String>,
+
+    /// Key git signs commits and tags with, set up on apply
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_key: Option<String>,
+}
+
+impl GpgConfig {
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.signing_key.is_none()
+    }
+}
+
+/// Where the encrypted export of the key `fingerprint` is stored
+pub fn stored_key_path(dotfiles_dir: &Path, profile: &str, fingerprint: &str) -> PathBuf {
+    dotfiles_dir
+        .join("secrets")
+        .join(profile)
+        .join(STORAGE_DIR)
+        .join(format!("{fingerprint}.asc"))
+}
+
+/// The keyring apply imports into: `.gnupg` under the target root
+pub fn home_dir(target_root: &Path) -> PathBuf {
+    target_root.join(".gnupg")
+}
+
+fn gpg(gnupg_home: &Path) -> Result<Command> {
+    let gpg = which::which("gpg").map_err(|_| {
+        anyhow::anyhow!(
+            "gpg is not installed or not found in PATH. Install it with 'brew install gnupg'."
+        )
+    })?;
+    let mut command = Command::new(gpg);
+    command.arg("--homedir").arg(gnupg_home);
+    Ok(command)
+}
+
+/// Run `command` with `input` on stdin, returning stdout
+fn run(mut command: Command, input: Option<&[u8]>, effect: Effect, what: &str) -> Result<Vec<u8>> {
+    command.stderr(Stdio::piped());
+    let output = match input {
+        Some(input) => environment::output_with_input(&mut command, input, effect),
+        None => environment::output(command.stdin(Stdio::null()), effect),
+    }
+    .with_context(|| format!("Failed to run gpg to {what}"))?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "gpg failed to {what}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// The first fingerprint in `gpg --with-colons` output
+pub fn parse_fingerprint(colons: &str) -> Option<String> {
+    colons
+        .lines()
+        .find(|line| line.starts_with("fpr:"))
+        .and_then(|line| line.split(':').nth(9))
+        .filter(|fpr| !fpr.is_empty())
+        .map(str::to_string)
+}
+
+/// Fingerprint of the secret key `key_id` (an id, fingerprint, or email)
+/// in the keyring at `gnupg_home`
+pub fn fingerprint(gnupg_home: &Path, key_id: &str) -> Result<String> {
+    let mut command = gpg(gnupg_home)?;
+    command.args(["--batch", "--with-colons", "--list-secret-keys", key_id]);
+    let stdout = run(
+        command,
+        None,
+        Effect::Reads,
+        &format!("find secret key '{key_id}'"),
+    )?;
+    parse_fingerprint(&String::from_utf8_lossy(&stdout))
+        .ok_or_else(|| anyhow::anyhow!("No secret key '{key_id}' in {}", gnupg_home.display()))
+}
+
+/// Whether the keyring at `gnupg_home` holds the secret key `fingerprint`
+pub fn has_secret_key(gnupg_home: &Path, fingerprint: &str) -> bool {
+    gpg(gnupg_home).is_ok_and(|mut command| {
+        command
+            .args(["--batch", "--list-secret-keys", fingerprint])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        environment::status(&mut command, Effect::Reads).is_ok_and(|status| status.success())
+    })
+}
+
+/// The secret key `fingerprint`, ASCII-armored. gpg asks for its passphrase.
+pub fn export_secret_key(gnupg_home: &Path, fingerprint: &str) -> Result<Vec<u8>> {
+    let mut command = gpg(gnupg_home)?;
+    command.args(["--armor", "--export-secret-keys", fingerprint]);
+    let armored = run(
+        command,
+        None,
+        Effect::Reads,
+        &format!("export secret key {fingerprint}"),
+    )?;
+    if armored.is_empty() {
+        return Err(anyhow::anyhow!("gpg exported nothing for {fingerprint}"));
+    }
+    Ok(armored)
+}
+
+/// Import an exported key into the keyring at `gnupg_home`, then trust it
+/// ultimately, as your own keys are
+pub fn import(gnupg_home: &Path, fingerprint: &str, armored: &[u8]) -> Result<()> {
+    if !gnupg_home.exists() {
+        fs::create_dir_all(gnupg_home)?;
+        crate::utils::set_file_mode(gnupg_home, 0o700)?;
+    }
+    let mut command = gpg(gnupg_home)?;
+    command.args(["--batch", "--import"]);
+    run(
+        command,
+        Some(armored),
+        Effect::Changes,
+        &format!("import {fingerprint}"),
+    )?;
+    let mut command = gpg(gnupg_home)?;
+    command.arg("--import-ownertrust");
+    run(
+        command,
+        Some(ownertrust(fingerprint).as_bytes()),
+        Effect::Changes,
+        &format!("trust {fingerprint}"),
+    )?;
+    Ok(())
+}
+
+/// `--import-ownertrust` input giving `fingerprint` ultimate trust
+pub fn ownertrust(fingerprint: &str) -> String {
+    format!("{fingerprint}:6:\n")
+}
+
+/// The git settings that sign commits and tags with `key`
+pub fn signing_settings(key: &str) -> [(&'static str, String); 3] {
+    [
+        ("user.signingkey", key.to_string()),
+        ("commit.gpgsign", "true".to_string()),
+        ("tag.gpgsign", "true".to_string()),
+    ]
+}
+
+/// The user's git config under `target_root`: `~/.gitconfig`, or
+/// `~/.config/git/config` when only that one exists
+pub fn git_config_path(target_root: &Path) -> PathBuf {
+    let dotfile = target_root.join(".gitconfig");
+    let xdg = target_root.join(".config/git/config");
+    if !dotfile.exists() && xdg.exists() {
+        xdg
+    } else {
+        dotfile
+    }
+}
+
+/// Point git at `key` for signing in the config at `git_config`, changing
+/// only settings that differ. Returns the settings written.
+pub fn configure_git_signing(git_config: &Path, key: &str) -> Result<Vec<String>> {
+    let mut written = Vec::new();
+    for (name, value) in signing_settings(key) {
+        let current = environment::output(
+            Command::new("git")
+                .arg("config")
+                .arg("--file")
+                .arg(git_config)
+                .args(["--get", name]),
+            Effect::Reads,
+        )
+        .context("Failed to run git config")?;
+        if String::from_utf8_lossy(&current.stdout).trim() == value {
+            continue;
+        }
+        let status = environment::status(
+            Command::new("git")
+                .arg("config")
+                .arg("--file")
+                .arg(git_config)
+                .args([name, &value]),
+            Effect::Changes,
+        )
+        .context("Failed to run git config")?;
+        if !status.success() {
+            return Err(anyhow::anyhow!(
+                "git config failed to set {name} in {}",
+                git_config.display()
+            ));
+        }
+        written.push(format!("{name} = {value}"));
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fingerprint_and_git_signing() {
+        let colons = "sec:u:255:22:0123456789ABCDEF:1700000000:::u:::scESC:::+:::23::0:\n\
+                      fpr:::::::::AAAABBBBCCCCDDDDEEEEFFFF0123456789ABCDEF:\n\
+                      grp:::::::::1234:\n\
+                      fpr:::::::::9999999999999999999999999999999999999999:\n";
+        assert_eq!(
+            parse_fingerprint(colons).as_deref(),
+            Some("AAAABBBBCCCCDDDDEEEEFFFF0123456789ABCDEF")
+        );
+        assert_eq!(parse_fingerprint("sec:u:255\n"), None);
+        assert_eq!(ownertrust("ABCD"), "ABCD:6:\n");
+
+        let home = tempfile::tempdir().unwrap();
+        let git_config = git_config_path(home.path());
+        assert_eq!(git_config, home.path().join(".gitconfig"));
+        let written = configure_git_signing(&git_config, "ABCD").unwrap();
+        assert_eq!(
+            written,
+            [
+                "user.signingkey = ABCD",
+                "commit.gpgsign = true",
+                "tag.gpgsign = true"
+            ]
+        );
+        assert!(configure_git_signing(&git_config, "ABCD")
+            .unwrap()
+            .is_empty());
+    }
+}
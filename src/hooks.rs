@@ -0,0 +1,213 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::info;
+
+use crate::bootstrap::{BootstrapManager, SafetyLevel};
+use crate::config::Config;
+
+/// What happens when a hook script fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HookFailure {
+    /// Stop the command that ran the hook
+    #[default]
+    Abort,
+
+    /// Print a warning and carry on
+    Warn,
+}
+
+/// Points at which hook scripts run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    PreApply,
+    PostApply,
+    PreCommit,
+    PostPush,
+}
+
+impl fmt::Display for HookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HookEvent::PreApply => "pre_apply",
+            HookEvent::PostApply => "post_apply",
+            HookEvent::PreCommit => "pre_commit",
+            HookEvent::PostPush => "post_push",
+        })
+    }
+}
+
+/// Scripts to run around apply, commit, and push. Paths are relative to the
+/// dotfiles repository.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HooksConfig {
+    /// Run before `ordinator apply` changes anything
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_apply: Vec<String>,
+
+    /// Run after `ordinator apply` completes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_apply: Vec<String>,
+
+    /// Run before `ordinator commit` scans and commits
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_commit: Vec<String>,
+
+    /// Run after `ordinator push` or `ordinator sync` pushes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_push: Vec<String>,
+
+    /// Whether a failing hook aborts the command (default) or only warns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_failure: Option<HookFailure>,
+}
+
+impl HooksConfig {
+    pub fn is_empty(&self) -> bool {
+        self.pre_apply.is_empty()
+            && self.post_apply.is_empty()
+            && self.pre_commit.is_empty()
+            && self.post_push.is_empty()
+            && self.on_failure.is_none()
+    }
+
+    /// Scripts registered for an event, in run order
+    pub fn scripts(&self, event: HookEvent) -> &[String] {
+        match event {
+            HookEvent::PreApply => &self.pre_apply,
+            HookEvent::PostApply => &self.post_apply,
+            HookEvent::PreCommit => &self.pre_commit,
+            HookEvent::PostPush => &self.post_push,
+        }
+    }
+
+    /// Append another set of hooks after these; its `on_failure` wins if set
+    pub fn merge(&mut self, other: &HooksConfig) {
+        self.pre_apply.extend(other.pre_apply.iter().cloned());
+        self.post_apply.extend(other.post_apply.iter().cloned());
+        self.pre_commit.extend(other.pre_commit.iter().cloned());
+        self.post_push.extend(other.post_push.iter().cloned());
+        if other.on_failure.is_some() {
+            self.on_failure = other.on_failure;
+        }
+    }
+}
+
+/// Runs hook scripts from the dotfiles repository
+pub struct HookRunner {
+    dotfiles_dir: PathBuf,
+    dry_run: bool,
+}
+
+impl HookRunner {
+    pub fn new(dotfiles_dir: &Path, dry_run: bool) -> Self {
+        Self {
+            dotfiles_dir: dotfiles_dir.to_path_buf(),
+            dry_run,
+        }
+    }
+
+    /// Run every script registered for `event`, streaming its output. Scripts
+    /// are scanned like bootstrap scripts; blocked scripts never run.
+    pub fn run(&self, hooks: &HooksConfig, event: HookEvent, profile: &str) -> Result<()> {
+        let on_failure = hooks.on_failure.unwrap_or_default();
+        for script in hooks.scripts(event) {
+            if let Err(e) = self.run_script(script, event, profile) {
+                match on_failure {
+                    HookFailure::Abort => return Err(e),
+                    HookFailure::Warn => eprintln!("Warning: {e}"),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn run_script(&self, script: &str, event: HookEvent, profile: &str) -> Result<()> {
+        let path = self.dotfiles_dir.join(script);
+        if self.dry_run {
+            eprintln!("DRY-RUN: Would run {event} hook: {script}");
+            return Ok(());
+        }
+        if !path.is_file() {
+            return Err(anyhow::anyhow!(
+                "{event} hook '{script}' not found at {}",
+                path.display()
+            ));
+        }
+
+        match BootstrapManager::new(false).validate_script(&path)? {
+            SafetyLevel::Blocked => {
+                return Err(anyhow::anyhow!(
+                    "{event} hook '{script}' was not run: it contains blocked commands"
+                ));
+            }
+            SafetyLevel::Dangerous => {
+                eprintln!("⚠️  {event} hook '{script}' contains dangerous commands (e.g. sudo)");
+            }
+            SafetyLevel::Warning => {
+                eprintln!("⚠️  {event} hook '{script}' contains potentially risky commands");
+            }
+            SafetyLevel::Safe => {}
+        }
+
+        info!("Running {} hook: {}", event, path.display());
+        eprintln!("Running {event} hook: {script}");
+        let status = Command::new("bash")
+            .arg(&path)
+            .current_dir(&self.dotfiles_dir)
+            .env("ORDINATOR_HOOK", event.to_string())
+            .env("ORDINATOR_PROFILE", profile)
+            .env("ORDINATOR_DOTFILES_DIR", &self.dotfiles_dir)
+            .status()
+            .with_context(|| format!("Failed to run {event} hook '{script}'"))?;
+        if !status.success() {
+            return Err(anyhow::anyhow!(
+                "{event} hook '{script}' failed with {status}"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Run the default profile's hooks for `event`. Commits and pushes aren't
+/// tied to a profile, so their hooks come from the default one.
+pub fn run_for_default_profile(
+    config: &Config,
+    dotfiles_dir: &Path,
+    event: HookEvent,
+    dry_run: bool,
+) -> Result<()> {
+    let profile = config.default_profile();
+    let hooks = config.resolve_profile(profile)?.hooks;
+    HookRunner::new(dotfiles_dir, dry_run).run(&hooks, event, profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_appends_and_overrides_on_failure() {
+        let mut hooks: HooksConfig = toml::from_str(
+            r#"pre_apply = ["scripts/global.sh"]
+post_push = ["scripts/notify.sh"]"#,
+        )
+        .unwrap();
+        let profile: HooksConfig = toml::from_str(
+            r#"pre_apply = ["scripts/work/pre.sh"]
+on_failure = "warn""#,
+        )
+        .unwrap();
+        hooks.merge(&profile);
+        assert_eq!(
+            hooks.scripts(HookEvent::PreApply),
+            ["scripts/global.sh", "scripts/work/pre.sh"]
+        );
+        assert_eq!(hooks.scripts(HookEvent::PostPush), ["scripts/notify.sh"]);
+        assert_eq!(hooks.on_failure, Some(HookFailure::Warn));
+    }
+}
@@ -0,0 +1,624 @@
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use crate::utils::{is_symlink, symlink_points_to};
+
+/// Directory (relative to the dotfiles repository) holding apply journals
+const JOURNAL_DIR: &str = "transactions";
+
+/// A single reversible change made by `ordinator apply`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum JournalAction {
+    /// A symlink was created where nothing existed before
+    SymlinkCreated { path: PathBuf, target: PathBuf },
+
+    /// An existing file was replaced by a symlink (backup holds the original, if any)
+    FileReplaced {
+        path: PathBuf,
+        target: PathBuf,
+        backup: Option<PathBuf>,
+    },
+
+    /// An existing symlink was re-pointed at a new target
+    SymlinkRepaired {
+        path: PathBuf,
+        previous_target: PathBuf,
+    },
+
+    /// A secret was decrypted to disk (backup holds the previous contents, if any)
+    SecretDecrypted {
+        path: PathBuf,
+        backup: Option<PathBuf>,
+    },
+
+    /// A template was rendered to disk, replacing a file (backup) or a symlink (previous_target)
+    FileRendered {
+        path: PathBuf,
+        backup: Option<PathBuf>,
+        previous_target: Option<PathBuf>,
+    },
+
+    /// A managed block was written into a file; `previous` holds the block's
+    /// old contents, none when it had no block, and `previous_target` the
+    /// symlink it replaced
+    BlockWritten {
+        path: PathBuf,
+        previous: Option<String>,
+        previous_target: Option<PathBuf>,
+    },
+
+    /// A missing parent directory was created, with `mode` when one was set
+    DirectoryCreated {
+        path: PathBuf,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mode: Option<String>,
+    },
+}
+
+impl JournalAction {
+    /// Backup of the file this change replaced, if one was made
+    pub fn backup(&self) -> Option<&Path> {
+        match self {
+            JournalAction::FileReplaced { backup, .. }
+            | JournalAction::SecretDecrypted { backup, .. }
+            | JournalAction::FileRendered { backup, .. } => backup.as_deref(),
+            JournalAction::SymlinkCreated { .. }
+            | JournalAction::SymlinkRepaired { .. }
+            | JournalAction::BlockWritten { .. }
+            | JournalAction::DirectoryCreated { .. } => None,
+        }
+    }
+
+    /// One-line human-readable summary of the change
+    pub fn describe(&self) -> String {
+        match self {
+            JournalAction::SymlinkCreated { path, target } => {
+                format!("created symlink {} -> {}", path.display(), target.display())
+            }
+            JournalAction::FileReplaced { path, backup, .. } => match backup {
+                Some(backup) => {
+                    format!("replaced {} (backup: {})", path.display(), backup.display())
+                }
+                None => format!("replaced {} (no backup)", path.display()),
+            },
+            JournalAction::SymlinkRepaired {
+                path,
+                previous_target,
+            } => format!(
+                "repointed {} (was -> {})",
+                path.display(),
+                previous_target.display()
+            ),
+            JournalAction::SecretDecrypted { path, .. } => {
+                format!("decrypted secret to {}", path.display())
+            }
+            JournalAction::FileRendered { path, .. } => {
+                format!("rendered template to {}", path.display())
+            }
+            JournalAction::BlockWritten { path, .. } => {
+                format!("updated managed block in {}", path.display())
+            }
+            JournalAction::DirectoryCreated { path, mode } => match mode {
+                Some(mode) => format!("created directory {} (mode {mode})", path.display()),
+                None => format!("created directory {}", path.display()),
+            },
+        }
+    }
+}
+
+/// A secret file `apply` decrypted to disk, gathered from the journals
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecryptedSecret {
+    pub path: PathBuf,
+
+    /// Profile of the most recent apply that decrypted it (comma-separated
+    /// when several were applied together)
+    pub profile: String,
+
+    /// What was there before the first apply decrypted it, if anything
+    pub original_backup: Option<PathBuf>,
+}
+
+impl DecryptedSecret {
+    /// Whether `profile` was among the profiles applied when it was decrypted
+    pub fn applied_with(&self, profile: &str) -> bool {
+        self.profile.split(',').any(|applied| applied == profile)
+    }
+}
+
+/// Transaction log for one run of `ordinator apply`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplyJournal {
+    /// Journal identifier (also the file stem)
+    pub id: String,
+
+    /// Profile that was applied
+    pub profile: String,
+
+    /// When the apply started
+    pub started_at: String,
+
+    /// Whether this apply has been rolled back
+    #[serde(default)]
+    pub rolled_back: bool,
+
+    /// Changes in the order they were made
+    #[serde(default)]
+    pub actions: Vec<JournalAction>,
+
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl ApplyJournal {
+    /// Start a new journal for an apply of `profile`
+    pub fn begin(dotfiles_dir: &Path, profile: &str) -> Result<Self> {
+        let now = Local::now();
+        let dir = dotfiles_dir.join(JOURNAL_DIR);
+
+        // Several applies can start within the same second; keep ids unique
+        let base = format!("apply-{}", now.format("%Y%m%d-%H%M%S"));
+        let mut id = base.clone();
+        let mut counter = 1;
+        while dir.join(format!("{id}.json")).exists() {
+            id = format!("{base}-{counter}");
+            counter += 1;
+        }
+
+        Ok(Self {
+            path: dir.join(format!("{id}.json")),
+            id,
+            profile: profile.to_string(),
+            started_at: now.to_rfc3339(),
+            rolled_back: false,
+            actions: Vec::new(),
+        })
+    }
+
+    /// Record a change and persist the journal immediately, so an apply that
+    /// fails part-way can still be rolled back. Applies that change nothing
+    /// never write a journal.
+    pub fn record(&mut self, action: JournalAction) -> Result<()> {
+        self.actions.push(action);
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(&self.path, content)
+            .with_context(|| format!("Failed to write journal {}", self.path.display()))
+    }
+
+    /// Load every journal, oldest first
+    pub fn list(dotfiles_dir: &Path) -> Result<Vec<Self>> {
+        let dir = dotfiles_dir.join(JOURNAL_DIR);
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut journals = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read journal {}", path.display()))?;
+            let mut journal: Self = serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse journal {}", path.display()))?;
+            journal.path = path;
+            journals.push(journal);
+        }
+        journals.sort_by(|a, b| a.started_at.cmp(&b.started_at).then(a.id.cmp(&b.id)));
+        Ok(journals)
+    }
+
+    /// The most recent apply that has not been rolled back
+    pub fn latest(dotfiles_dir: &Path) -> Result<Option<Self>> {
+        Ok(Self::list(dotfiles_dir)?
+            .into_iter()
+            .rev()
+            .find(|journal| !journal.rolled_back))
+    }
+
+    /// Whether `profile` was among the profiles applied
+    pub fn applied_with(&self, profile: &str) -> bool {
+        self.profile.split(',').any(|applied| applied == profile)
+    }
+
+    /// Directories created by applies of any of `profiles` that haven't been
+    /// rolled back and still exist, innermost first so each can be removed
+    /// once it's empty
+    pub fn created_directories(dotfiles_dir: &Path, profiles: &[String]) -> Result<Vec<PathBuf>> {
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        for journal in Self::list(dotfiles_dir)? {
+            if journal.rolled_back || !profiles.iter().any(|p| journal.applied_with(p)) {
+                continue;
+            }
+            for action in &journal.actions {
+                if let JournalAction::DirectoryCreated { path, .. } = action {
+                    if path.is_dir() && !is_symlink(path) && !dirs.contains(path) {
+                        dirs.push(path.clone());
+                    }
+                }
+            }
+        }
+        dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+        Ok(dirs)
+    }
+
+    /// Decrypted secrets recorded by applies that haven't been rolled back
+    /// and are still on disk, each destination once
+    pub fn decrypted_secrets(dotfiles_dir: &Path) -> Result<Vec<DecryptedSecret>> {
+        let mut secrets: Vec<DecryptedSecret> = Vec::new();
+        for journal in Self::list(dotfiles_dir)? {
+            if journal.rolled_back {
+                continue;
+            }
+            for action in &journal.actions {
+                let JournalAction::SecretDecrypted { path, backup } = action else {
+                    continue;
+                };
+                match secrets.iter_mut().find(|secret| &secret.path == path) {
+                    Some(secret) => secret.profile = journal.profile.clone(),
+                    None => secrets.push(DecryptedSecret {
+                        path: path.clone(),
+                        profile: journal.profile.clone(),
+                        original_backup: backup.clone(),
+                    }),
+                }
+            }
+        }
+        secrets.retain(|secret| secret.path.is_file() && !is_symlink(&secret.path));
+        Ok(secrets)
+    }
+
+    /// Revert every recorded change in reverse order and mark the journal as rolled back.
+    /// Returns a warning for each change that could not be fully reverted.
+    pub fn rollback(&mut self) -> Result<Vec<String>> {
+        let mut warnings = Vec::new();
+        for action in self.actions.iter().rev() {
+            info!("Rolling back: {:?}", action);
+            if let Some(warning) = revert(action)? {
+                warnings.push(warning);
+            }
+        }
+        self.rolled_back = true;
+        self.save()?;
+        Ok(warnings)
+    }
+}
+
+/// Remove `path` only if it is still a symlink to `target`
+fn remove_symlink_if_unchanged(path: &Path, target: &Path) -> Result<Option<String>> {
+    if !is_symlink(path) {
+        return Ok(Some(format!(
+            "{} is no longer a symlink; left untouched",
+            path.display()
+        )));
+    }
+    if !symlink_points_to(path, target) {
+        return Ok(Some(format!(
+            "{} now points elsewhere; left untouched",
+            path.display()
+        )));
+    }
+    fs::remove_file(path)?;
+    Ok(None)
+}
+
+fn restore_backup(backup: &Option<PathBuf>, path: &Path) -> Result<Option<String>> {
+    match backup {
+        Some(backup) if backup.is_dir() => {
+            crate::utils::copy_dir_recursive(backup, path).with_context(|| {
+                format!(
+                    "Failed to restore {} from {}",
+                    path.display(),
+                    backup.display()
+                )
+            })?;
+            Ok(None)
+        }
+        Some(backup) if backup.exists() => {
+            fs::copy(backup, path).with_context(|| {
+                format!(
+                    "Failed to restore {} from {}",
+                    path.display(),
+                    backup.display()
+                )
+            })?;
+            Ok(None)
+        }
+        Some(backup) => Ok(Some(format!(
+            "Backup {} is missing; could not restore {}",
+            backup.display(),
+            path.display()
+        ))),
+        None => Ok(Some(format!(
+            "No backup was recorded for {}; original contents cannot be restored",
+            path.display()
+        ))),
+    }
+}
+
+fn revert(action: &JournalAction) -> Result<Option<String>> {
+    match action {
+        JournalAction::SymlinkCreated { path, target } => remove_symlink_if_unchanged(path, target),
+        JournalAction::FileReplaced {
+            path,
+            target,
+            backup,
+        } => {
+            if let Some(warning) = remove_symlink_if_unchanged(path, target)? {
+                return Ok(Some(warning));
+            }
+            restore_backup(backup, path)
+        }
+        JournalAction::SymlinkRepaired {
+            path,
+            previous_target,
+        } => {
+            if !is_symlink(path) {
+                return Ok(Some(format!(
+                    "{} is no longer a symlink; left untouched",
+                    path.display()
+                )));
+            }
+            fs::remove_file(path)?;
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(previous_target, path)?;
+            Ok(None)
+        }
+        JournalAction::SecretDecrypted { path, backup } => {
+            if backup.is_none() {
+                if path.exists() {
+                    fs::remove_file(path)?;
+                }
+                return Ok(None);
+            }
+            restore_backup(backup, path)
+        }
+        JournalAction::FileRendered {
+            path,
+            backup,
+            previous_target,
+        } => {
+            if backup.is_some() {
+                return restore_backup(backup, path);
+            }
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+            if let Some(previous_target) = previous_target {
+                #[cfg(unix)]
+                std::os::unix::fs::symlink(previous_target, path)?;
+            }
+            Ok(None)
+        }
+        JournalAction::BlockWritten {
+            path,
+            previous,
+            previous_target,
+        } => {
+            if let Some(previous_target) = previous_target {
+                if path.exists() {
+                    fs::remove_file(path)?;
+                }
+                #[cfg(unix)]
+                std::os::unix::fs::symlink(previous_target, path)?;
+                return Ok(None);
+            }
+            let Ok(text) = fs::read_to_string(path) else {
+                return Ok(Some(format!("{} is gone; left untouched", path.display())));
+            };
+            match previous {
+                Some(previous) => fs::write(path, crate::block::upsert(&text, previous))?,
+                None => match crate::block::remove(&text) {
+                    Some(rest) if rest.is_empty() => fs::remove_file(path)?,
+                    Some(rest) => fs::write(path, rest)?,
+                    None => {}
+                },
+            }
+            Ok(None)
+        }
+        JournalAction::DirectoryCreated { path, .. } => {
+            if !path.is_dir() || is_symlink(path) {
+                return Ok(None);
+            }
+            if fs::read_dir(path)?.next().is_some() {
+                return Ok(Some(format!(
+                    "{} is no longer empty; left in place",
+                    path.display()
+                )));
+            }
+            fs::remove_dir(path)?;
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_decrypted_secrets_across_applies() {
+        let dir = tempdir().unwrap();
+        let secret = dir.path().join("secret.env");
+        let gone = dir.path().join("gone.env");
+        fs::write(&secret, "TOKEN=1\n").unwrap();
+        let original = dir.path().join("secret.env.backup");
+
+        let mut first = ApplyJournal::begin(dir.path(), "default").unwrap();
+        first
+            .record(JournalAction::SecretDecrypted {
+                path: secret.clone(),
+                backup: Some(original.clone()),
+            })
+            .unwrap();
+        first
+            .record(JournalAction::SecretDecrypted {
+                path: gone,
+                backup: None,
+            })
+            .unwrap();
+        let mut second = ApplyJournal::begin(dir.path(), "work").unwrap();
+        second
+            .record(JournalAction::SecretDecrypted {
+                path: secret.clone(),
+                backup: Some(dir.path().join("second.backup")),
+            })
+            .unwrap();
+
+        let secrets = ApplyJournal::decrypted_secrets(dir.path()).unwrap();
+        assert_eq!(
+            secrets,
+            [DecryptedSecret {
+                path: secret,
+                profile: "work".to_string(),
+                original_backup: Some(original),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_journal_written_on_first_record() {
+        let dir = tempdir().unwrap();
+        let mut journal = ApplyJournal::begin(dir.path(), "default").unwrap();
+        assert!(ApplyJournal::list(dir.path()).unwrap().is_empty());
+        journal
+            .record(JournalAction::SecretDecrypted {
+                path: dir.path().join("secret.env"),
+                backup: None,
+            })
+            .unwrap();
+        let journals = ApplyJournal::list(dir.path()).unwrap();
+        assert_eq!(journals.len(), 1);
+        assert_eq!(journals[0].profile, "default");
+        assert_eq!(journals[0].actions.len(), 1);
+    }
+
+    #[test]
+    fn test_rollback_reverts_actions() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("source.txt");
+        fs::write(&source, "tracked").unwrap();
+
+        // A newly created symlink
+        let created = dir.path().join("created.txt");
+        std::os::unix::fs::symlink(&source, &created).unwrap();
+
+        // A file replaced by a symlink, with a backup
+        let replaced = dir.path().join("replaced.txt");
+        let backup = dir.path().join("replaced.txt.backup");
+        fs::write(&backup, "original").unwrap();
+        std::os::unix::fs::symlink(&source, &replaced).unwrap();
+
+        // A decrypted secret with no previous contents
+        let secret = dir.path().join("secret.env");
+        fs::write(&secret, "TOKEN=1").unwrap();
+
+        let mut journal = ApplyJournal::begin(dir.path(), "default").unwrap();
+        journal
+            .record(JournalAction::SymlinkCreated {
+                path: created.clone(),
+                target: source.clone(),
+            })
+            .unwrap();
+        journal
+            .record(JournalAction::FileReplaced {
+                path: replaced.clone(),
+                target: source.clone(),
+                backup: Some(backup),
+            })
+            .unwrap();
+        journal
+            .record(JournalAction::SecretDecrypted {
+                path: secret.clone(),
+                backup: None,
+            })
+            .unwrap();
+
+        let mut latest = ApplyJournal::latest(dir.path()).unwrap().unwrap();
+        assert_eq!(latest.actions.len(), 3);
+        let warnings = latest.rollback().unwrap();
+        assert!(warnings.is_empty());
+
+        assert!(!created.exists() && !is_symlink(&created));
+        assert!(!is_symlink(&replaced));
+        assert_eq!(fs::read_to_string(&replaced).unwrap(), "original");
+        assert!(!secret.exists());
+
+        // Rolled-back journals are skipped
+        assert!(ApplyJournal::latest(dir.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_created_directories_removed_once_empty() {
+        let dir = tempdir().unwrap();
+        let outer = dir.path().join(".config");
+        let inner = outer.join("foo");
+        fs::create_dir_all(&inner).unwrap();
+
+        let mut journal = ApplyJournal::begin(dir.path(), "default,work").unwrap();
+        for path in [&outer, &inner] {
+            journal
+                .record(JournalAction::DirectoryCreated {
+                    path: path.clone(),
+                    mode: None,
+                })
+                .unwrap();
+        }
+        let profiles = ["work".to_string()];
+        let created = ApplyJournal::created_directories(dir.path(), &profiles).unwrap();
+        assert_eq!(created, [inner.clone(), outer.clone()]);
+        assert!(
+            ApplyJournal::created_directories(dir.path(), &["personal".to_string()])
+                .unwrap()
+                .is_empty()
+        );
+
+        fs::write(inner.join("bar.toml"), "").unwrap();
+        let warnings = journal.rollback().unwrap();
+        assert_eq!(warnings.len(), 2);
+        fs::remove_file(inner.join("bar.toml")).unwrap();
+        for action in journal.actions.iter().rev() {
+            assert_eq!(revert(action).unwrap(), None);
+        }
+        assert!(!outer.exists());
+    }
+
+    #[test]
+    fn test_rollback_leaves_modified_paths_alone() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("source.txt");
+        fs::write(&source, "tracked").unwrap();
+        let path = dir.path().join("edited.txt");
+        fs::write(&path, "user replaced the symlink").unwrap();
+
+        let mut journal = ApplyJournal::begin(dir.path(), "work").unwrap();
+        journal
+            .record(JournalAction::SymlinkCreated {
+                path: path.clone(),
+                target: source,
+            })
+            .unwrap();
+
+        let warnings = journal.rollback().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "user replaced the symlink"
+        );
+    }
+}
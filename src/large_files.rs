@@ -0,0 +1,251 @@
+//! `[global.large_files]`: spotting large and binary files as `watch`,
+//! `add`, and `adopt` store them, and routing them through Git LFS.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::Path;
+use std::process::Command;
+
+/// Git attributes file at the root of the dotfiles repository
+const ATTRIBUTES_FILE: &str = ".gitattributes";
+
+/// Bytes git itself looks at to decide whether a file is binary
+const BINARY_PROBE_BYTES: u64 = 8000;
+
+/// What to do when a large file is stored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LargeFileAction {
+    /// Print a warning, and offer Git LFS when running in a terminal
+    #[default]
+    Warn,
+
+    /// Add the stored copy to `.gitattributes` so git-lfs stores it
+    Lfs,
+
+    /// Don't check
+    Off,
+}
+
+/// `[global.large_files]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LargeFilesConfig {
+    /// Files of at least this many KiB are large
+    #[serde(default = "default_threshold_kb")]
+    pub threshold_kb: u64,
+
+    /// Also treat binary files (e.g. binary plists, fonts) as large, whatever their size
+    #[serde(default)]
+    pub binary: bool,
+
+    #[serde(default)]
+    pub action: LargeFileAction,
+}
+
+fn default_threshold_kb() -> u64 {
+    1024
+}
+
+impl Default for LargeFilesConfig {
+    fn default() -> Self {
+        Self {
+            threshold_kb: default_threshold_kb(),
+            binary: false,
+            action: LargeFileAction::default(),
+        }
+    }
+}
+
+impl LargeFilesConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Why `path` counts as a large file, if it does
+    pub fn check(&self, path: &Path) -> Result<Option<LargeFile>> {
+        if self.action == LargeFileAction::Off {
+            return Ok(None);
+        }
+        let size = std::fs::metadata(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .len();
+        let binary = is_binary(path)?;
+        let large = size >= self.threshold_kb.saturating_mul(1024) || (self.binary && binary);
+        Ok(large.then_some(LargeFile { size, binary }))
+    }
+}
+
+/// A stored file over the `[global.large_files]` limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LargeFile {
+    pub size: u64,
+    pub binary: bool,
+}
+
+impl LargeFile {
+    /// e.g. `2.5 MiB, binary`
+    pub fn describe(&self) -> String {
+        let size = crate::utils::format_size(self.size);
+        if self.binary {
+            format!("{size}, binary")
+        } else {
+            size
+        }
+    }
+}
+
+/// Whether `path` looks binary: a NUL byte near the start, as git decides
+pub fn is_binary(path: &Path) -> Result<bool> {
+    let mut head = Vec::new();
+    std::fs::File::open(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?
+        .take(BINARY_PROBE_BYTES)
+        .read_to_end(&mut head)?;
+    Ok(head.contains(&0))
+}
+
+/// Whether `git lfs` can be run
+pub fn lfs_available() -> bool {
+    Command::new("git")
+        .args(["lfs", "version"])
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// `.gitattributes` line storing `stored` with Git LFS
+fn lfs_rule(dotfiles_dir: &Path, stored: &Path) -> String {
+    let relative = stored.strip_prefix(dotfiles_dir).unwrap_or(stored);
+    // Patterns can't contain plain spaces; the slash anchors it to the repository root
+    let pattern = relative.to_string_lossy().replace(' ', "[[:space:]]");
+    format!("/{pattern} filter=lfs diff=lfs merge=lfs -text")
+}
+
+fn read_attributes(path: &Path) -> Result<String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// Whether `.gitattributes` already stores `stored` with Git LFS
+pub fn is_lfs_tracked(dotfiles_dir: &Path, stored: &Path) -> Result<bool> {
+    let content = read_attributes(&dotfiles_dir.join(ATTRIBUTES_FILE))?;
+    let rule = lfs_rule(dotfiles_dir, stored);
+    Ok(content.lines().any(|line| line == rule))
+}
+
+/// Add a Git LFS rule for `stored` to the repository's `.gitattributes`;
+/// false when the rule is already there
+pub fn track_with_lfs(dotfiles_dir: &Path, stored: &Path) -> Result<bool> {
+    if is_lfs_tracked(dotfiles_dir, stored)? {
+        return Ok(false);
+    }
+    let path = dotfiles_dir.join(ATTRIBUTES_FILE);
+    let mut content = read_attributes(&path)?;
+    let rule = lfs_rule(dotfiles_dir, stored);
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&rule);
+    content.push('\n');
+    std::fs::write(&path, content)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(true)
+}
+
+/// Check a copy `watch`, `add`, or `adopt` just stored for `tracked_path`,
+/// and warn about it or route it through Git LFS as configured
+pub fn handle_stored(
+    config: &LargeFilesConfig,
+    dotfiles_dir: &Path,
+    stored: &Path,
+    tracked_path: &str,
+) -> Result<()> {
+    let Some(large) = config.check(stored)? else {
+        return Ok(());
+    };
+    // Updating a file already stored with LFS needs nothing new
+    if is_lfs_tracked(dotfiles_dir, stored)? {
+        return Ok(());
+    }
+    let use_lfs = match config.action {
+        LargeFileAction::Off => false,
+        LargeFileAction::Lfs => true,
+        LargeFileAction::Warn => {
+            eprintln!(
+                "⚠️  Warning: '{tracked_path}' is large ({}); every change to it adds a full copy to the repository's history",
+                large.describe()
+            );
+            eprintln!(
+                "   Set action = \"lfs\" under [global.large_files] to store files like it with Git LFS"
+            );
+            // `--yes` shouldn't opt into LFS on its own
+            !crate::prompt::assume_yes() && crate::prompt::confirm("Store it with Git LFS?", false)
+        }
+    };
+    if !use_lfs {
+        return Ok(());
+    }
+    if track_with_lfs(dotfiles_dir, stored)? {
+        eprintln!(
+            "Storing '{tracked_path}' ({}) with Git LFS (added to {ATTRIBUTES_FILE})",
+            large.describe()
+        );
+    }
+    if !lfs_available() {
+        eprintln!(
+            "⚠️  git-lfs is not installed, so '{tracked_path}' would be committed as a regular file."
+        );
+        eprintln!(
+            "   Install it (brew install git-lfs) and run 'git lfs install' before committing"
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_and_track_large_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let text = temp.path().join("files/default/abc_notes.txt");
+        std::fs::create_dir_all(text.parent().unwrap()).unwrap();
+        std::fs::write(&text, "x".repeat(4096)).unwrap();
+        let plist = temp
+            .path()
+            .join("files/default/def_com.googlecode.iterm2.plist");
+        std::fs::write(&plist, b"bplist00\xd1\x01\x02\x00").unwrap();
+
+        let mut config = LargeFilesConfig {
+            threshold_kb: 4,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.check(&text).unwrap(),
+            Some(LargeFile {
+                size: 4096,
+                binary: false
+            })
+        );
+        assert_eq!(config.check(&plist).unwrap(), None);
+        config.binary = true;
+        assert!(config.check(&plist).unwrap().unwrap().binary);
+        config.action = LargeFileAction::Off;
+        assert_eq!(config.check(&text).unwrap(), None);
+
+        let spaced = temp.path().join("files/default/0a_My Font.otf");
+        assert!(track_with_lfs(temp.path(), &plist).unwrap());
+        assert!(!track_with_lfs(temp.path(), &plist).unwrap());
+        assert!(track_with_lfs(temp.path(), &spaced).unwrap());
+        assert_eq!(
+            std::fs::read_to_string(temp.path().join(".gitattributes")).unwrap(),
+            "/files/default/def_com.googlecode.iterm2.plist filter=lfs diff=lfs merge=lfs -text\n\
+             /files/default/0a_My[[:space:]]Font.otf filter=lfs diff=lfs merge=lfs -text\n"
+        );
+    }
+}
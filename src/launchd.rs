@@ -0,0 +1,212 @@
+//! launchd agents tracked per profile. Plists are kept in the repository under
+//! `launchd/<profile>/` and copied (launchd ignores symlinked plists) to
+//! `~/Library/LaunchAgents` on apply, then loaded with `launchctl`.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{info, warn};
+
+use crate::environment::{self, Effect};
+
+/// Repository directory holding each profile's plists
+pub const STORAGE_DIR: &str = "launchd";
+
+/// Where a tracked plist is stored in the repository
+pub fn stored_path(dotfiles_dir: &Path, profile: &str, name: &str) -> PathBuf {
+    dotfiles_dir.join(STORAGE_DIR).join(profile).join(name)
+}
+
+/// The per-user agents directory under `home_dir`
+pub fn agents_dir(home_dir: &Path) -> PathBuf {
+    home_dir.join("Library").join("LaunchAgents")
+}
+
+/// The `Label` of the plist at `path`
+pub fn label(path: &Path) -> Result<String> {
+    let value = plist::Value::from_file(path)
+        .with_context(|| format!("Failed to read plist {}", path.display()))?;
+    value
+        .as_dictionary()
+        .and_then(|dict| dict.get("Label"))
+        .and_then(|label| label.as_string())
+        .map(str::to_string)
+        .ok_or_else(|| anyhow::anyhow!("{} has no Label", path.display()))
+}
+
+/// Copy the plist at `source` into the repository for `profile`. Returns the
+/// file name it's tracked under.
+pub fn track(dotfiles_dir: &Path, profile: &str, source: &Path) -> Result<String> {
+    label(source)?;
+    let name = source
+        .file_name()
+        .and_then(|name| name.to_str())
+        .filter(|name| name.ends_with(".plist"))
+        .ok_or_else(|| anyhow::anyhow!("{} is not a .plist file", source.display()))?
+        .to_string();
+    let stored = stored_path(dotfiles_dir, profile, &name);
+    if let Some(dir) = stored.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::copy(source, &stored).with_context(|| {
+        format!(
+            "Failed to copy {} to {}",
+            source.display(),
+            stored.display()
+        )
+    })?;
+    Ok(name)
+}
+
+/// What installing an agent did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallOutcome {
+    /// The installed plist already matched
+    Unchanged,
+
+    /// The plist was new to this machine
+    Installed,
+
+    /// An older copy was unloaded and replaced
+    Updated,
+}
+
+/// Installs and removes tracked launchd agents
+pub struct LaunchdManager {
+    dry_run: bool,
+}
+
+impl LaunchdManager {
+    pub fn new(dry_run: bool) -> Self {
+        Self { dry_run }
+    }
+
+    /// Copy the stored plist into `agents_dir` and load it, unloading any
+    /// older copy first
+    pub fn install(&self, stored: &Path, agents_dir: &Path) -> Result<InstallOutcome> {
+        let content =
+            fs::read(stored).with_context(|| format!("Failed to read {}", stored.display()))?;
+        let installed = agents_dir.join(stored.file_name().unwrap_or_default());
+        let outcome = match fs::read(&installed) {
+            Ok(existing) if existing == content => return Ok(InstallOutcome::Unchanged),
+            Ok(_) => InstallOutcome::Updated,
+            Err(_) => InstallOutcome::Installed,
+        };
+        if self.dry_run {
+            info!("[DRY RUN] Would install {}", installed.display());
+            return Ok(outcome);
+        }
+
+        fs::create_dir_all(agents_dir)?;
+        if outcome == InstallOutcome::Updated {
+            launchctl("unload", &installed)?;
+        }
+        // A symlink left by an earlier setup would be written through
+        if crate::utils::is_symlink(&installed) {
+            fs::remove_file(&installed)?;
+        }
+        fs::write(&installed, content)
+            .with_context(|| format!("Failed to write {}", installed.display()))?;
+        launchctl("load", &installed)?;
+        info!("Installed launch agent {}", installed.display());
+        Ok(outcome)
+    }
+
+    /// Unload and delete the agent `name` from `agents_dir`; returns false if
+    /// it was not installed
+    pub fn remove(&self, name: &str, agents_dir: &Path) -> Result<bool> {
+        let installed = agents_dir.join(name);
+        if fs::symlink_metadata(&installed).is_err() {
+            return Ok(false);
+        }
+        if self.dry_run {
+            info!("[DRY RUN] Would remove {}", installed.display());
+            return Ok(true);
+        }
+
+        launchctl("unload", &installed)?;
+        fs::remove_file(&installed)
+            .with_context(|| format!("Failed to remove {}", installed.display()))?;
+        info!("Removed launch agent {}", installed.display());
+        Ok(true)
+    }
+}
+
+/// Run `launchctl <action> -w <plist>`. Skipped where launchctl isn't available.
+pub fn launchctl(action: &str, plist_path: &Path) -> Result<()> {
+    if which::which("launchctl").is_err() {
+        warn!("launchctl not found; skipping '{}'", action);
+        return Ok(());
+    }
+
+    let output = environment::service(
+        Command::new("launchctl")
+            .args([action, "-w"])
+            .arg(plist_path),
+        Effect::Changes,
+    )
+    .with_context(|| format!("Failed to run launchctl {action}"))?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "launchctl {} failed: {}",
+            action,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>com.example.backup</string>
+</dict>
+</plist>
+"#;
+
+    #[test]
+    fn test_track_and_install() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("com.example.backup.plist");
+        fs::write(&source, PLIST).unwrap();
+        let dotfiles = temp.path().join("dotfiles");
+
+        let name = track(&dotfiles, "work", &source).unwrap();
+        assert_eq!(name, "com.example.backup.plist");
+        let stored = stored_path(&dotfiles, "work", &name);
+        assert_eq!(label(&stored).unwrap(), "com.example.backup");
+
+        let agents = agents_dir(&temp.path().join("home"));
+        let manager = LaunchdManager::new(false);
+        assert_eq!(
+            manager.install(&stored, &agents).unwrap(),
+            InstallOutcome::Installed
+        );
+        assert_eq!(
+            manager.install(&stored, &agents).unwrap(),
+            InstallOutcome::Unchanged
+        );
+        fs::write(&stored, PLIST.replace("backup<", "backup.v2<")).unwrap();
+        assert_eq!(
+            manager.install(&stored, &agents).unwrap(),
+            InstallOutcome::Updated
+        );
+        assert!(manager.remove(&name, &agents).unwrap());
+        assert!(!agents.join(&name).exists());
+        assert!(!manager.remove(&name, &agents).unwrap());
+
+        fs::write(
+            temp.path().join("empty.plist"),
+            PLIST.replace("Label", "Name"),
+        )
+        .unwrap();
+        assert!(track(&dotfiles, "work", &temp.path().join("empty.plist")).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+//! Core of the `ordinator` dotfiles manager. The `ordinator` binary is a thin
+//! wrapper around [`cli::run`]; command logic such as [`apply::run`] can also
+//! be called directly. Tools built on ordinator should start from [`api`],
+//! the stable part of this library.
+
+pub mod adopt;
+pub mod age;
+pub mod age_key;
+pub mod api;
+pub mod apply;
+pub mod auth;
+pub mod backups;
+pub mod block;
+pub mod bootstrap;
+pub mod brew;
+pub mod bundle;
+pub mod cli;
+pub mod commit;
+pub mod config;
+pub mod config_merge;
+pub mod daemon;
+pub mod defaults;
+pub mod diff;
+pub mod directory;
+pub mod discard;
+pub mod drift;
+pub mod environment;
+pub mod error;
+pub mod exit_code;
+pub mod extensions;
+pub mod filter;
+pub mod fsck;
+pub mod git;
+pub mod gnupg;
+pub mod hooks;
+pub mod journal;
+pub mod large_files;
+pub mod launchd;
+pub mod listing;
+pub mod live;
+pub mod lock;
+pub mod logging;
+pub mod machine;
+pub mod migrate;
+pub mod parents;
+pub mod paths;
+pub mod permissions;
+pub mod plan;
+pub mod progress;
+pub mod prompt;
+pub mod push;
+pub mod readme;
+pub mod receipts;
+pub mod repo;
+pub mod rotation;
+pub mod run_log;
+pub mod scan;
+pub mod search;
+pub mod secret_meta;
+pub mod secrets;
+pub mod setup;
+pub mod shell;
+pub mod snapshot;
+pub mod ssh;
+pub mod state;
+pub mod sudo;
+pub mod switch;
+pub mod template;
+pub mod transfer;
+pub mod uninstall;
+pub mod unwatch;
+pub mod utils;
+pub mod watch;
+pub mod why;
+pub mod wizard;
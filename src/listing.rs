@@ -0,0 +1,285 @@
+//! `ordinator files`: every tracked file of a profile with where it lives in
+//! the repository, where it goes, and what state it's in.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::drift::{hash_bytes, HashCache};
+use crate::template::{is_template, render_file, rendered_path};
+use crate::utils::{is_broken_symlink, is_symlink};
+
+/// What's at a tracked file's destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkState {
+    /// A symlink to the tracked copy, directly or through a linked directory
+    Linked,
+
+    /// A template's rendered output
+    Rendered,
+
+    /// A secret's decrypted copy
+    Decrypted,
+
+    /// A file holding the tracked copy in its managed block
+    Block,
+
+    /// A symlink whose target doesn't exist
+    Broken,
+
+    /// A symlink pointing somewhere other than the tracked copy
+    WrongTarget,
+
+    /// A regular file that isn't linked to the tracked copy
+    NotLinked,
+
+    /// Nothing
+    Missing,
+}
+
+impl LinkState {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LinkState::Linked => "linked",
+            LinkState::Rendered => "rendered",
+            LinkState::Decrypted => "decrypted",
+            LinkState::Block => "managed block",
+            LinkState::Broken => "broken",
+            LinkState::WrongTarget => "wrong target",
+            LinkState::NotLinked => "not linked",
+            LinkState::Missing => "missing",
+        }
+    }
+}
+
+/// Which entries `ordinator files --filter` keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFilter {
+    /// Destination content differs from the tracked copy
+    Modified,
+
+    /// Broken symlinks and symlinks pointing elsewhere
+    Broken,
+
+    /// Nothing at the destination, or no tracked copy in the repository
+    Missing,
+}
+
+impl std::str::FromStr for FileFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "modified" => Ok(FileFilter::Modified),
+            "broken" => Ok(FileFilter::Broken),
+            "missing" => Ok(FileFilter::Missing),
+            other => Err(anyhow::anyhow!(
+                "Unknown filter '{other}'. Use 'modified', 'broken', or 'missing'."
+            )),
+        }
+    }
+}
+
+/// One tracked file or secret
+#[derive(Debug, Clone, Serialize)]
+pub struct FileEntry {
+    /// Tracked path, as listed in the profile
+    pub path: String,
+
+    /// Profile the entry comes from (may be a parent of the listed profile)
+    pub profile: String,
+
+    /// Tracked copy, relative to the dotfiles repository
+    pub repo_path: PathBuf,
+
+    /// Whether the tracked copy exists
+    pub in_repo: bool,
+
+    /// Where apply puts the file
+    pub target: PathBuf,
+
+    pub state: LinkState,
+
+    /// When `watch`, `add`, or `adopt` last stored the file on this machine (RFC 3339)
+    pub added_at: Option<String>,
+
+    /// Whether the destination's content matches the tracked copy (rendered,
+    /// for templates); unknown when either is missing, and for secrets
+    pub hash_match: Option<bool>,
+
+    /// For secrets: whether the tracked copy is encrypted
+    pub secret: Option<SecretState>,
+}
+
+/// Encryption state of a tracked secret
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretState {
+    Encrypted,
+
+    /// Stored in plaintext; `ordinator secrets check` reports these too
+    NotEncrypted,
+}
+
+impl FileEntry {
+    pub fn matches(&self, filter: FileFilter) -> bool {
+        match filter {
+            FileFilter::Modified => self.hash_match == Some(false),
+            FileFilter::Broken => {
+                matches!(self.state, LinkState::Broken | LinkState::WrongTarget)
+            }
+            FileFilter::Missing => !self.in_repo || self.state == LinkState::Missing,
+        }
+    }
+}
+
+/// List the tracked files (one entry per file inside tracked directories)
+/// and secrets of `profile`, as deployed under `home_dir`
+pub fn list(
+    config: &Config,
+    profile: &str,
+    home_dir: &Path,
+    dotfiles_dir: &Path,
+) -> Result<Vec<FileEntry>> {
+    let resolved = config.resolve_profile(profile)?;
+    let mut cache = HashCache::load(dotfiles_dir);
+    let relative = |source: &Path| {
+        source
+            .strip_prefix(dotfiles_dir)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|_| source.to_path_buf())
+    };
+
+    let mut entries = Vec::new();
+    for (file, source_profile, source) in config.link_targets(&resolved, true)? {
+        let target = crate::paths::destination(home_dir, rendered_path(&file));
+        let in_repo = source.exists();
+        let options = config.file_options(&source_profile, &file);
+        let encrypted = options.encrypt;
+        let state = if options.managed_block && target.is_file() && !is_symlink(&target) {
+            LinkState::Block
+        } else if encrypted && target.is_file() && !is_symlink(&target) {
+            LinkState::Decrypted
+        } else if is_template(&file) && target.is_file() && !is_symlink(&target) {
+            LinkState::Rendered
+        } else {
+            link_state(&source, &target)
+        };
+        let hash_match = if in_repo && target.is_file() && options.managed_block {
+            let stored = if encrypted {
+                crate::secrets::decrypt_tracked_file(&source)?
+            } else if is_template(&file) {
+                render_file(&source, &resolved.vars)?.into_bytes()
+            } else {
+                fs::read(&source)?
+            };
+            Some(crate::block::live_hash(&target)? == crate::block::hash(&stored))
+        } else if in_repo && target.is_file() {
+            let repo_hash = if encrypted {
+                hash_bytes(&crate::secrets::decrypt_tracked_file(&source)?)
+            } else if is_template(&file) {
+                hash_bytes(render_file(&source, &resolved.vars)?.as_bytes())
+            } else {
+                cache.hash_file(&source)?
+            };
+            Some(cache.hash_file(&target)? == repo_hash)
+        } else {
+            None
+        };
+        entries.push(FileEntry {
+            added_at: cache.added_at(&source_profile, &file).map(String::from),
+            repo_path: relative(&source),
+            path: file,
+            profile: source_profile,
+            in_repo,
+            target,
+            state,
+            hash_match,
+            secret: None,
+        });
+    }
+
+    for entry in &resolved.secrets {
+        let source = config.get_source_file_path(&entry.profile, &entry.path)?;
+        let target = crate::paths::destination(home_dir, &entry.path);
+        let state = if is_symlink(&target) {
+            link_state(&source, &target)
+        } else if target.is_file() {
+            LinkState::Decrypted
+        } else {
+            LinkState::Missing
+        };
+        let encrypted = crate::secrets::is_file_encrypted(&source);
+        entries.push(FileEntry {
+            path: entry.path.clone(),
+            profile: entry.profile.clone(),
+            repo_path: relative(&source),
+            in_repo: source.exists(),
+            target,
+            state,
+            added_at: cache
+                .added_at(&entry.profile, &entry.path)
+                .map(String::from),
+            hash_match: None,
+            secret: Some(if encrypted {
+                SecretState::Encrypted
+            } else {
+                SecretState::NotEncrypted
+            }),
+        });
+    }
+    cache.save()?;
+    Ok(entries)
+}
+
+/// What's at `target` compared with the tracked copy at `source`
+fn link_state(source: &Path, target: &Path) -> LinkState {
+    if is_broken_symlink(target) {
+        return LinkState::Broken;
+    }
+    if !target.exists() {
+        return LinkState::Missing;
+    }
+    // Canonical paths also match files inside a directory linked as a whole
+    let linked = match (fs::canonicalize(source), fs::canonicalize(target)) {
+        (Ok(source), Ok(target)) => source == target,
+        _ => false,
+    };
+    if linked {
+        LinkState::Linked
+    } else if is_symlink(target) {
+        LinkState::WrongTarget
+    } else {
+        LinkState::NotLinked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_state() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("source");
+        fs::write(&source, "tracked").unwrap();
+        let other = temp.path().join("other");
+        fs::write(&other, "other").unwrap();
+
+        let target = temp.path().join("target");
+        assert_eq!(link_state(&source, &target), LinkState::Missing);
+        std::os::unix::fs::symlink(&source, &target).unwrap();
+        assert_eq!(link_state(&source, &target), LinkState::Linked);
+        fs::remove_file(&target).unwrap();
+        std::os::unix::fs::symlink(&other, &target).unwrap();
+        assert_eq!(link_state(&source, &target), LinkState::WrongTarget);
+        fs::remove_file(&other).unwrap();
+        assert_eq!(link_state(&source, &target), LinkState::Broken);
+        fs::remove_file(&target).unwrap();
+        fs::write(&target, "tracked").unwrap();
+        assert_eq!(link_state(&source, &target), LinkState::NotLinked);
+    }
+}
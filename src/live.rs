@@ -0,0 +1,275 @@
+use anyhow::{Context, Result};
+use notify::{RecursiveMode, Watcher};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::drift::{hash_bytes, HashCache};
+use crate::filter::PathFilter;
+use crate::template::{is_template, render_file, rendered_path};
+
+/// How long to wait for more events after a change, so an editor's save
+/// (often a write, a rename, and a chmod) is handled once
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// A tracked file `apply --watch` keeps up to date
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiveTarget {
+    /// Tracked path, as listed in the profile
+    pub file: String,
+
+    /// Profile the file comes from (may be a parent of the applied profile)
+    pub profile: String,
+
+    pub source: PathBuf,
+    pub dest: PathBuf,
+}
+
+/// What [`refresh`] did to a destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Refresh {
+    Rendered,
+    Relinked,
+    Unchanged,
+
+    /// The source was deleted; the destination is left alone
+    SourceMissing,
+
+    /// A file that isn't ours is in the way
+    Conflict,
+}
+
+/// Bring one destination up to date with its source: templates are
+/// re-rendered, and missing or stale symlinks are pointed back at the source.
+pub fn refresh(
+    target: &LiveTarget,
+    vars: &HashMap<String, String>,
+    cache: &mut HashCache,
+) -> Result<Refresh> {
+    if !target.source.exists() {
+        return Ok(Refresh::SourceMissing);
+    }
+    let dest = &target.dest;
+    let dest_is_symlink = dest
+        .symlink_metadata()
+        .is_ok_and(|meta| meta.file_type().is_symlink());
+
+    if is_template(&target.file) {
+        let rendered = render_file(&target.source, vars)?;
+        cache.record_sync(
+            &target.profile,
+            &target.file,
+            hash_bytes(rendered.as_bytes()),
+        );
+        if !dest_is_symlink {
+            if dest.is_dir() {
+                return Ok(Refresh::Conflict);
+            }
+            if fs::read_to_string(dest).ok().as_deref() == Some(rendered.as_str()) {
+                return Ok(Refresh::Unchanged);
+            }
+        } else {
+            fs::remove_file(dest)?;
+        }
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(dest, rendered).with_context(|| format!("Failed to write {}", dest.display()))?;
+        return Ok(Refresh::Rendered);
+    }
+
+    if target.source.is_file() {
+        let hash = cache.hash_file(&target.source)?;
+        cache.record_sync(&target.profile, &target.file, hash);
+    }
+    if dest_is_symlink {
+        if crate::utils::symlink_points_to(dest, &target.source) {
+            return Ok(Refresh::Unchanged);
+        }
+        fs::remove_file(dest)?;
+    } else if dest.symlink_metadata().is_ok() {
+        return Ok(Refresh::Conflict);
+    }
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    std::os::unix::fs::symlink(&target.source, dest)
+        .with_context(|| format!("Failed to link {}", dest.display()))?;
+    Ok(Refresh::Relinked)
+}
+
+/// The path events for `path` arrive under: its parent resolved through
+/// symlinks (FSEvents reports `/private/var/...` for `/var/...`)
+fn event_key(path: &Path) -> PathBuf {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => fs::canonicalize(parent)
+            .unwrap_or_else(|_| parent.to_path_buf())
+            .join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Watch the sources of `profiles` and refresh each destination as its
+/// source changes. Runs until interrupted.
+pub fn run(
+    config: &Config,
+    profiles: &[String],
+    home_dir: &Path,
+    dotfiles_dir: &Path,
+    filter: &PathFilter,
+    quiet: bool,
+) -> Result<()> {
+    let (resolved, _) = config.resolve_profiles(profiles)?;
+    let targets: Vec<LiveTarget> = config
+        .link_targets(&resolved, false)?
+        .into_iter()
+        .filter(|(file, _, _)| filter.matches(file))
+        // Encrypted files are decrypted by apply; watching their ciphertext gains
+        // nothing. Managed blocks are only written by apply too.
+        .filter(|(file, profile, _)| {
+            let options = config.file_options(profile, file);
+            !options.encrypt && !options.managed_block
+        })
+        .map(|(file, profile, source)| LiveTarget {
+            dest: crate::paths::destination(home_dir, rendered_path(&file)),
+            file,
+            profile,
+            source,
+        })
+        .collect();
+    if targets.is_empty() {
+        eprintln!(
+            "Profile '{}' has no tracked files to watch.",
+            profiles.join(",")
+        );
+        return Ok(());
+    }
+
+    // Editors often save by writing a new file and renaming it over the old
+    // one, so the directories holding the sources are watched, not the files
+    let by_key: BTreeMap<PathBuf, usize> = targets
+        .iter()
+        .enumerate()
+        .map(|(index, target)| (event_key(&target.source), index))
+        .collect();
+    let dirs: BTreeSet<PathBuf> = targets
+        .iter()
+        .filter_map(|target| target.source.parent().map(Path::to_path_buf))
+        .filter(|dir| dir.is_dir())
+        .collect();
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("Failed to start file watcher")?;
+    for dir in &dirs {
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {}", dir.display()))?;
+    }
+    eprintln!(
+        "Watching {} tracked file(s) for changes. Press Ctrl-C to stop.",
+        targets.len()
+    );
+
+    let changed_targets = |event: notify::Result<notify::Event>, changed: &mut BTreeSet<usize>| {
+        if let Ok(event) = event {
+            if event.kind.is_access() {
+                return;
+            }
+            for path in &event.paths {
+                if let Some(index) = by_key.get(&event_key(path)) {
+                    changed.insert(*index);
+                }
+            }
+        }
+    };
+
+    while let Ok(event) = rx.recv() {
+        let mut changed = BTreeSet::new();
+        changed_targets(event, &mut changed);
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            changed_targets(event, &mut changed);
+        }
+        if changed.is_empty() {
+            continue;
+        }
+
+        let mut cache = HashCache::load(dotfiles_dir);
+        for index in changed {
+            let target = &targets[index];
+            let dest = target.dest.display();
+            match refresh(target, &resolved.vars, &mut cache) {
+                Ok(Refresh::Rendered) if !quiet => eprintln!("Rendered: {dest}"),
+                Ok(Refresh::Relinked) if !quiet => {
+                    eprintln!("Relinked: {dest} -> {}", target.source.display())
+                }
+                Ok(Refresh::SourceMissing) => eprintln!(
+                    "Warning: {} was removed; leaving {dest} as is",
+                    target.source.display()
+                ),
+                Ok(Refresh::Conflict) => eprintln!(
+                    "Warning: {dest} is in the way. Run 'ordinator apply --force' to replace it."
+                ),
+                Ok(_) => {}
+                // A template saved halfway through an edit may not render yet
+                Err(e) => eprintln!("Warning: could not update {dest}: {e}"),
+            }
+        }
+        cache.save()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refresh_renders_and_relinks() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut cache = HashCache::load(temp.path());
+        let vars = HashMap::from([("name".to_string(), "Ada".to_string())]);
+
+        let source = temp.path().join("files/gitconfig.tmpl");
+        fs::create_dir_all(source.parent().unwrap()).unwrap();
+        fs::write(&source, "name = {{ name }}\n").unwrap();
+        let template = LiveTarget {
+            file: ".gitconfig.tmpl".to_string(),
+            profile: "default".to_string(),
+            source: source.clone(),
+            dest: temp.path().join("home/.gitconfig"),
+        };
+        assert_eq!(
+            refresh(&template, &vars, &mut cache).unwrap(),
+            Refresh::Rendered
+        );
+        assert_eq!(fs::read_to_string(&template.dest).unwrap(), "name = Ada\n");
+        assert_eq!(
+            refresh(&template, &vars, &mut cache).unwrap(),
+            Refresh::Unchanged
+        );
+
+        let source = temp.path().join("files/zshrc");
+        fs::write(&source, "export EDITOR=vim\n").unwrap();
+        let plain = LiveTarget {
+            file: ".zshrc".to_string(),
+            profile: "default".to_string(),
+            source: source.clone(),
+            dest: temp.path().join("home/.zshrc"),
+        };
+        std::os::unix::fs::symlink(temp.path().join("elsewhere"), &plain.dest).unwrap();
+        assert_eq!(
+            refresh(&plain, &vars, &mut cache).unwrap(),
+            Refresh::Relinked
+        );
+        assert_eq!(fs::read_link(&plain.dest).unwrap(), source);
+
+        fs::remove_file(&source).unwrap();
+        assert_eq!(
+            refresh(&plain, &vars, &mut cache).unwrap(),
+            Refresh::SourceMissing
+        );
+    }
+}
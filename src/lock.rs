@@ -0,0 +1,279 @@
+//! `ordinator.lock`: hashes of every stored file and encrypted secret, and the
+//! Homebrew packages the profiles declare with the versions installed when it
+//! was written. `ordinator commit` keeps it current and `apply --frozen`
+//! refuses to apply a repository that no longer matches it.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use walkdir::WalkDir;
+
+use crate::config::Config;
+use crate::environment::{self, Effect};
+
+/// Lock file, relative to the dotfiles repository
+pub const LOCK_FILE: &str = "ordinator.lock";
+
+/// Recorded for a declared package that isn't installed, or when Homebrew isn't
+pub const NOT_INSTALLED: &str = "not installed";
+
+/// Repository directories whose contents are hashed
+const HASHED_DIRS: [&str; 2] = ["files", "secrets"];
+
+/// Exact contents of the repository at the last lock
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    /// SHA-256 of each file under `files/`, by repository path
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+
+    /// SHA-256 of each encrypted secret under `secrets/`, by repository path
+    #[serde(default)]
+    pub secrets: BTreeMap<String, String>,
+
+    /// Declared Homebrew formulas and casks, with the installed version
+    #[serde(default)]
+    pub packages: BTreeMap<String, String>,
+}
+
+/// How the repository differs from its lock
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct LockDiff {
+    /// Entries whose content changed
+    pub changed: Vec<String>,
+
+    /// Entries in the lock that are gone
+    pub missing: Vec<String>,
+
+    /// Entries not in the lock
+    pub unlocked: Vec<String>,
+}
+
+impl LockDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.missing.is_empty() && self.unlocked.is_empty()
+    }
+
+    /// One line per difference, e.g. `changed: files/default/.zshrc`
+    pub fn lines(&self) -> Vec<String> {
+        let label = |kind: &str, entries: &[String]| {
+            entries
+                .iter()
+                .map(|entry| format!("{kind}: {entry}"))
+                .collect::<Vec<_>>()
+        };
+        let mut lines = label("changed", &self.changed);
+        lines.extend(label("missing", &self.missing));
+        lines.extend(label("not in lock", &self.unlocked));
+        lines
+    }
+}
+
+impl Lockfile {
+    pub fn path(dotfiles_dir: &Path) -> PathBuf {
+        dotfiles_dir.join(LOCK_FILE)
+    }
+
+    /// Hash the repository and record the declared packages. `versions` maps
+    /// installed package names to versions.
+    pub fn generate(
+        config: &Config,
+        dotfiles_dir: &Path,
+        versions: &BTreeMap<String, String>,
+    ) -> Result<Self> {
+        let mut lock = Self {
+            files: hash_dir(dotfiles_dir, HASHED_DIRS[0])?,
+            secrets: hash_dir(dotfiles_dir, HASHED_DIRS[1])?,
+            packages: BTreeMap::new(),
+        };
+        for profile in config.profiles.values() {
+            for name in profile
+                .homebrew_formulas
+                .iter()
+                .chain(&profile.homebrew_casks)
+            {
+                let short = name.rsplit('/').next().unwrap_or(name);
+                let version = versions
+                    .get(short)
+                    .cloned()
+                    .unwrap_or_else(|| NOT_INSTALLED.to_string());
+                lock.packages.insert(name.clone(), version);
+            }
+        }
+        Ok(lock)
+    }
+
+    /// Load the lock, or `None` if the repository has none
+    pub fn load(dotfiles_dir: &Path) -> Result<Option<Self>> {
+        let path = Self::path(dotfiles_dir);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content)
+            .map(Some)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self, dotfiles_dir: &Path) -> Result<()> {
+        let path = Self::path(dotfiles_dir);
+        fs::write(&path, toml::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// How `current` differs from this lock. Package versions depend on the
+    /// machine, so only which packages are declared is compared.
+    pub fn diff(&self, current: &Lockfile) -> LockDiff {
+        let mut diff = LockDiff::default();
+        for (locked, live) in [
+            (&self.files, &current.files),
+            (&self.secrets, &current.secrets),
+        ] {
+            for (path, hash) in locked {
+                match live.get(path) {
+                    Some(live_hash) if live_hash != hash => diff.changed.push(path.clone()),
+                    Some(_) => {}
+                    None => diff.missing.push(path.clone()),
+                }
+            }
+            diff.unlocked.extend(
+                live.keys()
+                    .filter(|path| !locked.contains_key(*path))
+                    .cloned(),
+            );
+        }
+        diff.missing.extend(
+            self.packages
+                .keys()
+                .filter(|name| !current.packages.contains_key(*name))
+                .map(|name| format!("package {name}")),
+        );
+        diff.unlocked.extend(
+            current
+                .packages
+                .keys()
+                .filter(|name| !self.packages.contains_key(*name))
+                .map(|name| format!("package {name}")),
+        );
+        diff
+    }
+}
+
+/// SHA-256 of every file under `dotfiles_dir/dir`, keyed by repository path
+fn hash_dir(dotfiles_dir: &Path, dir: &str) -> Result<BTreeMap<String, String>> {
+    let mut hashes = BTreeMap::new();
+    let root = dotfiles_dir.join(dir);
+    if !root.exists() {
+        return Ok(hashes);
+    }
+    for entry in WalkDir::new(&root).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(dotfiles_dir)?;
+        let content = fs::read(entry.path())
+            .with_context(|| format!("Failed to read {}", entry.path().display()))?;
+        hashes.insert(
+            relative.to_string_lossy().replace('\\', "/"),
+            crate::drift::hash_bytes(&content),
+        );
+    }
+    Ok(hashes)
+}
+
+/// Installed Homebrew formula and cask versions, empty without Homebrew
+pub fn installed_versions() -> BTreeMap<String, String> {
+    let mut versions = BTreeMap::new();
+    for kind in ["--formula", "--cask"] {
+        let Ok(output) = environment::output(
+            Command::new("brew").args(["list", "--versions", kind]),
+            Effect::Reads,
+        ) else {
+            return versions;
+        };
+        if !output.status.success() {
+            continue;
+        }
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let mut parts = line.split_whitespace();
+            if let (Some(name), Some(version)) = (parts.next(), parts.last()) {
+                versions.insert(name.to_string(), version.to_string());
+            }
+        }
+    }
+    versions
+}
+
+/// Write a fresh lock for the repository
+pub fn write(config: &Config, dotfiles_dir: &Path) -> Result<Lockfile> {
+    let lock = Lockfile::generate(config, dotfiles_dir, &installed_versions())?;
+    lock.save(dotfiles_dir)?;
+    Ok(lock)
+}
+
+/// Refresh the lock if the repository has one; returns whether it did
+pub fn refresh(config: &Config, dotfiles_dir: &Path) -> Result<bool> {
+    if !Lockfile::path(dotfiles_dir).exists() {
+        return Ok(false);
+    }
+    write(config, dotfiles_dir)?;
+    Ok(true)
+}
+
+/// How the repository differs from its lock; an error if it has none
+pub fn check(config: &Config, dotfiles_dir: &Path) -> Result<LockDiff> {
+    let locked = Lockfile::load(dotfiles_dir)?.ok_or_else(|| {
+        anyhow::anyhow!("No {LOCK_FILE} in the repository. Run 'ordinator lock' to create one.")
+    })?;
+    let current = Lockfile::generate(config, dotfiles_dir, &BTreeMap::new())?;
+    Ok(locked.diff(&current))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProfileConfig;
+
+    #[test]
+    fn test_lock_diff() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("files/default")).unwrap();
+        fs::create_dir_all(dir.join("secrets/default")).unwrap();
+        fs::write(dir.join("files/default/.zshrc"), "export A=1\n").unwrap();
+        fs::write(dir.join("secrets/default/token"), "ENC[abc]").unwrap();
+        let mut config = Config::default();
+        let profile = ProfileConfig {
+            homebrew_formulas: vec!["git".into()],
+            ..Default::default()
+        };
+        config.add_profile("default".into(), profile).unwrap();
+
+        let versions = BTreeMap::from([("git".to_string(), "2.44.0".to_string())]);
+        let lock = Lockfile::generate(&config, dir, &versions).unwrap();
+        assert_eq!(lock.packages["git"], "2.44.0");
+        assert_eq!(lock.secrets.len(), 1);
+        lock.save(dir).unwrap();
+        assert_eq!(Lockfile::load(dir).unwrap(), Some(lock.clone()));
+        assert!(check(&config, dir).unwrap().is_empty());
+
+        fs::write(dir.join("files/default/.zshrc"), "export A=2\n").unwrap();
+        fs::remove_file(dir.join("secrets/default/token")).unwrap();
+        fs::write(dir.join("files/default/.vimrc"), "set nu\n").unwrap();
+        config
+            .profiles
+            .get_mut("default")
+            .unwrap()
+            .homebrew_formulas = vec!["jq".into()];
+        let diff = check(&config, dir).unwrap();
+        assert_eq!(diff.changed, vec!["files/default/.zshrc"]);
+        assert_eq!(diff.missing, vec!["secrets/default/token", "package git"]);
+        assert_eq!(diff.unlocked, vec!["files/default/.vimrc", "package jq"]);
+        assert_eq!(diff.lines()[0], "changed: files/default/.zshrc");
+    }
+}
@@ -0,0 +1,160 @@
+//! Logging: `tracing` output on stdout (INFO, or DEBUG with `--verbose`;
+//! `RUST_LOG` overrides both), plus JSON lines in the file given to
+//! `--log-file` for attaching to bug reports, and in the run log.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::layer::{Context as LayerContext, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+/// Set up logging for one run, also writing events to `run_log` (see
+/// [`crate::run_log`]). Does nothing if a subscriber is already set.
+pub fn init(verbose: bool, log_file: Option<&Path>, run_log: Option<File>) -> Result<()> {
+    let level = if verbose {
+        LevelFilter::DEBUG
+    } else {
+        LevelFilter::INFO
+    };
+    let rust_log = env_filter();
+    let console_filter = rust_log.clone().unwrap_or_else(|| own_targets(level));
+    let console = tracing_subscriber::fmt::layer()
+        .with_ansi(crate::utils::color_enabled())
+        .with_filter(console_filter);
+
+    let file_layer = match log_file {
+        Some(path) => {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("Failed to open log file {}", path.display()))?;
+            // The file gets everything ordinator logs, whatever the console shows
+            let filter = rust_log.unwrap_or_else(|| own_targets(LevelFilter::DEBUG));
+            Some(JsonLogLayer::new(file).with_filter(filter))
+        }
+        None => None,
+    };
+    let run_log_layer =
+        run_log.map(|file| JsonLogLayer::new(file).with_filter(own_targets(LevelFilter::DEBUG)));
+
+    let _ = tracing_subscriber::registry()
+        .with(console)
+        .with(file_layer)
+        .with(run_log_layer)
+        .try_init();
+    Ok(())
+}
+
+/// `RUST_LOG` as target directives, e.g. `debug` or `ordinator_core=trace,reqwest=info`
+fn env_filter() -> Option<Targets> {
+    let value = std::env::var("RUST_LOG").ok()?;
+    match value.parse() {
+        Ok(targets) => Some(targets),
+        Err(e) => {
+            eprintln!("Warning: Ignoring RUST_LOG '{value}': {e}");
+            None
+        }
+    }
+}
+
+/// `level` for ordinator itself; other crates only log warnings
+fn own_targets(level: LevelFilter) -> Targets {
+    Targets::new()
+        .with_default(LevelFilter::WARN)
+        .with_target("ordinator", level)
+        .with_target("ordinator_core", level)
+}
+
+/// Writes each event as one JSON object per line:
+/// `{"timestamp": ..., "level": ..., "target": ..., "message": ..., "fields": {...}}`
+struct JsonLogLayer {
+    file: Mutex<File>,
+}
+
+impl JsonLogLayer {
+    fn new(file: File) -> Self {
+        Self {
+            file: Mutex::new(file),
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for JsonLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: LayerContext<'_, S>) {
+        let metadata = event.metadata();
+        let mut visitor = JsonVisitor::default();
+        event.record(&mut visitor);
+        let line = serde_json::json!({
+            "timestamp": chrono::Local::now().to_rfc3339(),
+            "level": level_name(metadata.level()),
+            "target": metadata.target(),
+            "message": visitor.message,
+            "fields": visitor.fields,
+        });
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        // Logging must never fail the command
+        let _ = writeln!(file, "{line}");
+    }
+}
+
+fn level_name(level: &Level) -> &'static str {
+    match *level {
+        Level::ERROR => "error",
+        Level::WARN => "warn",
+        Level::INFO => "info",
+        Level::DEBUG => "debug",
+        Level::TRACE => "trace",
+    }
+}
+
+#[derive(Default)]
+struct JsonVisitor {
+    message: String,
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl JsonVisitor {
+    fn insert(&mut self, field: &Field, value: serde_json::Value) {
+        if field.name() == "message" {
+            self.message = match value {
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            };
+        } else {
+            self.fields.insert(field.name().to_string(), value);
+        }
+    }
+}
+
+impl Visit for JsonVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.insert(field, format!("{value:?}").into());
+    }
+}
@@ -0,0 +1,311 @@
+use globset::Glob;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::process::Command;
+use std::sync::OnceLock;
+
+use crate::config::Config;
+
+/// Facts about the current machine that `[profiles.<name>.match]` rules test
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MachineInfo {
+    pub hostname: Option<String>,
+    pub username: Option<String>,
+
+    /// CPU architecture as Rust names it, e.g. `aarch64` or `x86_64`
+    pub arch: String,
+
+    /// macOS product version, e.g. `14.5`; None on other systems
+    pub macos_version: Option<String>,
+}
+
+/// Overrides the hostname used for match rules and host overrides
+pub const HOSTNAME_ENV: &str = "ORDINATOR_HOSTNAME";
+
+/// Trimmed stdout of a command, or None if it fails or prints nothing
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+impl MachineInfo {
+    /// Look up the current machine
+    pub fn detect() -> Self {
+        Self {
+            hostname: std::env::var(HOSTNAME_ENV)
+                .ok()
+                .filter(|name| !name.is_empty())
+                .or_else(|| command_output("hostname", &[]))
+                .or_else(|| std::env::var("HOSTNAME").ok()),
+            username: std::env::var("USER")
+                .ok()
+                .filter(|user| !user.is_empty())
+                .or_else(|| command_output("whoami", &[])),
+            arch: std::env::consts::ARCH.to_string(),
+            macos_version: command_output("sw_vers", &["-productVersion"]),
+        }
+    }
+}
+
+/// This machine's name for host overrides under `files/<profile>/<host>/`:
+/// `ORDINATOR_HOSTNAME` if set, else the hostname up to the first dot, so
+/// `mac-studio.local` is `mac-studio`
+pub fn host_name() -> Option<String> {
+    static DETECTED: OnceLock<Option<String>> = OnceLock::new();
+    let name = match std::env::var(HOSTNAME_ENV) {
+        Ok(name) if !name.is_empty() => name,
+        _ => DETECTED
+            .get_or_init(|| {
+                command_output("hostname", &[]).or_else(|| std::env::var("HOSTNAME").ok())
+            })
+            .clone()?,
+    };
+    name.split('.')
+        .next()
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+}
+
+/// Rules a machine must meet for a profile to be picked automatically. Every
+/// rule that is set must match.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileMatch {
+    /// Glob for the hostname, e.g. `"work-*"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+
+    /// Exact login name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+
+    /// `arm64` (or `aarch64`) or `x86_64`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arch: Option<String>,
+
+    /// macOS version: a major version like `"14"`, or a glob like `"14.*"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub macos_version: Option<String>,
+}
+
+/// The outcome of one rule against the current machine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleResult {
+    pub rule: &'static str,
+    pub expected: String,
+    pub actual: Option<String>,
+    pub matched: bool,
+}
+
+impl fmt::Display for RuleResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} = \"{}\" (this machine: {})",
+            if self.matched { "✓" } else { "✗" },
+            self.rule,
+            self.expected,
+            self.actual.as_deref().unwrap_or("unknown")
+        )
+    }
+}
+
+fn glob_matches(pattern: &str, value: &str) -> bool {
+    Glob::new(pattern)
+        .map(|glob| glob.compile_matcher().is_match(value))
+        .unwrap_or(false)
+}
+
+/// `arm64` is what macOS calls `aarch64`
+fn normalize_arch(arch: &str) -> &str {
+    match arch {
+        "arm64" => "aarch64",
+        "amd64" => "x86_64",
+        other => other,
+    }
+}
+
+impl ProfileMatch {
+    pub fn is_empty(&self) -> bool {
+        self.hostname.is_none()
+            && self.username.is_none()
+            && self.arch.is_none()
+            && self.macos_version.is_none()
+    }
+
+    /// Test each configured rule against `machine`
+    pub fn evaluate(&self, machine: &MachineInfo) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+        if let Some(pattern) = &self.hostname {
+            results.push(RuleResult {
+                rule: "hostname",
+                expected: pattern.clone(),
+                actual: machine.hostname.clone(),
+                matched: machine
+                    .hostname
+                    .as_deref()
+                    .is_some_and(|hostname| glob_matches(pattern, hostname)),
+            });
+        }
+        if let Some(username) = &self.username {
+            results.push(RuleResult {
+                rule: "username",
+                expected: username.clone(),
+                actual: machine.username.clone(),
+                matched: machine.username.as_ref() == Some(username),
+            });
+        }
+        if let Some(arch) = &self.arch {
+            results.push(RuleResult {
+                rule: "arch",
+                expected: arch.clone(),
+                actual: Some(machine.arch.clone()),
+                matched: normalize_arch(arch) == normalize_arch(&machine.arch),
+            });
+        }
+        if let Some(version) = &self.macos_version {
+            results.push(RuleResult {
+                rule: "macos_version",
+                expected: version.clone(),
+                actual: machine.macos_version.clone(),
+                matched: machine.macos_version.as_deref().is_some_and(|actual| {
+                    actual == version
+                        || actual.starts_with(&format!("{version}."))
+                        || glob_matches(version, actual)
+                }),
+            });
+        }
+        results
+    }
+
+    /// Whether every configured rule matches. Empty rules never match.
+    pub fn matches(&self, machine: &MachineInfo) -> bool {
+        !self.is_empty() && self.evaluate(machine).iter().all(|result| result.matched)
+    }
+}
+
+/// A machine role under `[machines]`: the profiles one kind of machine gets,
+/// applied together in order, and what apply does on it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MachineRole {
+    /// Profiles to apply; where two track the same path, the later one wins
+    pub profiles: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Whether apply decrypts secrets (default true)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<bool>,
+
+    /// Whether apply installs Homebrew packages (default true)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub brew: Option<bool>,
+
+    /// Whether apply writes macOS defaults (default true)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub defaults: Option<bool>,
+}
+
+impl MachineRole {
+    /// `base, work, gpu (no secrets)`, for listings
+    pub fn describe(&self) -> String {
+        let skipped: Vec<&str> = [
+            (self.secrets, "secrets"),
+            (self.brew, "brew"),
+            (self.defaults, "defaults"),
+        ]
+        .into_iter()
+        .filter(|(enabled, _)| *enabled == Some(false))
+        .map(|(_, name)| name)
+        .collect();
+        let profiles = self.profiles.join(", ");
+        if skipped.is_empty() {
+            profiles
+        } else {
+            format!("{profiles} (no {})", skipped.join(", no "))
+        }
+    }
+}
+
+/// The profile whose `match` rules fit `machine`. When several fit, the one
+/// with the most rules wins, then the first by name. Disabled profiles are skipped.
+pub fn matching_profile<'a>(config: &'a Config, machine: &MachineInfo) -> Option<&'a str> {
+    let mut names: Vec<&String> = config.profiles.keys().collect();
+    names.sort();
+    let mut best: Option<(&str, usize)> = None;
+    for name in names {
+        let profile = &config.profiles[name];
+        if !profile.enabled || !profile.match_rules.matches(machine) {
+            continue;
+        }
+        let rules = profile.match_rules.evaluate(machine).len();
+        if best.is_none_or(|(_, best_rules)| rules > best_rules) {
+            best = Some((name, rules));
+        }
+    }
+    best.map(|(name, _)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn machine() -> MachineInfo {
+        MachineInfo {
+            hostname: Some("work-mbp.local".to_string()),
+            username: Some("alice".to_string()),
+            arch: "aarch64".to_string(),
+            macos_version: Some("14.5".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_rules_match() {
+        let rules = ProfileMatch {
+            hostname: Some("work-*".to_string()),
+            username: Some("alice".to_string()),
+            arch: Some("arm64".to_string()),
+            macos_version: Some("14".to_string()),
+        };
+        assert!(rules.matches(&machine()));
+
+        let rules = ProfileMatch {
+            macos_version: Some("13.*".to_string()),
+            ..rules
+        };
+        let results = rules.evaluate(&machine());
+        assert!(!rules.matches(&machine()));
+        assert_eq!(
+            results.last().unwrap().to_string(),
+            "✗ macos_version = \"13.*\" (this machine: 14.5)"
+        );
+        assert!(!ProfileMatch::default().matches(&machine()));
+    }
+
+    #[test]
+    fn test_matching_profile_prefers_most_specific() {
+        let mut config = Config::create_default();
+        config.get_profile_mut("personal").unwrap().match_rules = ProfileMatch {
+            arch: Some("aarch64".to_string()),
+            ..Default::default()
+        };
+        config.get_profile_mut("work").unwrap().match_rules = ProfileMatch {
+            arch: Some("aarch64".to_string()),
+            hostname: Some("work-*".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(matching_profile(&config, &machine()), Some("work"));
+
+        config.get_profile_mut("work").unwrap().enabled = false;
+        assert_eq!(matching_profile(&config, &machine()), Some("personal"));
+
+        config.get_profile_mut("personal").unwrap().match_rules = ProfileMatch::default();
+        assert_eq!(matching_profile(&config, &machine()), None);
+    }
+}
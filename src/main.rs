@@ -1,33 +1,19 @@
 use clap::Parser;
 use tracing::{error, info};
 
-mod bootstrap;
-mod brew;
-mod cli;
-mod config;
-mod git;
-mod readme;
-mod repo;
-mod secrets;
-mod utils;
-
-use cli::Args;
+use ordinator_core::cli::{self, Args};
+use ordinator_core::{error, exit_code};
 
 #[tokio::main]
 async fn main() {
-    // Initialize logging
-    tracing_subscriber::fmt::init();
-
-    info!("Starting Ordinator...");
-
-    // Parse command line arguments
+    // Parse command line arguments; logging is set up from them in cli::run
     let args = Args::parse();
 
     // Run the application
     if let Err(e) = cli::run(args).await {
         error!("Application error: {}", e);
-        eprintln!("{e}"); // Print error to stderr for user visibility
-        std::process::exit(1);
+        eprintln!("{}", error::report(&e)); // Print error to stderr for user visibility
+        std::process::exit(exit_code::ERROR);
     }
 
     info!("Ordinator completed successfully");
@@ -0,0 +1,704 @@
+//! `ordinator migrate`: import a chezmoi source directory, a GNU Stow
+//! directory, or a yadm repository into a profile. Each tool's naming is
+//! translated into tracked paths (`dot_zshrc` becomes `.zshrc`), and
+//! anything with no ordinator equivalent is reported instead of guessed at.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use walkdir::WalkDir;
+
+use crate::config::Config;
+
+/// The dotfiles manager a repository comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    Chezmoi,
+    Stow,
+    Yadm,
+}
+
+impl FromStr for Tool {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "chezmoi" => Ok(Self::Chezmoi),
+            "stow" => Ok(Self::Stow),
+            "yadm" => Ok(Self::Yadm),
+            _ => Err(anyhow::anyhow!(
+                "Can't migrate from '{name}'. Supported: chezmoi, stow, yadm"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Tool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Chezmoi => "chezmoi",
+            Self::Stow => "stow",
+            Self::Yadm => "yadm",
+        })
+    }
+}
+
+impl Tool {
+    /// Where the tool keeps its repository unless told otherwise
+    pub fn default_path(&self, home: &Path) -> PathBuf {
+        match self {
+            Self::Chezmoi => home.join(".local/share/chezmoi"),
+            Self::Stow => home.join("dotfiles"),
+            Self::Yadm => home.join(".local/share/yadm/repo.git"),
+        }
+    }
+}
+
+/// A file that can be tracked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Path as it would be tracked, relative to the home directory
+    pub path: String,
+
+    /// Where the content is, relative to the migrated repository
+    pub source: String,
+
+    /// Stored as this machine's host override instead of the profile's copy
+    pub host: Option<String>,
+
+    /// Mode the tool gives the file, e.g. `600` for chezmoi's `private_`
+    pub mode: Option<&'static str>,
+}
+
+/// A file left out, and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Untranslated {
+    /// Relative to the migrated repository
+    pub source: String,
+    pub reason: String,
+}
+
+impl fmt::Display for Untranslated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.source, self.reason)
+    }
+}
+
+/// What a repository translates into
+#[derive(Debug)]
+pub struct Migration {
+    pub tool: Tool,
+
+    /// Profile copies first, then host overrides
+    pub entries: Vec<Entry>,
+    pub untranslated: Vec<Untranslated>,
+
+    /// Directory the entries' sources are relative to
+    root: PathBuf,
+
+    /// yadm's files, checked out of its bare repository
+    _checkout: Option<tempfile::TempDir>,
+}
+
+impl Migration {
+    /// Full path of an entry's content
+    pub fn source_path(&self, entry: &Entry) -> PathBuf {
+        self.root.join(&entry.source)
+    }
+}
+
+/// Read the repository at `path` as `tool` lays it out
+pub fn scan(tool: Tool, path: &Path) -> Result<Migration> {
+    if !path.exists() {
+        return Err(anyhow::anyhow!(
+            "No {tool} repository at {}. Pass its path: ordinator migrate --from {tool} <path>",
+            path.display()
+        ));
+    }
+    let (root, checkout) = match tool {
+        // yadm's work tree is the home directory, so its files are read from the repository
+        Tool::Yadm => {
+            let checkout = checkout_head(path)?;
+            (checkout.path().to_path_buf(), Some(checkout))
+        }
+        _ => (path.to_path_buf(), None),
+    };
+    let files = repository_files(&root)?;
+    let (mut entries, untranslated) = match tool {
+        Tool::Chezmoi => scan_chezmoi(&files, &chezmoi_ignore(&root)?),
+        Tool::Stow => scan_stow(&files),
+        Tool::Yadm => scan_yadm(&files),
+    };
+    entries.sort_by(|a, b| (a.host.is_some(), &a.path).cmp(&(b.host.is_some(), &b.path)));
+    Ok(Migration {
+        tool,
+        entries,
+        untranslated,
+        root,
+        _checkout: checkout,
+    })
+}
+
+/// Files under `root` relative to it, leaving out Git's own
+fn repository_files(root: &Path) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    let walker = WalkDir::new(root)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".git");
+    for entry in walker {
+        let entry = entry.with_context(|| format!("Failed to read {}", root.display()))?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(root)?;
+        files.push(relative.to_string_lossy().into_owned());
+    }
+    Ok(files)
+}
+
+/// Write the files at `HEAD` of the Git repository at `path` (bare or not)
+/// into a temporary directory, keeping the executable bit
+fn checkout_head(path: &Path) -> Result<tempfile::TempDir> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = git2::Repository::open(path)
+        .with_context(|| format!("{} is not a Git repository", path.display()))?;
+    let tree = repo
+        .head()
+        .and_then(|head| head.peel_to_tree())
+        .with_context(|| format!("{} has no commits", path.display()))?;
+    let checkout = tempfile::tempdir()?;
+    let mut failure = None;
+    tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+        if entry.kind() != Some(git2::ObjectType::Blob) {
+            return git2::TreeWalkResult::Ok;
+        }
+        let name = entry.name().unwrap_or_default();
+        let dest = checkout.path().join(dir).join(name);
+        let written = (|| -> Result<()> {
+            let blob = entry.to_object(&repo)?.peel_to_blob()?;
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&dest, blob.content())?;
+            let mode = if entry.filemode() == 0o100755 {
+                0o755
+            } else {
+                0o644
+            };
+            fs::set_permissions(&dest, fs::Permissions::from_mode(mode))?;
+            Ok(())
+        })();
+        match written {
+            Ok(()) => git2::TreeWalkResult::Ok,
+            Err(error) => {
+                failure = Some(error.context(format!("Failed to check out {dir}{name}")));
+                git2::TreeWalkResult::Abort
+            }
+        }
+    })?;
+    match failure {
+        Some(error) => Err(error),
+        None => Ok(checkout),
+    }
+}
+
+fn untranslated(source: &str, reason: &str) -> Untranslated {
+    Untranslated {
+        source: source.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+/// A chezmoi source name's target name and the mode its attributes give,
+/// or why it can't be translated
+fn chezmoi_name(name: &str, is_dir: bool) -> std::result::Result<(String, Option<u32>), String> {
+    if name.starts_with(".chezmoi") {
+        return Err("chezmoi's own configuration".to_string());
+    }
+    if name.starts_with('.') {
+        return Err("ignored by chezmoi".to_string());
+    }
+    let mut rest = name;
+    let mut private = false;
+    let mut executable = false;
+    while let Some((prefix, after)) = rest.split_once('_') {
+        match (prefix, is_dir) {
+            ("literal", _) => {
+                rest = after;
+                break;
+            }
+            ("dot", _) => {
+                rest = after;
+                break;
+            }
+            ("private", _) => private = true,
+            ("executable", false) => executable = true,
+            ("readonly" | "empty" | "create", false) | ("exact", true) => {}
+            ("encrypted", false) => {
+                return Err(
+                    "encrypted with chezmoi's key; decrypt it with chezmoi, then 'ordinator watch --encrypt'"
+                        .to_string(),
+                );
+            }
+            ("modify", false) => {
+                return Err("a chezmoi modify script; it has no ordinator equivalent".to_string());
+            }
+            ("run", false) => {
+                return Err("a chezmoi script; run it from a bootstrap script instead".to_string());
+            }
+            ("symlink", false) => {
+                return Err("a chezmoi symlink; link it yourself or track its target".to_string());
+            }
+            ("remove", _) => return Err("a chezmoi removal".to_string()),
+            ("external", true) => {
+                return Err("a chezmoi external; its contents come from elsewhere".to_string());
+            }
+            _ => break,
+        }
+        rest = after;
+    }
+    // `dot_` starts the name proper, so whatever follows it is kept
+    let target = if rest.len() < name.len() && name[..name.len() - rest.len()].ends_with("dot_") {
+        format!(".{rest}")
+    } else {
+        rest.to_string()
+    };
+    if !is_dir && target.ends_with(".tmpl") {
+        return Err(
+            "a chezmoi template (Go syntax); rewrite it for ordinator's templates and 'ordinator watch' it"
+                .to_string(),
+        );
+    }
+    let mode = match (private && !is_dir, executable) {
+        (true, true) => Some(0o700),
+        (true, false) => Some(0o600),
+        (false, true) => Some(0o755),
+        (false, false) => None,
+    };
+    let target = target
+        .strip_suffix(".literal")
+        .unwrap_or(&target)
+        .to_string();
+    Ok((target, mode))
+}
+
+fn mode_str(mode: u32) -> &'static str {
+    match mode {
+        0o700 => "700",
+        0o600 => "600",
+        _ => "755",
+    }
+}
+
+/// Patterns from `.chezmoiignore` that apply everywhere: lines inside
+/// template conditions and negations are left out
+fn chezmoi_ignore(root: &Path) -> Result<globset::GlobSet> {
+    let mut builder = globset::GlobSetBuilder::new();
+    let text = fs::read_to_string(root.join(".chezmoiignore")).unwrap_or_default();
+    let mut depth = 0usize;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with("{{") {
+            if line.contains("end") {
+                depth = depth.saturating_sub(1);
+            } else if line.contains("if") || line.contains("range") || line.contains("with") {
+                depth += 1;
+            }
+            continue;
+        }
+        if depth > 0 || line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+            continue;
+        }
+        if let Ok(glob) = globset::Glob::new(line) {
+            builder.add(glob);
+        }
+    }
+    Ok(builder.build()?)
+}
+
+fn scan_chezmoi(files: &[String], ignore: &globset::GlobSet) -> (Vec<Entry>, Vec<Untranslated>) {
+    let mut entries = Vec::new();
+    let mut skipped = Vec::new();
+    for source in files {
+        let components: Vec<&str> = source.split('/').collect();
+        let last = components.len() - 1;
+        let mut target = Vec::new();
+        let mut mode = None;
+        let mut reason = None;
+        for (index, component) in components.iter().enumerate() {
+            match chezmoi_name(component, index < last) {
+                Ok((name, component_mode)) => {
+                    target.push(name);
+                    if index == last {
+                        mode = component_mode;
+                    }
+                }
+                Err(why) => {
+                    reason = Some(why);
+                    break;
+                }
+            }
+        }
+        match reason {
+            // chezmoi skips dotfiles in its source directory; only its own are worth a mention
+            Some(why) if why == "ignored by chezmoi" => {}
+            Some(why) => skipped.push(untranslated(source, &why)),
+            None if ignore.is_match(target.join("/")) => {}
+            None => entries.push(Entry {
+                path: target.join("/"),
+                source: source.clone(),
+                host: None,
+                mode: mode.map(mode_str),
+            }),
+        }
+    }
+    (entries, skipped)
+}
+
+/// Stow's default ignore list, for the files that aren't configuration
+fn stow_ignored(package_path: &str) -> bool {
+    let name = package_path.rsplit('/').next().unwrap_or(package_path);
+    let top_level = !package_path.contains('/');
+    matches!(name, ".gitignore" | ".gitmodules" | ".stow-local-ignore")
+        || name.ends_with('~')
+        || (top_level
+            && (name.starts_with("README")
+                || name.starts_with("LICENSE")
+                || name.starts_with("COPYING")))
+}
+
+fn scan_stow(files: &[String]) -> (Vec<Entry>, Vec<Untranslated>) {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut skipped = Vec::new();
+    let mut packages: BTreeMap<String, String> = BTreeMap::new();
+    for source in files {
+        let Some((package, inside)) = source.split_once('/') else {
+            if !source.starts_with('.') && !stow_ignored(source) {
+                skipped.push(untranslated(source, "not inside a stow package"));
+            }
+            continue;
+        };
+        if package.starts_with('.') || stow_ignored(inside) {
+            continue;
+        }
+        // `stow --dotfiles` names `.zshrc` as `dot-zshrc`
+        let path = inside
+            .split('/')
+            .map(|component| match component.strip_prefix("dot-") {
+                Some(name) => format!(".{name}"),
+                None => component.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        if let Some(first) = packages.get(&path) {
+            skipped.push(untranslated(
+                source,
+                &format!("also in package '{first}', which is used"),
+            ));
+            continue;
+        }
+        packages.insert(path.clone(), package.to_string());
+        entries.push(Entry {
+            path,
+            source: source.clone(),
+            host: None,
+            mode: None,
+        });
+    }
+    (entries, skipped)
+}
+
+/// How well a yadm alternate's conditions fit this machine: the profile's
+/// copy, a host override, or a reason it doesn't translate
+enum Alternate {
+    Base { rank: u8 },
+    Host(String),
+    Unsupported(String),
+}
+
+fn yadm_alternate(conditions: &str) -> Alternate {
+    let mut rank = 0;
+    let mut host = None;
+    for condition in conditions.split(',') {
+        let (key, value) = condition.split_once('.').unwrap_or((condition, ""));
+        match key {
+            "default" => {}
+            "o" | "os" if value == "Darwin" => rank = 1,
+            "o" | "os" => {
+                return Alternate::Unsupported(format!("for {value}; ordinator is for macOS"));
+            }
+            "h" | "hostname" => host = Some(value.to_string()),
+            "t" | "template" => {
+                return Alternate::Unsupported(
+                    "a yadm template; rewrite it for ordinator's templates and 'ordinator watch' it"
+                        .to_string(),
+                );
+            }
+            "c" | "class" => {
+                return Alternate::Unsupported(
+                    "for a yadm class; put it in the profile for that class".to_string(),
+                );
+            }
+            "e" | "extension" => {}
+            _ => {
+                return Alternate::Unsupported(format!(
+                    "yadm condition '{key}' has no ordinator equivalent"
+                ));
+            }
+        }
+    }
+    match host {
+        Some(host) => Alternate::Host(host),
+        None => Alternate::Base { rank },
+    }
+}
+
+fn scan_yadm(files: &[String]) -> (Vec<Entry>, Vec<Untranslated>) {
+    let mut entries = Vec::new();
+    let mut skipped = Vec::new();
+    // Best profile copy of each alternate: (rank, source)
+    let mut bases: BTreeMap<String, (u8, String)> = BTreeMap::new();
+    let mut hosts: Vec<(String, String, String)> = Vec::new();
+    for source in files {
+        if source.starts_with(".config/yadm/") || source.starts_with(".local/share/yadm/") {
+            skipped.push(untranslated(
+                source,
+                "yadm's own configuration; move a bootstrap to ordinator's bootstrap script",
+            ));
+            continue;
+        }
+        let (dir, name) = match source.rsplit_once('/') {
+            Some((dir, name)) => (format!("{dir}/"), name),
+            None => (String::new(), source.as_str()),
+        };
+        if dir.contains("##") {
+            skipped.push(untranslated(
+                source,
+                "inside a yadm alternate directory; track the directory's files instead",
+            ));
+            continue;
+        }
+        let Some((base, conditions)) = name.split_once("##") else {
+            entries.push(Entry {
+                path: source.clone(),
+                source: source.clone(),
+                host: None,
+                mode: None,
+            });
+            continue;
+        };
+        let path = format!("{dir}{base}");
+        match yadm_alternate(conditions) {
+            Alternate::Base { rank } => match bases.get(&path) {
+                Some((best, _)) if *best >= rank => skipped.push(untranslated(
+                    source,
+                    "a less specific alternate than the one used",
+                )),
+                _ => {
+                    if let Some((_, replaced)) = bases.insert(path, (rank, source.clone())) {
+                        skipped.push(untranslated(
+                            &replaced,
+                            "a less specific alternate than the one used",
+                        ));
+                    }
+                }
+            },
+            Alternate::Host(host) => hosts.push((path, host, source.clone())),
+            Alternate::Unsupported(reason) => skipped.push(untranslated(source, &reason)),
+        }
+    }
+    for (path, (_, source)) in &bases {
+        entries.push(Entry {
+            path: path.clone(),
+            source: source.clone(),
+            host: None,
+            mode: None,
+        });
+    }
+    for (path, host, source) in hosts {
+        // A host override needs a profile copy for the other machines to fall back on
+        if bases.contains_key(&path) || entries.iter().any(|entry| entry.path == path) {
+            entries.push(Entry {
+                path,
+                source,
+                host: Some(host),
+                mode: None,
+            });
+        } else {
+            skipped.push(untranslated(
+                &source,
+                "only has host alternates; add a ##default one so other machines get a copy",
+            ));
+        }
+    }
+    (entries, skipped)
+}
+
+/// Copy an entry into `profile` and track it: as the profile's copy, or as
+/// a host override of one tracked before it
+pub fn import(
+    config: &mut Config,
+    dotfiles_dir: &Path,
+    profile: &str,
+    migration: &Migration,
+    entry: &Entry,
+) -> Result<()> {
+    let source = migration.source_path(entry);
+    let stored = match &entry.host {
+        Some(host) => config.get_host_override_path(profile, &entry.path, host)?,
+        None => config.get_profile_file_path(profile, &entry.path)?,
+    };
+    if let Some(parent) = stored.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(&source, &stored)
+        .with_context(|| format!("Failed to copy {} into the profile", source.display()))?;
+    if entry.host.is_some() {
+        return Ok(());
+    }
+    crate::large_files::handle_stored(
+        &config.global.large_files,
+        dotfiles_dir,
+        &stored,
+        &entry.path,
+    )?;
+    config.add_file_to_profile(profile, entry.path.clone())?;
+    match entry.mode {
+        Some(mode) => {
+            let profile = config.get_profile_mut(profile).ok_or_else(|| {
+                crate::error::OrdinatorError::ProfileNotFound(profile.to_string())
+            })?;
+            profile
+                .file_modes
+                .insert(entry.path.clone(), mode.to_string());
+        }
+        None => config.record_file_mode(profile, &entry.path, &source)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|path| path.to_string()).collect()
+    }
+
+    #[test]
+    fn test_chezmoi_names() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(
+            root.path().join(".chezmoiignore"),
+            "README.md\n{{ if ne .chezmoi.os \"darwin\" }}\n.zshrc\n{{ end }}\n",
+        )
+        .unwrap();
+        let ignore = chezmoi_ignore(root.path()).unwrap();
+        let (entries, skipped) = scan_chezmoi(
+            &strings(&[
+                ".chezmoiignore",
+                ".git-hooks/pre-commit",
+                "dot_zshrc",
+                "private_dot_ssh/private_config",
+                "dot_local/bin/executable_backup",
+                "dot_gitconfig.tmpl",
+                "encrypted_private_dot_netrc.age",
+                "run_once_install.sh",
+                "literal_dot_not_a_prefix",
+                "README.md",
+            ]),
+            &ignore,
+        );
+        let found: Vec<(&str, Option<&str>)> = entries
+            .iter()
+            .map(|entry| (entry.path.as_str(), entry.mode))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (".zshrc", None),
+                (".ssh/config", Some("600")),
+                (".local/bin/backup", Some("755")),
+                ("dot_not_a_prefix", None),
+            ]
+        );
+        let sources: Vec<&str> = skipped.iter().map(|s| s.source.as_str()).collect();
+        assert_eq!(
+            sources,
+            [
+                ".chezmoiignore",
+                "dot_gitconfig.tmpl",
+                "encrypted_private_dot_netrc.age",
+                "run_once_install.sh",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stow_packages() {
+        let (entries, skipped) = scan_stow(&strings(&[
+            "README.md",
+            "notes.txt",
+            "git/.gitconfig",
+            "nvim/.config/nvim/init.lua",
+            "nvim/README.md",
+            "zsh/dot-zshrc",
+            "zsh-work/dot-zshrc",
+        ]));
+        let paths: Vec<&str> = entries.iter().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(paths, [".gitconfig", ".config/nvim/init.lua", ".zshrc"]);
+        assert_eq!(
+            skipped,
+            [
+                untranslated("notes.txt", "not inside a stow package"),
+                untranslated("zsh-work/dot-zshrc", "also in package 'zsh', which is used"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_yadm_alternates() {
+        let (entries, skipped) = scan_yadm(&strings(&[
+            ".config/yadm/bootstrap",
+            ".gitconfig##default",
+            ".gitconfig##hostname.mac-studio",
+            ".tmux.conf##os.Linux",
+            ".vimrc",
+            ".zshrc##default",
+            ".zshrc##os.Darwin",
+            ".ssh/config##h.laptop",
+        ]));
+        let found: Vec<(&str, &str, Option<&str>)> = entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.source.as_str(), e.host.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (".vimrc", ".vimrc", None),
+                (".gitconfig", ".gitconfig##default", None),
+                (".zshrc", ".zshrc##os.Darwin", None),
+                (
+                    ".gitconfig",
+                    ".gitconfig##hostname.mac-studio",
+                    Some("mac-studio")
+                ),
+            ]
+        );
+        let sources: Vec<&str> = skipped.iter().map(|s| s.source.as_str()).collect();
+        assert_eq!(
+            sources,
+            [
+                ".config/yadm/bootstrap",
+                ".tmux.conf##os.Linux",
+                ".zshrc##default",
+                ".ssh/config##h.laptop",
+            ]
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! Parent directories apply creates for nested destinations such as
+//! `.config/foo/bar.toml`: each missing one is created with the mode
+//! `[global] directory_modes` gives it (private directories like `.ssh` and
+//! `.gnupg` default to 700), and returned so apply can journal it for
+//! rollback and uninstall.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::parse_mode;
+
+/// Directories tools refuse to use unless only the owner can read them,
+/// relative to the target root
+pub const PRIVATE: &[&str] = &[".ssh", ".gnupg"];
+
+/// Modes for directories apply creates, by absolute path
+#[derive(Debug, Clone, Default)]
+pub struct DirectoryModes {
+    modes: BTreeMap<PathBuf, u32>,
+}
+
+impl DirectoryModes {
+    /// [`PRIVATE`] at 700, then `configured` (`".gnupg" = "700"`), each
+    /// path resolved like a tracked path under `root`
+    pub fn new(root: &Path, configured: &BTreeMap<String, String>) -> Result<Self> {
+        let mut modes: BTreeMap<PathBuf, u32> =
+            PRIVATE.iter().map(|dir| (root.join(dir), 0o700)).collect();
+        for (dir, mode) in configured {
+            let mode = parse_mode(mode)
+                .with_context(|| format!("Invalid mode for '{dir}' in directory_modes"))?;
+            modes.insert(
+                crate::paths::destination(root, dir.trim_end_matches('/')),
+                mode,
+            );
+        }
+        Ok(Self { modes })
+    }
+
+    /// The mode `dir` is created with; none leaves it to the umask
+    pub fn mode(&self, dir: &Path) -> Option<u32> {
+        self.modes.get(dir).copied()
+    }
+
+    /// Create the missing parent directories of `dest`, returning each with
+    /// the mode it was given, outermost first
+    pub fn create_parents(&self, dest: &Path) -> Result<Vec<(PathBuf, Option<u32>)>> {
+        let missing: Vec<&Path> = dest
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+            .collect();
+        let mut created = Vec::new();
+        for dir in missing.into_iter().rev() {
+            fs::create_dir(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+            let mode = self.mode(dir);
+            if let Some(mode) = mode {
+                crate::utils::set_file_mode(dir, mode)?;
+            }
+            created.push((dir.to_path_buf(), mode));
+        }
+        Ok(created)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_parents_with_modes() {
+        let home = tempfile::tempdir().unwrap();
+        let configured = BTreeMap::from([("~/.config/foo/".to_string(), "750".to_string())]);
+        let modes = DirectoryModes::new(home.path(), &configured).unwrap();
+
+        let created = modes
+            .create_parents(&home.path().join(".config/foo/bar.toml"))
+            .unwrap();
+        assert_eq!(
+            created,
+            [
+                (home.path().join(".config"), None),
+                (home.path().join(".config/foo"), Some(0o750)),
+            ]
+        );
+        let mode = crate::utils::file_mode(&home.path().join(".config/foo")).unwrap();
+        assert_eq!(mode, 0o750);
+
+        let created = modes
+            .create_parents(&home.path().join(".gnupg/gpg.conf"))
+            .unwrap();
+        assert_eq!(created, [(home.path().join(".gnupg"), Some(0o700))]);
+        assert!(modes
+            .create_parents(&home.path().join(".config/foo/baz.toml"))
+            .unwrap()
+            .is_empty());
+
+        let invalid = BTreeMap::from([(".ssh".to_string(), "rwx".to_string())]);
+        assert!(DirectoryModes::new(home.path(), &invalid).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+//! Tracked paths as `ordinator.toml` writes them: relative to the target root
+//! (`.zshrc`), under `~/`, absolute (`/Library/Preferences/...`), or under an
+//! XDG base directory (`$XDG_CONFIG_HOME/nvim`). Entries keep the form the
+//! user wrote, tidied by [`normalize`] when the config is loaded;
+//! [`destination`] says where each one lives.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Variables a tracked path may start with, besides `$HOME`, and where each
+/// one defaults to under the target root
+pub const XDG_DIRS: [(&str, &str); 4] = [
+    ("XDG_CONFIG_HOME", ".config"),
+    ("XDG_DATA_HOME", ".local/share"),
+    ("XDG_STATE_HOME", ".local/state"),
+    ("XDG_CACHE_HOME", ".cache"),
+];
+
+/// Split `$NAME/rest` or `${NAME}/rest` into the variable and the rest
+fn split_variable(path: &str) -> Option<(&str, &str)> {
+    let path = path.strip_prefix('$')?;
+    let (name, rest) = match path.strip_prefix('{') {
+        Some(braced) => braced.split_once('}')?,
+        None => path
+            .split_once('/')
+            .map_or((path, ""), |(name, rest)| (name, rest)),
+    };
+    Some((name, rest.trim_start_matches('/')))
+}
+
+/// The tidy form of a tracked path: `./`, `//`, and trailing slashes
+/// dropped, and `$HOME` written as `~`. `..` and variables other than
+/// `$HOME` and the XDG base directories are errors.
+pub fn normalize(path: &str) -> Result<String> {
+    let (prefix, rest) = if path == "~" || path.starts_with("~/") {
+        ("~", &path[1..])
+    } else if path.starts_with('/') {
+        ("", path)
+    } else if let Some((name, rest)) = split_variable(path) {
+        match name {
+            "HOME" => ("~", rest),
+            _ if XDG_DIRS.iter().any(|(xdg, _)| *xdg == name) => (name, rest),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Tracked path '{path}' starts with ${name}; only $HOME and $XDG_CONFIG_HOME, $XDG_DATA_HOME, $XDG_STATE_HOME, and $XDG_CACHE_HOME are supported"
+                ))
+            }
+        }
+    } else {
+        ("", path)
+    };
+    let mut components = Vec::new();
+    for component in rest.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                return Err(anyhow::anyhow!(
+                    "Tracked path '{path}' contains '..'; write it as an absolute path or under ~/"
+                ))
+            }
+            _ => components.push(component),
+        }
+    }
+    let rest = components.join("/");
+    Ok(match prefix {
+        "" if path.starts_with('/') => format!("/{rest}"),
+        "" if rest.is_empty() => return Err(anyhow::anyhow!("Tracked path is empty")),
+        "" => rest,
+        "~" if rest.is_empty() => "~".to_string(),
+        "~" => format!("~/{rest}"),
+        name if rest.is_empty() => format!("${name}"),
+        name => format!("${name}/{rest}"),
+    })
+}
+
+/// Where the XDG base directory `name` is for `home_dir`: the variable's
+/// value for the user's own home, else its default under `home_dir`
+fn xdg_dir(name: &str, home_dir: &Path) -> Option<PathBuf> {
+    let default = XDG_DIRS.iter().find(|(xdg, _)| *xdg == name)?.1;
+    let own_home = std::env::var_os("ORDINATOR_HOME").is_none()
+        && dirs::home_dir().is_some_and(|home| home == home_dir);
+    match std::env::var_os(name) {
+        Some(dir) if own_home && Path::new(&dir).is_absolute() => Some(PathBuf::from(dir)),
+        _ => Some(home_dir.join(default)),
+    }
+}
+
+/// Where the tracked path `path` lives: `~/` and relative paths under
+/// `home_dir` (the target root), absolute paths as they are, and
+/// `$XDG_...` under that base directory
+pub fn destination(home_dir: &Path, path: &str) -> PathBuf {
+    if path == "~" {
+        return home_dir.to_path_buf();
+    }
+    if let Some(rest) = path.strip_prefix("~/") {
+        return home_dir.join(rest);
+    }
+    if let Some((name, rest)) = split_variable(path) {
+        let base = if name == "HOME" {
+            Some(home_dir.to_path_buf())
+        } else {
+            xdg_dir(name, home_dir)
+        };
+        if let Some(base) = base {
+            return if rest.is_empty() {
+                base
+            } else {
+                base.join(rest)
+            };
+        }
+    }
+    home_dir.join(path)
+}
+
+/// The live file `path` names when a command reads it from the current
+/// directory, as `watch` and `add` do: relative paths stay relative, the
+/// others are resolved by [`destination`]
+pub fn live(home_dir: &Path, path: &str) -> PathBuf {
+    if path.starts_with(['~', '/', '$']) {
+        destination(home_dir, path)
+    } else {
+        PathBuf::from(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_and_destination() {
+        assert_eq!(normalize("./.config//nvim/").unwrap(), ".config/nvim");
+        assert_eq!(normalize("~/.zshrc").unwrap(), "~/.zshrc");
+        assert_eq!(normalize("$HOME/.zshrc").unwrap(), "~/.zshrc");
+        assert_eq!(normalize("${HOME}").unwrap(), "~");
+        assert_eq!(
+            normalize("${XDG_CONFIG_HOME}/nvim/").unwrap(),
+            "$XDG_CONFIG_HOME/nvim"
+        );
+        assert_eq!(
+            normalize("/Library/Preferences/com.example.plist").unwrap(),
+            "/Library/Preferences/com.example.plist"
+        );
+        assert!(normalize("$WORK/notes").is_err());
+        assert!(normalize("../outside").is_err());
+        assert!(normalize("./").is_err());
+
+        let home = Path::new("/Users/me/not-my-home");
+        assert_eq!(destination(home, ".zshrc"), home.join(".zshrc"));
+        assert_eq!(destination(home, "~/.zshrc"), home.join(".zshrc"));
+        assert_eq!(destination(home, "~"), home);
+        assert_eq!(
+            destination(home, "$XDG_CONFIG_HOME/nvim"),
+            home.join(".config/nvim")
+        );
+        assert_eq!(
+            destination(home, "/Library/Fonts/a.ttf"),
+            Path::new("/Library/Fonts/a.ttf")
+        );
+        assert_eq!(live(home, ".zshrc"), Path::new(".zshrc"));
+        assert_eq!(live(home, "~/.zshrc"), home.join(".zshrc"));
+    }
+}
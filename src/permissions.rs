@@ -0,0 +1,233 @@
+//! Pre-flight permission checks for apply: every destination it can't write
+//! is found before anything changes, and reported grouped by cause with the
+//! fix for each, instead of apply stopping at the first `Permission denied`.
+
+use std::collections::BTreeSet;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Directories System Integrity Protection keeps read-only, even for root
+const PROTECTED: &[&str] = &["/System", "/bin", "/sbin", "/usr"];
+
+/// Directories under [`PROTECTED`] that stay writable
+const UNPROTECTED: &[&str] = &["/usr/local"];
+
+/// Why a destination can't be written
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Blocker {
+    /// Under a directory System Integrity Protection guards
+    Protected,
+
+    /// On a volume mounted read-only
+    ReadOnlyVolume,
+
+    /// A file is where one of its parent directories should be
+    NotADirectory(PathBuf),
+
+    /// Its parent directory is missing, and the existing directory it would
+    /// be created in isn't writable
+    MissingParent(PathBuf),
+
+    /// Its directory isn't writable
+    DirectoryDenied(PathBuf),
+
+    /// The file is written in place (a template, encrypted file, or managed
+    /// block) and isn't writable
+    FileDenied,
+}
+
+impl Blocker {
+    /// Whether running as root would get past it, so a link made with sudo is fine
+    pub fn sudo_can_fix(&self) -> bool {
+        matches!(self, Self::MissingParent(_) | Self::DirectoryDenied(_))
+    }
+
+    fn heading(&self) -> &'static str {
+        match self {
+            Self::Protected => {
+                "Protected by System Integrity Protection (not even sudo can change these)"
+            }
+            Self::ReadOnlyVolume => "On a read-only volume",
+            Self::NotADirectory(_) => "A file is in the way of a parent directory",
+            Self::MissingParent(_) => "Parent directory is missing and can't be created",
+            Self::DirectoryDenied(_) => "Directory isn't writable",
+            Self::FileDenied => "File isn't writable",
+        }
+    }
+
+    /// The directory or file the fix is about, if it isn't the destination
+    fn culprit(&self) -> Option<&Path> {
+        match self {
+            Self::NotADirectory(path) | Self::MissingParent(path) | Self::DirectoryDenied(path) => {
+                Some(path)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A destination apply can't write
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub dest: PathBuf,
+    pub blocker: Blocker,
+}
+
+fn is_protected(dest: &Path) -> bool {
+    PROTECTED.iter().any(|dir| dest.starts_with(dir))
+        && !UNPROTECTED.iter().any(|dir| dest.starts_with(dir))
+}
+
+/// What would stop apply writing `dest`, if anything. Replacing a path
+/// means writing its directory, which is probed with a temporary file;
+/// `in_place` destinations are also opened for writing when they exist.
+pub fn check(dest: &Path, in_place: bool) -> Option<Blocker> {
+    if is_protected(dest) {
+        return Some(Blocker::Protected);
+    }
+    let parent = dest.parent()?;
+    let existing = parent.ancestors().find(|dir| dir.exists())?;
+    if !existing.is_dir() {
+        return Some(Blocker::NotADirectory(existing.to_path_buf()));
+    }
+    match tempfile::tempfile_in(existing) {
+        Err(e) if e.kind() == ErrorKind::ReadOnlyFilesystem => {
+            return Some(Blocker::ReadOnlyVolume);
+        }
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+            return Some(if existing == parent {
+                Blocker::DirectoryDenied(existing.to_path_buf())
+            } else {
+                Blocker::MissingParent(existing.to_path_buf())
+            });
+        }
+        _ => {}
+    }
+    let file = dest.symlink_metadata().is_ok_and(|meta| meta.is_file());
+    if in_place && file {
+        if let Err(e) = std::fs::OpenOptions::new().write(true).open(dest) {
+            if e.kind() == ErrorKind::PermissionDenied {
+                return Some(Blocker::FileDenied);
+            }
+        }
+    }
+    None
+}
+
+/// Paths quoted for a shell command
+fn quoted<'a>(paths: impl IntoIterator<Item = &'a Path>) -> String {
+    paths
+        .into_iter()
+        .map(|path| format!("'{}'", path.display().to_string().replace('\'', "'\\''")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// How to fix every problem in one group
+fn fix(blocker: &Blocker, problems: &[&Problem]) -> String {
+    let culprits: BTreeSet<&Path> = problems
+        .iter()
+        .filter_map(|problem| problem.blocker.culprit())
+        .collect();
+    match blocker {
+        Blocker::Protected => {
+            "Track them under /usr/local or /Library instead, or remove them with 'ordinator unwatch'.".to_string()
+        }
+        Blocker::ReadOnlyVolume => {
+            "Remount the volume read-write, or remove them with 'ordinator unwatch'.".to_string()
+        }
+        Blocker::NotADirectory(_) => format!("Move {} out of the way.", quoted(culprits)),
+        Blocker::MissingParent(_) => {
+            let parents: BTreeSet<&Path> = problems
+                .iter()
+                .filter_map(|problem| problem.dest.parent())
+                .collect();
+            format!(
+                "Create them yourself: sudo mkdir -p {dirs} && sudo chown \"$USER\" {dirs}",
+                dirs = quoted(parents)
+            )
+        }
+        Blocker::DirectoryDenied(_) => format!(
+            "Take ownership with: sudo chown \"$USER\" {}. Files outside your home can be linked with sudo instead: set requires_sudo for them.",
+            quoted(culprits)
+        ),
+        Blocker::FileDenied => format!(
+            "Make them writable with: chmod u+w {}",
+            quoted(problems.iter().map(|problem| problem.dest.as_path()))
+        ),
+    }
+}
+
+/// The problems grouped by cause, each group with its fix
+pub fn report(problems: &[Problem]) -> String {
+    let mut groups: Vec<(&Blocker, Vec<&Problem>)> = Vec::new();
+    for problem in problems {
+        let kind = std::mem::discriminant(&problem.blocker);
+        match groups
+            .iter_mut()
+            .find(|(blocker, _)| std::mem::discriminant(*blocker) == kind)
+        {
+            Some((_, members)) => members.push(problem),
+            None => groups.push((&problem.blocker, vec![problem])),
+        }
+    }
+    groups.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut text = format!("{} destination(s) can't be written:", problems.len());
+    for (blocker, members) in groups {
+        text.push_str(&format!("\n  {}:", blocker.heading()));
+        for problem in &members {
+            match problem.blocker.culprit() {
+                Some(culprit) => text.push_str(&format!(
+                    "\n    {} ({})",
+                    problem.dest.display(),
+                    culprit.display()
+                )),
+                None => text.push_str(&format!("\n    {}", problem.dest.display())),
+            }
+        }
+        text.push_str(&format!("\n    fix: {}", fix(blocker, &members)));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_and_report() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(temp.path().join(".config"), "").unwrap();
+        let nested = temp.path().join(".config/nvim/init.lua");
+        assert_eq!(
+            check(&nested, false),
+            Some(Blocker::NotADirectory(temp.path().join(".config")))
+        );
+        assert_eq!(check(&temp.path().join("a/b/.zshrc"), true), None);
+        assert_eq!(
+            check(Path::new("/usr/bin/tool"), false),
+            Some(Blocker::Protected)
+        );
+        assert!(!is_protected(Path::new("/usr/local/bin/tool")));
+
+        let problems = vec![
+            Problem {
+                dest: nested,
+                blocker: Blocker::NotADirectory(temp.path().join(".config")),
+            },
+            Problem {
+                dest: PathBuf::from("/usr/bin/tool"),
+                blocker: Blocker::Protected,
+            },
+        ];
+        let text = report(&problems);
+        assert!(text.starts_with(
+            "2 destination(s) can't be written:\n  Protected by System Integrity Protection"
+        ));
+        assert!(text.contains(&format!(
+            "\n    fix: Move '{}' out of the way.",
+            temp.path().join(".config").display()
+        )));
+    }
+}
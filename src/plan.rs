@@ -0,0 +1,357 @@
+use anyhow::Result;
+use std::fmt;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::utils::{is_symlink, symlink_points_to};
+
+/// A kind of change `ordinator apply` would make
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PlanAction {
+    /// Symlink a tracked file into place
+    CreateSymlink,
+
+    /// Point an existing symlink at the tracked file
+    RepairSymlink,
+
+    /// A file or directory that is not a symlink is in the way
+    Conflict,
+
+    /// The tracked copy is missing from the repository
+    MissingSource,
+
+    /// Render a template to its destination
+    RenderTemplate,
+
+    /// Decrypt a secret to its destination
+    DecryptSecret,
+
+    /// Write a tracked file into the managed block of its destination
+    UpdateBlock,
+
+    /// `brew tap`
+    InstallTap,
+
+    /// `brew install`
+    InstallFormula,
+
+    /// `brew install --cask`
+    InstallCask,
+
+    /// `mas install`
+    InstallMasApp,
+
+    /// `defaults write`
+    WriteDefault,
+}
+
+impl PlanAction {
+    /// Marker shown before each change: `+` adds, `~` changes, `!` needs attention
+    fn marker(&self) -> char {
+        match self {
+            PlanAction::RepairSymlink | PlanAction::UpdateBlock | PlanAction::WriteDefault => '~',
+            PlanAction::Conflict | PlanAction::MissingSource => '!',
+            _ => '+',
+        }
+    }
+
+    /// Label for a single change
+    fn label(&self) -> &'static str {
+        match self {
+            PlanAction::CreateSymlink => "symlink",
+            PlanAction::RepairSymlink => "repair symlink",
+            PlanAction::Conflict => "conflict",
+            PlanAction::MissingSource => "missing source",
+            PlanAction::RenderTemplate => "render",
+            PlanAction::DecryptSecret => "decrypt",
+            PlanAction::UpdateBlock => "update block",
+            PlanAction::InstallTap => "brew tap",
+            PlanAction::InstallFormula => "brew formula",
+            PlanAction::InstallCask => "brew cask",
+            PlanAction::InstallMasApp => "mas app",
+            PlanAction::WriteDefault => "defaults",
+        }
+    }
+
+    /// Phrase used in the summary line, after the count
+    fn summary(&self) -> &'static str {
+        match self {
+            PlanAction::CreateSymlink => "symlink(s) to create",
+            PlanAction::RepairSymlink => "symlink(s) to repair",
+            PlanAction::Conflict => "conflict(s)",
+            PlanAction::MissingSource => "missing source file(s)",
+            PlanAction::RenderTemplate => "template(s) to render",
+            PlanAction::DecryptSecret => "secret(s) to decrypt",
+            PlanAction::UpdateBlock => "managed block(s) to update",
+            PlanAction::InstallTap => "tap(s) to add",
+            PlanAction::InstallFormula => "formula(s) to install",
+            PlanAction::InstallCask => "cask(s) to install",
+            PlanAction::InstallMasApp => "App Store app(s) to install",
+            PlanAction::WriteDefault => "macOS default(s) to change",
+        }
+    }
+}
+
+/// One change in a plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedChange {
+    pub action: PlanAction,
+
+    /// File, package, or setting the change applies to
+    pub target: String,
+
+    /// Extra context, e.g. the symlink source
+    pub detail: Option<String>,
+}
+
+impl fmt::Display for PlannedChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.action.marker(),
+            self.action.label(),
+            self.target
+        )?;
+        if let Some(detail) = &self.detail {
+            write!(f, " ({detail})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Everything `ordinator apply` would change for a profile
+#[derive(Debug, Default)]
+pub struct Plan {
+    pub changes: Vec<PlannedChange>,
+
+    /// Files apply leaves out for shells this machine doesn't have
+    pub shells_skipped: Vec<crate::shell::SkippedShell>,
+}
+
+impl Plan {
+    pub fn push(&mut self, action: PlanAction, target: impl Into<String>, detail: Option<String>) {
+        self.changes.push(PlannedChange {
+            action,
+            target: target.into(),
+            detail,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Counts per action, e.g. "2 symlink(s) to create, 1 conflict(s)"
+    pub fn summary(&self) -> String {
+        let mut counts: Vec<(PlanAction, usize)> = Vec::new();
+        for change in &self.changes {
+            match counts.iter_mut().find(|(a, _)| *a == change.action) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((change.action, 1)),
+            }
+        }
+        counts.sort();
+        counts
+            .iter()
+            .map(|(action, count)| format!("{count} {}", action.summary()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// What apply would do to link `source` at `dest`, or None if it is already linked
+pub fn link_action(source: &Path, dest: &Path) -> Option<PlanAction> {
+    if !source.exists() {
+        Some(PlanAction::MissingSource)
+    } else if is_symlink(dest) {
+        if symlink_points_to(dest, source) {
+            None
+        } else {
+            Some(PlanAction::RepairSymlink)
+        }
+    } else if dest.exists() {
+        Some(PlanAction::Conflict)
+    } else {
+        Some(PlanAction::CreateSymlink)
+    }
+}
+
+/// Work out what `ordinator apply` would change for a profile, without changing anything
+pub async fn build(
+    config: &Config,
+    profile: &str,
+    home_dir: &Path,
+    skip_secrets: bool,
+    skip_brew: bool,
+    skip_defaults: bool,
+) -> Result<Plan> {
+    use crate::template::{is_template, render_file, rendered_path};
+
+    let mut resolved = config.resolve_profile(profile)?;
+    let mut plan = Plan {
+        shells_skipped: config.withhold_shell_files(&mut resolved, &crate::shell::Shells::detect()),
+        ..Default::default()
+    };
+    // Secrets apply would leave encrypted: a shared machine, or a profile with secrets off
+    let skip_secrets =
+        skip_secrets || crate::state::MachineState::load(home_dir)?.shared == Some(true);
+    config.withhold_secrets(&[profile.to_string()], &[], &mut resolved.secrets)?;
+
+    for (file, source_profile, source) in config.link_targets(&resolved, false)? {
+        let dest = crate::paths::destination(home_dir, rendered_path(&file));
+        let needs_sudo = config.file_options(&source_profile, &file).requires_sudo
+            || (!dest.starts_with(home_dir) && crate::sudo::needs_privileges(&dest));
+        let detail = Some(if needs_sudo {
+            format!("from {}, needs sudo", source.display())
+        } else {
+            format!("from {}", source.display())
+        });
+        if source.exists() && config.file_options(&source_profile, &file).encrypt {
+            // Decrypted only to compare; a file that won't decrypt is left to apply to report
+            let up_to_date = !crate::utils::is_symlink(&dest)
+                && crate::secrets::decrypt_tracked_file(&source)
+                    .is_ok_and(|plaintext| std::fs::read(&dest).ok() == Some(plaintext));
+            if !up_to_date {
+                plan.push(
+                    PlanAction::DecryptSecret,
+                    file,
+                    Some(format!("from {}, encrypted", source.display())),
+                );
+            }
+        } else if source.exists() && config.file_options(&source_profile, &file).managed_block {
+            let contents = if is_template(&file) {
+                render_file(&source, &resolved.vars)?
+            } else {
+                std::fs::read_to_string(&source)?
+            };
+            let up_to_date = !crate::utils::is_symlink(&dest)
+                && crate::block::read(&dest)?.as_deref()
+                    == Some(crate::block::normalize(&contents).as_str());
+            if !up_to_date {
+                plan.push(PlanAction::UpdateBlock, rendered_path(&file), detail);
+            }
+        } else if source.exists() && is_template(&file) {
+            let rendered = render_file(&source, &resolved.vars)?;
+            let up_to_date = !crate::utils::is_symlink(&dest)
+                && std::fs::read_to_string(&dest).ok().as_deref() == Some(rendered.as_str());
+            if !up_to_date {
+                plan.push(PlanAction::RenderTemplate, rendered_path(&file), detail);
+            }
+        } else if let Some(action) = link_action(&source, &dest) {
+            plan.push(action, file, detail);
+        }
+    }
+
+    if !skip_secrets {
+        for entry in &resolved.secrets {
+            let encrypted = config.get_source_file_path(&entry.profile, &entry.path)?;
+            if !encrypted.exists() {
+                plan.push(
+                    PlanAction::MissingSource,
+                    entry.path.clone(),
+                    Some(format!("secret {}", encrypted.display())),
+                );
+            } else if crate::secrets::is_file_encrypted(&encrypted)
+                && !crate::paths::destination(home_dir, &entry.path).exists()
+            {
+                plan.push(PlanAction::DecryptSecret, entry.path.clone(), None);
+            }
+        }
+    }
+
+    let has_packages = !resolved.homebrew_taps.is_empty()
+        || !resolved.homebrew_formulas.is_empty()
+        || !resolved.homebrew_casks.is_empty()
+        || !resolved.mas_apps.is_empty();
+    if !skip_brew && has_packages {
+        use crate::brew::BrewManager;
+        if BrewManager::check_homebrew_installed() {
+            let missing = BrewManager::new(true).missing_packages(&resolved).await?;
+            for tap in missing.taps {
+                plan.push(PlanAction::InstallTap, tap, None);
+            }
+            for formula in missing.formulas {
+                plan.push(PlanAction::InstallFormula, formula, None);
+            }
+            for cask in missing.casks {
+                plan.push(PlanAction::InstallCask, cask, None);
+            }
+            for app in missing.mas_apps {
+                plan.push(
+                    PlanAction::InstallMasApp,
+                    app.name,
+                    Some(app.id.to_string()),
+                );
+            }
+        } else {
+            eprintln!("Homebrew not installed - packages left out of the plan");
+        }
+    }
+
+    if !skip_defaults && !resolved.defaults.is_empty() {
+        use crate::defaults::DefaultsManager;
+        if DefaultsManager::is_available() {
+            for change in DefaultsManager::new(true).diff(&resolved.defaults)? {
+                let current = change
+                    .current
+                    .map(|v| v.to_string())
+                    .unwrap_or_else(|| "unset".to_string());
+                plan.push(
+                    PlanAction::WriteDefault,
+                    format!("{} {}", change.domain, change.key),
+                    Some(format!("{current} -> {}", change.desired)),
+                );
+            }
+        } else {
+            eprintln!("'defaults' command not found - macOS defaults left out of the plan");
+        }
+    }
+
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_link_action() {
+        let temp = tempdir().unwrap();
+        let source = temp.path().join("source");
+        let other = temp.path().join("other");
+        let dest = temp.path().join("dest");
+        fs::write(&source, "a").unwrap();
+        fs::write(&other, "b").unwrap();
+
+        assert_eq!(
+            link_action(&temp.path().join("gone"), &dest),
+            Some(PlanAction::MissingSource)
+        );
+        assert_eq!(link_action(&source, &dest), Some(PlanAction::CreateSymlink));
+        std::os::unix::fs::symlink(&other, &dest).unwrap();
+        assert_eq!(link_action(&source, &dest), Some(PlanAction::RepairSymlink));
+        fs::remove_file(&dest).unwrap();
+        std::os::unix::fs::symlink(&source, &dest).unwrap();
+        assert_eq!(link_action(&source, &dest), None);
+        fs::remove_file(&dest).unwrap();
+        fs::write(&dest, "c").unwrap();
+        assert_eq!(link_action(&source, &dest), Some(PlanAction::Conflict));
+    }
+
+    #[test]
+    fn test_plan_summary() {
+        let mut plan = Plan::default();
+        assert!(plan.is_empty());
+        plan.push(PlanAction::InstallFormula, "ripgrep", None);
+        plan.push(PlanAction::CreateSymlink, ".zshrc", None);
+        plan.push(PlanAction::CreateSymlink, ".vimrc", None);
+        assert_eq!(
+            plan.summary(),
+            "2 symlink(s) to create, 1 formula(s) to install"
+        );
+        assert_eq!(plan.changes[1].to_string(), "+ symlink .zshrc");
+    }
+}
@@ -0,0 +1,184 @@
+use anyhow::Result;
+use colored::*;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use is_terminal::IsTerminal;
+use serde_json::json;
+
+use crate::utils::color_enabled;
+
+/// How `watch`, `add`, and `apply` report progress through many files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProgressMode {
+    /// A progress bar when stderr is a terminal, plain lines otherwise
+    #[default]
+    Auto,
+    /// One `[n/m] item` line per file
+    Plain,
+    /// One JSON event per line on stdout, for scripts and editor integrations
+    Json,
+}
+
+impl std::str::FromStr for ProgressMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "auto" => Ok(ProgressMode::Auto),
+            "plain" => Ok(ProgressMode::Plain),
+            "json" => Ok(ProgressMode::Json),
+            other => Err(anyhow::anyhow!(
+                "Unknown progress mode '{other}'. Use 'auto', 'plain', or 'json'."
+            )),
+        }
+    }
+}
+
+enum Output {
+    Bar(ProgressBar),
+    Plain,
+    Json,
+    Hidden,
+}
+
+/// Reports progress through a fixed number of items. Lines printed while a
+/// bar is drawn must go through [`Progress::println`] so they don't tear it.
+pub struct Progress {
+    output: Output,
+    task: &'static str,
+    total: usize,
+    position: usize,
+}
+
+impl Progress {
+    /// Start reporting `total` items for `task` (e.g. `"apply"`). Quiet mode
+    /// hides bars and plain lines, but JSON events are still written.
+    pub fn start(mode: ProgressMode, quiet: bool, task: &'static str, total: usize) -> Self {
+        let output = match mode {
+            ProgressMode::Json => Output::Json,
+            _ if quiet => Output::Hidden,
+            ProgressMode::Auto if std::io::stderr().is_terminal() => {
+                let bar =
+                    ProgressBar::with_draw_target(Some(total as u64), ProgressDrawTarget::stderr());
+                if let Ok(style) =
+                    ProgressStyle::with_template("{prefix} [{bar:30}] {pos}/{len} {wide_msg}")
+                {
+                    bar.set_style(style.progress_chars("=> "));
+                }
+                bar.set_prefix(task);
+                Output::Bar(bar)
+            }
+            _ => Output::Plain,
+        };
+        let progress = Self {
+            output,
+            task,
+            total,
+            position: 0,
+        };
+        progress.event(json!({
+            "event": "start",
+            "task": task,
+            "total": total,
+        }));
+        progress
+    }
+
+    fn event(&self, event: serde_json::Value) {
+        if let Output::Json = self.output {
+            println!("{event}");
+        }
+    }
+
+    /// Move on to the next item
+    pub fn advance(&mut self, item: &str) {
+        self.position += 1;
+        match &self.output {
+            Output::Bar(bar) => {
+                bar.set_message(item.to_string());
+                bar.inc(1);
+            }
+            Output::Plain => {
+                let line = format!("[{}/{}] {item}", self.position, self.total);
+                if color_enabled() {
+                    println!("{}", line.cyan());
+                } else {
+                    println!("{line}");
+                }
+            }
+            Output::Json => self.event(json!({
+                "event": "progress",
+                "task": self.task,
+                "current": self.position,
+                "total": self.total,
+                "item": item,
+            })),
+            Output::Hidden => {}
+        }
+    }
+
+    /// Print a line about the current item on stdout. JSON mode drops it so
+    /// the events aren't interleaved with per-file messages.
+    pub fn println(&self, line: impl AsRef<str>) {
+        match &self.output {
+            Output::Bar(bar) => bar.println(line.as_ref()),
+            Output::Plain => println!("{}", line.as_ref()),
+            Output::Json | Output::Hidden => {}
+        }
+    }
+
+    /// Print a warning or status line on stderr, hiding the bar while it's written
+    pub fn eprintln(&self, line: impl AsRef<str>) {
+        match &self.output {
+            Output::Bar(bar) => bar.suspend(|| eprintln!("{}", line.as_ref())),
+            _ => eprintln!("{}", line.as_ref()),
+        }
+    }
+
+    /// Run `f` with the bar hidden, e.g. to ask a question
+    pub fn suspend<R>(&self, f: impl FnOnce() -> R) -> R {
+        match &self.output {
+            Output::Bar(bar) => bar.suspend(f),
+            _ => f(),
+        }
+    }
+
+    /// Clear the bar once every item is done
+    pub fn finish(self) {
+        match &self.output {
+            Output::Bar(bar) => bar.finish_and_clear(),
+            Output::Json => self.event(json!({
+                "event": "finish",
+                "task": self.task,
+                "current": self.position,
+                "total": self.total,
+            })),
+            Output::Plain | Output::Hidden => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_progress_mode() {
+        assert_eq!("json".parse::<ProgressMode>().unwrap(), ProgressMode::Json);
+        assert_eq!(
+            "plain".parse::<ProgressMode>().unwrap(),
+            ProgressMode::Plain
+        );
+        assert!("bars".parse::<ProgressMode>().is_err());
+    }
+
+    #[test]
+    fn test_quiet_hides_all_but_json() {
+        let mut progress = Progress::start(ProgressMode::Plain, true, "add", 2);
+        assert!(matches!(progress.output, Output::Hidden));
+        progress.advance(".zshrc");
+        assert_eq!(progress.position, 1);
+
+        let progress = Progress::start(ProgressMode::Json, true, "add", 2);
+        assert!(matches!(progress.output, Output::Json));
+    }
+}
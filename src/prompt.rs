@@ -0,0 +1,130 @@
+use is_terminal::IsTerminal;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Environment variable that answers yes to every prompt, like `--yes`
+pub const ASSUME_YES_ENV: &str = "ORDINATOR_ASSUME_YES";
+
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+static NO_INPUT: AtomicBool = AtomicBool::new(false);
+
+/// Answer yes to every confirmation (`--yes`)
+pub fn set_assume_yes(assume_yes: bool) {
+    ASSUME_YES.store(assume_yes, Ordering::Relaxed);
+}
+
+/// Never read from stdin; every prompt takes its default answer (`--no-input`)
+pub fn set_no_input(no_input: bool) {
+    NO_INPUT.store(no_input, Ordering::Relaxed);
+}
+
+/// Whether confirmations are answered yes by `--yes` or `ORDINATOR_ASSUME_YES`
+pub fn assume_yes() -> bool {
+    ASSUME_YES.load(Ordering::Relaxed)
+        || std::env::var(ASSUME_YES_ENV)
+            .is_ok_and(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+}
+
+/// Whether prompts can be shown: stdin is a terminal and `--no-input` wasn't given
+pub fn interactive() -> bool {
+    !NO_INPUT.load(Ordering::Relaxed) && io::stdin().is_terminal()
+}
+
+/// Print `prompt` on stderr and read one trimmed line; `None` at end of input
+fn read_answer(prompt: &str) -> Option<String> {
+    eprint!("{prompt}");
+    io::stderr().flush().ok();
+    let mut input = String::new();
+    match io::stdin().read_line(&mut input) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(input.trim().to_string()),
+    }
+}
+
+/// Ask a yes/no question. `--yes` answers yes; when prompts can't be shown,
+/// `default` is the answer.
+pub fn confirm(question: &str, default: bool) -> bool {
+    if assume_yes() {
+        return true;
+    }
+    if !interactive() {
+        return default;
+    }
+    let hint = if default { "[Y/n]" } else { "[y/N]" };
+    match read_answer(&format!("{question} {hint}: ")) {
+        Some(answer) if !answer.is_empty() => {
+            matches!(answer.to_lowercase().as_str(), "y" | "yes")
+        }
+        _ => default,
+    }
+}
+
+/// Ask for one of `options` by number, returning its index. `--yes` and
+/// runs where prompts can't be shown take `default`.
+pub fn choose(question: &str, options: &[String], default: usize) -> usize {
+    if assume_yes() || !interactive() {
+        return default;
+    }
+    eprintln!("{question}");
+    for (index, option) in options.iter().enumerate() {
+        eprintln!("  {}. {option}", index + 1);
+    }
+    let Some(answer) = read_answer(&format!("Enter number (default: {}): ", default + 1))
+        .filter(|answer| !answer.is_empty())
+    else {
+        return default;
+    };
+    match answer.parse::<usize>() {
+        Ok(number) if (1..=options.len()).contains(&number) => number - 1,
+        _ => {
+            eprintln!("Invalid selection. Using {}.", options[default]);
+            default
+        }
+    }
+}
+
+/// Read a line such as a pasted key; `None` when prompts can't be shown
+pub fn input(question: &str) -> Option<String> {
+    if !interactive() {
+        return None;
+    }
+    read_answer(question)
+}
+
+/// Read a value such as a token without echoing it; `None` when prompts
+/// can't be shown
+pub fn secret(question: &str) -> Option<String> {
+    if !interactive() {
+        return None;
+    }
+    dialoguer::Password::new()
+        .with_prompt(question)
+        .interact()
+        .ok()
+}
+
+/// Held by tests that change the `--yes` and `--no-input` settings, which
+/// are shared by every test in the process
+#[cfg(test)]
+pub(crate) static TEST_SETTINGS: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompts_without_a_terminal_take_defaults() {
+        let _settings = TEST_SETTINGS.lock().unwrap_or_else(|e| e.into_inner());
+        set_no_input(true);
+        assert!(confirm("Continue?", true));
+        assert!(!confirm("Remove everything?", false));
+        let options = vec!["work".to_string(), "personal".to_string()];
+        assert_eq!(choose("Pick a profile:", &options, 1), 1);
+        assert_eq!(input("Paste your key: "), None);
+
+        set_assume_yes(true);
+        assert!(confirm("Remove everything?", false));
+        set_assume_yes(false);
+        set_no_input(false);
+    }
+}
@@ -0,0 +1,249 @@
+//! `ordinator push` and `ordinator sync`: push the dotfiles repository to
+//! `origin` or to every configured remote. Every push ordinator makes goes
+//! through [`push`], so each one is recorded for `status --remote` and
+//! followed by the default profile's `post_push` hooks.
+
+use anyhow::Result;
+use std::path::Path;
+use tracing::info;
+
+use crate::config::Config;
+use crate::error::OrdinatorError;
+use crate::git::{GitConfig, GitManager, RemoteStatus};
+use crate::hooks::{self, HookEvent};
+use crate::state::MachineState;
+
+/// What `ordinator push` and `ordinator sync` should do
+#[derive(Debug, Clone, Default)]
+pub struct PushOptions {
+    /// Point `origin` at this URL first
+    pub repo_url: Option<String>,
+
+    /// Pull before pushing (`sync`)
+    pub pull: bool,
+
+    pub force: bool,
+
+    /// Force push without typing the branch name back (`--i-know-what-im-doing`)
+    pub skip_force_confirmation: bool,
+
+    pub branch: Option<String>,
+
+    /// Push to every configured remote, as `[git] push_all_remotes` does
+    pub all_remotes: bool,
+
+    pub quiet: bool,
+}
+
+/// Push the repository at `dotfiles_dir` as `options` say: confirm a force
+/// push against where it will go, set `origin`, pull when asked, then [`push`].
+pub fn run(config: &Config, dotfiles_dir: &Path, options: &PushOptions) -> Result<()> {
+    let git_manager = GitManager::new(dotfiles_dir.to_path_buf()).with_config(config.git.clone());
+    if !git_manager.exists() {
+        return Err(OrdinatorError::RepositoryNotFound.into());
+    }
+    let all_remotes = options.all_remotes || config.git.push_all_remotes;
+    let branch = options.branch.as_deref();
+    if options.force {
+        confirm_force_push(
+            &git_manager,
+            &config.git,
+            all_remotes,
+            branch,
+            options.repo_url.as_deref(),
+            options.skip_force_confirmation,
+        )?;
+    }
+
+    if let Some(url) = &options.repo_url {
+        crate::repo::RepoManager::new(dotfiles_dir.to_path_buf())
+            .parse_github_url(url)
+            .map_err(|e| anyhow::anyhow!("Invalid repository URL '{}': {}", url, e))?;
+        info!("Setting remote 'origin' to: {}", url);
+        if !options.quiet {
+            eprintln!("Setting remote 'origin' to: {url}");
+        }
+        git_manager.add_remote("origin", url)?;
+    }
+
+    if options.pull {
+        git_manager.pull(false, branch)?;
+        record_sync(config, true, false)?;
+    }
+    push(
+        config,
+        &git_manager,
+        dotfiles_dir,
+        all_remotes,
+        options.force,
+        branch,
+        options.quiet,
+    )
+}
+
+/// Push to `origin`, or to every configured remote when `all_remotes` is set,
+/// reporting each remote's result unless `quiet`. Fails if any remote
+/// rejected the push; otherwise the push is recorded and the `post_push`
+/// hooks run.
+pub fn push(
+    config: &Config,
+    git_manager: &GitManager,
+    dotfiles_dir: &Path,
+    all_remotes: bool,
+    force: bool,
+    branch: Option<&str>,
+    quiet: bool,
+) -> Result<()> {
+    if all_remotes {
+        push_all(git_manager, force, branch, quiet)?;
+    } else {
+        git_manager.push(force, branch)?;
+    }
+    record_sync(config, false, true)?;
+    hooks::run_for_default_profile(config, dotfiles_dir, HookEvent::PostPush, false)
+}
+
+fn push_all(
+    git_manager: &GitManager,
+    force: bool,
+    branch: Option<&str>,
+    quiet: bool,
+) -> Result<()> {
+    let results = git_manager.push_all(force, branch);
+    let total = results.len();
+    let mut failed = 0;
+    for (name, forced, result) in results {
+        match result {
+            Ok(()) if !quiet => {
+                eprintln!("Pushed to '{name}'{}", if forced { " (force)" } else { "" })
+            }
+            Ok(()) => {}
+            Err(e) => {
+                failed += 1;
+                eprintln!("Failed to push to '{name}': {e:#}");
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(anyhow::anyhow!(
+            "Push failed for {failed} of {total} remote(s)"
+        ));
+    }
+    Ok(())
+}
+
+/// Note a successful pull or push in the machine state, for `status --remote`
+pub fn record_sync(config: &Config, pulled: bool, pushed: bool) -> Result<()> {
+    let home_dir = config.target_root()?;
+    let mut state = MachineState::load(&home_dir)?;
+    if pulled {
+        state.record_pull();
+    }
+    if pushed {
+        state.record_push();
+    }
+    state.save(&home_dir)
+}
+
+/// Before a force push, say what it replaces and have the branch name typed
+/// back, unless `[git] protect_force_push = false` or `bypass`
+/// (`--i-know-what-im-doing`). `--yes` doesn't answer it.
+fn confirm_force_push(
+    git_manager: &GitManager,
+    config: &GitConfig,
+    all_remotes: bool,
+    branch: Option<&str>,
+    new_origin: Option<&str>,
+    bypass: bool,
+) -> Result<()> {
+    if bypass || !config.force_push_protected() {
+        return Ok(());
+    }
+    let branch_name = git_manager.resolve_branch(branch);
+    let mut statuses = git_manager.remote_statuses().unwrap_or_default();
+    if let Some(url) = new_origin {
+        // What was fetched from the old origin says nothing about the new one
+        statuses.retain(|status| status.name != "origin");
+        statuses.insert(
+            0,
+            RemoteStatus {
+                name: "origin".to_string(),
+                url: Some(url.to_string()),
+                force: false,
+                ahead_behind: None,
+            },
+        );
+    }
+    let targets: Vec<_> = statuses
+        .iter()
+        .filter(|status| all_remotes || status.name == "origin")
+        .collect();
+    let names: Vec<String> = targets
+        .iter()
+        .map(|status| match &status.url {
+            Some(url) => format!("{} ({url})", status.name),
+            None => status.name.clone(),
+        })
+        .collect();
+    eprintln!(
+        "⚠️  Warning: force-pushing replaces '{branch_name}' on {} with your local branch. \
+         Other machines that pulled it may lose history.",
+        names.join(", ")
+    );
+    // Commits only the remote has, as of the last fetch, are the ones lost
+    if branch
+        .is_none_or(|branch| git_manager.current_branch().ok().flatten().as_deref() == Some(branch))
+    {
+        for status in &targets {
+            if let Some((_, behind)) = status.ahead_behind.filter(|(_, behind)| *behind > 0) {
+                eprintln!(
+                    "   {behind} commit(s) on '{}' aren't in your local branch and would be lost.",
+                    status.name
+                );
+            }
+        }
+    }
+    if !crate::prompt::interactive() {
+        return Err(anyhow::anyhow!(
+            "Refusing to force-push '{branch_name}' without confirmation. Run it in a terminal, \
+             pass --i-know-what-im-doing, or set protect_force_push = false under [git]."
+        ));
+    }
+    match crate::prompt::input(&format!(
+        "Type the branch name ('{branch_name}') to confirm: "
+    )) {
+        Some(typed) if typed == branch_name => Ok(()),
+        _ => Err(anyhow::anyhow!(
+            "Force push cancelled: the name typed doesn't match '{branch_name}'."
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_run_checks_repository_and_url_before_pushing() {
+        let config = Config::default();
+        let dir = TempDir::new().unwrap();
+        let error = run(&config, dir.path(), &PushOptions::default()).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<OrdinatorError>(),
+            Some(OrdinatorError::RepositoryNotFound)
+        ));
+
+        git2::Repository::init(dir.path()).unwrap();
+        let options = PushOptions {
+            repo_url: Some("not a url".to_string()),
+            ..PushOptions::default()
+        };
+        let error = run(&config, dir.path(), &options).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Invalid repository URL 'not a url'"));
+        let repo = git2::Repository::open(dir.path()).unwrap();
+        assert!(repo.find_remote("origin").is_err());
+    }
+}
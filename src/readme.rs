@@ -91,6 +91,7 @@ fn write_state(dotfiles_dir: &Path, hash: &str) {
 
 /// Configuration for README generation
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ReadmeConfig {
     #[serde(default)]
     pub auto_update: bool,
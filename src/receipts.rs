@@ -0,0 +1,158 @@
+//! Bootstrap receipts: each `ordinator bootstrap run` appends one JSON line
+//! (the script's hash, profile, exit code, duration, and when it ran) to
+//! `bootstrap-receipts.jsonl` beside the machine state file, so
+//! `ordinator bootstrap history` can list past runs and `apply` can warn when
+//! a script changed since it last succeeded on this machine.
+
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// File (beside the machine state file) receipts are appended to
+const RECEIPTS_FILE: &str = "bootstrap-receipts.jsonl";
+
+/// One run of a bootstrap script
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Receipt {
+    pub profile: String,
+
+    /// Script path, relative to the dotfiles repository
+    pub script: String,
+
+    /// SHA-256 of the script as it ran
+    pub script_hash: String,
+
+    /// Exit code, or none if the script was killed
+    pub exit_code: Option<i32>,
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+
+    pub duration_ms: u64,
+
+    /// When the run started (RFC 3339)
+    pub ran_at: String,
+}
+
+impl Receipt {
+    /// A receipt for a run of `script` (its `content`) that started now
+    pub fn new(profile: &str, script: &str, content: &[u8]) -> Self {
+        Self {
+            profile: profile.to_string(),
+            script: script.to_string(),
+            script_hash: crate::drift::hash_bytes(content),
+            exit_code: None,
+            timed_out: false,
+            duration_ms: 0,
+            ran_at: Local::now().to_rfc3339(),
+        }
+    }
+
+    /// This receipt, filled in with how the run ended
+    pub fn finish(mut self, outcome: &crate::bootstrap::RunOutcome) -> Self {
+        self.exit_code = outcome.exit_code;
+        self.timed_out = outcome.timed_out;
+        self.duration_ms = outcome.duration.as_millis() as u64;
+        self
+    }
+
+    pub fn success(&self) -> bool {
+        !self.timed_out && self.exit_code == Some(0)
+    }
+
+    /// "ok", "exit 3", "timed out", or "killed"
+    pub fn outcome(&self) -> String {
+        match self.exit_code {
+            _ if self.timed_out => "timed out".to_string(),
+            Some(0) => "ok".to_string(),
+            Some(code) => format!("exit {code}"),
+            None => "killed".to_string(),
+        }
+    }
+}
+
+/// Where receipts for `home_dir` are kept: beside its machine state file
+pub fn path(home_dir: &Path) -> PathBuf {
+    crate::state::MachineState::path(home_dir).with_file_name(RECEIPTS_FILE)
+}
+
+/// Append `receipt` to the receipts for `home_dir`
+pub fn record(home_dir: &Path, receipt: &Receipt) -> Result<()> {
+    let path = path(home_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(receipt)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Every receipt for `home_dir`, oldest first. Lines that don't parse are skipped.
+pub fn load(home_dir: &Path) -> Result<Vec<Receipt>> {
+    let path = path(home_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// The most recent successful run of `profile`'s script, if its content
+/// (`script`) has changed since
+pub fn changed_since_success<'a>(
+    receipts: &'a [Receipt],
+    profile: &str,
+    script: &[u8],
+) -> Option<&'a Receipt> {
+    let last = receipts
+        .iter()
+        .rev()
+        .find(|receipt| receipt.profile == profile && receipt.success())?;
+    (last.script_hash != crate::drift::hash_bytes(script)).then_some(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_load_and_changed_since_success() {
+        let home = tempfile::tempdir().unwrap();
+        assert!(load(home.path()).unwrap().is_empty());
+
+        let mut ok = Receipt::new("work", "scripts/bootstrap-work.sh", b"echo one\n");
+        ok.exit_code = Some(0);
+        record(home.path(), &ok).unwrap();
+        let mut failed = Receipt::new("work", "scripts/bootstrap-work.sh", b"echo two\n");
+        failed.exit_code = Some(3);
+        record(home.path(), &failed).unwrap();
+
+        let receipts = load(home.path()).unwrap();
+        assert_eq!(receipts, [ok.clone(), failed]);
+        assert_eq!(receipts[1].outcome(), "exit 3");
+        // Failed runs don't count: the last success ran "echo one"
+        assert_eq!(
+            changed_since_success(&receipts, "work", b"echo two\n"),
+            Some(&ok)
+        );
+        assert_eq!(
+            changed_since_success(&receipts, "work", b"echo one\n"),
+            None
+        );
+        assert_eq!(
+            changed_since_success(&receipts, "home", b"echo two\n"),
+            None
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use anyhow::{anyhow, Result};
+use std::cell::RefCell;
 use std::path::PathBuf;
-use std::process::Command;
 use tracing::{info, warn};
 use url::Url;
 
@@ -10,12 +10,34 @@ use crate::git::GitManager;
 /// Repository manager for handling remote repository initialization
 pub struct RepoManager {
     target_dir: PathBuf,
+
+    /// Fetch only this many commits of history (`--depth`)
+    depth: Option<u32>,
+
+    /// Times a failed download is retried
+    retries: u32,
 }
 
 impl RepoManager {
     /// Create a new repository manager
     pub fn new(target_dir: PathBuf) -> Self {
-        Self { target_dir }
+        Self {
+            target_dir,
+            depth: None,
+            retries: crate::transfer::DEFAULT_RETRIES,
+        }
+    }
+
+    /// Clone only the last `depth` commits; `None` clones the whole history
+    pub fn with_depth(mut self, depth: Option<u32>) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Retry a clone or download that fails on a flaky network up to `retries` times
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
     }
 
     /// Initialize from a remote repository URL
@@ -26,8 +48,16 @@ impl RepoManager {
         let repo_info = self.parse_github_url(repo_url)?;
         info!("Repository info: {:?}", repo_info);
 
-        // Check if target directory exists
-        if self.target_dir.exists() {
+        // An empty directory is cloned into, and an interrupted clone is finished
+        if self.partial_clone(repo_url).is_some() {
+            info!("Resuming the clone in {}", self.target_dir.display());
+            if !crate::transfer::quiet() {
+                eprintln!(
+                    "Resuming the interrupted clone in {}",
+                    self.target_dir.display()
+                );
+            }
+        } else if self.target_dir.exists() && !is_empty_dir(&self.target_dir) {
             if !force {
                 return Err(anyhow!(
                     "Target directory '{}' already exists. Use --force to overwrite.",
@@ -38,11 +68,14 @@ impl RepoManager {
             warn!("Target directory exists, would remove with --force");
         }
 
-        // Try Git clone first (for public repositories)
-        if let Ok(()) = self.try_git_clone(repo_url).await {
-            info!("Successfully cloned repository using Git");
-            return self.validate_and_setup_repository();
-        }
+        // Try Git clone first, with the SSH agent, keys, or a token
+        let clone_error = match self.try_git_clone(repo_url).await {
+            Ok(()) => {
+                info!("Successfully cloned repository using Git");
+                return self.validate_and_setup_repository();
+            }
+            Err(e) => e,
+        };
 
         // Fall back to source archive download (for private repositories)
         info!("Git clone failed, trying source archive download");
@@ -51,14 +84,17 @@ impl RepoManager {
             return self.validate_and_setup_repository();
         }
 
-        // If both methods fail, guide user to manual setup
+        // If both methods fail, say why the clone failed and guide user to manual setup
+        let resume = if self.partial_clone(repo_url).is_some() {
+            "Run the same command again to resume the clone, or run"
+        } else {
+            "Run"
+        };
         Err(anyhow!(
-            "Failed to initialize from repository. Please ensure:\n\
-             - The repository is accessible (public or you have access)\n\
-             - Your SSH key is properly configured (for SSH URLs)\n\
-             - You have network connectivity\n\
+            "Failed to initialize from repository: {clone_error}\n\
              \n\
-             You can manually clone the repository and run 'ordinator init' in the directory."
+             {resume} 'ordinator auth test {repo_url}' to check access, or clone the \
+             repository manually and run 'ordinator init' in the directory."
         ))
     }
 
@@ -174,41 +210,91 @@ impl RepoManager {
     async fn try_git_clone(&self, repo_url: &str) -> Result<()> {
         info!("Attempting Git clone: {}", repo_url);
 
-        // Check if we're in test mode
-        if std::env::var("ORDINATOR_TEST_MODE").ok().as_deref() == Some("1") {
-            info!("[TEST MODE] Simulating Git clone for: {}", repo_url);
+        match self.clone_into(repo_url) {
+            Ok(()) => {
+                info!("Git clone successful");
+                Ok(())
+            }
+            Err(reason) => {
+                warn!("Git clone failed: {}", reason);
+                Err(anyhow!("Git clone failed: {reason}"))
+            }
+        }
+    }
 
-            // Create the target directory structure for testing
-            std::fs::create_dir_all(&self.target_dir)?;
+    /// The repository an interrupted clone of `repo_url` left in the target
+    /// directory: `origin` points at the URL but the files aren't checked out
+    fn partial_clone(&self, repo_url: &str) -> Option<git2::Repository> {
+        let repo = git2::Repository::open(&self.target_dir).ok()?;
+        let same_origin = repo
+            .find_remote("origin")
+            .is_ok_and(|origin| origin.url() == Some(repo_url));
+        let checked_out = repo.head().is_ok() && self.target_dir.join("ordinator.toml").exists();
+        (same_origin && !checked_out).then_some(repo)
+    }
 
-            // Simulate different failure scenarios based on the URL
-            if repo_url.contains("nonexistent") || repo_url.contains("private") {
-                return Err(anyhow!("Git clone failed: Repository not found"));
+    /// Clone `repo_url` into the target directory, or finish a clone that was
+    /// interrupted there. The repository and its `origin` are set up before
+    /// anything is fetched, so a failed download can be resumed.
+    fn clone_into(&self, repo_url: &str) -> Result<()> {
+        let repo = match self.partial_clone(repo_url) {
+            Some(repo) => repo,
+            None => {
+                let repo = git2::Repository::init(&self.target_dir)?;
+                repo.remote("origin", repo_url)?;
+                repo
             }
+        };
+        crate::environment::remote(repo_url, || self.fetch_and_check_out(&repo, repo_url))
+    }
 
-            // For valid-looking URLs, simulate success but create a minimal structure
-            // Create a .git directory to make it look like a git repo
-            let git_dir = self.target_dir.join(".git");
-            std::fs::create_dir_all(&git_dir)?;
-
-            info!("[TEST MODE] Git clone simulation successful");
-            return Ok(());
+    /// Fetch everything from `origin` and check out its default branch
+    fn fetch_and_check_out(&self, repo: &git2::Repository, repo_url: &str) -> Result<()> {
+        let mut remote = repo.find_remote("origin")?;
+
+        let methods = crate::auth::AuthMethods::detect(&crate::utils::get_home_dir()?);
+        let attempts = RefCell::new(crate::auth::Attempts::default());
+        let bar = crate::transfer::TransferBar::start("clone");
+        let fetched = crate::transfer::retry(self.retries, "Clone", || {
+            *attempts.borrow_mut() = crate::auth::Attempts::default();
+            let mut callbacks = crate::auth::callbacks(&methods, &attempts);
+            bar.attach(&mut callbacks);
+            let mut fetch_options = git2::FetchOptions::new();
+            fetch_options.remote_callbacks(callbacks);
+            if let Some(depth) = self.depth {
+                fetch_options.depth(i32::try_from(depth).unwrap_or(i32::MAX));
+            }
+            remote.fetch::<&str>(&[], Some(&mut fetch_options), None)
+        });
+        bar.finish();
+        if let Err(e) = fetched {
+            return Err(crate::auth::explain(
+                repo_url,
+                &e,
+                &methods,
+                &attempts.borrow(),
+            ));
         }
 
-        let output = Command::new("git")
-            .arg("clone")
-            .arg(repo_url)
-            .arg(&self.target_dir)
-            .output()?;
-
-        if output.status.success() {
-            info!("Git clone successful");
-            Ok(())
-        } else {
-            let error = String::from_utf8_lossy(&output.stderr);
-            warn!("Git clone failed: {}", error);
-            Err(anyhow!("Git clone failed: {}", error))
-        }
+        // Check out the remote's default branch
+        let branch = remote
+            .default_branch()
+            .ok()
+            .and_then(|name| {
+                name.as_str()
+                    .and_then(|name| name.strip_prefix("refs/heads/"))
+                    .map(String::from)
+            })
+            .or_else(|| fetched_branch(repo))
+            .ok_or_else(|| anyhow!("The repository has no branches"))?;
+        let commit = repo
+            .find_reference(&format!("refs/remotes/origin/{branch}"))?
+            .peel_to_commit()?;
+        let mut local = repo.branch(&branch, &commit, true)?;
+        local.set_upstream(Some(&format!("origin/{branch}")))?;
+        repo.set_head(&format!("refs/heads/{branch}"))?;
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))?;
+        Ok(())
     }
 
     /// Download repository as source archive
@@ -218,30 +304,6 @@ impl RepoManager {
             repo_info.owner, repo_info.repo
         );
 
-        // Check if we're in test mode
-        if std::env::var("ORDINATOR_TEST_MODE").ok().as_deref() == Some("1") {
-            info!(
-                "[TEST MODE] Simulating source archive download for {}/{}",
-                repo_info.owner, repo_info.repo
-            );
-
-            // Create the target directory structure for testing
-            std::fs::create_dir_all(&self.target_dir)?;
-
-            // Simulate different failure scenarios based on the repo info
-            if repo_info.owner.contains("nonexistent") || repo_info.owner.contains("private") {
-                return Err(anyhow!("Failed to download archive: HTTP 404"));
-            }
-
-            // For valid-looking repos, simulate success but create a minimal structure
-            // Create a .git directory to make it look like a git repo
-            let git_dir = self.target_dir.join(".git");
-            std::fs::create_dir_all(&git_dir)?;
-
-            info!("[TEST MODE] Source archive download simulation successful");
-            return Ok(());
-        }
-
         // Construct archive URL
         let archive_url = format!(
             "https://github.com/{}/{}/archive/refs/heads/master.tar.gz",
@@ -250,18 +312,20 @@ impl RepoManager {
 
         info!("Archive URL: {}", archive_url);
 
-        // Download the archive
-        let response = reqwest::get(&archive_url).await?;
-        if !response.status().is_success() {
-            return Err(anyhow!(
-                "Failed to download archive: HTTP {}",
-                response.status()
-            ));
-        }
-
         // Create target directory
         std::fs::create_dir_all(&self.target_dir)?;
 
+        crate::environment::remote_async(
+            &archive_url,
+            self.download_archive(&archive_url, repo_info),
+        )
+        .await
+    }
+
+    /// Download and unpack the archive at `archive_url` into the target directory
+    async fn download_archive(&self, archive_url: &str, repo_info: &GitHubRepoInfo) -> Result<()> {
+        let response = self.download_with_retries(archive_url).await?;
+
         // Download and extract the archive
         let bytes = response.bytes().await?;
         let mut archive = flate2::read::GzDecoder::new(&bytes[..]);
@@ -285,6 +349,52 @@ impl RepoManager {
         Ok(())
     }
 
+    /// GET `url`, with a token for private repositories. Dropped connections,
+    /// rate limits, and server errors are retried, waiting as long as the
+    /// server's `Retry-After` asks.
+    async fn download_with_retries(&self, url: &str) -> Result<reqwest::Response> {
+        use reqwest::StatusCode;
+
+        let token = crate::auth::TOKEN_VARS
+            .iter()
+            .find_map(|var| std::env::var(var).ok().filter(|token| !token.is_empty()));
+        let mut failures = 0;
+        loop {
+            let mut request = reqwest::Client::new().get(url);
+            if let Some(token) = &token {
+                request = request.bearer_auth(token);
+            }
+            let (reason, retry_after) = match request.send().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => {
+                    let status = response.status();
+                    let headers = response.headers();
+                    let rate_limited = status == StatusCode::TOO_MANY_REQUESTS
+                        || (status == StatusCode::FORBIDDEN
+                            && headers
+                                .get("x-ratelimit-remaining")
+                                .is_some_and(|remaining| remaining == "0"));
+                    if !rate_limited && !status.is_server_error() {
+                        return Err(anyhow!("Failed to download archive: HTTP {status}"));
+                    }
+                    let retry_after = crate::transfer::retry_after(
+                        headers
+                            .get(reqwest::header::RETRY_AFTER)
+                            .and_then(|value| value.to_str().ok()),
+                    );
+                    (format!("HTTP {status}"), retry_after)
+                }
+                Err(e) => (e.to_string(), None),
+            };
+            if failures >= self.retries {
+                return Err(anyhow!("Failed to download archive: {reason}"));
+            }
+            failures += 1;
+            let wait = retry_after.unwrap_or_else(|| crate::transfer::backoff(failures));
+            crate::transfer::wait_to_retry("Download", &reason, failures, self.retries, wait);
+        }
+    }
+
     /// Validate repository structure and set up configuration
     fn validate_and_setup_repository(&self) -> Result<()> {
         info!("Validating repository structure");
@@ -314,6 +424,34 @@ impl RepoManager {
     }
 }
 
+fn is_empty_dir(dir: &std::path::Path) -> bool {
+    std::fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_none())
+}
+
+/// A fetched branch to check out when the remote doesn't name a default:
+/// `main`, then `master`, then the first one
+fn fetched_branch(repo: &git2::Repository) -> Option<String> {
+    let branches: Vec<String> = repo
+        .branches(Some(git2::BranchType::Remote))
+        .ok()?
+        .filter_map(|branch| {
+            branch
+                .ok()?
+                .0
+                .name()
+                .ok()??
+                .strip_prefix("origin/")
+                .map(String::from)
+        })
+        .filter(|name| name != "HEAD")
+        .collect();
+    ["main", "master"]
+        .into_iter()
+        .find(|name| branches.iter().any(|branch| branch == name))
+        .map(String::from)
+        .or_else(|| branches.into_iter().next())
+}
+
 /// Information about a GitHub repository
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -532,4 +670,57 @@ mod tests {
             assert!(result.is_err(), "Should fail for trailing slash: {url}");
         }
     }
+
+    /// A repository with one commit adding `ordinator.toml`, on branch `main`
+    fn source_repository(dir: &std::path::Path) -> String {
+        let repo = git2::Repository::init(dir).unwrap();
+        std::fs::write(dir.join("ordinator.toml"), "[global]\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index
+            .add_path(std::path::Path::new("ordinator.toml"))
+            .unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(
+            Some("refs/heads/main"),
+            &signature,
+            &signature,
+            "Initial commit",
+            &tree,
+            &[],
+        )
+        .unwrap();
+        repo.set_head("refs/heads/main").unwrap();
+        dir.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_clone_resumes_an_interrupted_clone() {
+        let source = tempdir().unwrap();
+        let url = source_repository(source.path());
+        let temp_dir = tempdir().unwrap();
+        let target = temp_dir.path().join("dotfiles");
+        let manager = RepoManager::new(target.clone()).with_retries(0);
+        assert!(manager.partial_clone(&url).is_none());
+
+        // What a clone leaves behind when the download fails
+        let repo = git2::Repository::init(&target).unwrap();
+        repo.remote("origin", &url).unwrap();
+        assert!(manager.partial_clone(&url).is_some());
+        assert!(manager
+            .partial_clone("git@github.com:user/other.git")
+            .is_none());
+
+        manager.clone_into(&url).unwrap();
+        assert!(target.join("ordinator.toml").is_file());
+        assert!(manager.partial_clone(&url).is_none());
+        let head = repo.head().unwrap();
+        assert_eq!(head.shorthand(), Some("main"));
+        let upstream = repo
+            .find_branch("main", git2::BranchType::Local)
+            .unwrap()
+            .upstream()
+            .unwrap();
+        assert_eq!(upstream.name().unwrap(), Some("origin/main"));
+    }
 }
@@ -0,0 +1,155 @@
+//! When secrets were last re-encrypted and the age key last replaced, kept in
+//! `.secrets-rotation.toml` in the dotfiles repository so every machine sees
+//! it, and the `[secrets] key_max_age_days` policy checked against it.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+
+/// Rotation record file, relative to the dotfiles repository
+pub const ROTATION_FILE: &str = ".secrets-rotation.toml";
+
+/// The last rotation of the age key and re-encryption of the stored secrets
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RotationRecord {
+    /// When `ordinator secrets rotate` last replaced the age key (RFC 3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_key_rotation: Option<String>,
+
+    /// When the stored secrets were last re-encrypted, with or without a new key (RFC 3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_reencrypted: Option<String>,
+
+    /// Number of secrets re-encrypted that time
+    #[serde(default)]
+    pub secrets: usize,
+
+    /// Age public keys the secrets were encrypted for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<String>,
+}
+
+impl RotationRecord {
+    pub fn path(dotfiles_dir: &Path) -> PathBuf {
+        dotfiles_dir.join(ROTATION_FILE)
+    }
+
+    /// Load the record, or an empty one if nothing was rotated yet
+    pub fn load(dotfiles_dir: &Path) -> Result<Self> {
+        let path = Self::path(dotfiles_dir);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse rotation record {}", path.display()))
+    }
+
+    pub fn save(&self, dotfiles_dir: &Path) -> Result<()> {
+        let path = Self::path(dotfiles_dir);
+        fs::write(&path, toml::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Record a re-encryption of `secrets` secrets for `recipients`; with
+    /// `new_key`, also that the age key was replaced
+    pub fn record(
+        dotfiles_dir: &Path,
+        secrets: usize,
+        recipients: Vec<String>,
+        new_key: bool,
+    ) -> Result<Self> {
+        let mut record = Self::load(dotfiles_dir)?;
+        let now = Utc::now().to_rfc3339();
+        if new_key {
+            record.last_key_rotation = Some(now.clone());
+        }
+        record.last_reencrypted = Some(now);
+        record.secrets = secrets;
+        record.recipients = recipients;
+        record.save(dotfiles_dir)?;
+        Ok(record)
+    }
+}
+
+/// When the age key was created: its last recorded rotation, else the newest
+/// profile `created_on`, else the key file's modification time
+pub fn key_created(config: &Config, record: &RotationRecord) -> Option<DateTime<Utc>> {
+    let parse = |timestamp: &str| {
+        DateTime::parse_from_rfc3339(timestamp)
+            .ok()
+            .map(|time| time.with_timezone(&Utc))
+    };
+    if let Some(rotated) = record.last_key_rotation.as_deref().and_then(parse) {
+        return Some(rotated);
+    }
+    let created_on = config
+        .profiles
+        .values()
+        .filter_map(|profile| profile.created_on.as_deref().and_then(parse))
+        .max();
+    if created_on.is_some() {
+        return created_on;
+    }
+    let modified = fs::metadata(config.secrets.age_key_file.as_ref()?)
+        .and_then(|metadata| metadata.modified())
+        .ok()?;
+    Some(DateTime::<Utc>::from(modified))
+}
+
+/// A warning when the age key is older than `[secrets] key_max_age_days`
+pub fn key_age_warning(config: &Config, dotfiles_dir: &Path) -> Result<Option<String>> {
+    let Some(max_age) = config.secrets.key_max_age_days else {
+        return Ok(None);
+    };
+    if config.secrets.age_key_file.is_none() {
+        return Ok(None);
+    }
+    let record = RotationRecord::load(dotfiles_dir)?;
+    let Some(created) = key_created(config, &record) else {
+        return Ok(None);
+    };
+    let age = (Utc::now() - created).num_days();
+    if age < i64::from(max_age) {
+        return Ok(None);
+    }
+    Ok(Some(format!(
+        "The age key is {age} days old (created {}), over the {max_age} day limit in [secrets] key_max_age_days. Run: ordinator secrets rotate",
+        created.format("%Y-%m-%d")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_age_warning_uses_last_rotation() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.secrets.age_key_file = Some(temp.path().join("key.txt"));
+        config.secrets.key_max_age_days = Some(30);
+
+        let old = RotationRecord {
+            last_key_rotation: Some((Utc::now() - chrono::Duration::days(45)).to_rfc3339()),
+            ..Default::default()
+        };
+        old.save(temp.path()).unwrap();
+        let warning = key_age_warning(&config, temp.path()).unwrap().unwrap();
+        assert!(warning.contains("45 days old"));
+
+        let record = RotationRecord::record(temp.path(), 2, vec!["age1a".into()], true).unwrap();
+        assert_eq!(record.last_key_rotation, record.last_reencrypted);
+        assert_eq!(RotationRecord::load(temp.path()).unwrap(), record);
+        assert_eq!(key_age_warning(&config, temp.path()).unwrap(), None);
+
+        config.secrets.key_max_age_days = None;
+        old.save(temp.path()).unwrap();
+        assert_eq!(key_age_warning(&config, temp.path()).unwrap(), None);
+    }
+}
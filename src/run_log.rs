@@ -0,0 +1,279 @@
+//! Run logs: each command's arguments, log events, duration, and result,
+//! written as JSON lines to one file per run under
+//! `~/.local/state/ordinator/logs/`, so `ordinator logs` can show what an
+//! earlier run did. Only the newest [`KEEP`] runs are kept.
+//!
+//! A run log file starts with a `{"event": "start", ...}` line and ends with
+//! a `{"event": "finish", ...}` line; the lines between are log events in the
+//! `--log-file` format. Writing the log never fails the command.
+
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Directory (beside the machine state file) holding run logs
+const LOG_DIR: &str = "logs";
+
+/// How many runs are kept
+pub const KEEP: usize = 50;
+
+/// The run in progress, finished by [`end`] or [`exit`]
+static CURRENT: Mutex<Option<RunLog>> = Mutex::new(None);
+
+/// First and last line of a run log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Marker {
+    Start {
+        timestamp: String,
+        command: String,
+        args: Vec<String>,
+        version: String,
+    },
+    Finish {
+        timestamp: String,
+        duration_ms: u64,
+        exit_code: i32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+/// A run being logged
+pub struct RunLog {
+    file: File,
+    started: Instant,
+}
+
+/// Where run logs for `home_dir` are kept: beside its machine state file
+pub fn logs_dir(home_dir: &Path) -> PathBuf {
+    let state = crate::state::MachineState::path(home_dir);
+    state
+        .parent()
+        .map_or_else(|| PathBuf::from(LOG_DIR), |dir| dir.join(LOG_DIR))
+}
+
+fn write_line(file: &mut File, value: &impl Serialize) {
+    if let Ok(line) = serde_json::to_string(value) {
+        let _ = writeln!(file, "{line}");
+    }
+}
+
+impl RunLog {
+    /// Start logging a run of `command`, removing the oldest runs beyond [`KEEP`]
+    pub fn create(home_dir: &Path, command: &str, args: Vec<String>) -> Result<Self> {
+        let dir = logs_dir(home_dir);
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let now = Local::now();
+        let base = format!(
+            "{}-{}",
+            now.format("%Y%m%d-%H%M%S"),
+            command.split(' ').next().unwrap_or(command)
+        );
+        let mut path = dir.join(format!("{base}.jsonl"));
+        let mut counter = 1;
+        while path.exists() {
+            path = dir.join(format!("{base}-{counter}.jsonl"));
+            counter += 1;
+        }
+        let mut file =
+            File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+        write_line(
+            &mut file,
+            &Marker::Start {
+                timestamp: now.to_rfc3339(),
+                command: command.to_string(),
+                args,
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+        );
+        let runs = Run::list(home_dir)?;
+        for run in runs.iter().take(runs.len().saturating_sub(KEEP)) {
+            let _ = fs::remove_file(&run.path);
+        }
+        Ok(Self {
+            file,
+            started: Instant::now(),
+        })
+    }
+
+    /// A second handle on the file, for the logging layer that records events
+    pub fn events(&self) -> Result<File> {
+        Ok(self.file.try_clone()?)
+    }
+
+    fn finish(mut self, exit_code: i32, error: Option<String>) {
+        let duration_ms = u64::try_from(self.started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        write_line(
+            &mut self.file,
+            &Marker::Finish {
+                timestamp: Local::now().to_rfc3339(),
+                duration_ms,
+                exit_code,
+                error,
+            },
+        );
+    }
+}
+
+/// Make `log` the run [`end`] and [`exit`] finish
+pub fn begin(log: RunLog) {
+    *CURRENT.lock().unwrap_or_else(|e| e.into_inner()) = Some(log);
+}
+
+/// Record how the current run ended, if one is being logged
+pub fn end(exit_code: i32, error: Option<String>) {
+    let log = CURRENT.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(log) = log {
+        log.finish(exit_code, error);
+    }
+}
+
+/// [`std::process::exit`], recording the exit code in the run log first
+pub fn exit(code: i32) -> ! {
+    end(code, None);
+    std::process::exit(code)
+}
+
+/// One logged event
+#[derive(Debug, Clone, Deserialize)]
+pub struct Event {
+    pub timestamp: String,
+    pub level: String,
+    pub message: String,
+}
+
+/// A logged run, read back from its file
+#[derive(Debug, Clone)]
+pub struct Run {
+    /// File name without `.jsonl`
+    pub id: String,
+    pub path: PathBuf,
+    pub started_at: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub events: Vec<Event>,
+    pub duration_ms: Option<u64>,
+
+    /// None when the run never finished: it crashed, or is still going
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+impl Run {
+    fn read(path: &Path) -> Result<Option<Self>> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut lines = content.lines();
+        let Some(Ok(Marker::Start {
+            timestamp,
+            command,
+            args,
+            ..
+        })) = lines.next().map(serde_json::from_str::<Marker>)
+        else {
+            return Ok(None);
+        };
+        let mut run = Self {
+            id: path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            path: path.to_path_buf(),
+            started_at: timestamp,
+            command,
+            args,
+            events: Vec::new(),
+            duration_ms: None,
+            exit_code: None,
+            error: None,
+        };
+        for line in lines {
+            if let Ok(Marker::Finish {
+                duration_ms,
+                exit_code,
+                error,
+                ..
+            }) = serde_json::from_str(line)
+            {
+                run.duration_ms = Some(duration_ms);
+                run.exit_code = Some(exit_code);
+                run.error = error;
+            } else if let Ok(event) = serde_json::from_str(line) {
+                run.events.push(event);
+            }
+        }
+        Ok(Some(run))
+    }
+
+    /// Every logged run for `home_dir`, oldest first
+    pub fn list(home_dir: &Path) -> Result<Vec<Self>> {
+        let dir = logs_dir(home_dir);
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut paths: Vec<PathBuf> = fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+            .collect();
+        paths.sort();
+        let mut runs = Vec::new();
+        for path in paths {
+            if let Some(run) = Self::read(&path)? {
+                runs.push(run);
+            }
+        }
+        runs.sort_by(|a, b| a.started_at.cmp(&b.started_at).then(a.id.cmp(&b.id)));
+        Ok(runs)
+    }
+
+    /// Whether this is a run of `command`, e.g. `apply` or `secrets watch`
+    pub fn is_command(&self, command: &str) -> bool {
+        self.command == command || self.command.split(' ').next() == Some(command)
+    }
+
+    /// "ok", "failed", "exit 2" for the other documented exit codes, or
+    /// "unfinished"
+    pub fn outcome(&self) -> String {
+        match self.exit_code {
+            Some(crate::exit_code::OK) => "ok".to_string(),
+            Some(crate::exit_code::ERROR) => "failed".to_string(),
+            Some(code) => format!("exit {code}"),
+            None => "unfinished".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_finish_and_list() {
+        let home = tempfile::tempdir().unwrap();
+        let log = RunLog::create(home.path(), "apply", vec!["--force".to_string()]).unwrap();
+        let mut events = log.events().unwrap();
+        writeln!(
+            events,
+            r#"{{"timestamp":"t","level":"info","target":"ordinator_core::apply","message":"Applying profile: default","fields":{{}}}}"#
+        )
+        .unwrap();
+        log.finish(3, Some("conflicts".to_string()));
+        RunLog::create(home.path(), "secrets watch", Vec::new()).unwrap();
+
+        let runs = Run::list(home.path()).unwrap();
+        assert_eq!(runs.len(), 2);
+        assert!(runs[0].is_command("apply"));
+        assert_eq!(runs[0].args, ["--force"]);
+        assert_eq!(runs[0].events[0].message, "Applying profile: default");
+        assert_eq!(runs[0].outcome(), "exit 3");
+        assert_eq!(runs[0].error.as_deref(), Some("conflicts"));
+        assert!(runs[1].is_command("secrets"));
+        assert_eq!(runs[1].outcome(), "unfinished");
+    }
+}
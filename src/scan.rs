@@ -0,0 +1,698 @@
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use crate::config::Config;
+
+/// A comment containing this allows the line it's on and the line after it
+pub const ALLOW_MARKER: &str = "ordinator:allow-secret";
+
+/// A comment containing this anywhere in a file skips the whole file
+pub const ALLOW_FILE_MARKER: &str = "ordinator:allow-secret-file";
+
+/// Baseline file (relative to the dotfiles repository) used when none is configured
+pub const DEFAULT_BASELINE: &str = ".secrets-baseline.json";
+
+/// Files larger than this many KiB are skipped unless `max_file_size` says otherwise
+pub const DEFAULT_MAX_FILE_SIZE_KB: u64 = 1024;
+
+/// Seconds one file may take to scan unless `timeout` says otherwise
+pub const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
+/// Cache of earlier results (relative to the dotfiles repository); machine-specific, so gitignored
+const CACHE_FILE: &str = "cache/secret-scan.json";
+
+/// Bytes read at a time; the first read also decides whether a file is binary
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// Patterns every scan checks, most specific first
+const BUILTIN_RULES: &[(&str, &str)] = &[
+    ("API Key", r"(?i)api[_-]?key\s*[:=]\s*[a-zA-Z0-9_-]{20,}"),
+    (
+        "OAuth Token",
+        r"(?i)oauth[_-]?token\s*[:=]\s*[a-zA-Z0-9]{20,}",
+    ),
+    ("JWT Token", r"(?i)jwt[_-]?token\s*[:=]\s*[a-zA-Z0-9]{20,}"),
+    (
+        "AWS Access Key",
+        r"(?i)aws_access_key_id\s*[:=]\s*[A-Z0-9]{20}",
+    ),
+    (
+        "AWS Secret Key",
+        r"(?i)aws_secret_access_key\s*[:=]\s*[A-Za-z0-9/+=]{40}",
+    ),
+    ("Database URL", r"(?i)database_url\s*[:=]\s*[a-zA-Z]+://"),
+    (
+        "Database Password",
+        r"(?i)db_password\s*[:=]\s*[a-zA-Z0-9!@#$%^&*]{8,}",
+    ),
+    ("Token", r"(?i)token\s*[:=]\s*[a-zA-Z0-9]{20,}"),
+    ("Secret", r"(?i)secret\s*[:=]\s*[a-zA-Z0-9]{20,}"),
+    ("Password", r"(?i)password\s*[:=]\s*[a-zA-Z0-9!@#$%^&*]{8,}"),
+    ("Private Key", r"-----BEGIN.*PRIVATE KEY-----"),
+    ("Certificate", r"-----BEGIN.*CERTIFICATE-----"),
+    ("High-entropy string", r"[a-zA-Z0-9]{32,}"),
+];
+
+/// `[secrets.scan]`: tuning for the plaintext secret scanner
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScanConfig {
+    /// Extra patterns, checked before the built-in ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<ScanRule>,
+
+    /// Globs for tracked files that are never scanned
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_files: Vec<String>,
+
+    /// Baseline of known findings, relative to the dotfiles repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<PathBuf>,
+
+    /// Files larger than this many KiB aren't scanned (default 1024; 0 scans every file)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_size: Option<u64>,
+
+    /// Seconds one file may take to scan before it's abandoned (default 10; 0 never gives up)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+impl ScanConfig {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+            && self.allow_files.is_empty()
+            && self.baseline.is_none()
+            && self.max_file_size.is_none()
+            && self.timeout.is_none()
+    }
+}
+
+/// A custom pattern under `[[secrets.scan.rules]]`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScanRule {
+    /// Shown in scan output, e.g. `"Internal API token"`
+    pub name: String,
+
+    /// Regular expression matched against each line
+    pub regex: String,
+}
+
+/// A line that looks like it holds a secret
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    /// Tracked path, as listed in the profile
+    pub file: String,
+
+    /// 1-based line number
+    pub line: usize,
+
+    pub rule: String,
+
+    /// Hash of the file, rule, and line text, so a finding stays known
+    /// when lines above it move
+    pub fingerprint: String,
+}
+
+fn fingerprint(file: &str, rule: &str, line: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(file.as_bytes());
+    hasher.update([0]);
+    hasher.update(rule.as_bytes());
+    hasher.update([0]);
+    hasher.update(line.trim().as_bytes());
+    format!("{:x}", hasher.finalize())[..16].to_string()
+}
+
+/// Built-in and configured rules, compiled once per run (see [`Scanner::shared`])
+pub struct Scanner {
+    rules: Vec<(String, Regex)>,
+    allow_files: Option<GlobSet>,
+
+    /// Larger files are skipped; None scans every file
+    max_bytes: Option<u64>,
+
+    /// How long one file may take; None waits for every file
+    timeout: Option<Duration>,
+}
+
+/// The scanner built for the last `[secrets.scan]` seen, reused while it's unchanged
+static SHARED: Mutex<Option<(ScanConfig, Arc<Scanner>)>> = Mutex::new(None);
+
+impl Scanner {
+    pub fn new(config: &ScanConfig) -> Result<Self> {
+        let mut rules = Vec::new();
+        for rule in &config.rules {
+            let regex = Regex::new(&rule.regex)
+                .with_context(|| format!("Invalid regex for secret scan rule '{}'", rule.name))?;
+            rules.push((rule.name.clone(), regex));
+        }
+        for (name, pattern) in BUILTIN_RULES {
+            rules.push((name.to_string(), Regex::new(pattern)?));
+        }
+        let allow_files = if config.allow_files.is_empty() {
+            None
+        } else {
+            let mut builder = GlobSetBuilder::new();
+            for pattern in &config.allow_files {
+                builder.add(
+                    Glob::new(pattern)
+                        .with_context(|| format!("Invalid allow_files glob '{pattern}'"))?,
+                );
+            }
+            Some(builder.build()?)
+        };
+        let max_bytes = match config.max_file_size.unwrap_or(DEFAULT_MAX_FILE_SIZE_KB) {
+            0 => None,
+            kb => Some(kb.saturating_mul(1024)),
+        };
+        let timeout = match config.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS) {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        };
+        Ok(Self {
+            rules,
+            allow_files,
+            max_bytes,
+            timeout,
+        })
+    }
+
+    /// The scanner for `config`, compiled on first use and shared by every
+    /// scan in this run that has the same `[secrets.scan]` settings
+    pub fn shared(config: &ScanConfig) -> Result<Arc<Self>> {
+        let mut shared = SHARED.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((cached, scanner)) = shared.as_ref() {
+            if cached == config {
+                return Ok(Arc::clone(scanner));
+            }
+        }
+        let scanner = Arc::new(Self::new(config)?);
+        *shared = Some((config.clone(), Arc::clone(&scanner)));
+        Ok(scanner)
+    }
+
+    /// Findings in `content`, at most one per line (the first rule that matches)
+    pub fn scan_content(&self, file: &str, content: &str) -> Vec<Finding> {
+        self.scan_reader(file, content.as_bytes(), None)
+            .unwrap_or_default()
+    }
+
+    /// Findings in the tracked file `file`, read from `path`. Missing, binary,
+    /// oversized, and allow-listed files have none.
+    pub fn scan_file(&self, file: &str, path: &Path) -> Vec<Finding> {
+        if self.allows(file) {
+            return Vec::new();
+        }
+        match self.open(path) {
+            Some(reader) => self.scan_reader(file, reader, None).unwrap_or_default(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Whether `file` matches `allow_files`
+    fn allows(&self, file: &str) -> bool {
+        self.allow_files
+            .as_ref()
+            .is_some_and(|globs| globs.is_match(file))
+    }
+
+    /// Scan the tracked file `file` at `path` like [`scan_file`](Self::scan_file),
+    /// reusing `cached` when the content hash still matches and giving up
+    /// after the configured timeout
+    fn scan_file_cached(&self, file: &str, path: &Path, cached: Option<&CachedScan>) -> FileScan {
+        if self.allows(file) {
+            return FileScan::Skipped;
+        }
+        let Some(mut reader) = self.open(path) else {
+            return FileScan::Skipped;
+        };
+        let mut content = Vec::new();
+        if std::io::Read::read_to_end(&mut reader, &mut content).is_err() {
+            return FileScan::Skipped;
+        }
+        let hash = crate::drift::hash_bytes(&content);
+        if let Some(cached) = cached.filter(|cached| cached.hash == hash) {
+            return FileScan::Scanned(cached.clone());
+        }
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        match self.scan_reader(file, content.as_slice(), deadline) {
+            Some(findings) => FileScan::Scanned(CachedScan { hash, findings }),
+            None => FileScan::TimedOut,
+        }
+    }
+
+    /// Names of every rule that matches anywhere in the file at `path`
+    pub fn matching_rules(&self, path: &Path) -> Vec<String> {
+        let Some(mut reader) = self.open(path) else {
+            return Vec::new();
+        };
+        let mut content = String::new();
+        if std::io::Read::read_to_string(&mut reader, &mut content).is_err()
+            || content.contains('\0')
+        {
+            return Vec::new();
+        }
+        self.rules
+            .iter()
+            .filter(|(_, regex)| regex.is_match(&content))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// A reader over `path`, or None if it's missing, over the size limit,
+    /// or binary. Only the first chunk is read to tell.
+    fn open(&self, path: &Path) -> Option<BufReader<fs::File>> {
+        let file = fs::File::open(path).ok()?;
+        let size = file.metadata().ok()?.len();
+        if self.max_bytes.is_some_and(|max| size > max) {
+            debug!(
+                "Not scanning {} ({size} bytes, over [secrets.scan] max_file_size)",
+                path.display()
+            );
+            return None;
+        }
+        let mut reader = BufReader::with_capacity(CHUNK_BYTES, file);
+        if reader.fill_buf().ok()?.contains(&0) {
+            debug!("Not scanning binary file {}", path.display());
+            return None;
+        }
+        Some(reader)
+    }
+
+    /// Scan line by line, a chunk at a time. Content that turns out to be
+    /// binary or not UTF-8, or that has the allow-file marker, has no findings.
+    /// None if `deadline` passes first.
+    fn scan_reader(
+        &self,
+        file: &str,
+        mut reader: impl BufRead,
+        deadline: Option<Instant>,
+    ) -> Option<Vec<Finding>> {
+        let mut findings = Vec::new();
+        let mut previous_allows = false;
+        let mut bytes = Vec::new();
+        for index in 0.. {
+            bytes.clear();
+            match reader.read_until(b'\n', &mut bytes) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(_) => return Some(Vec::new()),
+            }
+            if deadline.is_some_and(|deadline| Instant::now() > deadline) {
+                return None;
+            }
+            if bytes.contains(&0) {
+                return Some(Vec::new());
+            }
+            let Ok(line) = std::str::from_utf8(&bytes) else {
+                return Some(Vec::new());
+            };
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.contains(ALLOW_FILE_MARKER) {
+                return Some(Vec::new());
+            }
+            let allowed = previous_allows || line.contains(ALLOW_MARKER);
+            previous_allows = line.contains(ALLOW_MARKER);
+            if allowed {
+                continue;
+            }
+            if let Some((rule, _)) = self.rules.iter().find(|(_, regex)| regex.is_match(line)) {
+                findings.push(Finding {
+                    file: file.to_string(),
+                    line: index + 1,
+                    rule: rule.clone(),
+                    fingerprint: fingerprint(file, rule, line),
+                });
+            }
+        }
+        Some(findings)
+    }
+}
+
+/// How scanning one file went
+enum FileScan {
+    /// Missing, binary, oversized, or allow-listed
+    Skipped,
+    Scanned(CachedScan),
+    TimedOut,
+}
+
+/// Findings for a file's content, valid while its hash is unchanged
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CachedScan {
+    hash: String,
+    findings: Vec<Finding>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheData {
+    /// Hash of the `[secrets.scan]` settings the results were found with
+    #[serde(default)]
+    settings: String,
+
+    /// Keyed by tracked path
+    #[serde(default)]
+    files: BTreeMap<String, CachedScan>,
+}
+
+/// Results of earlier scans, so unchanged files aren't scanned again
+struct ScanCache {
+    path: PathBuf,
+    data: CacheData,
+}
+
+impl ScanCache {
+    /// Load the cache for a dotfiles repository. A missing or unreadable
+    /// cache, or one from different settings, starts empty.
+    fn load(dotfiles_dir: &Path, config: &ScanConfig) -> Self {
+        let path = dotfiles_dir.join(CACHE_FILE);
+        let settings = crate::drift::hash_bytes(&serde_json::to_vec(config).unwrap_or_default());
+        let data = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<CacheData>(&content).ok())
+            .filter(|data| data.settings == settings)
+            .unwrap_or(CacheData {
+                settings,
+                files: BTreeMap::new(),
+            });
+        Self { path, data }
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.data)?)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+/// Findings from a scan of several files
+#[derive(Debug, Default)]
+pub struct ScanReport {
+    pub findings: Vec<Finding>,
+
+    /// Files read, including ones whose results came from the cache
+    pub scanned: usize,
+
+    /// Files abandoned after `[secrets.scan] timeout`
+    pub timed_out: Vec<String>,
+}
+
+impl ScanReport {
+    /// Whether a commit has to wait: unaccepted findings, or files that
+    /// couldn't be scanned in time and so might hide one
+    pub fn blocks_commit(&self) -> bool {
+        !self.findings.is_empty() || !self.timed_out.is_empty()
+    }
+}
+
+/// Scan the tracked files `files`, resolved against `base_dir`, several at a
+/// time on the shared blocking pool. Results are cached in the repository by
+/// content hash, so files that haven't changed since the last scan aren't
+/// scanned again.
+pub async fn scan_files(
+    config: &ScanConfig,
+    base_dir: &Path,
+    files: &[String],
+) -> Result<ScanReport> {
+    let files = files
+        .iter()
+        .map(|file| (file.clone(), base_dir.join(file)))
+        .collect();
+    scan_paths(config, base_dir, files).await
+}
+
+/// Scan like [`scan_files`], reading each tracked file from the path paired
+/// with it, such as its stored copy in the repository at `base_dir`
+pub async fn scan_paths(
+    config: &ScanConfig,
+    base_dir: &Path,
+    files: Vec<(String, PathBuf)>,
+) -> Result<ScanReport> {
+    let scanner = Scanner::shared(config)?;
+    let mut cache = ScanCache::load(base_dir, config);
+    let work: Vec<(String, PathBuf, Option<CachedScan>)> = files
+        .into_iter()
+        .map(|(file, path)| {
+            let cached = cache.data.files.get(&file).cloned();
+            (file, path, cached)
+        })
+        .collect();
+    let results = crate::utils::map_concurrent(
+        work,
+        crate::utils::default_jobs(),
+        move |(file, path, cached)| {
+            let scan = scanner.scan_file_cached(&file, &path, cached.as_ref());
+            (file, scan)
+        },
+    )
+    .await?;
+
+    let mut report = ScanReport::default();
+    for (file, scan) in results {
+        match scan {
+            FileScan::Skipped => report.scanned += 1,
+            FileScan::Scanned(result) => {
+                report.scanned += 1;
+                report.findings.extend(result.findings.iter().cloned());
+                cache.data.files.insert(file, result);
+            }
+            FileScan::TimedOut => {
+                debug!("Gave up scanning {file} after [secrets.scan] timeout");
+                report.timed_out.push(file);
+            }
+        }
+    }
+    if let Err(e) = cache.save() {
+        debug!("Not caching secret scan results: {e:#}");
+    }
+    Ok(report)
+}
+
+/// Scan the tracked files of `profiles` like [`scan_files`]; a file tracked
+/// by several profiles is scanned once
+pub async fn scan_profiles(
+    config: &Config,
+    base_dir: &Path,
+    profiles: &[String],
+) -> Result<ScanReport> {
+    let mut seen = BTreeSet::new();
+    let mut files = Vec::new();
+    for profile in profiles.iter().filter_map(|name| config.get_profile(name)) {
+        for file in &profile.files {
+            if seen.insert(file) {
+                files.push(file.clone());
+            }
+        }
+    }
+    scan_files(&config.secrets.scan, base_dir, &files).await
+}
+
+/// Plaintext secrets that aren't allowed inline or accepted in the baseline,
+/// in the stored copies under `files/` that are about to be committed. Copies
+/// are reported under the tracked path they belong to, so `allow_files` and
+/// the baseline match them.
+pub async fn unaccepted_staged_findings(
+    config: &Config,
+    base_dir: &Path,
+    staged: &crate::git::StagedChanges,
+) -> Result<ScanReport> {
+    let files: Vec<(String, PathBuf)> = staged
+        .added
+        .iter()
+        .chain(&staged.modified)
+        .filter(|file| Path::new(file).starts_with("files"))
+        .map(|file| {
+            let tracked = config
+                .tracked_path_for_stored(Path::new(file))
+                .unwrap_or_else(|| file.clone());
+            (tracked, base_dir.join(file))
+        })
+        .collect();
+    let mut report = scan_paths(&config.secrets.scan, base_dir, files).await?;
+    let baseline_path = Baseline::path(base_dir, &config.secrets.scan);
+    let findings = std::mem::take(&mut report.findings);
+    (report.findings, _) = Baseline::load(&baseline_path)?.partition(findings);
+    Ok(report)
+}
+
+/// Findings accepted with `ordinator secrets scan --update-baseline`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    pub findings: Vec<Finding>,
+}
+
+impl Baseline {
+    /// Where the baseline lives for this repository
+    pub fn path(dotfiles_dir: &Path, config: &ScanConfig) -> PathBuf {
+        dotfiles_dir.join(
+            config
+                .baseline
+                .as_deref()
+                .unwrap_or(Path::new(DEFAULT_BASELINE)),
+        )
+    }
+
+    /// Load the baseline, or an empty one if the file doesn't exist
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse secrets baseline {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Split `findings` into new ones and ones already in the baseline
+    pub fn partition(&self, findings: Vec<Finding>) -> (Vec<Finding>, Vec<Finding>) {
+        let known: BTreeSet<&str> = self
+            .findings
+            .iter()
+            .map(|finding| finding.fingerprint.as_str())
+            .collect();
+        findings
+            .into_iter()
+            .partition(|finding| !known.contains(finding.fingerprint.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allow_comments_and_custom_rules() {
+        let config = ScanConfig {
+            rules: vec![ScanRule {
+                name: "Internal token".to_string(),
+                regex: r"itk_[0-9a-f]{8}".to_string(),
+            }],
+            ..Default::default()
+        };
+        let scanner = Scanner::new(&config).unwrap();
+        let content = "\
+export TOKEN=itk_0123abcd
+password=hunter2hunter2 # ordinator:allow-secret
+# ordinator:allow-secret
+password=examplepassword
+api_key=sk_test_1234567890abcdef
+";
+        let findings = scanner.scan_content(".zshrc", content);
+        let found: Vec<(usize, &str)> = findings
+            .iter()
+            .map(|finding| (finding.line, finding.rule.as_str()))
+            .collect();
+        assert_eq!(found, [(1, "Internal token"), (5, "API Key")]);
+
+        let content = format!("# {ALLOW_FILE_MARKER}\n{content}");
+        assert!(scanner.scan_content(".zshrc", &content).is_empty());
+    }
+
+    #[test]
+    fn test_scan_file_skips_binary_and_oversized_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let secret = "password=examplepassword\n";
+        let text = temp.path().join(".env");
+        fs::write(&text, secret.repeat(4000)).unwrap();
+        let binary = temp.path().join("keychain.db");
+        fs::write(&binary, [b"\0\x01".as_slice(), secret.as_bytes()].concat()).unwrap();
+
+        let config = ScanConfig::default();
+        let scanner = Scanner::shared(&config).unwrap();
+        assert!(Arc::ptr_eq(&scanner, &Scanner::shared(&config).unwrap()));
+        // Lines past the first chunk are still scanned
+        assert_eq!(scanner.scan_file(".env", &text).len(), 4000);
+        assert!(scanner.scan_file("keychain.db", &binary).is_empty());
+        assert!(scanner.matching_rules(&binary).is_empty());
+        assert_eq!(scanner.matching_rules(&text), ["Password"]);
+
+        let capped = Scanner::new(&ScanConfig {
+            max_file_size: Some(16),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(capped.scan_file(".env", &text).is_empty());
+        let uncapped = Scanner::new(&ScanConfig {
+            max_file_size: Some(0),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(uncapped.scan_file(".env", &text).len(), 4000);
+    }
+
+    #[tokio::test]
+    async fn test_scan_files_caches_and_times_out() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join(".env"), "password=examplepassword\n").unwrap();
+        let files = vec![".env".to_string(), ".missing".to_string()];
+        let config = ScanConfig::default();
+
+        let report = scan_files(&config, temp.path(), &files).await.unwrap();
+        assert_eq!((report.findings.len(), report.scanned), (1, 2));
+        let cache = ScanCache::load(temp.path(), &config);
+        assert_eq!(cache.data.files[".env"].findings, report.findings);
+
+        // A cached result is used while the content hash matches
+        let mut cache = cache;
+        cache.data.files.get_mut(".env").unwrap().findings.clear();
+        cache.save().unwrap();
+        let report = scan_files(&config, temp.path(), &files).await.unwrap();
+        assert!(report.findings.is_empty());
+        fs::write(temp.path().join(".env"), "password=otherpassword\n").unwrap();
+        let report = scan_files(&config, temp.path(), &files).await.unwrap();
+        assert_eq!(report.findings.len(), 1);
+
+        let scanner = Scanner::new(&config).unwrap();
+        let past = Instant::now() - Duration::from_secs(1);
+        assert_eq!(
+            scanner.scan_reader(".env", "a\n".as_bytes(), Some(past)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_baseline_matches_moved_lines() {
+        let scanner = Scanner::new(&ScanConfig::default()).unwrap();
+        let baseline = Baseline {
+            findings: scanner.scan_content(".env", "password=examplepassword\n"),
+        };
+        let findings = scanner.scan_content(
+            ".env",
+            "# new comment\npassword=examplepassword\ntoken=abcdefghijklmnopqrstuvwxyz\n",
+        );
+        let (new, known) = baseline.partition(findings);
+        assert_eq!(known.len(), 1);
+        assert_eq!(known[0].line, 2);
+        assert_eq!(new.len(), 1);
+        assert_eq!(new[0].rule, "Token");
+
+        let config = ScanConfig {
+            rules: vec![ScanRule {
+                name: "Broken".to_string(),
+                regex: "(".to_string(),
+            }],
+            ..Default::default()
+        };
+        assert!(Scanner::new(&config).is_err());
+    }
+}
@@ -0,0 +1,161 @@
+//! `ordinator search`: a regex search over the repository copies of tracked
+//! files, and optionally over secrets decrypted in memory.
+
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use crate::config::Config;
+
+/// A line that matched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchMatch {
+    /// Profile that tracks the file
+    pub profile: String,
+
+    /// Tracked path, as listed in the profile
+    pub path: String,
+
+    /// 1-based line number
+    pub line: usize,
+
+    pub text: String,
+
+    /// Whether the line came from a decrypted secret
+    pub secret: bool,
+}
+
+/// Compile the search pattern; with `fixed_strings` it's matched literally
+pub fn build_pattern(pattern: &str, ignore_case: bool, fixed_strings: bool) -> Result<Regex> {
+    let source = if fixed_strings {
+        regex::escape(pattern)
+    } else {
+        pattern.to_string()
+    };
+    RegexBuilder::new(&source)
+        .case_insensitive(ignore_case)
+        .build()
+        .with_context(|| format!("Invalid search pattern '{pattern}'"))
+}
+
+/// Matching lines in `content`, with their 1-based line numbers
+pub fn search_content(regex: &Regex, content: &str) -> Vec<(usize, String)> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| regex.is_match(line))
+        .map(|(index, line)| (index + 1, line.to_string()))
+        .collect()
+}
+
+/// Text of a stored file, or None if it's missing or binary
+fn read_text(path: &Path) -> Option<String> {
+    let bytes = fs::read(path).ok()?;
+    if bytes.contains(&0) {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Search the stored copies of the files and directories `profiles` track,
+/// inherited ones included. A file shared through `extends` is searched once,
+/// under the profile that tracks it.
+pub fn search_files(
+    config: &Config,
+    profiles: &[String],
+    regex: &Regex,
+) -> Result<Vec<SearchMatch>> {
+    let mut seen = BTreeSet::new();
+    let mut matches = Vec::new();
+    for profile in profiles {
+        let resolved = config.resolve_profile(profile)?;
+        for (path, owner, source) in config.link_targets(&resolved, true)? {
+            if !seen.insert((owner.clone(), path.clone())) {
+                continue;
+            }
+            let Some(content) = read_text(&source) else {
+                continue;
+            };
+            matches.extend(
+                search_content(regex, &content)
+                    .into_iter()
+                    .map(|(line, text)| SearchMatch {
+                        profile: owner.clone(),
+                        path: path.clone(),
+                        line,
+                        text,
+                        secret: false,
+                    }),
+            );
+        }
+    }
+    Ok(matches)
+}
+
+/// Secrets `profiles` track, as (profile, tracked path), each once
+pub fn secret_entries(config: &Config, profiles: &[String]) -> Result<Vec<(String, String)>> {
+    let mut entries = Vec::new();
+    for profile in profiles {
+        for entry in config.resolve_profile(profile)?.secrets {
+            let key = (entry.profile, entry.path);
+            if !entries.contains(&key) {
+                entries.push(key);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Decrypt each secret in memory and search it. Secrets without a stored
+/// copy are skipped; a failed decryption is an error.
+pub fn search_secrets(
+    config: &Config,
+    base_dir: &Path,
+    entries: &[(String, String)],
+    regex: &Regex,
+) -> Result<Vec<SearchMatch>> {
+    let mut matches = Vec::new();
+    for (profile, path) in entries {
+        let stored = crate::secrets::stored_secret_path(config, base_dir, profile, path)?;
+        if !stored.exists() {
+            continue;
+        }
+        let plaintext = crate::secrets::decrypt_stored(&stored)?;
+        matches.extend(
+            search_content(regex, &plaintext)
+                .into_iter()
+                .map(|(line, text)| SearchMatch {
+                    profile: profile.clone(),
+                    path: path.clone(),
+                    line,
+                    text,
+                    secret: true,
+                }),
+        );
+    }
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_pattern_and_search_content() {
+        let content = "alias ll='ls -la'\nexport EDITOR=vim\nALIAS_FILE=~/.aliases\n";
+        let regex = build_pattern("^alias", false, false).unwrap();
+        assert_eq!(
+            search_content(&regex, content),
+            [(1, "alias ll='ls -la'".to_string())]
+        );
+        let regex = build_pattern("alias", true, false).unwrap();
+        assert_eq!(search_content(&regex, content).len(), 2);
+        let regex = build_pattern("ls -la'", false, true).unwrap();
+        assert_eq!(search_content(&regex, content)[0].0, 1);
+        let regex = build_pattern("(", false, true).unwrap();
+        assert!(search_content(&regex, content).is_empty());
+        assert!(build_pattern("(", false, false).is_err());
+    }
+}
@@ -0,0 +1,140 @@
+//! Metadata kept beside each secret stored by `secrets add`: a small TOML
+//! sidecar, `secrets/<profile>/<name>.enc.meta`, recording the path the
+//! secret was tracked as, its permissions, and its owner. The encrypted file
+//! name is a hash, so the sidecar is what ties it back to a destination when
+//! `file_mappings` doesn't, and what `secrets list` and `fsck` show and check.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// Appended to the stored secret's file name
+const SUFFIX: &str = ".meta";
+
+/// What a stored secret decrypts to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SecretMeta {
+    /// Tracked path: relative to the home directory, or absolute
+    pub target: String,
+
+    /// Octal permissions of the original file, e.g. `"600"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+
+    /// User that owned the original file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+}
+
+impl SecretMeta {
+    /// Metadata for `source`, tracked as `target`
+    pub fn of(source: &Path, target: &str) -> Self {
+        let metadata = fs::metadata(source).ok();
+        Self {
+            target: target.to_string(),
+            mode: metadata
+                .as_ref()
+                .map(|metadata| crate::config::format_mode(metadata.mode())),
+            owner: metadata.and_then(|metadata| user_name(metadata.uid())),
+        }
+    }
+
+    /// The recorded permissions, if they parse
+    pub fn mode(&self) -> Option<u32> {
+        crate::config::parse_mode(self.mode.as_deref()?).ok()
+    }
+
+    /// `~/.npmrc (600, alice)`, for listings
+    pub fn describe(&self) -> String {
+        let target = if Path::new(&self.target).is_absolute() || self.target.starts_with('~') {
+            self.target.clone()
+        } else {
+            format!("~/{}", self.target)
+        };
+        let details: Vec<&str> = [self.mode.as_deref(), self.owner.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect();
+        if details.is_empty() {
+            target
+        } else {
+            format!("{target} ({})", details.join(", "))
+        }
+    }
+}
+
+/// The sidecar for the secret stored at `stored`
+pub fn path(stored: &Path) -> PathBuf {
+    let mut path = stored.as_os_str().to_owned();
+    path.push(SUFFIX);
+    PathBuf::from(path)
+}
+
+/// Whether `path` is a sidecar rather than a stored secret
+pub fn is_sidecar(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "meta")
+}
+
+/// The metadata for the secret stored at `stored`; none for secrets added
+/// before sidecars were written
+pub fn read(stored: &Path) -> Result<Option<SecretMeta>> {
+    let path = path(stored);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let meta =
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok(Some(meta))
+}
+
+/// Write the sidecar for the secret stored at `stored`
+pub fn write(stored: &Path, meta: &SecretMeta) -> Result<()> {
+    let path = path(stored);
+    fs::write(&path, toml::to_string(meta)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// The login name for `uid`, from `id`
+fn user_name(uid: u32) -> Option<String> {
+    let output = std::process::Command::new("id")
+        .args(["-nu", &uid.to_string()])
+        .output()
+        .ok()?;
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !name.is_empty()).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_read_and_describe() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join(".npmrc");
+        fs::write(&source, "token=abc\n").unwrap();
+        fs::set_permissions(&source, std::os::unix::fs::PermissionsExt::from_mode(0o600)).unwrap();
+
+        let stored = dir.path().join("abc123_.npmrc.enc");
+        assert_eq!(read(&stored).unwrap(), None);
+        let meta = SecretMeta::of(&source, ".npmrc");
+        assert_eq!(meta.mode(), Some(0o600));
+        write(&stored, &meta).unwrap();
+        assert!(is_sidecar(&path(&stored)));
+        assert!(!is_sidecar(&stored));
+        assert_eq!(read(&stored).unwrap(), Some(meta.clone()));
+
+        let bare = SecretMeta {
+            target: "/etc/hosts".to_string(),
+            mode: Some("644".to_string()),
+            owner: None,
+        };
+        assert_eq!(bare.describe(), "/etc/hosts (644)");
+        assert!(meta.describe().starts_with("~/.npmrc (600"));
+    }
+}
@@ -1,13 +1,16 @@
-use crate::config::Config;
-use anyhow::Result;
+use crate::config::{Config, SecretsBackend};
+use crate::environment::{self, Effect};
+use crate::error::OrdinatorError;
+use anyhow::{Context, Result};
 use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::io::{BufRead, BufReader};
 use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
-use tracing::info;
+use tracing::{debug, info};
 use walkdir::WalkDir;
 
 /// Secrets manager using SOPS and age
@@ -147,7 +150,7 @@ impl SecretsManager {
         let base_dir = &self.base_dir;
         for entry in WalkDir::new(base_dir).into_iter().filter_map(|e| e.ok()) {
             let path = entry.path();
-            if path.is_file() {
+            if path.is_file() && !crate::secret_meta::is_sidecar(path) {
                 let rel = path.strip_prefix(base_dir).unwrap_or(path);
                 let rel_str = rel.to_string_lossy();
                 let encrypt_match = encrypt_set.is_match(&*rel_str);
@@ -164,68 +167,20 @@ impl SecretsManager {
         Ok(results)
     }
 
-    /// Check if a file contains plaintext secrets
+    /// Check if a file contains plaintext secrets, honoring `[secrets.scan]`
+    /// rules and allow comments
     #[allow(dead_code)]
     pub fn check_for_plaintext_secrets(&self, file_path: &std::path::Path) -> Result<bool> {
         if !file_path.exists() || !file_path.is_file() {
             return Ok(false);
         }
 
-        // Skip binary files
-        if let Ok(content) = fs::read_to_string(file_path) {
-            if content.contains('\0') {
-                return Ok(false); // Binary file
-            }
-
-            // Check for common secret patterns - check in order of specificity
-            let secret_patterns = [
-                // API keys - must be checked before generic token
-                (r"(?i)api[_-]?key\s*[:=]\s*[a-zA-Z0-9_-]{20,}", "API Key"),
-                // OAuth and JWT tokens - specific token types
-                (
-                    r"(?i)oauth[_-]?token\s*[:=]\s*[a-zA-Z0-9]{20,}",
-                    "OAuth Token",
-                ),
-                (r"(?i)jwt[_-]?token\s*[:=]\s*[a-zA-Z0-9]{20,}", "JWT Token"),
-                // AWS credentials
-                (
-                    r"(?i)aws_access_key_id\s*[:=]\s*[A-Z0-9]{20}",
-                    "AWS Access Key",
-                ),
-                (
-                    r"(?i)aws_secret_access_key\s*[:=]\s*[A-Za-z0-9/+=]{40}",
-                    "AWS Secret Key",
-                ),
-                // Database credentials
-                (r"(?i)database_url\s*[:=]\s*[a-zA-Z]+://", "Database URL"),
-                (
-                    r"(?i)db_password\s*[:=]\s*[a-zA-Z0-9!@#$%^&*]{8,}",
-                    "Database Password",
-                ),
-                // Generic patterns - check these last
-                (r"(?i)token\s*[:=]\s*[a-zA-Z0-9]{20,}", "Token"),
-                (r"(?i)secret\s*[:=]\s*[a-zA-Z0-9]{20,}", "Secret"),
-                (r"(?i)password\s*[:=]\s*[a-zA-Z0-9!@#$%^&*]{8,}", "Password"),
-                // SSH private keys
-                (r"-----BEGIN.*PRIVATE KEY-----", "SSH Private Key"),
-                // Private keys and certificates
-                (r"-----BEGIN.*PRIVATE KEY-----", "Private Key"),
-                (r"-----BEGIN.*CERTIFICATE-----", "Certificate"),
-                // Generic high-entropy strings (potential secrets)
-                (r"[a-zA-Z0-9]{32,}", "High-entropy string"),
-            ];
-
-            for (pattern, secret_type) in &secret_patterns {
-                if let Ok(regex) = regex::Regex::new(pattern) {
-                    if regex.is_match(&content) {
-                        info!("Potential {} found in file: {:?}", secret_type, file_path);
-                        return Ok(true);
-                    }
-                }
-            }
+        let scanner = crate::scan::Scanner::shared(&self.config.secrets.scan)?;
+        let findings = scanner.scan_file(&file_path.to_string_lossy(), file_path);
+        if let Some(finding) = findings.first() {
+            info!("Potential {} found in file: {:?}", finding.rule, file_path);
         }
-
-        Ok(false)
+        Ok(!findings.is_empty())
     }
 
     /// Get detailed information about secrets found in a file
@@ -235,66 +190,9 @@ impl SecretsManager {
             return Ok(vec![]);
         }
 
-        let mut found_types = Vec::new();
-
-        // Skip binary files
-        if let Ok(content) = fs::read_to_string(file_path) {
-            if content.contains('\0') {
-                return Ok(vec![]); // Binary file
-            }
-
-            println!("[DEBUG] Scanning file: {file_path:?}");
-            println!("[DEBUG] Content: {content}");
-
-            // Check for common secret patterns
-            let secret_patterns = [
-                // API keys and tokens
-                (r"(?i)api[_-]?key\s*[:=]\s*[a-zA-Z0-9_-]{20,}", "API Key"),
-                (r"(?i)token\s*[:=]\s*[a-zA-Z0-9]{20,}", "Token"),
-                (r"(?i)secret\s*[:=]\s*[a-zA-Z0-9]{20,}", "Secret"),
-                (r"(?i)password\s*[:=]\s*[a-zA-Z0-9!@#$%^&*]{8,}", "Password"),
-                // SSH private keys
-                (r"-----BEGIN.*PRIVATE KEY-----", "SSH Private Key"),
-                // AWS credentials
-                (
-                    r"(?i)aws_access_key_id\s*[:=]\s*[A-Z0-9]{20}",
-                    "AWS Access Key",
-                ),
-                (
-                    r"(?i)aws_secret_access_key\s*[:=]\s*[A-Za-z0-9/+=]{40}",
-                    "AWS Secret Key",
-                ),
-                // Database credentials
-                (r"(?i)database_url\s*[:=]\s*[a-zA-Z]+://", "Database URL"),
-                (
-                    r"(?i)db_password\s*[:=]\s*[a-zA-Z0-9!@#$%^&*]{8,}",
-                    "Database Password",
-                ),
-                // OAuth and JWT tokens
-                (
-                    r"(?i)oauth[_-]?token\s*[:=]\s*[a-zA-Z0-9]{20,}",
-                    "OAuth Token",
-                ),
-                (r"(?i)jwt[_-]?token\s*[:=]\s*[a-zA-Z0-9]{20,}", "JWT Token"),
-                // Private keys and certificates
-                (r"-----BEGIN.*PRIVATE KEY-----", "Private Key"),
-                (r"-----BEGIN.*CERTIFICATE-----", "Certificate"),
-                // Generic high-entropy strings (potential secrets)
-                (r"[a-zA-Z0-9]{32,}", "High-entropy string"),
-            ];
-
-            for (pattern, secret_type) in &secret_patterns {
-                if let Ok(regex) = regex::Regex::new(pattern) {
-                    if regex.is_match(&content) {
-                        println!("[DEBUG] Matched pattern: {pattern} for type: {secret_type}");
-                        found_types.push(secret_type.to_string());
-                    } else {
-                        println!("[DEBUG] No match for pattern: {pattern}");
-                    }
-                }
-            }
-        }
-
+        let scanner = crate::scan::Scanner::shared(&self.config.secrets.scan)?;
+        let found_types = scanner.matching_rules(file_path);
+        debug!("Secret types in {}: {:?}", file_path.display(), found_types);
         Ok(found_types)
     }
 
@@ -306,21 +204,21 @@ impl SecretsManager {
 }
 
 pub fn check_sops_and_age() -> anyhow::Result<()> {
-    let sops = which::which("sops").map_err(|_| anyhow::anyhow!(
-        "SOPS is not installed or not found in PATH.\nInstall it: https://github.com/mozilla/sops#downloads"
-    ))?;
-    let age = which::which("age").map_err(|_| anyhow::anyhow!(
-        "age is not installed or not found in PATH.\nInstall it: https://github.com/FiloSottile/age#installation"
-    ))?;
-    println!("Found sops at: {}", sops.display());
-    println!("Found age at: {}", age.display());
+    let sops = which::which("sops").map_err(|_| OrdinatorError::SopsNotInstalled)?;
+    let age = which::which("age").map_err(|_| OrdinatorError::AgeNotInstalled)?;
+    debug!("Found sops at: {}", sops.display());
+    debug!("Found age at: {}", age.display());
     Ok(())
 }
 
 /// Set up SOPS and age for secrets management
 pub fn setup_sops_and_age(profile: &str, force: bool) -> anyhow::Result<()> {
     info!("Setting up SOPS and age for profile: {}", profile);
+    let native = Config::from_file_or_default()?.secrets.backend == SecretsBackend::Native;
     match check_sops_and_age() {
+        _ if native => {
+            println!("✅ Using the native age backend; SOPS and age aren't needed");
+        }
         Ok(()) => {
             println!("✅ SOPS and age are already installed");
         }
@@ -339,7 +237,11 @@ pub fn setup_sops_and_age(profile: &str, force: bool) -> anyhow::Result<()> {
     let age_key_path = generate_age_key(&config_base, profile, force)?;
     let sops_config_path = create_sops_config(profile, &age_key_path, force)?;
     update_ordinator_config(profile, &age_key_path, &sops_config_path)?;
-    println!("✅ SOPS and age setup complete for profile: {profile}");
+    if native {
+        println!("✅ Native age setup complete for profile: {profile}");
+    } else {
+        println!("✅ SOPS and age setup complete for profile: {profile}");
+    }
     println!("   Age key: {}", age_key_path.display());
     println!("   SOPS config: {}", sops_config_path.display());
     Ok(())
@@ -351,15 +253,14 @@ fn install_sops_and_age() -> anyhow::Result<()> {
 
     // Check if Homebrew is installed
     if which::which("brew").is_err() {
-        return Err(anyhow::anyhow!(
-            "Homebrew is not installed. Please install Homebrew first: https://brew.sh"
-        ));
+        return Err(OrdinatorError::BrewNotInstalled.into());
     }
 
     // Install SOPS and age
-    let status = Command::new("brew")
-        .args(["install", "sops", "age"])
-        .status()?;
+    let status = environment::status(
+        Command::new("brew").args(["install", "sops", "age"]),
+        Effect::Changes,
+    )?;
 
     if !status.success() {
         return Err(anyhow::anyhow!(
@@ -405,15 +306,20 @@ fn generate_age_key(
         return Ok(key_path);
     }
     println!("Generating age key for profile: {profile}");
-    let output = Command::new("age-keygen")
-        .arg("-o")
-        .arg(&key_path)
-        .output()?;
-    if !output.status.success() {
-        return Err(anyhow::anyhow!(
-            "Failed to generate age key: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
+    if Config::from_file_or_default()?.secrets.backend == SecretsBackend::Native {
+        // Same format as age-keygen, without needing it installed
+        fs::write(&key_path, crate::age::generate_identity()?)?;
+    } else {
+        let output = environment::output(
+            Command::new("age-keygen").arg("-o").arg(&key_path),
+            Effect::Changes,
+        )?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "Failed to generate age key: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
     }
     fs::set_permissions(&key_path, fs::Permissions::from_mode(0o600))?;
     println!("✅ Age key generated: {}", key_path.display());
@@ -495,15 +401,246 @@ fn create_sops_config(
             )
         })?;
 
-    // Create SOPS configuration
-    let sops_config = format!(r#"creation_rules:\n  - age: >-\n      {public_key}\n"#);
-
-    fs::write(&sops_config_path, sops_config)?;
+    // Encrypt for the profile's key plus any configured team recipients
+    let mut recipients = vec![public_key.to_string()];
+    if let Ok(config) = Config::from_file_or_default() {
+        for recipient in config.secrets.recipients {
+            if !recipients.contains(&recipient) {
+                recipients.push(recipient);
+            }
+        }
+    }
+    fs::write(&sops_config_path, sops_config_content(&recipients))?;
 
     println!("✅ SOPS config created: {}", sops_config_path.display());
     Ok(sops_config_path)
 }
 
+/// Public key from an age key file written by `age-keygen`
+fn read_age_public_key(age_key_path: &Path) -> anyhow::Result<String> {
+    // A key kept in the Keychain or behind a passphrase leaves its public key beside it
+    let public_key_path = crate::age_key::public_key_path(age_key_path);
+    let readable = if !age_key_path.exists() && public_key_path.exists() {
+        public_key_path.as_path()
+    } else {
+        age_key_path
+    };
+    let content = fs::read_to_string(readable)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", age_key_path.display(), e))?;
+    content
+        .lines()
+        .find_map(|line| {
+            if line.starts_with("# public key: ") {
+                Some(line.trim_start_matches("# public key: ").trim().to_string())
+            } else if line.starts_with("age1") {
+                Some(line.trim().to_string())
+            } else {
+                None
+            }
+        })
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Could not find age public key in {}",
+                age_key_path.display()
+            )
+        })
+}
+
+/// Check that a string is an age public key (`age1` followed by 58 bech32 characters)
+pub fn validate_age_recipient(key: &str) -> anyhow::Result<()> {
+    const BECH32_CHARS: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+    let valid = key.len() == 62
+        && key.starts_with("age1")
+        && key[4..].chars().all(|c| BECH32_CHARS.contains(c));
+    if !valid {
+        return Err(OrdinatorError::InvalidRecipient(key.to_string()).into());
+    }
+    Ok(())
+}
+
+/// Every age public key secrets are encrypted for: the configured age key's
+/// own public key, followed by `[secrets].recipients`
+pub fn secrets_recipients(config: &Config) -> Vec<String> {
+    let mut recipients = Vec::new();
+    if let Some(key_file) = &config.secrets.age_key_file {
+        if let Ok(public_key) = read_age_public_key(key_file) {
+            recipients.push(public_key);
+        }
+    }
+    for recipient in &config.secrets.recipients {
+        if !recipients.contains(recipient) {
+            recipients.push(recipient.clone());
+        }
+    }
+    recipients
+}
+
+/// SOPS creation rules that encrypt for every recipient
+pub fn sops_config_content(recipients: &[String]) -> String {
+    let mut content = String::from("creation_rules:\n  - age: >-\n");
+    for (index, recipient) in recipients.iter().enumerate() {
+        let separator = if index + 1 < recipients.len() {
+            ","
+        } else {
+            ""
+        };
+        content.push_str(&format!("      {recipient}{separator}\n"));
+    }
+    content
+}
+
+/// Rewrite the SOPS config from the current recipients list. Uses the
+/// configured `sops_config` path, or the default profile's location.
+pub fn write_sops_config(config: &Config) -> anyhow::Result<PathBuf> {
+    let recipients = secrets_recipients(config);
+    if recipients.is_empty() {
+        return Err(OrdinatorError::NoRecipients.into());
+    }
+    let sops_config_path = match &config.secrets.sops_config {
+        Some(path) => path.clone(),
+        None => {
+            let ordinator_config = std::env::var("ORDINATOR_CONFIG_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| {
+                    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+                    PathBuf::from(home).join(".config").join("ordinator")
+                });
+            ordinator_config.join(".sops.yaml")
+        }
+    };
+    if let Some(parent) = sops_config_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&sops_config_path, sops_config_content(&recipients))?;
+    info!(
+        "Wrote SOPS config for {} recipient(s): {}",
+        recipients.len(),
+        sops_config_path.display()
+    );
+    Ok(sops_config_path)
+}
+
+/// Encrypted secrets stored under `secrets/` (or `secrets/<profile>/`) in the dotfiles repository
+pub fn stored_secret_files(base_dir: &Path, profile: Option<&str>) -> Vec<PathBuf> {
+    let mut root = base_dir.join("secrets");
+    if let Some(profile) = profile {
+        root = root.join(profile);
+    }
+    let mut files: Vec<PathBuf> = WalkDir::new(root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .filter(|path| !crate::secret_meta::is_sidecar(path))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Re-encrypt a secret's data key for the recipients in `sops_config`
+/// (`sops updatekeys`), reading it as `format`. Needs a key that can
+/// decrypt the file.
+pub fn update_keys_with_sops(file: &Path, format: &str, sops_config: &Path) -> anyhow::Result<()> {
+    let config = crate::config::Config::from_file_or_default()?;
+    let mut command = Command::new("sops");
+    command
+        .arg("--config")
+        .arg(sops_config)
+        .args(["updatekeys", "--yes", "--input-type", format])
+        .arg(file);
+    crate::age_key::set_sops_key(&mut command, &config.secrets)?;
+    let output = environment::output(&mut command, Effect::Changes)?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "sops failed to update keys for {}: {}",
+            file.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    info!("Updated recipients of {}", file.display());
+    Ok(())
+}
+
+/// Re-encrypt an age file for the current recipients with the native
+/// backend, as [`update_keys_with_sops`] does for SOPS files
+fn update_keys_natively(config: &Config, file: &Path) -> anyhow::Result<()> {
+    let plaintext = decrypt_secret(file)?;
+    let ciphertext = encrypt_native(config, &plaintext, file)?;
+    let staged = file.with_extension("ordinator-tmp");
+    fs::write(&staged, ciphertext)?;
+    fs::rename(&staged, file).with_context(|| format!("Failed to replace {}", file.display()))?;
+    info!("Updated recipients of {}", file.display());
+    Ok(())
+}
+
+fn is_age_file(file: &Path) -> bool {
+    fs::read(file).is_ok_and(|content| crate::age::is_age(&content))
+}
+
+/// Decrypt a SOPS or age file stored as `format` into memory with a
+/// specific age key
+fn decrypt_with_age_key(file: &Path, format: &str, age_key_file: &Path) -> anyhow::Result<Vec<u8>> {
+    if is_age_file(file) {
+        let identity = fs::read_to_string(age_key_file)?;
+        let content = fs::read(file)?;
+        return crate::age::decrypt(&content, &identity).map_err(|e| {
+            OrdinatorError::Age {
+                action: "decrypt",
+                path: file.to_path_buf(),
+                reason: e.to_string(),
+            }
+            .into()
+        });
+    }
+    let output = environment::output(
+        Command::new("sops")
+            .arg("--decrypt")
+            .args(["--input-type", format, "--output-type", format])
+            .arg(file)
+            .env("SOPS_AGE_KEY_FILE", age_key_file),
+        Effect::Reads,
+    )?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "sops failed to decrypt {}: {}",
+            file.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// Encrypt plaintext as `format` for the recipients in `sops_config`, named
+/// `file_name` in errors
+fn encrypt_for_sops_config(
+    plaintext: &[u8],
+    file_name: &std::ffi::OsStr,
+    format: &str,
+    sops_config: &Path,
+) -> anyhow::Result<Vec<u8>> {
+    let temp_dir = tempfile::tempdir()?;
+    let temp_file = temp_dir.path().join(file_name);
+    fs::write(&temp_file, plaintext)?;
+    fs::set_permissions(&temp_file, fs::Permissions::from_mode(0o600))?;
+    let output = environment::output(
+        Command::new("sops")
+            .arg("--config")
+            .arg(sops_config)
+            .arg("--encrypt")
+            .args(["--input-type", format, "--output-type", format])
+            .arg(&temp_file),
+        Effect::Reads,
+    )?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "sops failed to encrypt {}: {}",
+            file_name.to_string_lossy(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
 /// Update ordinator.toml with secrets configuration
 fn update_ordinator_config(
     _profile: &str,
@@ -552,9 +689,9 @@ pub fn encrypt_file_with_sops(file: &str) -> anyhow::Result<String> {
 
     // Load configuration to get age key file
     let config = crate::config::Config::from_file_or_default()?;
-    let age_key_file = config.secrets.age_key_file.ok_or_else(|| {
-        anyhow::anyhow!("No age key file configured. Run 'ordinator secrets setup' first.")
-    })?;
+    if config.secrets.age_key_file.is_none() {
+        return Err(OrdinatorError::NoAgeKey.into());
+    }
 
     let input_path = Path::new(file);
     if !input_path.exists() {
@@ -595,14 +732,18 @@ pub fn encrypt_file_with_sops(file: &str) -> anyhow::Result<String> {
 
     // Call sops to encrypt with age key file set
     let mut command = Command::new("sops");
+    // Use ordinator's SOPS config so every recipient can decrypt
+    if let Some(sops_config) = config.secrets.sops_config.as_ref().filter(|p| p.exists()) {
+        command.arg("--config").arg(sops_config);
+    }
     command
         .arg("--encrypt")
         .arg(&file)
         .arg("--output")
-        .arg(&output_path)
-        .env("SOPS_AGE_KEY_FILE", age_key_file);
+        .arg(&output_path);
+    crate::age_key::set_sops_key(&mut command, &config.secrets)?;
 
-    let status = command.status()?;
+    let status = environment::status(&mut command, Effect::Changes)?;
     if !status.success() {
         return Err(anyhow::anyhow!("sops failed to encrypt file: {}", file));
     }
@@ -619,9 +760,9 @@ pub fn decrypt_file_with_sops(file: &str) -> anyhow::Result<()> {
 
     // Load configuration to get age key file
     let config = crate::config::Config::from_file_or_default()?;
-    let age_key_file = config.secrets.age_key_file.ok_or_else(|| {
-        anyhow::anyhow!("No age key file configured. Run 'ordinator secrets setup' first.")
-    })?;
+    if config.secrets.age_key_file.is_none() {
+        return Err(OrdinatorError::NoAgeKey.into());
+    }
 
     let input_path = Path::new(file);
     if !input_path.exists() {
@@ -630,12 +771,10 @@ pub fn decrypt_file_with_sops(file: &str) -> anyhow::Result<()> {
 
     // Call sops to decrypt with age key file set
     let mut command = Command::new("sops");
-    command
-        .arg("--decrypt")
-        .arg(file)
-        .env("SOPS_AGE_KEY_FILE", age_key_file);
+    command.arg("--decrypt").arg(file);
+    crate::age_key::set_sops_key(&mut command, &config.secrets)?;
 
-    let status = command.status()?;
+    let status = environment::status(&mut command, Effect::Reads)?;
     if !status.success() {
         return Err(anyhow::anyhow!("sops failed to decrypt file: {}", file));
     }
@@ -643,13 +782,13 @@ pub fn decrypt_file_with_sops(file: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
-/// Returns true if the file appears to be SOPS-encrypted (by header)
+/// Returns true if the file appears to be encrypted: SOPS (by header) or age
 pub fn is_file_encrypted(path: &std::path::Path) -> bool {
     if let Ok(file) = fs::File::open(path) {
         let reader = BufReader::new(file);
         for (i, line) in reader.lines().enumerate() {
             if let Ok(l) = line {
-                if l.trim().starts_with("sops:") {
+                if l.trim().starts_with("sops:") || (i == 0 && crate::age::is_age(l.as_bytes())) {
                     return true;
                 }
             }
@@ -681,6 +820,16 @@ pub fn decrypt_content_with_sops(content: &str) -> anyhow::Result<String> {
     Ok(decrypted_content)
 }
 
+/// Encrypt content in memory with the configured `[secrets] backend`
+pub fn encrypt_content(content: &str) -> Result<String> {
+    let config = Config::from_file_or_default()?;
+    if config.secrets.backend == SecretsBackend::Native {
+        let ciphertext = encrypt_native(&config, content.as_bytes(), Path::new("secret"))?;
+        return Ok(String::from_utf8(ciphertext)?);
+    }
+    encrypt_content_with_sops(content)
+}
+
 /// Encrypt content in memory using SOPS
 pub fn encrypt_content_with_sops(content: &str) -> anyhow::Result<String> {
     // Create a temporary file with the content
@@ -701,6 +850,477 @@ pub fn encrypt_content_with_sops(content: &str) -> anyhow::Result<String> {
     Ok(encrypted_content)
 }
 
+/// Encrypt `source` into `secrets/<profile>/<hash>_<name>.enc` and track it
+/// as `tracked_path` in `profile`. Returns the stored file's name.
+pub fn store_secret(
+    config: &mut Config,
+    base_dir: &Path,
+    profile: &str,
+    tracked_path: &str,
+    source: &Path,
+) -> Result<String> {
+    config.add_secret_to_profile(profile, tracked_path.to_string())?;
+    let file_content = fs::read_to_string(source)?;
+    let encrypted_content = encrypt_content(&file_content)?;
+    let hash = crate::utils::generate_file_hash(tracked_path);
+    let filename = source
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let hash_filename = format!("{hash}_{filename}.enc");
+    let secrets_dir = base_dir.join("secrets").join(profile);
+    fs::create_dir_all(&secrets_dir)?;
+    let stored = secrets_dir.join(&hash_filename);
+    fs::write(&stored, encrypted_content)?;
+    crate::secret_meta::write(
+        &stored,
+        &crate::secret_meta::SecretMeta::of(source, tracked_path),
+    )?;
+    if let Some(profile) = config.get_profile_mut(profile) {
+        profile
+            .file_mappings
+            .insert(hash_filename.clone(), tracked_path.to_string());
+    }
+    Ok(hash_filename)
+}
+
+/// Where the encrypted copy of `secret` tracked in `profile` is stored: the
+/// `secrets/<profile>/` file it's mapped to by `secrets add`, else the one
+/// whose metadata names it as the target, else the file layout `apply`
+/// reads from
+pub fn stored_secret_path(
+    config: &Config,
+    base_dir: &Path,
+    profile: &str,
+    secret: &str,
+) -> Result<PathBuf> {
+    let mapped = config.get_profile(profile).and_then(|p| {
+        p.file_mappings
+            .iter()
+            .filter(|(stored, tracked)| *tracked == secret && stored.ends_with(".enc"))
+            .map(|(stored, _)| base_dir.join("secrets").join(profile).join(stored))
+            .find(|path| path.exists())
+    });
+    if let Some(path) = mapped {
+        return Ok(path);
+    }
+    let described = stored_secret_files(base_dir, Some(profile))
+        .into_iter()
+        .find(|stored| {
+            crate::secret_meta::read(stored)
+                .ok()
+                .flatten()
+                .is_some_and(|meta| meta.target == secret)
+        });
+    match described {
+        Some(path) => Ok(path),
+        None => config.get_source_file_path(profile, secret),
+    }
+}
+
+/// The `sops` input and output type for a stored secret. Files `secrets add`
+/// writes end in `.enc` and hold sops' binary format.
+fn sops_format(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("yaml" | "yml") => "yaml",
+        Some("json") => "json",
+        Some("env") => "dotenv",
+        Some("ini") => "ini",
+        _ => "binary",
+    }
+}
+
+/// `sops` with ordinator's SOPS config and age key, reading and writing `format`
+fn sops_command(format: &str) -> Result<Command> {
+    check_sops_and_age()?;
+    let config = Config::from_file_or_default()?;
+    if config.secrets.age_key_file.is_none() {
+        return Err(OrdinatorError::NoAgeKey.into());
+    }
+    let mut command = Command::new("sops");
+    if let Some(sops_config) = config.secrets.sops_config.as_ref().filter(|p| p.exists()) {
+        command.arg("--config").arg(sops_config);
+    }
+    command.args(["--input-type", format, "--output-type", format]);
+    crate::age_key::set_sops_key(&mut command, &config.secrets)?;
+    Ok(command)
+}
+
+/// A directory only the current user can read, on a RAM-backed filesystem
+/// when there is one, so plaintext being edited never reaches the disk
+pub fn private_temp_dir() -> Result<tempfile::TempDir> {
+    let ram_dirs = [
+        std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from),
+        Some(PathBuf::from("/dev/shm")),
+    ];
+    let parent = ram_dirs
+        .into_iter()
+        .flatten()
+        .find(|dir| dir.is_dir())
+        .unwrap_or_else(std::env::temp_dir);
+    let dir = tempfile::Builder::new()
+        .prefix("ordinator-secret-")
+        .tempdir_in(parent)?;
+    fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o700))?;
+    Ok(dir)
+}
+
+/// Write `plaintext` to a 0600 file named `name`, open it in `editor`, and
+/// return the new content if it was changed. The file is overwritten and
+/// removed afterwards, whether or not the editor succeeded.
+pub fn edit_plaintext(name: &str, plaintext: &str, editor: &str) -> Result<Option<String>> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let dir = private_temp_dir()?;
+    let path = dir.path().join(name);
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)?
+        .write_all(plaintext.as_bytes())?;
+
+    let status = Command::new(editor).arg(&path).status();
+    let edited = fs::read_to_string(&path);
+    if let Ok(len) = fs::metadata(&path).map(|meta| meta.len()) {
+        let _ = fs::write(&path, vec![0u8; len as usize]);
+    }
+    drop(dir);
+
+    let status = status.with_context(|| format!("Failed to run editor '{editor}'"))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "Editor exited with status: {status}. The secret was not changed."
+        ));
+    }
+    let edited = edited?;
+    Ok((edited != plaintext).then_some(edited))
+}
+
+/// Decrypt a stored secret in memory; sops writes the plaintext to a pipe,
+/// never to disk
+pub fn decrypt_stored(stored: &Path) -> Result<String> {
+    String::from_utf8(decrypt_secret(stored)?)
+        .with_context(|| format!("{} is not a text file", stored.display()))
+}
+
+/// Decrypt a stored secret to its plaintext bytes without writing them anywhere
+pub fn decrypt_secret(stored: &Path) -> Result<Vec<u8>> {
+    if let Some(plaintext) = decrypt_native(stored)? {
+        return Ok(plaintext);
+    }
+    let mut command = sops_command(sops_format(stored))?;
+    command.arg("--decrypt").arg(stored);
+    let output = environment::output(&mut command, Effect::Reads).context("Failed to run sops")?;
+    if !output.status.success() {
+        return Err(OrdinatorError::Sops {
+            action: "decrypt",
+            path: stored.to_path_buf(),
+            reason: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        }
+        .into());
+    }
+    Ok(output.stdout)
+}
+
+/// Encrypt `source` into `dest`, for a tracked file with `encrypt = true`.
+/// The whole file is encrypted as binary data, whatever its name; sops
+/// writes the ciphertext to a pipe, so no other copy is made.
+pub fn encrypt_tracked_file(source: &Path, dest: &Path) -> Result<()> {
+    let config = Config::from_file_or_default()?;
+    let ciphertext = if config.secrets.backend == SecretsBackend::Native {
+        let plaintext =
+            fs::read(source).with_context(|| format!("Failed to read {}", source.display()))?;
+        encrypt_native(&config, &plaintext, source)?
+    } else {
+        let mut command = sops_command("binary")?;
+        command.arg("--encrypt").arg(source);
+        let output =
+            environment::output(&mut command, Effect::Reads).context("Failed to run sops")?;
+        if !output.status.success() {
+            return Err(OrdinatorError::Sops {
+                action: "encrypt",
+                path: source.to_path_buf(),
+                reason: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            }
+            .into());
+        }
+        output.stdout
+    };
+    fs::write(dest, ciphertext).with_context(|| format!("Failed to write {}", dest.display()))
+}
+
+/// Decrypt a tracked file stored with [`encrypt_tracked_file`], in memory
+pub fn decrypt_tracked_file(stored: &Path) -> Result<Vec<u8>> {
+    if let Some(plaintext) = decrypt_native(stored)? {
+        return Ok(plaintext);
+    }
+    let mut command = sops_command("binary")?;
+    command.arg("--decrypt").arg(stored);
+    let output = environment::output(&mut command, Effect::Reads).context("Failed to run sops")?;
+    if !output.status.success() {
+        return Err(OrdinatorError::Sops {
+            action: "decrypt",
+            path: stored.to_path_buf(),
+            reason: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        }
+        .into());
+    }
+    Ok(output.stdout)
+}
+
+/// The configured age key's contents, for the native backend
+fn native_identity(config: &Config) -> Result<String> {
+    let key_file = config
+        .secrets
+        .age_key_file
+        .as_ref()
+        .ok_or(OrdinatorError::NoAgeKey)?;
+    crate::age_key::read(key_file, config.secrets.age_key_storage)
+}
+
+/// Encrypt `plaintext` (read from `path`, for errors) with the native
+/// backend, for every recipient
+fn encrypt_native(config: &Config, plaintext: &[u8], path: &Path) -> Result<Vec<u8>> {
+    let recipients = secrets_recipients(config);
+    if recipients.is_empty() {
+        return Err(OrdinatorError::NoAgeKey.into());
+    }
+    crate::age::encrypt(plaintext, &recipients).map_err(|e| {
+        OrdinatorError::Age {
+            action: "encrypt",
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        }
+        .into()
+    })
+}
+
+/// Decrypt `stored` in memory if it's an age file, whichever backend is
+/// configured; `None` for a SOPS file
+fn decrypt_native(stored: &Path) -> Result<Option<Vec<u8>>> {
+    let content = match fs::read(stored) {
+        Ok(content) if crate::age::is_age(&content) => content,
+        _ => return Ok(None),
+    };
+    let config = Config::from_file_or_default()?;
+    let identity = native_identity(&config)?;
+    crate::age::decrypt(&content, &identity)
+        .map(Some)
+        .map_err(|e| {
+            OrdinatorError::Age {
+                action: "decrypt",
+                path: stored.to_path_buf(),
+                reason: e.to_string(),
+            }
+            .into()
+        })
+}
+
+/// Decrypt a stored secret, open it in `editor`, and re-encrypt it in place
+/// if it changed. Returns whether it was re-encrypted.
+pub fn edit_secret(stored: &Path, name: &str, editor: &str) -> Result<bool> {
+    let format = sops_format(stored);
+    let plaintext = decrypt_stored(stored)?;
+
+    let Some(edited) = edit_plaintext(name, &plaintext, editor)? else {
+        return Ok(false);
+    };
+    write_stored(stored, format, name, &edited)?;
+    Ok(true)
+}
+
+/// Encrypt `plaintext` as `format` and write it to `stored`, replacing what
+/// was there in one step so a failed write can't truncate it
+fn write_stored(stored: &Path, format: &str, name: &str, plaintext: &str) -> Result<()> {
+    let backend = Config::from_file_or_default()?.secrets.backend;
+    let ciphertext = encrypt_with(backend, format, name, plaintext.as_bytes())
+        .map_err(|e| anyhow::anyhow!("{e}. The stored secret was not changed."))?;
+    let staged = stored.with_extension("ordinator-tmp");
+    fs::write(&staged, &ciphertext)?;
+    fs::rename(&staged, stored).with_context(|| format!("Failed to replace {}", stored.display()))
+}
+
+/// Encrypt `plaintext` with `backend`. sops reads it as `format`, from the
+/// same kind of private file `edit` uses, named `name`.
+fn encrypt_with(
+    backend: SecretsBackend,
+    format: &str,
+    name: &str,
+    plaintext: &[u8],
+) -> Result<Vec<u8>> {
+    if backend == SecretsBackend::Native {
+        let config = Config::from_file_or_default()?;
+        return encrypt_native(&config, plaintext, Path::new(name));
+    }
+    let dir = private_temp_dir()?;
+    let input = dir.path().join(name);
+    fs::write(&input, plaintext)?;
+    fs::set_permissions(&input, fs::Permissions::from_mode(0o600))?;
+    let mut command = sops_command(format)?;
+    command.arg("--encrypt").arg(&input);
+    let output = environment::output(&mut command, Effect::Reads).context("Failed to run sops")?;
+    let _ = fs::write(&input, vec![0u8; plaintext.len()]);
+    drop(dir);
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "sops failed to encrypt {}: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// File in `secrets/<profile>/` holding the profile's secret values
+/// (`secrets set`), encrypted by sops as a dotenv file
+pub const SECRET_VALUES_FILE: &str = "values.env";
+
+/// Where `profile`'s secret values are stored
+pub fn secret_values_path(base_dir: &Path, profile: &str) -> PathBuf {
+    base_dir
+        .join("secrets")
+        .join(profile)
+        .join(SECRET_VALUES_FILE)
+}
+
+/// Check that `key` can name a secret value. Values become environment and
+/// template variables, so a key is letters, digits, and `_`, and doesn't
+/// start with a digit.
+pub fn validate_secret_key(key: &str) -> Result<()> {
+    let valid = key
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(OrdinatorError::InvalidSecretName(key.to_string()).into());
+    }
+    Ok(())
+}
+
+/// Decrypt `profile`'s secret values in memory; empty when it has none
+pub fn secret_values(base_dir: &Path, profile: &str) -> Result<BTreeMap<String, String>> {
+    let stored = secret_values_path(base_dir, profile);
+    if !stored.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let plaintext = decrypt_stored(&stored)
+        .with_context(|| format!("Failed to decrypt the secret values of profile '{profile}'"))?;
+    Ok(parse_secret_values(&plaintext))
+}
+
+fn parse_secret_values(plaintext: &str) -> BTreeMap<String, String> {
+    plaintext
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Set `key` to `value` in `profile`'s secret values, or remove it when
+/// `value` is `None`, and re-encrypt them. Returns whether anything changed.
+pub fn set_secret_value(
+    base_dir: &Path,
+    profile: &str,
+    key: &str,
+    value: Option<&str>,
+) -> Result<bool> {
+    validate_secret_key(key)?;
+    if value.is_some_and(|value| value.contains(['\n', '\r'])) {
+        return Err(anyhow::anyhow!(
+            "Secret values are a single line. Track a multi-line secret as a file with 'ordinator secrets add'."
+        ));
+    }
+    let mut values = secret_values(base_dir, profile)?;
+    let changed = match value {
+        Some(value) => values.insert(key.to_string(), value.to_string()).as_deref() != Some(value),
+        None => values.remove(key).is_some(),
+    };
+    if !changed {
+        return Ok(false);
+    }
+    let stored = secret_values_path(base_dir, profile);
+    if values.is_empty() {
+        fs::remove_file(&stored)?;
+        return Ok(true);
+    }
+    if let Some(parent) = stored.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let plaintext: String = values
+        .iter()
+        .map(|(key, value)| format!("{key}={value}\n"))
+        .collect();
+    write_stored(&stored, "dotenv", SECRET_VALUES_FILE, &plaintext)?;
+    Ok(true)
+}
+
+/// Add the secret values of `profiles`, in order, to `vars`, so a later
+/// profile's value overrides an earlier one and a secret value overrides a
+/// plain variable of the same name
+pub fn add_secret_values(
+    base_dir: &Path,
+    profiles: &[String],
+    vars: &mut HashMap<String, String>,
+) -> Result<()> {
+    for profile in profiles {
+        vars.extend(secret_values(base_dir, profile)?);
+    }
+    Ok(())
+}
+
+/// Delete a secret `apply` decrypted to disk. With `shred`, the contents are
+/// overwritten with random bytes and synced first. Copy-on-write filesystems
+/// such as APFS may keep the old blocks, so shredding is best effort.
+pub fn remove_decrypted_secret(path: &Path, shred: bool) -> Result<()> {
+    if shred {
+        let len = fs::metadata(path)?.len();
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .with_context(|| format!("Failed to open {} for shredding", path.display()))?;
+        let random = fs::File::open("/dev/urandom")?;
+        std::io::copy(&mut std::io::Read::take(random, len), &mut file)?;
+        file.sync_all()?;
+    }
+    fs::remove_file(path).with_context(|| format!("Failed to remove {}", path.display()))
+}
+
+/// Remove decrypted secrets, reporting each one. Returns the ones that were
+/// (or, in a dry run, would be) removed.
+pub fn remove_decrypted_secrets(
+    secrets: &[crate::journal::DecryptedSecret],
+    shred: bool,
+    dry_run: bool,
+    quiet: bool,
+) -> Vec<&crate::journal::DecryptedSecret> {
+    let verb = if shred { "shred" } else { "remove" };
+    let mut removed = Vec::new();
+    for secret in secrets {
+        let path = secret.path.display();
+        if dry_run {
+            eprintln!("Would {verb} decrypted secret: {path}");
+            removed.push(secret);
+            continue;
+        }
+        match remove_decrypted_secret(&secret.path, shred) {
+            Ok(()) => {
+                if !quiet {
+                    let done = if shred { "Shredded" } else { "Removed" };
+                    eprintln!("{done} decrypted secret: {path}");
+                }
+                removed.push(secret);
+            }
+            Err(e) => eprintln!("Error: Failed to {verb} decrypted secret {path}: {e:#}"),
+        }
+    }
+    removed
+}
+
 /// Check if an age key exists for the specified profile
 pub fn age_key_exists(profile: &str) -> bool {
     let ordinator_config = std::env::var("ORDINATOR_CONFIG_DIR")
@@ -718,7 +1338,7 @@ pub fn age_key_exists(profile: &str) -> bool {
     };
     let key_path = age_dir.join(key_filename);
 
-    key_path.exists()
+    crate::age_key::exists(&key_path)
 }
 
 /// Get the age key path for a profile
@@ -753,7 +1373,7 @@ pub fn check_key_rotation_needed(profile: &str) -> anyhow::Result<Option<String>
     // Get profile to check created_on timestamp
     let profile_config = config
         .get_profile(profile)
-        .ok_or_else(|| anyhow::anyhow!("Profile '{}' not found", profile))?;
+        .ok_or_else(|| OrdinatorError::ProfileNotFound(profile.to_string()))?;
 
     let created_on = match &profile_config.created_on {
         Some(timestamp) => {
@@ -814,7 +1434,8 @@ pub fn check_key_rotation_needed(profile: &str) -> anyhow::Result<Option<String>
     }
 }
 
-/// Rotate age keys for a profile, update SOPS config, and re-encrypt all secrets
+/// Rotate age keys for a profile, update SOPS config, and re-encrypt all of
+/// its encrypted files: stored secrets and tracked files with `encrypt = true`
 pub fn rotate_age_keys(profile: &str, backup_old_key: bool, _force: bool) -> anyhow::Result<()> {
     use std::fs;
     use std::path::PathBuf;
@@ -835,25 +1456,111 @@ pub fn rotate_age_keys(profile: &str, backup_old_key: bool, _force: bool) -> any
     let key_path = age_dir.join(&key_filename);
     let backup_path = age_dir.join(format!("{key_filename}.bak"));
 
-    // 2. Backup old key if requested
+    // 2. Decrypt every stored secret with the old key before touching anything
+    let (config, config_path) = crate::config::Config::load()?;
+    if !config.secrets.age_key_storage.is_file() {
+        return Err(anyhow::anyhow!(
+            "The age key is kept in {}; rotating keys needs a key file. Run 'ordinator age key export --output <file>' and 'ordinator age key import <file> --storage file --force' first.",
+            config.secrets.age_key_storage.description()
+        ));
+    }
+    let base_dir = config_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let secrets = encrypted_files(&config, &base_dir, Some(profile));
+    let native = config.secrets.backend == SecretsBackend::Native;
+    let old_key_path = if key_path.exists() {
+        Some(key_path.clone())
+    } else {
+        config.secrets.age_key_file.clone().filter(|p| p.exists())
+    };
+    let mut plaintexts = Vec::new();
+    if !secrets.is_empty() {
+        let old_key_path = old_key_path.as_ref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Cannot rotate keys for profile '{}': {} stored secret(s) but no current age key to decrypt them",
+                profile,
+                secrets.len()
+            )
+        })?;
+        if !native || !secrets.iter().all(|(file, _)| is_age_file(file)) {
+            check_sops_and_age()?;
+        }
+        for (file, format) in &secrets {
+            plaintexts.push(decrypt_with_age_key(file, format, old_key_path)?);
+        }
+    }
+    let old_key = match &old_key_path {
+        Some(path) if path == &key_path => Some(fs::read(path)?),
+        _ => None,
+    };
+
+    // 3. Backup old key if requested
     if key_path.exists() && backup_old_key {
         fs::copy(&key_path, &backup_path)?;
         println!("Backed up old key to: {}", backup_path.display());
     }
 
-    // 3. Generate new key (force overwrite)
+    // 4. Generate new key (force overwrite)
     let new_key_path = generate_age_key(&ordinator_config, profile, true)?;
     println!("Generated new age key: {}", new_key_path.display());
 
-    // 4. Update SOPS config
+    // 5. Update SOPS config
     let sops_config_path = create_sops_config(profile, &new_key_path, true)?;
     println!("Updated SOPS config: {}", sops_config_path.display());
+    let mut new_recipients = vec![read_age_public_key(&new_key_path)?];
+    for recipient in &config.secrets.recipients {
+        if !new_recipients.contains(recipient) {
+            new_recipients.push(recipient.clone());
+        }
+    }
+
+    // 6. Re-encrypt each secret for the new key into a staging file. If any
+    // fails, discard the staged files and put the old key back.
+    let mut staged = Vec::new();
+    for ((file, format), plaintext) in secrets.iter().zip(&plaintexts) {
+        let mut staging_path = file.as_os_str().to_owned();
+        staging_path.push(".rotating");
+        let staging_path = PathBuf::from(staging_path);
+        let ciphertext = if native {
+            crate::age::encrypt(plaintext, &new_recipients)
+        } else {
+            encrypt_for_sops_config(
+                plaintext,
+                file.file_name().unwrap_or_default(),
+                format,
+                &sops_config_path,
+            )
+        };
+        let result = ciphertext.and_then(|ciphertext| Ok(fs::write(&staging_path, ciphertext)?));
+        if let Err(e) = result {
+            let _ = fs::remove_file(&staging_path);
+            for path in &staged {
+                let _ = fs::remove_file(path);
+            }
+            if let Some(old_key) = &old_key {
+                fs::write(&key_path, old_key)?;
+                create_sops_config(profile, &key_path, true)?;
+            }
+            return Err(e.context(format!(
+                "Failed to re-encrypt {}; the previous key was restored and no secrets were changed",
+                file.display()
+            )));
+        }
+        staged.push(staging_path);
+    }
+
+    // 7. Swap the re-encrypted secrets in
+    for ((file, _), staging_path) in secrets.iter().zip(&staged) {
+        fs::rename(staging_path, file)?;
+    }
 
-    // 5. Update ordinator config
+    // 8. Update ordinator config
     update_ordinator_config(profile, &new_key_path, &sops_config_path)?;
     println!("Updated ordinator.toml with new key and SOPS config");
 
-    // 5.5. Update created_on timestamp in profile config
+    // 9. Update created_on timestamp in profile config
     let (mut config, config_path) = crate::config::Config::load()?;
     if let Some(profile_config) = config.get_profile_mut(profile) {
         use chrono::Utc;
@@ -863,116 +1570,225 @@ pub fn rotate_age_keys(profile: &str, backup_old_key: bool, _force: bool) -> any
         println!("✅ Updated profile '{profile}' with new created_on timestamp: {timestamp}");
     }
 
-    // 6. Re-encrypt all secrets for this profile
-    let (config, config_path) = crate::config::Config::load()?;
-    let base_dir = if let Some(parent) = config_path.parent() {
-        parent.to_path_buf()
-    } else {
-        PathBuf::from(".")
-    };
-    let manager = crate::secrets::SecretsManager::new(None, None, config, base_dir.clone());
-    let profile_files = manager
-        .config
-        .get_profile(profile)
-        .map(|p| p.files.clone())
-        .unwrap_or_default();
-    let mut updated = 0;
-    for tracked_file in profile_files {
-        if tracked_file.starts_with("secrets/") {
-            let abs_path = base_dir.join(&tracked_file);
-            if abs_path.exists() {
-                // Decrypt and re-encrypt
-                crate::secrets::decrypt_file_with_sops(abs_path.to_str().unwrap())?;
-                // Assume sops outputs to stdout or overwrites file; if not, adjust logic
-                // For now, just re-encrypt the file
-                crate::secrets::encrypt_file_with_sops(abs_path.to_str().unwrap())?;
-                updated += 1;
+    crate::rotation::RotationRecord::record(
+        &base_dir,
+        secrets.len(),
+        secrets_recipients(&config),
+        true,
+    )?;
+    println!(
+        "Re-encrypted {} secrets for profile '{profile}'",
+        secrets.len()
+    );
+    Ok(())
+}
+
+/// Re-encrypt every encrypted file (all, or one profile's) for the current
+/// recipients without generating a new key, and record it in the rotation
+/// record. Returns the files re-encrypted.
+pub fn reencrypt_secrets(
+    config: &mut Config,
+    config_path: &Path,
+    profile: Option<&str>,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let base_dir = config_path.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let secrets = encrypted_files(config, &base_dir, profile);
+    let sops_config = write_sops_config(config)?;
+    if config.secrets.sops_config.is_none() {
+        config.secrets.sops_config = Some(sops_config.clone());
+        config.save_to_file(config_path)?;
+    }
+    if !secrets.iter().all(|(file, _)| is_age_file(file)) {
+        check_sops_and_age()?;
+    }
+    for (file, format) in &secrets {
+        if is_age_file(file) {
+            update_keys_natively(config, file)?;
+        } else {
+            update_keys_with_sops(file, format, &sops_config)?;
+        }
+    }
+    crate::rotation::RotationRecord::record(
+        &base_dir,
+        secrets.len(),
+        secrets_recipients(config),
+        false,
+    )?;
+    Ok(secrets.into_iter().map(|(path, _)| path).collect())
+}
+
+/// Every encrypted file in the repository (or one profile's), with the sops
+/// format it's stored as: stored secrets, and tracked files with
+/// `encrypt = true`
+pub fn encrypted_files(
+    config: &Config,
+    base_dir: &Path,
+    profile: Option<&str>,
+) -> Vec<(PathBuf, &'static str)> {
+    let mut files: Vec<(PathBuf, &'static str)> = stored_secret_files(base_dir, profile)
+        .into_iter()
+        .filter(|path| {
+            !path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        })
+        .map(|path| {
+            let format = sops_format(&path);
+            (path, format)
+        })
+        .collect();
+    for (name, profile_config) in &config.profiles {
+        if profile.is_some_and(|profile| profile != name) {
+            continue;
+        }
+        for (path, options) in &profile_config.file_options {
+            if !options.encrypt {
+                continue;
             }
+            let Ok(stored) = config.get_source_file_path(name, path) else {
+                continue;
+            };
+            files.extend(
+                WalkDir::new(stored)
+                    .into_iter()
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.file_type().is_file())
+                    .map(|e| (e.into_path(), "binary")),
+            );
         }
     }
-    println!("Re-encrypted {updated} secrets for profile '{profile}'");
-    Ok(())
+    files.sort();
+    files.dedup();
+    files
 }
 
-/// Handle interactive age key setup during apply
-pub fn handle_interactive_age_key_setup(profile: &str) -> anyhow::Result<()> {
-    use std::io::{self, Write};
+/// Encrypted files in the repository not yet stored with `backend`
+pub fn files_to_migrate(config: &Config, base_dir: &Path, backend: SecretsBackend) -> Vec<PathBuf> {
+    encrypted_files(config, base_dir, None)
+        .into_iter()
+        .map(|(path, _)| path)
+        .filter(|path| is_age_file(path) != (backend == SecretsBackend::Native))
+        .collect()
+}
 
-    println!("❌ AGE key not found for profile '{profile}'");
-    println!("Would you like to generate a new AGE key? (y/N): ");
-    io::stdout().flush()?;
+/// Re-encrypt every encrypted file in the repository with `backend` and make
+/// it the configured `[secrets] backend`. Nothing is replaced until every
+/// file has been re-encrypted. Returns the files re-encrypted.
+pub fn migrate_backend(
+    config: &mut Config,
+    config_path: &Path,
+    backend: SecretsBackend,
+) -> Result<Vec<PathBuf>> {
+    let base_dir = config_path.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let files: Vec<(PathBuf, &'static str)> = encrypted_files(config, &base_dir, None)
+        .into_iter()
+        .filter(|(path, _)| is_age_file(path) != (backend == SecretsBackend::Native))
+        .collect();
+    if backend == SecretsBackend::Sops {
+        check_sops_and_age()?;
+        let sops_config = write_sops_config(config)?;
+        if config.secrets.sops_config.is_none() {
+            config.secrets.sops_config = Some(sops_config);
+            config.save_to_file(config_path)?;
+        }
+    } else if !files.is_empty() {
+        // Decrypting the SOPS files still needs sops, this one last time
+        check_sops_and_age()?;
+    }
+
+    let mut staged = Vec::new();
+    for (file, format) in &files {
+        let mut staging_path = file.as_os_str().to_owned();
+        staging_path.push(".migrating");
+        let staging_path = PathBuf::from(staging_path);
+        let name = file
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let result = if *format == "binary" {
+            decrypt_tracked_file(file)
+        } else {
+            decrypt_secret(file)
+        }
+        .and_then(|plaintext| encrypt_with(backend, format, &name, &plaintext))
+        .and_then(|ciphertext| Ok(fs::write(&staging_path, ciphertext)?));
+        if let Err(e) = result {
+            let _ = fs::remove_file(&staging_path);
+            for path in &staged {
+                let _ = fs::remove_file(path);
+            }
+            return Err(e.context(format!(
+                "Failed to re-encrypt {}; no secrets were changed",
+                file.display()
+            )));
+        }
+        staged.push(staging_path);
+    }
+    for ((file, _), staging_path) in files.iter().zip(&staged) {
+        fs::rename(staging_path, file)?;
+    }
 
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
+    config.secrets.backend = backend;
+    config.save_to_file(config_path)?;
+    Ok(files.into_iter().map(|(path, _)| path).collect())
+}
 
-    match input.trim().to_lowercase().as_str() {
-        "y" | "yes" => {
-            // Generate new key
-            let ordinator_config = std::env::var("ORDINATOR_CONFIG_DIR")
-                .map(PathBuf::from)
-                .unwrap_or_else(|_| {
-                    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
-                    PathBuf::from(home).join(".config").join("ordinator")
-                });
+/// Handle interactive age key setup during apply
+pub fn handle_interactive_age_key_setup(profile: &str) -> anyhow::Result<()> {
+    use crate::prompt;
 
-            let age_key_path = generate_age_key(&ordinator_config, profile, false)?;
-            let sops_config_path = create_sops_config(profile, &age_key_path, false)?;
-            update_ordinator_config(profile, &age_key_path, &sops_config_path)?;
+    println!("❌ AGE key not found for profile '{profile}'");
+    if prompt::confirm("Would you like to generate a new AGE key?", false) {
+        // Generate new key
+        let ordinator_config = std::env::var("ORDINATOR_CONFIG_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| {
+                let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+                PathBuf::from(home).join(".config").join("ordinator")
+            });
+
+        let age_key_path = generate_age_key(&ordinator_config, profile, false)?;
+        let sops_config_path = create_sops_config(profile, &age_key_path, false)?;
+        update_ordinator_config(profile, &age_key_path, &sops_config_path)?;
+
+        println!("✅ AGE key generated successfully");
+        println!("   Key stored at: {}", age_key_path.display());
+        println!("   SOPS config created at: {}", sops_config_path.display());
+        return Ok(());
+    }
+
+    // Ask if they want to import existing key
+    if !prompt::confirm("Do you have an existing AGE key to import?", false) {
+        println!("⚠️  AGE key setup cancelled.");
+        println!("   You can run 'ordinator age setup --profile {profile}' later.");
+        return Err(anyhow::anyhow!("AGE key setup cancelled by user"));
+    }
+    let key_input =
+        prompt::input("Please paste your AGE private key (it will be stored securely): ")
+            .unwrap_or_default();
+    let key_content = key_input.trim();
+
+    // Validate key format (basic check)
+    if !key_content.starts_with("AGE-SECRET-KEY-") {
+        println!("❌ Invalid AGE key format. The key must start with 'AGE-SECRET-KEY-'.");
+        return Err(anyhow::anyhow!("Invalid AGE key format"));
+    }
+
+    // Store the imported key
+    let key_path = get_age_key_path(profile);
+    let key_dir = key_path.parent().unwrap();
+    fs::create_dir_all(key_dir)?;
+    fs::write(&key_path, key_content)?;
+    fs::set_permissions(&key_path, fs::Permissions::from_mode(0o600))?;
 
-            println!("✅ AGE key generated successfully");
-            println!("   Key stored at: {}", age_key_path.display());
-            println!("   SOPS config created at: {}", sops_config_path.display());
-            Ok(())
-        }
-        _ => {
-            // Ask if they want to import existing key
-            println!("Do you have an existing AGE key to import? (y/N): ");
-            io::stdout().flush()?;
-
-            let mut import_input = String::new();
-            io::stdin().read_line(&mut import_input)?;
-
-            match import_input.trim().to_lowercase().as_str() {
-                "y" | "yes" => {
-                    println!("Please paste your AGE private key (it will be stored securely):");
-                    io::stdout().flush()?;
-
-                    let mut key_input = String::new();
-                    io::stdin().read_line(&mut key_input)?;
-                    let key_content = key_input.trim();
-
-                    // Validate key format (basic check)
-                    if !key_content.starts_with("AGE-SECRET-KEY-") {
-                        println!(
-                            "❌ Invalid AGE key format. The key must start with 'AGE-SECRET-KEY-'."
-                        );
-                        return Err(anyhow::anyhow!("Invalid AGE key format"));
-                    }
+    // Create SOPS config
+    let sops_config_path = create_sops_config(profile, &key_path, false)?;
+    update_ordinator_config(profile, &key_path, &sops_config_path)?;
 
-                    // Store the imported key
-                    let key_path = get_age_key_path(profile);
-                    let key_dir = key_path.parent().unwrap();
-                    fs::create_dir_all(key_dir)?;
-                    fs::write(&key_path, key_content)?;
-                    fs::set_permissions(&key_path, fs::Permissions::from_mode(0o600))?;
-
-                    // Create SOPS config
-                    let sops_config_path = create_sops_config(profile, &key_path, false)?;
-                    update_ordinator_config(profile, &key_path, &sops_config_path)?;
-
-                    println!("✅ AGE key imported successfully");
-                    println!("   Key stored at: {}", key_path.display());
-                    println!("   SOPS config created at: {}", sops_config_path.display());
-                    Ok(())
-                }
-                _ => {
-                    println!("⚠️  AGE key setup cancelled.");
-                    println!("   You can run 'ordinator age setup --profile {profile}' later.");
-                    Err(anyhow::anyhow!("AGE key setup cancelled by user"))
-                }
-            }
-        }
-    }
+    println!("✅ AGE key imported successfully");
+    println!("   Key stored at: {}", key_path.display());
+    println!("   SOPS config created at: {}", sops_config_path.display());
+    Ok(())
 }
 
 /// Check if decryption fails due to key mismatch and handle gracefully
@@ -980,7 +1796,7 @@ pub fn handle_key_mismatch_error(
     encrypted_file_path: &Path,
     error: &anyhow::Error,
 ) -> anyhow::Result<bool> {
-    use std::io::{self, Write};
+    use crate::prompt;
 
     // Check if the error indicates a key mismatch (SOPS decryption failure)
     let error_msg = error.to_string().to_lowercase();
@@ -992,41 +1808,22 @@ pub fn handle_key_mismatch_error(
         println!("   The current AGE key cannot decrypt this file.");
         println!("   This usually means the secrets were encrypted with a different key.");
         println!();
-        println!("What would you like to do?");
-        println!(
-            "  1. Skip this file and continue (the secret will NOT be available on this machine)"
-        );
-        println!("  2. Cancel the apply operation");
-        println!(
-            "  3. Import the correct AGE key for this profile (will overwrite the current key)"
-        );
-        println!();
-        print!("Enter your choice [1/2/3, default: 1]: ");
-        io::stdout().flush()?;
-
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-
-        match input.trim() {
-            "1" | "" => {
-                println!(
-                    "⚠️  Skipped encrypted file: {}",
-                    encrypted_file_path.display()
-                );
-                println!("   You can try again later with: ordinator age setup --profile default");
-                Ok(true) // Skip this file
-            }
-            "2" => {
+        let options = [
+            "Skip this file and continue (the secret will NOT be available on this machine)",
+            "Cancel the apply operation",
+            "Import the correct AGE key for this profile (will overwrite the current key)",
+        ]
+        .map(String::from);
+
+        match prompt::choose("What would you like to do?", &options, 0) {
+            1 => {
                 println!("❌ Apply operation cancelled by user.");
                 println!("   You can retry after importing the correct AGE key with: ordinator age setup --profile default");
                 Err(anyhow::anyhow!("User cancelled apply due to key mismatch"))
             }
-            "3" => {
-                println!("Please paste the correct AGE private key:");
-                io::stdout().flush()?;
-
-                let mut key_input = String::new();
-                io::stdin().read_line(&mut key_input)?;
+            2 => {
+                let key_input =
+                    prompt::input("Please paste the correct AGE private key: ").unwrap_or_default();
                 let key_content = key_input.trim();
 
                 // Validate key format
@@ -1052,7 +1849,10 @@ pub fn handle_key_mismatch_error(
                 Ok(false) // Retry decryption
             }
             _ => {
-                println!("⚠️  Invalid choice. Skipping file.");
+                println!(
+                    "⚠️  Skipped encrypted file: {}",
+                    encrypted_file_path.display()
+                );
                 println!("   You can try again later with: ordinator age setup --profile default");
                 Ok(true) // Skip this file
             }
@@ -1072,6 +1872,48 @@ mod tests {
     use std::os::unix::fs::PermissionsExt;
     use tempfile::tempdir;
 
+    #[test]
+    fn test_remove_decrypted_secret_shreds() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join(".env");
+        fs::write(&path, "TOKEN=secret\n").unwrap();
+        remove_decrypted_secret(&path, true).unwrap();
+        assert!(!path.exists());
+        assert!(remove_decrypted_secret(&path, false).is_err());
+    }
+
+    #[test]
+    fn test_edit_plaintext_uses_private_file() {
+        let temp = tempdir().unwrap();
+        let editor = temp.path().join("editor.sh");
+        // Records the file's mode and path, then appends a line, like a user saving
+        let script = r#"#!/bin/sh
+out=$(dirname "$0")
+stat -c %a "$1" > "$out/mode" 2>/dev/null || stat -f %Lp "$1" > "$out/mode"
+echo "$1" > "$out/path"
+echo 'TOKEN=new' >> "$1"
+"#;
+        fs::write(&editor, script).unwrap();
+        fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).unwrap();
+        let editor = editor.to_str().unwrap();
+
+        let edited = edit_plaintext(".env", "TOKEN=old\n", editor).unwrap();
+        assert_eq!(edited.as_deref(), Some("TOKEN=old\nTOKEN=new\n"));
+        let mode = fs::read_to_string(temp.path().join("mode")).unwrap();
+        assert_eq!(mode.trim(), "600");
+        let edited_path = fs::read_to_string(temp.path().join("path")).unwrap();
+        assert!(edited_path.trim().ends_with("/.env"));
+        assert!(!Path::new(edited_path.trim()).exists());
+
+        assert_eq!(edit_plaintext(".env", "x\n", "true").unwrap(), None);
+        assert!(edit_plaintext(".env", "x\n", "false").is_err());
+        assert_eq!(
+            sops_format(Path::new("secrets/work/abc_.env.enc")),
+            "binary"
+        );
+        assert_eq!(sops_format(Path::new("files/work/secrets.yaml")), "yaml");
+    }
+
     /// Test isolation guard that ensures complete isolation and automatic cleanup
     struct TestIsolationGuard {
         temp_dir: tempfile::TempDir,
@@ -1903,4 +2745,19 @@ jwt_token=eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9
         let result = super::check_key_rotation_needed(&profile_name).unwrap();
         assert!(result.is_none());
     }
+
+    #[test]
+    fn test_sops_config_lists_every_recipient() {
+        let alice = format!("age1{}", "q".repeat(58));
+        let bob = format!("age1{}", "p".repeat(58));
+        assert!(validate_age_recipient(&alice).is_ok());
+        assert!(validate_age_recipient("age1tooshort").is_err());
+        assert!(validate_age_recipient(&format!("age1{}", "B".repeat(58))).is_err());
+
+        let content = sops_config_content(&[alice.clone(), bob.clone()]);
+        assert_eq!(
+            content,
+            format!("creation_rules:\n  - age: >-\n      {alice},\n      {bob}\n")
+        );
+    }
 }
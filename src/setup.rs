@@ -0,0 +1,480 @@
+//! `ordinator setup`: clone a dotfiles repository and set up a new machine
+//! from it in one go. Each step that finishes is recorded in a checkpoint
+//! next to the machine state, so running setup again after a failure picks
+//! up where it stopped.
+
+use anyhow::{Context, Result};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::error::OrdinatorError;
+use crate::progress::ProgressMode;
+use crate::utils::color_enabled;
+
+/// One step of `ordinator setup`, in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SetupStep {
+    /// Clone the repository (or use the one already there)
+    Clone,
+
+    /// Generate or import age keys for profiles with secrets
+    Keys,
+
+    /// Install the profile's Homebrew packages
+    Brew,
+
+    /// Run the profile's bootstrap script
+    Bootstrap,
+
+    /// Link files, decrypt secrets, and write macOS defaults
+    Apply,
+}
+
+impl SetupStep {
+    pub const ALL: [SetupStep; 5] = [
+        SetupStep::Clone,
+        SetupStep::Keys,
+        SetupStep::Brew,
+        SetupStep::Bootstrap,
+        SetupStep::Apply,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SetupStep::Clone => "clone",
+            SetupStep::Keys => "keys",
+            SetupStep::Brew => "brew",
+            SetupStep::Bootstrap => "bootstrap",
+            SetupStep::Apply => "apply",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            SetupStep::Clone => "Clone the dotfiles repository",
+            SetupStep::Keys => "Set up age keys for secrets",
+            SetupStep::Brew => "Install Homebrew packages",
+            SetupStep::Bootstrap => "Run the bootstrap script",
+            SetupStep::Apply => "Apply the profile",
+        }
+    }
+}
+
+/// Progress of a setup that hasn't finished, kept in
+/// `~/.local/state/ordinator/setup.toml`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SetupCheckpoint {
+    pub repo_url: String,
+    pub dotfiles_dir: PathBuf,
+
+    /// Profile being set up; chosen after cloning when not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+
+    /// Steps that finished, in order
+    #[serde(default)]
+    pub completed: Vec<SetupStep>,
+
+    /// When the setup was started (RFC 3339)
+    pub started_at: String,
+}
+
+impl SetupCheckpoint {
+    /// Checkpoint file for the user's own home directory
+    pub fn path() -> Result<PathBuf> {
+        let home_dir = crate::utils::get_home_dir()?;
+        Ok(crate::state::MachineState::path(&home_dir).with_file_name("setup.toml"))
+    }
+
+    /// The unfinished setup recorded at `path`, if any
+    pub fn load_from(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let checkpoint = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Some(checkpoint))
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn is_done(&self, step: SetupStep) -> bool {
+        self.completed.contains(&step)
+    }
+
+    fn complete(&mut self, step: SetupStep) {
+        if !self.is_done(step) {
+            self.completed.push(step);
+        }
+    }
+}
+
+/// What `ordinator setup` should do
+#[derive(Debug, Clone)]
+pub struct SetupOptions {
+    pub repo_url: String,
+
+    /// Where to clone the repository, normally `~/.dotfiles`
+    pub dotfiles_dir: PathBuf,
+
+    /// Profile to set up (chosen like apply when omitted)
+    pub profile: Option<String>,
+
+    pub skip_secrets: bool,
+    pub skip_brew: bool,
+    pub skip_bootstrap: bool,
+
+    /// Overwrite existing files while applying
+    pub force: bool,
+
+    /// Ignore an unfinished setup and start again from the first step
+    pub restart: bool,
+
+    /// Most files to check or hash at once while applying
+    pub jobs: usize,
+    pub progress: ProgressMode,
+
+    pub dry_run: bool,
+    pub quiet: bool,
+    pub verbose: bool,
+}
+
+/// Run the setup steps that haven't finished yet. A failed step stops the
+/// setup and leaves the checkpoint in place for the next run.
+pub async fn run(options: &SetupOptions) -> Result<()> {
+    let checkpoint_path = SetupCheckpoint::path()?;
+    let mut checkpoint = match SetupCheckpoint::load_from(&checkpoint_path)? {
+        Some(previous) if !options.restart => resume_checkpoint(previous, options)?,
+        _ => SetupCheckpoint {
+            repo_url: options.repo_url.clone(),
+            dotfiles_dir: options.dotfiles_dir.clone(),
+            profile: options.profile.clone(),
+            completed: Vec::new(),
+            started_at: chrono::Local::now().to_rfc3339(),
+        },
+    };
+    if !checkpoint.completed.is_empty() && !options.quiet {
+        let done: Vec<&str> = checkpoint.completed.iter().map(|s| s.label()).collect();
+        eprintln!(
+            "Resuming setup of {} started {} (done: {})",
+            checkpoint.repo_url,
+            crate::utils::format_timestamp(&checkpoint.started_at),
+            done.join(", ")
+        );
+    }
+
+    let total = SetupStep::ALL.len();
+    for (index, step) in SetupStep::ALL.into_iter().enumerate() {
+        if checkpoint.is_done(step) {
+            continue;
+        }
+        if !options.quiet {
+            let heading = format!("[{}/{total}] {}", index + 1, step.description());
+            if color_enabled() {
+                eprintln!("{}", heading.cyan().bold());
+            } else {
+                eprintln!("{heading}");
+            }
+        }
+        if options.dry_run {
+            eprintln!("DRY-RUN: Would {}", step.description().to_lowercase());
+            continue;
+        }
+        if let Err(e) = run_step(step, &mut checkpoint, options).await {
+            checkpoint.save_to(&checkpoint_path)?;
+            eprintln!(
+                "Setup stopped at step '{}'. Fix the problem below and run 'ordinator setup {}' again to resume.",
+                step.label(),
+                checkpoint.repo_url
+            );
+            return Err(e);
+        }
+        checkpoint.complete(step);
+        checkpoint.save_to(&checkpoint_path)?;
+    }
+    if options.dry_run {
+        return Ok(());
+    }
+
+    if checkpoint_path.exists() {
+        std::fs::remove_file(&checkpoint_path)
+            .with_context(|| format!("Failed to remove {}", checkpoint_path.display()))?;
+    }
+    if !options.quiet {
+        let msg = format!(
+            "✅ Setup complete: profile '{}' applied from {}",
+            checkpoint.profile.as_deref().unwrap_or_default(),
+            checkpoint.dotfiles_dir.display()
+        );
+        if color_enabled() {
+            eprintln!("{}", msg.green());
+        } else {
+            eprintln!("{msg}");
+        }
+    }
+    Ok(())
+}
+
+/// Carry on with `previous` if it's the same setup as `options` asks for
+fn resume_checkpoint(previous: SetupCheckpoint, options: &SetupOptions) -> Result<SetupCheckpoint> {
+    if previous.repo_url != options.repo_url {
+        return Err(anyhow::anyhow!(
+            "A setup of {} hasn't finished. Run 'ordinator setup {}' to resume it, or pass --restart to discard it.",
+            previous.repo_url,
+            previous.repo_url
+        ));
+    }
+    if let (Some(wanted), Some(recorded)) = (&options.profile, &previous.profile) {
+        if wanted != recorded {
+            return Err(anyhow::anyhow!(
+                "The unfinished setup is for profile '{recorded}', not '{wanted}'. Pass --restart to discard it."
+            ));
+        }
+    }
+    Ok(previous)
+}
+
+async fn run_step(
+    step: SetupStep,
+    checkpoint: &mut SetupCheckpoint,
+    options: &SetupOptions,
+) -> Result<()> {
+    let dotfiles_dir = checkpoint.dotfiles_dir.clone();
+    let config_path = dotfiles_dir.join("ordinator.toml");
+    if step == SetupStep::Clone {
+        clone_repository(&checkpoint.repo_url, &dotfiles_dir, options.quiet).await?;
+    }
+    let config = Config::from_file(&config_path)?;
+    let profile = match &checkpoint.profile {
+        Some(profile) => profile.clone(),
+        None => {
+            let machine = crate::machine::MachineInfo::detect();
+            let profile = match crate::machine::matching_profile(&config, &machine) {
+                Some(name) => {
+                    eprintln!("Using profile '{name}' (its match rules fit this machine)");
+                    name.to_string()
+                }
+                None => config.global.default_profile.clone(),
+            };
+            checkpoint.profile = Some(profile.clone());
+            profile
+        }
+    };
+    if !config.profiles.contains_key(&profile) {
+        return Err(OrdinatorError::ProfileNotFound(profile).into());
+    }
+
+    match step {
+        SetupStep::Clone => Ok(()),
+        SetupStep::Keys => set_up_keys(&config, &profile, options),
+        SetupStep::Brew => install_packages(&config, &profile, options).await,
+        SetupStep::Bootstrap => run_bootstrap(&config, &dotfiles_dir, &profile, options),
+        SetupStep::Apply => {
+            let apply_options = crate::apply::ApplyOptions {
+                profiles: vec![profile],
+                machine: None,
+                home_dir: config.target_root()?,
+                filter: crate::filter::PathFilter::default(),
+                // Both already ran as their own steps
+                skip_bootstrap: true,
+                skip_brew: true,
+                skip_secrets: options.skip_secrets,
+                skip_defaults: false,
+                no_secrets_for: Vec::new(),
+                shared: None,
+                frozen: false,
+                force: options.force,
+                sudo_script: None,
+                snapshot: None,
+                strict: false,
+                jobs: options.jobs,
+                progress: options.progress,
+                dry_run: false,
+                quiet: options.quiet,
+                verbose: options.verbose,
+            };
+            crate::apply::run(&config, &config_path, &apply_options).await?;
+            Ok(())
+        }
+    }
+}
+
+/// Clone `repo_url` into `dotfiles_dir`, unless a repository with an
+/// `ordinator.toml` is already there
+async fn clone_repository(repo_url: &str, dotfiles_dir: &Path, quiet: bool) -> Result<()> {
+    if dotfiles_dir.join("ordinator.toml").is_file() {
+        if !quiet {
+            eprintln!("Using the repository already at {}", dotfiles_dir.display());
+        }
+        return Ok(());
+    }
+    let repo_manager = crate::repo::RepoManager::new(dotfiles_dir.to_path_buf());
+    repo_manager
+        .parse_github_url(repo_url)
+        .with_context(|| format!("Invalid GitHub URL '{repo_url}'"))?;
+    repo_manager.init_from_url(repo_url, false).await?;
+    if !quiet {
+        eprintln!("Cloned {repo_url} to {}", dotfiles_dir.display());
+    }
+    Ok(())
+}
+
+/// Make sure every profile with secrets has an age key, prompting to
+/// generate or import the missing ones
+fn set_up_keys(config: &Config, profile: &str, options: &SetupOptions) -> Result<()> {
+    if options.skip_secrets {
+        eprintln!("Skipping: --skip-secrets");
+        return Ok(());
+    }
+    let resolved = config.resolve_profile(profile)?;
+    let mut owners: Vec<&str> = resolved
+        .secrets
+        .iter()
+        .map(|s| s.profile.as_str())
+        .collect();
+    owners.sort();
+    owners.dedup();
+    if owners.is_empty() {
+        eprintln!("Profile '{profile}' has no secrets; no keys needed");
+        return Ok(());
+    }
+    // Apply decrypts with the key of the profile being applied
+    if crate::secrets::age_key_exists(profile) {
+        eprintln!("Age key for profile '{profile}' found");
+        return Ok(());
+    }
+    crate::secrets::handle_interactive_age_key_setup(profile).map_err(|e| {
+        anyhow::anyhow!(
+            "{e}. Secrets can't be decrypted without an age key; pass --skip-secrets to set them up later."
+        )
+    })
+}
+
+async fn install_packages(config: &Config, profile: &str, options: &SetupOptions) -> Result<()> {
+    use crate::brew::BrewManager;
+
+    if options.skip_brew {
+        eprintln!("Skipping: --skip-brew");
+        return Ok(());
+    }
+    let resolved = config.resolve_profile(profile)?;
+    if resolved.homebrew_formulas.is_empty()
+        && resolved.homebrew_casks.is_empty()
+        && resolved.homebrew_taps.is_empty()
+    {
+        eprintln!("Profile '{profile}' has no Homebrew packages");
+        return Ok(());
+    }
+    if !BrewManager::check_homebrew_installed() {
+        return Err(OrdinatorError::BrewNotInstalled.into());
+    }
+    let report = BrewManager::new(false)
+        .install_resolved(profile, &resolved)
+        .await?;
+    if !report.is_empty() {
+        eprintln!("{}", report.table());
+    }
+    // Like apply, setup carries on past packages that failed; rerun 'ordinator brew install' for them
+    if report.failures() > 0 {
+        eprintln!(
+            "Warning: {} Homebrew package(s) failed to install. Retry them with: ordinator brew install --profile {profile}",
+            report.failures()
+        );
+    }
+    Ok(())
+}
+
+fn run_bootstrap(
+    config: &Config,
+    dotfiles_dir: &Path,
+    profile: &str,
+    options: &SetupOptions,
+) -> Result<()> {
+    use crate::bootstrap::{BootstrapManager, DEFAULT_TIMEOUT_SECS};
+
+    if options.skip_bootstrap {
+        eprintln!("Skipping: --skip-bootstrap");
+        return Ok(());
+    }
+    let Some(script_path) = config.get_bootstrap_script(profile) else {
+        eprintln!("No bootstrap script configured for profile '{profile}'");
+        return Ok(());
+    };
+    let full_script_path = dotfiles_dir.join(&script_path);
+    let timeout = config
+        .global
+        .bootstrap_timeout_secs
+        .unwrap_or(DEFAULT_TIMEOUT_SECS);
+    let content = std::fs::read(&full_script_path).unwrap_or_default();
+    let receipt = crate::receipts::Receipt::new(profile, &script_path.to_string_lossy(), &content);
+    let outcome = BootstrapManager::new(false).run_logged(
+        &full_script_path,
+        profile,
+        std::time::Duration::from_secs(timeout),
+        &dotfiles_dir.join("logs").join("bootstrap"),
+    )?;
+    if let Err(e) = crate::receipts::record(&config.target_root()?, &receipt.finish(&outcome)) {
+        eprintln!("⚠️  Warning: Failed to record the bootstrap receipt: {e}");
+    }
+    if outcome.timed_out {
+        return Err(anyhow::anyhow!(
+            "Bootstrap script timed out after {timeout}s and was stopped. Log: {}",
+            outcome.log_path.display()
+        ));
+    }
+    if !outcome.success() {
+        return Err(anyhow::anyhow!(
+            "Bootstrap script failed with exit code {}. Log: {}",
+            outcome
+                .exit_code
+                .map_or("unknown".to_string(), |code| code.to_string()),
+            outcome.log_path.display()
+        ));
+    }
+    eprintln!(
+        "Bootstrap script finished in {}s. Log: {}",
+        outcome.duration.as_secs(),
+        outcome.log_path.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_roundtrip() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("setup.toml");
+        assert_eq!(SetupCheckpoint::load_from(&path).unwrap(), None);
+
+        let mut checkpoint = SetupCheckpoint {
+            repo_url: "https://github.com/me/dotfiles".to_string(),
+            dotfiles_dir: PathBuf::from("/Users/me/.dotfiles"),
+            profile: Some("work".to_string()),
+            completed: Vec::new(),
+            started_at: "2024-05-01T09:30:00+00:00".to_string(),
+        };
+        checkpoint.complete(SetupStep::Clone);
+        checkpoint.complete(SetupStep::Keys);
+        checkpoint.complete(SetupStep::Clone);
+        checkpoint.save_to(&path).unwrap();
+
+        let loaded = SetupCheckpoint::load_from(&path).unwrap().unwrap();
+        assert_eq!(loaded.completed, vec![SetupStep::Clone, SetupStep::Keys]);
+        assert!(!loaded.is_done(SetupStep::Brew));
+        assert_eq!(loaded, checkpoint);
+    }
+}
@@ -0,0 +1,119 @@
+//! Per-shell file sets, `[profiles.<name>.shell.<shell>]`: tracked files
+//! applied only when a shell is installed, or is the login shell, e.g.
+//! `config.fish` only on machines with fish.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Overrides the login shell, which is otherwise taken from `SHELL`
+pub const SHELL_ENV: &str = "ORDINATOR_SHELL";
+
+/// When a shell's files are applied
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShellWhen {
+    /// The shell is on `PATH` (or is the login shell)
+    #[default]
+    Installed,
+
+    /// The shell is the login shell
+    Login,
+}
+
+impl ShellWhen {
+    fn is_installed(&self) -> bool {
+        *self == ShellWhen::Installed
+    }
+}
+
+/// Tracked paths one shell needs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShellFiles {
+    /// Tracked files or directories (also listed in `files` or `directories`)
+    /// applied only for this shell
+    #[serde(default)]
+    pub files: Vec<String>,
+
+    /// `installed` (default) or `login`
+    #[serde(default, skip_serializing_if = "ShellWhen::is_installed")]
+    pub when: ShellWhen,
+}
+
+/// The shells found on this machine
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Shells {
+    /// File name of the login shell, e.g. `zsh`
+    pub login: Option<String>,
+}
+
+/// Why a shell's files were left out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedShell {
+    pub shell: String,
+    pub when: ShellWhen,
+
+    /// Tracked paths not applied
+    pub files: Vec<String>,
+}
+
+impl fmt::Display for SkippedShell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.when {
+            ShellWhen::Installed => "isn't installed",
+            ShellWhen::Login => "isn't the login shell",
+        };
+        write!(f, "{} {reason}: {}", self.shell, self.files.join(", "))
+    }
+}
+
+impl Shells {
+    /// Look up the login shell
+    pub fn detect() -> Self {
+        let login = std::env::var(SHELL_ENV)
+            .or_else(|_| std::env::var("SHELL"))
+            .ok()
+            .and_then(|shell| {
+                let name = std::path::Path::new(shell.trim()).file_name()?;
+                Some(name.to_string_lossy().into_owned())
+            })
+            .filter(|name| !name.is_empty());
+        Self { login }
+    }
+
+    /// Whether `shell` is found the way `when` asks
+    pub fn has(&self, shell: &str, when: ShellWhen) -> bool {
+        let login = self.login.as_deref() == Some(shell);
+        match when {
+            ShellWhen::Login => login,
+            ShellWhen::Installed => login || which::which(shell).is_ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_found_by_login_or_path() {
+        let shells = Shells {
+            login: Some("zsh".to_string()),
+        };
+        assert!(shells.has("zsh", ShellWhen::Login));
+        assert!(shells.has("zsh", ShellWhen::Installed));
+        assert!(!shells.has("sh", ShellWhen::Login));
+        assert!(shells.has("sh", ShellWhen::Installed));
+        assert!(!shells.has("no-such-shell-here", ShellWhen::Installed));
+
+        let skipped = SkippedShell {
+            shell: "fish".to_string(),
+            when: ShellWhen::Installed,
+            files: vec![".config/fish/config.fish".to_string()],
+        };
+        assert_eq!(
+            skipped.to_string(),
+            "fish isn't installed: .config/fish/config.fish"
+        );
+    }
+}
@@ -0,0 +1,337 @@
+//! Snapshots taken by `apply --snapshot`: every destination an apply may
+//! touch, copied into one timestamped directory beside the machine state, so
+//! `ordinator snapshots restore <id>` puts them all back at once. Coarser than
+//! the per-file backups and the journal, but it doesn't depend on either.
+
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+use crate::utils::is_symlink;
+
+/// Directory (beside the machine state file) holding snapshots
+const SNAPSHOT_DIR: &str = "snapshots";
+
+/// What a snapshot directory records its destinations in
+const MANIFEST: &str = "snapshot.json";
+
+/// How `apply --snapshot` saves the destinations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotKind {
+    /// Copy them into the snapshot directory
+    Copy,
+
+    /// Copy them, and take an APFS local snapshot of the volume with `tmutil`
+    Apfs,
+}
+
+impl FromStr for SnapshotKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "copy" => Ok(Self::Copy),
+            "apfs" => Ok(Self::Apfs),
+            other => Err(anyhow::anyhow!(
+                "Unknown snapshot kind '{other}'. Use 'copy' or 'apfs'."
+            )),
+        }
+    }
+}
+
+/// What was at a destination when the snapshot was taken
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Saved {
+    /// Nothing
+    Absent,
+
+    /// A symlink, saved as its target
+    Symlink { target: PathBuf },
+
+    /// A file, copied to `copy` inside the snapshot directory
+    File { copy: String },
+
+    /// A directory, copied to `copy` inside the snapshot directory
+    Directory { copy: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    pub path: PathBuf,
+
+    #[serde(flatten)]
+    pub saved: Saved,
+}
+
+/// One snapshot: its destinations, and where their copies are
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Snapshot identifier (also the directory name)
+    pub id: String,
+
+    /// Profile applied after the snapshot was taken
+    pub profile: String,
+
+    pub created_at: String,
+
+    /// Why it was taken, when not by `apply --snapshot`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+
+    /// Date of the APFS local snapshot taken with it, as `tmutil` names it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apfs: Option<String>,
+
+    #[serde(default)]
+    pub entries: Vec<SnapshotEntry>,
+
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+/// Where snapshots for `home_dir` are kept: beside its machine state file
+pub fn snapshots_dir(home_dir: &Path) -> PathBuf {
+    let state = crate::state::MachineState::path(home_dir);
+    state
+        .parent()
+        .map_or_else(|| PathBuf::from(SNAPSHOT_DIR), |dir| dir.join(SNAPSHOT_DIR))
+}
+
+/// Take an APFS local snapshot, returning its date
+fn apfs_snapshot() -> Result<String> {
+    let output = Command::new("tmutil")
+        .arg("localsnapshot")
+        .output()
+        .context("APFS snapshots need tmutil, which comes with macOS")?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "tmutil localsnapshot failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    // "Created local snapshot with date: 2025-01-01-120000"
+    stdout
+        .lines()
+        .find_map(|line| {
+            line.split_once("date: ")
+                .map(|(_, date)| date.trim().to_string())
+        })
+        .ok_or_else(|| anyhow::anyhow!("Unexpected tmutil output: {}", stdout.trim()))
+}
+
+impl Snapshot {
+    /// Save what's at each of `paths` in a new snapshot for `home_dir`.
+    /// With [`SnapshotKind::Apfs`], an APFS local snapshot is taken too; when
+    /// that fails, the returned warning says so and the copy still stands.
+    pub fn take(
+        home_dir: &Path,
+        profile: &str,
+        note: Option<String>,
+        paths: &[PathBuf],
+        kind: SnapshotKind,
+    ) -> Result<(Self, Option<String>)> {
+        let now = Local::now();
+        let root = snapshots_dir(home_dir);
+
+        // Several snapshots can be taken within the same second; keep ids unique
+        let base = now.format("%Y%m%d-%H%M%S").to_string();
+        let mut id = base.clone();
+        let mut counter = 1;
+        while root.join(&id).exists() {
+            id = format!("{base}-{counter}");
+            counter += 1;
+        }
+        let dir = root.join(&id);
+        fs::create_dir_all(dir.join("files"))
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        let mut entries = Vec::new();
+        for (index, path) in paths.iter().enumerate() {
+            let copy = format!("files/{index}");
+            let saved = if is_symlink(path) {
+                Saved::Symlink {
+                    target: fs::read_link(path)?,
+                }
+            } else if path.is_dir() {
+                crate::utils::copy_dir_recursive(path, &dir.join(&copy))?;
+                Saved::Directory { copy }
+            } else if path.exists() {
+                fs::copy(path, dir.join(&copy))
+                    .with_context(|| format!("Failed to snapshot {}", path.display()))?;
+                Saved::File { copy }
+            } else {
+                Saved::Absent
+            };
+            entries.push(SnapshotEntry {
+                path: path.clone(),
+                saved,
+            });
+        }
+
+        let (apfs, warning) = match kind {
+            SnapshotKind::Copy => (None, None),
+            SnapshotKind::Apfs => match apfs_snapshot() {
+                Ok(date) => (Some(date), None),
+                Err(e) => (None, Some(format!("No APFS snapshot taken: {e}"))),
+            },
+        };
+        let snapshot = Self {
+            id,
+            profile: profile.to_string(),
+            created_at: now.to_rfc3339(),
+            note,
+            apfs,
+            entries,
+            dir,
+        };
+        snapshot.save()?;
+        Ok((snapshot, warning))
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = self.dir.join(MANIFEST);
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Every snapshot for `home_dir`, oldest first
+    pub fn list(home_dir: &Path) -> Result<Vec<Self>> {
+        let root = snapshots_dir(home_dir);
+        if !root.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut snapshots = Vec::new();
+        for entry in fs::read_dir(&root)? {
+            let dir = entry?.path();
+            let manifest = dir.join(MANIFEST);
+            if !manifest.is_file() {
+                continue;
+            }
+            let content = fs::read_to_string(&manifest)
+                .with_context(|| format!("Failed to read {}", manifest.display()))?;
+            let mut snapshot: Self = serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", manifest.display()))?;
+            snapshot.dir = dir;
+            snapshots.push(snapshot);
+        }
+        snapshots.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        Ok(snapshots)
+    }
+
+    /// The snapshot named `id`, or the newest for `latest`
+    pub fn find(home_dir: &Path, id: &str) -> Result<Self> {
+        let mut snapshots = Self::list(home_dir)?;
+        let found = if id == "latest" {
+            snapshots.pop()
+        } else {
+            snapshots.into_iter().find(|snapshot| snapshot.id == id)
+        };
+        found.ok_or_else(|| {
+            anyhow::anyhow!(
+                "No snapshot '{id}'. Run 'ordinator snapshots list' to see the snapshots taken."
+            )
+        })
+    }
+
+    /// Destinations that changed since the snapshot
+    pub fn pending(&self) -> Vec<&SnapshotEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| !self.matches(entry))
+            .collect()
+    }
+
+    /// Put every destination that changed back as it was, returning them
+    pub fn restore(&self) -> Result<Vec<PathBuf>> {
+        let mut restored = Vec::new();
+        for entry in self.pending() {
+            let path = &entry.path;
+            if is_symlink(path) || path.is_file() {
+                fs::remove_file(path)?;
+            } else if path.is_dir() {
+                fs::remove_dir_all(path)?;
+            }
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            match &entry.saved {
+                Saved::Absent => {}
+                Saved::Symlink { target } => std::os::unix::fs::symlink(target, path)?,
+                Saved::File { copy } => {
+                    fs::copy(self.dir.join(copy), path)
+                        .with_context(|| format!("Failed to restore {}", path.display()))?;
+                }
+                Saved::Directory { copy } => {
+                    crate::utils::copy_dir_recursive(&self.dir.join(copy), path)?;
+                }
+            }
+            restored.push(path.clone());
+        }
+        Ok(restored)
+    }
+
+    /// Whether the destination is still as the snapshot saved it
+    fn matches(&self, entry: &SnapshotEntry) -> bool {
+        let path = &entry.path;
+        match &entry.saved {
+            Saved::Absent => !path.exists() && !is_symlink(path),
+            Saved::Symlink { target } => {
+                is_symlink(path) && fs::read_link(path).is_ok_and(|current| &current == target)
+            }
+            Saved::File { copy } => {
+                !is_symlink(path)
+                    && path.is_file()
+                    && fs::read(path).ok() == fs::read(self.dir.join(copy)).ok()
+            }
+            // Directories are always copied back
+            Saved::Directory { .. } => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_and_restore() {
+        let home = tempfile::tempdir().unwrap();
+        let zshrc = home.path().join(".zshrc");
+        let vimrc = home.path().join(".vimrc");
+        let gitconfig = home.path().join(".gitconfig");
+        fs::write(&zshrc, "export EDITOR=vim\n").unwrap();
+        std::os::unix::fs::symlink("/elsewhere/vimrc", &vimrc).unwrap();
+
+        let paths = vec![zshrc.clone(), vimrc.clone(), gitconfig.clone()];
+        let (snapshot, warning) =
+            Snapshot::take(home.path(), "default", None, &paths, SnapshotKind::Copy).unwrap();
+        assert_eq!(warning, None);
+        assert_eq!(
+            snapshot.entries[1].saved,
+            Saved::Symlink {
+                target: PathBuf::from("/elsewhere/vimrc")
+            }
+        );
+        assert_eq!(snapshot.entries[2].saved, Saved::Absent);
+
+        // What an apply might do
+        fs::remove_file(&zshrc).unwrap();
+        std::os::unix::fs::symlink("/dotfiles/zshrc", &zshrc).unwrap();
+        fs::write(&gitconfig, "[user]\n").unwrap();
+
+        let found = Snapshot::find(home.path(), "latest").unwrap();
+        assert_eq!(found.id, snapshot.id);
+        assert_eq!(found.restore().unwrap(), [zshrc.clone(), gitconfig.clone()]);
+        assert!(!is_symlink(&zshrc));
+        assert_eq!(fs::read_to_string(&zshrc).unwrap(), "export EDITOR=vim\n");
+        assert!(!gitconfig.exists());
+        assert!(found.restore().unwrap().is_empty());
+        assert!(Snapshot::find(home.path(), "nope").is_err());
+    }
+}
@@ -0,0 +1,180 @@
+//! Machine state: which profiles were applied here, when, and at which commit,
+//! the machine role they came from, when the repository was last pulled and
+//! pushed from here, and whether this is a shared machine that never gets secrets.
+//! Kept outside the dotfiles repository, in `~/.local/state/ordinator/state.toml`
+//! under the home directory (or `target_root`) the profiles were applied to.
+
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Record of one applied profile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedProfile {
+    /// When the profile was last applied (RFC 3339)
+    pub applied_at: String,
+
+    /// Short hash of the dotfiles repository's HEAD at the time, if it is a git repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+
+    /// Dotfiles repository the profile was applied from
+    pub dotfiles_dir: PathBuf,
+}
+
+impl AppliedProfile {
+    /// `applied_at` in local time, e.g. `2024-05-01 09:30`
+    pub fn applied_at_display(&self) -> String {
+        crate::utils::format_timestamp(&self.applied_at)
+    }
+}
+
+/// What has been applied on this machine
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MachineState {
+    /// Profile applied most recently
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_profile: Option<String>,
+
+    /// Every profile applied and not uninstalled since, by name
+    #[serde(default)]
+    pub applied: BTreeMap<String, AppliedProfile>,
+
+    /// Answer to "is this machine shared?": when true, apply never decrypts
+    /// secrets here. Unset until the first apply with secrets asks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shared: Option<bool>,
+
+    /// Machine role last applied here with `apply --machine`; plain `apply` uses it again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub machine: Option<String>,
+
+    /// When `ordinator pull` or `sync` last succeeded here (RFC 3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_pull: Option<String>,
+
+    /// When `ordinator push` or `sync` last succeeded here (RFC 3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_push: Option<String>,
+}
+
+impl MachineState {
+    /// State file for profiles applied to `home_dir`:
+    /// `<home_dir>/.local/state/ordinator/state.toml`, or
+    /// `$XDG_STATE_HOME/ordinator/state.toml` for the user's own home
+    pub fn path(home_dir: &Path) -> PathBuf {
+        let own_home = std::env::var_os("ORDINATOR_HOME").is_none()
+            && dirs::home_dir().is_some_and(|home| home == home_dir);
+        let state_home = match std::env::var_os("XDG_STATE_HOME") {
+            Some(dir) if own_home => PathBuf::from(dir),
+            _ => home_dir.join(".local").join("state"),
+        };
+        state_home.join("ordinator").join("state.toml")
+    }
+
+    /// Load the state for `home_dir`; a missing file is an empty state
+    pub fn load(home_dir: &Path) -> Result<Self> {
+        Self::load_from(&Self::path(home_dir))
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self, home_dir: &Path) -> Result<()> {
+        self.save_to(&Self::path(home_dir))
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// The most recently applied profile and its record
+    pub fn current(&self) -> Option<(&str, &AppliedProfile)> {
+        let name = self.current_profile.as_deref()?;
+        Some((name, self.applied.get(name)?))
+    }
+
+    /// Note that `profile` was just applied from `dotfiles_dir`
+    pub fn record_apply(&mut self, profile: &str, dotfiles_dir: &Path, commit: Option<String>) {
+        self.applied.insert(
+            profile.to_string(),
+            AppliedProfile {
+                applied_at: Local::now().to_rfc3339(),
+                commit,
+                dotfiles_dir: dotfiles_dir.to_path_buf(),
+            },
+        );
+        self.current_profile = Some(profile.to_string());
+    }
+
+    /// Note that the repository was just pulled
+    pub fn record_pull(&mut self) {
+        self.last_pull = Some(Local::now().to_rfc3339());
+    }
+
+    /// Note that the repository was just pushed
+    pub fn record_push(&mut self) {
+        self.last_push = Some(Local::now().to_rfc3339());
+    }
+
+    /// Forget an uninstalled profile; the current profile falls back to the
+    /// most recently applied one that's left
+    pub fn record_uninstall(&mut self, profile: &str) {
+        self.applied.remove(profile);
+        if self.current_profile.as_deref() == Some(profile) {
+            self.current_profile = self
+                .applied
+                .iter()
+                .max_by(|a, b| a.1.applied_at.cmp(&b.1.applied_at))
+                .map(|(name, _)| name.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_apply_and_uninstall_roundtrip() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("state.toml");
+        let dotfiles = Path::new("/Users/me/.dotfiles");
+
+        let mut state = MachineState::load_from(&path).unwrap();
+        assert_eq!(state, MachineState::default());
+        state.record_apply("personal", dotfiles, None);
+        state.record_apply("work", dotfiles, Some("abc1234".to_string()));
+        state.save_to(&path).unwrap();
+
+        let mut state = MachineState::load_from(&path).unwrap();
+        let (name, applied) = state.current().unwrap();
+        assert_eq!(name, "work");
+        assert_eq!(applied.commit.as_deref(), Some("abc1234"));
+
+        state.record_uninstall("work");
+        assert_eq!(state.current_profile.as_deref(), Some("personal"));
+        state.record_uninstall("personal");
+        assert!(state.current().is_none());
+        assert!(state.applied.is_empty());
+
+        assert!(state.last_push.is_none());
+        state.record_push();
+        state.save_to(&path).unwrap();
+        let state = MachineState::load_from(&path).unwrap();
+        assert!(state.last_push.is_some());
+        assert!(state.last_pull.is_none());
+    }
+}
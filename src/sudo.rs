@@ -0,0 +1,143 @@
+//! Links to destinations the current user can't write, like `/etc/hosts`.
+//! Apply collects them and creates them all with one `sudo sh`, or writes
+//! the script out with `--sudo-script` for the user to review and run.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::info;
+
+/// A symlink apply can't create as the current user
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrivilegedLink {
+    pub source: PathBuf,
+    pub dest: PathBuf,
+
+    /// A real file or directory is at `dest` and is removed first: it's
+    /// been backed up, or `--permanent` was given
+    pub remove_existing: bool,
+}
+
+/// Whether creating or replacing `dest` needs more privileges than the current
+/// user has. Replacing a path means writing to its directory, so the nearest
+/// existing ancestor is probed with a temporary file.
+pub fn needs_privileges(dest: &Path) -> bool {
+    let mut dir = dest.parent();
+    while let Some(candidate) = dir {
+        if candidate.is_dir() {
+            return tempfile::tempfile_in(candidate)
+                .is_err_and(|e| e.kind() == std::io::ErrorKind::PermissionDenied);
+        }
+        dir = candidate.parent();
+    }
+    false
+}
+
+/// Quote a path for a POSIX shell
+fn shell_quote(path: &Path) -> String {
+    format!("'{}'", path.display().to_string().replace('\'', "'\\''"))
+}
+
+/// A shell script that creates every link, meant to be run with `sudo sh`
+pub fn script(links: &[PrivilegedLink]) -> String {
+    let mut script = String::from(
+        "#!/bin/sh\n# Links from `ordinator apply` that need administrator privileges.\n# Run with: sudo sh <this file>\nset -e\n",
+    );
+    for link in links {
+        if let Some(parent) = link.dest.parent() {
+            script.push_str(&format!("mkdir -p {}\n", shell_quote(parent)));
+        }
+        // `ln -sfn` would overwrite a file in place and nest the link inside a directory
+        if link.remove_existing {
+            script.push_str(&format!("rm -rf {}\n", shell_quote(&link.dest)));
+        }
+        script.push_str(&format!(
+            "ln -sfn {} {}\n",
+            shell_quote(&link.source),
+            shell_quote(&link.dest)
+        ));
+    }
+    script
+}
+
+/// Write the script for `links` to `path`, ready to run with `sudo sh`
+pub fn write_script(links: &[PrivilegedLink], path: &Path) -> Result<()> {
+    fs::write(path, script(links))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
+
+/// Create every link in one `sudo` invocation, so the password is asked for once
+pub fn run(links: &[PrivilegedLink]) -> Result<()> {
+    info!("Running sudo for {} privileged link(s)", links.len());
+    let status = crate::environment::status(
+        Command::new("sudo").args(["sh", "-c", &script(links)]),
+        crate::environment::Effect::Changes,
+    )
+    .context("Failed to run sudo")?;
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "sudo failed with {status}. Re-run with --sudo-script <path> to review the commands and run them yourself."
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_quotes_paths() {
+        let links = vec![PrivilegedLink {
+            source: PathBuf::from("/dotfiles/files/work/etc/hosts"),
+            dest: PathBuf::from("/Library/It's Here/hosts"),
+            remove_existing: false,
+        }];
+        let script = script(&links);
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains("mkdir -p '/Library/It'\\''s Here'\n"));
+        assert!(script
+            .contains("ln -sfn '/dotfiles/files/work/etc/hosts' '/Library/It'\\''s Here/hosts'\n"));
+        assert!(!script.contains("rm -rf"));
+    }
+
+    #[test]
+    fn test_script_removes_what_is_in_the_way() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("source");
+        let file = temp.path().join("file");
+        let dir = temp.path().join("dir");
+        fs::write(&source, "linked").unwrap();
+        fs::write(&file, "in the way").unwrap();
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        let links: Vec<_> = [&file, &dir]
+            .into_iter()
+            .map(|dest| PrivilegedLink {
+                source: source.clone(),
+                dest: dest.clone(),
+                remove_existing: true,
+            })
+            .collect();
+
+        let status = Command::new("sh")
+            .args(["-c", &script(&links)])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(fs::read_link(&file).unwrap(), source);
+        assert_eq!(fs::read_link(&dir).unwrap(), source);
+    }
+
+    #[test]
+    fn test_needs_privileges_for_writable_dir() {
+        let temp = tempfile::tempdir().unwrap();
+        assert!(!needs_privileges(&temp.path().join("missing/nested/file")));
+    }
+}
@@ -0,0 +1,196 @@
+//! `ordinator profile switch`: move this machine from one profile to another.
+//! Links only the old profile made are removed, the new profile is applied,
+//! Homebrew packages are swapped when asked, and the machine state forgets
+//! the old profile.
+
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::error::OrdinatorError;
+use crate::progress::ProgressMode;
+use crate::state::MachineState;
+
+/// What `ordinator profile switch` should do
+#[derive(Debug, Clone)]
+pub struct SwitchOptions {
+    /// Profile to switch away from; the current profile when None
+    pub from: Option<String>,
+    pub to: String,
+
+    /// Uninstall formulas and casks only the old profile lists, and install the new profile's
+    pub brew: bool,
+
+    pub skip_secrets: bool,
+    pub skip_bootstrap: bool,
+    pub force: bool,
+    pub jobs: usize,
+    pub progress: ProgressMode,
+    pub dry_run: bool,
+    pub quiet: bool,
+    pub verbose: bool,
+}
+
+/// What switching changes before the new profile is applied
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SwitchPlan {
+    /// Symlinks the old profile made at destinations the new one doesn't track
+    pub unlink: Vec<PathBuf>,
+
+    /// Formulas and casks the old profile lists and the new one doesn't
+    pub formulas: Vec<String>,
+    pub casks: Vec<String>,
+}
+
+/// Work out what to remove when going from `from` to `to`
+pub fn plan(config: &Config, home_dir: &Path, from: &str, to: &str) -> Result<SwitchPlan> {
+    let old = config.resolve_profile(from)?;
+    let new = config.resolve_profile(to)?;
+    let only_old = |names: &[String], keep: &[String]| -> Vec<String> {
+        names
+            .iter()
+            .filter(|name| !keep.contains(name))
+            .cloned()
+            .collect()
+    };
+    Ok(SwitchPlan {
+        unlink: links_to_remove(
+            &config.link_targets(&old, false)?,
+            &config.link_targets(&new, false)?,
+            home_dir,
+        ),
+        formulas: only_old(&old.homebrew_formulas, &new.homebrew_formulas),
+        casks: only_old(&old.homebrew_casks, &new.homebrew_casks),
+    })
+}
+
+/// Destinations among the old link targets that the new ones don't cover.
+/// A destination counts as the old profile's only while it's still a
+/// symlink to the old profile's stored copy; anything else there is left alone.
+fn links_to_remove(
+    old: &[(String, String, PathBuf)],
+    new: &[(String, String, PathBuf)],
+    home_dir: &Path,
+) -> Vec<PathBuf> {
+    let kept: BTreeSet<PathBuf> = new
+        .iter()
+        .map(|(path, _, _)| crate::paths::destination(home_dir, path))
+        .collect();
+    let mut unlink = Vec::new();
+    for (path, _, source) in old {
+        let dest = crate::paths::destination(home_dir, path);
+        if !kept.contains(&dest)
+            && crate::utils::is_symlink(&dest)
+            && crate::utils::symlink_points_to(&dest, source)
+            && !unlink.contains(&dest)
+        {
+            unlink.push(dest);
+        }
+    }
+    unlink
+}
+
+/// Switch profiles, returning the profile switched away from
+pub async fn run(config: &Config, config_path: &Path, options: &SwitchOptions) -> Result<String> {
+    let home_dir = config.target_root()?;
+    let state = MachineState::load(&home_dir)?;
+    let from = match options
+        .from
+        .clone()
+        .or_else(|| state.current_profile.clone())
+    {
+        Some(from) => from,
+        None => return Err(OrdinatorError::NoProfileApplied(options.to.clone()).into()),
+    };
+    for name in [&from, &options.to] {
+        if !config.profiles.contains_key(name) {
+            return Err(OrdinatorError::ProfileNotFound(name.clone()).into());
+        }
+    }
+    if from == options.to {
+        return Err(OrdinatorError::AlreadyCurrentProfile(from).into());
+    }
+
+    let plan = plan(config, &home_dir, &from, &options.to)?;
+    if !options.quiet {
+        eprintln!("Switching from '{from}' to '{}'", options.to);
+    }
+    for dest in &plan.unlink {
+        if options.dry_run {
+            eprintln!("DRY-RUN: Would remove symlink: {}", dest.display());
+        } else {
+            crate::discard::delete(dest)?;
+            if !options.quiet {
+                eprintln!("Removed symlink: {}", dest.display());
+            }
+        }
+    }
+    if options.brew {
+        crate::brew::BrewManager::new(options.dry_run)
+            .uninstall_packages(&plan.formulas, &plan.casks)
+            .await?;
+    }
+
+    let apply = crate::apply::ApplyOptions {
+        profiles: vec![options.to.clone()],
+        machine: None,
+        home_dir: home_dir.clone(),
+        filter: crate::filter::PathFilter::default(),
+        skip_bootstrap: options.skip_bootstrap,
+        skip_secrets: options.skip_secrets,
+        skip_brew: !options.brew,
+        skip_defaults: false,
+        no_secrets_for: Vec::new(),
+        shared: None,
+        frozen: false,
+        force: options.force,
+        sudo_script: None,
+        snapshot: None,
+        strict: false,
+        jobs: options.jobs,
+        progress: options.progress,
+        dry_run: options.dry_run,
+        quiet: options.quiet,
+        verbose: options.verbose,
+    };
+    crate::apply::run(config, config_path, &apply).await?;
+
+    if !options.dry_run {
+        // Apply recorded the new profile; the old one is no longer applied here
+        let mut state = MachineState::load(&home_dir)?;
+        state.applied.remove(&from);
+        state.save(&home_dir)?;
+    }
+    Ok(from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_links_to_remove_keeps_shared_and_replaced_paths() {
+        let temp = tempfile::tempdir().unwrap();
+        let home = temp.path().join("home");
+        let stored = temp.path().join("files/work");
+        std::fs::create_dir_all(&home).unwrap();
+        std::fs::create_dir_all(&stored).unwrap();
+
+        let target = |path: &str| {
+            let source = stored.join(path.trim_start_matches("~/"));
+            (path.to_string(), "work".to_string(), source)
+        };
+        let old = [target("~/.work"), target("~/.shared"), target("~/.edited")];
+        for (path, _, source) in &old {
+            std::fs::write(source, "").unwrap();
+            std::os::unix::fs::symlink(source, crate::paths::destination(&home, path)).unwrap();
+        }
+        // Replaced by hand since apply, so it isn't the old profile's any more
+        std::fs::remove_file(home.join(".edited")).unwrap();
+        std::fs::write(home.join(".edited"), "mine").unwrap();
+
+        let new = [target("~/.shared")];
+        assert_eq!(links_to_remove(&old, &new, &home), [home.join(".work")]);
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::Result;
+use minijinja::{Environment, UndefinedBehavior};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Suffix that marks a tracked file as a template
+pub const TEMPLATE_SUFFIX: &str = ".tmpl";
+
+/// Whether a tracked path is a template rendered during apply
+pub fn is_template(path: &str) -> bool {
+    path.len() > TEMPLATE_SUFFIX.len() && path.ends_with(TEMPLATE_SUFFIX)
+}
+
+/// Destination path for a tracked path: templates lose their `.tmpl` suffix
+pub fn rendered_path(path: &str) -> &str {
+    if is_template(path) {
+        &path[..path.len() - TEMPLATE_SUFFIX.len()]
+    } else {
+        path
+    }
+}
+
+/// Render template source with the given variables.
+/// Referencing a variable that is not defined is an error.
+pub fn render(source: &str, vars: &HashMap<String, String>) -> Result<String> {
+    let mut env = Environment::new();
+    env.set_keep_trailing_newline(true);
+    env.set_undefined_behavior(UndefinedBehavior::Strict);
+    env.render_str(source, vars)
+        .map_err(|e| anyhow::anyhow!("{e}"))
+}
+
+/// Render a template file with the given variables
+pub fn render_file(path: &Path, vars: &HashMap<String, String>) -> Result<String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read template {}: {}", path.display(), e))?;
+    render(&source, vars)
+        .map_err(|e| anyhow::anyhow!("Failed to render template {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rendered_path() {
+        assert!(is_template(".gitconfig.tmpl"));
+        assert!(!is_template(".gitconfig"));
+        assert!(!is_template(".tmpl"));
+        assert_eq!(rendered_path(".gitconfig.tmpl"), ".gitconfig");
+        assert_eq!(rendered_path(".zshrc"), ".zshrc");
+    }
+
+    #[test]
+    fn test_render() {
+        let vars = HashMap::from([("email".to_string(), "me@example.com".to_string())]);
+        let rendered = render("[user]\n  email = {{ email }}\n", &vars).unwrap();
+        assert_eq!(rendered, "[user]\n  email = me@example.com\n");
+    }
+
+    #[test]
+    fn test_render_undefined_variable() {
+        let err = render("proxy = {{ proxy_url }}", &HashMap::new()).unwrap_err();
+        assert!(err.to_string().contains("undefined"));
+    }
+}
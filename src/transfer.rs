@@ -0,0 +1,201 @@
+//! Network transfers for clone and pull: retries with backoff when a remote
+//! drops the connection or rate-limits, and a progress bar while objects
+//! download.
+
+use git2::{ErrorClass, ErrorCode, RemoteCallbacks};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use is_terminal::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Times a failed transfer is retried when `--retries` isn't given
+pub const DEFAULT_RETRIES: u32 = 3;
+
+/// Longest wait between attempts, also the cap on a server's `Retry-After`
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Hide transfer progress and retry notices (`--quiet`)
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Whether `--quiet` was given
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Wait before retry `attempt` (1-based): 2s, 4s, 8s, ... up to a minute
+pub fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs(2u64.saturating_pow(attempt.min(6))).min(MAX_BACKOFF)
+}
+
+/// Wait a server asked for with `Retry-After: <seconds>`, capped at a minute
+pub fn retry_after(header: Option<&str>) -> Option<Duration> {
+    let seconds = header?.trim().parse::<u64>().ok()?;
+    Some(Duration::from_secs(seconds).min(MAX_BACKOFF))
+}
+
+/// Whether a git error is worth retrying: dropped connections, timeouts,
+/// rate limits, and server errors. Bad credentials and missing repositories
+/// fail the same way every time.
+pub fn is_transient(error: &git2::Error) -> bool {
+    let message = error.message().to_lowercase();
+    if error.code() == ErrorCode::Auth
+        || ["401", "403", "404", "not found", "authentication"]
+            .iter()
+            .any(|fatal| message.contains(fatal))
+    {
+        return false;
+    }
+    matches!(
+        error.class(),
+        ErrorClass::Net | ErrorClass::Http | ErrorClass::Os | ErrorClass::Ssh
+    ) || [
+        "timed out",
+        "connection reset",
+        "early eof",
+        "429",
+        "500",
+        "502",
+        "503",
+        "504",
+    ]
+    .iter()
+    .any(|transient| message.contains(transient))
+}
+
+/// Say why `what` failed and wait `wait` before retry `failures` of `retries`
+pub fn wait_to_retry(what: &str, reason: &str, failures: u32, retries: u32, wait: Duration) {
+    if !quiet() {
+        eprintln!(
+            "⚠️  Warning: {what} failed: {reason}. Retrying in {}s ({failures}/{retries})",
+            wait.as_secs()
+        );
+    }
+    std::thread::sleep(wait);
+}
+
+/// Run `attempt` and retry it up to `retries` times while it fails with a
+/// transient error, waiting longer each time
+pub fn retry<T>(
+    retries: u32,
+    what: &str,
+    mut attempt: impl FnMut() -> Result<T, git2::Error>,
+) -> Result<T, git2::Error> {
+    let mut failures = 0;
+    loop {
+        match attempt() {
+            Err(e) if failures < retries && is_transient(&e) => {
+                failures += 1;
+                wait_to_retry(what, e.message(), failures, retries, backoff(failures));
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Progress bar for objects received from a remote, drawn on stderr when
+/// it's a terminal
+pub struct TransferBar {
+    bar: Option<ProgressBar>,
+}
+
+impl TransferBar {
+    /// Start a bar labelled `task` (e.g. `"clone"`)
+    pub fn start(task: &'static str) -> Self {
+        if quiet() || !std::io::stderr().is_terminal() {
+            return Self { bar: None };
+        }
+        let bar = ProgressBar::with_draw_target(None, ProgressDrawTarget::stderr());
+        if let Ok(style) =
+            ProgressStyle::with_template("{prefix} [{bar:30}] {pos}/{len} objects {wide_msg}")
+        {
+            bar.set_style(style.progress_chars("=> "));
+        }
+        bar.set_prefix(task);
+        Self { bar: Some(bar) }
+    }
+
+    fn update(&self, stats: &git2::Progress) {
+        let Some(bar) = &self.bar else {
+            return;
+        };
+        bar.set_length(stats.total_objects() as u64);
+        bar.set_position(stats.received_objects() as u64);
+        let received = indicatif::HumanBytes(stats.received_bytes() as u64);
+        if stats.received_objects() == stats.total_objects() && stats.total_deltas() > 0 {
+            bar.set_message(format!(
+                "{received}, resolving deltas {}/{}",
+                stats.indexed_deltas(),
+                stats.total_deltas()
+            ));
+        } else {
+            bar.set_message(received.to_string());
+        }
+    }
+
+    /// Report progress through this bar from `callbacks`
+    pub fn attach<'a>(&'a self, callbacks: &mut RemoteCallbacks<'a>) {
+        callbacks.transfer_progress(move |stats| {
+            self.update(&stats);
+            true
+        });
+    }
+
+    /// Clear the bar once the transfer is done or has failed
+    pub fn finish(self) {
+        if let Some(bar) = self.bar {
+            bar.finish_and_clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_and_retry_after() {
+        assert_eq!(backoff(1), Duration::from_secs(2));
+        assert_eq!(backoff(3), Duration::from_secs(8));
+        assert_eq!(backoff(20), MAX_BACKOFF);
+        assert_eq!(retry_after(Some("5")), Some(Duration::from_secs(5)));
+        assert_eq!(retry_after(Some("3600")), Some(MAX_BACKOFF));
+        assert_eq!(retry_after(Some("Wed, 21 Oct 2015 07:28:00 GMT")), None);
+        assert_eq!(retry_after(None), None);
+    }
+
+    #[test]
+    fn test_only_transient_errors_are_retried() {
+        let reset = git2::Error::new(ErrorCode::GenericError, ErrorClass::Net, "connection reset");
+        let limited = git2::Error::new(
+            ErrorCode::GenericError,
+            ErrorClass::Callback,
+            "unexpected http status code: 429",
+        );
+        let denied = git2::Error::new(ErrorCode::Auth, ErrorClass::Http, "authentication failed");
+        let missing = git2::Error::new(
+            ErrorCode::GenericError,
+            ErrorClass::Http,
+            "unexpected http status code: 404",
+        );
+        assert!(is_transient(&reset));
+        assert!(is_transient(&limited));
+        assert!(!is_transient(&denied));
+        assert!(!is_transient(&missing));
+
+        let mut calls = 0;
+        let result = retry(3, "fetch", || {
+            calls += 1;
+            Err::<(), _>(git2::Error::new(
+                ErrorCode::Auth,
+                ErrorClass::Http,
+                "authentication failed",
+            ))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+}
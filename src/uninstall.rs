@@ -0,0 +1,690 @@
+//! `ordinator uninstall`: take back what apply put on this machine. Links,
+//! managed blocks, decrypted secrets, launchd agents, the directories apply
+//! created, and the files ordinator generated are removed, with backups put
+//! back on request. Homebrew packages and macOS defaults are left alone.
+
+use anyhow::Result;
+use colored::*;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use crate::config::Config;
+use crate::directory::DirectoryLink;
+use crate::error::OrdinatorError;
+use crate::journal::ApplyJournal;
+use crate::state::MachineState;
+use crate::utils::color_enabled;
+
+/// What `ordinator uninstall` should do
+#[derive(Debug, Clone, Default)]
+pub struct UninstallOptions {
+    /// Profile to uninstall; the profiles applied on this machine when None
+    pub profile: Option<String>,
+
+    /// Uninstall every profile in the config, applied or not
+    pub all: bool,
+
+    /// Put back the newest backup of each file a link replaced
+    pub restore_backups: bool,
+
+    /// Remove everything without asking
+    pub force: bool,
+
+    /// Overwrite decrypted secrets with random data before removing them
+    pub shred: bool,
+
+    /// Delete the dotfiles repository once everything else is gone
+    pub purge_repo: bool,
+
+    pub dry_run: bool,
+    pub quiet: bool,
+}
+
+/// What an uninstall removed, and what it left behind and why
+#[derive(Debug, Default)]
+struct Summary {
+    profiles: usize,
+    symlinks: usize,
+    secrets: usize,
+    agents: usize,
+    directories: usize,
+    generated: usize,
+    backups: usize,
+
+    /// Links and managed blocks left in place, still pointing into the repository
+    kept: usize,
+    untouched: Vec<String>,
+}
+
+/// Uninstall the profiles `options` selects, printing what happens to each
+/// path and a summary of what was removed and what was left untouched
+pub fn run(config: &Config, config_path: &Path, options: &UninstallOptions) -> Result<()> {
+    let dotfiles_dir = config_path.parent().unwrap();
+    let home_dir = config.target_root()?;
+    let mut state = MachineState::load(&home_dir)?;
+
+    let (profiles, target) = select_profiles(config, &state, options)?;
+    info!("Uninstalling dotfiles for profile: {}", target);
+    if !options.quiet {
+        eprintln!("Uninstalling dotfiles for profile: {target}");
+    }
+
+    let mut summary = Summary::default();
+    for profile_name in &profiles {
+        uninstall_profile(
+            config,
+            dotfiles_dir,
+            &home_dir,
+            profile_name,
+            options,
+            &mut summary,
+        )?;
+    }
+    let secrets_kept = remove_secrets(dotfiles_dir, &profiles, options, &mut summary)?;
+    remove_agents(config, &home_dir, &profiles, options, &mut summary);
+
+    // Parent directories apply created go once nothing is left in them
+    for dir in ApplyJournal::created_directories(dotfiles_dir, &profiles)? {
+        if options.dry_run {
+            eprintln!("Would remove directory if empty: {}", dir.display());
+        } else if std::fs::remove_dir(&dir).is_ok() {
+            if !options.quiet {
+                eprintln!("Removed empty directory: {}", dir.display());
+            }
+            summary.directories += 1;
+        }
+    }
+
+    if !options.dry_run && profiles.iter().any(|name| state.applied.contains_key(name)) {
+        for profile_name in &profiles {
+            state.record_uninstall(profile_name);
+        }
+        state.save(&home_dir)?;
+    }
+    let still_applied: Vec<&String> = state
+        .applied
+        .keys()
+        .filter(|name| !profiles.contains(name))
+        .collect();
+
+    remove_generated(
+        dotfiles_dir,
+        &home_dir,
+        &profiles,
+        still_applied.is_empty(),
+        secrets_kept,
+        options,
+        &mut summary,
+    );
+
+    // Links still in place would point into a deleted repository
+    let keep_repo = if !still_applied.is_empty() {
+        Some("other profiles are still applied")
+    } else if summary.kept > 0 {
+        Some("some links or managed blocks were kept")
+    } else {
+        None
+    };
+    let purged = options.purge_repo
+        && match keep_repo {
+            Some(reason) => {
+                eprintln!(
+                    "⚠️  Warning: Not deleting {}: {reason}",
+                    dotfiles_dir.display()
+                );
+                false
+            }
+            None => purge_dotfiles_repo(dotfiles_dir, &home_dir, options.force, options.dry_run),
+        };
+
+    if !still_applied.is_empty() {
+        summary.untouched.push(format!(
+            "profiles still applied: {}",
+            still_applied
+                .iter()
+                .map(|name| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    if profiles
+        .iter()
+        .filter_map(|profile| config.get_profile(profile))
+        .any(|profile| {
+            !profile.homebrew_formulas.is_empty()
+                || !profile.homebrew_casks.is_empty()
+                || !profile.defaults.is_empty()
+        })
+    {
+        summary
+            .untouched
+            .push("Homebrew packages and macOS defaults".to_string());
+    }
+    if let Some(key) = config
+        .secrets
+        .age_key_file
+        .as_ref()
+        .filter(|key| key.exists())
+    {
+        summary
+            .untouched
+            .push(format!("the age key at {}", key.display()));
+    }
+    if !purged {
+        summary.untouched.push(format!(
+            "the dotfiles repository at {}{}",
+            dotfiles_dir.display(),
+            match keep_repo {
+                Some(reason) if options.purge_repo => format!(" (not deleted: {reason})"),
+                _ if options.purge_repo => String::new(),
+                _ => " (delete it with --purge-repo)".to_string(),
+            }
+        ));
+    }
+    summary.print();
+    Ok(())
+}
+
+/// The profiles to uninstall and how to name them: `--profile`, else the
+/// profiles applied here unless `--all`, else every profile in the config
+fn select_profiles(
+    config: &Config,
+    state: &MachineState,
+    options: &UninstallOptions,
+) -> Result<(Vec<String>, String)> {
+    let applied: Vec<String> = state
+        .applied
+        .keys()
+        .filter(|name| config.profiles.contains_key(*name))
+        .cloned()
+        .collect();
+    if let Some(profile_name) = &options.profile {
+        if !config.profiles.contains_key(profile_name) {
+            return Err(OrdinatorError::ProfileNotFound(profile_name.to_string()).into());
+        }
+        return Ok((vec![profile_name.clone()], profile_name.clone()));
+    }
+    if !options.all && !applied.is_empty() {
+        let target = format!("{} (applied)", applied.join(", "));
+        return Ok((applied, target));
+    }
+    let profiles = config.list_profiles();
+    if profiles.is_empty() {
+        return Err(OrdinatorError::NoProfiles.into());
+    }
+    let profiles = profiles.into_iter().map(|s| s.to_string()).collect();
+    Ok((profiles, "all".to_string()))
+}
+
+/// Remove one profile's links and managed blocks, restoring backups with
+/// `--restore-backups`
+fn uninstall_profile(
+    config: &Config,
+    dotfiles_dir: &Path,
+    home_dir: &Path,
+    profile_name: &str,
+    options: &UninstallOptions,
+    summary: &mut Summary,
+) -> Result<()> {
+    let Some(profile_cfg) = config.get_profile(profile_name) else {
+        return Ok(());
+    };
+    if !options.quiet {
+        if color_enabled() {
+            eprintln!("🔧 Processing profile: {}", profile_name.cyan());
+        } else {
+            eprintln!("Processing profile: {profile_name}");
+        }
+    }
+
+    if profile_cfg.files.is_empty() && profile_cfg.directories.is_empty() {
+        eprintln!("Info: Profile '{profile_name}' has no tracked files. Nothing to uninstall.");
+        return Ok(());
+    }
+
+    // Tracked files, then each tracked directory: the directory itself when
+    // it's linked as a whole, otherwise every file linked inside it
+    let mut targets: Vec<(String, bool)> = profile_cfg
+        .files
+        .iter()
+        .map(|file| (file.clone(), false))
+        .collect();
+    for directory in &profile_cfg.directories {
+        let entry = crate::config::ResolvedDirectory {
+            directory: directory.clone(),
+            profile: profile_name.to_string(),
+        };
+        let whole = directory.link == DirectoryLink::Directory;
+        for (path, _, _) in config.directory_link_targets(&entry, false)? {
+            targets.push((path, whole));
+        }
+    }
+
+    // Backups come newest first
+    let mut files_with_backups = Vec::new();
+    if options.restore_backups {
+        let backup_dir = crate::backups::BackupDir::new(config, dotfiles_dir, home_dir)?;
+        for (file_path, _) in &targets {
+            if let Some(latest) = backup_dir.backups_for(file_path)?.into_iter().next() {
+                files_with_backups.push((file_path.clone(), latest.path));
+            }
+        }
+    }
+    let total_to_restore = files_with_backups.len();
+    let mut restore_idx = 0;
+
+    let mut symlinks_removed = 0;
+    let mut backups_restored = 0;
+    let mut blocks_removed = 0;
+    for (file_path, is_directory_link) in &targets {
+        let target_path = crate::paths::destination(home_dir, file_path);
+        if !options.quiet {
+            if color_enabled() {
+                eprintln!(
+                    "  📁 Checking: {}",
+                    target_path.display().to_string().yellow()
+                );
+            } else {
+                eprintln!("  Checking: {}", target_path.display());
+            }
+        }
+        match remove_link(
+            config,
+            profile_name,
+            file_path,
+            &target_path,
+            *is_directory_link,
+            options,
+        )? {
+            Removed::Link => symlinks_removed += 1,
+            Removed::Block => blocks_removed += 1,
+            Removed::Kept(reason) => {
+                summary.kept += 1;
+                summary.untouched.push(reason);
+            }
+            Removed::Nothing(Some(reason)) => summary.untouched.push(reason),
+            Removed::Nothing(None) => {}
+        }
+
+        if !options.restore_backups {
+            continue;
+        }
+        if let Some((_, latest_backup)) = files_with_backups.iter().find(|(f, _)| f == file_path) {
+            restore_idx += 1;
+            eprintln!(
+                "Restoring backup {restore_idx}/{total_to_restore} for profile {profile_name}: {}",
+                target_path.display()
+            );
+            if restore_backup(latest_backup, &target_path, options) {
+                backups_restored += 1;
+            }
+        } else if !config.global.create_backups.unwrap_or(true) {
+            eprintln!("Backups are disabled in config. No backups will be restored.");
+        }
+    }
+
+    summary.symlinks += symlinks_removed;
+    summary.backups += backups_restored;
+    summary.profiles += 1;
+
+    eprintln!("Profile '{profile_name}' summary: {symlinks_removed} symlinks removed, {backups_restored} backups restored");
+    if blocks_removed > 0 {
+        eprintln!("  Managed blocks removed: {blocks_removed}");
+    }
+    Ok(())
+}
+
+/// What [`remove_link`] took away from a destination
+enum Removed {
+    Link,
+    Block,
+
+    /// Nothing: the link or managed block stays, for the reason given
+    Kept(String),
+
+    /// Nothing, and why when the user should hear about it in the summary
+    Nothing(Option<String>),
+}
+
+/// Remove what apply put at `target_path`: the link, or only the managed
+/// block in a file with `managed_block = true`. Anything else stays.
+fn remove_link(
+    config: &Config,
+    profile_name: &str,
+    file_path: &str,
+    target_path: &Path,
+    is_directory_link: bool,
+    options: &UninstallOptions,
+) -> Result<Removed> {
+    let place = target_path.display();
+    if crate::utils::is_symlink(target_path) {
+        let kind = if is_directory_link {
+            "directory symlink"
+        } else {
+            "symlink"
+        };
+        if options.dry_run {
+            if options.force {
+                eprintln!("Would remove {kind}: {place}");
+            } else {
+                eprintln!("Would prompt to remove {kind}: {place}");
+            }
+            return Ok(Removed::Link);
+        }
+        if !options.force && !crate::prompt::confirm(&format!("Remove {kind} at {place}?"), false) {
+            eprintln!("Skipped {kind} removal: {place}");
+            return Ok(Removed::Kept(format!("{place} (skipped)")));
+        }
+        return Ok(match crate::discard::delete(target_path) {
+            Ok(()) => {
+                eprintln!("Removed {kind}: {place}");
+                Removed::Link
+            }
+            Err(e) => {
+                eprintln!("Error: {e:#}");
+                Removed::Kept(format!("{place} (couldn't be removed)"))
+            }
+        });
+    }
+
+    if config.file_options(profile_name, file_path).managed_block
+        && crate::block::read(target_path)?.is_some()
+    {
+        // Only ordinator's lines go; the rest of the file stays
+        if options.dry_run {
+            eprintln!("Would remove the managed block from {place}");
+            return Ok(Removed::Block);
+        }
+        if !options.force
+            && !crate::prompt::confirm(&format!("Remove the managed block from {place}?"), false)
+        {
+            eprintln!("Skipped removing the managed block from {place}");
+            return Ok(Removed::Kept(format!("{place} (managed block kept)")));
+        }
+        let text = std::fs::read_to_string(target_path)?;
+        if let Some(rest) = crate::block::remove(&text) {
+            std::fs::write(target_path, rest)?;
+        }
+        eprintln!("Removed the managed block from {place}");
+        return Ok(Removed::Block);
+    }
+
+    if is_directory_link && target_path.is_dir() {
+        eprintln!("Directory exists (not a symlink): {place}. Skipping.");
+        Ok(Removed::Nothing(Some(format!("{place} (not a symlink)"))))
+    } else if target_path.exists() {
+        eprintln!("File exists (not a symlink): {place}. Skipping.");
+        Ok(Removed::Nothing(Some(format!("{place} (not a symlink)"))))
+    } else {
+        eprintln!("File does not exist: {place}. Skipping.");
+        Ok(Removed::Nothing(None))
+    }
+}
+
+/// Copy `backup` back to `target_path` after confirmation; returns whether
+/// it was (or, in a dry run, would be) restored
+fn restore_backup(backup: &Path, target_path: &Path, options: &UninstallOptions) -> bool {
+    let place = target_path.display();
+    if options.dry_run {
+        if options.force {
+            eprintln!("Would restore from backup: {place}");
+        } else {
+            eprintln!("Would prompt to restore from backup: {place}");
+        }
+        return true;
+    }
+    if !options.force && !crate::prompt::confirm(&format!("Restore backup to {place}?"), false) {
+        eprintln!("Skipped backup restoration: {place}");
+        return false;
+    }
+    if crate::backups::copy_back(backup, target_path).is_ok() {
+        eprintln!("Restored from backup: {place}");
+        true
+    } else {
+        eprintln!("Error: Failed to restore from backup: {place}");
+        false
+    }
+}
+
+/// Remove the secrets apply decrypted for `profiles`. Decrypted secrets are
+/// copies, not symlinks; the apply journals say where they went. Returns
+/// whether the user chose to keep them.
+fn remove_secrets(
+    dotfiles_dir: &Path,
+    profiles: &[String],
+    options: &UninstallOptions,
+    summary: &mut Summary,
+) -> Result<bool> {
+    let secrets: Vec<_> = ApplyJournal::decrypted_secrets(dotfiles_dir)?
+        .into_iter()
+        .filter(|secret| profiles.iter().any(|profile| secret.applied_with(profile)))
+        .collect();
+    if secrets.is_empty() {
+        return Ok(false);
+    }
+    let confirmed = options.force
+        || options.dry_run
+        || crate::prompt::confirm(
+            &format!("Remove {} decrypted secret file(s)?", secrets.len()),
+            false,
+        );
+    if !confirmed {
+        eprintln!("Skipped removing decrypted secrets");
+        summary
+            .untouched
+            .push(format!("{} decrypted secret(s)", secrets.len()));
+        return Ok(true);
+    }
+    let removed = crate::secrets::remove_decrypted_secrets(
+        &secrets,
+        options.shred,
+        options.dry_run,
+        options.quiet,
+    );
+    summary.secrets = removed.len();
+    for secret in removed
+        .iter()
+        .filter(|_| options.restore_backups && !options.dry_run)
+    {
+        if let Some(backup) = secret.original_backup.as_ref().filter(|b| b.is_file()) {
+            if std::fs::copy(backup, &secret.path).is_ok() {
+                eprintln!("Restored from backup: {}", secret.path.display());
+                summary.backups += 1;
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Unload and remove the launchd agents of `profiles`. They're copies too.
+fn remove_agents(
+    config: &Config,
+    home_dir: &Path,
+    profiles: &[String],
+    options: &UninstallOptions,
+    summary: &mut Summary,
+) {
+    let agents: Vec<&String> = profiles
+        .iter()
+        .filter_map(|profile| config.get_profile(profile))
+        .flat_map(|profile| &profile.launch_agents)
+        .collect();
+    if agents.is_empty() {
+        return;
+    }
+    let confirmed = options.force
+        || options.dry_run
+        || crate::prompt::confirm(
+            &format!("Unload and remove {} launchd agent(s)?", agents.len()),
+            false,
+        );
+    if !confirmed {
+        eprintln!("Skipped removing launchd agents");
+        summary
+            .untouched
+            .push(format!("{} launchd agent(s)", agents.len()));
+        return;
+    }
+    let launchd_manager = crate::launchd::LaunchdManager::new(options.dry_run);
+    let agents_dir = crate::launchd::agents_dir(home_dir);
+    for agent in agents {
+        match launchd_manager.remove(agent, &agents_dir) {
+            Ok(true) => {
+                let verb = if options.dry_run {
+                    "Would remove"
+                } else {
+                    "Removed"
+                };
+                eprintln!("{verb} launchd agent: {}", agents_dir.join(agent).display());
+                summary.agents += 1;
+            }
+            Ok(false) => {}
+            Err(e) => eprintln!("Error: Failed to remove launchd agent {agent}: {e}"),
+        }
+    }
+}
+
+/// Remove the files ordinator generated on this machine. The journals, logs,
+/// hash cache, and state file go only once no profile is applied any more
+/// (`last_profile`), and the journals not while decrypted secrets they
+/// record are kept.
+fn remove_generated(
+    dotfiles_dir: &Path,
+    home_dir: &Path,
+    profiles: &[String],
+    last_profile: bool,
+    secrets_kept: bool,
+    options: &UninstallOptions,
+    summary: &mut Summary,
+) {
+    let mut generated: Vec<PathBuf> = profiles
+        .iter()
+        .map(|profile| {
+            dotfiles_dir
+                .join("scripts")
+                .join(profile)
+                .join("bootstrap-secrets.env")
+        })
+        .collect();
+    if last_profile {
+        if !secrets_kept {
+            generated.push(dotfiles_dir.join("transactions"));
+        }
+        generated.push(dotfiles_dir.join("logs"));
+        generated.push(dotfiles_dir.join("cache"));
+        generated.push(crate::snapshot::snapshots_dir(home_dir));
+        generated.push(crate::run_log::logs_dir(home_dir));
+        generated.push(crate::receipts::path(home_dir));
+        generated.push(MachineState::path(home_dir));
+    }
+    generated.retain(|path| path.exists());
+    if generated.is_empty() {
+        return;
+    }
+    let confirmed = options.force
+        || options.dry_run
+        || crate::prompt::confirm(
+            &format!(
+                "Remove {} generated file(s) (bootstrap secrets, apply journals, logs, bootstrap receipts, cache, snapshots, machine state)?",
+                generated.len()
+            ),
+            false,
+        );
+    if !confirmed {
+        eprintln!("Skipped removing generated files");
+        summary
+            .untouched
+            .extend(generated.iter().map(|path| path.display().to_string()));
+        return;
+    }
+    for path in &generated {
+        if options.dry_run {
+            eprintln!("Would remove generated file: {}", path.display());
+            summary.generated += 1;
+            continue;
+        }
+        let removed = if path.is_dir() {
+            std::fs::remove_dir_all(path)
+        } else {
+            std::fs::remove_file(path)
+        };
+        match removed {
+            Ok(()) => {
+                if !options.quiet {
+                    eprintln!("Removed generated file: {}", path.display());
+                }
+                summary.generated += 1;
+            }
+            Err(e) => eprintln!(
+                "Error: Failed to remove generated file {}: {e}",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// Delete the dotfiles repository after confirmation; returns whether it was
+/// (or, in a dry run, would be) deleted. A repository that holds the home
+/// directory is never deleted.
+fn purge_dotfiles_repo(dotfiles_dir: &Path, home_dir: &Path, force: bool, dry_run: bool) -> bool {
+    if home_dir.starts_with(dotfiles_dir) {
+        eprintln!(
+            "⚠️  Warning: Not deleting {}: it contains the home directory",
+            dotfiles_dir.display()
+        );
+        return false;
+    }
+    if dry_run {
+        eprintln!(
+            "Would delete the dotfiles repository: {}",
+            dotfiles_dir.display()
+        );
+        return true;
+    }
+    if !force
+        && !crate::prompt::confirm(
+            &format!(
+                "Delete the dotfiles repository at {}? Anything not pushed is lost.",
+                dotfiles_dir.display()
+            ),
+            false,
+        )
+    {
+        eprintln!("Skipped deleting the dotfiles repository");
+        return false;
+    }
+    match std::fs::remove_dir_all(dotfiles_dir) {
+        Ok(()) => {
+            eprintln!(
+                "Deleted the dotfiles repository: {}",
+                dotfiles_dir.display()
+            );
+            true
+        }
+        Err(e) => {
+            eprintln!(
+                "Error: Failed to delete the dotfiles repository {}: {e}",
+                dotfiles_dir.display()
+            );
+            false
+        }
+    }
+}
+
+impl Summary {
+    fn print(&self) {
+        eprintln!();
+        eprintln!("Uninstall Summary:");
+        eprintln!("  Profiles processed: {}", self.profiles);
+        eprintln!("  Symlinks removed: {}", self.symlinks);
+        eprintln!("  Decrypted secrets removed: {}", self.secrets);
+        eprintln!("  launchd agents removed: {}", self.agents);
+        eprintln!("  Empty directories removed: {}", self.directories);
+        eprintln!("  Generated files removed: {}", self.generated);
+        eprintln!("  Backups restored: {}", self.backups);
+        eprintln!("Left untouched:");
+        for entry in &self.untouched {
+            eprintln!("  - {entry}");
+        }
+    }
+}
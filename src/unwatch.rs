@@ -0,0 +1,196 @@
+//! What `ordinator unwatch` does with a file it stops tracking: keep the
+//! repository copy, put a real copy back in place of the symlink, or remove
+//! both, so the home directory is never left with a dangling link.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::utils::{is_symlink, symlink_points_to};
+
+/// How unwatch leaves the repository copy and the destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnwatchMode {
+    /// Stop tracking, but keep the repository copy; the symlink still works
+    KeepFile,
+
+    /// Replace the symlink with a real copy, then delete the repository copy
+    Restore,
+
+    /// Delete the repository copy and what apply put at the destination
+    Purge,
+}
+
+impl UnwatchMode {
+    /// Every mode, in the order the prompt offers them
+    pub const ALL: [UnwatchMode; 3] = [Self::Restore, Self::KeepFile, Self::Purge];
+
+    /// What the mode does, as the prompt explains it
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Self::Restore => {
+                "Restore: replace the symlink with a real copy of the file, then delete the repository copy"
+            }
+            Self::KeepFile => {
+                "Keep file: stop tracking, but keep the repository copy (the symlink keeps working until you remove it)"
+            }
+            Self::Purge => "Purge: delete the repository copy and the file in your home directory",
+        }
+    }
+
+    /// Whether the repository copy is deleted
+    pub fn deletes_stored(&self) -> bool {
+        *self != Self::KeepFile
+    }
+}
+
+/// How apply puts a tracked file at its destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// A symlink to the repository copy
+    Linked,
+
+    /// A file written in its place: a rendered template or decrypted file
+    Written,
+
+    /// A managed block inside a file
+    Block,
+}
+
+/// What happened at a destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The symlink was replaced with a copy of the content
+    Restored,
+
+    /// Ordinator's file, link, or block was removed
+    Removed,
+
+    /// Still a symlink to the kept repository copy
+    StillLinked,
+
+    /// Nothing of ordinator's was there
+    Untouched,
+}
+
+/// Release `dest` from the repository copy at `stored` as `mode` says. The
+/// copy itself is left for the caller to delete.
+pub fn release(
+    dest: &Path,
+    stored: &Path,
+    placement: Placement,
+    mode: UnwatchMode,
+) -> Result<Outcome> {
+    if is_symlink(dest) {
+        if !symlink_points_to(dest, stored) {
+            return Ok(Outcome::Untouched);
+        }
+        return match mode {
+            UnwatchMode::KeepFile => Ok(Outcome::StillLinked),
+            UnwatchMode::Purge => {
+                fs::remove_file(dest)?;
+                Ok(Outcome::Removed)
+            }
+            UnwatchMode::Restore => {
+                fs::remove_file(dest)?;
+                let copied = if stored.is_dir() {
+                    crate::utils::copy_dir_recursive(stored, dest)
+                } else {
+                    fs::copy(stored, dest).map(|_| ()).map_err(Into::into)
+                };
+                copied.with_context(|| format!("Failed to copy {} back", dest.display()))?;
+                Ok(Outcome::Restored)
+            }
+        };
+    }
+    if mode != UnwatchMode::Purge || !dest.is_file() {
+        return Ok(Outcome::Untouched);
+    }
+    match placement {
+        Placement::Linked => Ok(Outcome::Untouched),
+        Placement::Written => {
+            fs::remove_file(dest)?;
+            Ok(Outcome::Removed)
+        }
+        Placement::Block => {
+            let text = fs::read_to_string(dest)?;
+            match crate::block::remove(&text) {
+                Some(rest) => {
+                    fs::write(dest, rest)?;
+                    Ok(Outcome::Removed)
+                }
+                None => Ok(Outcome::Untouched),
+            }
+        }
+    }
+}
+
+/// [`release`] for a tracked directory: the directory symlink itself, or
+/// each file linked from `storage`
+pub fn release_directory(
+    dest: &Path,
+    storage: &Path,
+    mode: UnwatchMode,
+) -> Result<Vec<(PathBuf, Outcome)>> {
+    if is_symlink(dest) {
+        let outcome = release(dest, storage, Placement::Linked, mode)?;
+        return Ok(vec![(dest.to_path_buf(), outcome)]);
+    }
+    let mut outcomes = Vec::new();
+    for entry in walkdir::WalkDir::new(storage).min_depth(1) {
+        let entry = entry?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        let file = dest.join(entry.path().strip_prefix(storage)?);
+        let outcome = release(&file, entry.path(), Placement::Linked, mode)?;
+        outcomes.push((file, outcome));
+    }
+    Ok(outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_each_mode() {
+        let temp = tempfile::tempdir().unwrap();
+        let stored = temp.path().join("stored");
+        let dest = temp.path().join(".zshrc");
+        fs::write(&stored, "export EDITOR=vim\n").unwrap();
+        let link = || {
+            let _ = fs::remove_file(&dest);
+            std::os::unix::fs::symlink(&stored, &dest).unwrap();
+        };
+
+        link();
+        let outcome = release(&dest, &stored, Placement::Linked, UnwatchMode::KeepFile);
+        assert_eq!(outcome.unwrap(), Outcome::StillLinked);
+        assert!(is_symlink(&dest));
+
+        let outcome = release(&dest, &stored, Placement::Linked, UnwatchMode::Restore);
+        assert_eq!(outcome.unwrap(), Outcome::Restored);
+        assert!(!is_symlink(&dest));
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "export EDITOR=vim\n");
+
+        // A real file that isn't ordinator's is left alone
+        let outcome = release(&dest, &stored, Placement::Linked, UnwatchMode::Purge);
+        assert_eq!(outcome.unwrap(), Outcome::Untouched);
+        assert!(dest.exists());
+
+        link();
+        let outcome = release(&dest, &stored, Placement::Linked, UnwatchMode::Purge);
+        assert_eq!(outcome.unwrap(), Outcome::Removed);
+        assert!(!dest.exists() && !is_symlink(&dest));
+
+        fs::write(
+            &dest,
+            crate::block::upsert("export PATH=/opt/bin\n", "alias ll='ls -l'"),
+        )
+        .unwrap();
+        let outcome = release(&dest, &stored, Placement::Block, UnwatchMode::Purge);
+        assert_eq!(outcome.unwrap(), Outcome::Removed);
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "export PATH=/opt/bin\n");
+    }
+}
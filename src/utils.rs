@@ -1,23 +1,25 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use chrono::Local;
 use sha2::{Digest, Sha256};
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::error::OrdinatorError;
+
+static STRICT_PATHS: AtomicBool = AtomicBool::new(false);
 
 /// Utility functions for Ordinator
 #[allow(dead_code)]
 /// Get the home directory
 pub fn get_home_dir() -> Result<PathBuf> {
-    if let Ok(path) = std::env::var("ORDINATOR_HOME") {
-        return Ok(PathBuf::from(path));
-    }
-    dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))
+    crate::environment::current().home_dir()
 }
 
-#[allow(dead_code)]
-/// Get the dotfiles directory
-pub fn get_dotfiles_dir() -> Result<PathBuf> {
-    Ok(get_home_dir()?.join(".dotfiles"))
+/// Whether to colorize output (only when stdout is a terminal)
+pub fn color_enabled() -> bool {
+    use is_terminal::IsTerminal;
+    std::io::stdout().is_terminal()
 }
 
 /// Create a symlink with backup
@@ -55,6 +57,21 @@ pub fn is_symlink(path: &Path) -> bool {
         .unwrap_or(false)
 }
 
+/// Permission bits of a file, e.g. `0o644`
+pub fn file_mode(path: &Path) -> Result<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Ok(fs::metadata(path)?.permissions().mode() & 0o7777)
+}
+
+/// Set a file's permission bits, leaving it untouched if they already match
+pub fn set_file_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    if file_mode(path)? != mode {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+    Ok(())
+}
+
 /// Get the target of a symlink
 #[allow(dead_code)]
 pub fn get_symlink_target(path: &Path) -> Result<PathBuf> {
@@ -68,42 +85,87 @@ pub fn validate_symlink(symlink_path: &Path, expected_target: &Path) -> Result<b
         return Ok(false);
     }
 
-    let actual_target = get_symlink_target(symlink_path)?;
-    Ok(actual_target == expected_target && expected_target.exists())
+    Ok(symlink_points_to(symlink_path, expected_target) && expected_target.exists())
 }
 
-/// Check if a symlink is broken (target doesn't exist)
-pub fn is_broken_symlink(path: &Path) -> bool {
-    if !is_symlink(path) {
+/// Compare symlink targets exactly as stored instead of canonically (`--strict-paths`)
+pub fn set_strict_paths(strict: bool) {
+    STRICT_PATHS.store(strict, Ordering::Relaxed);
+}
+
+/// `path` with symlinks, `.`, and `..` resolved. The part that doesn't
+/// exist yet is appended to the canonical form of the part that does.
+pub fn canonical_path(path: &Path) -> PathBuf {
+    if let Ok(canonical) = fs::canonicalize(path) {
+        return canonical;
+    }
+    let mut components = path.components();
+    match components.next_back() {
+        Some(Component::Normal(name)) => canonical_path(components.as_path()).join(name),
+        Some(Component::CurDir) => canonical_path(components.as_path()),
+        Some(Component::ParentDir) => {
+            let parent = canonical_path(components.as_path());
+            parent.parent().map(Path::to_path_buf).unwrap_or(parent)
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Whether `a` and `b` are the same location once canonicalized, so
+/// `/var/folders/x` and `/private/var/folders/x` on macOS are equal
+pub fn same_path(a: &Path, b: &Path) -> bool {
+    a == b || canonical_path(a) == canonical_path(b)
+}
+
+/// Whether the symlink at `link` points at `expected`. A relative target is
+/// resolved from the link's directory, and both are compared canonically,
+/// unless `--strict-paths` asks for the target exactly as stored.
+pub fn symlink_points_to(link: &Path, expected: &Path) -> bool {
+    let Ok(target) = get_symlink_target(link) else {
+        return false;
+    };
+    if target == expected {
+        return true;
+    }
+    if STRICT_PATHS.load(Ordering::Relaxed) {
         return false;
     }
+    let target = match link.parent() {
+        Some(dir) if target.is_relative() => dir.join(target),
+        _ => target,
+    };
+    same_path(&target, expected)
+}
+
+/// An RFC 3339 timestamp in local time, e.g. `2024-05-01 09:30`; anything
+/// unparseable is returned as-is
+pub fn format_timestamp(rfc3339: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(rfc3339)
+        .map(|time| {
+            time.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|_| rfc3339.to_string())
+}
 
-    match get_symlink_target(path) {
-        Ok(target) => !target.exists(),
-        Err(_) => true,
+/// A file size for messages, e.g. `2.5 MiB` or `12 KiB`
+pub fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{} KiB", bytes.div_ceil(1024))
     }
 }
 
-/// Get the next backup number for a file
-fn get_next_backup_number(backup_dir: &Path, filename: &str) -> Result<u32> {
-    let mut number = 1;
-    loop {
-        let backup_name = format!(
-            "{}.backup.{}.{}",
-            filename,
-            number,
-            Local::now().format("%Y%m%d-%H%M%S")
-        );
-        let backup_path = backup_dir.join(backup_name);
-        if !backup_path.exists() {
-            return Ok(number);
-        }
-        number += 1;
-        if number > 1000 {
-            // Prevent infinite loops
-            return Err(anyhow::anyhow!("Too many backup files for {}", filename));
-        }
+/// Check if a symlink is broken (target doesn't exist)
+pub fn is_broken_symlink(path: &Path) -> bool {
+    if !is_symlink(path) {
+        return false;
     }
+
+    // exists() follows the link, resolving relative targets from its directory
+    !path.exists()
 }
 
 /// Check if a file contains secrets (basic heuristic)
@@ -138,64 +200,72 @@ pub fn backup_file_to_dotfiles_backup(original: &Path, config_path: &Path) -> Re
     Ok(backup_path)
 }
 
-/// Enhanced backup with hybrid naming (number.timestamp)
-pub fn backup_file_hybrid(original: &Path, config_path: &Path) -> Result<PathBuf> {
-    let backup_dir = config_path.parent().unwrap().join("backups");
-    std::fs::create_dir_all(&backup_dir)?;
+/// Copy `original` into `backup_dir` as `<name>.backup.<n>.<timestamp>`,
+/// taking the lowest `n` nothing has used yet, so a backup is never overwritten
+pub fn backup_into(original: &Path, backup_dir: &Path) -> Result<PathBuf> {
+    std::fs::create_dir_all(backup_dir)
+        .with_context(|| format!("Failed to create {}", backup_dir.display()))?;
     let filename = original.file_name().unwrap_or_default().to_string_lossy();
-    let backup_number = get_next_backup_number(&backup_dir, &filename)?;
     let timestamp = Local::now().format("%Y%m%d-%H%M%S");
-    let backup_name = format!("{filename}.backup.{backup_number}.{timestamp}");
-    let backup_path = backup_dir.join(backup_name);
-    std::fs::copy(original, &backup_path)?;
+    let backup_path = (1..)
+        .map(|number| backup_dir.join(format!("{filename}.backup.{number}.{timestamp}")))
+        .find(|path| path.symlink_metadata().is_err())
+        .expect("backup numbers are unbounded");
+    if original.is_dir() {
+        copy_dir_recursive(original, &backup_path)?;
+    } else {
+        std::fs::copy(original, &backup_path)?;
+    }
     Ok(backup_path)
 }
 
-/// Create symlink with enhanced conflict resolution
+/// Recursively copy a directory's files to `dst`, creating it as needed
+pub fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
+    for entry in walkdir::WalkDir::new(src) {
+        let entry = entry?;
+        let target = dst.join(entry.path().strip_prefix(src)?);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Create symlink with enhanced conflict resolution, returning the backup path if one was made
 pub fn create_symlink_with_conflict_resolution(
     source: &Path,
     target: &Path,
     force: bool,
-    backup: bool,
-    config_path: &Path,
-) -> Result<()> {
+    backup_dir: Option<&crate::backups::BackupDir>,
+) -> Result<Option<PathBuf>> {
+    let mut backup_path = None;
     if target.exists() {
         if is_symlink(target) {
             // Check if it's already the correct symlink
-            if let Ok(actual_target) = get_symlink_target(target) {
-                if actual_target == source {
-                    return Ok(()); // Already correct
-                }
+            if symlink_points_to(target, source) {
+                return Ok(None); // Already correct
             }
         } else {
             // If it's not a symlink, treat as conflict unless force is set
             if !force {
-                eprintln!(
-                    "[DEBUG] Conflict: target {} exists and is not a symlink. Returning error.",
-                    target.display()
-                );
-                return Err(anyhow::anyhow!(
-                    "Target {} already exists and is not a symlink. Use --force to overwrite.",
-                    target.display()
-                ));
+                return Err(OrdinatorError::TargetExists(target.to_path_buf()).into());
             }
         }
 
         // Backup if enabled
-        if backup {
-            let backup_path = backup_file_hybrid(target, config_path)?;
-            eprintln!(
-                "Backed up {} to {}",
-                target.display(),
-                backup_path.display()
-            );
+        if let Some(backup_dir) = backup_dir {
+            let path = backup_dir.backup(target)?;
+            eprintln!("Backed up {} to {}", target.display(), path.display());
+            backup_path = Some(path);
         }
 
-        // Remove the existing file/symlink
-        if target.is_dir() {
-            fs::remove_dir_all(target)?;
-        } else {
-            fs::remove_file(target)?;
+        // Remove the existing file/symlink; without a backup it goes to the Trash
+        if backup_path.is_some() {
+            crate::discard::delete(target)?;
+        } else if crate::discard::discard(target)? == crate::discard::Discarded::Trashed {
+            eprintln!("Moved {} to the Trash", target.display());
         }
     }
 
@@ -211,7 +281,7 @@ pub fn create_symlink_with_conflict_resolution(
     #[cfg(windows)]
     std::os::windows::fs::symlink_file(source, target)?;
 
-    Ok(())
+    Ok(backup_path)
 }
 
 /// Repair a broken symlink
@@ -223,8 +293,7 @@ pub fn repair_symlink(symlink_path: &Path, expected_target: &Path) -> Result<()>
         ));
     }
 
-    let actual_target = get_symlink_target(symlink_path)?;
-    if actual_target == expected_target && expected_target.exists() {
+    if symlink_points_to(symlink_path, expected_target) && expected_target.exists() {
         return Ok(()); // Already correct
     }
 
@@ -254,6 +323,46 @@ pub fn generate_file_hash(path: &str) -> String {
     format!("{result:x}")[0..6].to_string()
 }
 
+/// Default limit for concurrent file operations: one per CPU, capped at 16
+pub fn default_jobs() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(16)
+}
+
+/// Run `f` on every item on tokio's blocking thread pool, at most `jobs` at a
+/// time. Results come back in the same order as `items`.
+pub async fn map_concurrent<T, R, F>(items: Vec<T>, jobs: usize, f: F) -> Result<Vec<R>>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+    F: Fn(T) -> R + Send + Sync + 'static,
+{
+    let f = std::sync::Arc::new(f);
+    // Workers use this thread's environment, including a test's Override
+    let env = crate::environment::current();
+    let mut results = Vec::with_capacity(items.len());
+    let mut running = std::collections::VecDeque::new();
+    for item in items {
+        if running.len() >= jobs.max(1) {
+            if let Some(task) = running.pop_front() {
+                results.push(task.await?);
+            }
+        }
+        let f = std::sync::Arc::clone(&f);
+        let env = env.clone();
+        running.push_back(tokio::task::spawn_blocking(move || {
+            let _env = crate::environment::Override::new(env);
+            f(item)
+        }));
+    }
+    for task in running {
+        results.push(task.await?);
+    }
+    Ok(results)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -275,14 +384,6 @@ mod tests {
         assert!(home.exists());
     }
 
-    #[test]
-    fn test_get_dotfiles_dir() {
-        std::env::set_var("ORDINATOR_HOME", "/test/home");
-        let dotfiles = get_dotfiles_dir().unwrap();
-        assert_eq!(dotfiles, PathBuf::from("/test/home/.dotfiles"));
-        std::env::remove_var("ORDINATOR_HOME");
-    }
-
     #[test]
     fn test_is_symlink() {
         let dir = tempdir().unwrap();
@@ -390,6 +491,9 @@ mod tests {
     #[test]
     fn test_create_symlink_with_conflict_resolution() {
         let dir = tempdir().unwrap();
+        let _env = crate::environment::Override::new(std::sync::Arc::new(
+            crate::environment::Fake::new(dir.path()),
+        ));
         let config_path = dir.path().join("ordinator.toml");
         let source = dir.path().join("source.txt");
         let target = dir.path().join("target.txt");
@@ -399,54 +503,48 @@ mod tests {
         File::create(&source).unwrap();
 
         // Test creating new symlink
-        create_symlink_with_conflict_resolution(&source, &target, false, false, &config_path)
-            .unwrap();
+        create_symlink_with_conflict_resolution(&source, &target, false, None).unwrap();
         assert!(is_symlink(&target));
 
         // Test with existing correct symlink (should do nothing)
-        create_symlink_with_conflict_resolution(&source, &target, false, false, &config_path)
-            .unwrap();
+        create_symlink_with_conflict_resolution(&source, &target, false, None).unwrap();
         assert!(is_symlink(&target));
 
         // Test with existing file (should fail without force)
         let new_target = dir.path().join("new_target.txt");
-        File::create(&new_target).unwrap();
-        assert!(create_symlink_with_conflict_resolution(
-            &source,
-            &new_target,
-            false,
-            false,
-            &config_path
-        )
-        .is_err());
-
-        // Test with force
-        create_symlink_with_conflict_resolution(&source, &new_target, true, false, &config_path)
-            .unwrap();
+        fs::write(&new_target, "mine").unwrap();
+        assert!(
+            create_symlink_with_conflict_resolution(&source, &new_target, false, None).is_err()
+        );
+
+        // Test with force: without a backup, the file goes to the Trash
+        create_symlink_with_conflict_resolution(&source, &new_target, true, None).unwrap();
         assert!(is_symlink(&new_target));
+        let trashed = dir.path().join(".Trash/new_target.txt");
+        assert_eq!(fs::read_to_string(trashed).unwrap(), "mine");
     }
 
     #[test]
     fn test_create_symlink_with_conflict_resolution_with_backup() {
         let dir = tempdir().unwrap();
-        let config_path = dir.path().join("ordinator.toml");
         let source = dir.path().join("source.txt");
         let target = dir.path().join("target.txt");
 
-        // Create config and source
-        File::create(&config_path).unwrap();
+        // Create source
         File::create(&source).unwrap();
 
         // Create existing file
         File::create(&target).unwrap();
 
-        // Test with backup enabled
-        create_symlink_with_conflict_resolution(&source, &target, true, true, &config_path)
-            .unwrap();
+        // Test with backups going to <dir>/backups
+        let mut config = crate::config::Config::default();
+        config.global.backup_dir = Some("backups".to_string());
+        let backups = crate::backups::BackupDir::new(&config, dir.path(), dir.path()).unwrap();
+        create_symlink_with_conflict_resolution(&source, &target, true, Some(&backups)).unwrap();
         assert!(is_symlink(&target));
 
         // Check backup was created
-        let backup_dir = config_path.parent().unwrap().join("backups");
+        let backup_dir = dir.path().join("backups/home");
         assert!(backup_dir.exists());
         let backup_files: Vec<_> = fs::read_dir(&backup_dir)
             .unwrap()
@@ -475,20 +573,19 @@ mod tests {
     }
 
     #[test]
-    fn test_backup_file_hybrid_multiple_backups() {
+    fn test_backup_into_multiple_backups() {
         let dir = tempdir().unwrap();
-        let config_path = dir.path().join("ordinator.toml");
+        let backup_dir = dir.path().join("backups");
         let file = dir.path().join("file.txt");
 
-        // Create config and file
-        File::create(&config_path).unwrap();
+        // Create file
         let mut f = File::create(&file).unwrap();
         writeln!(f, "test content").unwrap();
 
         // Create multiple backups
-        let backup1 = backup_file_hybrid(&file, &config_path).unwrap();
-        let backup2 = backup_file_hybrid(&file, &config_path).unwrap();
-        let backup3 = backup_file_hybrid(&file, &config_path).unwrap();
+        let backup1 = backup_into(&file, &backup_dir).unwrap();
+        let backup2 = backup_into(&file, &backup_dir).unwrap();
+        let backup3 = backup_into(&file, &backup_dir).unwrap();
 
         // All should exist and be different
         assert!(backup1.exists());
@@ -519,6 +616,40 @@ mod tests {
         assert!(is_broken_symlink(&symlink));
     }
 
+    #[test]
+    fn test_symlink_points_to_compares_canonical_paths() {
+        let dir = tempdir().unwrap();
+        // "var" stands in for macOS's /var, an alias of /private/var
+        let private_var = dir.path().join("private/var");
+        fs::create_dir_all(private_var.join("dotfiles")).unwrap();
+        unix_fs::symlink(&private_var, dir.path().join("var")).unwrap();
+        let source = dir.path().join("var/dotfiles/zshrc");
+        File::create(&source).unwrap();
+
+        let aliased = dir.path().join("aliased");
+        unix_fs::symlink(private_var.join("dotfiles/zshrc"), &aliased).unwrap();
+        assert!(symlink_points_to(&aliased, &source));
+
+        let dotted = dir.path().join("dotted");
+        unix_fs::symlink(dir.path().join("var/dotfiles/../dotfiles/zshrc"), &dotted).unwrap();
+        assert!(symlink_points_to(&dotted, &source));
+
+        let relative = dir.path().join("relative");
+        unix_fs::symlink("var/dotfiles/zshrc", &relative).unwrap();
+        assert!(symlink_points_to(&relative, &source));
+        assert!(!is_broken_symlink(&relative));
+
+        let other = dir.path().join("var/dotfiles/other");
+        File::create(&other).unwrap();
+        assert!(!symlink_points_to(&aliased, &other));
+
+        // Paths that don't exist yet still compare by their existing parents
+        assert!(same_path(
+            &private_var.join("new/../missing.txt"),
+            &dir.path().join("var/missing.txt")
+        ));
+    }
+
     #[test]
     fn test_repair_symlink() {
         let dir = tempdir().unwrap();
@@ -536,14 +667,12 @@ mod tests {
     }
 
     #[test]
-    fn test_backup_file_hybrid() {
+    fn test_backup_into() {
         let dir = tempdir().unwrap();
-        let config_path = dir.path().join("ordinator.toml");
-        File::create(&config_path).unwrap();
         let file = dir.path().join("file.txt");
         let mut f = File::create(&file).unwrap();
         writeln!(f, "test").unwrap();
-        let backup_path = backup_file_hybrid(&file, &config_path).unwrap();
+        let backup_path = backup_into(&file, &dir.path().join("backups")).unwrap();
         assert!(backup_path.exists());
         let backup_content = fs::read_to_string(&backup_path).unwrap();
         assert!(backup_content.contains("test"));
@@ -561,12 +690,35 @@ mod tests {
         File::create(&source).unwrap();
 
         // Test creating symlink in nested directory
-        create_symlink_with_conflict_resolution(&source, &target, false, false, &config_path)
-            .unwrap();
+        create_symlink_with_conflict_resolution(&source, &target, false, None).unwrap();
         assert!(is_symlink(&target));
         assert!(target.parent().unwrap().exists());
     }
 
+    #[tokio::test]
+    async fn test_map_concurrent_keeps_order() {
+        let items: Vec<u64> = (0..20).collect();
+        let doubled = map_concurrent(items, 3, |n| {
+            std::thread::sleep(std::time::Duration::from_millis(20 - n));
+            n * 2
+        })
+        .await
+        .unwrap();
+        assert_eq!(doubled, (0..20).map(|n| n * 2).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_map_concurrent_workers_share_the_environment() {
+        let fake = std::sync::Arc::new(crate::environment::Fake::new("/Users/fake"));
+        let _env = crate::environment::Override::new(fake);
+        let homes = map_concurrent(vec![(); 4], 2, |()| {
+            crate::environment::current().home_dir().unwrap()
+        })
+        .await
+        .unwrap();
+        assert!(homes.iter().all(|home| home == Path::new("/Users/fake")));
+    }
+
     #[test]
     fn test_generate_file_hash_deterministic() {
         let path = "/Users/test/.zshrc";
@@ -0,0 +1,317 @@
+//! `ordinator watch`: start tracking a file or directory in a profile. Also
+//! the helpers that store live copies in the repository, shared with `add`
+//! and the other commands that update stored copies.
+
+use anyhow::Result;
+use colored::*;
+use std::path::Path;
+
+use crate::config::{Config, FileOptions};
+use crate::directory::{DirectoryLink, SyncReport, TrackedDirectory};
+use crate::error::OrdinatorError;
+use crate::progress::{Progress, ProgressMode};
+use crate::utils::color_enabled;
+
+/// What `ordinator watch` should do
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// Path to start tracking, as given on the command line
+    pub path: String,
+
+    /// For directories: globs of files to track, and of files never to track
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+
+    /// For directories: link each file, or the directory as a whole
+    pub link: DirectoryLink,
+
+    pub sudo: bool,
+    pub encrypt: bool,
+    pub managed_block: bool,
+
+    /// How to report progress through a directory's files
+    pub progress: ProgressMode,
+
+    pub dry_run: bool,
+    pub quiet: bool,
+    pub verbose: bool,
+}
+
+/// Start tracking `options.path` in `profile_name`: store its copy, add it
+/// to the profile, and save the config. Returns the path as tracked, or None
+/// for a dry run or when the user cancels at the conflict prompt.
+pub fn watch(
+    config: &mut Config,
+    config_path: &Path,
+    profile_name: &str,
+    options: &WatchOptions,
+) -> Result<Option<String>> {
+    if !config.profiles.contains_key(profile_name) {
+        return Err(OrdinatorError::ProfileNotFound(profile_name.to_string()).into());
+    }
+    let path = crate::paths::normalize(&options.path)?;
+
+    let exclusion_set = config.exclusion_set_for_profile(profile_name)?;
+    if exclusion_set.is_match(&path) {
+        return Err(anyhow::anyhow!(
+            "Path '{}' matches an exclusion pattern and cannot be tracked.",
+            path
+        ));
+    }
+
+    if options.dry_run {
+        println!("DRY-RUN: Would start watching '{path}' for profile '{profile_name}'");
+        return Ok(None);
+    }
+
+    let live_path = crate::paths::live(&config.target_root()?, &path);
+    let path_obj = live_path.as_path();
+    if !path_obj.exists() {
+        return Err(anyhow::anyhow!("Path '{}' does not exist on disk.", path));
+    }
+    if options.link == DirectoryLink::Directory && !path_obj.is_dir() {
+        return Err(anyhow::anyhow!(
+            "'{path}' is not a directory; only directories can be linked as a whole."
+        ));
+    }
+    if options.encrypt && !path_obj.is_file() {
+        return Err(anyhow::anyhow!(
+            "'{path}' is not a file; only files can be stored encrypted."
+        ));
+    }
+    if options.managed_block && !path_obj.is_file() {
+        return Err(anyhow::anyhow!(
+            "'{path}' is not a file; only files can hold a managed block."
+        ));
+    }
+    let file_options = FileOptions {
+        requires_sudo: options.sudo,
+        encrypt: options.encrypt,
+        managed_block: options.managed_block,
+        ..Default::default()
+    };
+
+    let conflicts = check_file_conflicts(config, &path, profile_name);
+    if !conflicts.is_empty() && !prompt_for_conflict_resolution(&path, &conflicts) {
+        eprintln!("Operation cancelled by user.");
+        return Ok(None);
+    }
+
+    let profile_file_path = config.get_profile_file_path(profile_name, &path)?;
+    if let Some(parent) = profile_file_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    if path_obj.is_file() {
+        store_tracked_copy(
+            &config.global.large_files,
+            &crate::config::get_dotfiles_dir()?,
+            path_obj,
+            &profile_file_path,
+            &path,
+            &file_options,
+        )?;
+    } else if path_obj.is_dir() {
+        // Directories are tracked file by file, filtered by include/exclude globs
+        let directory = TrackedDirectory {
+            include: options.include.clone(),
+            exclude: options.exclude.clone(),
+            link: options.link,
+            ..TrackedDirectory::new(&path)
+        };
+        let report = sync_tracked_directory(config, profile_name, &directory, false)?;
+        let total = report.added.len();
+        let mut progress = Progress::start(options.progress, options.quiet, "watch", total);
+        for file in &report.added {
+            progress.advance(&file.display().to_string());
+        }
+        let linked_as = match directory.link {
+            DirectoryLink::Directory => ", linked as one symlink",
+            DirectoryLink::Files => "",
+        };
+        let msg = format!(
+            "Started watching directory '{}' ({} file(s){linked_as}) for profile '{profile_name}'",
+            directory.path, total
+        );
+        if color_enabled() {
+            progress.println(msg.green().to_string());
+        } else {
+            progress.println(msg);
+        }
+        progress.finish();
+        config.add_directory_to_profile(profile_name, directory)?;
+    }
+
+    if path_obj.is_file() {
+        config.add_file_to_profile(profile_name, path.clone())?;
+        config.record_file_mode(profile_name, &path, path_obj)?;
+        record_sync_baseline(
+            config_path,
+            profile_name,
+            &path,
+            path_obj,
+            options.managed_block,
+        )?;
+    }
+    if options.sudo || options.encrypt || options.managed_block {
+        if let Some(profile) = config.get_profile_mut(profile_name) {
+            let entry = profile.file_options.entry(path.clone()).or_default();
+            entry.requires_sudo |= options.sudo;
+            entry.encrypt |= options.encrypt;
+            entry.managed_block |= options.managed_block;
+        }
+    }
+    config.save_to_file(config_path)?;
+
+    // An encrypted copy needs no warning
+    if path_obj.is_file() && !options.encrypt {
+        warn_about_plaintext_secrets(config, config_path, profile_name, &path, path_obj, options);
+    }
+
+    if options.encrypt {
+        println!("Started watching '{path}' for profile '{profile_name}' (stored encrypted)");
+    } else {
+        println!("Started watching '{path}' for profile '{profile_name}'");
+    }
+    Ok(Some(path))
+}
+
+/// Scan a newly watched file and say how to store it encrypted if it looks
+/// like it holds secrets
+fn warn_about_plaintext_secrets(
+    config: &Config,
+    config_path: &Path,
+    profile_name: &str,
+    path: &str,
+    live: &Path,
+    options: &WatchOptions,
+) {
+    let base_dir = config_path.parent().unwrap().to_path_buf();
+    let manager = crate::secrets::SecretsManager::new(None, None, config.clone(), base_dir);
+
+    match manager.check_for_plaintext_secrets(live) {
+        Ok(true) => {
+            eprintln!("⚠️  Warning: '{path}' contains potential secrets");
+            match manager.get_secrets_info(live) {
+                Ok(secret_types) => eprintln!("   Found: {}", secret_types.join(", ")),
+                Err(_) => eprintln!("   Found: potential secrets"),
+            }
+            eprintln!("   Consider using: ordinator secrets watch {path} --profile {profile_name}");
+            eprintln!("   Use 'ordinator commit --force' to commit anyway");
+        }
+        Ok(false) => {}
+        Err(e) => {
+            if options.verbose {
+                eprintln!("Warning: Could not scan '{path}' for secrets: {e}");
+            }
+        }
+    }
+}
+
+/// Profiles other than `target_profile` that already track `file_path`
+fn check_file_conflicts(config: &Config, file_path: &str, target_profile: &str) -> Vec<String> {
+    let mut conflicts = Vec::new();
+    for (profile_name, profile_config) in &config.profiles {
+        if profile_name != target_profile && profile_config.files.contains(&file_path.to_string()) {
+            conflicts.push(profile_name.clone());
+        }
+    }
+    conflicts
+}
+
+fn prompt_for_conflict_resolution(file_path: &str, conflicts: &[String]) -> bool {
+    if conflicts.is_empty() {
+        return true;
+    }
+
+    eprintln!("⚠️  Warning: File '{file_path}' already exists in other profiles:");
+    for conflict in conflicts {
+        eprintln!("   - {conflict}");
+    }
+    eprintln!("   This will create separate copies for each profile.");
+    crate::prompt::confirm("Continue?", true)
+}
+
+/// Sync a tracked directory's live contents into its stored copy
+pub fn sync_tracked_directory(
+    config: &Config,
+    profile_name: &str,
+    directory: &TrackedDirectory,
+    dry_run: bool,
+) -> Result<SyncReport> {
+    let filter = config.directory_filter(profile_name, directory)?;
+    let storage = config.get_directory_storage_path(profile_name, directory)?;
+    let report = crate::directory::sync_into_storage(
+        &crate::paths::live(&config.target_root()?, &directory.path),
+        &storage,
+        &filter,
+        dry_run,
+    )?;
+    if !dry_run {
+        let dotfiles_dir = crate::config::get_dotfiles_dir()?;
+        for file in report.added.iter().chain(&report.updated) {
+            let tracked_path = Path::new(&directory.path).join(file);
+            crate::large_files::handle_stored(
+                &config.global.large_files,
+                &dotfiles_dir,
+                &storage.join(file),
+                &tracked_path.to_string_lossy(),
+            )?;
+        }
+    }
+    Ok(report)
+}
+
+/// Store a copy of a tracked file in the repository: only its managed block
+/// with `managed_block = true`, encrypted with sops when the file has
+/// `encrypt = true`, else copied and checked against `[global.large_files]`
+pub fn store_tracked_copy(
+    large_files: &crate::large_files::LargeFilesConfig,
+    dotfiles_dir: &Path,
+    source: &Path,
+    stored: &Path,
+    tracked_path: &str,
+    options: &FileOptions,
+) -> Result<()> {
+    if options.managed_block {
+        let contents = crate::block::read(source)?.unwrap_or_default();
+        if !options.encrypt {
+            return Ok(std::fs::write(stored, contents)?);
+        }
+        let fragment = tempfile::NamedTempFile::new()?;
+        std::fs::write(fragment.path(), contents)?;
+        return crate::secrets::encrypt_tracked_file(fragment.path(), stored);
+    }
+    if options.encrypt {
+        return crate::secrets::encrypt_tracked_file(source, stored);
+    }
+    std::fs::copy(source, stored)?;
+    crate::large_files::handle_stored(large_files, dotfiles_dir, stored, tracked_path)
+}
+
+/// Record that a tracked file was just stored and its live and stored copies
+/// match, so `ordinator status` can tell later which side changed. `live` is
+/// the file that was stored. Templates get no baseline here: theirs is the
+/// rendered output, recorded by apply. A managed block's baseline is the
+/// block alone.
+pub fn record_sync_baseline(
+    config_path: &Path,
+    profile: &str,
+    tracked_path: &str,
+    live: &Path,
+    managed_block: bool,
+) -> Result<()> {
+    let dotfiles_dir = config_path.parent().unwrap();
+    let mut cache = crate::drift::HashCache::load(dotfiles_dir);
+    cache.record_added(profile, tracked_path);
+    if crate::template::is_template(tracked_path) {
+        return cache.save();
+    }
+    let hash = if managed_block {
+        crate::block::live_hash(live)?
+    } else {
+        cache.hash_file(live)?
+    };
+    cache.record_sync(profile, tracked_path, hash);
+    cache.save()
+}
@@ -0,0 +1,186 @@
+//! `ordinator why <path>`: everything ordinator knows about one destination,
+//! from the profiles that track it and its stored copy to when it was last
+//! added and applied and how it compares with the repository now.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::config::{Config, FileOptions};
+use crate::drift::{classify, hash_bytes, Drift, HashCache};
+use crate::listing::FileEntry;
+use crate::state::MachineState;
+use crate::template::{is_template, render_file};
+
+/// What ordinator knows about one tracked file or secret at a destination
+#[derive(Debug, Clone, Serialize)]
+pub struct Provenance {
+    /// The file as `ordinator files` lists it
+    #[serde(flatten)]
+    pub entry: FileEntry,
+
+    /// Profiles that deploy it: the one that tracks it and any that inherit it
+    pub used_by: Vec<String>,
+
+    /// Hash-based name of the stored copy, from the profile's `file_mappings`
+    pub mapping: Option<String>,
+
+    /// Whether the file is rendered from a `.tmpl` template
+    pub template: bool,
+
+    /// The file's `file_options`
+    pub options: FileOptions,
+
+    /// When each profile in `used_by` was last applied on this machine (RFC 3339)
+    pub applied_at: BTreeMap<String, String>,
+
+    /// How the destination compares with the stored copy; none for secrets
+    pub drift: Option<Drift>,
+}
+
+/// The destination `path` names: `~/` is the target root, `$XDG_CONFIG_HOME`
+/// and the like their base directories, and a relative path is taken from
+/// the current directory
+pub fn resolve_path(path: &str, home_dir: &Path) -> Result<PathBuf> {
+    Ok(std::env::current_dir()?.join(crate::paths::live(home_dir, path)))
+}
+
+/// Everything known about `target`, one entry per tracked file or secret
+/// deployed there. Empty when no profile tracks it.
+pub fn explain(
+    config: &Config,
+    target: &Path,
+    home_dir: &Path,
+    dotfiles_dir: &Path,
+    state: &MachineState,
+) -> Result<Vec<Provenance>> {
+    let mut profiles: Vec<&String> = config.profiles.keys().collect();
+    profiles.sort();
+    let mut cache = HashCache::load(dotfiles_dir);
+    let mut found: Vec<Provenance> = Vec::new();
+    for profile in profiles {
+        for entry in crate::listing::list(config, profile, home_dir, dotfiles_dir)? {
+            if !crate::utils::same_path(&entry.target, target) {
+                continue;
+            }
+            // An inherited file is the same file, deployed by one more profile
+            if let Some(known) = found.iter_mut().find(|known| {
+                known.entry.profile == entry.profile
+                    && known.entry.path == entry.path
+                    && known.entry.secret.is_some() == entry.secret.is_some()
+            }) {
+                known.used_by.push(profile.clone());
+                continue;
+            }
+            let vars = config.resolve_profile(profile)?.vars;
+            let drift = drift(config, &entry, dotfiles_dir, &vars, &mut cache)?;
+            found.push(Provenance {
+                used_by: vec![profile.clone()],
+                mapping: config.get_profile(&entry.profile).and_then(|owner| {
+                    owner
+                        .file_mappings
+                        .iter()
+                        .find(|(_, path)| **path == entry.path)
+                        .map(|(name, _)| name.clone())
+                }),
+                template: entry.secret.is_none() && is_template(&entry.path),
+                options: config.file_options(&entry.profile, &entry.path),
+                applied_at: BTreeMap::new(),
+                drift,
+                entry,
+            });
+        }
+    }
+    cache.save()?;
+    for provenance in &mut found {
+        provenance.applied_at = provenance
+            .used_by
+            .iter()
+            .filter_map(|profile| {
+                let applied = state.applied.get(profile)?;
+                Some((profile.clone(), applied.applied_at.clone()))
+            })
+            .collect();
+    }
+    Ok(found)
+}
+
+/// How a tracked file's destination compares with its stored copy, by
+/// content and against the hash recorded at the last sync
+fn drift(
+    config: &Config,
+    entry: &FileEntry,
+    dotfiles_dir: &Path,
+    vars: &HashMap<String, String>,
+    cache: &mut HashCache,
+) -> Result<Option<Drift>> {
+    if entry.secret.is_some() {
+        return Ok(None);
+    }
+    if !entry.in_repo {
+        return Ok(Some(Drift::MissingInRepo));
+    }
+    if !entry.target.exists() {
+        return Ok(Some(Drift::MissingLocally));
+    }
+    let Some(hash_match) = entry.hash_match else {
+        return Ok(None);
+    };
+    if hash_match {
+        return Ok(Some(Drift::InSync));
+    }
+    let source = dotfiles_dir.join(&entry.repo_path);
+    let options = config.file_options(&entry.profile, &entry.path);
+    let stored = if options.encrypt {
+        Some(crate::secrets::decrypt_tracked_file(&source)?)
+    } else if is_template(&entry.path) {
+        Some(render_file(&source, vars)?.into_bytes())
+    } else {
+        None
+    };
+    let (repo_hash, live_hash) = if options.managed_block {
+        let stored = match stored {
+            Some(stored) => stored,
+            None => std::fs::read(&source)?,
+        };
+        (
+            crate::block::hash(&stored),
+            crate::block::live_hash(&entry.target)?,
+        )
+    } else {
+        let repo_hash = match stored {
+            Some(stored) => hash_bytes(&stored),
+            None => cache.hash_file(&source)?,
+        };
+        (repo_hash, cache.hash_file(&entry.target)?)
+    };
+    Ok(Some(classify(
+        &live_hash,
+        &repo_hash,
+        cache.synced_hash(&entry.profile, &entry.path),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_path() {
+        let home = Path::new("/Users/me");
+        assert_eq!(
+            resolve_path("~/.zshrc", home).unwrap(),
+            Path::new("/Users/me/.zshrc")
+        );
+        assert_eq!(resolve_path("~", home).unwrap(), home);
+        assert_eq!(
+            resolve_path("/etc/hosts", home).unwrap(),
+            Path::new("/etc/hosts")
+        );
+        assert_eq!(
+            resolve_path(".vimrc", home).unwrap(),
+            std::env::current_dir().unwrap().join(".vimrc")
+        );
+    }
+}
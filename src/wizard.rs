@@ -0,0 +1,152 @@
+//! `ordinator init --interactive`: a first-run wizard. It asks whether to
+//! start a new repository or clone one, then, for a new one, which profiles
+//! to create, whether to set up secrets and export Homebrew packages, and
+//! which common dotfiles to track, and builds `ordinator.toml` from the answers.
+
+use anyhow::Result;
+use std::path::Path;
+
+use crate::config::{Config, ProfileConfig};
+use crate::prompt;
+
+/// Dotfiles the wizard offers to track, relative to the home directory
+pub const COMMON_DOTFILES: [&str; 3] = [".zshrc", ".gitconfig", ".ssh/config"];
+
+/// What the wizard was told
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Answers {
+    /// Repository to clone; none starts a new one
+    pub clone_url: Option<String>,
+
+    /// Profiles to create; the first becomes `default_profile`
+    pub profiles: Vec<String>,
+
+    /// Whether to run `ordinator age setup` for the first profile
+    pub setup_secrets: bool,
+
+    /// Whether to export the installed Homebrew packages into the first profile
+    pub export_brew: bool,
+
+    /// Entries of [`COMMON_DOTFILES`] to track in the first profile
+    pub dotfiles: Vec<String>,
+}
+
+/// Profile names from a comma- or space-separated answer, in order and
+/// without repeats; `default` when the answer is empty
+pub fn parse_profiles(answer: &str) -> Result<Vec<String>> {
+    let mut profiles: Vec<String> = Vec::new();
+    for name in answer.split([',', ' ']).filter(|name| !name.is_empty()) {
+        Config::validate_profile_name(name)?;
+        if !profiles.iter().any(|p| p == name) {
+            profiles.push(name.to_string());
+        }
+    }
+    if profiles.is_empty() {
+        profiles.push("default".to_string());
+    }
+    Ok(profiles)
+}
+
+/// Ask the wizard's questions. Dotfiles are only offered when they exist
+/// under `home_dir`, and the Homebrew export only when Homebrew is installed.
+/// Without a terminal every question takes its default.
+pub fn ask(home_dir: &Path, brew_installed: bool) -> Result<Answers> {
+    let options = vec![
+        "Start a new dotfiles repository".to_string(),
+        "Clone an existing one".to_string(),
+    ];
+    if prompt::choose("How do you want to start?", &options, 0) == 1 {
+        let url = prompt::input("Repository URL: ")
+            .filter(|url| !url.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("No repository URL given"))?;
+        let setup_secrets = prompt::confirm("Set up age encryption for secrets?", false);
+        return Ok(Answers {
+            clone_url: Some(url),
+            profiles: Vec::new(),
+            setup_secrets,
+            export_brew: false,
+            dotfiles: Vec::new(),
+        });
+    }
+
+    let profiles = loop {
+        let answer = prompt::input("Profiles to create, separated by commas (default: default): ")
+            .unwrap_or_default();
+        match parse_profiles(&answer) {
+            Ok(profiles) => break profiles,
+            Err(e) if prompt::interactive() => eprintln!("{e}"),
+            Err(e) => return Err(e),
+        }
+    };
+    let first = &profiles[0];
+    let setup_secrets = prompt::confirm(
+        &format!("Set up age encryption for secrets in profile '{first}'?"),
+        false,
+    );
+    let export_brew = brew_installed
+        && prompt::confirm(
+            &format!("Export your installed Homebrew packages into profile '{first}'?"),
+            false,
+        );
+    let dotfiles = COMMON_DOTFILES
+        .iter()
+        .filter(|file| home_dir.join(file).is_file())
+        .filter(|file| prompt::confirm(&format!("Track ~/{file} in profile '{first}'?"), true))
+        .map(|file| file.to_string())
+        .collect();
+    Ok(Answers {
+        clone_url: None,
+        profiles,
+        setup_secrets,
+        export_brew,
+        dotfiles,
+    })
+}
+
+/// `config` with its profiles replaced by the ones the wizard was told to
+/// create, the first as the default
+pub fn apply_answers(config: &mut Config, answers: &Answers) -> Result<()> {
+    config.profiles.clear();
+    for name in &answers.profiles {
+        config.add_profile(
+            name.clone(),
+            ProfileConfig {
+                enabled: true,
+                ..Default::default()
+            },
+        )?;
+    }
+    if let Some(first) = answers.profiles.first() {
+        config.global.default_profile = first.clone();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_profiles_and_apply_answers() {
+        assert_eq!(parse_profiles("").unwrap(), ["default"]);
+        assert_eq!(
+            parse_profiles("work, personal,work").unwrap(),
+            ["work", "personal"]
+        );
+        assert!(parse_profiles("work/laptop").is_err());
+
+        let mut config = Config::create_default();
+        let answers = Answers {
+            clone_url: None,
+            profiles: vec!["work".to_string(), "personal".to_string()],
+            setup_secrets: false,
+            export_brew: false,
+            dotfiles: Vec::new(),
+        };
+        apply_answers(&mut config, &answers).unwrap();
+        let mut names: Vec<&String> = config.profiles.keys().collect();
+        names.sort();
+        assert_eq!(names, ["personal", "work"]);
+        assert_eq!(config.global.default_profile, "work");
+    }
+}
@@ -88,9 +88,14 @@ fn test_add_nonexistent_file_errors() {
     // Try to add a file that does not exist in the same temp dir
     let mut cmd = common::create_ordinator_command(&temp);
     cmd.args(["add", "does_not_exist.txt"]);
-    cmd.assert().failure().stdout(contains(
-        "File 'does_not_exist.txt' is not tracked for profile 'default'. Use 'ordinator watch does_not_exist.txt --profile default' to start tracking it."
-    ));
+    cmd.assert()
+        .failure()
+        .stderr(contains(
+            "error[E0116]: File 'does_not_exist.txt' is not tracked in profile 'default'.",
+        ))
+        .stderr(contains(
+            "  hint: Use 'ordinator watch does_not_exist.txt --profile default' to start tracking it.",
+        ));
 }
 
 #[test]
@@ -101,9 +106,14 @@ fn test_add_nonexistent_directory_errors() {
     // Try to add a directory that does not exist in the same temp dir
     let mut cmd = common::create_ordinator_command(&temp);
     cmd.args(["add", "no_such_dir/"]);
-    cmd.assert().failure().stdout(contains(
-        "File 'no_such_dir/' is not tracked for profile 'default'. Use 'ordinator watch no_such_dir/ --profile default' to start tracking it."
-    ));
+    cmd.assert()
+        .failure()
+        .stderr(contains(
+            "error[E0116]: File 'no_such_dir/' is not tracked in profile 'default'.",
+        ))
+        .stderr(contains(
+            "  hint: Use 'ordinator watch no_such_dir/ --profile default' to start tracking it.",
+        ));
 }
 
 #[test]
@@ -122,9 +132,14 @@ fn test_add_to_nonexistent_profile_suggests_profile_add() {
     // Try to add a file to a non-existent profile in the same temp dir
     let mut cmd = common::create_ordinator_command(&temp);
     cmd.args(["add", "testfile.txt", "--profile", "ghost"]);
-    cmd.assert().failure().stdout(predicates::str::contains(
-        "Profile 'ghost' does not exist. To create it, run: ordinator profile add ghost",
-    ));
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "error[E0102]: Profile 'ghost' does not exist.",
+        ))
+        .stderr(predicates::str::contains(
+            "  hint: To create it, run: ordinator profile add ghost",
+        ));
 }
 
 #[test]
@@ -188,3 +203,224 @@ exclude = ["*.tmp"]
         "matches an exclusion pattern and cannot be tracked",
     ));
 }
+
+#[test]
+fn test_add_warns_when_importing_rendered_template() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let config_file = temp.child("ordinator.toml");
+    let config_content = r#"
+[profiles.default]
+files = [".gitconfig.tmpl", ".gitconfig"]
+enabled = true
+"#;
+    std::fs::write(config_file.path(), config_content).unwrap();
+    temp.child(".gitconfig")
+        .write_str("[user]\n  email = me@example.com\n")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["add", ".gitconfig", "--profile", "default"]);
+    cmd.assert().success().stderr(predicates::str::contains(
+        "'.gitconfig' is rendered from template '.gitconfig.tmpl'",
+    ));
+}
+
+#[test]
+fn test_add_all_syncs_tracked_directory() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let nvim = temp.child(".config/nvim");
+    nvim.child("init.lua").write_str("-- init").unwrap();
+    nvim.child("lua/options.lua")
+        .write_str("-- options")
+        .unwrap();
+    nvim.child("notes.txt").write_str("not tracked").unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".config/nvim", "--include", "**/*.lua"]);
+    cmd.assert().success().stdout(contains(
+        "Started watching directory '.config/nvim' (2 file(s))",
+    ));
+
+    let config = std::fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("path = \".config/nvim\""));
+    assert!(config.contains("**/*.lua"));
+
+    // Add a file, change a file, delete a file
+    nvim.child("lua/keymaps.lua")
+        .write_str("-- keymaps")
+        .unwrap();
+    nvim.child("init.lua").write_str("-- init v2").unwrap();
+    std::fs::remove_file(nvim.child("lua/options.lua").path()).unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["add", "--all", "--verbose"]);
+    cmd.assert()
+        .success()
+        .stdout(contains(
+            "Synced '.config/nvim': 1 added, 1 updated, 1 removed",
+        ))
+        .stdout(contains("+ lua/keymaps.lua"))
+        .stdout(contains("- lua/options.lua"));
+
+    let storage = std::fs::read_dir(temp.child("files/default").path())
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .find(|p| p.to_string_lossy().ends_with("_nvim"))
+        .unwrap();
+    assert!(storage.join("lua/keymaps.lua").exists());
+    assert!(!storage.join("lua/options.lua").exists());
+    assert!(!storage.join("notes.txt").exists());
+    assert_eq!(
+        std::fs::read_to_string(storage.join("init.lua")).unwrap(),
+        "-- init v2"
+    );
+}
+
+#[test]
+fn test_watch_and_add_all_honor_ordinatorignore() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    temp.child(".ordinatorignore").write_str("*.log\n").unwrap();
+
+    let code = temp.child(".config/Code");
+    code.child(".ordinatorignore")
+        .write_str("node_modules/\n/Cache/\n")
+        .unwrap();
+    code.child("settings.json").write_str("{}").unwrap();
+    code.child("Cache/data").write_str("cached").unwrap();
+    code.child("node_modules/pkg/index.js")
+        .write_str("module.exports = {}")
+        .unwrap();
+    code.child("main.log").write_str("log").unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".config/Code"]);
+    cmd.assert().success().stdout(contains(
+        "Started watching directory '.config/Code' (2 file(s))",
+    ));
+
+    code.child("User/keybindings.json").write_str("[]").unwrap();
+    code.child("User/Cache/more").write_str("cached").unwrap();
+    code.child("node_modules/other/index.js")
+        .write_str("module.exports = {}")
+        .unwrap();
+    code.child("renderer.log").write_str("log").unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["add", "--all", "--verbose"]);
+    cmd.assert()
+        .success()
+        .stdout(contains(
+            "Synced '.config/Code': 2 added, 0 updated, 0 removed",
+        ))
+        .stdout(contains("+ User/keybindings.json"))
+        .stdout(contains("+ User/Cache/more"));
+}
+
+#[test]
+fn test_watch_and_add_handle_large_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["config", "set", "global.large_files.threshold_kb", "1"]);
+    cmd.assert().success();
+
+    // By default a large file is tracked with a warning
+    temp.child("big.txt").write_str(&"x".repeat(2048)).unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", "big.txt"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("'big.txt' is large (2 KiB)"));
+    temp.child(".gitattributes")
+        .assert(predicates::path::missing());
+
+    // With action = "lfs", the stored copy gets a Git LFS rule, once
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["config", "set", "global.large_files.action", "lfs"]);
+    cmd.assert().success();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["add", "big.txt"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Storing 'big.txt' (2 KiB) with Git LFS"));
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["add", "big.txt"]);
+    cmd.assert().success();
+    let attributes = std::fs::read_to_string(temp.child(".gitattributes").path()).unwrap();
+    assert_eq!(attributes.lines().count(), 1);
+    assert!(attributes.starts_with("/files/default/"));
+    assert!(attributes.ends_with("_big.txt filter=lfs diff=lfs merge=lfs -text\n"));
+}
+
+#[test]
+fn test_add_host_override_wins_on_that_host() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let gitconfig = temp.child(".gitconfig");
+    gitconfig.write_str("[user]\n\tname = Work\n").unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".gitconfig", "--profile", "work"]);
+    cmd.assert().success();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["add", ".gitconfig", "--profile", "work"]);
+    cmd.assert().success();
+    let stored = std::fs::read_dir(temp.child("files/work").path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.to_string_lossy().ends_with("_.gitconfig"))
+        .unwrap();
+    let hash_filename = stored.file_name().unwrap().to_string_lossy().to_string();
+
+    gitconfig
+        .write_str("[user]\n\tname = Work\n[gpg]\n\tprogram = /opt/homebrew/bin/gpg\n")
+        .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("ORDINATOR_HOSTNAME", "mac-studio.local").args([
+        "add",
+        ".gitconfig",
+        "--profile",
+        "work",
+        "--host",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(contains("on host 'mac-studio'"));
+    let host_copy = temp.child(format!("files/work/mac-studio/{hash_filename}"));
+    assert!(std::fs::read_to_string(host_copy.path())
+        .unwrap()
+        .contains("[gpg]"));
+
+    // The host override is linked on mac-studio, the profile's copy elsewhere
+    std::fs::remove_file(gitconfig.path()).unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("ORDINATOR_HOSTNAME", "mac-studio")
+        .args(["apply", "--profile", "work", "--skip-brew"]);
+    cmd.assert().success();
+    assert_eq!(
+        std::fs::read_link(gitconfig.path()).unwrap(),
+        host_copy.path()
+    );
+
+    std::fs::remove_file(gitconfig.path()).unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("ORDINATOR_HOSTNAME", "macbook")
+        .args(["apply", "--profile", "work", "--skip-brew"]);
+    cmd.assert().success();
+    assert_ne!(
+        std::fs::read_link(gitconfig.path()).unwrap(),
+        host_copy.path()
+    );
+    assert!(!std::fs::read_to_string(gitconfig.path())
+        .unwrap()
+        .contains("[gpg]"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["add", ".gitconfig", "--all", "--host", "macbook"]);
+    cmd.assert().failure();
+}
@@ -0,0 +1,198 @@
+mod common;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use predicates::str::contains;
+use std::fs;
+
+#[test]
+fn test_adopt_replaces_symlinks_into_another_repo() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let stow_repo = assert_fs::TempDir::new().unwrap();
+    stow_repo
+        .child("zsh/.zshrc")
+        .write_str("export EDITOR=vim\n")
+        .unwrap();
+    temp.child(".config/git").create_dir_all().unwrap();
+    stow_repo.child("git/config").write_str("[user]\n").unwrap();
+    std::os::unix::fs::symlink(
+        stow_repo.child("zsh/.zshrc").path(),
+        temp.child(".zshrc").path(),
+    )
+    .unwrap();
+    std::os::unix::fs::symlink(
+        stow_repo.child("git/config").path(),
+        temp.child(".config/git/config").path(),
+    )
+    .unwrap();
+    temp.child(".DS_Store").write_str("").unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["adopt", "--profile", "default", "--symlinks-only"]);
+    cmd.assert()
+        .failure()
+        .stdout(contains("Found 2 file(s) to adopt"))
+        .stderr(contains("Re-run with --yes"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "--dry-run",
+        "adopt",
+        "--profile",
+        "default",
+        "--symlinks-only",
+    ]);
+    cmd.assert()
+        .success()
+        .stderr(contains("DRY-RUN: Would adopt 2 file(s)"));
+    assert!(fs::read_link(temp.child(".zshrc").path())
+        .unwrap()
+        .starts_with(stow_repo.path()));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["adopt", "--profile", "default", "--symlinks-only", "--yes"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("Adopted '.zshrc' (symlink replaced)"))
+        .stderr(contains("Adopted 2 file(s) into profile 'default'"));
+
+    // The links now point into the ordinator repository and survive the old one
+    for file in [".zshrc", ".config/git/config"] {
+        let target = fs::read_link(temp.child(file).path()).unwrap();
+        assert!(target.starts_with(temp.path()));
+    }
+    drop(stow_repo);
+    temp.child(".zshrc").assert("export EDITOR=vim\n");
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("\".zshrc\""));
+    assert!(config.contains("\".config/git/config\""));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["adopt", "--profile", "default", "--symlinks-only", "--yes"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("No dotfiles to adopt"))
+        .stdout(contains("Found").not());
+}
+
+#[test]
+fn test_migrate_from_chezmoi_reports_what_it_cannot_translate() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let chezmoi = temp.child(".local/share/chezmoi");
+    chezmoi
+        .child(".chezmoiignore")
+        .write_str("README.md\n")
+        .unwrap();
+    chezmoi
+        .child("README.md")
+        .write_str("# dotfiles\n")
+        .unwrap();
+    chezmoi
+        .child("dot_zshrc")
+        .write_str("export EDITOR=vim\n")
+        .unwrap();
+    chezmoi
+        .child("private_dot_ssh/private_config")
+        .write_str("Host *\n")
+        .unwrap();
+    chezmoi
+        .child("dot_gitconfig.tmpl")
+        .write_str("[user]\n  email = {{ .email }}\n")
+        .unwrap();
+    chezmoi
+        .child("run_once_install.sh")
+        .write_str("#!/bin/sh\n")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "--dry-run",
+        "migrate",
+        "--from",
+        "chezmoi",
+        "--profile",
+        "default",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(contains("Would migrate 2 file(s) from chezmoi"))
+        .stdout(contains(
+            "  .ssh/config (from private_dot_ssh/private_config)",
+        ))
+        .stdout(contains("Could not translate 3 file(s):"))
+        .stdout(contains(
+            "  dot_gitconfig.tmpl: a chezmoi template (Go syntax)",
+        ))
+        .stdout(contains("  run_once_install.sh: a chezmoi script"))
+        .stdout(contains("README.md").not());
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(!config.contains("\".zshrc\""));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["migrate", "--from", "chezmoi", "--profile", "default"]);
+    cmd.assert().success().stderr(contains(
+        "Migrated 2 file(s) from chezmoi into profile 'default'",
+    ));
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("\".zshrc\""));
+    assert!(config.contains("\".ssh/config\" = \"600\""), "{config}");
+
+    // Migrating again leaves tracked files alone
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["migrate", "--from", "chezmoi", "--profile", "default"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("Migrating 0 file(s)"))
+        .stdout(contains("dot_zshrc: '.zshrc' is already tracked"));
+}
+
+#[test]
+fn test_migrate_from_yadm_stores_host_alternates_as_overrides() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let work = assert_fs::TempDir::new().unwrap();
+    work.child(".gitconfig##default")
+        .write_str("[user]\n  name = default\n")
+        .unwrap();
+    work.child(".gitconfig##hostname.mac-studio")
+        .write_str("[user]\n  name = studio\n")
+        .unwrap();
+    work.child(".tmux.conf##os.Linux")
+        .write_str("set -g mouse on\n")
+        .unwrap();
+    let repo = git2::Repository::init(work.path()).unwrap();
+    let mut index = repo.index().unwrap();
+    index
+        .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+        .unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = git2::Signature::now("test", "test@example.com").unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, "dotfiles", &tree, &[])
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["migrate", "--from", "yadm", "--profile", "default"])
+        .arg(work.path());
+    cmd.assert()
+        .success()
+        .stdout(contains("  .gitconfig (from .gitconfig##default)"))
+        .stdout(contains(
+            "  .gitconfig on host mac-studio (from .gitconfig##hostname.mac-studio)",
+        ))
+        .stdout(contains(
+            ".tmux.conf##os.Linux: for Linux; ordinator is for macOS",
+        ));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("ORDINATOR_HOSTNAME", "mac-studio").args([
+        "apply",
+        "--profile",
+        "default",
+        "--skip-brew",
+        "--skip-secrets",
+    ]);
+    cmd.assert().success();
+    temp.child(".gitconfig").assert("[user]\n  name = studio\n");
+}
@@ -0,0 +1,44 @@
+mod common;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::prelude::*;
+use ordinator_core::api::{LinkState, PlanAction, Repository};
+
+#[tokio::test]
+async fn test_api_status_and_plan() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let _home_guard = common::EnvVarGuard::set("ORDINATOR_HOME", temp.path());
+
+    temp.child(".zshrc")
+        .write_str("export EDITOR=vim\n")
+        .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".zshrc", "--profile", "default"]);
+    cmd.assert().success();
+
+    let repo = Repository::discover().unwrap();
+    assert_eq!(repo.config_path(), temp.child("ordinator.toml").path());
+    assert_eq!(repo.default_profile(), "default");
+    assert!(repo.profiles().contains(&"default"));
+
+    // Watched, but still the original file rather than a link
+    let status = repo.status("default").unwrap();
+    let zshrc = status.iter().find(|entry| entry.path == ".zshrc").unwrap();
+    assert_eq!(zshrc.state, LinkState::NotLinked);
+    assert_eq!(zshrc.hash_match, Some(true));
+    let plan = repo.plan("default").await.unwrap();
+    assert!(plan
+        .changes
+        .iter()
+        .any(|change| change.action == PlanAction::Conflict && change.target == ".zshrc"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-brew", "--skip-bootstrap", "--force"]);
+    cmd.assert().success();
+
+    let status = repo.status("default").unwrap();
+    let zshrc = status.iter().find(|entry| entry.path == ".zshrc").unwrap();
+    assert_eq!(zshrc.state, LinkState::Linked);
+    let plan = repo.plan("default").await.unwrap();
+    assert!(!plan.changes.iter().any(|change| change.target == ".zshrc"));
+}
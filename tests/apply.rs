@@ -3,6 +3,7 @@ use assert_fs::fixture::PathChild;
 use assert_fs::prelude::*;
 
 use assert_cmd::assert::OutputAssertExt;
+use predicates::prelude::PredicateBooleanExt;
 use std::fs;
 
 #[test]
@@ -287,3 +288,1486 @@ exclude_patterns = ["*.bak"]
     // The command should either succeed (if no secrets to decrypt) or fail gracefully
     assert!(output.status.code().is_some());
 }
+
+#[test]
+fn test_apply_links_files_inherited_from_base_profile() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let config_file = temp.child("ordinator.toml");
+    let config_content = r#"
+[global]
+default_profile = "base"
+
+[profiles.base]
+files = ["base.txt"]
+enabled = true
+
+[profiles.work]
+extends = ["base"]
+files = ["work.txt"]
+enabled = true
+"#;
+    std::fs::write(config_file.path(), config_content).unwrap();
+    temp.child("files/base/base.txt")
+        .write_str("from base")
+        .unwrap();
+    temp.child("files/work/work.txt")
+        .write_str("from work")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "apply",
+        "--profile",
+        "work",
+        "--skip-secrets",
+        "--skip-brew",
+        "--verbose",
+    ]);
+    cmd.assert().success().stderr(predicates::str::contains(
+        "base.txt: inherited from profile 'base'",
+    ));
+
+    let base_link = fs::read_link(temp.child("base.txt").path()).unwrap();
+    assert!(base_link.ends_with("files/base/base.txt"));
+    let work_link = fs::read_link(temp.child("work.txt").path()).unwrap();
+    assert!(work_link.ends_with("files/work/work.txt"));
+}
+
+#[test]
+fn test_apply_rejects_unknown_parent_profile() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let config_file = temp.child("ordinator.toml");
+    let config_content = r#"
+[profiles.work]
+extends = ["missing"]
+enabled = true
+"#;
+    std::fs::write(config_file.path(), config_content).unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--profile", "work"]);
+    cmd.assert().failure().stderr(predicates::str::contains(
+        "Profile 'work' extends unknown profile 'missing'",
+    ));
+}
+
+#[test]
+fn test_rollback_reverts_most_recent_apply() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let config_file = temp.child("ordinator.toml");
+    let config_content = r#"
+[profiles.default]
+files = ["new.txt", "existing.txt"]
+enabled = true
+"#;
+    std::fs::write(config_file.path(), config_content).unwrap();
+    temp.child("files/default/new.txt")
+        .write_str("tracked new")
+        .unwrap();
+    temp.child("files/default/existing.txt")
+        .write_str("tracked existing")
+        .unwrap();
+    temp.child("existing.txt")
+        .write_str("original contents")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--force", "--skip-secrets", "--skip-brew"]);
+    cmd.assert().success().stderr(predicates::str::contains(
+        "To undo this apply, run: ordinator rollback",
+    ));
+    assert!(fs::symlink_metadata(temp.child("new.txt").path())
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert!(fs::symlink_metadata(temp.child("existing.txt").path())
+        .unwrap()
+        .file_type()
+        .is_symlink());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["rollback", "--list"]);
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains("profile 'default'  2 change(s)"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["rollback"]);
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains("Rolled back 2 change(s)"));
+
+    assert!(!temp.child("new.txt").path().exists());
+    let restored = temp.child("existing.txt");
+    assert!(!fs::symlink_metadata(restored.path())
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(
+        fs::read_to_string(restored.path()).unwrap(),
+        "original contents"
+    );
+
+    // The journal is spent; a second rollback has nothing to do
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["rollback"]);
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains("Nothing to roll back."));
+}
+
+#[test]
+fn test_apply_renders_templates_with_profile_vars() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let config_file = temp.child("ordinator.toml");
+    let config_content = r#"
+[profiles.base]
+files = [".gitconfig.tmpl"]
+enabled = true
+
+[profiles.base.vars]
+email = "me@home.example"
+name = "Me"
+
+[profiles.work]
+extends = ["base"]
+enabled = true
+
+[profiles.work.vars]
+email = "me@work.example"
+"#;
+    std::fs::write(config_file.path(), config_content).unwrap();
+    temp.child("files/base/.gitconfig.tmpl")
+        .write_str("[user]\n  name = {{ name }}\n  email = {{ email }}\n")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "apply",
+        "--profile",
+        "work",
+        "--skip-secrets",
+        "--skip-brew",
+    ]);
+    cmd.assert().success();
+
+    let rendered = temp.child(".gitconfig");
+    assert!(!fs::symlink_metadata(rendered.path())
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(
+        fs::read_to_string(rendered.path()).unwrap(),
+        "[user]\n  name = Me\n  email = me@work.example\n"
+    );
+    assert!(!temp.child(".gitconfig.tmpl").path().exists());
+
+    // diff compares rendered output, so a freshly rendered file is clean
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["diff", "--profile", "work"]);
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains("No differences"));
+
+    rendered
+        .write_str("[user]\n  name = Me\n  email = other@example.com\n")
+        .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["diff", ".gitconfig", "--profile", "work"]);
+    cmd.assert()
+        .success()
+        .stdout(predicates::str::contains("-  email = me@work.example"))
+        .stdout(predicates::str::contains("+  email = other@example.com"));
+}
+
+#[test]
+fn test_apply_fails_on_undefined_template_variable() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let config_file = temp.child("ordinator.toml");
+    let config_content = r#"
+[profiles.default]
+files = [".curlrc.tmpl"]
+enabled = true
+"#;
+    std::fs::write(config_file.path(), config_content).unwrap();
+    temp.child("files/default/.curlrc.tmpl")
+        .write_str("proxy = {{ proxy_url }}\n")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-secrets", "--skip-brew"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains("Failed to render template"));
+    assert!(!temp.child(".curlrc").path().exists());
+}
+
+#[test]
+fn test_apply_links_tracked_directories() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    temp.child(".config/fish/config.fish")
+        .write_str("set -x EDITOR nvim")
+        .unwrap();
+    temp.child(".config/fish/functions/ll.fish")
+        .write_str("function ll; ls -l; end")
+        .unwrap();
+    temp.child(".config/kitty/kitty.conf")
+        .write_str("font_size 13")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".config/fish"]);
+    cmd.assert().success();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".config/kitty", "--link", "directory"]);
+    cmd.assert().success();
+
+    // Simulate a fresh machine with one untracked local file
+    fs::remove_dir_all(temp.child(".config").path()).unwrap();
+    temp.child(".config/fish/local.fish")
+        .write_str("# machine specific")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-secrets", "--skip-brew"]);
+    cmd.assert().success();
+
+    // Per-file mode links each tracked file and leaves other files alone
+    let fish = temp.child(".config/fish");
+    assert!(!fs::symlink_metadata(fish.path())
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    for file in ["config.fish", "functions/ll.fish"] {
+        assert!(fs::symlink_metadata(fish.child(file).path())
+            .unwrap()
+            .file_type()
+            .is_symlink());
+    }
+    assert_eq!(
+        fs::read_to_string(fish.child("functions/ll.fish").path()).unwrap(),
+        "function ll; ls -l; end"
+    );
+    assert_eq!(
+        fs::read_to_string(fish.child("local.fish").path()).unwrap(),
+        "# machine specific"
+    );
+
+    // Directory mode links the directory itself
+    let kitty = temp.child(".config/kitty");
+    assert!(fs::symlink_metadata(kitty.path())
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(
+        fs::read_to_string(kitty.child("kitty.conf").path()).unwrap(),
+        "font_size 13"
+    );
+}
+
+#[test]
+fn test_apply_only_and_skip_filter_tracked_paths() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    temp.child(".config/nvim/init.lua")
+        .write_str("vim.o.number = true")
+        .unwrap();
+    temp.child(".gnupg/gpg.conf").write_str("armor").unwrap();
+    temp.child(".zshrc").write_str("export A=1").unwrap();
+    for path in [".config/nvim", ".gnupg", ".zshrc"] {
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.args(["watch", path]);
+        cmd.assert().success();
+    }
+    for path in [".config", ".gnupg", ".zshrc"] {
+        let path = temp.child(path);
+        if path.path().is_dir() {
+            fs::remove_dir_all(path.path()).unwrap();
+        } else {
+            fs::remove_file(path.path()).unwrap();
+        }
+    }
+
+    // --only links just the matching files, and leaves brew and friends alone
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-secrets", "--only", ".config/nvim/**"]);
+    cmd.assert().success().stderr(predicates::str::contains(
+        "Only applying tracked files matching --only",
+    ));
+    assert!(temp.child(".config/nvim/init.lua").path().is_symlink());
+    assert!(!temp.child(".gnupg/gpg.conf").path().exists());
+    assert!(!temp.child(".zshrc").path().exists());
+
+    // Status and diff look at the same paths
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["status", "--short", "--skip", ".gnupg/**"]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout
+        .lines()
+        .filter(|line| !line.contains("INFO"))
+        .collect();
+    assert_eq!(lines, ["D  .zshrc"]);
+
+    // --skip applies everything else
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "apply",
+        "--skip-secrets",
+        "--skip-brew",
+        "--skip",
+        ".gnupg/**",
+    ]);
+    cmd.assert().success();
+    assert!(temp.child(".zshrc").path().is_symlink());
+    assert!(!temp.child(".gnupg/gpg.conf").path().exists());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--only", "a/["]);
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains("Invalid glob pattern 'a/['"));
+}
+
+#[test]
+fn test_plan_reports_pending_changes() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    temp.child(".zshrc").write_str("export A=1").unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".zshrc"]);
+    cmd.assert().success();
+
+    // The live file is a regular file, so apply would hit a conflict
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["plan"]);
+    cmd.assert()
+        .code(2)
+        .stdout(predicates::str::contains("! conflict .zshrc"))
+        .stdout(predicates::str::contains("Plan: 1 conflict(s)"));
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["plan", "--check"]);
+    cmd.assert()
+        .code(3)
+        .stdout(predicates::str::contains("Plan for profile").not())
+        .stderr(predicates::str::contains("Plan: 1 conflict(s)"));
+
+    fs::remove_file(temp.child(".zshrc").path()).unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["plan", "--profile", "default"]);
+    cmd.assert()
+        .code(2)
+        .stdout(predicates::str::contains("+ symlink .zshrc"))
+        .stdout(predicates::str::contains("Plan: 1 symlink(s) to create"));
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["plan", "--check"]);
+    cmd.assert()
+        .code(2)
+        .stdout(predicates::str::contains("+ symlink").not());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-brew", "--skip-defaults"]);
+    cmd.assert().success();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["plan"]);
+    cmd.assert().code(0).stderr(predicates::str::contains(
+        "No changes. Profile 'default' is up to date.",
+    ));
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["plan", "--check"]);
+    cmd.assert().code(0);
+}
+
+#[test]
+fn test_apply_batches_sudo_links_into_script() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    // Stands in for /etc: an absolute destination outside the home directory
+    let system = assert_fs::TempDir::new().unwrap();
+    let hosts = system.child("hosts");
+    hosts.write_str("127.0.0.1 localhost\n").unwrap();
+    let hosts_path = hosts.path().to_str().unwrap().to_string();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", &hosts_path, "--profile", "default", "--sudo"]);
+    cmd.assert().success();
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("requires_sudo = true"));
+    let source = temp
+        .child("files/default")
+        .path()
+        .join(hosts_path.trim_start_matches('/'));
+    assert!(source.is_file());
+    fs::remove_file(hosts.path()).unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["plan"]);
+    cmd.assert()
+        .code(2)
+        .stdout(predicates::str::contains("needs sudo"));
+
+    let script = temp.child("link.sh");
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-brew", "--skip-defaults", "--sudo-script"])
+        .arg(script.path());
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains("Wrote 1 link(s) that need sudo"));
+    assert!(!hosts.path().exists());
+    let content = fs::read_to_string(script.path()).unwrap();
+    assert!(content.contains(&format!("ln -sfn '{}' '{hosts_path}'", source.display())));
+
+    // The destination is writable here, so the script runs without sudo
+    let status = std::process::Command::new("sh")
+        .arg(script.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(fs::read_link(hosts.path()).unwrap(), source);
+
+    // A directory in the way isn't linked into: without a backup it's refused,
+    // and with --permanent the script removes it first
+    fs::remove_file(hosts.path()).unwrap();
+    hosts.child("nested").create_dir_all().unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["config", "set", "global.create_backups", "false"]);
+    cmd.assert().success();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "apply",
+        "--skip-brew",
+        "--skip-defaults",
+        "--force",
+        "--sudo-script",
+    ])
+    .arg(script.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains("Cannot replace"))
+        .stderr(predicates::str::contains("--permanent"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "--permanent",
+        "apply",
+        "--skip-brew",
+        "--skip-defaults",
+        "--force",
+        "--sudo-script",
+    ])
+    .arg(script.path());
+    cmd.assert().success();
+    let content = fs::read_to_string(script.path()).unwrap();
+    assert!(content.contains(&format!("rm -rf '{hosts_path}'\nln -sfn")));
+    let status = std::process::Command::new("sh")
+        .arg(script.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(fs::read_link(hosts.path()).unwrap(), source);
+}
+
+#[test]
+fn test_apply_links_many_files_concurrently() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    for i in 0..40 {
+        temp.child(format!(".config/many/{i:02}.conf"))
+            .write_str(&format!("value = {i}\n"))
+            .unwrap();
+    }
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".config/many", "--profile", "default"]);
+    cmd.assert().success();
+    fs::remove_dir_all(temp.child(".config/many").path()).unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-brew", "--skip-defaults", "--jobs", "4"]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(
+        "Files: 40 symlinked, 0 replaced, 0 repaired, 0 rendered, 0 with sudo, 0 unchanged"
+    ));
+
+    // Progress lines stay in tracked order
+    let linked: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("Symlinked:"))
+        .collect();
+    assert_eq!(linked.len(), 40);
+    for (i, line) in linked.iter().enumerate() {
+        assert!(line.contains(&format!(".config/many/{i:02}.conf")));
+    }
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-brew", "--skip-defaults", "-j", "1"]);
+    cmd.assert().success().stderr(predicates::str::contains(
+        "Files: 0 symlinked, 0 replaced, 0 repaired, 0 rendered, 0 with sudo, 40 unchanged",
+    ));
+}
+
+#[test]
+fn test_apply_progress_json_events() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    for i in 0..3 {
+        temp.child(format!(".config/few/{i}.conf"))
+            .write_str(&format!("value = {i}\n"))
+            .unwrap();
+    }
+
+    // Without a terminal, auto progress is one plain line per file
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".config/few", "--profile", "default"]);
+    cmd.assert()
+        .success()
+        .stdout(predicates::str::contains("[3/3] 2.conf"));
+    fs::remove_dir_all(temp.child(".config/few").path()).unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "apply",
+        "--skip-brew",
+        "--skip-defaults",
+        "--progress",
+        "json",
+    ]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let events: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(events.len(), 5);
+    assert_eq!(
+        events[0],
+        serde_json::json!({"event": "start", "task": "apply", "total": 3})
+    );
+    assert_eq!(events[1]["event"], "progress");
+    assert_eq!(events[3]["current"], 3);
+    assert_eq!(events[3]["item"], ".config/few/2.conf");
+    assert_eq!(events[4]["event"], "finish");
+}
+
+#[test]
+fn test_apply_watch_rerenders_changed_templates() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let config_content = r#"
+[profiles.default]
+files = [".curlrc.tmpl"]
+enabled = true
+
+[profiles.default.vars]
+proxy_url = "http://proxy.example:3128"
+"#;
+    fs::write(temp.child("ordinator.toml").path(), config_content).unwrap();
+    let source = temp.child("files/default/.curlrc.tmpl");
+    source.write_str("proxy = {{ proxy_url }}\n").unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-brew", "--skip-defaults", "--watch"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+    let child = common::ChildGuard(cmd.spawn().unwrap());
+
+    let rendered = temp.child(".curlrc");
+    let wait_for = |expected: &str| {
+        for _ in 0..100 {
+            if fs::read_to_string(rendered.path()).is_ok_and(|content| content == expected) {
+                return true;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+        false
+    };
+    let applied = wait_for("proxy = http://proxy.example:3128\n");
+    // Give the watcher a moment to start after the initial apply
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    source
+        .write_str("proxy = {{ proxy_url }}\nsilent\n")
+        .unwrap();
+    let rerendered = wait_for("proxy = http://proxy.example:3128\nsilent\n");
+    drop(child);
+
+    assert!(applied);
+    assert!(rerendered);
+}
+
+#[test]
+fn test_apply_assume_yes_replaces_conflicting_file() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    temp.child("dotfile.txt").write_str("contents").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", "dotfile.txt"]);
+    cmd.assert().success();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["add", "dotfile.txt"]);
+    cmd.assert().success();
+
+    // Without a terminal the conflict isn't replaced
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-secrets", "--skip-brew"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains("Use --force to overwrite"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("ORDINATOR_ASSUME_YES", "1");
+    cmd.args(["apply", "--skip-secrets", "--skip-brew"]);
+    cmd.assert().success();
+    let meta = fs::symlink_metadata(temp.child("dotfile.txt").path()).unwrap();
+    assert!(meta.file_type().is_symlink());
+}
+
+#[test]
+fn test_apply_restores_recorded_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    temp.child("dotfile.txt").write_str("contents").unwrap();
+    fs::set_permissions(
+        temp.child("dotfile.txt").path(),
+        fs::Permissions::from_mode(0o600),
+    )
+    .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", "dotfile.txt"]);
+    cmd.assert().success();
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("\"dotfile.txt\" = \"600\""));
+
+    // A fresh clone only keeps the executable bit
+    for entry in fs::read_dir(temp.child("files/default").path()).unwrap() {
+        fs::set_permissions(entry.unwrap().path(), fs::Permissions::from_mode(0o644)).unwrap();
+    }
+    fs::remove_file(temp.child("dotfile.txt").path()).unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-secrets", "--skip-brew"]);
+    cmd.assert().success();
+    let meta = fs::metadata(temp.child("dotfile.txt").path()).unwrap();
+    assert_eq!(meta.permissions().mode() & 0o7777, 0o600);
+}
+
+#[test]
+fn test_apply_and_uninstall_use_target_root() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    temp.child("dotfile.txt").write_str("contents").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", "dotfile.txt"]);
+    cmd.assert().success();
+    let root = temp.child("mnt/home");
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["config", "set", "global.target_root"])
+        .arg(root.path());
+    cmd.assert().success();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-secrets", "--skip-brew"]);
+    cmd.assert().success();
+    let linked = root.child("dotfile.txt");
+    assert!(fs::symlink_metadata(linked.path())
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    // The real home is left alone
+    assert!(!fs::symlink_metadata(temp.child("dotfile.txt").path())
+        .unwrap()
+        .file_type()
+        .is_symlink());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["uninstall", "--force"]);
+    cmd.assert().success();
+    assert!(fs::symlink_metadata(linked.path()).is_err());
+}
+
+#[test]
+fn test_apply_multiple_profiles_later_profile_wins() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    for (file, profile) in [
+        (".gitconfig", "work"),
+        (".npmrc", "work"),
+        (".gitconfig", "personal"),
+        (".hugo", "personal"),
+    ] {
+        temp.child(file).write_str(profile).unwrap();
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.args(["watch", file, "--profile", profile]);
+        cmd.assert().success();
+    }
+    for file in [".gitconfig", ".npmrc", ".hugo"] {
+        fs::remove_file(temp.child(file).path()).unwrap();
+    }
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "apply",
+        "--profile",
+        "work",
+        "--profile",
+        "personal",
+        "--skip-secrets",
+        "--skip-brew",
+    ]);
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains(
+            "Applying profiles: work, personal",
+        ))
+        .stderr(predicates::str::contains(
+            ".gitconfig: from 'personal', not 'work'",
+        ));
+    for (file, contents) in [
+        (".gitconfig", "personal"),
+        (".npmrc", "work"),
+        (".hugo", "personal"),
+    ] {
+        assert_eq!(
+            fs::read_to_string(temp.child(file).path()).unwrap(),
+            contents
+        );
+    }
+
+    // A path tracked as a file by one profile and a secret by another stops the apply up front
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    let (head, personal) = config.split_once("[profiles.personal]").unwrap();
+    let personal = personal.replacen("secrets = []", "secrets = [\".npmrc\"]", 1);
+    fs::write(
+        temp.child("ordinator.toml").path(),
+        format!("{head}[profiles.personal]{personal}"),
+    )
+    .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "apply",
+        "--profiles",
+        "work,personal",
+        "--skip-secrets",
+        "--skip-brew",
+    ]);
+    cmd.assert().failure().stderr(predicates::str::contains(
+        "Profiles can't be applied together",
+    ));
+}
+
+#[test]
+fn test_apply_machine_role_composes_profiles_and_is_remembered() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    for (file, profile) in [
+        (".gitconfig", "work"),
+        (".gitconfig", "personal"),
+        (".npmrc", "work"),
+    ] {
+        temp.child(file).write_str(profile).unwrap();
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.args(["watch", file, "--profile", profile]);
+        cmd.assert().success();
+    }
+    for file in [".gitconfig", ".npmrc"] {
+        fs::remove_file(temp.child(file).path()).unwrap();
+    }
+    let config_path = temp.child("ordinator.toml");
+    let mut config = fs::read_to_string(config_path.path()).unwrap();
+    config.push_str(
+        "\n[machines]\nstudio = { profiles = [\"work\", \"personal\"], secrets = false, brew = false }\n",
+    );
+    fs::write(config_path.path(), config).unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--machine", "studio", "--skip-defaults"]);
+    cmd.assert().success().stderr(predicates::str::contains(
+        "Applying profiles: work, personal",
+    ));
+    assert_eq!(
+        fs::read_to_string(temp.child(".gitconfig").path()).unwrap(),
+        "personal"
+    );
+    let state = temp.child(".local/state/ordinator/state.toml");
+    assert!(fs::read_to_string(state.path())
+        .unwrap()
+        .contains("machine = \"studio\""));
+
+    // Later applies without --profile use the role again
+    fs::remove_file(temp.child(".npmrc").path()).unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-defaults"]);
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains("Using machine role 'studio'"))
+        .stderr(predicates::str::contains(
+            "Applying profiles: work, personal",
+        ));
+    assert!(temp.child(".npmrc").path().is_symlink());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.arg("profiles");
+    cmd.assert().success().stderr(predicates::str::contains(
+        "studio: work, personal (no secrets, no brew) (this machine)",
+    ));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--machine", "laptop"]);
+    cmd.assert().failure().stderr(predicates::str::contains(
+        "Machine role 'laptop' not found. Roles: studio",
+    ));
+}
+
+#[test]
+fn test_apply_debug_output_goes_to_verbose_and_log_file() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    temp.child(".zshrc").write_str("export A=1").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".zshrc"]);
+    cmd.assert().success();
+    fs::remove_file(temp.child(".zshrc").path()).unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env_remove("RUST_LOG");
+    cmd.args(["apply", "--skip-secrets", "--skip-brew"]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("[DEBUG]"), "stderr: {stderr}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("DEBUG"), "stdout: {stdout}");
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env_remove("RUST_LOG");
+    cmd.args(["apply", "--skip-secrets", "--skip-brew", "--verbose"]);
+    cmd.assert()
+        .success()
+        .stdout(predicates::str::contains("Config loaded from"));
+
+    let log_file = temp.child("ordinator.log");
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env_remove("RUST_LOG");
+    cmd.args(["apply", "--skip-secrets", "--skip-brew", "--log-file"]);
+    cmd.arg(log_file.path());
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("Config loaded from"));
+    let log = fs::read_to_string(log_file.path()).unwrap();
+    let entries: Vec<serde_json::Value> = log
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert!(entries.iter().any(|entry| entry["level"] == "debug"
+        && entry["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Config loaded from")));
+}
+
+#[test]
+fn test_dir_link_repair_and_uninstall() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    temp.child(".config/nvim/init.lua")
+        .write_str("vim.o.number = true")
+        .unwrap();
+    temp.child(".vimrc").write_str("set number").unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".vimrc", "--dir-link"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains("only directories can be linked"));
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".config/nvim", "--dir-link"]);
+    cmd.assert()
+        .success()
+        .stdout(predicates::str::contains("linked as one symlink"));
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("link = \"directory\""), "{config}");
+
+    fs::remove_dir_all(temp.child(".config/nvim").path()).unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-secrets", "--skip-brew"]);
+    cmd.assert().success();
+    let nvim = temp.child(".config/nvim");
+    let stored = fs::read_link(nvim.path()).unwrap();
+
+    // A directory symlink pointing elsewhere is repaired as a unit
+    fs::remove_file(nvim.path()).unwrap();
+    std::os::unix::fs::symlink(temp.child("elsewhere").path(), nvim.path()).unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["repair"]);
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains("Repaired directory symlink"));
+    assert_eq!(fs::read_link(nvim.path()).unwrap(), stored);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["uninstall", "--profile", "default", "--force"]);
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains("Removed directory symlink"));
+    assert!(fs::symlink_metadata(nvim.path()).is_err());
+    assert_eq!(
+        fs::read_to_string(stored.join("init.lua")).unwrap(),
+        "vim.o.number = true"
+    );
+}
+
+#[test]
+fn test_apply_accepts_equivalent_symlink_targets_unless_strict() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    temp.child(".vimrc").write_str("set number").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".vimrc"]);
+    cmd.assert().success();
+    fs::remove_file(temp.child(".vimrc").path()).unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-secrets", "--skip-brew"]);
+    cmd.assert().success();
+
+    // Same file, reached through a `..` segment
+    let vimrc = temp.child(".vimrc");
+    let stored = fs::read_link(vimrc.path()).unwrap();
+    let dotted = stored
+        .parent()
+        .unwrap()
+        .join("../default")
+        .join(stored.file_name().unwrap());
+    fs::remove_file(vimrc.path()).unwrap();
+    std::os::unix::fs::symlink(&dotted, vimrc.path()).unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-secrets", "--skip-brew"]);
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains("Repaired").not());
+    assert_eq!(fs::read_link(vimrc.path()).unwrap(), dotted);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-secrets", "--skip-brew", "--strict-paths"]);
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains("Repaired:"));
+    assert_eq!(fs::read_link(vimrc.path()).unwrap(), stored);
+}
+
+#[test]
+fn test_apply_frozen_refuses_changes_since_lock() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    temp.child(".zshrc").write_str("export A=1\n").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".zshrc"]);
+    cmd.assert().success();
+
+    // No lock yet
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--frozen", "--skip-brew"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains("Run 'ordinator lock'"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.arg("lock");
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains("Wrote ordinator.lock (1 files"));
+    let lock = fs::read_to_string(temp.child("ordinator.lock").path()).unwrap();
+    let stored = lock
+        .lines()
+        .find_map(|line| line.strip_prefix('"'))
+        .and_then(|line| line.split('"').next())
+        .unwrap()
+        .to_string();
+    assert!(stored.starts_with("files/"), "{lock}");
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--frozen", "--skip-brew", "--force"]);
+    cmd.assert().success();
+
+    // Editing the stored copy without re-locking is refused
+    temp.child(&stored).write_str("export A=2\n").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--frozen", "--skip-brew", "--force"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains(format!("changed: {stored}")));
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["lock", "--check"]);
+    cmd.assert()
+        .code(2)
+        .stdout(predicates::str::contains(format!("changed: {stored}")));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.arg("lock");
+    cmd.assert().success();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["lock", "--check"]);
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains("matches ordinator.lock"));
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--frozen", "--skip-brew", "--force"]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_apply_leaves_secrets_encrypted_when_turned_off() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let config = r#"
+[global]
+default_profile = "default"
+auto_push = false
+
+[profiles.default]
+files = []
+secrets = [".npmrc"]
+
+[profiles.work]
+extends = ["default"]
+secrets_enabled = false
+"#;
+    let (_config_guard, _test_mode_guard) =
+        common::setup_test_environment_with_config(&temp, Some(config));
+    let apply = |args: &[&str]| {
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.args(["apply", "--skip-brew", "--skip-bootstrap"])
+            .args(args);
+        cmd.assert().success()
+    };
+
+    apply(&["--profile", "work"])
+        .stderr(predicates::str::contains(
+            "Skipping 1 secret(s): secrets are off for profile 'work'",
+        ))
+        .stderr(predicates::str::contains("Decrypting secrets").not());
+    apply(&["--profile", "default", "--no-secrets-for", "default"]).stderr(
+        predicates::str::contains("Skipping 1 secret(s): secrets are off for profile 'default'"),
+    );
+
+    // A shared machine is remembered until it's cleared
+    let state = temp.child(".local/state/ordinator/state.toml");
+    apply(&["--profile", "default", "--shared"]).stderr(predicates::str::contains(
+        "this machine is marked as shared",
+    ));
+    assert!(fs::read_to_string(state.path())
+        .unwrap()
+        .contains("shared = true"));
+    apply(&["--profile", "default"]).stderr(predicates::str::contains(
+        "this machine is marked as shared",
+    ));
+    apply(&["--profile", "default", "--not-shared"])
+        .stderr(predicates::str::contains("marked as shared").not());
+    assert!(fs::read_to_string(state.path())
+        .unwrap()
+        .contains("shared = false"));
+}
+
+#[test]
+fn test_apply_errors_show_code_and_hint() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let config = r#"
+[global]
+default_profile = "default"
+auto_push = false
+
+[profiles.default]
+files = [".zshrc"]
+"#;
+    let (_config_guard, _test_mode_guard) =
+        common::setup_test_environment_with_config(&temp, Some(config));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-brew", "--profile", "nope"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "error[E0102]: Profile 'nope' does not exist.",
+        ))
+        .stderr(predicates::str::contains(
+            "  hint: To create it, run: ordinator profile add nope.",
+        ));
+
+    // .zshrc is tracked but was never stored in the repository
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-brew", "--skip-bootstrap"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains("error[E0402]: "))
+        .stderr(predicates::str::contains(
+            "  hint: Run 'ordinator add <file> --profile <profile>' to store it again",
+        ));
+}
+
+#[test]
+fn test_managed_block_keeps_the_rest_of_the_file() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let zshrc = temp.child(".zshrc");
+    zshrc
+        .write_str(
+            "export PATH=/opt/bin:$PATH\n# >>> ordinator >>>\nalias ll='ls -l'\n# <<< ordinator <<<\n",
+        )
+        .unwrap();
+    let run = |args: &[&str]| {
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.args(args);
+        cmd.assert().success()
+    };
+    let stored = |dir: &str| {
+        let path = fs::read_dir(temp.child(dir).path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.is_file())
+            .unwrap();
+        fs::read_to_string(path).unwrap()
+    };
+
+    // Only the block is stored
+    run(&["watch", ".zshrc", "--managed-block", "--no-commit"]);
+    assert_eq!(stored("files/default"), "alias ll='ls -l'\n");
+    assert!(fs::read_to_string(temp.child("ordinator.toml").path())
+        .unwrap()
+        .contains("managed_block = true"));
+
+    // Apply puts the block back after the file's own lines, once
+    zshrc.write_str("export PATH=/opt/bin:$PATH\n").unwrap();
+    let expected =
+        "export PATH=/opt/bin:$PATH\n\n# >>> ordinator >>>\nalias ll='ls -l'\n# <<< ordinator <<<\n";
+    run(&["apply", "--skip-brew", "--skip-bootstrap"])
+        .stdout(predicates::str::contains("Updated block:"));
+    assert_eq!(fs::read_to_string(zshrc.path()).unwrap(), expected);
+    run(&["apply", "--skip-brew", "--skip-bootstrap"])
+        .stdout(predicates::str::contains("Updated block:").not());
+    assert_eq!(fs::read_to_string(zshrc.path()).unwrap(), expected);
+
+    // An edit inside the block is drift, and add stores it without the lines around it
+    zshrc
+        .write_str(&format!(
+            "{}eval \"$(rbenv init -)\"\n",
+            expected.replace("ls -l'", "ls -la'")
+        ))
+        .unwrap();
+    run(&["status", "--short"]).stdout(predicates::str::contains(".zshrc"));
+    run(&["add", ".zshrc", "--no-commit"]);
+    assert_eq!(stored("files/default"), "alias ll='ls -la'\n");
+
+    // Uninstall takes out the block and leaves the rest
+    run(&["uninstall", "--profile", "default", "--force"])
+        .stderr(predicates::str::contains("Removed the managed block from"));
+    assert_eq!(
+        fs::read_to_string(zshrc.path()).unwrap(),
+        "export PATH=/opt/bin:$PATH\n\neval \"$(rbenv init -)\"\n"
+    );
+}
+
+#[test]
+fn test_shell_files_follow_the_shells_found() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let zshrc = temp.child(".zshrc");
+    let fish = temp.child(".config/fish/config.fish");
+    zshrc.write_str("alias ll='ls -l'\n").unwrap();
+    fish.write_str("set -x EDITOR vim\n").unwrap();
+    for path in [".zshrc", ".config/fish/config.fish"] {
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.args(["watch", path, "--profile", "default", "--no-commit"]);
+        cmd.assert().success();
+    }
+    let config = temp.child("ordinator.toml");
+    let mut toml = fs::read_to_string(config.path()).unwrap();
+    toml.push_str(
+        r#"
+[profiles.default.shell.fish]
+files = [".config/fish"]
+
+[profiles.default.shell.zsh]
+files = [".zshrc"]
+when = "login"
+"#,
+    );
+    fs::write(config.path(), toml).unwrap();
+    fs::remove_file(zshrc.path()).unwrap();
+    fs::remove_file(fish.path()).unwrap();
+
+    // No fish on PATH, and bash is the login shell
+    let empty = temp.child("empty-bin");
+    empty.create_dir_all().unwrap();
+    let run = |args: &[&str], path: &std::path::Path, shell: &str| {
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.args(args)
+            .env("PATH", path)
+            .env("ORDINATOR_SHELL", shell);
+        cmd.assert()
+    };
+    run(&["plan"], empty.path(), "bash")
+        .success()
+        .stderr(predicates::str::contains(
+            "Skipping 1 file(s): fish isn't installed: .config/fish/config.fish",
+        ))
+        .stderr(predicates::str::contains(
+            "Skipping 1 file(s): zsh isn't the login shell: .zshrc",
+        ))
+        .stderr(predicates::str::contains("No changes."));
+    run(
+        &["apply", "--skip-brew", "--skip-bootstrap"],
+        empty.path(),
+        "bash",
+    )
+    .success();
+    assert!(!zshrc.path().exists() && !fish.path().exists());
+
+    // fish installed and zsh the login shell
+    let bin = temp.child("bin");
+    bin.child("fish").write_str("#!/bin/sh\n").unwrap();
+    fs::set_permissions(
+        bin.child("fish").path(),
+        std::os::unix::fs::PermissionsExt::from_mode(0o755),
+    )
+    .unwrap();
+    run(&["plan"], bin.path(), "/bin/zsh")
+        .code(2)
+        .stderr(predicates::str::contains("Skipping").not());
+    run(
+        &["apply", "--skip-brew", "--skip-bootstrap"],
+        bin.path(),
+        "zsh",
+    )
+    .success();
+    assert!(zshrc.path().is_symlink() && fish.path().is_symlink());
+}
+
+#[test]
+fn test_apply_reports_unwritable_destinations_before_changing_anything() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    temp.child(".zshrc")
+        .write_str("export EDITOR=vim\n")
+        .unwrap();
+    temp.child(".config/nvim/init.lua")
+        .write_str("-- nvim\n")
+        .unwrap();
+    for file in [".zshrc", ".config/nvim/init.lua"] {
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.args(["watch", file, "--profile", "default"]);
+        cmd.assert().success();
+    }
+    // A file where .config should be stops the link under it
+    fs::remove_dir_all(temp.child(".config").path()).unwrap();
+    temp.child(".config").write_str("").unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["--dry-run", "apply", "--skip-brew", "--skip-bootstrap"]);
+    cmd.assert().success().stderr(predicates::str::contains(
+        "1 destination(s) can't be written:",
+    ));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--force", "--skip-brew", "--skip-bootstrap"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "1 destination(s) can't be written:\n  A file is in the way of a parent directory:\n",
+        ))
+        .stderr(predicates::str::contains(".config/nvim/init.lua ("))
+        .stderr(predicates::str::contains("    fix: Move '"))
+        .stderr(predicates::str::contains(
+            "error[E0403]: Apply can't write 1 destination(s); nothing was changed.",
+        ));
+    // .zshrc would have been replaced by a link
+    assert!(!temp.child(".zshrc").path().is_symlink());
+}
+
+#[test]
+fn test_apply_resolves_tilde_xdg_and_absolute_paths() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let outside = assert_fs::TempDir::new().unwrap();
+    let plist = outside.child("Preferences/com.example.plist");
+    let plist_path = plist.path().to_string_lossy().into_owned();
+
+    temp.child("ordinator.toml")
+        .write_str(&format!(
+            r#"
+[profiles.default]
+files = ["$XDG_CONFIG_HOME/app/config.toml", "${{HOME}}/.vimrc", "{plist_path}"]
+"#
+        ))
+        .unwrap();
+    temp.child("files/default/$XDG_CONFIG_HOME/app/config.toml")
+        .write_str("theme = \"dark\"\n")
+        .unwrap();
+    temp.child("files/default/~/.vimrc")
+        .write_str("set number\n")
+        .unwrap();
+    temp.child(format!(
+        "files/default/{}",
+        plist_path.trim_start_matches('/')
+    ))
+    .write_str("<plist/>\n")
+    .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-secrets", "--skip-brew"]);
+    cmd.assert().success();
+    for link in [
+        temp.child(".config/app/config.toml").path().to_path_buf(),
+        temp.child(".vimrc").path().to_path_buf(),
+        plist.path().to_path_buf(),
+    ] {
+        assert!(
+            fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink(),
+            "{} is not a symlink",
+            link.display()
+        );
+    }
+
+    // Entries keep the form they were written in; ${HOME} is written as ~
+    temp.child(".gitconfig").write_str("[user]\n").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", "~/.gitconfig"]);
+    cmd.assert().success();
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("\"$XDG_CONFIG_HOME/app/config.toml\""));
+    assert!(config.contains("\"~/.vimrc\""));
+    assert!(config.contains("\"~/.gitconfig\""));
+    assert!(config.contains(&format!("\"{plist_path}\"")));
+
+    temp.child("ordinator.toml")
+        .write_str("[profiles.default]\nfiles = [\"$WORK_DIR/notes.txt\"]\n")
+        .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-secrets", "--skip-brew"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains("starts with $WORK_DIR"));
+}
+
+#[test]
+fn test_apply_leaves_unchanged_secrets_unwritten() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let key = temp.child("age.key");
+    key.write_str("# public key: age1test\nAGE-SECRET-KEY-1TEST\n")
+        .unwrap();
+    let config = format!(
+        r#"
+[global]
+default_profile = "default"
+auto_push = false
+
+[profiles.default]
+files = []
+secrets = [".npmrc"]
+
+[profiles.default.file_mappings]
+"abc123_.npmrc.enc" = ".npmrc"
+
+[secrets]
+age_key_file = "{}"
+"#,
+        key.path().display()
+    );
+    let (_config_guard, _test_mode_guard) =
+        common::setup_test_environment_with_config(&temp, Some(&config));
+    temp.child("config/age/key.txt")
+        .write_str("AGE-SECRET-KEY-1TEST\n")
+        .unwrap();
+    let stored = temp.child("secrets/default/abc123_.npmrc.enc");
+    stored.write_str("sops:\ntoken=abc\n").unwrap();
+
+    // Stand-in sops "decrypts" by dropping the marker line
+    let bin_dir = temp.child("bin");
+    bin_dir.create_dir_all().unwrap();
+    for (name, script) in [
+        (
+            "sops",
+            "#!/bin/sh\nfor last; do :; done\nsed 1d \"$last\"\n",
+        ),
+        ("age", "#!/bin/sh\nexit 0\n"),
+    ] {
+        let path = bin_dir.child(name);
+        path.write_str(script).unwrap();
+        std::fs::set_permissions(
+            path.path(),
+            std::os::unix::fs::PermissionsExt::from_mode(0o755),
+        )
+        .unwrap();
+    }
+    let path = format!(
+        "{}:{}",
+        bin_dir.path().display(),
+        std::env::var("PATH").unwrap()
+    );
+    let apply = || {
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.env("PATH", &path)
+            .env("ORDINATOR_CONFIG_DIR", temp.child("config").path())
+            .args([
+                "apply",
+                "--profile",
+                "default",
+                "--skip-brew",
+                "--skip-bootstrap",
+            ]);
+        cmd.assert().success()
+    };
+
+    apply().stderr(predicates::str::contains(
+        "Secrets: 1 updated, 0 up to date",
+    ));
+    let npmrc = temp.child(".npmrc");
+    assert_eq!(
+        std::fs::read_to_string(npmrc.path()).unwrap(),
+        "token=abc\n"
+    );
+    let written = std::fs::metadata(npmrc.path()).unwrap().modified().unwrap();
+
+    apply()
+        .stderr(predicates::str::contains(
+            "Secrets: 0 updated, 1 up to date",
+        ))
+        .stderr(predicates::str::contains("  up to date  "));
+    let unchanged = std::fs::metadata(npmrc.path()).unwrap().modified().unwrap();
+    assert_eq!(written, unchanged);
+
+    stored.write_str("sops:\ntoken=xyz\n").unwrap();
+    apply().stderr(predicates::str::contains(
+        "Secrets: 1 updated, 0 up to date",
+    ));
+    assert_eq!(
+        std::fs::read_to_string(npmrc.path()).unwrap(),
+        "token=xyz\n"
+    );
+}
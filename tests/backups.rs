@@ -0,0 +1,258 @@
+mod common;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use predicates::str::contains;
+
+/// Write backups of .zshrc as apply would have left them
+fn write_backups(temp: &assert_fs::TempDir) {
+    temp.child("backups/.zshrc.backup.1.20250101-090000")
+        .write_str("oldest\n")
+        .unwrap();
+    temp.child("backups/.zshrc.backup.1.20250102-090000")
+        .write_str("older\n")
+        .unwrap();
+    temp.child("backups/.zshrc.backup.1.20250103-090000")
+        .write_str("newest\n")
+        .unwrap();
+    temp.child("backups/.vimrc.backup.1.20250101-090000")
+        .write_str("vim\n")
+        .unwrap();
+}
+
+#[test]
+fn test_backups_list_groups_by_file_newest_first() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    write_backups(&temp);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["backups", "list"]);
+    cmd.assert().success().stdout(
+        contains(".vimrc:\n  .vimrc.backup.1.20250101-090000  (2025-01-01 09:00:00)\n").and(
+            contains(
+                ".zshrc:\n  .zshrc.backup.1.20250103-090000  (2025-01-03 09:00:00)\n  .zshrc.backup.1.20250102-090000",
+            ),
+        ),
+    );
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["backups", "list", ".vimrc"]);
+    cmd.assert()
+        .success()
+        .stdout(contains(".vimrc.backup").and(contains(".zshrc").not()));
+}
+
+#[test]
+fn test_backups_prune_keeps_newest() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    write_backups(&temp);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["backups", "prune"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("No backup retention configured"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["--dry-run", "backups", "prune", "--max-count", "1"]);
+    cmd.assert()
+        .success()
+        .stderr(contains(
+            "DRY-RUN: Would delete .zshrc.backup.1.20250101-090000",
+        ))
+        .stderr(contains(
+            "DRY-RUN: Would delete .zshrc.backup.1.20250102-090000",
+        ));
+    temp.child("backups/.zshrc.backup.1.20250101-090000")
+        .assert(predicate::path::exists());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["backups", "prune", "--max-count", "1"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Pruned 2 backup(s)"));
+    temp.child("backups/.zshrc.backup.1.20250101-090000")
+        .assert(predicate::path::missing());
+    temp.child("backups/.zshrc.backup.1.20250102-090000")
+        .assert(predicate::path::missing());
+    temp.child("backups/.zshrc.backup.1.20250103-090000")
+        .assert(predicate::path::exists());
+    temp.child("backups/.vimrc.backup.1.20250101-090000")
+        .assert(predicate::path::exists());
+}
+
+#[test]
+fn test_backups_restore_replaces_current_file() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    write_backups(&temp);
+    temp.child(".zshrc").write_str("current\n").unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["backups", "restore", ".zshrc"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("from .zshrc.backup.1.20250103-090000"));
+    temp.child(".zshrc").assert("newest\n");
+
+    // The file that was replaced is kept as a new backup
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["backups", "list", ".zshrc"]);
+    let output = cmd.output().unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout)
+            .matches(".zshrc.backup.")
+            .count(),
+        4
+    );
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "backups",
+        "restore",
+        "~/.zshrc",
+        "--backup",
+        ".zshrc.backup.1.20250101-090000",
+    ]);
+    cmd.assert().success();
+    temp.child(".zshrc").assert("oldest\n");
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["backups", "restore", ".zshrc", "--backup", "missing"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("Backup 'missing' not found for '.zshrc'"));
+}
+
+#[test]
+fn test_apply_backups_mirror_the_home_directory() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    for dir in ["a", "b"] {
+        let file = format!(".config/{dir}/init.lua");
+        temp.child(&file).write_str(&format!("-- {dir}\n")).unwrap();
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.args(["watch", &file, "--profile", "default"]);
+        cmd.assert().success();
+    }
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-brew", "--force"]);
+    cmd.assert().success();
+    let backups = temp.child(".local/state/ordinator/backups/home/.config");
+    for dir in ["a", "b"] {
+        let names: Vec<String> = std::fs::read_dir(backups.child(dir).path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names.len(), 1, "{names:?}");
+        assert!(names[0].starts_with("init.lua.backup.1."), "{names:?}");
+    }
+    assert!(!temp.child("backups").path().exists());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["backups", "list"]);
+    cmd.assert().success().stdout(
+        contains(".config/a/init.lua:\n  init.lua.backup.1.")
+            .and(contains(".config/b/init.lua:\n  init.lua.backup.1.")),
+    );
+
+    // A backup directory inside the repository is kept out of commits
+    let config_file = temp.child("ordinator.toml");
+    let mut config: toml::Value =
+        toml::from_str(&std::fs::read_to_string(config_file.path()).unwrap()).unwrap();
+    config["global"]
+        .as_table_mut()
+        .unwrap()
+        .insert("backup_dir".to_string(), "backups".into());
+    std::fs::write(config_file.path(), toml::to_string(&config).unwrap()).unwrap();
+    std::fs::remove_file(temp.child(".config/a/init.lua").path()).unwrap();
+    temp.child(".config/a/init.lua")
+        .write_str("-- edited\n")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-brew", "--force"]);
+    cmd.assert().success();
+    assert_eq!(
+        std::fs::read_dir(temp.child("backups/home/.config/a").path())
+            .unwrap()
+            .count(),
+        1
+    );
+    let gitignore = std::fs::read_to_string(temp.child(".gitignore").path()).unwrap();
+    assert!(gitignore.contains("\n/backups/\n"), "{gitignore}");
+}
+
+#[test]
+fn test_apply_snapshot_restores_every_destination() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    temp.child(".zshrc")
+        .write_str("export EDITOR=vim\n")
+        .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".zshrc", "--profile", "default"]);
+    cmd.assert().success();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["--dry-run", "apply", "--skip-brew", "--snapshot"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("DRY-RUN: Would snapshot 1 destination(s)"));
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["snapshots", "list"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("No snapshots found."));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-brew", "--force", "--snapshot"]);
+    let output = cmd.output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert!(stderr.contains("saved 1 destination(s)"), "{stderr}");
+    assert!(stderr.contains("To put back everything it touched, run: ordinator snapshots restore "));
+    assert!(temp.child(".zshrc").path().is_symlink());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["snapshots", "list"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("profile 'default'  1 destination(s)"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["--dry-run", "snapshots", "restore", "latest"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("DRY-RUN: Would restore from snapshot"));
+    assert!(temp.child(".zshrc").path().is_symlink());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["snapshots", "restore", "latest"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("Restored "))
+        .stderr(contains("Restored 1 destination(s) from snapshot"));
+    assert!(!temp.child(".zshrc").path().is_symlink());
+    temp.child(".zshrc").assert("export EDITOR=vim\n");
+
+    // The link apply made was saved before the restore, so it can be undone
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["snapshots", "list"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("(before restoring "));
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["snapshots", "restore", "latest"]);
+    cmd.assert().success();
+    assert!(temp.child(".zshrc").path().is_symlink());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["snapshots", "restore", "nope"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("No snapshot 'nope'"));
+}
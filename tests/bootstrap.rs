@@ -2,6 +2,7 @@ mod common;
 use assert_cmd::assert::OutputAssertExt;
 use assert_fs::fixture::FileWriteStr;
 use assert_fs::fixture::PathChild;
+use predicates::prelude::PredicateBooleanExt;
 use predicates::str::contains;
 
 #[test]
@@ -266,3 +267,183 @@ echo "Blocked script"
         .stderr(contains("BLOCKED"))
         .stderr(contains("rm -rf /"));
 }
+
+#[test]
+fn test_bootstrap_run_logs_output_and_enforces_timeout() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    temp.child("ordinator.toml")
+        .write_str(
+            r#"
+[global]
+bootstrap_timeout_secs = 1
+
+[profiles.work]
+bootstrap_script = "scripts/work/bootstrap.sh"
+files = []
+"#,
+        )
+        .unwrap();
+    temp.child("scripts/work/bootstrap.sh")
+        .write_str("#!/bin/bash\necho \"setting up $ORDINATOR_PROFILE\"\n")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["bootstrap", "run", "--profile", "work"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("] setting up work"))
+        .stderr(contains("Bootstrap script finished"));
+    let logs: Vec<_> = std::fs::read_dir(temp.child("logs/bootstrap").path())
+        .unwrap()
+        .collect();
+    assert_eq!(logs.len(), 1);
+
+    temp.child("scripts/work/bootstrap.sh")
+        .write_str("#!/bin/bash\nsleep 30\n")
+        .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["bootstrap", "run", "--profile", "work"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("timed out after 1s"));
+}
+
+#[test]
+fn test_bootstrap_history_and_apply_warning_when_script_changed() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    temp.child("ordinator.toml")
+        .write_str(
+            r#"
+[profiles.work]
+bootstrap_script = "scripts/work/bootstrap.sh"
+files = []
+"#,
+        )
+        .unwrap();
+    let script = temp.child("scripts/work/bootstrap.sh");
+    script.write_str("#!/bin/bash\necho one\n").unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["bootstrap", "history"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("No bootstrap runs recorded on this machine"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["bootstrap", "run", "--profile", "work"]);
+    cmd.assert().success();
+    script.write_str("#!/bin/bash\nexit 3\n").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["bootstrap", "run", "--profile", "work"]);
+    cmd.assert().failure();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["bootstrap", "history"]);
+    let output = cmd.output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.contains("  work  "))
+        .collect();
+    assert_eq!(lines.len(), 2, "{stdout}");
+    assert!(lines[0].contains("  work  exit 3 "), "{stdout}");
+    assert!(lines[1].contains("  work  ok "), "{stdout}");
+    assert!(lines[1].contains("scripts/work/bootstrap.sh"), "{stdout}");
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["bootstrap", "history", "--limit", "1"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("exit 3").and(contains(" ok ").not()));
+
+    // The failed run doesn't count: the script changed since the last successful one
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--profile", "work", "--skip-brew"]);
+    cmd.assert().success().stderr(contains(
+        "The bootstrap script for profile 'work' changed since it last ran successfully here",
+    ));
+    script.write_str("#!/bin/bash\necho one\n").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--profile", "work", "--skip-brew"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("changed since it last ran").not());
+}
+
+#[test]
+fn test_bootstrap_run_refuses_blocked_script() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    temp.child("ordinator.toml")
+        .write_str(
+            r#"
+[profiles.work]
+bootstrap_script = "scripts/work/bootstrap.sh"
+files = []
+"#,
+        )
+        .unwrap();
+    temp.child("scripts/work/bootstrap.sh")
+        .write_str("#!/bin/bash\nrm -rf /\n")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["bootstrap", "run", "--profile", "work"]);
+    cmd.assert().failure().stderr(contains("blocked commands"));
+    assert!(!temp.child("logs").path().exists());
+}
+
+#[test]
+fn test_bootstrap_run_for_profiles_matching_a_glob() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    temp.child("ordinator.toml")
+        .write_str(
+            r#"
+[profiles.personal]
+files = []
+
+[profiles.work-a]
+bootstrap_script = "scripts/work-a/bootstrap.sh"
+files = []
+
+[profiles.work-b]
+bootstrap_script = "scripts/work-b/bootstrap.sh"
+files = []
+"#,
+        )
+        .unwrap();
+    temp.child("scripts/work-a/bootstrap.sh")
+        .write_str("#!/bin/bash\necho \"setting up $ORDINATOR_PROFILE\"\n")
+        .unwrap();
+    temp.child("scripts/work-b/bootstrap.sh")
+        .write_str("#!/bin/bash\nexit 3\n")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["bootstrap", "run", "--profile", "work*"]);
+    cmd.assert()
+        .failure()
+        .stdout(contains("] setting up work-a"))
+        .stderr(contains("✅ work-a: bootstrap script finished"))
+        .stderr(contains(
+            "❌ work-b: Bootstrap script failed with exit code 3",
+        ))
+        .stderr(contains("Bootstrap scripts failed for 1 profile(s)"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["bootstrap", "run", "--all-profiles", "--dry-run"]);
+    cmd.assert()
+        .success()
+        .stderr(contains(
+            "No bootstrap script configured for profile 'personal'; skipping it",
+        ))
+        .stderr(contains("Would run").count(2));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["bootstrap", "run", "--profile", "home*"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("error[E0103]: No profile matches 'home*'."));
+}
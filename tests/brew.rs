@@ -2,6 +2,7 @@ mod common;
 use assert_cmd::assert::OutputAssertExt;
 use assert_fs::fixture::PathChild;
 use assert_fs::fixture::PathCreateDir;
+use predicates::prelude::PredicateBooleanExt;
 use predicates::str::contains;
 use std::io::Write;
 use std::os::unix::fs::PermissionsExt;
@@ -126,3 +127,314 @@ fn test_brew_install_and_apply_with_dummy_brew() {
         .success()
         .stderr(contains("Homebrew packages installed successfully"));
 }
+
+#[test]
+fn test_brew_import_brewfile() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let brewfile = temp.child("Brewfile");
+    std::fs::write(
+        brewfile.path(),
+        "tap \"homebrew/cask-fonts\"\nbrew \"git\"\nbrew \"postgresql@16\", restart_service: true\ncask \"firefox\"\nmas \"Xcode\", id: 497799835\nvscode \"rust-lang.rust-analyzer\"\n",
+    )
+    .unwrap();
+
+    // Importing only edits config, so it works without Homebrew installed
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "brew",
+        "import",
+        brewfile.path().to_str().unwrap(),
+        "--profile",
+        "work",
+    ]);
+    cmd.assert()
+        .success()
+        .stderr(contains(
+            "Imported 1 tap(s), 2 formula(s), 1 cask(s), and 1 App Store app(s) into profile 'work'",
+        ))
+        .stderr(contains("Skipped entries ordinator does not manage"));
+
+    let config = std::fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("homebrew/cask-fonts"));
+    assert!(config.contains("postgresql@16"));
+    assert!(config.contains("firefox"));
+    assert!(config.contains("497799835"));
+    assert!(!config.contains("rust-analyzer"));
+}
+
+#[test]
+fn test_brew_diff_and_sync_with_dummy_brew() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    // Dummy brew: git and wget installed, git outdated; uninstalls are logged
+    let brew_dir = temp.child("dummy_bin");
+    brew_dir.create_dir_all().unwrap();
+    let brew_path = brew_dir.child("brew");
+    let log = temp.child("brew.log");
+    std::fs::write(
+        brew_path.path(),
+        format!(
+            r#"#!/bin/sh
+case "$1 $2" in
+  "--version "*) echo 'Homebrew 4.0.0' ;;
+  "leaves -r") printf 'git\nwget\n' ;;
+  "list --cask") echo 'firefox' ;;
+  "outdated --json=v2") echo '{{"formulae":[{{"name":"git","installed_versions":["2.40.0"],"current_version":"2.41.0"}}],"casks":[]}}' ;;
+  *) echo "$@" >> '{}' ;;
+esac
+exit 0
+"#,
+            log.path().display()
+        ),
+    )
+    .unwrap();
+    let mut perms = std::fs::metadata(brew_path.path()).unwrap().permissions();
+    perms.set_mode(0o755);
+    std::fs::set_permissions(brew_path.path(), perms).unwrap();
+    let new_path = format!(
+        "{}:{}",
+        brew_dir.path().display(),
+        std::env::var("PATH").unwrap()
+    );
+
+    let brewfile = temp.child("Brewfile");
+    std::fs::write(
+        brewfile.path(),
+        "brew \"git\"\nbrew \"awscli\"\ncask \"firefox\"\n",
+    )
+    .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["brew", "import", brewfile.path().to_str().unwrap()]);
+    cmd.assert().success();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &new_path);
+    cmd.args(["brew", "diff", "--profile", "default"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("+ awscli (formula, not installed)"))
+        .stdout(contains("- wget (formula, not in profile)"))
+        .stdout(contains("~ git 2.40.0 -> 2.41.0 (outdated)"))
+        .stdout(contains("1 missing, 1 extra, 1 outdated"));
+
+    // Extras are kept unless asked
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &new_path);
+    cmd.args(["brew", "sync", "--profile", "default"]);
+    cmd.assert().success().stderr(contains(
+        "1 package(s) not in the profile were left installed",
+    ));
+    let logged = std::fs::read_to_string(log.path()).unwrap();
+    assert!(logged.contains("install awscli"));
+    assert!(!logged.contains("uninstall"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &new_path);
+    cmd.args(["brew", "sync", "--profile", "default", "--remove-extra"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Homebrew packages synced with profile 'default'"));
+    let logged = std::fs::read_to_string(log.path()).unwrap();
+    assert!(logged.contains("uninstall wget"));
+}
+
+#[test]
+fn test_brew_install_continues_past_failures() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    // Dummy brew: nothing installed, and 'nosuchformula' can't be installed
+    let brew_dir = temp.child("dummy_bin");
+    brew_dir.create_dir_all().unwrap();
+    let brew_path = brew_dir.child("brew");
+    let log = temp.child("brew.log");
+    std::fs::write(
+        brew_path.path(),
+        format!(
+            r#"#!/bin/sh
+echo "$@" >> '{}'
+case "$1 $2" in
+  "--version "*) echo 'Homebrew 4.0.0' ;;
+  "install nosuchformula") echo 'Error: No available formula with the name "nosuchformula".' >&2; exit 1 ;;
+esac
+exit 0
+"#,
+            log.path().display()
+        ),
+    )
+    .unwrap();
+    let mut perms = std::fs::metadata(brew_path.path()).unwrap().permissions();
+    perms.set_mode(0o755);
+    std::fs::set_permissions(brew_path.path(), perms).unwrap();
+    let new_path = format!(
+        "{}:{}",
+        brew_dir.path().display(),
+        std::env::var("PATH").unwrap()
+    );
+
+    let brewfile = temp.child("Brewfile");
+    std::fs::write(
+        brewfile.path(),
+        "brew \"nosuchformula\"\nbrew \"ripgrep\"\ncask \"firefox\"\n",
+    )
+    .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["brew", "import", brewfile.path().to_str().unwrap()]);
+    cmd.assert().success();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &new_path);
+    cmd.args(["brew", "install", "--profile", "default", "--force"]);
+    cmd.assert()
+        .success()
+        .stderr(contains(
+            "nosuchformula  formula        failed: No available formula with the name \"nosuchformula\".",
+        ))
+        .stderr(contains("ripgrep        formula        installed"))
+        .stderr(contains("firefox        cask           installed"))
+        .stderr(contains("1 Homebrew package(s) failed to install"));
+    let logged = std::fs::read_to_string(log.path()).unwrap();
+    assert!(logged.contains("fetch --deps ripgrep"));
+    assert!(logged.contains("fetch --cask firefox"));
+    assert!(logged.contains("install --cask firefox"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &new_path);
+    cmd.args([
+        "brew",
+        "install",
+        "--profile",
+        "default",
+        "--force",
+        "--strict",
+    ]);
+    cmd.assert().failure().stderr(contains(
+        "error[E0503]: 1 Homebrew package(s) failed to install.",
+    ));
+
+    // apply reports the failure and carries on, unless --strict
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &new_path);
+    cmd.args(["apply", "--yes", "--skip-bootstrap"]);
+    cmd.assert().success().stderr(contains(
+        "failed to install; the rest of the apply continues",
+    ));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &new_path);
+    cmd.args(["apply", "--yes", "--skip-bootstrap", "--strict"]);
+    cmd.assert().failure().stderr(contains("E0503"));
+}
+
+#[test]
+fn test_brew_outdated_and_status_warning() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    // Dummy brew: git (declared) and wget (not declared) are outdated
+    let brew_dir = temp.child("dummy_bin");
+    brew_dir.create_dir_all().unwrap();
+    let brew_path = brew_dir.child("brew");
+    std::fs::write(
+        brew_path.path(),
+        r#"#!/bin/sh
+case "$1 $2" in
+  "--version "*) echo 'Homebrew 4.0.0' ;;
+  "outdated --json=v2") echo '{"formulae":[{"name":"git","installed_versions":["2.40.0"],"current_version":"2.41.0"},{"name":"wget","installed_versions":["1.21"],"current_version":"1.24"}],"casks":[]}' ;;
+esac
+exit 0
+"#,
+    )
+    .unwrap();
+    let mut perms = std::fs::metadata(brew_path.path()).unwrap().permissions();
+    perms.set_mode(0o755);
+    std::fs::set_permissions(brew_path.path(), perms).unwrap();
+    let new_path = format!(
+        "{}:{}",
+        brew_dir.path().display(),
+        std::env::var("PATH").unwrap()
+    );
+    std::fs::write(
+        temp.child("ordinator.toml").path(),
+        "[profiles.default]\nhomebrew_formulas = [\"git\"]\n",
+    )
+    .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &new_path);
+    cmd.args(["brew", "outdated", "--profile", "default"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("git 2.40.0 -> 2.41.0 (formula)"))
+        .stdout(contains("wget").not())
+        .stdout(contains("1 outdated package(s) in profile 'default'"));
+
+    // The notice is off unless asked for
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &new_path);
+    cmd.args(["status", "--profile", "default"]);
+    cmd.assert().success().stderr(contains("outdated").not());
+
+    std::fs::write(
+        temp.child("ordinator.toml").path(),
+        "[brew]\nwarn_outdated = true\n\n[profiles.default]\nhomebrew_formulas = [\"git\"]\n",
+    )
+    .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &new_path);
+    cmd.args(["status", "--profile", "default"]);
+    cmd.assert().success().stderr(contains(
+        "1 Homebrew package(s) declared in profile 'default' are outdated (git)",
+    ));
+}
+
+#[test]
+fn test_simulate_shows_brew_commands_without_running_them() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    // Dummy brew: logs every call, nothing installed
+    let brew_dir = temp.child("dummy_bin");
+    brew_dir.create_dir_all().unwrap();
+    let brew_path = brew_dir.child("brew");
+    let log = temp.child("brew.log");
+    std::fs::write(
+        brew_path.path(),
+        format!(
+            "#!/bin/sh\necho \"$@\" >> '{}'\n[ \"$1\" = --version ] && echo 'Homebrew 4.0.0'\nexit 0\n",
+            log.path().display()
+        ),
+    )
+    .unwrap();
+    let mut perms = std::fs::metadata(brew_path.path()).unwrap().permissions();
+    perms.set_mode(0o755);
+    std::fs::set_permissions(brew_path.path(), perms).unwrap();
+    let new_path = format!(
+        "{}:{}",
+        brew_dir.path().display(),
+        std::env::var("PATH").unwrap()
+    );
+    std::fs::write(
+        temp.child("ordinator.toml").path(),
+        "[profiles.default]\nhomebrew_formulas = [\"jq\"]\nhomebrew_casks = [\"iterm2\"]\n",
+    )
+    .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &new_path);
+    cmd.args(["--simulate", "brew", "install", "--profile", "default"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("SIMULATE: No changes will be made"))
+        .stderr(contains("SIMULATE: Would run: brew install jq"))
+        .stderr(contains("SIMULATE: Would run: brew install --cask iterm2"));
+
+    // Listing ran for real; installing did not
+    let calls = std::fs::read_to_string(log.path()).unwrap();
+    assert!(calls.contains("list"));
+    assert!(!calls.contains("install"));
+    assert!(!calls.contains("fetch"));
+}
@@ -3,6 +3,7 @@ use assert_cmd::assert::OutputAssertExt;
 use assert_cmd::output::OutputOkExt;
 use assert_cmd::Command;
 use assert_fs::fixture::PathChild;
+use predicates::prelude::PredicateBooleanExt;
 use std::fs;
 
 #[test]
@@ -54,11 +55,9 @@ fn test_commit_with_empty_message() {
 fn test_commit_with_secrets_detection_edge_cases() {
     let temp = assert_fs::TempDir::new().unwrap();
     let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
-
-    // Initialize a proper Git repository using the CLI
-    let mut init_cmd = common::create_ordinator_command(&temp);
-    init_cmd.arg("init");
-    init_cmd.unwrap();
+    // Only staged files are scanned, and staging needs a real repository
+    fs::remove_dir_all(temp.path().join(".git")).unwrap();
+    git2::Repository::init(temp.path()).unwrap();
 
     // Create files with various secret patterns
     let files_dir = temp.path().join("files");
@@ -94,6 +93,66 @@ fn test_commit_with_secrets_detection_edge_cases() {
         .stderr(predicates::str::contains("Plaintext secrets detected"));
 }
 
+#[test]
+fn test_commit_scans_only_staged_tracked_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    fs::remove_dir_all(temp.path().join(".git")).unwrap();
+    git2::Repository::init(temp.path()).unwrap();
+
+    fs::create_dir_all(temp.path().join("files")).unwrap();
+    fs::write(
+        temp.path().join("files/.env"),
+        "password=mysecretpassword123\n",
+    )
+    .unwrap();
+    fs::write(temp.path().join("files/.zshrc"), "export EDITOR=vim\n").unwrap();
+    for file in ["files/.env", "files/.zshrc"] {
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.args(["watch", file]);
+        cmd.assert().success();
+    }
+
+    // .env isn't part of this commit, so its secret doesn't block it
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["commit", "-m", "Add zshrc", "--yes", "files/.zshrc"]);
+    cmd.assert().success();
+    assert!(temp.path().join("cache/secret-scan.json").exists());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["commit", "-m", "Everything", "--yes"]);
+    cmd.assert().failure().stderr(
+        predicates::str::contains("Plaintext secrets detected")
+            .and(predicates::str::contains("files/.env")),
+    );
+}
+
+#[test]
+fn test_commit_scans_stored_copies_when_home_isnt_the_repository() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    fs::remove_dir_all(temp.path().join(".git")).unwrap();
+    git2::Repository::init(temp.path()).unwrap();
+
+    let home = temp.path().join("home");
+    fs::create_dir_all(&home).unwrap();
+    fs::write(home.join(".env"), "password=mysecretpassword123\n").unwrap();
+    for command in ["watch", "add"] {
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.env("ORDINATOR_HOME", &home);
+        cmd.args([command, home.join(".env").to_str().unwrap()]);
+        cmd.assert().success();
+    }
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("ORDINATOR_HOME", &home);
+    cmd.args(["commit", "-m", "Add env", "--yes"]);
+    cmd.assert().failure().stderr(
+        predicates::str::contains("Plaintext secrets detected")
+            .and(predicates::str::contains(".env")),
+    );
+}
+
 #[test]
 fn test_commit_with_very_long_message() {
     let temp = assert_fs::TempDir::new().unwrap();
@@ -161,3 +220,144 @@ fn test_commit_with_force_flag_bypasses_secrets_check() {
     // Should bypass secrets check with --force
     assert!(output.status.success());
 }
+
+#[test]
+fn test_commit_shows_summary_of_selected_paths() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    // init only creates an empty .git in test mode; staging needs a real repository
+    fs::remove_dir_all(temp.path().join(".git")).unwrap();
+    git2::Repository::init(temp.path()).unwrap();
+
+    fs::create_dir_all(temp.path().join("files")).unwrap();
+    fs::write(temp.path().join("files/.zshrc"), "export EDITOR=vim\n").unwrap();
+    fs::create_dir_all(temp.path().join("secrets/default")).unwrap();
+    fs::write(temp.path().join("secrets/default/abc123_token.enc"), "sops").unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "commit",
+        "-m",
+        "Add zshrc",
+        "--exclude-secrets",
+        "--yes",
+        "files",
+    ]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("added    files/.zshrc"));
+    assert!(stderr.contains("1 added, 0 modified, 0 deleted (1 KiB)"));
+    assert!(!stderr.contains("secrets/default"));
+    assert!(!stderr.contains("ordinator.toml"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["commit", "-m", "Everything", "--yes"]);
+    let output = cmd.output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("added    secrets/default/abc123_token.enc"));
+    assert!(stderr.contains("added    ordinator.toml"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "commit",
+        "-m",
+        "Conflicting",
+        "--include-secrets",
+        "--exclude-secrets",
+    ]);
+    cmd.assert().failure();
+}
+
+#[test]
+fn test_commit_include_secrets_stages_them_with_selected_paths() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    fs::remove_dir_all(temp.path().join(".git")).unwrap();
+    git2::Repository::init(temp.path()).unwrap();
+
+    fs::create_dir_all(temp.path().join("files")).unwrap();
+    fs::write(temp.path().join("files/.zshrc"), "export EDITOR=vim\n").unwrap();
+    fs::create_dir_all(temp.path().join("secrets/default")).unwrap();
+    fs::write(temp.path().join("secrets/default/abc123_token.enc"), "sops").unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["commit", "-m", "Add zshrc", "--yes", "files"]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("secrets/default"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "commit",
+        "-m",
+        "Add zshrc and token",
+        "--include-secrets",
+        "--yes",
+        "files",
+    ]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("added    secrets/default/abc123_token.enc"));
+    assert!(!stderr.contains("ordinator.toml"));
+}
+
+#[test]
+fn test_commit_refreshes_existing_lock() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    fs::remove_dir_all(temp.path().join(".git")).unwrap();
+    git2::Repository::init(temp.path()).unwrap();
+    fs::create_dir_all(temp.path().join("files")).unwrap();
+    fs::write(temp.path().join("files/.zshrc"), "export EDITOR=vim\n").unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.arg("lock");
+    cmd.assert().success();
+
+    fs::write(temp.path().join("files/.zshrc"), "export EDITOR=nvim\n").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["commit", "-m", "Use nvim", "--yes", "files"]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("added    ordinator.lock"), "{stderr}");
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["lock", "--check"]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_auto_commit_and_push_after_watch() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    fs::remove_dir_all(temp.path().join(".git")).unwrap();
+    let repo = git2::Repository::init(temp.path()).unwrap();
+    repo.remote("origin", "https://github.com/testuser/dotfiles.git")
+        .unwrap();
+    for (key, value) in [("global.auto_commit", "true"), ("global.auto_push", "true")] {
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.args(["config", "set", key, value]);
+        cmd.assert().success();
+    }
+
+    let zshrc = temp.child(".zshrc");
+    fs::write(zshrc.path(), "export EDITOR=vim\n").unwrap();
+    let zshrc = zshrc.path().to_str().unwrap().to_string();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", &zshrc, "--profile", "work"]);
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains(format!(
+            "Committed: ordinator: watch {zshrc} in work"
+        )))
+        .stderr(predicates::str::contains("Pushed to 'origin'"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["add", &zshrc, "--profile", "work", "--no-commit"]);
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains("Committed:").not());
+}
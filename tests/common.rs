@@ -30,6 +30,46 @@ impl Drop for EnvVarGuard {
     }
 }
 
+/// A spawned child process that is killed and reaped when dropped, so a
+/// failing assertion doesn't leave it running
+#[allow(dead_code)]
+pub struct ChildGuard(pub std::process::Child);
+
+impl Drop for ChildGuard {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Put a stand-in `sops` (rot13 behind a `sops:` header, decrypted to
+/// stdout) and a no-op `age` in `bin/` under `temp`. Returns a `PATH` that
+/// finds them first.
+#[allow(dead_code)]
+pub fn with_fake_sops(temp: &assert_fs::TempDir) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    let bin_dir = temp.child("bin");
+    bin_dir.create_dir_all().unwrap();
+    let tools = [
+        (
+            "sops",
+            "#!/bin/sh\nfor arg; do case \"$arg\" in --encrypt) mode=enc ;; --decrypt) mode=dec ;; esac; file=$arg; done\nif [ \"$mode\" = enc ]; then echo 'sops:'; tr 'a-z' 'n-za-m' < \"$file\"; else sed 1d \"$file\" | tr 'a-z' 'n-za-m'; fi\n",
+        ),
+        ("age", "#!/bin/sh\nexit 0\n"),
+    ];
+    for (name, script) in tools {
+        let tool = bin_dir.child(name);
+        tool.write_str(script).unwrap();
+        std::fs::set_permissions(tool.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    format!(
+        "{}:{}",
+        bin_dir.path().display(),
+        std::env::var("PATH").unwrap()
+    )
+}
+
 /// Set up test environment with optional custom config
 #[allow(dead_code)]
 pub fn setup_test_environment_with_config(
@@ -0,0 +1,118 @@
+mod common;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::fixture::PathChild;
+use predicates::str::contains;
+use std::os::unix::fs::PermissionsExt;
+
+#[test]
+fn test_config_validate_reports_unknown_key() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let config_path = temp.child("ordinator.toml");
+    let mut content = std::fs::read_to_string(config_path.path()).unwrap();
+    content = content.replace("[global]\n", "[global]\ncreate_backup = true\n");
+    std::fs::write(config_path.path(), content).unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["config", "validate"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("unknown field `create_backup`"))
+        .stderr(contains("did you mean `create_backups`?"))
+        .stderr(contains("is not a valid ordinator config"));
+
+    // Other commands refuse the file too, with the same position
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["profiles"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("line "))
+        .stderr(contains("did you mean `create_backups`?"));
+}
+
+#[test]
+fn test_config_validate_warns_about_deprecated_keys() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let config_path = temp.child("ordinator.toml");
+    let mut content = std::fs::read_to_string(config_path.path()).unwrap();
+    content.push_str("\n[profiles.legacy]\nhomebrew_packages = [\"git\"]\n");
+    std::fs::write(config_path.path(), content).unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["config", "validate"]);
+    cmd.assert()
+        .success()
+        .stderr(contains(
+            "`homebrew_packages` in [profiles.legacy] is deprecated",
+        ))
+        .stderr(contains("is valid (1 warning(s))"));
+}
+
+#[test]
+fn test_config_get_and_set() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "config",
+        "set",
+        "profiles.default.description",
+        "Work laptop",
+    ]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Set profiles.default.description = Work laptop"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["config", "set", "--append", "global.exclude", "*.log"]);
+    cmd.assert().success();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["config", "get", "profiles.default.description"]);
+    cmd.assert().success().stdout(contains("Work laptop"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["config", "get", "global.exclude"]);
+    cmd.assert().success().stdout(contains("\"*.log\""));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["config", "get", "global.missing"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("'global.missing' is not set"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["config", "set", "global.auto_push", "maybe"]);
+    cmd.assert().failure().stderr(contains("invalid type"));
+}
+
+#[test]
+fn test_config_edit_discards_invalid_changes() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let config_path = temp.child("ordinator.toml");
+    let original = std::fs::read_to_string(config_path.path()).unwrap();
+
+    let editor = temp.child("editor.sh");
+    std::fs::write(
+        editor.path(),
+        "#!/bin/sh\nsed 's/^auto_push/auto_pushh/' \"$1\" > \"$1.tmp\" && mv \"$1.tmp\" \"$1\"\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(editor.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("EDITOR", editor.path());
+    cmd.args(["config", "edit"]);
+    cmd.stdin(std::process::Stdio::null());
+    cmd.assert()
+        .failure()
+        .stderr(contains("did you mean `auto_push`?"))
+        .stderr(contains("Discarded invalid changes"));
+    assert_eq!(
+        std::fs::read_to_string(config_path.path()).unwrap(),
+        original
+    );
+}
@@ -0,0 +1,136 @@
+mod common;
+use assert_fs::prelude::*;
+
+use assert_cmd::assert::OutputAssertExt;
+use predicates::str::contains;
+use std::fs;
+
+#[test]
+fn test_daemon_install_status_uninstall() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["daemon", "install", "--interval", "15", "--watch-files"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Installed launch agent"))
+        .stderr(contains(
+            "Syncing every 15 minute(s) and whenever a tracked file changes",
+        ));
+
+    let plist = temp.child("Library/LaunchAgents/com.ordinator.sync.plist");
+    assert!(plist.path().exists());
+    let content = fs::read_to_string(plist.path()).unwrap();
+    assert!(content.contains("<integer>900</integer>"));
+    assert!(content.contains("<string>run</string>"));
+
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("interval_minutes = 15"));
+    assert!(config.contains("watch_files = true"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["daemon", "status"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Launch agent: installed"))
+        .stderr(contains("Interval: 15 minute(s)"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["daemon", "uninstall"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Launch agent removed."));
+    assert!(!plist.path().exists());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["daemon", "uninstall"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Launch agent is not installed."));
+}
+
+#[test]
+fn test_daemon_install_rejects_unknown_apply_profile() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["daemon", "install", "--apply-profile", "nope"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("Profile 'nope' does not exist."));
+    assert!(!temp
+        .child("Library/LaunchAgents/com.ordinator.sync.plist")
+        .path()
+        .exists());
+}
+
+#[test]
+fn test_daemon_run_single_cycle() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["daemon", "run"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Pulled from remote"))
+        .stderr(contains("Sync complete"));
+}
+
+#[test]
+fn test_daemon_run_holds_back_plaintext_secrets() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    fs::remove_dir_all(temp.path().join(".git")).unwrap();
+    let repo = git2::Repository::init(temp.path()).unwrap();
+
+    let home = temp.path().join("home");
+    fs::create_dir_all(&home).unwrap();
+    fs::write(home.join(".env"), "password=mysecretpassword123\n").unwrap();
+    for command in ["watch", "add"] {
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.env("ORDINATOR_HOME", &home);
+        cmd.args([command, home.join(".env").to_str().unwrap()]);
+        cmd.assert().success();
+    }
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("ORDINATOR_HOME", &home);
+    cmd.args(["daemon", "run"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Not committing or pushing local changes"))
+        .stderr(contains(".env"));
+    assert!(repo.head().is_err(), "nothing was committed");
+}
+
+#[test]
+fn test_daemon_run_runs_commit_and_push_hooks() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    fs::remove_dir_all(temp.path().join(".git")).unwrap();
+    let repo = git2::Repository::init(temp.path()).unwrap();
+    repo.remote("origin", "https://github.com/testuser/dotfiles.git")
+        .unwrap();
+    let config_path = temp.child("ordinator.toml");
+    let mut config = fs::read_to_string(config_path.path()).unwrap();
+    config.push_str("\n[hooks]\npre_commit = [\"log.sh\"]\npost_push = [\"log.sh\"]\n");
+    fs::write(config_path.path(), config).unwrap();
+    temp.child("log.sh")
+        .write_str("echo \"$ORDINATOR_HOOK\" >> hooks.log\n")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["daemon", "run"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Running pre_commit hook: log.sh"))
+        .stderr(contains("Committed local changes"))
+        .stderr(contains("Pushed to remote"));
+    assert_eq!(
+        fs::read_to_string(temp.child("hooks.log").path()).unwrap(),
+        "pre_commit\npost_push\n"
+    );
+}
@@ -0,0 +1,109 @@
+mod common;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::fixture::PathChild;
+use assert_fs::fixture::PathCreateDir;
+use predicates::str::contains;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+
+/// Install a fake `defaults` that exports a fixed Dock domain and logs writes.
+/// Returns the PATH to use.
+fn install_dummy_defaults(temp: &assert_fs::TempDir) -> String {
+    let bin_dir = temp.child("dummy_bin");
+    bin_dir.create_dir_all().unwrap();
+    let script_path = bin_dir.child("defaults");
+    let log_path = temp.child("defaults.log");
+    let mut script = std::fs::File::create(script_path.path()).unwrap();
+    writeln!(script, "#!/bin/sh").unwrap();
+    writeln!(
+        script,
+        "if [ \"$1\" = 'write' ]; then echo \"$@\" >> '{}'; exit 0; fi",
+        log_path.path().display()
+    )
+    .unwrap();
+    writeln!(script, "cat <<'EOF'").unwrap();
+    writeln!(
+        script,
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<plist version=\"1.0\">\n<dict>\n<key>autohide</key>\n<false/>\n<key>tilesize</key>\n<integer>36</integer>\n<key>persistent-apps</key>\n<array/>\n</dict>\n</plist>"
+    )
+    .unwrap();
+    writeln!(script, "EOF").unwrap();
+    let mut perms = std::fs::metadata(script_path.path()).unwrap().permissions();
+    perms.set_mode(0o755);
+    std::fs::set_permissions(script_path.path(), perms).unwrap();
+
+    let old_path = std::env::var("PATH").unwrap();
+    format!("{}:{}", bin_dir.path().display(), old_path)
+}
+
+#[test]
+fn test_defaults_export_diff_apply() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let path = install_dummy_defaults(&temp);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path);
+    cmd.args(["defaults", "export", "com.apple.dock"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Captured 2 setting(s) from com.apple.dock"))
+        .stderr(contains("Skipped 1 key(s)"));
+
+    let config_path = temp.child("ordinator.toml");
+    let config = std::fs::read_to_string(config_path.path()).unwrap();
+    assert!(config.contains("autohide = false"));
+    assert!(config.contains("tilesize = 36"));
+    assert!(!config.contains("persistent-apps"));
+
+    // Nothing differs right after an export
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path);
+    cmd.args(["defaults", "diff"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("No differences for profile 'default'."));
+
+    // Change a setting in the config
+    std::fs::write(
+        config_path.path(),
+        config.replace("autohide = false", "autohide = true"),
+    )
+    .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path);
+    cmd.args(["defaults", "diff"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("com.apple.dock autohide: false -> true"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path);
+    cmd.args(["--dry-run", "defaults", "apply"]);
+    cmd.assert().success().stdout(contains(
+        "[DRY-RUN] Would run: defaults write com.apple.dock autohide -bool true",
+    ));
+    assert!(!temp.child("defaults.log").path().exists());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path);
+    cmd.args(["defaults", "apply"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Applied 1 macOS default(s)"));
+    let log = std::fs::read_to_string(temp.child("defaults.log").path()).unwrap();
+    assert_eq!(log.trim(), "write com.apple.dock autohide -bool true");
+}
+
+#[test]
+fn test_defaults_export_unknown_profile() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["defaults", "export", "com.apple.dock", "--profile", "nope"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("Profile 'nope' does not exist."));
+}
@@ -0,0 +1,78 @@
+mod common;
+use assert_fs::prelude::*;
+
+use assert_cmd::assert::OutputAssertExt;
+use predicates::str::contains;
+
+fn track_file(temp: &assert_fs::TempDir, name: &str, content: &str) {
+    temp.child(name).write_str(content).unwrap();
+    let mut cmd = common::create_ordinator_command(temp);
+    cmd.args(["watch", name]);
+    cmd.assert().success();
+    let mut cmd = common::create_ordinator_command(temp);
+    cmd.args(["add", name]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_diff_shows_changes_to_tracked_file() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    track_file(&temp, "diff_test.txt", "one\ntwo\n");
+
+    temp.child("diff_test.txt")
+        .write_str("one\nthree\n")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["diff", "diff_test.txt"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("--- a/diff_test.txt"))
+        .stdout(contains("+++ b/diff_test.txt"))
+        .stdout(contains("-two"))
+        .stdout(contains("+three"));
+}
+
+#[test]
+fn test_diff_stat_and_no_changes() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    track_file(&temp, "stat_a.txt", "a\n");
+    track_file(&temp, "stat_b.txt", "b\n");
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["diff"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("No differences for profile 'default'"));
+
+    temp.child("stat_a.txt").write_str("a\nmore\n").unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["diff", "--stat"]);
+    cmd.assert()
+        .success()
+        .stdout(contains(" stat_a.txt | 1 +"))
+        .stdout(contains(
+            "1 file(s) changed, 1 insertion(s)(+), 0 deletion(s)(-)",
+        ));
+}
+
+#[test]
+fn test_diff_rejects_untracked_path_and_unknown_profile() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["diff", "not_tracked.txt"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("is not tracked in profile 'default'"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["diff", "--profile", "nope"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("Profile 'nope' does not exist."));
+}
@@ -4,7 +4,6 @@ use assert_cmd::Command;
 use assert_fs::fixture::FileWriteStr;
 use assert_fs::fixture::PathChild;
 use assert_fs::fixture::PathCreateDir;
-use predicates::prelude::PredicateBooleanExt;
 use predicates::str::contains;
 use std::fs;
 use std::os::unix::fs::PermissionsExt;
@@ -76,10 +75,8 @@ fn test_cli_with_missing_dependencies() {
     cmd.env("PATH", bin_dir.path());
     cmd.arg("status");
 
-    // In test mode, git operations are skipped, so it should succeed
-    cmd.assert()
-        .success()
-        .stderr(contains("TEST MODE").or(contains("Git repository")));
+    // Git is read through libgit2, so a broken git binary doesn't matter
+    cmd.assert().success().stderr(contains("Repository Status"));
 }
 
 #[test]
@@ -240,12 +237,12 @@ fn test_cli_with_nested_directories() {
     watch_cmd.args(["watch", "nested/"]);
     watch_cmd.assert().success();
 
-    // Add the nested directory - should fail because CLI only supports files, not directories
+    // Adding the tracked directory syncs it; nothing has changed since watch
     let mut cmd = common::create_ordinator_command(&temp);
     cmd.args(["add", "nested/"]);
-    cmd.assert().failure().stderr(contains(
-        "neither a regular file nor a symlink to a regular file",
-    ));
+    cmd.assert()
+        .success()
+        .stdout(contains("'nested' is up to date"));
 }
 
 #[test]
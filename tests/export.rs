@@ -0,0 +1,83 @@
+mod common;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::fixture::PathChild;
+use predicates::str::contains;
+use std::os::unix::fs::PermissionsExt;
+
+/// Stand-in for age that passes data through unencrypted
+fn fake_age(temp: &assert_fs::TempDir) -> String {
+    let bin = temp.child("bin");
+    std::fs::create_dir_all(bin.path()).unwrap();
+    let age = bin.child("age");
+    std::fs::write(
+        age.path(),
+        "#!/bin/sh\nmode=$1; shift\nwhile [ $# -gt 0 ]; do\n  case \"$1\" in\n    --output) out=$2; shift 2 ;;\n    --recipient|--identity) shift 2 ;;\n    *) input=$1; shift ;;\n  esac\ndone\nif [ \"$mode\" = --encrypt ]; then cat > \"$out\"; else cat \"$input\"; fi\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(age.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+    format!(
+        "{}:{}",
+        bin.path().display(),
+        std::env::var("PATH").unwrap()
+    )
+}
+
+#[test]
+fn test_export_and_import_bundle() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let path = fake_age(&temp);
+    let keys = temp.child("config/age");
+    std::fs::create_dir_all(keys.path()).unwrap();
+    std::fs::write(keys.child("key.txt").path(), "AGE-SECRET-KEY-1").unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .env("ORDINATOR_CONFIG_DIR", temp.child("config").path());
+    cmd.args([
+        "export",
+        "--output",
+        "bundle.tar.age",
+        "--recipient",
+        "age1example",
+        "--include-keys",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(contains("1 age key(s) to bundle.tar.age"));
+
+    let restored = temp.child("restored");
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .env("ORDINATOR_CONFIG_DIR", temp.child("new-config").path());
+    cmd.args(["import", "bundle.tar.age", "--target-dir"])
+        .arg(restored.path());
+    cmd.assert().success().stdout(contains("1 age key(s)"));
+    assert!(restored.child("ordinator.toml").path().is_file());
+    assert!(!restored.child("bundle.tar.age").path().exists());
+    let key = temp.child("new-config/age/key.txt");
+    assert_eq!(
+        std::fs::read_to_string(key.path()).unwrap(),
+        "AGE-SECRET-KEY-1"
+    );
+
+    // A second import would overwrite the first
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path);
+    cmd.args(["import", "bundle.tar.age", "--target-dir"])
+        .arg(restored.path());
+    cmd.assert().failure().stderr(contains("is not empty"));
+}
+
+#[test]
+fn test_export_without_recipient_needs_terminal() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let path = fake_age(&temp);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path).stdin(std::process::Stdio::null());
+    cmd.args(["export", "--output", "bundle.tar.age"]);
+    cmd.assert().failure().stderr(contains("Pass --recipient"));
+    assert!(!temp.child("bundle.tar.age").path().exists());
+}
@@ -0,0 +1,105 @@
+mod common;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::fixture::{FileWriteStr, PathChild, PathCreateDir};
+use predicates::str::contains;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+/// A stand-in `code` that keeps its installed extensions in `code-extensions`.
+/// Returns the PATH to run ordinator with.
+fn fake_code(temp: &assert_fs::TempDir) -> String {
+    let bin = temp.child("bin");
+    bin.create_dir_all().unwrap();
+    let list = temp.child("code-extensions");
+    list.write_str("ms-python.python\nrust-lang.rust-analyzer\n")
+        .unwrap();
+    let code = bin.child("code");
+    code.write_str(&format!(
+        "#!/bin/sh\ncase \"$1\" in\n  --list-extensions) cat '{list}' ;;\n  --install-extension) echo \"$2\" >> '{list}' ;;\nesac\n",
+        list = list.path().display()
+    ))
+    .unwrap();
+    fs::set_permissions(code.path(), fs::Permissions::from_mode(0o755)).unwrap();
+    format!(
+        "{}:{}",
+        bin.path().display(),
+        std::env::var("PATH").unwrap()
+    )
+}
+
+#[test]
+fn test_extensions_export_list_and_install() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let path = fake_code(&temp);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path).args([
+        "extensions",
+        "export",
+        "--tool",
+        "vscode",
+        "--profile",
+        "work",
+    ]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Exported 2 vscode extension(s) to profile 'work'"));
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("[profiles.work.extensions]"));
+    assert!(config.contains("\"rust-lang.rust-analyzer\""));
+
+    // Exporting again would drop the stored list
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .args(["extensions", "export", "--profile", "work"]);
+    cmd.assert().failure().stderr(contains("--force"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "config",
+        "set",
+        "profiles.work.extensions.vscode",
+        "--append",
+    ])
+    .arg("EditorConfig.EditorConfig");
+    cmd.assert().success();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["extensions", "list", "--profile", "work"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("vscode extensions for profile 'work':"))
+        .stdout(contains("  - EditorConfig.EditorConfig"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .args(["extensions", "install", "--profile", "work"]);
+    cmd.assert()
+        .success()
+        .stderr(contains(
+            "Installed vscode extension: EditorConfig.EditorConfig",
+        ))
+        .stderr(contains("Installed 1 extension(s)"));
+    let installed = fs::read_to_string(temp.child("code-extensions").path()).unwrap();
+    assert!(installed.ends_with("EditorConfig.EditorConfig\n"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .args(["extensions", "install", "--profile", "work"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("All extensions for profile 'work' are installed"));
+}
+
+#[test]
+fn test_extensions_reject_unknown_editor() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["extensions", "export", "--tool", "emacs"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("Unknown editor 'emacs'"));
+}
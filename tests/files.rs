@@ -0,0 +1,132 @@
+mod common;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::fixture::PathChild;
+use assert_fs::prelude::*;
+use predicates::prelude::PredicateBooleanExt;
+use predicates::str::contains;
+
+#[test]
+fn test_files_lists_state_and_filters() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    for file in [".zshrc", ".vimrc", ".tmux.conf"] {
+        temp.child(file).write_str(file).unwrap();
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.args(["watch", file]);
+        cmd.assert().success();
+        std::fs::remove_file(temp.child(file).path()).unwrap();
+    }
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-secrets", "--skip-brew"]);
+    cmd.assert().success();
+
+    // .vimrc is edited as a plain file in place of its link; .tmux.conf is removed
+    std::fs::remove_file(temp.child(".vimrc").path()).unwrap();
+    temp.child(".vimrc").write_str("edited").unwrap();
+    std::fs::remove_file(temp.child(".tmux.conf").path()).unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.arg("files");
+    cmd.assert()
+        .success()
+        .stdout(contains("REPO PATH"))
+        .stdout(contains("linked"))
+        .stdout(contains("not linked"))
+        .stdout(contains("missing"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["ls", "--filter", "modified", "--json"]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    // Log lines may follow the JSON on stdout
+    let json = &stdout[stdout.find("[\n").unwrap()..];
+    let entries: serde_json::Value = serde_json::Deserializer::from_str(json)
+        .into_iter()
+        .next()
+        .unwrap()
+        .unwrap();
+    let entries = entries.as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["path"], ".vimrc");
+    assert_eq!(entries[0]["state"], "not_linked");
+    assert_eq!(entries[0]["hash_match"], false);
+    assert!(entries[0]["added_at"].is_string());
+    assert!(entries[0]["repo_path"]
+        .as_str()
+        .unwrap()
+        .starts_with("files/default/"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["files", "--filter", "missing"]);
+    cmd.assert()
+        .success()
+        .stdout(contains(".tmux.conf"))
+        .stdout(contains(".zshrc").not());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["files", "--filter", "stale"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("Use 'modified', 'broken', or 'missing'"));
+}
+
+#[test]
+fn test_why_explains_a_tracked_path() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    temp.child(".zshrc").write_str("export A=1\n").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".zshrc", "--profile", "default"]);
+    cmd.assert().success();
+    std::fs::remove_file(temp.child(".zshrc").path()).unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-secrets", "--skip-brew"]);
+    cmd.assert().success();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["why", "~/.zshrc"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("Profile:"))
+        .stdout(contains("Tracked as:   .zshrc (file)"))
+        .stdout(contains("Stored at:    files/default/"))
+        .stdout(contains("(default)"))
+        .stdout(contains("linked, in sync"))
+        .stdout(contains("To fix").not());
+
+    // Edited in place of its link: the fix is to add it
+    std::fs::remove_file(temp.child(".zshrc").path()).unwrap();
+    temp.child(".zshrc").write_str("export A=2\n").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["why", ".zshrc"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("not linked, modified locally"))
+        .stdout(contains(
+            "To fix: run 'ordinator add .zshrc --profile default'",
+        ));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["why", ".zshrc", "--json"]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let json = &stdout[stdout.find("[\n").unwrap()..];
+    let found: serde_json::Value = serde_json::Deserializer::from_str(json)
+        .into_iter()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(found[0]["profile"], "default");
+    assert_eq!(found[0]["drift"], "modified_locally");
+    assert!(found[0]["applied_at"]["default"].is_string());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["why", ".bashrc"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("isn't tracked by any profile"));
+}
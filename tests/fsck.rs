@@ -0,0 +1,88 @@
+mod common;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::fixture::{FileWriteStr, PathChild};
+use ordinator_core::utils::generate_file_hash;
+use predicates::prelude::PredicateBooleanExt;
+use predicates::str::contains;
+
+#[test]
+fn test_fsck_reports_problems_and_fixes_mappings() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let zshrc = format!("{}_.zshrc", generate_file_hash(".zshrc"));
+    let vimrc = format!("{}_.vimrc", generate_file_hash(".vimrc"));
+    let token = format!("{}_token.enc", generate_file_hash(".config/token"));
+    temp.child("ordinator.toml")
+        .write_str(&format!(
+            r#"
+[profiles.default]
+files = [".zshrc", ".vimrc", ".bashrc"]
+secrets = [".config/token"]
+
+[profiles.default.file_mappings]
+"{zshrc}" = ".zshrc"
+"000000_.vimrc" = ".vimrc"
+"111111_.gitconfig" = ".gitconfig"
+"{token}" = ".config/token"
+"#
+        ))
+        .unwrap();
+    temp.child(format!("files/default/{zshrc}"))
+        .write_str("export EDITOR=vim\n")
+        .unwrap();
+    temp.child("files/default/000000_.vimrc")
+        .write_str("set number\n")
+        .unwrap();
+    temp.child("files/default/leftover.txt")
+        .write_str("old\n")
+        .unwrap();
+    temp.child(format!("secrets/default/{token}"))
+        .write_str("plaintext-token\n")
+        .unwrap();
+    temp.child("backups/.profile.backup.1.20250101-120000")
+        .write_str("old profile\n")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.arg("fsck");
+    cmd.assert()
+        .code(2)
+        .stdout(contains(
+            "missing: .bashrc is tracked by profile 'default', but its stored copy is gone",
+        ))
+        .stdout(contains(
+            "unreferenced: files/default/leftover.txt is not tracked by profile 'default'",
+        ))
+        .stdout(contains(format!(
+            "mapping: 000000_.vimrc in profile 'default' maps .vimrc, whose stored name should be {vimrc} (fixable with --fix)"
+        )))
+        .stdout(contains(
+            "mapping: 111111_.gitconfig in profile 'default' maps .gitconfig, which the profile no longer tracks (fixable with --fix)",
+        ))
+        .stdout(contains(format!(
+            "secret: secrets/default/{token} is not valid JSON"
+        )))
+        .stdout(contains(
+            "backup: backups/.profile.backup.1.20250101-120000 is a backup of .profile, which no profile tracks",
+        ))
+        .stderr(contains("6 problem(s) found, 2 fixable with --fix"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["fsck", "--fix", "--dry-run"]);
+    cmd.assert()
+        .code(2)
+        .stderr(contains("DRY-RUN: Would fix 000000_.vimrc"));
+    assert!(temp.child("files/default/000000_.vimrc").path().exists());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["fsck", "--fix"]);
+    cmd.assert()
+        .code(2)
+        .stderr(contains("✅ Fixed 2 problem(s)"))
+        .stderr(contains("4 problem(s) found, 0 fixable with --fix"))
+        .stdout(contains("mapping:").not());
+    assert!(temp.child(format!("files/default/{vimrc}")).path().exists());
+    let config = std::fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains(&format!("\"{vimrc}\" = \".vimrc\"")));
+    assert!(!config.contains(".gitconfig"));
+}
@@ -0,0 +1,157 @@
+mod common;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use predicates::str::contains;
+
+/// Append hook settings to the test config and write the scripts they run
+fn configure_hooks(temp: &assert_fs::TempDir, hooks: &str) {
+    let config_path = temp.child("ordinator.toml");
+    let mut config = std::fs::read_to_string(config_path.path()).unwrap();
+    config.push_str(hooks);
+    std::fs::write(config_path.path(), config).unwrap();
+
+    temp.child("scripts/log.sh")
+        .write_str("echo \"$ORDINATOR_HOOK $ORDINATOR_PROFILE\" >> hooks.log\n")
+        .unwrap();
+    temp.child("scripts/fail.sh")
+        .write_str("echo failing >&2\nexit 3\n")
+        .unwrap();
+    temp.child("scripts/blocked.sh")
+        .write_str("rm -rf /\n")
+        .unwrap();
+}
+
+fn hook_log(temp: &assert_fs::TempDir) -> String {
+    std::fs::read_to_string(temp.child("hooks.log").path()).unwrap_or_default()
+}
+
+#[test]
+fn test_apply_runs_global_and_profile_hooks_in_order() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    configure_hooks(
+        &temp,
+        "\n[hooks]\npre_apply = [\"scripts/log.sh\"]\n\n[profiles.default.hooks]\npre_apply = [\"scripts/log.sh\"]\npost_apply = [\"scripts/log.sh\"]\n",
+    );
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["--dry-run", "apply"]);
+    cmd.assert()
+        .success()
+        .stderr(contains(
+            "DRY-RUN: Would run pre_apply hook: scripts/log.sh",
+        ))
+        .stderr(contains(
+            "DRY-RUN: Would run post_apply hook: scripts/log.sh",
+        ));
+    assert_eq!(hook_log(&temp), "");
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-brew", "--skip-defaults"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Running pre_apply hook: scripts/log.sh"));
+    assert_eq!(
+        hook_log(&temp),
+        "pre_apply default\npre_apply default\npost_apply default\n"
+    );
+}
+
+#[test]
+fn test_failing_hook_aborts_or_warns() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    configure_hooks(
+        &temp,
+        "\n[hooks]\npre_apply = [\"scripts/fail.sh\"]\npost_apply = [\"scripts/log.sh\"]\n",
+    );
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-brew", "--skip-defaults"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("failing"))
+        .stderr(contains("pre_apply hook 'scripts/fail.sh' failed"));
+    assert_eq!(hook_log(&temp), "");
+
+    let config_path = temp.child("ordinator.toml");
+    let config = std::fs::read_to_string(config_path.path()).unwrap();
+    std::fs::write(
+        config_path.path(),
+        config.replace("[hooks]\n", "[hooks]\non_failure = \"warn\"\n"),
+    )
+    .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-brew", "--skip-defaults"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Warning: pre_apply hook 'scripts/fail.sh' failed"));
+    assert_eq!(hook_log(&temp), "post_apply default\n");
+}
+
+#[test]
+fn test_blocked_hook_is_not_run() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    configure_hooks(&temp, "\n[hooks]\npre_commit = [\"scripts/blocked.sh\"]\n");
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["commit", "-m", "test"]);
+    cmd.assert().failure().stderr(contains(
+        "pre_commit hook 'scripts/blocked.sh' was not run: it contains blocked commands",
+    ));
+}
+
+#[test]
+fn test_auto_commit_runs_commit_and_push_hooks() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    std::fs::remove_dir_all(temp.path().join(".git")).unwrap();
+    let repo = git2::Repository::init(temp.path()).unwrap();
+    repo.remote("origin", "https://github.com/testuser/dotfiles.git")
+        .unwrap();
+    for (key, value) in [("global.auto_commit", "true"), ("global.auto_push", "true")] {
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.args(["config", "set", key, value]);
+        cmd.assert().success();
+    }
+    configure_hooks(
+        &temp,
+        "\n[hooks]\npre_commit = [\"scripts/log.sh\"]\npost_push = [\"scripts/log.sh\"]\n",
+    );
+
+    let zshrc = temp.child(".zshrc");
+    zshrc.write_str("export EDITOR=vim\n").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", zshrc.path().to_str().unwrap()]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Running pre_commit hook: scripts/log.sh"))
+        .stderr(contains("Pushed to 'origin'"));
+    assert_eq!(hook_log(&temp), "pre_commit default\npost_push default\n");
+
+    // A failing pre_commit hook leaves the change uncommitted
+    configure_hooks(&temp, "");
+    let config_path = temp.child("ordinator.toml");
+    let config = std::fs::read_to_string(config_path.path()).unwrap();
+    std::fs::write(
+        config_path.path(),
+        config.replace(
+            "pre_commit = [\"scripts/log.sh\"]",
+            "pre_commit = [\"scripts/fail.sh\"]",
+        ),
+    )
+    .unwrap();
+    let bashrc = temp.child(".bashrc");
+    bashrc.write_str("export EDITOR=vim\n").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", bashrc.path().to_str().unwrap()]);
+    cmd.assert()
+        .success()
+        .stderr(contains(
+            "Warning: Not committing automatically: pre_commit hook 'scripts/fail.sh' failed",
+        ))
+        .stderr(contains("Committed:").not());
+}
@@ -4,6 +4,7 @@ use assert_cmd::Command;
 use assert_fs::fixture::FileWriteStr;
 use assert_fs::fixture::PathChild;
 use assert_fs::fixture::PathCreateDir;
+use predicates::prelude::PredicateBooleanExt;
 use predicates::str::contains;
 
 #[test]
@@ -295,3 +296,44 @@ fn test_init_with_invalid_repo_structure() {
         .success()
         .stderr(contains("Initializing new repository with remote URL"));
 }
+
+#[test]
+fn test_init_interactive_takes_default_answers_without_a_terminal() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child(".zshrc")
+        .write_str("export EDITOR=vim\n")
+        .unwrap();
+    temp.child(".gitconfig").write_str("[user]\n").unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["--no-input", "init", "--interactive", "--dry-run"]);
+    cmd.assert()
+        .success()
+        .stderr(contains(
+            "DRY-RUN: Would track ~/.zshrc in profile 'default'",
+        ))
+        .stderr(contains("age encryption").not());
+    assert!(!temp.child("ordinator.toml").path().exists());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["--no-input", "init", "--interactive"]);
+    cmd.assert()
+        .success()
+        .stderr(contains(
+            "Not running interactively; using the default answers",
+        ))
+        .stderr(contains("Tracking ~/.gitconfig in profile 'default'"))
+        .stderr(contains("Tracking ~/.ssh/config").not());
+    let config = std::fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("[profiles.default]"));
+    assert!(!config.contains("[profiles.work]"));
+    assert!(config.contains(".zshrc"));
+    assert!(config.contains(".gitconfig"));
+
+    // A second run would replace the config
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["--no-input", "init", "--interactive"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("already exists. Use --force to start over."));
+}
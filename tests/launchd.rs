@@ -0,0 +1,117 @@
+mod common;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::fixture::{FileWriteStr, PathChild};
+use predicates::prelude::PredicateBooleanExt;
+use predicates::str::contains;
+use std::fs;
+
+const PLIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>com.example.backup</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>/usr/local/bin/backup</string>
+    </array>
+</dict>
+</plist>
+"#;
+
+#[test]
+fn test_launchd_track_apply_and_uninstall() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let plist = temp.child("com.example.backup.plist");
+    plist.write_str(PLIST).unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["launchd", "track", "--profile", "work"])
+        .arg(plist.path());
+    cmd.assert()
+        .success()
+        .stdout(contains("Tracking launchd agent 'com.example.backup'"));
+    assert!(temp
+        .child("launchd/work/com.example.backup.plist")
+        .path()
+        .exists());
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("launch_agents = [\"com.example.backup.plist\"]"));
+
+    // Apply copies the plist into ~/Library/LaunchAgents (launchctl is skipped in test mode)
+    let installed = temp.child("Library/LaunchAgents/com.example.backup.plist");
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--profile", "work", "--skip-brew"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Installed launchd agent"));
+    assert_eq!(fs::read_to_string(installed.path()).unwrap(), PLIST);
+    assert!(!installed.path().is_symlink());
+
+    // Unchanged agents are left alone
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--profile", "work", "--skip-brew"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("launchd agent").not());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["launchd", "list"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("com.example.backup.plist (installed)"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["uninstall", "--profile", "work", "--force"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Removed launchd agent"))
+        .stderr(contains("launchd agents removed: 1"));
+    assert!(!installed.path().exists());
+}
+
+#[test]
+fn test_launchd_untrack_and_invalid_plist() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let plist = temp.child("com.example.backup.plist");
+    plist.write_str(PLIST).unwrap();
+
+    let invalid = temp.child("nolabel.plist");
+    invalid.write_str(&PLIST.replace("Label", "Name")).unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["launchd", "track", "--profile", "default"])
+        .arg(invalid.path());
+    cmd.assert().failure().stderr(contains("has no Label"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["launchd", "track", "--profile", "default"])
+        .arg(plist.path());
+    cmd.assert().success();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--profile", "default", "--skip-brew"]);
+    cmd.assert().success();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "launchd",
+        "untrack",
+        "com.example.backup.plist",
+        "--profile",
+        "default",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(contains("Untracked launchd agent"));
+    assert!(!temp
+        .child("Library/LaunchAgents/com.example.backup.plist")
+        .path()
+        .exists());
+    assert!(!temp
+        .child("launchd/default/com.example.backup.plist")
+        .path()
+        .exists());
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(!config.contains("launch_agents"));
+}
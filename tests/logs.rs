@@ -0,0 +1,66 @@
+mod common;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use predicates::str::contains;
+
+#[test]
+fn test_logs_list_and_last() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    temp.child(".zshrc")
+        .write_str("export EDITOR=vim\n")
+        .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".zshrc"]);
+    cmd.assert().success();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--profile", "nope", "--skip-brew"]);
+    cmd.assert().failure();
+
+    // Drift, for an exit code of 2
+    temp.child(".zshrc")
+        .write_str("export EDITOR=nano\n")
+        .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["status", "--check"]);
+    cmd.assert().code(2);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.arg("logs");
+    cmd.assert()
+        .success()
+        .stdout(contains("ordinator status --check  exit 2"))
+        .stdout(contains(
+            "ordinator apply --profile nope --skip-brew  failed",
+        ))
+        .stdout(contains("ordinator watch .zshrc  ok"))
+        .stdout(contains("ordinator init  ok"))
+        // Reading the logs isn't logged
+        .stdout(contains("ordinator logs").not());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["logs", "--last", "--command", "apply"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("ordinator apply --profile nope --skip-brew ("))
+        .stdout(contains("Result: failed in "))
+        .stdout(contains("nope"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["logs", "--last", "--command", "watch"]);
+    cmd.assert().success().stdout(contains("Result: ok in "));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["logs", "--command", "pull"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("No runs of 'pull' logged"));
+
+    let logs = std::fs::read_dir(temp.child(".local/state/ordinator/logs").path())
+        .unwrap()
+        .count();
+    assert_eq!(logs, 4);
+}
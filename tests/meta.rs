@@ -47,7 +47,7 @@ fn test_init_dry_run() {
     // Dry run should show what would be done without actually doing it
     assert!(output.status.success(), "Init dry-run failed: {stderr}");
     assert!(
-        stdout.contains("dry-run") || stderr.contains("dry-run"),
+        stdout.contains("DRY-RUN") || stderr.contains("DRY-RUN"),
         "Expected dry-run indication"
     );
 }
@@ -75,7 +75,7 @@ fn test_add_dry_run() {
     // Dry run should show what would be done without actually doing it
     assert!(output.status.success(), "Add dry-run failed: {stderr}");
     assert!(
-        stdout.contains("dry-run") || stderr.contains("dry-run"),
+        stdout.contains("DRY-RUN") || stderr.contains("DRY-RUN"),
         "Expected dry-run indication"
     );
 }
@@ -914,9 +914,28 @@ fn test_cli_push_with_force() {
     let temp = assert_fs::TempDir::new().unwrap();
     let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
 
+    // Without a terminal to type the branch name in, a force push is refused
     let mut cmd = common::create_ordinator_command(&temp);
     cmd.args(["push", "--force"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("force-pushing replaces"))
+        .stderr(contains("Refusing to force-push"));
+
+    // The warning names the URL a new origin is about to point at
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["push", "--force", "https://github.com/test/new.git"]);
+    cmd.assert().failure().stderr(contains(
+        "force-pushing replaces 'main' on origin (https://github.com/test/new.git)",
+    ));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["push", "--force", "--i-know-what-im-doing"]);
     cmd.assert().success(); // Should succeed in test mode since git operations are mocked
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["push", "--i-know-what-im-doing"]);
+    cmd.assert().failure().stderr(contains("--force"));
 }
 
 #[test]
@@ -934,6 +953,16 @@ fn test_cli_sync_with_force() {
     let temp = assert_fs::TempDir::new().unwrap();
     let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
 
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["sync", "--force", "--yes"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("Refusing to force-push"));
+
+    // Turning the protection off in the config allows it again
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["config", "set", "git.protect_force_push", "false"]);
+    cmd.assert().success();
     let mut cmd = common::create_ordinator_command(&temp);
     cmd.args(["sync", "--force"]);
     cmd.assert().success(); // Should succeed in test mode
@@ -1039,6 +1068,22 @@ fn test_cli_generate_script() {
         "default",
     ]);
     cmd.assert().success();
+
+    let script = std::fs::read_to_string(temp.child("test.sh").path()).unwrap();
+    assert!(script.starts_with("#!/usr/bin/env bash"));
+    assert!(script.contains("Linking dotfiles"));
+}
+
+#[test]
+fn test_cli_generate_script_nonexistent_profile() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["generate-script", "--profile", "nonexistent"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains("does not exist"));
 }
 
 #[test]
@@ -1595,8 +1640,8 @@ fn test_cli_force_flag_combinations() {
         vec!["apply", "--force"],
         vec!["uninstall", "--force"],
         vec!["commit", "-m", "test commit", "--force"],
-        vec!["push", "--force"],
-        vec!["sync", "--force"],
+        vec!["push", "--force", "--i-know-what-im-doing"],
+        vec!["sync", "--force", "--i-know-what-im-doing"],
     ];
 
     for args in force_commands {
@@ -76,3 +76,235 @@ fn test_profiles_with_config() {
         .stderr(contains("work"))
         .stderr(contains("Work environment profile"));
 }
+
+#[test]
+fn test_profile_add_creates_profile() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["profile", "add", "laptop", "--description", "Laptop setup"]);
+    cmd.assert().success();
+
+    let config = std::fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("[profiles.laptop]"));
+    assert!(config.contains("Laptop setup"));
+
+    let mut dup_cmd = common::create_ordinator_command(&temp);
+    dup_cmd.args(["profile", "add", "laptop"]);
+    dup_cmd
+        .assert()
+        .failure()
+        .stderr(contains("Profile 'laptop' already exists"));
+}
+
+#[test]
+fn test_profile_add_rejects_invalid_name() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["profile", "add", "bad name"]);
+    cmd.assert().failure().stderr(contains("filesystem-safe"));
+}
+
+#[test]
+fn test_profile_remove_requires_confirmation_with_tracked_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    temp.child("test.txt").touch().unwrap();
+    let mut watch_cmd = common::create_ordinator_command(&temp);
+    watch_cmd.args(["watch", "test.txt", "--profile", "work"]);
+    watch_cmd.assert().success();
+
+    // Non-interactive without --force leaves the profile in place
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["profile", "remove", "work"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Use --force"))
+        .stderr(contains("Operation cancelled"));
+    let config = std::fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("[profiles.work]"));
+
+    let mut force_cmd = common::create_ordinator_command(&temp);
+    force_cmd.args(["profile", "remove", "work", "--force"]);
+    force_cmd.assert().success();
+    let config = std::fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(!config.contains("[profiles.work]"));
+    assert!(!temp.child("files").child("work").path().exists());
+}
+
+#[test]
+fn test_profile_remove_with_yes() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    temp.child("test.txt").touch().unwrap();
+    let mut watch_cmd = common::create_ordinator_command(&temp);
+    watch_cmd.args(["watch", "test.txt", "--profile", "work"]);
+    watch_cmd.assert().success();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["profile", "remove", "work", "--yes"]);
+    cmd.assert().success();
+    let config = std::fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(!config.contains("[profiles.work]"));
+}
+
+#[test]
+fn test_profile_remove_default_profile_fails() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["profile", "remove", "default", "--force"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("is the default profile"));
+}
+
+#[test]
+fn test_profile_rename_migrates_tracked_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    temp.child("test.txt").touch().unwrap();
+    let mut watch_cmd = common::create_ordinator_command(&temp);
+    watch_cmd.args(["watch", "test.txt", "--profile", "work"]);
+    watch_cmd.assert().success();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["profile", "rename", "work", "office"]);
+    cmd.assert().success();
+
+    let config = std::fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("[profiles.office]"));
+    assert!(!config.contains("[profiles.work]"));
+    assert!(config.contains("test.txt"));
+    assert!(temp.child("files").child("office").path().exists());
+    assert!(!temp.child("files").child("work").path().exists());
+
+    let mut conflict_cmd = common::create_ordinator_command(&temp);
+    conflict_cmd.args(["profile", "rename", "office", "personal"]);
+    conflict_cmd
+        .assert()
+        .failure()
+        .stderr(contains("Profile 'personal' already exists"));
+}
+
+#[test]
+fn test_profile_describe_updates_description() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["profile", "describe", "work", "Office laptop"]);
+    cmd.assert().success();
+
+    let mut list_cmd = common::create_ordinator_command(&temp);
+    list_cmd.arg("profiles");
+    list_cmd
+        .assert()
+        .success()
+        .stderr(predicates::str::is_match(r"work:\s+Office laptop").unwrap());
+}
+
+#[test]
+fn test_apply_picks_profile_from_match_rules() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let config_path = temp.child("ordinator.toml");
+    let mut config = std::fs::read_to_string(config_path.path()).unwrap();
+    config.push_str("\n[profiles.work.match]\nusername = \"ordinator-tester\"\nhostname = \"*\"\n");
+    std::fs::write(config_path.path(), config).unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("USER", "ordinator-tester");
+    cmd.args(["apply", "--skip-brew", "--skip-defaults"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Using profile 'work'"))
+        .stderr(contains("Applying profile: work"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("USER", "ordinator-tester");
+    cmd.args(["profiles", "--explain"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("  username: ordinator-tester"))
+        .stdout(contains("Profile 'work' (matches):"))
+        .stdout(contains("✓ username = \"ordinator-tester\""))
+        .stdout(contains("Apply without --profile uses 'work'"));
+
+    // Another user falls back to default_profile
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("USER", "someone-else");
+    cmd.args(["profiles", "--explain"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("Profile 'work' (does not match):"))
+        .stdout(contains("uses default_profile 'default'"));
+}
+
+#[test]
+fn test_profiles_switch_removes_links_only_the_old_profile_made() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    for (file, profiles) in [
+        (".work", &["work"][..]),
+        (".shared", &["work", "personal"][..]),
+        (".personal", &["personal"][..]),
+    ] {
+        std::fs::write(temp.child(file).path(), file).unwrap();
+        for profile in profiles {
+            let mut cmd = common::create_ordinator_command(&temp);
+            cmd.args(["watch", file, "--profile", profile]);
+            cmd.assert().success();
+        }
+    }
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "apply",
+        "--profile",
+        "work",
+        "--skip-brew",
+        "--skip-defaults",
+        "--force",
+    ]);
+    cmd.assert().success();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["profiles", "switch", "personal", "--dry-run"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Switching from 'work' to 'personal'"))
+        .stderr(contains("Would remove symlink"));
+    assert!(temp.child(".work").path().is_symlink());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["profiles", "switch", "personal", "--force"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("Switched from 'work' to 'personal'"));
+    assert!(!temp.child(".work").path().exists());
+    assert!(!temp.child(".work").path().is_symlink());
+    let shared = std::fs::read_link(temp.child(".shared").path()).unwrap();
+    assert!(shared.to_string_lossy().contains("personal"), "{shared:?}");
+    assert!(temp.child(".personal").path().is_symlink());
+
+    let state =
+        std::fs::read_to_string(temp.child(".local/state/ordinator/state.toml").path()).unwrap();
+    assert!(state.contains("current_profile = \"personal\""), "{state}");
+    assert!(!state.contains("[applied.work]"), "{state}");
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["profile", "switch", "personal"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains(
+            "error[E0114]: 'personal' is already the current profile.",
+        ))
+        .stderr(contains("  hint: Run 'ordinator apply' to reapply it."));
+}
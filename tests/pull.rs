@@ -1,4 +1,5 @@
 mod common;
+use assert_cmd::assert::OutputAssertExt;
 use assert_cmd::Command;
 use assert_fs::fixture::PathChild;
 
@@ -25,3 +26,51 @@ fn test_pull_errors_without_git_repo() {
     cmd.args(["pull"]);
     common::assert_config_error(cmd.assert().failure());
 }
+
+#[test]
+fn test_pull_branch_and_branch_create() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["pull", "--branch", "laptop"]);
+    cmd.assert().success();
+
+    // Branches are only switched with everything committed
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["branch", "create", "laptop"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains("You have uncommitted changes"));
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["commit", "-m", "Initial setup", "--yes"]);
+    cmd.assert().success();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["branch", "create", "laptop"]);
+    cmd.assert().success().stderr(predicates::str::contains(
+        "Created and switched to branch 'laptop'",
+    ));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["branch", "create", "bad..name"]);
+    cmd.assert().failure().stderr(predicates::str::contains(
+        "'bad..name' is not a valid branch name",
+    ));
+}
+
+#[test]
+fn test_pull_strategy_is_validated() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["pull", "--strategy", "theirs"]);
+    cmd.assert().success();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["pull", "--strategy", "newest"]);
+    cmd.assert().failure().stderr(predicates::str::contains(
+        "Unknown merge strategy 'newest'. Use 'merge', 'ours', or 'theirs'.",
+    ));
+}
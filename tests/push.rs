@@ -1,3 +1,96 @@
 mod common;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::fixture::PathChild;
+use predicates::prelude::*;
+use predicates::str::contains;
+use std::fs;
 
 // ... push-related tests from cli.rs ...
+
+#[test]
+fn test_push_all_remotes_reports_each_remote() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let config_path = temp.child("ordinator.toml");
+    let mut config = fs::read_to_string(config_path.path()).unwrap();
+    config.push_str(
+        "\n[[git.remotes]]\nname = \"mirror\"\nurl = \"git@gitlab.com:me/dotfiles.git\"\nforce = true\n",
+    );
+    fs::write(config_path.path(), config).unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["push", "--all-remotes"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Pushed to 'origin'\n"))
+        .stderr(contains("Pushed to 'mirror' (force)"));
+
+    // Without the flag only origin is pushed
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["push"]);
+    cmd.assert().success().stderr(contains("Pushed to").not());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["status"]);
+    cmd.assert().success().stderr(contains(
+        "mirror: git@gitlab.com:me/dotfiles.git (not pushed yet, force-pushed)",
+    ));
+}
+
+#[test]
+fn test_push_all_remotes_reports_an_unreachable_remote() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let config_path = temp.child("ordinator.toml");
+    let mut config = fs::read_to_string(config_path.path()).unwrap();
+    config.push_str(
+        "\n[[git.remotes]]\nname = \"mirror\"\nurl = \"git@gitlab.com:me/dotfiles.git\"\n",
+    );
+    fs::write(config_path.path(), config).unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("ORDINATOR_TEST_UNREACHABLE", "mirror");
+    cmd.args(["push", "--all-remotes"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("Pushed to 'origin'\n"))
+        .stderr(contains(
+            "Failed to push to 'mirror': Repository not found: mirror",
+        ))
+        .stderr(contains("Push failed for 1 of 2 remote(s)"));
+}
+
+#[test]
+fn test_status_remote_shows_last_pull_and_push() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    git2::Repository::open(temp.path())
+        .unwrap()
+        .remote("origin", "https://github.com/testuser/dotfiles.git")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["status", "--remote"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Origin: https://github.com/testuser/dotfiles.git"))
+        .stderr(contains("Last pull: never"))
+        .stderr(contains("Last push: never"))
+        .stderr(contains("Applied profile").not());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["push"]);
+    cmd.assert().success();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["status", "--remote", "--fetch"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Fetching from origin"))
+        .stderr(contains("Last pull: never"))
+        .stderr(contains("Last push: never").not());
+
+    // --fetch only goes with --remote
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["status", "--fetch"]);
+    cmd.assert().failure();
+}
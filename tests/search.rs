@@ -0,0 +1,127 @@
+mod common;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::fixture::{FileWriteStr, PathChild, PathCreateDir};
+use predicates::prelude::PredicateBooleanExt;
+use predicates::str::contains;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+fn track(temp: &assert_fs::TempDir, path: &str, content: &str, profile: &str) {
+    temp.child(path).write_str(content).unwrap();
+    let mut cmd = common::create_ordinator_command(temp);
+    cmd.args(["watch", path, "--profile", profile]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_search_tracked_files_by_profile() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    track(
+        &temp,
+        ".zshrc",
+        "export EDITOR=vim\nalias gs='git status'\n",
+        "work",
+    );
+    track(
+        &temp,
+        ".bashrc",
+        "ALIAS_GS=1\nalias ll='ls -la'\n",
+        "personal",
+    );
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["search", "^alias gs"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("work:.zshrc:2:alias gs='git status'"))
+        .stdout(contains(".bashrc").not());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["search", "alias_gs", "-i", "--profile", "personal"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("personal:.bashrc:1:ALIAS_GS=1"))
+        .stdout(contains(".zshrc").not());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["search", "alias gs", "--profile", "personal"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("No matches for 'alias gs'"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["search", "ll='ls", "-F"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("personal:.bashrc:2:"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["search", "x", "--profile", "missing"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("Profile 'missing' does not exist"));
+}
+
+#[test]
+fn test_search_secrets_decrypts_in_memory() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    // A sops that decrypts to stdout by dropping its `sops:` marker
+    let bin = temp.child("bin");
+    bin.create_dir_all().unwrap();
+    for (name, script) in [
+        (
+            "sops",
+            "#!/bin/sh\nfor last; do :; done\ngrep -v '^sops:$' \"$last\"\n",
+        ),
+        ("age", "#!/bin/sh\nexit 0\n"),
+    ] {
+        bin.child(name).write_str(script).unwrap();
+        fs::set_permissions(bin.child(name).path(), fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let path = format!(
+        "{}:{}",
+        bin.path().display(),
+        std::env::var("PATH").unwrap()
+    );
+    let key = temp.child("config/age/key.txt");
+    key.write_str("AGE-SECRET-KEY-1TEST\n").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["config", "set", "secrets.age_key_file"])
+        .arg(key.path());
+    cmd.assert().success();
+
+    temp.child(".env").write_str("GITHUB_TOKEN=abc\n").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["secrets", "watch", ".env", "--profile", "default"]);
+    cmd.assert().success();
+    temp.child("secrets/default/123abc_.env.enc")
+        .write_str("GITHUB_TOKEN=abc\nsops:\n")
+        .unwrap();
+    let config_path = temp.child("ordinator.toml");
+    let mut config: toml::Value =
+        toml::from_str(&fs::read_to_string(config_path.path()).unwrap()).unwrap();
+    config["profiles"]["default"]
+        .as_table_mut()
+        .unwrap()
+        .entry("file_mappings")
+        .or_insert(toml::Value::Table(Default::default()))
+        .as_table_mut()
+        .unwrap()
+        .insert("123abc_.env.enc".into(), ".env".into());
+    fs::write(config_path.path(), toml::to_string(&config).unwrap()).unwrap();
+
+    // Without a terminal or --yes, secrets aren't decrypted
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .args(["search", "GITHUB_TOKEN", "--secrets"]);
+    cmd.assert().failure().stderr(contains("Skipping secrets"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .args(["search", "GITHUB_TOKEN", "--secrets", "--yes"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("default:.env (secret):1:GITHUB_TOKEN=abc"));
+}
@@ -4,6 +4,7 @@ use assert_cmd::output::OutputOkExt;
 use assert_fs::fixture::FileWriteStr;
 use assert_fs::fixture::PathChild;
 use assert_fs::fixture::PathCreateDir;
+use predicates::prelude::PredicateBooleanExt;
 use predicates::str::contains;
 use std::fs;
 use std::os::unix::fs::PermissionsExt;
@@ -839,3 +840,901 @@ key_rotation_interval_days = 30
             || stderr.contains("Age encryption setup is valid")
     );
 }
+
+#[test]
+fn test_secrets_recipients_add_remove_reencrypts() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    // Mock sops logs its arguments; age only needs to exist
+    let bin_dir = temp.child("bin");
+    bin_dir.create_dir_all().unwrap();
+    let log_path = temp.child("sops.log");
+    let sops_path = bin_dir.child("sops");
+    sops_path
+        .write_str(&format!(
+            "#!/bin/sh\necho \"$@\" >> '{}'\nexit 0\n",
+            log_path.path().display()
+        ))
+        .unwrap();
+    fs::set_permissions(sops_path.path(), fs::Permissions::from_mode(0o755)).unwrap();
+    let age_path = bin_dir.child("age");
+    age_path.write_str("#!/bin/sh\nexit 0\n").unwrap();
+    fs::set_permissions(age_path.path(), fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!(
+        "{}:{}",
+        bin_dir.path().display(),
+        std::env::var("PATH").unwrap()
+    );
+
+    let own_key = format!("age1{}", "q".repeat(58));
+    let team_key = format!("age1{}", "p".repeat(58));
+    let key_file = temp.child("age.key");
+    key_file
+        .write_str(&format!("# public key: {own_key}\nAGE-SECRET-KEY-1TEST\n"))
+        .unwrap();
+    let sops_config = temp.child(".sops.yaml");
+    temp.child("ordinator.toml")
+        .write_str(&format!(
+            r#"
+[global]
+default_profile = "default"
+auto_push = false
+create_backups = true
+exclude = []
+
+[profiles.default]
+files = []
+enabled = true
+
+[secrets]
+age_key_file = "{}"
+sops_config = "{}"
+encrypt_patterns = []
+exclude_patterns = []
+"#,
+            key_file.path().display(),
+            sops_config.path().display()
+        ))
+        .unwrap();
+    temp.child("secrets/default/abc123_token.enc")
+        .write_str("{\"data\": \"ENC\", \"sops\": {}}")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path);
+    cmd.args(["secrets", "recipients", "add", &team_key]);
+    cmd.assert()
+        .success()
+        .stdout(contains(format!("Added recipient '{team_key}'")))
+        .stdout(contains("Re-encrypted 1 secret(s) for 2 recipient(s)"));
+
+    let sops_yaml = fs::read_to_string(sops_config.path()).unwrap();
+    assert!(sops_yaml.contains(&format!("{own_key},")));
+    assert!(sops_yaml.contains(&team_key));
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains(&format!("recipients = [\"{team_key}\"]")));
+    let log = fs::read_to_string(log_path.path()).unwrap();
+    assert!(log.contains("updatekeys --yes"));
+    assert!(log.contains("abc123_token.enc"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path);
+    cmd.args(["secrets", "recipients", "add", &team_key]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("is already configured"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path);
+    cmd.args(["secrets", "recipients", "add", "age1nope"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("is not a valid age public key"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path);
+    cmd.args(["secrets", "recipients", "remove", &team_key]);
+    cmd.assert()
+        .success()
+        .stdout(contains(format!("Removed recipient '{team_key}'")))
+        .stdout(contains("Re-encrypted 1 secret(s) for 1 recipient(s)"));
+    let sops_yaml = fs::read_to_string(sops_config.path()).unwrap();
+    assert!(sops_yaml.contains(&own_key));
+    assert!(!sops_yaml.contains(&team_key));
+}
+
+#[test]
+fn test_secrets_rotate_re_encrypt_only_and_key_age_policy() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let bin_dir = temp.child("bin");
+    bin_dir.create_dir_all().unwrap();
+    let log_path = temp.child("sops.log");
+    let sops_path = bin_dir.child("sops");
+    sops_path
+        .write_str(&format!(
+            "#!/bin/sh\necho \"$@\" >> '{}'\nexit 0\n",
+            log_path.path().display()
+        ))
+        .unwrap();
+    fs::set_permissions(sops_path.path(), fs::Permissions::from_mode(0o755)).unwrap();
+    let age_path = bin_dir.child("age");
+    age_path.write_str("#!/bin/sh\nexit 0\n").unwrap();
+    fs::set_permissions(age_path.path(), fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!(
+        "{}:{}",
+        bin_dir.path().display(),
+        std::env::var("PATH").unwrap()
+    );
+
+    let own_key = format!("age1{}", "q".repeat(58));
+    let key_file = temp.child("age.key");
+    key_file
+        .write_str(&format!("# public key: {own_key}\nAGE-SECRET-KEY-1TEST\n"))
+        .unwrap();
+    let sops_config = temp.child(".sops.yaml");
+    temp.child("ordinator.toml")
+        .write_str(&format!(
+            r#"
+[global]
+default_profile = "default"
+
+[profiles.default]
+files = []
+enabled = true
+created_on = "2020-01-01T00:00:00+00:00"
+
+[secrets]
+age_key_file = "{}"
+sops_config = "{}"
+key_max_age_days = 365
+"#,
+            key_file.path().display(),
+            sops_config.path().display()
+        ))
+        .unwrap();
+    temp.child("secrets/default/abc123_token.enc")
+        .write_str("{\"data\": \"ENC\", \"sops\": {}}")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.arg("status");
+    cmd.assert().success().stderr(contains(
+        "over the 365 day limit in [secrets] key_max_age_days",
+    ));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path);
+    cmd.args(["secrets", "rotate", "--re-encrypt-only"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("Re-encrypted 1 secret(s) for 1 recipient(s)"));
+    let log = fs::read_to_string(log_path.path()).unwrap();
+    assert!(log.contains("updatekeys --yes"));
+    let record = fs::read_to_string(temp.child(".secrets-rotation.toml").path()).unwrap();
+    assert!(record.contains("last_reencrypted = "));
+    assert!(record.contains("secrets = 1"));
+    assert!(record.contains(&own_key));
+    assert!(!record.contains("last_key_rotation"));
+
+    // The key itself is as old as before
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.arg("status");
+    cmd.assert().success().stderr(contains("key_max_age_days"));
+}
+
+#[test]
+fn test_age_rotate_keys_reencrypts_stored_secrets() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    // Mock sops: "encryption" prefixes ENC2:, decryption strips any ENC<n>: prefix.
+    // Encryption fails while a "fail" marker file exists.
+    let bin_dir = temp.child("bin");
+    bin_dir.create_dir_all().unwrap();
+    let fail_marker = temp.child("fail");
+    let sops_path = bin_dir.child("sops");
+    sops_path
+        .write_str(&format!(
+            "#!/bin/sh\nfor last; do :; done\ncase \"$1\" in\n  --decrypt) sed 's/^ENC[0-9]*://' \"$last\" ;;\n  --config) [ -f '{}' ] && exit 1; printf 'ENC2:'; cat \"$last\" ;;\nesac\n",
+            fail_marker.path().display()
+        ))
+        .unwrap();
+    fs::set_permissions(sops_path.path(), fs::Permissions::from_mode(0o755)).unwrap();
+    let age_path = bin_dir.child("age");
+    age_path.write_str("#!/bin/sh\nexit 0\n").unwrap();
+    fs::set_permissions(age_path.path(), fs::Permissions::from_mode(0o755)).unwrap();
+    let keygen_path = bin_dir.child("age-keygen");
+    keygen_path
+        .write_str(&format!(
+            "#!/bin/sh\nprintf '# public key: age1{}\\nAGE-SECRET-KEY-1NEW\\n' > \"$2\"\n",
+            "q".repeat(58)
+        ))
+        .unwrap();
+    fs::set_permissions(keygen_path.path(), fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!(
+        "{}:{}",
+        bin_dir.path().display(),
+        std::env::var("PATH").unwrap()
+    );
+
+    let config_dir = temp.child("config");
+    let key_file = config_dir.child("age/key.txt");
+    key_file
+        .write_str(&format!(
+            "# public key: age1{}\nAGE-SECRET-KEY-1OLD\n",
+            "p".repeat(58)
+        ))
+        .unwrap();
+    let secret = temp.child("secrets/default/abc123_token.enc");
+    secret.write_str("ENC1:hello").unwrap();
+
+    // Dry run lists the affected secrets and changes nothing
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .env("ORDINATOR_CONFIG_DIR", config_dir.path());
+    cmd.args(["age", "rotate-keys", "--profile", "default", "--dry-run"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("re-encrypt 1 secret(s)"))
+        .stdout(contains("secrets/default/abc123_token.enc"));
+    assert_eq!(fs::read_to_string(secret.path()).unwrap(), "ENC1:hello");
+
+    // A failed re-encryption keeps the old key and the old ciphertext
+    fail_marker.write_str("").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .env("ORDINATOR_CONFIG_DIR", config_dir.path());
+    cmd.args(["age", "rotate-keys", "--profile", "default"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("the previous key was restored"));
+    assert_eq!(fs::read_to_string(secret.path()).unwrap(), "ENC1:hello");
+    assert!(fs::read_to_string(key_file.path())
+        .unwrap()
+        .contains("AGE-SECRET-KEY-1OLD"));
+    assert!(!temp
+        .child("secrets/default/abc123_token.enc.rotating")
+        .path()
+        .exists());
+
+    fs::remove_file(fail_marker.path()).unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .env("ORDINATOR_CONFIG_DIR", config_dir.path());
+    cmd.args(["age", "rotate-keys", "--profile", "default"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("Re-encrypted 1 secrets for profile 'default'"));
+    assert_eq!(fs::read_to_string(secret.path()).unwrap(), "ENC2:hello");
+    assert!(fs::read_to_string(key_file.path())
+        .unwrap()
+        .contains("AGE-SECRET-KEY-1NEW"));
+}
+
+#[test]
+fn test_age_key_in_keychain() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    // Stand-in security keeps one file per account; stand-in sops records the
+    // key it was given
+    let bin_dir = temp.child("bin");
+    bin_dir.create_dir_all().unwrap();
+    let keychain = temp.child("keychain");
+    keychain.create_dir_all().unwrap();
+    let security_path = bin_dir.child("security");
+    security_path
+        .write_str(&format!(
+            "#!/bin/sh\necho \"$*\" >> '{0}'/args.log\ncmd=$1; shift\nwhile [ $# -gt 0 ]; do\n  case \"$1\" in\n    -a) account=$2; shift 2 ;;\n    -w) if [ $# -gt 1 ]; then password=$2; shift 2; else print=1; shift; fi ;;\n    -s) shift 2 ;;\n    *) shift ;;\n  esac\ndone\nentry='{0}'/$(echo \"$account\" | tr / _)\ncase $cmd in\n  add-generic-password) [ -n \"$print\" ] && read -r password; printf '%s' \"$password\" > \"$entry\" ;;\n  find-generic-password) [ -f \"$entry\" ] || exit 44; [ -n \"$print\" ] && cat \"$entry\"; exit 0 ;;\n  delete-generic-password) rm \"$entry\" || exit 44 ;;\nesac\n",
+            keychain.path().display()
+        ))
+        .unwrap();
+    let sops_path = bin_dir.child("sops");
+    sops_path
+        .write_str("#!/bin/sh\nwhile [ $# -gt 0 ]; do [ \"$1\" = --output ] && out=$2; shift; done\nprintf '%s|%s' \"$SOPS_AGE_KEY\" \"$SOPS_AGE_KEY_FILE\" > \"$out\"\n")
+        .unwrap();
+    let age_path = bin_dir.child("age");
+    age_path.write_str("#!/bin/sh\nexit 0\n").unwrap();
+    for tool in [&security_path, &sops_path, &age_path] {
+        fs::set_permissions(tool.path(), fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let path = format!(
+        "{}:{}",
+        bin_dir.path().display(),
+        std::env::var("PATH").unwrap()
+    );
+    let config_dir = temp.child("config");
+    let public_key = format!("age1{}", "p".repeat(58));
+    temp.child("exported.txt")
+        .write_str(&format!(
+            "# public key: {public_key}\nAGE-SECRET-KEY-1TEST\n"
+        ))
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .env("ORDINATOR_CONFIG_DIR", config_dir.path());
+    cmd.args([
+        "age",
+        "key",
+        "import",
+        "exported.txt",
+        "--storage",
+        "keychain",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(contains("into the macOS Keychain"));
+    let key_file = config_dir.child("age/key.txt");
+    assert!(!key_file.path().exists());
+    // The key goes to security on stdin, never on its command line
+    let security_args = fs::read_to_string(keychain.child("args.log").path()).unwrap();
+    assert!(security_args.contains("add-generic-password"));
+    assert!(!security_args.contains("AGE-SECRET-KEY"));
+    assert!(
+        fs::read_to_string(config_dir.child("age/key.txt.pub").path())
+            .unwrap()
+            .contains(&public_key)
+    );
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("age_key_storage = \"keychain\""));
+
+    // The key is already stored
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .env("ORDINATOR_CONFIG_DIR", config_dir.path());
+    cmd.args(["age", "key", "import", "exported.txt"]);
+    cmd.assert().failure().stderr(contains("--force"));
+
+    // sops gets the key itself rather than a key file
+    temp.child("token.txt").write_str("hello").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .env("ORDINATOR_CONFIG_DIR", config_dir.path());
+    cmd.args(["age", "encrypt", "token.txt"]);
+    cmd.assert().success();
+    assert_eq!(
+        fs::read_to_string(temp.child("token.txt.enc").path()).unwrap(),
+        "AGE-SECRET-KEY-1TEST\n|"
+    );
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .env("ORDINATOR_CONFIG_DIR", config_dir.path());
+    cmd.args(["age", "key", "export"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("AGE-SECRET-KEY-1TEST"));
+
+    // A passphrase is read from the terminal
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .env("ORDINATOR_CONFIG_DIR", config_dir.path());
+    cmd.args(["age", "key", "passphrase"]);
+    cmd.assert().failure().stderr(contains("needs a terminal"));
+}
+
+#[test]
+fn test_secrets_scan_allowlist_rules_and_baseline() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    temp.child(".env")
+        .write_str("password=examplepassword # ordinator:allow-secret\nEDITOR=vim\n")
+        .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".env", "--profile", "default"]);
+    cmd.assert().success();
+
+    // Allowed lines don't count
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["secrets", "scan"]);
+    cmd.assert().success();
+
+    // Custom rules from [secrets.scan] are checked too
+    let config_path = temp.child("ordinator.toml");
+    let mut config = fs::read_to_string(config_path.path()).unwrap();
+    config.push_str(
+        "\n[[secrets.scan.rules]]\nname = \"Internal token\"\nregex = 'itk_[0-9a-f]{8}'\n",
+    );
+    fs::write(config_path.path(), config).unwrap();
+    temp.child(".env")
+        .write_str(
+            "password=examplepassword # ordinator:allow-secret\nEDITOR=vim\nTOKEN=itk_0123abcd\n",
+        )
+        .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["secrets", "scan", "--verbose"]);
+    cmd.assert()
+        .code(1)
+        .stderr(contains("line 3: Internal token"));
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["secrets", "scan", "--check"]);
+    cmd.assert().code(4);
+
+    // Baselined findings no longer fail the scan
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["secrets", "scan", "--update-baseline"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Wrote 1 finding(s)"));
+    assert!(temp.child(".secrets-baseline.json").path().exists());
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["secrets", "scan"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("1 known finding(s) ignored"));
+}
+
+#[test]
+fn test_secrets_edit_finds_stored_copy() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["secrets", "edit", ".env"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("'.env' is not a tracked secret"));
+
+    temp.child(".env").write_str("TOKEN=old\n").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["secrets", "watch", ".env", "--profile", "default"]);
+    cmd.assert().success();
+
+    // Tracked but never encrypted
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["secrets", "edit", ".env"]);
+    let output = cmd.output().unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stored = stderr
+        .split("found at ")
+        .nth(1)
+        .and_then(|rest| rest.split(". Run").next())
+        .expect("error names the stored path");
+
+    fs::create_dir_all(std::path::Path::new(stored).parent().unwrap()).unwrap();
+    fs::write(stored, "sops:\n  version: 3.8.1\n").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["--dry-run", "secrets", "edit", ".env"]);
+    cmd.assert()
+        .success()
+        .stderr(contains(format!("DRY-RUN: Would decrypt {stored}")));
+}
+
+#[test]
+fn test_secrets_clean_removes_journaled_secrets() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["secrets", "clean"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("No decrypted secrets on disk."));
+
+    let secret = temp.child(".aws/credentials");
+    secret.write_str("aws_secret_access_key = x\n").unwrap();
+    let journal = serde_json::json!({
+        "id": "apply-20250101-000000",
+        "profile": "default",
+        "started_at": "2025-01-01T00:00:00+00:00",
+        "actions": [{
+            "action": "secret_decrypted",
+            "path": secret.path(),
+            "backup": null,
+        }],
+    });
+    temp.child("transactions/apply-20250101-000000.json")
+        .write_str(&journal.to_string())
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["secrets", "clean"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Removed 1 decrypted secret(s)"));
+    assert!(!secret.path().exists());
+}
+
+#[test]
+fn test_watch_encrypt_stores_ciphertext_and_apply_decrypts() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let path = common::with_fake_sops(&temp);
+    let key = temp.child("age.key");
+    key.write_str("# public key: age1test\nAGE-SECRET-KEY-1TEST\n")
+        .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["config", "set", "secrets.age_key_file"])
+        .arg(key.path());
+    cmd.assert().success();
+
+    let license = temp.child("license.conf");
+    license.write_str("license = abc-123\n").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .args(["watch", "--profile", "default", "--encrypt"])
+        .arg(license.path());
+    cmd.assert()
+        .success()
+        .stdout(contains("(stored encrypted)"));
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("encrypt = true"));
+    let stored_copy = temp.child(format!("files/default{}", license.path().display()));
+    assert_eq!(
+        fs::read_to_string(stored_copy.path()).unwrap(),
+        "sops:\nyvprafr = nop-123\n"
+    );
+
+    // Apply writes the plaintext, not a symlink to the ciphertext
+    fs::remove_file(license.path()).unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path).args([
+        "apply",
+        "--profile",
+        "default",
+        "--skip-secrets",
+        "--skip-brew",
+    ]);
+    cmd.assert().success().stdout(contains("Decrypted: "));
+    assert!(!license.path().is_symlink());
+    assert_eq!(
+        fs::read_to_string(license.path()).unwrap(),
+        "license = abc-123\n"
+    );
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path).args(["status", "--short"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("license.conf").not());
+
+    // A local edit shows as drift against the plaintext
+    license.write_str("license = xyz-789\n").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path).args(["status", "--short"]);
+    cmd.assert().success().stdout(contains("M  "));
+}
+
+#[test]
+fn test_secret_values_render_in_templates_and_export() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    let path = common::with_fake_sops(&temp);
+    temp.child("ordinator.toml")
+        .write_str("[profiles.default]\nfiles = [\".netrc.tmpl\"]\nenabled = true\n")
+        .unwrap();
+    temp.child("files/default/.netrc.tmpl")
+        .write_str("password {{ GITHUB_TOKEN }}\n")
+        .unwrap();
+    let key = temp.child("age.key");
+    key.write_str("# public key: age1test\nAGE-SECRET-KEY-1TEST\n")
+        .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["config", "set", "secrets.age_key_file"])
+        .arg(key.path());
+    cmd.assert().success();
+
+    // The value is read from stdin when it's piped
+    let mut cmd = assert_cmd::Command::from_std(common::create_ordinator_command(&temp));
+    cmd.env("PATH", &path)
+        .args(["secrets", "set", "default", "GITHUB_TOKEN"])
+        .write_stdin("ghp_it's\n");
+    cmd.assert().success().stderr(contains(
+        "Stored secret value 'GITHUB_TOKEN' in profile 'default'",
+    ));
+    let stored = fs::read_to_string(temp.child("secrets/default/values.env").path()).unwrap();
+    assert_eq!(stored, "sops:\nGITHUB_TOKEN=tuc_vg'f\n");
+
+    let mut cmd = assert_cmd::Command::from_std(common::create_ordinator_command(&temp));
+    cmd.env("PATH", &path)
+        .args(["secrets", "set", "default", "2FA"])
+        .write_stdin("x\n");
+    cmd.assert()
+        .failure()
+        .stderr(contains("Invalid secret name '2FA'"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .args(["secrets", "env", "--profile", "default"]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout
+        .lines()
+        .filter(|line| !line.contains("INFO"))
+        .collect();
+    assert_eq!(lines, ["export GITHUB_TOKEN='ghp_it'\\''s'"]);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .args(["apply", "--profile", "default", "--skip-brew"]);
+    cmd.assert().success();
+    assert_eq!(
+        fs::read_to_string(temp.child(".netrc").path()).unwrap(),
+        "password ghp_it's\n"
+    );
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &path)
+        .args(["secrets", "unset", "default", "GITHUB_TOKEN"]);
+    cmd.assert().success();
+    assert!(!temp.child("secrets/default/values.env").path().exists());
+}
+
+#[test]
+fn test_secrets_migrate_to_native_backend_works_without_sops() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let config_dir = temp.child("config");
+
+    let with_sops = common::with_fake_sops(&temp);
+    temp.child("ordinator.toml")
+        .write_str(
+            "[profiles.default]\nfiles = [\".netrc.tmpl\"]\nsecrets = [\".npmrc\"]\nenabled = true\n\n[profiles.default.file_mappings]\n\"abc123_.npmrc.enc\" = \".npmrc\"\n\n[secrets]\nbackend = \"native\"\n",
+        )
+        .unwrap();
+    temp.child("files/default/.netrc.tmpl")
+        .write_str("password {{ GITHUB_TOKEN }}\n")
+        .unwrap();
+
+    // The native backend generates the key itself
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", "/nonexistent")
+        .env("ORDINATOR_CONFIG_DIR", config_dir.path())
+        .args(["secrets", "setup"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("Native age setup complete"));
+    let key = fs::read_to_string(config_dir.child("age/key.txt").path()).unwrap();
+    assert!(key.contains("# public key: age1"));
+    assert!(key.contains("AGE-SECRET-KEY-1"));
+
+    // Secrets written with sops before the move
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["config", "set", "secrets.backend", "sops"]);
+    cmd.assert().success();
+    let mut cmd = assert_cmd::Command::from_std(common::create_ordinator_command(&temp));
+    cmd.env("PATH", &with_sops)
+        .args(["secrets", "set", "default", "GITHUB_TOKEN"])
+        .write_stdin("ghp_abc\n");
+    cmd.assert().success();
+    temp.child("secrets/default/abc123_.npmrc.enc")
+        .write_str("sops:\ngbxra=nop\n")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &with_sops)
+        .args(["--dry-run", "secrets", "migrate", "--to", "native"]);
+    cmd.assert()
+        .success()
+        .stdout(contains(
+            "DRY-RUN: Would re-encrypt 2 file(s) with the native backend",
+        ))
+        .stdout(contains("secrets/default/abc123_.npmrc.enc"))
+        .stdout(contains("secrets/default/values.env"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", &with_sops)
+        .args(["secrets", "migrate", "--to", "native"]);
+    cmd.assert().success().stdout(contains(
+        "Re-encrypted 2 file(s); secrets now use the native backend",
+    ));
+    for stored in ["abc123_.npmrc.enc", "values.env"] {
+        let content =
+            fs::read_to_string(temp.child("secrets/default").child(stored).path()).unwrap();
+        assert!(content.starts_with("-----BEGIN AGE ENCRYPTED FILE-----\n"));
+    }
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("backend = \"native\""));
+
+    // From here on neither sops nor age is installed
+    let mut cmd = assert_cmd::Command::from_std(common::create_ordinator_command(&temp));
+    cmd.env("PATH", "/nonexistent")
+        .args(["secrets", "set", "default", "NPM_TOKEN"])
+        .write_stdin("npm_xyz\n");
+    cmd.assert().success();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", "/nonexistent")
+        .args(["secrets", "env", "--profile", "default"]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("export "))
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            "export GITHUB_TOKEN='ghp_abc'",
+            "export NPM_TOKEN='npm_xyz'"
+        ]
+    );
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("PATH", "/nonexistent")
+        .env("ORDINATOR_CONFIG_DIR", config_dir.path())
+        .args([
+            "apply",
+            "--profile",
+            "default",
+            "--skip-brew",
+            "--skip-bootstrap",
+        ]);
+    cmd.assert().success();
+    assert_eq!(
+        fs::read_to_string(temp.child(".netrc").path()).unwrap(),
+        "password ghp_abc\n"
+    );
+    assert_eq!(
+        fs::read_to_string(temp.child(".npmrc").path()).unwrap(),
+        "token=abc\n"
+    );
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["--dry-run", "secrets", "migrate", "--to", "sops"]);
+    cmd.assert().success().stdout(contains(
+        "DRY-RUN: Would re-encrypt 2 file(s) with the sops backend",
+    ));
+}
+
+#[test]
+fn test_secrets_rotate_reencrypts_tracked_encrypted_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let config_dir = temp.child("config");
+    temp.child("ordinator.toml")
+        .write_str(
+            "[profiles.default]\nfiles = []\nenabled = true\n\n[secrets]\nbackend = \"native\"\n",
+        )
+        .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("ORDINATOR_CONFIG_DIR", config_dir.path())
+        .args(["secrets", "setup"]);
+    cmd.assert().success();
+
+    let license = temp.child("license.conf");
+    license.write_str("license = abc-123\n").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", "--profile", "default", "--encrypt"])
+        .arg(license.path());
+    cmd.assert().success();
+    let stored = temp.child(format!("files/default{}", license.path().display()));
+    let before = fs::read_to_string(stored.path()).unwrap();
+    assert!(before.starts_with("-----BEGIN AGE ENCRYPTED FILE-----"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("ORDINATOR_CONFIG_DIR", config_dir.path())
+        .args(["--dry-run", "secrets", "rotate"]);
+    cmd.assert()
+        .success()
+        .stdout(contains(
+            "Would rotate the age key and re-encrypt 1 secret(s)",
+        ))
+        .stdout(contains("license.conf"));
+
+    let old_key = fs::read_to_string(config_dir.child("age/key.txt").path()).unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("ORDINATOR_CONFIG_DIR", config_dir.path()).args([
+        "secrets",
+        "rotate",
+        "--profile",
+        "default",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(contains("Re-encrypted 1 secrets for profile 'default'"));
+    let new_key = fs::read_to_string(config_dir.child("age/key.txt").path()).unwrap();
+    assert_ne!(old_key, new_key);
+    assert_ne!(fs::read_to_string(stored.path()).unwrap(), before);
+
+    // The new key decrypts the tracked file on apply
+    fs::remove_file(license.path()).unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("ORDINATOR_CONFIG_DIR", config_dir.path()).args([
+        "apply",
+        "--profile",
+        "default",
+        "--skip-brew",
+        "--skip-bootstrap",
+    ]);
+    cmd.assert().success();
+    assert_eq!(
+        fs::read_to_string(license.path()).unwrap(),
+        "license = abc-123\n"
+    );
+}
+
+#[test]
+fn test_secrets_add_keeps_target_metadata_beside_the_secret() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let config_dir = temp.child("config");
+    temp.child("ordinator.toml")
+        .write_str(
+            "[profiles.default]\nfiles = []\nenabled = true\n\n[secrets]\nbackend = \"native\"\n",
+        )
+        .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("ORDINATOR_CONFIG_DIR", config_dir.path())
+        .args(["secrets", "setup"]);
+    cmd.assert().success();
+
+    let npmrc = temp.child(".npmrc");
+    npmrc.write_str("token=abc\n").unwrap();
+    fs::set_permissions(npmrc.path(), fs::Permissions::from_mode(0o640)).unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["secrets", "add", ".npmrc", "--profile", "default"]);
+    cmd.assert().success();
+
+    let stored = fs::read_dir(temp.child("secrets/default").path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.extension().is_some_and(|ext| ext == "enc"))
+        .unwrap();
+    let sidecar = temp.child(format!(
+        "secrets/default/{}.meta",
+        stored.file_name().unwrap().to_string_lossy()
+    ));
+    let meta = fs::read_to_string(sidecar.path()).unwrap();
+    assert!(meta.contains("target = \".npmrc\""));
+    assert!(meta.contains("mode = \"640\""));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["secrets", "list"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("Stored secrets:"))
+        .stdout(contains("_.npmrc.enc -> ~/.npmrc (640"));
+
+    // Without its mapping, apply finds the secret through its metadata and
+    // restores the recorded mode
+    let renamed = temp.child("secrets/default/renamed.enc");
+    fs::rename(&stored, renamed.path()).unwrap();
+    fs::rename(
+        sidecar.path(),
+        temp.child("secrets/default/renamed.enc.meta").path(),
+    )
+    .unwrap();
+    fs::remove_file(npmrc.path()).unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("ORDINATOR_CONFIG_DIR", config_dir.path()).args([
+        "apply",
+        "--profile",
+        "default",
+        "--skip-brew",
+        "--skip-bootstrap",
+    ]);
+    cmd.assert().success();
+    assert_eq!(fs::read_to_string(npmrc.path()).unwrap(), "token=abc\n");
+    assert_eq!(
+        fs::metadata(npmrc.path()).unwrap().permissions().mode() & 0o777,
+        0o640
+    );
+
+    // fsck checks metadata against mappings, and finds metadata left behind
+    fs::rename(renamed.path(), &stored).unwrap();
+    sidecar
+        .write_str(&meta.replace("\".npmrc\"", "\".yarnrc\""))
+        .unwrap();
+    temp.child("secrets/default/gone.enc.meta")
+        .write_str("target = \".netrc\"\n")
+        .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.arg("fsck");
+    cmd.assert()
+        .code(2)
+        .stdout(contains(
+            "is mapped to .npmrc, but its metadata says it decrypts to .yarnrc",
+        ))
+        .stdout(contains(
+            "secrets/default/gone.enc.meta is metadata for a secret that is gone",
+        ));
+}
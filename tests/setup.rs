@@ -0,0 +1,62 @@
+mod common;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::fixture::PathChild;
+use assert_fs::prelude::*;
+use predicates::prelude::PredicateBooleanExt;
+use predicates::str::contains;
+
+const REPO_URL: &str = "https://github.com/me/dotfiles";
+
+#[test]
+fn test_setup_resumes_after_failed_step() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    // The repository already in place is used instead of cloning
+    temp.child("ordinator.toml")
+        .write_str(
+            r#"
+[profiles.work]
+files = [".gitconfig"]
+bootstrap_script = "scripts/bootstrap.sh"
+"#,
+        )
+        .unwrap();
+    temp.child("files/work/.gitconfig")
+        .write_str("[user]\n")
+        .unwrap();
+    temp.child("scripts/bootstrap.sh")
+        .write_str("#!/bin/bash\ntest -f ready\n")
+        .unwrap();
+    let checkpoint = temp.child(".local/state/ordinator/setup.toml");
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["setup", REPO_URL, "--profile", "work", "--skip-brew"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("Using the repository already at"))
+        .stderr(contains("Setup stopped at step 'bootstrap'"))
+        .stderr(contains("Bootstrap script failed"));
+    let saved = std::fs::read_to_string(checkpoint.path()).unwrap();
+    assert!(saved.contains("completed = [\n    \"clone\",\n    \"keys\",\n    \"brew\",\n]"));
+    assert!(!temp.child(".gitconfig").path().exists());
+
+    // Another repository can't be set up until this one finishes or is discarded
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["setup", "https://github.com/me/other", "--skip-brew"]);
+    cmd.assert().failure().stderr(contains(
+        "A setup of https://github.com/me/dotfiles hasn't finished",
+    ));
+
+    temp.child("scripts/ready").touch().unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["setup", REPO_URL, "--skip-brew"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Resuming setup of https://github.com/me/dotfiles"))
+        .stderr(contains("[4/5] Run the bootstrap script"))
+        .stderr(contains("[1/5]").not())
+        .stderr(contains("Setup complete: profile 'work'"));
+    assert!(!checkpoint.path().exists());
+    let link = std::fs::read_link(temp.child(".gitconfig").path()).unwrap();
+    assert!(link.ends_with("files/work/.gitconfig"));
+}
@@ -3,6 +3,7 @@ use assert_fs::fixture::PathChild;
 use assert_fs::prelude::*;
 
 use assert_cmd::assert::OutputAssertExt;
+use predicates::prelude::PredicateBooleanExt;
 use std::fs;
 
 #[test]
@@ -77,7 +78,16 @@ fn test_status_symlinks_and_repair() {
     );
 
     // Break the symlink by removing the target
-    fs::remove_file(managed.path()).unwrap();
+    let target = fs::read_link(home_file.path()).unwrap();
+    fs::remove_file(&target).unwrap();
+    if managed.path().exists() {
+        fs::remove_file(managed.path()).unwrap();
+    }
+    // The copy watch stored would otherwise stand in for the missing one
+    let watched = temp.child("files/default/status_test_file.txt");
+    if watched.path().exists() {
+        fs::remove_file(watched.path()).unwrap();
+    }
 
     // Run repair
     let mut cmd = common::create_ordinator_command(&temp);
@@ -88,3 +98,180 @@ fn test_status_symlinks_and_repair() {
     // The repair command should remove the broken symlink
     assert!(!home_file.path().exists(), "Broken symlink was not removed");
 }
+
+#[test]
+fn test_status_reports_file_drift() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    temp.child(".zshrc").write_str("one").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".zshrc", "--profile", "default"]);
+    cmd.assert().success();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["status", "--verbose"]);
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains(".zshrc: in sync"))
+        .stderr(predicates::str::contains("1 in sync"));
+
+    // Edit the live copy
+    temp.child(".zshrc").write_str("two").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["status"]);
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains(".zshrc: modified locally"))
+        .stderr(predicates::str::contains(
+            "run 'ordinator add .zshrc --profile default'",
+        ));
+
+    // Restore it and edit the tracked copy instead
+    temp.child(".zshrc").write_str("one").unwrap();
+    temp.child("files/default/.zshrc")
+        .write_str("three")
+        .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["status"]);
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains(".zshrc: modified in repo"))
+        .stderr(predicates::str::contains(
+            "run 'ordinator apply --profile default'",
+        ));
+}
+
+#[test]
+fn test_status_check_exit_codes() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    temp.child(".zshrc").write_str("one").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".zshrc", "--profile", "default"]);
+    cmd.assert().success();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["status", "--check"]);
+    cmd.assert()
+        .code(0)
+        .stderr(predicates::str::contains("Applied profile").not());
+
+    temp.child(".zshrc").write_str("two").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["status", "--check"]);
+    cmd.assert()
+        .code(2)
+        .stderr(predicates::str::contains(".zshrc: modified locally"));
+
+    // Both sides changed since the last sync: a conflict
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["add", ".zshrc", "--profile", "default"]);
+    cmd.assert().success();
+    temp.child(".zshrc").write_str("three").unwrap();
+    let stored = fs::read_dir(temp.child("files/default").path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.to_string_lossy().ends_with("_.zshrc"))
+        .unwrap();
+    fs::write(stored, "four").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["status", "--check"]);
+    cmd.assert()
+        .code(3)
+        .stderr(predicates::str::contains("modified locally and in repo"));
+}
+
+#[test]
+fn test_repair_prune_drops_missing_sources_and_orphans() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    for file in [".zshrc", ".vimrc"] {
+        temp.child(file).write_str("contents").unwrap();
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.args(["watch", file, "--profile", "default"]);
+        cmd.assert().success();
+    }
+    fs::remove_file(temp.child("files/default/.vimrc").path()).unwrap();
+    let orphan = temp.child("files/default/old.conf");
+    orphan.write_str("stale").unwrap();
+    let host_override = temp.child("files/default/mac-studio/.zshrc");
+    host_override.write_str("host").unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["repair", "--prune", "--orphans", "--dry-run"]);
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains(
+            "Source missing: .vimrc (profile 'default')",
+        ))
+        .stderr(predicates::str::contains("old.conf"))
+        .stderr(predicates::str::contains("mac-studio").not());
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("\".vimrc\""));
+
+    // Declined without --yes
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["repair", "--prune", "--orphans"]);
+    cmd.assert().success().stderr(predicates::str::contains(
+        "Skipped pruning profile 'default'",
+    ));
+    orphan.assert(predicates::path::exists());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["--yes", "repair", "--prune", "--orphans"]);
+    cmd.assert().success().stderr(predicates::str::contains(
+        "Pruned 1 tracked path(s), deleted 1 orphaned file(s)",
+    ));
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(!config.contains("\".vimrc\""));
+    assert!(config.contains("\".zshrc\""));
+    orphan.assert(predicates::path::missing());
+    host_override.assert(predicates::path::exists());
+    temp.child("files/default/.zshrc")
+        .assert(predicates::path::exists());
+}
+
+#[test]
+fn test_status_short_and_aligned_output() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    for file in [".zshrc", ".gitconfig", ".vimrc"] {
+        temp.child(file).write_str("one").unwrap();
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.args(["watch", file, "--profile", "default"]);
+        cmd.assert().success();
+    }
+    temp.child(".zshrc").write_str("two").unwrap();
+    temp.child("files/default/.gitconfig")
+        .write_str("three")
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["status", "--short"]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout
+        .lines()
+        .filter(|line| !line.contains("INFO"))
+        .collect();
+    assert_eq!(lines, ["M  .zshrc", " M .gitconfig"]);
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("Applied profile"));
+
+    // States line up after the longest path
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["status", "--verbose"]);
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::contains("  .zshrc:     modified locally"))
+        .stderr(predicates::str::contains("  .gitconfig: modified in repo"))
+        .stderr(predicates::str::contains("  .vimrc:     in sync"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.arg("profiles");
+    cmd.assert()
+        .success()
+        .stderr(predicates::str::is_match(r"default:\s+.*\(default\)").unwrap());
+}
@@ -176,7 +176,7 @@ fn test_uninstall_when_no_backups_exist() {
     cmd.args(["apply", "--force"]);
     cmd.assert().success();
     // Remove backups
-    let backup_dir = temp.child("backups");
+    let backup_dir = temp.child(".local/state/ordinator/backups");
     if backup_dir.path().exists() {
         std::fs::remove_dir_all(backup_dir.path()).ok();
     }
@@ -223,16 +223,18 @@ fn test_uninstall_with_non_symlink_non_file_at_target() {
     let dir = temp.child(".zshrc");
     dir.create_dir_all().unwrap();
 
-    // Try to watch and add the directory (should fail)
+    // A directory at the target is tracked as a directory, not a file
     let mut watch_cmd = common::create_ordinator_command(&temp);
     watch_cmd.args(["watch", ".zshrc"]);
     watch_cmd.assert().success();
 
     let mut cmd = common::create_ordinator_command(&temp);
     cmd.args(["add", ".zshrc"]);
-    cmd.assert().failure().stderr(contains(
-        "neither a regular file nor a symlink to a regular file",
-    ));
+    cmd.assert()
+        .success()
+        .stdout(contains("'.zshrc' is up to date"));
+    let config = std::fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    assert!(config.contains("path = \".zshrc\""));
 }
 
 #[test]
@@ -252,7 +254,8 @@ fn test_uninstall_with_multiple_backups_restores_most_recent() {
     let mut cmd = common::create_ordinator_command(&temp);
     cmd.args(["apply", "--force"]);
     cmd.assert().success();
-    // Simulate multiple backups
+    // Simulate multiple backups, in place of the one apply just made
+    std::fs::remove_dir_all(temp.child(".local/state/ordinator/backups").path()).unwrap();
     let backup_dir = temp.child("backups");
     backup_dir.create_dir_all().unwrap();
     let backup1 = backup_dir.child(".zshrc.backup.1.20250101-120000");
@@ -340,3 +343,389 @@ fn test_uninstall_with_restore_backups_but_backups_disabled() {
         .success()
         .stderr(contains("Backups are disabled").or(contains("No backup found")));
 }
+
+#[test]
+fn test_replaced_and_uninstalled_files_go_to_trash_unless_permanent() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let config_file = temp.child("ordinator.toml");
+    let config_contents = std::fs::read_to_string(config_file.path()).unwrap();
+    let mut config: toml::Value = toml::from_str(&config_contents).unwrap();
+    config["global"]
+        .as_table_mut()
+        .unwrap()
+        .insert("create_backups".to_string(), toml::Value::Boolean(false));
+    std::fs::write(config_file.path(), toml::to_string(&config).unwrap()).unwrap();
+    temp.child(".zshrc").write_str("user contents").unwrap();
+    temp.child(".vimrc").write_str("set number").unwrap();
+    for file in [".zshrc", ".vimrc"] {
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.args(["watch", file]);
+        cmd.assert().success();
+    }
+
+    // In the test environment the Trash is ~/.Trash
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--force"]);
+    cmd.assert().success().stderr(contains(format!(
+        "Moved {} to the Trash",
+        temp.child(".zshrc").path().display()
+    )));
+    temp.child(".Trash/.zshrc").assert("user contents");
+    temp.child(".Trash/.vimrc").assert("set number");
+    assert!(temp.child(".zshrc").path().is_symlink());
+
+    // Ordinator's own links are deleted, not trashed
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["uninstall", "--force"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Removed symlink").and(contains("to the Trash").not()));
+    assert!(!temp.child(".Trash/.zshrc 2").path().exists());
+    assert!(!temp.child(".zshrc").path().exists());
+
+    // --permanent deletes instead
+    temp.child(".zshrc").write_str("newer contents").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--force", "--permanent"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("to the Trash").not());
+    assert!(!temp.child(".Trash/.zshrc 2").path().exists());
+    assert!(temp.child(".zshrc").path().is_symlink());
+}
+
+#[test]
+fn test_uninstall_removes_decrypted_secrets() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    // What apply records after decrypting a secret
+    let secret = temp.child(".env");
+    secret.write_str("TOKEN=secret\n").unwrap();
+    let journal = serde_json::json!({
+        "id": "apply-20250101-000000",
+        "profile": "default",
+        "started_at": "2025-01-01T00:00:00+00:00",
+        "actions": [{
+            "action": "secret_decrypted",
+            "path": secret.path(),
+            "backup": null,
+        }],
+    });
+    temp.child("transactions/apply-20250101-000000.json")
+        .write_str(&journal.to_string())
+        .unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["uninstall", "--dry-run", "--force"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Would remove decrypted secret:"));
+    secret.assert(predicates::path::exists());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["uninstall", "--force", "--shred"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Shredded decrypted secret:"))
+        .stderr(contains("Decrypted secrets removed: 1"));
+    secret.assert(predicates::path::missing());
+}
+
+#[test]
+fn test_uninstall_defaults_to_applied_profiles() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    for (file, profile) in [(".zshrc", "default"), (".work", "work")] {
+        temp.child(file).write_str("contents").unwrap();
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.args(["watch", file, "--profile", profile]);
+        cmd.assert().success();
+        std::fs::remove_file(temp.child(file).path()).unwrap();
+    }
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "apply",
+        "--profile",
+        "work",
+        "--skip-secrets",
+        "--skip-brew",
+    ]);
+    cmd.assert().success();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.arg("status");
+    cmd.assert()
+        .success()
+        .stderr(contains("Applied profile: work (applied "));
+
+    // Switching profiles is allowed, with a warning
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args([
+        "apply",
+        "--profile",
+        "default",
+        "--skip-secrets",
+        "--skip-brew",
+    ]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Switching from profile 'work'"));
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["uninstall", "--force"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("for profile: default, work (applied)"));
+    assert!(std::fs::symlink_metadata(temp.child(".zshrc").path()).is_err());
+    assert!(std::fs::symlink_metadata(temp.child(".work").path()).is_err());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.arg("status");
+    cmd.assert()
+        .success()
+        .stderr(contains("Applied profile: none"));
+}
+
+#[test]
+fn test_uninstall_removes_generated_files_and_reports_the_rest() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    temp.child(".zshrc").write_str("contents").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".zshrc", "--profile", "default"]);
+    cmd.assert().success();
+    std::fs::remove_file(temp.child(".zshrc").path()).unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-secrets", "--skip-brew"]);
+    cmd.assert().success();
+
+    let bootstrap_secrets = temp.child("scripts/default/bootstrap-secrets.env");
+    bootstrap_secrets.write_str("TOKEN=abc\n").unwrap();
+    let bootstrap = temp.child("scripts/default/bootstrap.sh");
+    bootstrap.write_str("#!/bin/sh\n").unwrap();
+    temp.child("logs/bootstrap/default.log")
+        .write_str("ok\n")
+        .unwrap();
+    let state = temp.child(".local/state/ordinator/state.toml");
+    state.assert(predicates::path::exists());
+    temp.child("transactions")
+        .assert(predicates::path::exists());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["uninstall", "--force"]);
+    cmd.assert()
+        .success()
+        // Run logs included
+        .stderr(contains("Generated files removed: 6"))
+        .stderr(contains("Left untouched:"))
+        .stderr(contains("(delete it with --purge-repo)"));
+    bootstrap_secrets.assert(predicates::path::missing());
+    temp.child("logs").assert(predicates::path::missing());
+    temp.child("cache").assert(predicates::path::missing());
+    temp.child("transactions")
+        .assert(predicates::path::missing());
+    state.assert(predicates::path::missing());
+    temp.child(".local/state/ordinator/logs")
+        .assert(predicates::path::missing());
+    // Tracked content stays with the repository
+    bootstrap.assert(predicates::path::exists());
+    temp.child("ordinator.toml")
+        .assert(predicates::path::exists());
+}
+
+#[test]
+fn test_uninstall_purge_repo() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+
+    // A repository that holds the home directory is never deleted
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["uninstall", "--force", "--purge-repo"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("it contains the home directory"));
+    temp.child("ordinator.toml")
+        .assert(predicates::path::exists());
+
+    let repo = temp.child("dotfiles");
+    repo.create_dir_all().unwrap();
+    std::fs::copy(
+        temp.child("ordinator.toml").path(),
+        repo.child("ordinator.toml").path(),
+    )
+    .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("ORDINATOR_CONFIG", repo.child("ordinator.toml").path());
+    cmd.args(["uninstall", "--force", "--purge-repo", "--dry-run"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Would delete the dotfiles repository"));
+    repo.assert(predicates::path::exists());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.env("ORDINATOR_CONFIG", repo.child("ordinator.toml").path());
+    cmd.args(["uninstall", "--force", "--purge-repo"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Deleted the dotfiles repository"))
+        .stderr(contains("the dotfiles repository at").not());
+    repo.assert(predicates::path::missing());
+}
+
+#[test]
+fn test_uninstall_purge_repo_keeps_repo_while_another_profile_is_applied() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    let repo = temp.child("dotfiles");
+    repo.create_dir_all().unwrap();
+    std::fs::copy(
+        temp.child("ordinator.toml").path(),
+        repo.child("ordinator.toml").path(),
+    )
+    .unwrap();
+    let config = repo.child("ordinator.toml");
+    let command = || {
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.env("ORDINATOR_CONFIG", config.path());
+        cmd
+    };
+
+    for (file, profile) in [(".zshrc", "default"), (".work", "work")] {
+        temp.child(file).write_str("contents").unwrap();
+        let mut cmd = command();
+        cmd.args(["watch", file, "--profile", profile]);
+        cmd.assert().success();
+        std::fs::remove_file(temp.child(file).path()).unwrap();
+        let mut cmd = command();
+        cmd.args([
+            "apply",
+            "--profile",
+            profile,
+            "--skip-secrets",
+            "--skip-brew",
+        ]);
+        cmd.assert().success();
+    }
+
+    let mut cmd = command();
+    cmd.args(["uninstall", "--profile", "work", "--force", "--purge-repo"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Not deleting"))
+        .stderr(contains("(not deleted: other profiles are still applied)"));
+    repo.assert(predicates::path::exists());
+    assert!(std::fs::symlink_metadata(temp.child(".work").path()).is_err());
+    temp.child(".zshrc").assert(predicates::path::exists());
+}
+
+#[test]
+fn test_apply_creates_parent_directories_and_uninstall_removes_them() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    temp.child(".config/foo/bar.toml")
+        .write_str("x = 1\n")
+        .unwrap();
+    temp.child(".gnupg/gpg.conf")
+        .write_str("use-agent\n")
+        .unwrap();
+    for file in [".config/foo/bar.toml", ".gnupg/gpg.conf"] {
+        let mut cmd = common::create_ordinator_command(&temp);
+        cmd.args(["watch", file, "--profile", "default"]);
+        cmd.assert().success();
+    }
+    let config = temp.child("ordinator.toml");
+    let content = std::fs::read_to_string(config.path()).unwrap();
+    config
+        .write_str(&format!(
+            "{content}\n[global.directory_modes]\n\".config/foo\" = \"750\"\n"
+        ))
+        .unwrap();
+    // A fresh machine: none of the parent directories exist yet
+    std::fs::remove_dir_all(temp.child(".config").path()).unwrap();
+    std::fs::remove_dir_all(temp.child(".gnupg").path()).unwrap();
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-secrets", "--skip-brew"]);
+    cmd.assert().success();
+    let mode = |dir: &str| {
+        std::fs::metadata(temp.child(dir).path())
+            .unwrap()
+            .permissions()
+            .mode()
+            & 0o777
+    };
+    assert_eq!(mode(".config/foo"), 0o750);
+    assert_eq!(mode(".gnupg"), 0o700);
+
+    // Something else put in .config keeps it there
+    temp.child(".config/other.toml").write_str("").unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["uninstall", "--force"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("Empty directories removed: 2"));
+    temp.child(".config/foo")
+        .assert(predicates::path::missing());
+    temp.child(".gnupg").assert(predicates::path::missing());
+    temp.child(".config/other.toml")
+        .assert(predicates::path::exists());
+}
+
+#[test]
+fn test_uninstall_restores_nested_tracked_directories() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    temp.child(".config/fish/config.fish")
+        .write_str("set -x EDITOR nvim\n")
+        .unwrap();
+    temp.child(".config/fish/functions/ll.fish")
+        .write_str("function ll; ls -l; end\n")
+        .unwrap();
+    temp.child(".config/kitty/kitty.conf")
+        .write_str("font_size 13\n")
+        .unwrap();
+    temp.child(".config/kitty/themes/dark.conf")
+        .write_str("background #000000\n")
+        .unwrap();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".config/fish"]);
+    cmd.assert().success();
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["watch", ".config/kitty", "--link", "directory"]);
+    cmd.assert().success();
+
+    // Apply replaces the originals, backing each one up
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["apply", "--skip-secrets", "--skip-brew", "--force"]);
+    cmd.assert().success();
+    assert!(temp
+        .child(".config/fish/functions/ll.fish")
+        .path()
+        .is_symlink());
+    assert!(temp.child(".config/kitty").path().is_symlink());
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["uninstall", "--force", "--restore-backups"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("3 symlinks removed, 3 backups restored"));
+    for (file, content) in [
+        (".config/fish/config.fish", "set -x EDITOR nvim\n"),
+        (
+            ".config/fish/functions/ll.fish",
+            "function ll; ls -l; end\n",
+        ),
+        (".config/kitty/kitty.conf", "font_size 13\n"),
+        (".config/kitty/themes/dark.conf", "background #000000\n"),
+    ] {
+        let child = temp.child(file);
+        assert!(!child.path().is_symlink(), "{file} is still a symlink");
+        child.assert(content);
+    }
+    assert!(!temp.child(".config/kitty").path().is_symlink());
+}
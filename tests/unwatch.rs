@@ -0,0 +1,77 @@
+mod common;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::prelude::*;
+use predicates::str::contains;
+use std::fs;
+
+/// Watch each file and apply, so each is a symlink into the repository
+fn watch_and_apply(temp: &assert_fs::TempDir, files: &[&str]) {
+    for file in files {
+        temp.child(file).write_str(&format!("# {file}\n")).unwrap();
+        let mut cmd = common::create_ordinator_command(temp);
+        cmd.args(["watch", file, "--profile", "default"]);
+        cmd.assert().success();
+    }
+    let mut cmd = common::create_ordinator_command(temp);
+    cmd.args(["apply", "--skip-brew", "--skip-bootstrap", "--force"]);
+    cmd.assert().success();
+    for file in files {
+        assert!(temp.child(file).path().is_symlink());
+    }
+}
+
+#[test]
+fn test_unwatch_keep_file_restore_and_purge() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let (_config_guard, _test_mode_guard) = common::setup_test_environment_with_config(&temp, None);
+    watch_and_apply(&temp, &[".zshrc", ".vimrc", ".gitconfig", ".inputrc"]);
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["--dry-run", "unwatch", ".zshrc", "--profile", "default"]);
+    cmd.assert().success().stdout(contains(
+        "DRY-RUN: Would stop watching '.zshrc' for profile 'default'. Restore: ",
+    ));
+
+    // --keep-file: the link keeps working, through the kept copy
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["unwatch", ".zshrc", "--profile", "default", "--keep-file"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("still links to the kept repository copy"));
+    assert!(temp.child(".zshrc").path().is_symlink());
+    temp.child(".zshrc").assert("# .zshrc\n");
+
+    // --restore: a real copy replaces the link
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["unwatch", ".vimrc", "--profile", "default", "--restore"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("Replaced the symlink at"));
+    assert!(!temp.child(".vimrc").path().is_symlink());
+    temp.child(".vimrc").assert("# .vimrc\n");
+
+    // --purge: nothing is left
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["unwatch", ".gitconfig", "--profile", "default", "--purge"]);
+    cmd.assert().success().stdout(contains("Removed "));
+    assert!(!temp.child(".gitconfig").path().exists());
+    assert!(!temp.child(".gitconfig").path().is_symlink());
+
+    // Without a terminal, the default is to restore
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["unwatch", ".inputrc", "--profile", "default"]);
+    cmd.assert().success();
+    assert!(!temp.child(".inputrc").path().is_symlink());
+    temp.child(".inputrc").assert("# .inputrc\n");
+
+    let config = fs::read_to_string(temp.child("ordinator.toml").path()).unwrap();
+    for file in [".zshrc", ".vimrc", ".gitconfig", ".inputrc"] {
+        assert!(!config.contains(&format!("\"{file}\"")), "{config}");
+    }
+
+    let mut cmd = common::create_ordinator_command(&temp);
+    cmd.args(["unwatch", ".vimrc", "--keep-file", "--purge"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("cannot be used with"));
+}